@@ -0,0 +1,225 @@
+use crate::mesh::AmyMesh;
+use raylib::{math::glam::Quat, prelude::*};
+
+/// Length of the gizmo's axis handles in world units
+const HANDLE_LENGTH: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransformKind {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+/// A world axis that a transform can be constrained to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub const ALL: [Self; 3] = [Self::X, Self::Y, Self::Z];
+
+    #[inline]
+    pub const fn vector(self) -> Vector3 {
+        match self {
+            Self::X => Vector3::X,
+            Self::Y => Vector3::Y,
+            Self::Z => Vector3::Z,
+        }
+    }
+
+    #[inline]
+    pub const fn color(self) -> Color {
+        match self {
+            Self::X => Color::RED,
+            Self::Y => Color::GREEN,
+            Self::Z => Color::BLUE,
+        }
+    }
+}
+
+/// How the screen relates to the world around a transform's pivot
+#[derive(Debug, Clone, Copy)]
+pub struct View {
+    pub camera: Camera3D,
+    /// Height of the screen in pixels
+    pub screen_height: f32,
+    /// Where the pivot appears on screen
+    pub pivot_screen: Vector2,
+}
+
+impl View {
+    /// World units per pixel at the pivot's depth
+    fn world_per_pixel(&self, pivot: Vector3) -> f32 {
+        let distance = self.camera.position.distance(pivot);
+        2.0 * distance * (0.5 * self.camera.fovy.to_radians()).tan() / self.screen_height
+    }
+}
+
+/// Where the end of each axis handle of a gizmo at `pivot` is, in world space
+#[inline]
+pub fn handle_ends(pivot: Vector3) -> [Vector3; 3] {
+    Axis::ALL.map(|axis| pivot + axis.vector() * HANDLE_LENGTH)
+}
+
+/// Draw the axis handles of a gizmo, highlighting `active` if there is one
+///
+/// `pivot` and `ends` are the screen positions of the pivot and of [`handle_ends`].
+pub fn draw_axes(
+    d: &mut impl RaylibDraw,
+    pivot: Vector2,
+    ends: [Vector2; 3],
+    active: Option<Axis>,
+) {
+    for (axis, end) in Axis::ALL.into_iter().zip(ends) {
+        let thick = if active == Some(axis) { 4.0 } else { 2.0 };
+        d.draw_line_ex(pivot, end, thick, axis.color());
+    }
+}
+
+/// A transform of some vertices that follows the mouse until it's finished or canceled
+///
+/// The mesh is modified as the mouse moves, so canceling puts the vertices back.
+#[derive(Debug, Clone)]
+pub struct TransformOp {
+    pub kind: TransformKind,
+    /// Restricts the transform to one axis
+    pub axis: Option<Axis>,
+    pivot: Vector3,
+    /// Where the mouse was when the transform started
+    start_mouse: Vector2,
+    /// Each transformed vertex and its position before the transform
+    original: Vec<(u16, Vector3)>,
+}
+
+impl TransformOp {
+    pub fn new(
+        kind: TransformKind,
+        mesh: &AmyMesh,
+        vertices: impl IntoIterator<Item = u16>,
+        pivot: Vector3,
+        start_mouse: Vector2,
+    ) -> Self {
+        Self {
+            kind,
+            axis: None,
+            pivot,
+            start_mouse,
+            original: vertices
+                .into_iter()
+                .map(|i| (i, mesh.vertices()[usize::from(i)]))
+                .collect(),
+        }
+    }
+
+    #[inline]
+    pub const fn pivot(&self) -> Vector3 {
+        self.pivot
+    }
+
+    /// Constrain to `axis`, or remove the constraint if already constrained to it
+    pub fn toggle_axis(&mut self, axis: Axis) {
+        self.axis = if self.axis == Some(axis) {
+            None
+        } else {
+            Some(axis)
+        };
+    }
+
+    /// Where `point` ends up with the mouse at `mouse`
+    fn transform_point(&self, point: Vector3, mouse: Vector2, view: &View) -> Vector3 {
+        let from_pivot = point - self.pivot;
+        let start = self.start_mouse - view.pivot_screen;
+        let current = mouse - view.pivot_screen;
+        match self.kind {
+            TransformKind::Translate => {
+                let delta = mouse - self.start_mouse;
+                let offset = (view.camera.right() * delta.x - view.camera.up() * delta.y)
+                    * view.world_per_pixel(self.pivot);
+                point
+                    + self
+                        .axis
+                        .map_or(offset, |axis| axis.vector() * offset.dot(axis.vector()))
+            }
+            TransformKind::Rotate => {
+                // Y points down, so this is clockwise on screen
+                let angle = start.perp_dot(current).atan2(start.dot(current));
+                let forward = view.camera.forward();
+                let (axis, angle) = match self.axis {
+                    Some(axis) if axis.vector().dot(forward) < 0.0 => (axis.vector(), -angle),
+                    Some(axis) => (axis.vector(), angle),
+                    None => (forward, angle),
+                };
+                self.pivot + Quat::from_axis_angle(axis, angle) * from_pivot
+            }
+            TransformKind::Scale => {
+                let factor = if start.length_squared() > f32::EPSILON {
+                    current.length() / start.length()
+                } else {
+                    1.0
+                };
+                let scale = self.axis.map_or(Vector3::splat(factor), |axis| {
+                    Vector3::ONE + axis.vector() * (factor - 1.0)
+                });
+                self.pivot + from_pivot * scale
+            }
+        }
+    }
+
+    /// Move the vertices to where the mouse at `mouse` puts them
+    pub fn apply(&self, mesh: &mut AmyMesh, mouse: Vector2, view: &View) {
+        for &(i, original) in &self.original {
+            mesh.vertices_mut()[usize::from(i)] = self.transform_point(original, mouse, view);
+        }
+    }
+
+    /// Put the vertices back where they started
+    pub fn cancel(self, mesh: &mut AmyMesh) {
+        for (i, original) in self.original {
+            mesh.vertices_mut()[usize::from(i)] = original;
+        }
+    }
+
+    /// Keep the vertices where they are, returning each one's index, old position, and new position
+    pub fn finish(self, mesh: &AmyMesh) -> Vec<(u16, Vector3, Vector3)> {
+        self.original
+            .into_iter()
+            .map(|(i, original)| (i, original, mesh.vertices()[usize::from(i)]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_and_cancel() {
+        let mut mesh = AmyMesh::gen_cube(1.0, 1.0, 1.0);
+        let view = View {
+            camera: Camera3D::perspective(
+                Vector3::new(0.0, 0.0, 10.0),
+                Vector3::ZERO,
+                Vector3::Y,
+                45.0,
+            ),
+            screen_height: 100.0,
+            pivot_screen: Vector2::ZERO,
+        };
+        let mut op = TransformOp::new(
+            TransformKind::Scale,
+            &mesh,
+            [7],
+            Vector3::ZERO,
+            Vector2::new(10.0, 0.0),
+        );
+        op.toggle_axis(Axis::X);
+        op.apply(&mut mesh, Vector2::new(20.0, 0.0), &view);
+        assert_eq!(mesh.vertices()[7], Vector3::new(2.0, 1.0, 1.0));
+        op.cancel(&mut mesh);
+        assert_eq!(mesh.vertices()[7], Vector3::ONE);
+    }
+}
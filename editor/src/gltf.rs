@@ -0,0 +1,158 @@
+//! Exporting meshes as binary glTF 2.0 (`.glb`)
+//!
+//! Faces are flat shaded, so every face gets its own copy of its corners. Points and lines have
+//! no surface and are left out.
+
+use crate::mesh::AmyMesh;
+use raylib::prelude::*;
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+const MAGIC: &[u8; 4] = b"glTF";
+const VERSION: u32 = 2;
+const CHUNK_JSON: &[u8; 4] = b"JSON";
+const CHUNK_BIN: &[u8; 4] = b"BIN\0";
+/// Buffer view target for vertex attributes
+const ARRAY_BUFFER: u32 = 34962;
+/// Accessor component type for `f32`
+const FLOAT: u32 = 5126;
+/// Primitive mode for a triangle list
+const TRIANGLES: u32 = 4;
+
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "mesh is too large for glTF")
+}
+
+/// Write `mesh` as a binary glTF file
+///
+/// Returns [`io::ErrorKind::InvalidInput`] if the mesh has no triangles or quads.
+pub fn write(mesh: &AmyMesh, w: &mut impl Write) -> io::Result<()> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut texcoords = Vec::new();
+    for (face, verts) in mesh.faces().iter().zip(mesh.face_vertices()) {
+        let corners: &[usize] = match verts.len() {
+            3 => &[0, 1, 2],
+            4 => &[0, 1, 2, 0, 2, 3],
+            _ => continue,
+        };
+        // glTF requires unit normals, even for faces with no area
+        let normal = Some(verts.normal())
+            .filter(|&n| n != Vector3::ZERO)
+            .unwrap_or(Vector3::Y);
+        let indices = face.indices();
+        for &corner in corners {
+            positions.push(*verts[corner]);
+            normals.push(normal);
+            texcoords.push(mesh.texcoords()[usize::from(indices[corner])]);
+        }
+    }
+    let Some(&first) = positions.first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "mesh has no faces to export",
+        ));
+    };
+    let (min, max) = positions
+        .iter()
+        .fold((first, first), |(min, max), &v| (min.min(v), max.max(v)));
+
+    let mut bin = Vec::new();
+    let mut buffer_views = Vec::new();
+    for floats in [
+        positions
+            .iter()
+            .flat_map(|v| v.to_array())
+            .collect::<Vec<_>>(),
+        normals.iter().flat_map(|v| v.to_array()).collect(),
+        texcoords.iter().flat_map(|v| v.to_array()).collect(),
+    ] {
+        let offset = bin.len();
+        bin.extend(floats.iter().flat_map(|x| x.to_le_bytes()));
+        let length = bin.len() - offset;
+        buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{offset},"byteLength":{length},"target":{ARRAY_BUFFER}}}"#
+        ));
+    }
+
+    let count = positions.len();
+    let accessors = [
+        format!(
+            r#"{{"bufferView":0,"componentType":{FLOAT},"count":{count},"type":"VEC3","min":[{},{},{}],"max":[{},{},{}]}}"#,
+            min.x, min.y, min.z, max.x, max.y, max.z,
+        ),
+        format!(r#"{{"bufferView":1,"componentType":{FLOAT},"count":{count},"type":"VEC3"}}"#),
+        format!(r#"{{"bufferView":2,"componentType":{FLOAT},"count":{count},"type":"VEC2"}}"#),
+    ];
+
+    let mut json = format!(
+        concat!(
+            r#"{{"asset":{{"version":"2.0","generator":"editor"}},"#,
+            r#""scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0}}],"#,
+            r#""meshes":[{{"primitives":[{{"#,
+            r#""attributes":{{"POSITION":0,"NORMAL":1,"TEXCOORD_0":2}},"mode":{mode}"#,
+            r#"}}]}}],"#,
+            r#""buffers":[{{"byteLength":{length}}}],"#,
+            r#""bufferViews":[{views}],"accessors":[{accessors}]}}"#,
+        ),
+        mode = TRIANGLES,
+        length = bin.len(),
+        views = buffer_views.join(","),
+        accessors = accessors.join(","),
+    )
+    .into_bytes();
+    // Chunks must be 4 byte aligned; JSON is padded with spaces, binary with zeros
+    json.resize(json.len().next_multiple_of(4), b' ');
+    bin.resize(bin.len().next_multiple_of(4), 0);
+
+    // Header, then each chunk's length and type before its contents
+    let total = 12 + 8 + json.len() + 8 + bin.len();
+    w.write_all(MAGIC)?;
+    w.write_all(&VERSION.to_le_bytes())?;
+    w.write_all(&u32::try_from(total).map_err(|_| too_large())?.to_le_bytes())?;
+    for (kind, chunk) in [(CHUNK_JSON, &json), (CHUNK_BIN, &bin)] {
+        w.write_all(
+            &u32::try_from(chunk.len())
+                .map_err(|_| too_large())?
+                .to_le_bytes(),
+        )?;
+        w.write_all(kind)?;
+        w.write_all(chunk)?;
+    }
+    Ok(())
+}
+
+/// Write `mesh` to the binary glTF file at `path`, replacing it if it exists
+pub fn save(mesh: &AmyMesh, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut w = io::BufWriter::new(std::fs::File::create(path)?);
+    write(mesh, &mut w)?;
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let mut out = Vec::new();
+        write(&AmyMesh::gen_cube(1.0, 1.0, 1.0), &mut out).unwrap();
+        let word = |at: usize| u32::from_le_bytes(out[at..at + 4].try_into().unwrap());
+        assert_eq!(&out[..4], MAGIC);
+        assert_eq!(word(8) as usize, out.len());
+
+        let json_len = word(12) as usize;
+        assert_eq!(&out[16..20], CHUNK_JSON);
+        let json = std::str::from_utf8(&out[20..20 + json_len]).unwrap();
+        // 6 quads, 2 triangles each
+        assert!(json.contains(r#""count":36"#));
+        assert!(json.contains(r#""min":[-1,-1,-1],"max":[1,1,1]"#));
+
+        let bin = 20 + json_len;
+        assert_eq!(&out[bin + 4..bin + 8], CHUNK_BIN);
+        // Positions, normals, and texcoords
+        assert_eq!(word(bin) as usize, 36 * (12 + 12 + 8));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::mesh::{AmyMesh, Face};
+use raylib::prelude::*;
+use std::collections::VecDeque;
+
+/// How much memory the undo history may use by default
+const DEFAULT_BUDGET: usize = 16 * 1024 * 1024;
+
+/// An undoable change to a mesh
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditorCommand {
+    /// Each vertex's index, position before, and position after
+    MoveVertices(Vec<(u16, Vector3, Vector3)>),
+    /// Each removed face and the index it was at, in increasing order of index
+    DeleteFaces(Vec<(usize, Face)>),
+    /// The whole mesh before and after a change to its topology, like an extrusion
+    ReplaceMesh { before: AmyMesh, after: AmyMesh },
+}
+
+/// Approximate bytes of memory used by the contents of `mesh`
+fn mesh_size(mesh: &AmyMesh) -> usize {
+    size_of_val(mesh.vertices()) + size_of_val(mesh.texcoords()) + size_of_val(mesh.faces())
+}
+
+impl EditorCommand {
+    /// Make the change
+    pub fn apply(&self, mesh: &mut AmyMesh) {
+        match self {
+            Self::MoveVertices(moves) => {
+                for &(i, _, after) in moves {
+                    mesh.vertices_mut()[usize::from(i)] = after;
+                }
+            }
+            Self::DeleteFaces(faces) => {
+                for &(index, _) in faces.iter().rev() {
+                    mesh.remove_face(index);
+                }
+            }
+            Self::ReplaceMesh { after, .. } => after.clone_into(mesh),
+        }
+    }
+
+    /// Take back the change made by [`Self::apply`]
+    pub fn revert(&self, mesh: &mut AmyMesh) {
+        match self {
+            Self::MoveVertices(moves) => {
+                for &(i, before, _) in moves {
+                    mesh.vertices_mut()[usize::from(i)] = before;
+                }
+            }
+            Self::DeleteFaces(faces) => {
+                for &(index, face) in faces {
+                    mesh.insert_face(index, face);
+                }
+            }
+            Self::ReplaceMesh { before, .. } => before.clone_into(mesh),
+        }
+    }
+
+    /// Approximate bytes of memory used
+    pub fn size(&self) -> usize {
+        size_of::<Self>()
+            + match self {
+                Self::MoveVertices(moves) => size_of_val(moves.as_slice()),
+                Self::DeleteFaces(faces) => size_of_val(faces.as_slice()),
+                Self::ReplaceMesh { before, after } => mesh_size(before) + mesh_size(after),
+            }
+    }
+}
+
+/// Every change made to the mesh, so they can be undone and redone
+///
+/// The oldest changes are forgotten once the history takes up more than its memory budget.
+#[derive(Debug, Clone)]
+pub struct History {
+    undo: VecDeque<EditorCommand>,
+    redo: Vec<EditorCommand>,
+    /// Bytes used by `undo` and `redo`
+    size: usize,
+    budget: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUDGET)
+    }
+}
+
+impl History {
+    #[inline]
+    pub const fn new(budget: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            size: 0,
+            budget,
+        }
+    }
+
+    /// Apply `command` and remember it
+    ///
+    /// Anything that was undone can no longer be redone.
+    pub fn execute(&mut self, command: EditorCommand, mesh: &mut AmyMesh) {
+        command.apply(mesh);
+        self.record(command);
+    }
+
+    /// Remember `command`, which has already been applied
+    ///
+    /// Anything that was undone can no longer be redone.
+    pub fn record(&mut self, command: EditorCommand) {
+        for redo in self.redo.drain(..) {
+            self.size -= redo.size();
+        }
+        self.size += command.size();
+        self.undo.push_back(command);
+        while self.size > self.budget
+            && let Some(oldest) = self.undo.pop_front()
+        {
+            self.size -= oldest.size();
+        }
+    }
+
+    /// Revert the most recent change, returning `false` if there was nothing to undo
+    pub fn undo(&mut self, mesh: &mut AmyMesh) -> bool {
+        let Some(command) = self.undo.pop_back() else {
+            return false;
+        };
+        command.revert(mesh);
+        self.redo.push(command);
+        true
+    }
+
+    /// Reapply the most recently undone change, returning `false` if there was nothing to redo
+    pub fn redo(&mut self, mesh: &mut AmyMesh) -> bool {
+        let Some(command) = self.redo.pop() else {
+            return false;
+        };
+        command.apply(mesh);
+        self.undo.push_back(command);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut mesh = AmyMesh::gen_cube(1.0, 1.0, 1.0);
+        let mut history = History::default();
+        history.execute(
+            EditorCommand::MoveVertices(vec![(0, mesh.vertices()[0], Vector3::ZERO)]),
+            &mut mesh,
+        );
+        let deleted = vec![(2, mesh.faces()[2]), (4, mesh.faces()[4])];
+        history.execute(EditorCommand::DeleteFaces(deleted.clone()), &mut mesh);
+        assert_eq!(mesh.faces().len(), 4);
+
+        assert!(history.undo(&mut mesh));
+        assert_eq!(mesh.faces()[2], deleted[0].1);
+        assert_eq!(mesh.faces()[4], deleted[1].1);
+        assert!(history.undo(&mut mesh));
+        assert_eq!(mesh.vertices()[0], Vector3::splat(-1.0));
+        assert!(!history.undo(&mut mesh));
+
+        assert!(history.redo(&mut mesh));
+        assert_eq!(mesh.vertices()[0], Vector3::ZERO);
+        history.execute(
+            EditorCommand::DeleteFaces(vec![(0, mesh.faces()[0])]),
+            &mut mesh,
+        );
+        assert!(!history.redo(&mut mesh));
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut mesh = AmyMesh::gen_cube(1.0, 1.0, 1.0);
+        let command = EditorCommand::MoveVertices(vec![(0, Vector3::ZERO, Vector3::ONE)]);
+        let mut history = History::new(2 * command.size());
+        for _ in 0..3 {
+            history.execute(command.clone(), &mut mesh);
+        }
+        assert!(history.undo(&mut mesh));
+        assert!(history.undo(&mut mesh));
+        assert!(!history.undo(&mut mesh));
+    }
+}
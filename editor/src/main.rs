@@ -10,13 +10,31 @@
 
 use arrayvec::ArrayVec;
 use raylib::{math::glam::Quat, prelude::*};
-use std::sync::OnceLock;
+use std::{path::PathBuf, sync::OnceLock};
 use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, IntoStaticStr};
 
-use crate::mesh::AmyMesh;
+use crate::{
+    gizmo::{Axis, TransformKind, TransformOp, View},
+    history::{EditorCommand, History},
+    mesh::AmyMesh,
+    selection::{Element, Selection},
+};
 
+mod gizmo;
+mod gltf;
+mod history;
 mod mesh;
+mod modeling;
+mod obj;
+mod selection;
+
+/// How far the mouse has to move while held before a click becomes a box selection
+const DRAG_THRESHOLD: f32 = 4.0;
+/// How far extruding moves faces along their normals
+const EXTRUDE_DISTANCE: f32 = 0.5;
+/// How much of its original size an inset face keeps
+const INSET_FACTOR: f32 = 0.75;
 
 pub trait DropdownEnum:
     'static + Sized + Copy + Eq + std::hash::Hash + IntoEnumIterator + Into<&'static str>
@@ -162,6 +180,19 @@ impl<T: RaylibMaterial> MaterialExt for T {}
 fn main() {
     use {KeyboardKey::*, MouseButton::*};
 
+    // The OBJ file being edited, which is created on save if it doesn't exist
+    let path = std::env::args_os()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("untitled.obj"), PathBuf::from);
+    let mut asset = if path.exists() {
+        obj::load(&path).unwrap_or_else(|e| {
+            eprintln!("failed to load {}: {e}", path.display());
+            std::process::exit(1)
+        })
+    } else {
+        AmyMesh::gen_cube(1.0, 1.0, 1.0)
+    };
+
     let (mut rl, thread) = init().title("editor").resizable().build();
 
     rl.set_target_fps(120);
@@ -171,7 +202,6 @@ fn main() {
         EditorMode::default(),
     );
 
-    let mut asset = AmyMesh::gen_cube(1.0, 1.0, 1.0);
     let mut camera =
         Camera::perspective(Vector3::new(2.0, 2.0, 2.0), Vector3::ZERO, Vector3::Y, 45.0);
     let mut material = rl.load_material_default(&thread);
@@ -181,6 +211,12 @@ fn main() {
 
     let mut keys_pressed = Vec::new();
 
+    let mut selection = Selection::default();
+    let mut history = History::default();
+    let mut transform: Option<TransformOp> = None;
+    // Where the left mouse button was pressed, if it's held
+    let mut drag_start: Option<Vector2> = None;
+
     while !rl.window_should_close() {
         let dt = rl.get_frame_time();
 
@@ -204,6 +240,154 @@ fn main() {
                 _ => unreachable!(),
             };
         }
+        selection.retain_mode(mode_dropdown.value);
+
+        let mouse = rl.get_mouse_position();
+        let shift = rl.is_key_down(KEY_LEFT_SHIFT) || rl.is_key_down(KEY_RIGHT_SHIFT);
+        let ctrl = rl.is_key_down(KEY_LEFT_CONTROL) || rl.is_key_down(KEY_RIGHT_CONTROL);
+        if let Some(op) = &mut transform {
+            for (key, axis) in [(KEY_X, Axis::X), (KEY_Y, Axis::Y), (KEY_Z, Axis::Z)] {
+                if keys_pressed.contains(&key) {
+                    op.toggle_axis(axis);
+                }
+            }
+            #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+            let view = View {
+                camera,
+                screen_height: rl.get_screen_height() as f32,
+                pivot_screen: rl.get_world_to_screen(op.pivot(), camera),
+            };
+            op.apply(&mut asset, mouse, &view);
+
+            let confirm =
+                rl.is_mouse_button_pressed(MOUSE_BUTTON_LEFT) || keys_pressed.contains(&KEY_ENTER);
+            let cancel = rl.is_mouse_button_pressed(MOUSE_BUTTON_RIGHT)
+                || keys_pressed.contains(&KEY_ESCAPE);
+            if confirm || cancel {
+                let op = transform.take().expect("transform should be in progress");
+                if cancel {
+                    op.cancel(&mut asset);
+                } else {
+                    let moves = op
+                        .finish(&asset)
+                        .into_iter()
+                        .filter(|(_, before, after)| before != after)
+                        .collect::<Vec<_>>();
+                    if !moves.is_empty() {
+                        history.record(EditorCommand::MoveVertices(moves));
+                    }
+                }
+                rl.set_exit_key(Some(KEY_ESCAPE));
+            }
+        } else if ctrl {
+            let undo = keys_pressed.contains(&KEY_Z) && !shift;
+            let redo = keys_pressed.contains(&KEY_Y) || (keys_pressed.contains(&KEY_Z) && shift);
+            // Face indices may have shifted
+            if (undo && history.undo(&mut asset)) || (redo && history.redo(&mut asset)) {
+                selection.clear();
+            }
+
+            let edge = selection.edges().next();
+            if keys_pressed.contains(&KEY_R)
+                && mode_dropdown.value == EditorMode::Edge
+                && let Some(edge) = edge
+                && let Some(after) = modeling::loop_cut(&asset, edge)
+            {
+                let before = asset.clone();
+                history.execute(EditorCommand::ReplaceMesh { before, after }, &mut asset);
+                selection.clear();
+            }
+
+            if keys_pressed.contains(&KEY_S) {
+                match obj::save(&asset, &path) {
+                    Ok(()) => println!("saved {}", path.display()),
+                    Err(e) => eprintln!("failed to save {}: {e}", path.display()),
+                }
+            }
+            if keys_pressed.contains(&KEY_E) {
+                let export = path.with_extension("glb");
+                match gltf::save(&asset, &export) {
+                    Ok(()) => println!("exported {}", export.display()),
+                    Err(e) => eprintln!("failed to export {}: {e}", export.display()),
+                }
+            }
+        } else {
+            if mode_dropdown.value == EditorMode::Face {
+                if keys_pressed.contains(&KEY_DELETE) {
+                    let faces = selection
+                        .faces()
+                        .map(|i| (i, asset.faces()[i]))
+                        .collect::<Vec<_>>();
+                    if !faces.is_empty() {
+                        history.execute(EditorCommand::DeleteFaces(faces), &mut asset);
+                        selection.clear();
+                    }
+                }
+
+                // Faces keep their indices, so the selection stays on the changed faces
+                let faces = selection.faces().collect::<Vec<_>>();
+                let after = if keys_pressed.contains(&KEY_E) {
+                    modeling::extrude(&asset, &faces, EXTRUDE_DISTANCE)
+                } else if keys_pressed.contains(&KEY_I) {
+                    modeling::inset(&asset, &faces, INSET_FACTOR)
+                } else {
+                    None
+                };
+                if let Some(after) = after {
+                    let before = asset.clone();
+                    history.execute(EditorCommand::ReplaceMesh { before, after }, &mut asset);
+                }
+            }
+
+            let kind = [
+                (KEY_G, TransformKind::Translate),
+                (KEY_R, TransformKind::Rotate),
+                (KEY_S, TransformKind::Scale),
+            ]
+            .into_iter()
+            .find_map(|(key, kind)| keys_pressed.contains(&key).then_some(kind));
+            if let Some(kind) = kind
+                && let Some(pivot) = selection.center(&asset)
+            {
+                transform = Some(TransformOp::new(
+                    kind,
+                    &asset,
+                    selection.vertices(&asset),
+                    pivot,
+                    mouse,
+                ));
+                // Escape cancels the transform instead of closing the editor
+                rl.set_exit_key(None);
+            }
+
+            let over_ui =
+                mode_dropdown.is_editing || mode_dropdown.bounds.check_collision_point_rec(mouse);
+            if rl.is_mouse_button_pressed(MOUSE_BUTTON_LEFT) && !over_ui {
+                drag_start = Some(mouse);
+            } else if rl.is_mouse_button_released(MOUSE_BUTTON_LEFT)
+                && let Some(start) = drag_start.take()
+            {
+                if !shift {
+                    selection.clear();
+                }
+                if start.distance(mouse) < DRAG_THRESHOLD {
+                    let ray = rl.get_screen_to_world_ray(mouse, camera);
+                    if let Some(element) = selection::pick(&asset, mode_dropdown.value, ray) {
+                        selection.toggle(element);
+                    }
+                } else {
+                    let min = start.min(mouse);
+                    let size = (start - mouse).abs();
+                    let rect = Rectangle::new(min.x, min.y, size.x, size.y);
+                    selection.extend(selection::pick_box(
+                        &asset,
+                        mode_dropdown.value,
+                        rect,
+                        |v| rl.get_world_to_screen(v, camera),
+                    ));
+                }
+            }
+        }
 
         if rl.is_mouse_button_pressed(MOUSE_BUTTON_MIDDLE) {
             rl.disable_cursor();
@@ -272,34 +456,61 @@ fn main() {
                 ..Default::default()
             };
 
+            let highlight = |element: Element| {
+                if selection.contains(&element) {
+                    Color::ORANGE
+                } else {
+                    Color::YELLOW
+                }
+            };
+
             match mode_dropdown.value {
                 EditorMode::Vertex => {
-                    for &vert_world in asset.vertices() {
+                    for (i, &vert_world) in (0..).zip(asset.vertices()) {
                         let vert_screen = d.get_world_to_screen(vert_world, camera);
                         square.x = vert_screen.x - vert_extent;
                         square.y = vert_screen.y - vert_extent;
-                        d.draw_rectangle_rec(square, Color::YELLOW);
+                        d.draw_rectangle_rec(square, highlight(Element::Vertex(i)));
                     }
                 }
                 EditorMode::Edge => {
-                    let mut face = ArrayVec::<&Vector3, 5>::new();
-                    for verts in asset.face_vertices() {
-                        face.clear();
-                        face.try_extend_from_slice(verts.as_slice())
-                            .expect("faces do not exceed 4 vertices");
-                        face.push(verts[0]);
-                        for &world_positions in face.array_windows::<2>() {
-                            let [start_screen, end_screen] =
-                                world_positions.map(|v| d.get_world_to_screen(*v, camera));
-                            d.draw_line_v(start_screen, end_screen, Color::YELLOW);
-                        }
+                    for [a, b] in asset.edges() {
+                        let [start_screen, end_screen] = [a, b].map(|i| {
+                            d.get_world_to_screen(asset.vertices()[usize::from(i)], camera)
+                        });
+                        d.draw_line_ex(
+                            start_screen,
+                            end_screen,
+                            2.0,
+                            highlight(Element::new_edge(a, b)),
+                        );
                     }
                 }
                 EditorMode::Border => {
                     // todo
                 }
-                EditorMode::Face => {
-                    // todo
+                EditorMode::Face =>
+                {
+                    #[allow(clippy::cast_precision_loss, reason = "faces have at most 4 vertices")]
+                    for (i, verts) in asset.face_vertices().enumerate() {
+                        let center = verts.iter().copied().sum::<Vector3>() / verts.len() as f32;
+                        let center_screen = d.get_world_to_screen(center, camera);
+                        square.x = center_screen.x - vert_extent;
+                        square.y = center_screen.y - vert_extent;
+                        let color = highlight(Element::Face(i));
+                        d.draw_rectangle_rec(square, color);
+                        if selection.contains(&Element::Face(i)) {
+                            let mut face = ArrayVec::<&Vector3, 5>::new();
+                            face.try_extend_from_slice(verts.as_slice())
+                                .expect("faces do not exceed 4 vertices");
+                            face.push(verts[0]);
+                            for &world_positions in face.array_windows::<2>() {
+                                let [start_screen, end_screen] =
+                                    world_positions.map(|v| d.get_world_to_screen(*v, camera));
+                                d.draw_line_ex(start_screen, end_screen, 2.0, color);
+                            }
+                        }
+                    }
                 }
                 EditorMode::Mesh => {
                     // todo
@@ -309,6 +520,30 @@ fn main() {
                 }
             }
 
+            // gizmo
+            let gizmo = transform
+                .as_ref()
+                .map(|op| (op.pivot(), op.axis))
+                .or_else(|| selection.center(&asset).map(|pivot| (pivot, None)));
+            if let Some((pivot, active)) = gizmo {
+                let pivot_screen = d.get_world_to_screen(pivot, camera);
+                let ends = gizmo::handle_ends(pivot).map(|end| d.get_world_to_screen(end, camera));
+                gizmo::draw_axes(&mut d, pivot_screen, ends, active);
+            }
+
+            // box selection
+            if let Some(start) = drag_start
+                && start.distance(mouse) >= DRAG_THRESHOLD
+            {
+                let min = start.min(mouse);
+                let size = (start - mouse).abs();
+                d.draw_rectangle_lines_ex(
+                    Rectangle::new(min.x, min.y, size.x, size.y),
+                    1.0,
+                    Color::WHITE,
+                );
+            }
+
             // origin
             d.draw_ring(
                 d.get_world_to_screen(camera.target, camera),
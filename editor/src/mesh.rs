@@ -1,4 +1,5 @@
 use raylib::prelude::*;
+use std::collections::BTreeSet;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FaceData<T> {
@@ -101,6 +102,20 @@ impl<T> FaceData<T> {
     }
 }
 
+impl FaceData<&Vector3> {
+    /// The normal of the face, from the order of its vertices
+    ///
+    /// Uses Newell's method, so quads that aren't quite flat still get a sensible normal. Points,
+    /// lines, and faces with no area have a normal of zero.
+    pub fn normal(&self) -> Vector3 {
+        let verts = self.as_slice();
+        (0..verts.len())
+            .map(|i| verts[i].cross(*verts[(i + 1) % verts.len()]))
+            .sum::<Vector3>()
+            .normalize_or_zero()
+    }
+}
+
 impl<'a, T: 'a> IntoIterator for &'a FaceData<T> {
     type Item = &'a T;
     type IntoIter = std::slice::Iter<'a, T>;
@@ -181,6 +196,18 @@ impl FaceData<u16> {
     }
 }
 
+impl From<FaceData<u16>> for Face {
+    #[inline]
+    fn from(value: FaceData<u16>) -> Self {
+        match value {
+            FaceData::Point([a]) => Self::new_point(a),
+            FaceData::Line([a, b]) => Self::new_line(a, b),
+            FaceData::Triangle([a, b, c]) => Self::new_triangle(a, b, c),
+            FaceData::Quad([a, b, c, d]) => Self::new_quad(a, b, c, d),
+        }
+    }
+}
+
 /// Library-level invariant: Repeated indices must continue to the back of the array
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Face([u16; 4]);
@@ -234,6 +261,7 @@ impl Face {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct AmyMesh {
     vertices: Vec<Vector3>,
     texcoords: Vec<Vector2>,
@@ -319,12 +347,13 @@ impl AmyMesh {
         //  \|       \|     z
         //   1--------5
 
+        // Counterclockwise when seen from outside, so normals point out
         let faces = vec![
             Face::new_quad(0, 1, 3, 2),
-            Face::new_quad(0, 1, 5, 4),
-            Face::new_quad(4, 5, 7, 6),
+            Face::new_quad(0, 4, 5, 1),
+            Face::new_quad(4, 6, 7, 5),
             Face::new_quad(2, 3, 7, 6),
-            Face::new_quad(0, 4, 6, 2),
+            Face::new_quad(0, 2, 6, 4),
             Face::new_quad(1, 5, 7, 3),
         ];
 
@@ -336,6 +365,12 @@ impl AmyMesh {
         self.vertices.as_slice()
     }
 
+    /// Vertices can be moved freely, but not added or removed, since faces index into them
+    #[inline]
+    pub const fn vertices_mut(&mut self) -> &mut [Vector3] {
+        self.vertices.as_mut_slice()
+    }
+
     #[inline]
     pub const fn texcoords(&self) -> &[Vector2] {
         self.texcoords.as_slice()
@@ -346,6 +381,80 @@ impl AmyMesh {
         self.faces.as_slice()
     }
 
+    /// Add a vertex, returning its index, or [`None`] if the mesh already has as many vertices as
+    /// a face can index
+    pub fn push_vertex(&mut self, position: Vector3, texcoord: Vector2) -> Option<u16> {
+        let index = u16::try_from(self.vertices.len()).ok()?;
+        self.vertices.push(position);
+        self.texcoords.push(texcoord);
+        Some(index)
+    }
+
+    /// Add a face after all the others
+    ///
+    /// # Panics
+    ///
+    /// If `face` indexes a vertex that doesn't exist
+    pub fn push_face(&mut self, face: Face) {
+        self.insert_face(self.faces.len(), face);
+    }
+
+    /// Replace the face at `index`, returning the old one
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds, or `face` indexes a vertex that doesn't exist
+    pub fn replace_face(&mut self, index: usize, face: Face) -> Face {
+        let old = self.remove_face(index);
+        self.insert_face(index, face);
+        old
+    }
+
+    /// Remove and return the face at `index`, shifting later faces down
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds
+    #[inline]
+    pub fn remove_face(&mut self, index: usize) -> Face {
+        self.faces.remove(index)
+    }
+
+    /// Insert `face` at `index`, shifting later faces up
+    ///
+    /// # Panics
+    ///
+    /// If `index` is greater than the number of faces, or `face` indexes a vertex that doesn't exist
+    pub fn insert_face(&mut self, index: usize, face: Face) {
+        assert!(
+            face.0.iter().all(|&i| usize::from(i) < self.vertices.len()),
+            "every index should be a valid index into the vertex array",
+        );
+        self.faces.insert(index, face);
+    }
+
+    /// Every edge of every face exactly once, with the smaller vertex index first
+    pub fn edges(&self) -> impl Iterator<Item = [u16; 2]> {
+        let mut edges = BTreeSet::new();
+        for face in &self.faces {
+            let indices = face.indices();
+            let indices = indices.as_slice();
+            match indices {
+                [_] => {}
+                &[a, b] => {
+                    edges.insert([a.min(b), a.max(b)]);
+                }
+                _ => {
+                    for (i, &a) in indices.iter().enumerate() {
+                        let b = indices[(i + 1) % indices.len()];
+                        edges.insert([a.min(b), a.max(b)]);
+                    }
+                }
+            }
+        }
+        edges.into_iter()
+    }
+
     #[inline]
     pub fn face_vertices(&self) -> impl ExactSizeIterator<Item = FaceData<&Vector3>> {
         self.faces.iter().map(|face| {
@@ -355,6 +464,12 @@ impl AmyMesh {
         })
     }
 
+    /// The normal of each face, recomputed from its current vertices
+    #[inline]
+    pub fn face_normals(&self) -> impl ExactSizeIterator<Item = Vector3> {
+        self.face_vertices().map(|verts| verts.normal())
+    }
+
     #[inline]
     pub fn face_texcoords(&self) -> impl ExactSizeIterator<Item = FaceData<&Vector2>> {
         self.faces.iter().map(|face| {
@@ -0,0 +1,236 @@
+//! Operations that change the topology of a mesh
+//!
+//! Each takes the mesh by reference and returns the changed copy, or [`None`] if there was nothing
+//! to change or the result would have more vertices than a face can index. Faces keep their
+//! indices where possible and new faces go after the existing ones, so a face selection still
+//! points at the same part of the mesh afterwards. Winding is kept consistent, so the normals
+//! recomputed from the changed faces point the same way as before.
+
+use crate::mesh::{AmyMesh, Face};
+use raylib::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Each edge of a face in winding order
+fn directed_edges(indices: &[u16]) -> impl Iterator<Item = [u16; 2]> {
+    (0..indices.len()).map(|i| [indices[i], indices[(i + 1) % indices.len()]])
+}
+
+/// Smaller index first, so an edge compares equal regardless of direction
+const fn undirected([a, b]: [u16; 2]) -> [u16; 2] {
+    if a < b { [a, b] } else { [b, a] }
+}
+
+/// Move the selected faces along their normals by `distance`, joining them to where they were
+/// with new side faces
+///
+/// Neighboring selected faces move together as one region, and vertices they share move along
+/// the average of their normals. Only the edges around the outside of a region get side faces.
+pub fn extrude(mesh: &AmyMesh, faces: &[usize], distance: f32) -> Option<AmyMesh> {
+    let faces: Vec<usize> = faces
+        .iter()
+        .copied()
+        .filter(|&i| mesh.faces()[i].indices().len() >= 3)
+        .collect();
+    if faces.is_empty() {
+        return None;
+    }
+    let mut result = mesh.clone();
+
+    let face_normals: Vec<Vector3> = mesh.face_normals().collect();
+    let mut normals = BTreeMap::<u16, Vector3>::new();
+    for &i in &faces {
+        for &v in &mesh.faces()[i].indices() {
+            *normals.entry(v).or_default() += face_normals[i];
+        }
+    }
+    let mut copies = BTreeMap::new();
+    for (&v, normal) in &normals {
+        let position = mesh.vertices()[usize::from(v)] + normal.normalize_or_zero() * distance;
+        let copy = result.push_vertex(position, mesh.texcoords()[usize::from(v)])?;
+        copies.insert(v, copy);
+    }
+
+    // An edge is on the outside of a region if no face in the region runs along it backwards
+    let edges: BTreeSet<[u16; 2]> = faces
+        .iter()
+        .flat_map(|&i| directed_edges(mesh.faces()[i].indices().as_slice()).collect::<Vec<_>>())
+        .collect();
+    for &[a, b] in &edges {
+        if !edges.contains(&[b, a]) {
+            result.push_face(Face::new_quad(a, b, copies[&b], copies[&a]));
+        }
+    }
+    for &i in &faces {
+        result.replace_face(i, mesh.faces()[i].indices().map(|v| copies[&v]).into());
+    }
+    Some(result)
+}
+
+/// Shrink each selected face towards its center by `factor`, filling the gap with a ring of new
+/// faces
+pub fn inset(mesh: &AmyMesh, faces: &[usize], factor: f32) -> Option<AmyMesh> {
+    let mut result = mesh.clone();
+    let mut changed = false;
+    for &i in faces {
+        let indices = mesh.faces()[i].indices();
+        if indices.len() < 3 {
+            continue;
+        }
+        #[allow(clippy::cast_precision_loss, reason = "faces have at most 4 vertices")]
+        let count = indices.len() as f32;
+        let center = indices
+            .iter()
+            .map(|&v| mesh.vertices()[usize::from(v)])
+            .sum::<Vector3>()
+            / count;
+        let center_uv = indices
+            .iter()
+            .map(|&v| mesh.texcoords()[usize::from(v)])
+            .sum::<Vector2>()
+            / count;
+
+        let mut inner = BTreeMap::new();
+        for &v in &indices {
+            let position = center.lerp(mesh.vertices()[usize::from(v)], factor);
+            let texcoord = center_uv.lerp(mesh.texcoords()[usize::from(v)], factor);
+            inner.insert(v, result.push_vertex(position, texcoord)?);
+        }
+        for [a, b] in directed_edges(indices.as_slice()) {
+            result.push_face(Face::new_quad(a, b, inner[&b], inner[&a]));
+        }
+        result.replace_face(i, indices.map(|v| inner[&v]).into());
+        changed = true;
+    }
+    changed.then_some(result)
+}
+
+/// Cut every quad in the strip running across `edge` in half, through the middle of the edges
+/// the strip crosses
+///
+/// The strip stops at faces that aren't quads and at the edge of the mesh, or goes all the way
+/// around if it comes back to where it started. Triangles at the ends of the strip get the new
+/// vertex on their side so the mesh doesn't come apart there.
+pub fn loop_cut(mesh: &AmyMesh, edge: [u16; 2]) -> Option<AmyMesh> {
+    let quads_across = |edge: [u16; 2]| {
+        mesh.faces()
+            .iter()
+            .enumerate()
+            .filter(move |(_, face)| {
+                let indices = face.indices();
+                indices.len() == 4
+                    && directed_edges(indices.as_slice()).any(|e| undirected(e) == edge)
+            })
+            .map(|(i, _)| i)
+    };
+    // The other side of `quad` from `edge`
+    let opposite = |quad: usize, edge: [u16; 2]| {
+        let indices = mesh.faces()[quad].indices();
+        let indices = indices.as_slice();
+        let at = (0..4)
+            .find(|&r| undirected([indices[r], indices[(r + 1) % 4]]) == edge)
+            .expect("quad should contain the edge");
+        undirected([indices[(at + 2) % 4], indices[(at + 3) % 4]])
+    };
+
+    let edge = undirected(edge);
+    let mut strip = Vec::new();
+    let mut cut = BTreeSet::from([edge]);
+    // An edge has two sides, so walk the strip both ways
+    for start in quads_across(edge).take(2).collect::<Vec<_>>() {
+        let mut edge = edge;
+        let mut quad = Some(start);
+        while let Some(current) = quad.filter(|q| !strip.contains(q)) {
+            strip.push(current);
+            edge = opposite(current, edge);
+            cut.insert(edge);
+            quad = quads_across(edge).find(|q| !strip.contains(q));
+        }
+    }
+    if strip.is_empty() {
+        return None;
+    }
+
+    let mut result = mesh.clone();
+    let mut midpoints = BTreeMap::new();
+    for &edge in &cut {
+        let [a, b] = edge.map(usize::from);
+        let position = mesh.vertices()[a].midpoint(mesh.vertices()[b]);
+        let texcoord = mesh.texcoords()[a].midpoint(mesh.texcoords()[b]);
+        midpoints.insert(edge, result.push_vertex(position, texcoord)?);
+    }
+    let mid = |a: u16, b: u16| midpoints[&undirected([a, b])];
+
+    for &quad in &strip {
+        let indices = mesh.faces()[quad].indices();
+        let indices = indices.as_slice();
+        let r = (0..4)
+            .find(|&r| {
+                cut.contains(&undirected([indices[r], indices[(r + 1) % 4]]))
+                    && cut.contains(&undirected([indices[(r + 2) % 4], indices[(r + 3) % 4]]))
+            })
+            .expect("strip quads should be cut on opposite sides");
+        let [p0, p1, p2, p3] = [0, 1, 2, 3].map(|i| indices[(r + i) % 4]);
+        result.replace_face(quad, Face::new_quad(p0, mid(p0, p1), mid(p2, p3), p3));
+        result.push_face(Face::new_quad(mid(p0, p1), p1, p2, mid(p2, p3)));
+    }
+
+    for (i, face) in mesh.faces().iter().enumerate() {
+        if let [a, b, c] = *face.indices().as_slice() {
+            let rotations = [[a, b, c], [b, c, a], [c, a, b]];
+            if let Some([x, y, z]) = rotations
+                .into_iter()
+                .find(|&[x, y, _]| cut.contains(&undirected([x, y])))
+            {
+                result.replace_face(i, Face::new_quad(x, mid(x, y), y, z));
+            }
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modeling() {
+        let cube = AmyMesh::gen_cube(1.0, 1.0, 1.0);
+
+        // The top face
+        let extruded = extrude(&cube, &[3], 1.0).unwrap();
+        assert_eq!(extruded.vertices().len(), 12);
+        assert_eq!(extruded.faces().len(), 10);
+        assert!(
+            extruded
+                .face_vertices()
+                .nth(3)
+                .unwrap()
+                .iter()
+                .all(|v| v.y == 2.0),
+            "extruded face should move up along its normal"
+        );
+        assert_eq!(
+            extruded.face_normals().nth(3),
+            cube.face_normals().nth(3),
+            "extruded face should keep its winding"
+        );
+
+        let inset = inset(&cube, &[3], 0.5).unwrap();
+        assert_eq!(inset.vertices().len(), 12);
+        assert_eq!(inset.faces().len(), 10);
+        assert!(
+            inset
+                .face_vertices()
+                .nth(3)
+                .unwrap()
+                .iter()
+                .all(|v| v.x.abs() == 0.5)
+        );
+
+        // Around the cube, through the four edges parallel to z
+        let cut = loop_cut(&cube, [1, 0]).unwrap();
+        assert_eq!(cut.vertices().len(), 12);
+        assert_eq!(cut.faces().len(), 10);
+        assert!(cut.vertices()[8..].iter().all(|v| v.z == 0.0));
+    }
+}
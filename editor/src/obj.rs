@@ -0,0 +1,293 @@
+//! Reading and writing Wavefront OBJ files
+//!
+//! Supports positions (`v`), texture coordinates (`vt`), normals (`vn`), and faces (`f`), lines
+//! (`l`), and points (`p`). Everything else (materials, groups, smoothing) is ignored.
+//!
+//! Meshes store one texture coordinate per vertex, so a position used with different texture
+//! coordinates by different faces becomes several vertices. Normals are derived from the winding
+//! of each face, so imported normals are only checked for validity.
+
+use crate::mesh::{AmyMesh, Face};
+use raylib::prelude::*;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    path::Path,
+};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(io::Error),
+    /// A line that couldn't be parsed, numbered from 1
+    Syntax {
+        line: usize,
+    },
+    /// A face refers to a position, texture coordinate, or normal that doesn't exist
+    BadIndex {
+        line: usize,
+    },
+    /// A face that uses the same vertex more than once
+    Degenerate {
+        line: usize,
+    },
+    /// More vertices than a mesh can index
+    TooManyVertices,
+}
+
+impl std::fmt::Display for ObjError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::Syntax { line } => write!(f, "syntax error on line {line}"),
+            Self::BadIndex { line } => write!(f, "index out of range on line {line}"),
+            Self::Degenerate { line } => write!(f, "face on line {line} repeats a vertex"),
+            Self::TooManyVertices => write!(f, "mesh has more than {} vertices", u16::MAX),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ObjError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Resolve a 1-based (or negative, counting back from the end) OBJ index into a 0-based index
+fn resolve(index: &str, len: usize, line: usize) -> Result<usize, ObjError> {
+    let index: isize = index.parse().map_err(|_| ObjError::Syntax { line })?;
+    let resolved = match index {
+        1.. => index.unsigned_abs() - 1,
+        ..0 => len
+            .checked_sub(index.unsigned_abs())
+            .ok_or(ObjError::BadIndex { line })?,
+        0 => return Err(ObjError::BadIndex { line }),
+    };
+    if resolved < len {
+        Ok(resolved)
+    } else {
+        Err(ObjError::BadIndex { line })
+    }
+}
+
+/// Parse exactly `N` floats, ignoring any optional components that follow
+fn floats<const N: usize>(
+    words: &mut std::str::SplitWhitespace<'_>,
+    line: usize,
+) -> Result<[f32; N], ObjError> {
+    let mut values = [0.0; N];
+    for value in &mut values {
+        *value = words
+            .next()
+            .and_then(|word| word.parse().ok())
+            .ok_or(ObjError::Syntax { line })?;
+    }
+    Ok(values)
+}
+
+/// Parse the contents of an OBJ file
+///
+/// Faces with more than 4 vertices are split into a fan of triangles.
+pub fn parse(src: &str) -> Result<AmyMesh, ObjError> {
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normal_count = 0;
+    // Line number, keyword, and the position and texcoord index of each corner
+    let mut elements = Vec::new();
+
+    for (line, text) in (1..).zip(src.lines()) {
+        let text = text.split_once('#').map_or(text, |(text, _)| text);
+        let mut words = text.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        match keyword {
+            "v" => positions.push(Vector3::from_array(floats(&mut words, line)?)),
+            "vt" => {
+                let [u, v] = floats(&mut words, line)?;
+                // OBJ puts the origin at the bottom left, raylib at the top left
+                uvs.push(Vector2::new(u, 1.0 - v));
+            }
+            "vn" => {
+                floats::<3>(&mut words, line)?;
+                normal_count += 1;
+            }
+            "f" | "l" | "p" => {
+                let mut corners = Vec::new();
+                for word in words {
+                    let mut parts = word.split('/');
+                    let position = resolve(parts.next().unwrap_or(""), positions.len(), line)?;
+                    let uv = match parts.next() {
+                        None | Some("") => None,
+                        Some(uv) => Some(resolve(uv, uvs.len(), line)?),
+                    };
+                    if let Some(normal) = parts.next() {
+                        resolve(normal, normal_count, line)?;
+                    }
+                    if parts.next().is_some() {
+                        return Err(ObjError::Syntax { line });
+                    }
+                    corners.push((position, uv));
+                }
+                elements.push((line, keyword, corners));
+            }
+            _ => {}
+        }
+    }
+
+    if u16::try_from(positions.len()).is_err() {
+        return Err(ObjError::TooManyVertices);
+    }
+    // Each position keeps its index for the first texcoord it's used with, so that files
+    // without seams load with their vertices in the same order
+    let mut vertices = positions.clone();
+    let mut texcoords = vec![Vector2::ZERO; positions.len()];
+    let mut claimed = vec![None; positions.len()];
+    let mut seams = HashMap::<(usize, Option<usize>), u16>::new();
+    let mut faces = Vec::new();
+
+    for (line, keyword, corners) in elements {
+        let mut indices = Vec::with_capacity(corners.len());
+        for (position, uv) in corners {
+            let index = match claimed[position] {
+                None => {
+                    claimed[position] = Some(uv);
+                    texcoords[position] = uv.map_or(Vector2::ZERO, |uv| uvs[uv]);
+                    position
+                }
+                Some(claimed_uv) if claimed_uv == uv => position,
+                Some(_) => match seams.get(&(position, uv)) {
+                    Some(&index) => usize::from(index),
+                    None => {
+                        let index =
+                            u16::try_from(vertices.len()).map_err(|_| ObjError::TooManyVertices)?;
+                        vertices.push(positions[position]);
+                        texcoords.push(uv.map_or(Vector2::ZERO, |uv| uvs[uv]));
+                        seams.insert((position, uv), index);
+                        usize::from(index)
+                    }
+                },
+            };
+            indices.push(u16::try_from(index).expect("vertex count was checked"));
+        }
+
+        let distinct = indices
+            .iter()
+            .enumerate()
+            .all(|(i, index)| !indices[..i].contains(index));
+        if keyword != "p" && !distinct {
+            return Err(ObjError::Degenerate { line });
+        }
+        match (keyword, indices.as_slice()) {
+            ("p", points) if !points.is_empty() => {
+                faces.extend(points.iter().map(|&i| Face::new_point(i)));
+            }
+            ("l", lines) if lines.len() >= 2 => {
+                faces.extend(lines.array_windows().map(|&[a, b]| Face::new_line(a, b)));
+            }
+            ("f", &[a, b, c]) => faces.push(Face::new_triangle(a, b, c)),
+            ("f", &[a, b, c, d]) => faces.push(Face::new_quad(a, b, c, d)),
+            ("f", &[a, ref rest @ ..]) if rest.len() >= 4 => {
+                faces.extend(
+                    rest.array_windows()
+                        .map(|&[b, c]| Face::new_triangle(a, b, c)),
+                );
+            }
+            _ => return Err(ObjError::Syntax { line }),
+        }
+    }
+
+    Ok(AmyMesh::new(vertices, texcoords, faces))
+}
+
+/// Read and parse the OBJ file at `path`
+pub fn load(path: impl AsRef<Path>) -> Result<AmyMesh, ObjError> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+/// Write `mesh` in OBJ format
+///
+/// Each face gets its own flat normal.
+pub fn write(mesh: &AmyMesh, w: &mut impl Write) -> io::Result<()> {
+    for v in mesh.vertices() {
+        writeln!(w, "v {} {} {}", v.x, v.y, v.z)?;
+    }
+    for uv in mesh.texcoords() {
+        writeln!(w, "vt {} {}", uv.x, 1.0 - uv.y)?;
+    }
+    let mut normal = 0;
+    for (face, n) in mesh.faces().iter().zip(mesh.face_normals()) {
+        let indices = face.indices();
+        let indices = indices.as_slice();
+        match *indices {
+            [a] => writeln!(w, "p {}", a + 1)?,
+            [a, b] => writeln!(w, "l {}/{} {}/{}", a + 1, a + 1, b + 1, b + 1)?,
+            _ => {
+                normal += 1;
+                writeln!(w, "vn {} {} {}", n.x, n.y, n.z)?;
+                write!(w, "f")?;
+                for &i in indices {
+                    write!(w, " {}/{}/{normal}", i + 1, i + 1)?;
+                }
+                writeln!(w)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write `mesh` to the OBJ file at `path`, replacing it if it exists
+pub fn save(mesh: &AmyMesh, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut w = io::BufWriter::new(std::fs::File::create(path)?);
+    write(mesh, &mut w)?;
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let cube = AmyMesh::gen_cube(1.0, 2.0, 0.5);
+        let mut out = Vec::new();
+        write(&cube, &mut out).unwrap();
+        let parsed = parse(std::str::from_utf8(&out).unwrap()).unwrap();
+        assert_eq!(parsed.vertices(), cube.vertices());
+        assert_eq!(parsed.texcoords(), cube.texcoords());
+        assert_eq!(parsed.faces(), cube.faces());
+    }
+
+    #[test]
+    fn test_parse() {
+        let src = "\
+            # a pentagon, with one corner textured differently by a second face\n\
+            v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv -1 0.5 0\n\
+            vt 0 0\nvt 1 1\n\
+            vn 0 0 1\n\
+            f 1//1 2//1 3//1 4//1 5//1\n\
+            f -5/-2 -4/2 -3/2\n";
+        let mesh = parse(src).unwrap();
+        assert_eq!(mesh.vertices().len(), 8);
+        assert_eq!(mesh.faces().len(), 4);
+        assert_eq!(mesh.texcoords()[5], Vector2::new(0.0, 1.0));
+        assert_eq!(mesh.faces()[3], Face::new_triangle(5, 6, 7));
+
+        assert!(matches!(
+            parse("v 0 0 0\nf 1 2 3\n"),
+            Err(ObjError::BadIndex { line: 2 })
+        ));
+        assert!(matches!(
+            parse("v 0 0 0\nv 1 0 0\nf 1 2 1\n"),
+            Err(ObjError::Degenerate { line: 3 })
+        ));
+    }
+}
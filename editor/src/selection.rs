@@ -0,0 +1,269 @@
+use crate::{EditorMode, mesh::AmyMesh};
+use raylib::prelude::*;
+use std::collections::BTreeSet;
+
+/// How far from the mouse ray something can be and still get picked, in radians
+const PICK_ANGLE: f32 = 0.02;
+
+/// A selectable part of a mesh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Element {
+    Vertex(u16),
+    /// Vertex indices, smallest first
+    Edge([u16; 2]),
+    /// Index into [`AmyMesh::faces`]
+    Face(usize),
+}
+
+impl Element {
+    #[inline]
+    pub fn new_edge(a: u16, b: u16) -> Self {
+        Self::Edge(if a < b { [a, b] } else { [b, a] })
+    }
+}
+
+/// Distance along `ray` to where it hits triangle `[a, b, c]` from either side
+///
+/// Möller–Trumbore intersection.
+pub fn ray_triangle(ray: Ray, [a, b, c]: [Vector3; 3]) -> Option<f32> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let p = ray.direction.cross(edge2);
+    let det = edge1.dot(p);
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let to_origin = ray.position - a;
+    let u = to_origin.dot(p) / det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = to_origin.cross(edge1);
+    let v = ray.direction.dot(q) / det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = edge2.dot(q) / det;
+    (t >= 0.0).then_some(t)
+}
+
+/// Distance along `ray` to the point nearest `point`, if `point` is within [`PICK_ANGLE`] of it
+fn ray_point(ray: Ray, point: Vector3) -> Option<f32> {
+    let t = (point - ray.position).dot(ray.direction);
+    let miss = point.distance(ray.position + ray.direction * t);
+    (t > 0.0 && miss <= t * PICK_ANGLE.tan()).then_some(t)
+}
+
+/// Distance along `ray` to the point nearest segment `[a, b]`, if the segment is within
+/// [`PICK_ANGLE`] of it
+fn ray_segment(ray: Ray, [a, b]: [Vector3; 2]) -> Option<f32> {
+    let along = b - a;
+    let to_origin = ray.position - a;
+    let d = ray.direction.dot(along);
+    let e = along.length_squared();
+    let denom = e - d * d;
+    // Nearest point on the segment, as a fraction of the way from `a` to `b`
+    let s = if denom.abs() < f32::EPSILON {
+        0.0
+    } else {
+        ((along.dot(to_origin) - d * ray.direction.dot(to_origin)) / denom).clamp(0.0, 1.0)
+    };
+    ray_point(ray, a + along * s)
+}
+
+/// The element of `mesh` nearest the camera that `ray` passes through or near
+///
+/// Which kind of element is picked depends on `mode`. Returns [`None`] in modes without
+/// element selection.
+pub fn pick(mesh: &AmyMesh, mode: EditorMode, ray: Ray) -> Option<Element> {
+    let nearest = |elements: &mut dyn Iterator<Item = (f32, Element)>| {
+        elements
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, element)| element)
+    };
+    match mode {
+        EditorMode::Vertex => nearest(
+            &mut (0..)
+                .zip(mesh.vertices())
+                .filter_map(|(i, &v)| ray_point(ray, v).map(|t| (t, Element::Vertex(i)))),
+        ),
+        EditorMode::Edge => nearest(&mut mesh.edges().filter_map(|[a, b]| {
+            let segment = [a, b].map(|i| mesh.vertices()[usize::from(i)]);
+            ray_segment(ray, segment).map(|t| (t, Element::new_edge(a, b)))
+        })),
+        EditorMode::Face => nearest(&mut mesh.face_vertices().enumerate().filter_map(
+            |(i, verts)| {
+                let verts = verts.map(|v| *v);
+                let triangles = match *verts.as_slice() {
+                    [a, b, c] => [Some([a, b, c]), None],
+                    [a, b, c, d] => [Some([a, b, c]), Some([a, c, d])],
+                    _ => [None, None],
+                };
+                triangles
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|triangle| ray_triangle(ray, triangle))
+                    .min_by(f32::total_cmp)
+                    .map(|t| (t, Element::Face(i)))
+            },
+        )),
+        EditorMode::Border | EditorMode::Mesh | EditorMode::Object => None,
+    }
+}
+
+/// Every element of `mesh` whose vertices all project inside `rect`
+///
+/// `to_screen` projects a point from world space to screen space.
+pub fn pick_box(
+    mesh: &AmyMesh,
+    mode: EditorMode,
+    rect: Rectangle,
+    to_screen: impl Fn(Vector3) -> Vector2,
+) -> Vec<Element> {
+    let inside: Vec<bool> = mesh
+        .vertices()
+        .iter()
+        .map(|&v| rect.check_collision_point_rec(to_screen(v)))
+        .collect();
+    let is_inside = |i: u16| inside[usize::from(i)];
+    match mode {
+        EditorMode::Vertex => (0..)
+            .zip(&inside)
+            .filter(|&(_, &inside)| inside)
+            .map(|(i, _)| Element::Vertex(i))
+            .collect(),
+        EditorMode::Edge => mesh
+            .edges()
+            .filter(|&[a, b]| is_inside(a) && is_inside(b))
+            .map(|[a, b]| Element::new_edge(a, b))
+            .collect(),
+        EditorMode::Face => mesh
+            .faces()
+            .iter()
+            .enumerate()
+            .filter(|(_, face)| face.indices().iter().all(|&i| is_inside(i)))
+            .map(|(i, _)| Element::Face(i))
+            .collect(),
+        EditorMode::Border | EditorMode::Mesh | EditorMode::Object => Vec::new(),
+    }
+}
+
+/// The selected parts of the mesh being edited
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    elements: BTreeSet<Element>,
+}
+
+impl Selection {
+    #[inline]
+    pub fn contains(&self, element: &Element) -> bool {
+        self.elements.contains(element)
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.elements.clear();
+    }
+
+    #[inline]
+    pub fn extend(&mut self, elements: impl IntoIterator<Item = Element>) {
+        self.elements.extend(elements);
+    }
+
+    /// Select `element` if it isn't selected, otherwise deselect it
+    pub fn toggle(&mut self, element: Element) {
+        if !self.elements.remove(&element) {
+            self.elements.insert(element);
+        }
+    }
+
+    /// Remove every element that isn't of the kind `mode` selects
+    pub fn retain_mode(&mut self, mode: EditorMode) {
+        self.elements.retain(|element| {
+            matches!(
+                (mode, element),
+                (EditorMode::Vertex, Element::Vertex(_))
+                    | (EditorMode::Edge, Element::Edge(_))
+                    | (EditorMode::Face, Element::Face(_))
+            )
+        });
+    }
+
+    /// The selected edges, in increasing order
+    pub fn edges(&self) -> impl Iterator<Item = [u16; 2]> {
+        self.elements.iter().filter_map(|element| match *element {
+            Element::Edge(edge) => Some(edge),
+            Element::Vertex(_) | Element::Face(_) => None,
+        })
+    }
+
+    /// Indices of the selected faces, in increasing order
+    pub fn faces(&self) -> impl Iterator<Item = usize> {
+        self.elements.iter().filter_map(|element| match *element {
+            Element::Face(i) => Some(i),
+            Element::Vertex(_) | Element::Edge(_) => None,
+        })
+    }
+
+    /// Indices of every vertex touched by the selection, without duplicates
+    pub fn vertices(&self, mesh: &AmyMesh) -> BTreeSet<u16> {
+        let mut vertices = BTreeSet::new();
+        for element in &self.elements {
+            match *element {
+                Element::Vertex(i) => {
+                    vertices.insert(i);
+                }
+                Element::Edge(edge) => vertices.extend(edge),
+                Element::Face(i) => {
+                    if let Some(face) = mesh.faces().get(i) {
+                        vertices.extend(face.indices().iter().copied());
+                    }
+                }
+            }
+        }
+        vertices
+    }
+
+    /// The average position of the selected vertices
+    #[allow(clippy::cast_precision_loss, reason = "meshes are small")]
+    pub fn center(&self, mesh: &AmyMesh) -> Option<Vector3> {
+        let vertices = self.vertices(mesh);
+        (!vertices.is_empty()).then(|| {
+            vertices
+                .iter()
+                .map(|&i| mesh.vertices()[usize::from(i)])
+                .sum::<Vector3>()
+                / vertices.len() as f32
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick() {
+        let mesh = AmyMesh::gen_cube(1.0, 1.0, 1.0);
+        // Looking down at the top of the cube, slightly off center
+        let ray = Ray {
+            position: Vector3::new(0.25, 10.0, 0.25),
+            direction: Vector3::NEG_Y,
+        };
+        assert_eq!(pick(&mesh, EditorMode::Face, ray), Some(Element::Face(3)));
+        assert_eq!(pick(&mesh, EditorMode::Vertex, ray), None);
+
+        let corner = Ray {
+            position: Vector3::new(1.0, 10.0, 1.0),
+            direction: Vector3::NEG_Y,
+        };
+        assert_eq!(
+            pick(&mesh, EditorMode::Vertex, corner),
+            Some(Element::Vertex(7))
+        );
+        assert!(matches!(
+            pick(&mesh, EditorMode::Edge, corner),
+            Some(Element::Edge([3 | 5 | 6, 7]))
+        ));
+    }
+}
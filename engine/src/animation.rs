@@ -0,0 +1,324 @@
+//! Skeletal animation.
+//!
+//! Keyframes are copied out of raylib [`ffi::ModelAnimation`]s into [`Clip`]s, so they can be
+//! sampled and blended without touching raylib. An [`Animator`] keeps track of how far through
+//! its clips something is, and [`apply_pose`] hands the result back to raylib to draw.
+
+use raylib::prelude::*;
+
+/// Where one bone is, relative to its parent.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BonePose {
+    /// Offset from the parent.
+    pub translation: Vector3,
+    /// Rotation about the parent.
+    pub rotation: Quaternion,
+    /// Scale along each axis.
+    pub scale: Vector3,
+}
+
+impl BonePose {
+    /// A bone right where its parent is.
+    pub const IDENTITY: Self = Self {
+        translation: Vector3::ZERO,
+        rotation: Quaternion::IDENTITY,
+        scale: Vector3::ONE,
+    };
+
+    /// Interpolates from `self` at 0 to `other` at 1.
+    #[must_use]
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+
+    /// Converts a raylib bone transform.
+    #[must_use]
+    pub fn from_raw(transform: &ffi::Transform) -> Self {
+        Self {
+            translation: transform.translation.into(),
+            rotation: transform.rotation.into(),
+            scale: transform.scale.into(),
+        }
+    }
+
+    /// Converts to a raylib bone transform.
+    #[must_use]
+    pub fn to_raw(&self) -> ffi::Transform {
+        ffi::Transform {
+            translation: self.translation.into(),
+            rotation: self.rotation.into(),
+            scale: self.scale.into(),
+        }
+    }
+}
+
+/// A looping animation: the pose of every bone at evenly spaced keyframes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Clip {
+    /// Every keyframe has a pose for each bone.
+    frames: Vec<Vec<BonePose>>,
+    /// Keyframes per second.
+    fps: f32,
+}
+
+impl Clip {
+    /// Creates a clip playing `frames` at `fps` keyframes per second.
+    ///
+    /// # Panics
+    ///
+    /// If the frames don't all have the same number of bones.
+    #[must_use]
+    pub fn new(frames: Vec<Vec<BonePose>>, fps: f32) -> Self {
+        assert!(
+            frames.windows(2).all(|pair| pair[0].len() == pair[1].len()),
+            "every keyframe should pose the same bones"
+        );
+        Self { frames, fps }
+    }
+
+    /// Copies the keyframes out of `animation`, to be played at `fps` keyframes per second.
+    ///
+    /// # Safety
+    ///
+    /// `animation` must have been loaded by raylib and not unloaded yet.
+    #[must_use]
+    pub unsafe fn from_raw(animation: &ffi::ModelAnimation, fps: f32) -> Self {
+        let bones = usize::try_from(animation.boneCount).unwrap_or(0);
+        let frame_count = usize::try_from(animation.frameCount).unwrap_or(0);
+        if animation.framePoses.is_null() {
+            return Self::default();
+        }
+        // SAFETY: raylib allocates `frameCount` frames
+        let frames = unsafe { std::slice::from_raw_parts(animation.framePoses, frame_count) };
+        let frames = (frames.iter())
+            .map(|&frame| {
+                // SAFETY: raylib allocates `boneCount` transforms for each frame
+                let poses = unsafe { std::slice::from_raw_parts(frame, bones) };
+                poses.iter().map(BonePose::from_raw).collect()
+            })
+            .collect();
+        Self { frames, fps }
+    }
+
+    /// Whether there's nothing to play.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Number of bones each keyframe poses.
+    #[must_use]
+    pub fn bone_count(&self) -> usize {
+        self.frames.first().map_or(0, Vec::len)
+    }
+
+    /// Seconds to play through once.
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "clips have far fewer than 2^24 keyframes"
+    )]
+    #[must_use]
+    pub fn duration(&self) -> f32 {
+        if self.fps > 0.0 {
+            self.frames.len() as f32 / self.fps
+        } else {
+            0.0
+        }
+    }
+
+    /// The pose `time` seconds in, interpolated between keyframes.
+    ///
+    /// Loops, so the last keyframe blends back into the first.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss,
+        reason = "wrapped to a keyframe index first"
+    )]
+    #[must_use]
+    pub fn sample(&self, time: f32) -> Vec<BonePose> {
+        let Some(first) = self.frames.first() else {
+            return Vec::new();
+        };
+        let duration = self.duration();
+        if duration <= 0.0 {
+            return first.clone();
+        }
+        let position = time.rem_euclid(duration) * self.fps;
+        let frame = (position.floor() as usize).min(self.frames.len() - 1);
+        let next = (frame + 1) % self.frames.len();
+        let t = position - frame as f32;
+        (self.frames[frame].iter())
+            .zip(&self.frames[next])
+            .map(|(a, b)| a.lerp(b, t))
+            .collect()
+    }
+}
+
+/// Which clip an [`Animator`] is blending toward.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum AnimationState {
+    /// Standing still.
+    #[default]
+    Idle,
+    /// Doing its job.
+    Working,
+}
+
+/// Plays an idle and a working clip, blending smoothly from one to the other when the state
+/// changes.
+///
+/// Only keeps track of time; the clips themselves are passed in when sampling, so many animators
+/// can share them.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Animator {
+    /// What it's blending toward.
+    pub state: AnimationState,
+    /// Seconds into the idle clip.
+    idle_time: f32,
+    /// Seconds into the working clip, which plays faster or slower with how hard it's working.
+    working_time: f32,
+    /// How much of the working clip is showing, from 0 to 1.
+    blend: f32,
+}
+
+impl Default for Animator {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Animator {
+    /// Seconds to blend all the way from one state to the other.
+    pub const BLEND_TIME: f32 = 0.5;
+
+    /// Creates an animator at the start of its clips, idle.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            state: AnimationState::Idle,
+            idle_time: 0.0,
+            working_time: 0.0,
+            blend: 0.0,
+        }
+    }
+
+    /// How much of the working clip is showing, from 0 when idle to 1 when working.
+    #[must_use]
+    pub const fn blend(&self) -> f32 {
+        self.blend
+    }
+
+    /// Advances `dt` seconds blending toward `state`, with the working clip played at `speed`
+    /// times its normal rate.
+    pub fn tick(&mut self, dt: f32, state: AnimationState, speed: f32) {
+        self.state = state;
+        self.idle_time += dt;
+        self.working_time += dt * speed.max(0.0);
+        let target = match state {
+            AnimationState::Idle => 0.0,
+            AnimationState::Working => 1.0,
+        };
+        let step = dt / Self::BLEND_TIME;
+        self.blend = if self.blend < target {
+            (self.blend + step).min(target)
+        } else {
+            (self.blend - step).max(target)
+        };
+    }
+
+    /// The pose to draw, blending `idle` and `working`.
+    ///
+    /// If only one clip has anything in it, that one is used on its own.
+    #[must_use]
+    pub fn pose(&self, idle: &Clip, working: &Clip) -> Vec<BonePose> {
+        match (idle.is_empty(), working.is_empty()) {
+            (_, true) => idle.sample(self.idle_time),
+            (true, false) => working.sample(self.working_time),
+            (false, false) => (idle.sample(self.idle_time).iter())
+                .zip(&working.sample(self.working_time))
+                .map(|(a, b)| a.lerp(b, self.blend))
+                .collect(),
+        }
+    }
+}
+
+/// Poses `model` as `pose` for the next time it's drawn, if it has that many bones.
+///
+/// The model's meshes are skinned on the CPU, so anything sharing them takes on the pose too;
+/// draw each posed copy before posing the next.
+pub fn apply_pose(model: &Model, pose: &[BonePose]) {
+    let raw = *model.as_ref();
+    if pose.is_empty() || usize::try_from(raw.boneCount).ok() != Some(pose.len()) {
+        return;
+    }
+    let mut transforms = pose.iter().map(BonePose::to_raw).collect::<Vec<_>>();
+    let mut frame = transforms.as_mut_ptr();
+    let animation = ffi::ModelAnimation {
+        boneCount: raw.boneCount,
+        frameCount: 1,
+        bones: raw.bones,
+        framePoses: &raw mut frame,
+        name: [0; 32],
+    };
+    // SAFETY: `animation` has one frame with a transform for each of the model's bones, which
+    // outlive the call
+    unsafe { ffi::UpdateModelAnimation(raw, animation, 0) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: f32) -> Vec<BonePose> {
+        vec![BonePose {
+            translation: Vector3::new(x, 0.0, 0.0),
+            ..BonePose::IDENTITY
+        }]
+    }
+
+    fn x_of(pose: &[BonePose]) -> f32 {
+        pose[0].translation.x
+    }
+
+    #[test]
+    fn test_clip_sample() {
+        let clip = Clip::new(vec![at(0.0), at(2.0)], 2.0);
+        assert!((clip.duration() - 1.0).abs() < f32::EPSILON);
+        assert!((x_of(&clip.sample(0.25)) - 1.0).abs() < 1e-5);
+        assert!(
+            (x_of(&clip.sample(0.75)) - 1.0).abs() < 1e-5,
+            "the last keyframe blends back into the first"
+        );
+        assert!((x_of(&clip.sample(1.25)) - 1.0).abs() < 1e-5, "clips loop");
+        assert!(Clip::default().sample(1.0).is_empty());
+    }
+
+    #[test]
+    fn test_animator_blend() {
+        let idle = Clip::new(vec![at(0.0)], 1.0);
+        let working = Clip::new(vec![at(10.0)], 1.0);
+        let mut animator = Animator::new();
+        assert!(x_of(&animator.pose(&idle, &working)).abs() < f32::EPSILON);
+
+        animator.tick(0.5 * Animator::BLEND_TIME, AnimationState::Working, 1.0);
+        assert!((x_of(&animator.pose(&idle, &working)) - 5.0).abs() < 1e-4);
+        animator.tick(Animator::BLEND_TIME, AnimationState::Working, 1.0);
+        assert!((animator.blend() - 1.0).abs() < f32::EPSILON);
+        animator.tick(2.0 * Animator::BLEND_TIME, AnimationState::Idle, 1.0);
+        assert!(animator.blend().abs() < f32::EPSILON);
+
+        let mut fast = Animator::new();
+        let moving = Clip::new(vec![at(0.0), at(4.0)], 1.0);
+        fast.tick(0.25, AnimationState::Working, 2.0);
+        assert!(
+            (x_of(&fast.pose(&Clip::default(), &moving)) - 2.0).abs() < 1e-4,
+            "working clips play faster the harder it works"
+        );
+    }
+}
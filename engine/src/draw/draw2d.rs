@@ -1,11 +1,35 @@
 //! 2D rendering.
 
-use super::Result;
-use raylib::prelude::*;
+use super::{Error, Result, apply_tint};
+use raylib::prelude::{glam::Affine2, *};
 use std::{marker::PhantomData, ptr::NonNull};
 
+/// Bundles values into [`Arguments`] that render them in order.
+///
+/// Values prefixed with `?` are rendered with [`DebugVis`], like `{:?}` in
+/// [`format_args!`]; the rest are rendered with [`Draw`].
+///
+/// ```ignore
+/// d.draw(render_args!(hud, ?collider))?;
+/// ```
+#[macro_export]
 macro_rules! render_args {
-    () => {};
+    (@args [$($done:expr,)*]) => {
+        $crate::draw2d::Arguments::new(&[$($done,)*])
+    };
+    (@args [$($done:expr,)*] ? $arg:expr $(, $($rest:tt)*)?) => {
+        $crate::render_args!(
+            @args [$($done,)* $crate::draw2d::Argument::new_debug_vis(&$arg),] $($($rest)*)?
+        )
+    };
+    (@args [$($done:expr,)*] $arg:expr $(, $($rest:tt)*)?) => {
+        $crate::render_args!(
+            @args [$($done,)* $crate::draw2d::Argument::new_draw(&$arg),] $($($rest)*)?
+        )
+    };
+    ($($args:tt)*) => {
+        $crate::render_args!(@args [] $($args)*)
+    };
 }
 
 /// This struct represents a generic "argument" which is taken by [`render_args!()`].
@@ -24,7 +48,39 @@ pub struct Argument<'a> {
     _lifetime: PhantomData<&'a ()>,
 }
 
-impl Argument<'_> {
+impl<'a> Argument<'a> {
+    #[inline]
+    const fn new<T>(value: &'a T, renderer: fn(&T, &mut Renderer<'_>) -> Result) -> Self {
+        Self {
+            value: NonNull::from_ref(value).cast(),
+            // SAFETY: `fn(&T, _) -> _` and `unsafe fn(NonNull<()>, _) -> _` have the same
+            // ABI, and `value` upholds the invariant by coming from a `&'a T`.
+            renderer: unsafe {
+                std::mem::transmute::<
+                    fn(&T, &mut Renderer<'_>) -> Result,
+                    unsafe fn(NonNull<()>, &mut Renderer<'_>) -> Result,
+                >(renderer)
+            },
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Used by [`render_args!`] for `?` arguments.
+    #[doc(hidden)]
+    #[inline]
+    #[must_use]
+    pub const fn new_debug_vis<T: DebugVis>(value: &'a T) -> Self {
+        Self::new(value, <T as DebugVis>::draw)
+    }
+
+    /// Used by [`render_args!`] for plain arguments.
+    #[doc(hidden)]
+    #[inline]
+    #[must_use]
+    pub const fn new_draw<T: Draw>(value: &'a T) -> Self {
+        Self::new(value, <T as Draw>::draw)
+    }
+
     /// Format this placeholder argument.
     ///
     /// # Safety
@@ -65,6 +121,16 @@ pub struct Arguments<'a> {
     args: &'a [Argument<'a>],
 }
 
+impl<'a> Arguments<'a> {
+    /// Used by [`render_args!`].
+    #[doc(hidden)]
+    #[inline]
+    #[must_use]
+    pub const fn new(args: &'a [Argument<'a>]) -> Self {
+        Self { args }
+    }
+}
+
 /// Takes an output stream and an `Arguments` struct that can be precompiled with
 /// the `render_args!` macro.
 ///
@@ -85,7 +151,54 @@ pub fn render(output: &mut dyn Render, args: Arguments<'_>) -> Result {
     Ok(())
 }
 
-/// A trait for drawing onto 3D buffers.
+/// A font that [`Render`] backends can draw text with.
+pub type FontHandle = Font;
+
+/// Where each glyph of `text` goes when drawn from `position`, given the size of each one.
+///
+/// Glyphs are spaced `spacing` apart, and each line starts `font_size` below the one before.
+fn layout_glyphs(
+    text: &str,
+    position: Vector2,
+    font_size: f32,
+    spacing: f32,
+    mut size_of: impl FnMut(char) -> Vector2,
+) -> impl Iterator<Item = (char, Rectangle)> {
+    let mut pen = position;
+    text.chars().filter_map(move |ch| {
+        if ch == '\n' {
+            pen = Vector2::new(position.x, pen.y + font_size);
+            return None;
+        }
+        let size = size_of(ch);
+        let cell = Rectangle::new(pen.x, pen.y, size.x, size.y);
+        pen.x += size.x + spacing;
+        Some((ch, cell))
+    })
+}
+
+/// The quad approximating a glyph laid out in `cell`, inset so neighboring glyphs stay
+/// distinguishable.
+const fn glyph_quad(cell: Rectangle) -> Rectangle {
+    Rectangle::new(
+        cell.x + 0.1 * cell.width,
+        cell.y + 0.2 * cell.height,
+        0.8 * cell.width,
+        0.8 * cell.height,
+    )
+}
+
+/// The corners of `rec`, counter-clockwise from the top left.
+const fn corners(rec: Rectangle) -> [Vector2; 4] {
+    [
+        Vector2::new(rec.x, rec.y),
+        Vector2::new(rec.x, rec.y + rec.height),
+        Vector2::new(rec.x + rec.width, rec.y + rec.height),
+        Vector2::new(rec.x + rec.width, rec.y),
+    ]
+}
+
+/// A trait for drawing onto 2D buffers.
 pub trait Render {
     /// Draws a line with optional thickness.
     fn draw_line(
@@ -99,6 +212,36 @@ pub trait Render {
     /// Draws a triangle.
     fn draw_triangle(&mut self, points: &[Vector2; 3], color: Color) -> Result;
 
+    /// Draws text with the top-left corner of its first line at `position`.
+    ///
+    /// The default implementation approximates each glyph with a filled quad, for
+    /// backends that can't rasterize fonts.
+    fn render_text(
+        &mut self,
+        font: &FontHandle,
+        text: &str,
+        position: Vector2,
+        font_size: f32,
+        spacing: f32,
+        color: Color,
+    ) -> Result {
+        let mut buf = [0; 4];
+        let glyphs = layout_glyphs(text, position, font_size, spacing, |ch| {
+            font.measure_text(ch.encode_utf8(&mut buf), font_size, 0.0)
+        });
+        for (ch, cell) in glyphs {
+            if !ch.is_whitespace() {
+                let [top_left, bottom_left, bottom_right, top_right] = corners(glyph_quad(cell));
+                self.draw_triangle(&[top_left, bottom_left, bottom_right], color)?;
+                self.draw_triangle(&[top_left, bottom_right, top_right], color)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Restricts drawing to `clip`, or removes the restriction if `None`.
+    fn set_clip(&mut self, clip: Option<Rectangle>) -> Result;
+
     fn draw(&mut self, args: Arguments<'_>) -> Result;
 }
 
@@ -122,6 +265,40 @@ impl<D: RaylibDraw> Render for D {
         Ok(())
     }
 
+    fn render_text(
+        &mut self,
+        font: &FontHandle,
+        text: &str,
+        position: Vector2,
+        font_size: f32,
+        spacing: f32,
+        color: Color,
+    ) -> Result {
+        self.draw_text_ex(font, text, position, font_size, spacing, color);
+        Ok(())
+    }
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "scissor rectangles are in whole screen pixels"
+    )]
+    fn set_clip(&mut self, clip: Option<Rectangle>) -> Result {
+        match clip {
+            // SAFETY: `self` is a draw handle, so a frame is being drawn.
+            Some(rec) => unsafe {
+                ffi::BeginScissorMode(
+                    rec.x as i32,
+                    rec.y as i32,
+                    rec.width as i32,
+                    rec.height as i32,
+                );
+            },
+            // SAFETY: `self` is a draw handle, so a frame is being drawn.
+            None => unsafe { ffi::EndScissorMode() },
+        }
+        Ok(())
+    }
+
     fn draw(&mut self, args: Arguments<'_>) -> Result {
         render(self, args)
     }
@@ -138,6 +315,8 @@ pub struct RenderingOptions {
     rotation: f32,
     scale: Vector2,
     tint: Color,
+    clip: Option<Rectangle>,
+    layer: i16,
 }
 
 impl Default for RenderingOptions {
@@ -154,6 +333,8 @@ impl RenderingOptions {
     /// - no rotation
     /// - 1x scale
     /// - no tint (white)
+    /// - no clipping
+    /// - layer 0
     #[must_use]
     pub const fn new() -> Self {
         Self {
@@ -161,6 +342,8 @@ impl RenderingOptions {
             rotation: 0.0,
             scale: Vector2::ONE,
             tint: Color::WHITE,
+            clip: None,
+            layer: 0,
         }
     }
 
@@ -194,6 +377,18 @@ impl RenderingOptions {
         self
     }
 
+    /// Restricts drawing to `clip`, given in the same space as the translation.
+    pub const fn clip(&mut self, clip: Rectangle) -> &mut Self {
+        self.clip = Some(clip);
+        self
+    }
+
+    /// Sets the layer. Higher layers are drawn over lower ones.
+    pub const fn layer(&mut self, layer: i16) -> &mut Self {
+        self.layer = layer;
+        self
+    }
+
     /// Returns the currnet translation.
     pub const fn get_translation(&mut self) -> Vector2 {
         self.translation
@@ -213,6 +408,64 @@ impl RenderingOptions {
     pub const fn get_tint(&mut self) -> Color {
         self.tint
     }
+
+    /// Returns the current clipping rectangle, if any.
+    pub const fn get_clip(&mut self) -> Option<Rectangle> {
+        self.clip
+    }
+
+    /// Returns the current layer.
+    pub const fn get_layer(&mut self) -> i16 {
+        self.layer
+    }
+
+    /// Returns the affine transform these options describe: scale, then rotate,
+    /// then translate.
+    #[must_use]
+    pub fn transform(&self) -> Affine2 {
+        Affine2::from_scale_angle_translation(
+            self.scale,
+            self.rotation.to_radians(),
+            self.translation,
+        )
+    }
+}
+
+/// The overlapping part of `a` and `b`, or an empty rectangle if they don't overlap.
+const fn intersect(a: Rectangle, b: Rectangle) -> Rectangle {
+    let x = a.x.max(b.x);
+    let y = a.y.max(b.y);
+    let width = ((a.x + a.width).min(b.x + b.width) - x).max(0.0);
+    let height = ((a.y + a.height).min(b.y + b.height) - y).max(0.0);
+    Rectangle::new(x, y, width, height)
+}
+
+/// A primitive waiting to be drawn by [`Renderer::flush`], already transformed and tinted
+enum Command {
+    Line {
+        start_pos: Vector2,
+        end_pos: Vector2,
+        thick: Option<f32>,
+        color: Color,
+    },
+    Triangle {
+        points: [Vector2; 3],
+        color: Color,
+    },
+    Text {
+        text: String,
+        position: Vector2,
+        font_size: f32,
+        spacing: f32,
+        color: Color,
+    },
+}
+
+/// A [`Command`] with the layer and clip it was queued in
+struct Queued {
+    layer: i16,
+    clip: Option<Rectangle>,
+    command: Command,
 }
 
 /// Configuration for 2D rendering.
@@ -224,9 +477,25 @@ impl RenderingOptions {
 /// To interact with a `Renderer`, you'll call various methods to change the
 /// various options related to rendering. For examples, please see the
 /// documentation of the methods defined on `Renderer` below.
+///
+/// Layers pushed with [`Renderer::push_layer`] nest: each one is transformed,
+/// tinted, and clipped relative to the layer below it. What's drawn is queued
+/// until [`Renderer::flush`] or until the renderer is dropped, then drawn from
+/// the lowest [`RenderingOptions::layer`] to the highest, in the order it was
+/// drawn within each layer.
 #[allow(missing_debug_implementations)]
 pub struct Renderer<'a> {
     options: RenderingOptions,
+    /// Maps the current layer's space to the buffer's
+    transform: Affine2,
+    font: Option<&'a FontHandle>,
+    /// Options and transforms to restore on [`Renderer::pop_layer`]
+    layers: Vec<(RenderingOptions, Affine2)>,
+    /// The clip last given to `buf` by this renderer
+    applied_clip: Option<Rectangle>,
+    /// The clip `buf` had before this renderer, restored when it's dropped
+    restore_clip: Option<Rectangle>,
+    queued: Vec<Queued>,
 
     buf: &'a mut (dyn Render + 'a),
 }
@@ -235,17 +504,287 @@ impl<'a> Renderer<'a> {
     pub fn new(render: &'a mut (dyn Render + 'a), options: RenderingOptions) -> Self {
         Self {
             options,
+            transform: options.transform(),
+            font: None,
+            layers: Vec::new(),
+            applied_clip: None,
+            restore_clip: None,
+            queued: Vec::new(),
             buf: render,
         }
     }
 
+    /// Sets the font used by [`Self::draw_text`].
+    #[must_use]
+    pub const fn with_font(mut self, font: &'a FontHandle) -> Self {
+        self.font = Some(font);
+        self
+    }
+
     /// Creates a new formatter based on this one with given [`RenderingOptions`].
+    ///
+    /// The new renderer sorts its own layers and draws them when it's dropped, so
+    /// under anything still queued on this one.
     pub fn with_options<'b>(&'b mut self, options: RenderingOptions) -> Renderer<'b> {
         Renderer {
             options,
+            transform: options.transform(),
+            font: self.font,
+            layers: Vec::new(),
+            applied_clip: self.applied_clip,
+            restore_clip: self.applied_clip,
+            queued: Vec::new(),
             buf: self.buf,
         }
     }
+
+    /// Returns the options of the current layer.
+    ///
+    /// The tint and clip include those of the layers below, but the translation,
+    /// rotation, and scale are only this layer's; see [`Self::transform`].
+    #[must_use]
+    pub const fn options(&self) -> &RenderingOptions {
+        &self.options
+    }
+
+    /// Returns the font used by [`Self::draw_text`], if one was set with [`Self::with_font`].
+    #[must_use]
+    pub const fn font(&self) -> Option<&'a FontHandle> {
+        self.font
+    }
+
+    /// Draws everything until the matching [`Self::pop_layer`] with `options`
+    /// applied on top of the current ones.
+    ///
+    /// Transforms compose, tints multiply, and clipping rectangles intersect, with
+    /// the new clip in the current layer's space. The layer key is added to the
+    /// current one, so a nested layer stays above or below the same things as its
+    /// parent unless it has a key of its own.
+    pub fn push_layer(&mut self, options: &RenderingOptions) {
+        let mut layer = *options;
+        layer.tint = self.tint(options.tint);
+        layer.layer = self.options.layer.saturating_add(options.layer);
+        layer.clip = match (self.options.clip, options.clip) {
+            (parent, None) => parent,
+            (None, Some(clip)) => Some(self.transform_rec(clip)),
+            (Some(parent), Some(clip)) => Some(intersect(parent, self.transform_rec(clip))),
+        };
+        let transform = self.transform * options.transform();
+        self.layers.push((
+            std::mem::replace(&mut self.options, layer),
+            std::mem::replace(&mut self.transform, transform),
+        ));
+    }
+
+    /// Restores the options from before the last [`Self::push_layer`].
+    ///
+    /// # Errors
+    ///
+    /// If there is no layer to pop.
+    pub fn pop_layer(&mut self) -> Result {
+        (self.options, self.transform) = self.layers.pop().ok_or(Error)?;
+        Ok(())
+    }
+
+    /// Returns the transform from the current layer's space to the buffer's.
+    #[must_use]
+    pub const fn transform(&self) -> Affine2 {
+        self.transform
+    }
+
+    /// Draws everything queued so far, lowest layer first, then empties the queue.
+    ///
+    /// # Errors
+    ///
+    /// If the underlying [`Render`] fails, or if text was queued without a font.
+    pub fn flush(&mut self) -> Result {
+        let mut queued = std::mem::take(&mut self.queued);
+        // Stable, so each layer keeps the order it was drawn in
+        queued.sort_by_key(|queued| queued.layer);
+        for Queued { clip, command, .. } in queued.drain(..) {
+            if self.applied_clip != clip {
+                self.buf.set_clip(clip)?;
+                self.applied_clip = clip;
+            }
+            match command {
+                Command::Line {
+                    start_pos,
+                    end_pos,
+                    thick,
+                    color,
+                } => self.buf.draw_line(start_pos, end_pos, thick, color)?,
+                Command::Triangle { points, color } => self.buf.draw_triangle(&points, color)?,
+                Command::Text {
+                    text,
+                    position,
+                    font_size,
+                    spacing,
+                    color,
+                } => {
+                    let font = self.font.ok_or(Error)?;
+                    self.buf
+                        .render_text(font, &text, position, font_size, spacing, color)?;
+                }
+            }
+        }
+        // Keep the allocation for the next frame's worth
+        self.queued = queued;
+        Ok(())
+    }
+
+    /// Queues `command` to be drawn in the current layer and clip.
+    fn queue(&mut self, command: Command) {
+        self.queued.push(Queued {
+            layer: self.options.layer,
+            clip: self.options.clip,
+            command,
+        });
+    }
+
+    /// The axis-aligned bounds of `rec` after the current transform.
+    ///
+    /// Exact unless the transform rotates by something other than a multiple of 90 degrees.
+    fn transform_rec(&self, rec: Rectangle) -> Rectangle {
+        let corners = corners(rec).map(|corner| self.transform_point(corner));
+        let min = corners.into_iter().reduce(Vector2::min).unwrap_or_default();
+        let max = corners.into_iter().reduce(Vector2::max).unwrap_or_default();
+        Rectangle::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    /// Applies the current transform to `point`.
+    fn transform_point(&self, point: Vector2) -> Vector2 {
+        self.transform.transform_point2(point)
+    }
+
+    /// How much the current transform scales lengths, averaged over both axes.
+    fn length_scale(&self) -> f32 {
+        self.transform.matrix2.determinant().abs().sqrt()
+    }
+
+    /// Multiplies `color` by the current tint.
+    const fn tint(&self, color: Color) -> Color {
+        apply_tint(color, self.options.tint)
+    }
+
+    /// Draws a line with optional thickness, transformed and tinted by the current options.
+    ///
+    /// The thickness is scaled along with the line, but hairlines stay hairlines.
+    ///
+    /// # Errors
+    ///
+    /// Never; the line is queued until [`Self::flush`].
+    pub fn draw_line(
+        &mut self,
+        start_pos: Vector2,
+        end_pos: Vector2,
+        thick: Option<f32>,
+        color: Color,
+    ) -> Result {
+        let (start_pos, end_pos) = (
+            self.transform_point(start_pos),
+            self.transform_point(end_pos),
+        );
+        let thick = thick.map(|thick| thick * self.length_scale());
+        let color = self.tint(color);
+        self.queue(Command::Line {
+            start_pos,
+            end_pos,
+            thick,
+            color,
+        });
+        Ok(())
+    }
+
+    /// Draws a triangle, transformed and tinted by the current options.
+    ///
+    /// Points should be in counter-clockwise order. A mirroring transform would
+    /// flip them to clockwise, so they're reordered to stay counter-clockwise.
+    ///
+    /// # Errors
+    ///
+    /// Never; the triangle is queued until [`Self::flush`].
+    pub fn draw_triangle(&mut self, points: &[Vector2; 3], color: Color) -> Result {
+        let [a, b, c] = points.map(|p| self.transform_point(p));
+        let points = if self.transform.matrix2.determinant() < 0.0 {
+            [a, c, b]
+        } else {
+            [a, b, c]
+        };
+        let color = self.tint(color);
+        self.queue(Command::Triangle { points, color });
+        Ok(())
+    }
+
+    /// Draws a filled rectangle as two triangles, transformed and tinted by the current options.
+    ///
+    /// # Errors
+    ///
+    /// Never; the rectangle is queued until [`Self::flush`].
+    pub fn draw_rectangle(&mut self, rec: Rectangle, color: Color) -> Result {
+        let [top_left, bottom_left, bottom_right, top_right] = corners(rec);
+        self.draw_triangle(&[top_left, bottom_left, bottom_right], color)?;
+        self.draw_triangle(&[top_left, bottom_right, top_right], color)
+    }
+
+    /// Draws the outline of a rectangle, transformed and tinted by the current options.
+    ///
+    /// # Errors
+    ///
+    /// Never; the outline is queued until [`Self::flush`].
+    pub fn draw_rectangle_lines(
+        &mut self,
+        rec: Rectangle,
+        thick: Option<f32>,
+        color: Color,
+    ) -> Result {
+        let corners = corners(rec);
+        for (i, &start_pos) in corners.iter().enumerate() {
+            self.draw_line(start_pos, corners[(i + 1) % 4], thick, color)?;
+        }
+        Ok(())
+    }
+
+    /// Draws text in this renderer's font, transformed and tinted by the current options.
+    ///
+    /// `position` is the top-left corner of the first line. Backends draw glyphs
+    /// upright, so the transform moves and scales text but doesn't rotate it.
+    ///
+    /// # Errors
+    ///
+    /// If no font was set with [`Self::with_font`]. Otherwise the text is queued until
+    /// [`Self::flush`].
+    pub fn draw_text(
+        &mut self,
+        text: &str,
+        position: Vector2,
+        font_size: f32,
+        spacing: f32,
+        color: Color,
+    ) -> Result {
+        self.font.ok_or(Error)?;
+        let position = self.transform_point(position);
+        let scale = self.length_scale();
+        let color = self.tint(color);
+        self.queue(Command::Text {
+            text: text.to_owned(),
+            position,
+            font_size: font_size * scale,
+            spacing: spacing * scale,
+            color,
+        });
+        Ok(())
+    }
+}
+
+impl Drop for Renderer<'_> {
+    fn drop(&mut self) {
+        // Nothing to report the error to; what's left is just not drawn this frame
+        _ = self.flush();
+        if self.applied_clip != self.restore_clip {
+            // Nothing to report the error to; the next draw with a clip resets it anyway
+            _ = self.buf.set_clip(self.restore_clip);
+        }
+    }
 }
 
 /// `DebugVis` should render the output in a programmer-facing, debugging context.
@@ -261,3 +800,204 @@ pub trait Draw {
     #[doc = include_str!("draw_trait_method_doc.md")]
     fn draw(&self, d: &mut Renderer<'_>) -> Result;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records primitives instead of drawing them
+    #[derive(Default)]
+    struct Recorder {
+        lines: Vec<(Vector2, Vector2, Option<f32>)>,
+        triangles: Vec<[Vector2; 3]>,
+        clips: Vec<Option<Rectangle>>,
+    }
+
+    impl Render for Recorder {
+        fn draw_line(
+            &mut self,
+            start_pos: Vector2,
+            end_pos: Vector2,
+            thick: Option<f32>,
+            _color: Color,
+        ) -> Result {
+            self.lines.push((start_pos, end_pos, thick));
+            Ok(())
+        }
+
+        fn draw_triangle(&mut self, points: &[Vector2; 3], _color: Color) -> Result {
+            self.triangles.push(*points);
+            Ok(())
+        }
+
+        fn set_clip(&mut self, clip: Option<Rectangle>) -> Result {
+            self.clips.push(clip);
+            Ok(())
+        }
+
+        fn draw(&mut self, args: Arguments<'_>) -> Result {
+            render(self, args)
+        }
+    }
+
+    fn assert_near(actual: Vector2, expected: Vector2) {
+        assert!(
+            actual.abs_diff_eq(expected, 1e-4),
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut recorder = Recorder::default();
+        let mut options = RenderingOptions::new();
+        options
+            .translation(Vector2::new(10.0, 20.0))
+            .rotation(90.0)
+            .scale(2.0);
+        let mut d = Renderer::new(&mut recorder, options);
+        d.draw_line(Vector2::ZERO, Vector2::X, Some(1.5), Color::WHITE)
+            .unwrap();
+        drop(d);
+
+        let (start, end, thick) = recorder.lines[0];
+        assert_near(start, Vector2::new(10.0, 20.0));
+        assert_near(end, Vector2::new(10.0, 22.0));
+        assert_eq!(thick, Some(3.0));
+    }
+
+    #[test]
+    fn test_mirror_keeps_winding() {
+        let mut recorder = Recorder::default();
+        let mut options = RenderingOptions::new();
+        options.scale_v(Vector2::new(-1.0, 1.0));
+        let mut d = Renderer::new(&mut recorder, options);
+        d.draw_triangle(&[Vector2::ZERO, Vector2::Y, Vector2::ONE], Color::WHITE)
+            .unwrap();
+        drop(d);
+
+        let [a, b, c] = recorder.triangles[0];
+        assert_near(a, Vector2::ZERO);
+        assert_near(b, Vector2::new(-1.0, 1.0));
+        assert_near(c, Vector2::Y);
+        // Y points down, so counter-clockwise on screen is a negative cross product
+        assert!((b - a).perp_dot(c - a) < 0.0, "winding flipped");
+    }
+
+    #[test]
+    fn test_layers() {
+        let mut recorder = Recorder::default();
+        let mut d = Renderer::new(&mut recorder, RenderingOptions::new());
+        d.push_layer(
+            RenderingOptions::new()
+                .translation(Vector2::new(5.0, 5.0))
+                .scale(2.0)
+                .clip(Rectangle::new(0.0, 0.0, 10.0, 10.0)),
+        );
+        d.push_layer(RenderingOptions::new().clip(Rectangle::new(2.0, 2.0, 10.0, 10.0)));
+        d.draw_line(Vector2::ZERO, Vector2::ONE, None, Color::WHITE)
+            .unwrap();
+        d.pop_layer().unwrap();
+        d.pop_layer().unwrap();
+        assert!(d.pop_layer().is_err());
+        d.draw_line(Vector2::ZERO, Vector2::ONE, None, Color::WHITE)
+            .unwrap();
+        drop(d);
+
+        assert_near(recorder.lines[0].0, Vector2::new(5.0, 5.0));
+        assert_near(recorder.lines[0].1, Vector2::new(7.0, 7.0));
+        assert_near(recorder.lines[1].1, Vector2::ONE);
+        assert_eq!(
+            recorder.clips,
+            [Some(Rectangle::new(9.0, 9.0, 1.0, 1.0)), None]
+        );
+    }
+
+    #[test]
+    fn test_layer_order() {
+        let mut recorder = Recorder::default();
+        let mut d = Renderer::new(&mut recorder, RenderingOptions::new());
+        let line = |d: &mut Renderer<'_>, x: f32| {
+            d.draw_line(Vector2::new(x, 0.0), Vector2::ONE, None, Color::WHITE)
+                .unwrap();
+        };
+        line(&mut d, 0.0);
+        d.push_layer(
+            RenderingOptions::new()
+                .layer(2)
+                .clip(Rectangle::new(0.0, 0.0, 4.0, 4.0)),
+        );
+        line(&mut d, 1.0);
+        d.push_layer(RenderingOptions::new().layer(-3));
+        line(&mut d, 2.0);
+        d.pop_layer().unwrap();
+        line(&mut d, 3.0);
+        d.pop_layer().unwrap();
+        line(&mut d, 4.0);
+        drop(d);
+
+        let order = recorder
+            .lines
+            .iter()
+            .map(|line| line.0.x)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            [2.0, 0.0, 4.0, 1.0, 3.0],
+            "nested layers are relative to their parent, and ties keep draw order"
+        );
+        let clip = Some(Rectangle::new(0.0, 0.0, 4.0, 4.0));
+        assert_eq!(recorder.clips, [clip, None, clip, None]);
+    }
+
+    #[test]
+    fn test_clip_math() {
+        let a = Rectangle::new(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(
+            intersect(a, Rectangle::new(5.0, -5.0, 10.0, 10.0)),
+            Rectangle::new(5.0, 0.0, 5.0, 5.0)
+        );
+        assert_eq!(
+            intersect(a, Rectangle::new(2.0, 3.0, 1.0, 1.0)),
+            Rectangle::new(2.0, 3.0, 1.0, 1.0)
+        );
+        let apart = intersect(a, Rectangle::new(20.0, 20.0, 5.0, 5.0));
+        assert!(
+            apart.width == 0.0 && apart.height == 0.0,
+            "no overlap is empty"
+        );
+
+        let mut recorder = Recorder::default();
+        let mut options = RenderingOptions::new();
+        options
+            .translation(Vector2::new(10.0, 0.0))
+            .rotation(90.0)
+            .scale(2.0);
+        let d = Renderer::new(&mut recorder, options);
+        let rec = d.transform_rec(Rectangle::new(0.0, 0.0, 2.0, 1.0));
+        assert_near(Vector2::new(rec.x, rec.y), Vector2::new(8.0, 0.0));
+        assert_near(Vector2::new(rec.width, rec.height), Vector2::new(2.0, 4.0));
+    }
+
+    #[test]
+    fn test_text_layout() {
+        let glyphs = layout_glyphs("ab c\nd", Vector2::new(5.0, 5.0), 20.0, 2.0, |ch| {
+            Vector2::new(if ch == ' ' { 4.0 } else { 10.0 }, 20.0)
+        })
+        .collect::<Vec<_>>();
+        assert_eq!(
+            glyphs,
+            [
+                ('a', Rectangle::new(5.0, 5.0, 10.0, 20.0)),
+                ('b', Rectangle::new(17.0, 5.0, 10.0, 20.0)),
+                (' ', Rectangle::new(29.0, 5.0, 4.0, 20.0)),
+                ('c', Rectangle::new(35.0, 5.0, 10.0, 20.0)),
+                ('d', Rectangle::new(5.0, 25.0, 10.0, 20.0)),
+            ]
+        );
+        assert_eq!(
+            glyph_quad(Rectangle::new(0.0, 0.0, 10.0, 20.0)),
+            Rectangle::new(1.0, 4.0, 8.0, 16.0)
+        );
+    }
+}
@@ -1,7 +1,125 @@
 //! 3D rendering.
 
-use super::Result;
+use super::{Result, apply_tint};
 use raylib::prelude::*;
+use std::{marker::PhantomData, ptr::NonNull};
+
+/// Bundles values into 3D [`Arguments`] that render them in order.
+///
+/// The 3D counterpart of [`render_args!`](crate::render_args). Values prefixed
+/// with `?` are rendered with [`DebugVis`]; the rest are rendered with [`Draw`].
+///
+/// ```ignore
+/// d.draw(render_args3d!(?machine, ?belt))?;
+/// ```
+#[macro_export]
+macro_rules! render_args3d {
+    (@args [$($done:expr,)*]) => {
+        $crate::draw3d::Arguments::new(&[$($done,)*])
+    };
+    (@args [$($done:expr,)*] ? $arg:expr $(, $($rest:tt)*)?) => {
+        $crate::render_args3d!(
+            @args [$($done,)* $crate::draw3d::Argument::new_debug_vis(&$arg),] $($($rest)*)?
+        )
+    };
+    (@args [$($done:expr,)*] $arg:expr $(, $($rest:tt)*)?) => {
+        $crate::render_args3d!(
+            @args [$($done,)* $crate::draw3d::Argument::new_draw(&$arg),] $($($rest)*)?
+        )
+    };
+    ($($args:tt)*) => {
+        $crate::render_args3d!(@args [] $($args)*)
+    };
+}
+
+/// This struct represents a generic "argument" which is taken by [`render_args3d!()`].
+///
+/// See [`crate::draw2d::Argument`]; this is the same thing for 3D [`Renderer`]s.
+#[derive(Copy, Clone)]
+pub struct Argument<'a> {
+    // INVARIANT: `renderer` has type `fn(&T, _) -> _` for some `T`, and `value`
+    // was derived from a `&'a T`.
+    value: NonNull<()>,
+    renderer: unsafe fn(NonNull<()>, &mut Renderer<'_>) -> Result,
+    _lifetime: PhantomData<&'a ()>,
+}
+
+impl<'a> Argument<'a> {
+    #[inline]
+    const fn new<T>(value: &'a T, renderer: fn(&T, &mut Renderer<'_>) -> Result) -> Self {
+        Self {
+            value: NonNull::from_ref(value).cast(),
+            // SAFETY: `fn(&T, _) -> _` and `unsafe fn(NonNull<()>, _) -> _` have the same
+            // ABI, and `value` upholds the invariant by coming from a `&'a T`.
+            renderer: unsafe {
+                std::mem::transmute::<
+                    fn(&T, &mut Renderer<'_>) -> Result,
+                    unsafe fn(NonNull<()>, &mut Renderer<'_>) -> Result,
+                >(renderer)
+            },
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Used by [`render_args3d!`] for `?` arguments.
+    #[doc(hidden)]
+    #[inline]
+    #[must_use]
+    pub const fn new_debug_vis<T: DebugVis>(value: &'a T) -> Self {
+        Self::new(value, <T as DebugVis>::draw)
+    }
+
+    /// Used by [`render_args3d!`] for plain arguments.
+    #[doc(hidden)]
+    #[inline]
+    #[must_use]
+    pub const fn new_draw<T: Draw>(value: &'a T) -> Self {
+        Self::new(value, <T as Draw>::draw)
+    }
+
+    /// Render this placeholder argument.
+    #[inline]
+    fn draw(&self, d: &mut Renderer<'_>) -> Result {
+        let Self {
+            renderer, value, ..
+        } = *self;
+        // SAFETY: By the invariant, `value` came from a `&'a T` that is still borrowed
+        // and `renderer` is a `fn(&T, _) -> _`, so this is the original call.
+        unsafe { renderer(value, d) }
+    }
+}
+
+/// A precompiled 3D render group, created with [`render_args3d!`].
+///
+/// See [`crate::draw2d::Arguments`].
+#[derive(Copy, Clone)]
+pub struct Arguments<'a> {
+    // Dynamic arguments for rendering
+    args: &'a [Argument<'a>],
+}
+
+impl<'a> Arguments<'a> {
+    /// Used by [`render_args3d!`].
+    #[doc(hidden)]
+    #[inline]
+    #[must_use]
+    pub const fn new(args: &'a [Argument<'a>]) -> Self {
+        Self { args }
+    }
+}
+
+/// Renders `args` into `output` with default [`RenderingOptions`].
+///
+/// # Errors
+///
+/// If rendering any of the arguments fails.
+pub fn render(output: &mut dyn Render, args: Arguments<'_>) -> Result {
+    let mut renderer = Renderer::new(output, RenderingOptions::new());
+    for arg in args.args {
+        arg.draw(&mut renderer)?;
+    }
+    Ok(())
+}
 
 /// A trait for drawing onto 3D buffers.
 pub trait Render {
@@ -13,6 +131,16 @@ pub trait Render {
 
     /// Draw a 3D mesh with material and transform.
     fn draw_mesh(&mut self, mesh: &Mesh, material: &Material, transform: &Matrix);
+
+    /// Draws a 3D mesh as a translucent ghost of one color, see [`ghost_pass`].
+    fn draw_ghost(&mut self, mesh: &Mesh, transform: &Matrix, color: Color);
+
+    /// Renders a precompiled render group.
+    ///
+    /// # Errors
+    ///
+    /// If rendering any of the arguments fails.
+    fn draw(&mut self, args: Arguments<'_>) -> Result;
 }
 
 impl<D: RaylibDraw3D> Render for D {
@@ -39,14 +167,138 @@ impl<D: RaylibDraw3D> Render for D {
         let material = unsafe { WeakMaterial::from_raw(*material.as_ref()) };
         self.draw_mesh(mesh, material, transform);
     }
+
+    fn draw_ghost(&mut self, mesh: &Mesh, transform: &Matrix, color: Color) {
+        ghost_pass(transform, color, |material, transform| {
+            RaylibDraw3D::draw_mesh(self, mesh, material, transform);
+        });
+    }
+
+    fn draw(&mut self, args: Arguments<'_>) -> Result {
+        render(self, args)
+    }
+}
+
+/// Whether a ghost shows something that could go where it's drawn.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GhostValidity {
+    /// Nothing is in the way.
+    Valid,
+    /// Something already there is in the way.
+    Colliding,
+}
+
+impl GhostValidity {
+    /// The translucent color ghosts are drawn in: blue if valid, red if colliding.
+    #[must_use]
+    pub const fn color(self) -> Color {
+        match self {
+            Self::Valid => Color::new(80, 160, 255, 110),
+            Self::Colliding => Color::new(255, 60, 60, 110),
+        }
+    }
+}
+
+/// How much bigger than their mesh ghosts are drawn.
+///
+/// Stands in for a depth bias: faces that line up with ones already drawn end up just in front
+/// of them, instead of z-fighting with them.
+pub const GHOST_DEPTH_BIAS: f32 = 0.01;
+
+/// Draws a ghost as its own render pass, for [`Render::draw_ghost`] implementations.
+///
+/// `draw_mesh` is called with a flat material of `color` and `transform` scaled up by
+/// [`GHOST_DEPTH_BIAS`]. Everything drawn before is flushed first, so the ghost blends over it.
+/// Depth is still tested, so walls hide ghosts behind them, but not written, so ghosts never
+/// hide each other or anything drawn after them.
+pub fn ghost_pass(transform: &Matrix, color: Color, draw_mesh: impl FnOnce(WeakMaterial, Matrix)) {
+    // SAFETY: Drawing at all means a window is open, which is all loading the default needs
+    let raw = unsafe { ffi::LoadMaterialDefault() };
+    // SAFETY: Unloaded below, after the last use of this copy
+    let mut material = unsafe { WeakMaterial::from_raw(raw) };
+    *material.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize].color_mut() = color;
+
+    let scale = 1.0 + GHOST_DEPTH_BIAS;
+    // SAFETY: Only flushes what's already been drawn
+    unsafe { ffi::rlDrawRenderBatchActive() };
+    // SAFETY: Turned back on below
+    unsafe { ffi::rlDisableDepthMask() };
+    draw_mesh(material, Matrix::scale(scale, scale, scale) * *transform);
+    // SAFETY: The ghost has to be drawn before depth writes come back on
+    unsafe { ffi::rlDrawRenderBatchActive() };
+    // SAFETY: Depth writes are on by default
+    unsafe { ffi::rlEnableDepthMask() };
+
+    // SAFETY: Loaded above, and nothing drawn with it is waiting to be drawn
+    unsafe { ffi::UnloadMaterial(raw) };
 }
 
+/// Options for 3D rendering.
+///
+/// Points are rotated about the origin, then offset.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct RenderingOptions {
     offset: Vector3,
     rotation: Quaternion,
     tint: Color,
 }
 
+impl Default for RenderingOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderingOptions {
+    /// Construct a new `RenderingOptions` with the following specifier:
+    ///
+    /// - no offset
+    /// - no rotation
+    /// - no tint (white)
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            offset: Vector3::ZERO,
+            rotation: Quaternion::IDENTITY,
+            tint: Color::WHITE,
+        }
+    }
+
+    /// Sets the offset.
+    pub const fn offset(&mut self, offset: Vector3) -> &mut Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the rotation.
+    pub const fn rotation(&mut self, rotation: Quaternion) -> &mut Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets the tint.
+    pub const fn tint(&mut self, tint: Color) -> &mut Self {
+        self.tint = tint;
+        self
+    }
+
+    /// Returns the current offset.
+    pub const fn get_offset(&mut self) -> Vector3 {
+        self.offset
+    }
+
+    /// Returns the current rotation.
+    pub const fn get_rotation(&mut self) -> Quaternion {
+        self.rotation
+    }
+
+    /// Returns the current tint.
+    pub const fn get_tint(&mut self) -> Color {
+        self.tint
+    }
+}
+
 /// Configuration for 3D rendering.
 ///
 /// A `Renderer` represents various options related to rendering. Users do not
@@ -65,6 +317,146 @@ pub struct Renderer<'a> {
 
 impl RaylibDraw for Renderer<'_> {}
 
+impl<'a> Renderer<'a> {
+    /// Creates a renderer that draws into `render` with `options`.
+    pub fn new(render: &'a mut (dyn Render + 'a), options: RenderingOptions) -> Self {
+        Self {
+            options,
+            buf: render,
+        }
+    }
+
+    /// Creates a new renderer based on this one with `options` applied on top of
+    /// the current ones.
+    ///
+    /// Rotations and offsets compose so that `options` is relative to this renderer,
+    /// and tints multiply.
+    pub fn with_options<'b>(&'b mut self, options: &RenderingOptions) -> Renderer<'b> {
+        Renderer {
+            options: RenderingOptions {
+                offset: self.transform(options.offset),
+                rotation: self.options.rotation * options.rotation,
+                tint: self.tint(options.tint),
+            },
+            buf: self.buf,
+        }
+    }
+
+    /// Returns the options this renderer applies to everything drawn with it.
+    #[must_use]
+    pub const fn options(&self) -> &RenderingOptions {
+        &self.options
+    }
+
+    /// Applies the current rotation and offset to `point`.
+    fn transform(&self, point: Vector3) -> Vector3 {
+        self.options.rotation * point + self.options.offset
+    }
+
+    /// Multiplies `color` by the current tint.
+    const fn tint(&self, color: Color) -> Color {
+        apply_tint(color, self.options.tint)
+    }
+
+    /// Draws a line with optional thickness, transformed and tinted by the current options.
+    ///
+    /// # Errors
+    ///
+    /// Never, but kept fallible to match [`crate::draw2d::Renderer`].
+    pub fn draw_line(
+        &mut self,
+        start_pos: Vector3,
+        end_pos: Vector3,
+        thick: Option<f32>,
+        color: Color,
+    ) -> Result {
+        let (start_pos, end_pos) = (self.transform(start_pos), self.transform(end_pos));
+        let color = self.tint(color);
+        self.buf.draw_line(start_pos, end_pos, thick, color);
+        Ok(())
+    }
+
+    /// Draws connected line segments through `points`, e.g. a belt's path.
+    ///
+    /// # Errors
+    ///
+    /// Never, but kept fallible to match [`crate::draw2d::Renderer`].
+    pub fn draw_line_strip(
+        &mut self,
+        points: &[Vector3],
+        thick: Option<f32>,
+        color: Color,
+    ) -> Result {
+        for segment in points.windows(2) {
+            self.draw_line(segment[0], segment[1], thick, color)?;
+        }
+        Ok(())
+    }
+
+    /// Draws a triangle, transformed and tinted by the current options.
+    ///
+    /// Points should be in counter-clockwise order.
+    ///
+    /// # Errors
+    ///
+    /// Never, but kept fallible to match [`crate::draw2d::Renderer`].
+    pub fn draw_triangle(&mut self, points: &[Vector3; 3], color: Color) -> Result {
+        let points = points.map(|p| self.transform(p));
+        let color = self.tint(color);
+        self.buf.draw_triangle(&points, color);
+        Ok(())
+    }
+
+    /// Draws `mesh` at `transform` as a translucent ghost colored by `validity`, e.g. to preview
+    /// where something would be built.
+    ///
+    /// Transformed by the current options, but not tinted, so it's always clear whether it's
+    /// valid.
+    ///
+    /// # Errors
+    ///
+    /// Never, but kept fallible to match [`crate::draw2d::Renderer`].
+    pub fn draw_ghost(
+        &mut self,
+        mesh: &Mesh,
+        transform: &Matrix,
+        validity: GhostValidity,
+    ) -> Result {
+        let (axis, angle) = self.options.rotation.to_axis_angle();
+        let Vector3 { x, y, z } = self.options.offset;
+        let transform = *transform * Matrix::rotate(axis, angle) * Matrix::translate(x, y, z);
+        self.buf.draw_ghost(mesh, &transform, validity.color());
+        Ok(())
+    }
+
+    /// Draws the edges of `bbox`, transformed and tinted by the current options.
+    ///
+    /// Under a rotation the box is drawn rotated, not re-fit to the axes.
+    ///
+    /// # Errors
+    ///
+    /// Never, but kept fallible to match [`crate::draw2d::Renderer`].
+    pub fn draw_bounding_box(&mut self, bbox: BoundingBox, color: Color) -> Result {
+        let BoundingBox { min, max } = bbox;
+        let corner = |i: usize| {
+            Vector3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        };
+        // Each corner connects to the corners that differ from it along one axis
+        for i in 0..8 {
+            for axis in [1, 2, 4] {
+                if i & axis == 0 {
+                    self.draw_line(corner(i), corner(i | axis), None, color)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 /// `DebugVis` should render the output in a programmer-facing, debugging context.
 pub trait DebugVis {
     #[doc = include_str!("draw_trait_method_doc.md")]
@@ -78,3 +470,106 @@ pub trait Draw {
     #[doc = include_str!("draw_trait_method_doc.md")]
     fn draw(&self, d: &mut Renderer<'_>) -> Result;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records lines and ghosts instead of drawing them
+    #[derive(Default)]
+    struct Recorder {
+        lines: Vec<(Vector3, Vector3)>,
+        ghosts: Vec<Color>,
+    }
+
+    impl Render for Recorder {
+        fn draw_line(&mut self, start_pos: Vector3, end_pos: Vector3, _: Option<f32>, _: Color) {
+            self.lines.push((start_pos, end_pos));
+        }
+
+        fn draw_triangle(&mut self, _: &[Vector3; 3], _: Color) {}
+
+        fn draw_mesh(&mut self, _: &Mesh, _: &Material, _: &Matrix) {}
+
+        fn draw_ghost(&mut self, _: &Mesh, _: &Matrix, color: Color) {
+            self.ghosts.push(color);
+        }
+
+        fn draw(&mut self, args: Arguments<'_>) -> Result {
+            render(self, args)
+        }
+    }
+
+    struct Marker(Vector3);
+
+    impl DebugVis for Marker {
+        fn draw(&self, d: &mut Renderer<'_>) -> Result {
+            d.draw_line(self.0, self.0 + Vector3::Y, None, Color::RED)
+        }
+    }
+
+    impl Draw for Marker {
+        fn draw(&self, d: &mut Renderer<'_>) -> Result {
+            let mut d = d.with_options(RenderingOptions::new().offset(Vector3::X));
+            d.draw_line(self.0, self.0 + Vector3::Y, None, Color::RED)
+        }
+    }
+
+    #[test]
+    fn test_render_args() {
+        let mut recorder = Recorder::default();
+        let (a, b) = (Marker(Vector3::ZERO), Marker(Vector3::Z));
+        recorder.draw(crate::render_args3d!(?a, b)).unwrap();
+        assert_eq!(
+            recorder.lines,
+            [
+                (Vector3::ZERO, Vector3::Y),
+                (Vector3::new(1.0, 0.0, 1.0), Vector3::new(1.0, 1.0, 1.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let mut recorder = Recorder::default();
+        let mut options = RenderingOptions::new();
+        options
+            .offset(Vector3::splat(10.0))
+            .rotation(Quaternion::from_rotation_y(std::f32::consts::FRAC_PI_2));
+        Renderer::new(&mut recorder, options)
+            .draw_bounding_box(
+                BoundingBox {
+                    min: Vector3::ZERO,
+                    max: Vector3::ONE,
+                },
+                Color::WHITE,
+            )
+            .unwrap();
+
+        assert_eq!(recorder.lines.len(), 12);
+        let x_edge = recorder.lines[0];
+        assert!(x_edge.0.abs_diff_eq(Vector3::splat(10.0), 1e-5));
+        assert!(x_edge.1.abs_diff_eq(Vector3::new(10.0, 10.0, 9.0), 1e-5));
+    }
+
+    #[test]
+    fn test_ghost_colors() {
+        let (valid, colliding) = (
+            GhostValidity::Valid.color(),
+            GhostValidity::Colliding.color(),
+        );
+        assert!(valid.b > valid.r, "valid ghosts are blue");
+        assert!(colliding.r > colliding.b, "colliding ghosts are red");
+        assert!(valid.a < u8::MAX && colliding.a < u8::MAX);
+
+        let mut recorder = Recorder::default();
+        // SAFETY: Only passed along to the recorder, never drawn
+        let raw = unsafe { std::mem::zeroed::<ffi::Mesh>() };
+        // SAFETY: Never dropped, so never unloaded
+        let mesh = std::mem::ManuallyDrop::new(unsafe { Mesh::from_raw(raw) });
+        Renderer::new(&mut recorder, *RenderingOptions::new().tint(Color::BLACK))
+            .draw_ghost(&mesh, &Matrix::identity(), GhostValidity::Colliding)
+            .unwrap();
+        assert_eq!(recorder.ghosts, [colliding], "ghosts aren't tinted");
+    }
+}
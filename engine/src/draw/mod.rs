@@ -1,5 +1,7 @@
 //! Drawing inspired by [`std::fmt`].
 
+use raylib::prelude::Color;
+
 pub mod draw2d;
 pub mod draw3d;
 
@@ -17,3 +19,20 @@ impl std::error::Error for Error {}
 
 /// The type returned by renderer methods.
 pub type Result = std::result::Result<(), Error>;
+
+/// Multiplies each channel of `color` by the same channel of `tint`.
+#[allow(
+    clippy::cast_possible_truncation,
+    reason = "product of two u8s divided by 255 fits in a u8"
+)]
+const fn apply_tint(color: Color, tint: Color) -> Color {
+    const fn mul(a: u8, b: u8) -> u8 {
+        (a as u16 * b as u16 / 255) as u8
+    }
+    Color {
+        r: mul(color.r, tint.r),
+        g: mul(color.g, tint.g),
+        b: mul(color.b, tint.b),
+        a: mul(color.a, tint.a),
+    }
+}
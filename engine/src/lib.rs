@@ -14,5 +14,6 @@
 #![deny(clippy::perf, clippy::multiple_unsafe_ops_per_block)]
 #![forbid(clippy::missing_safety_doc, clippy::undocumented_unsafe_blocks)]
 
+pub mod animation;
 pub mod draw;
 pub use draw::{draw2d, draw3d};
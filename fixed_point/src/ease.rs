@@ -0,0 +1,44 @@
+//! Easing curves, for interpolating less abruptly than a straight line
+
+/// A curve from 0 to 1, applied with `ease` on any of the Q types, e.g. [`Q32_32::ease`]
+///
+/// "In" curves start slow, "out" curves end slow, and "in-out" curves do both.
+///
+/// [`Q32_32::ease`]: crate::Q32_32::ease
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Easing {
+    /// No easing at all
+    #[default]
+    Linear,
+    /// `3t² - 2t³`, the same as GLSL's `smoothstep`
+    SmoothStep,
+    /// `6t⁵ - 15t⁴ + 10t³`, which also starts and ends with no acceleration
+    SmootherStep,
+    /// `t²`
+    InQuad,
+    /// `1 - (1 - t)²`
+    OutQuad,
+    /// [`Self::InQuad`] then [`Self::OutQuad`]
+    InOutQuad,
+    /// `t³`
+    InCubic,
+    /// `1 - (1 - t)³`
+    OutCubic,
+    /// [`Self::InCubic`] then [`Self::OutCubic`]
+    InOutCubic,
+}
+
+impl Easing {
+    /// Every curve
+    pub const ALL: [Self; 9] = [
+        Self::Linear,
+        Self::SmoothStep,
+        Self::SmootherStep,
+        Self::InQuad,
+        Self::OutQuad,
+        Self::InOutQuad,
+        Self::InCubic,
+        Self::OutCubic,
+        Self::InOutCubic,
+    ];
+}
@@ -1,9 +1,15 @@
 //! Fixed point number library
 
 #![warn(missing_docs)]
-#![feature(const_ops, const_trait_impl, maybe_uninit_slice)]
+#![feature(const_ops, const_trait_impl)]
 
-use std::{mem::MaybeUninit, ops::*};
+use std::ops::*;
+
+mod ease;
+mod vector;
+
+pub use ease::Easing;
+pub use vector::{FpVec2, FpVec3};
 
 macro_rules! define_fp {
     (
@@ -59,30 +65,56 @@ macro_rules! define_fp {
                 }
             }
 
+            /// Exact by default, or rounded half to even with a precision
+            ///
+            /// Width, fill, alignment, `+`, and `0` work the same as for integers.
             impl std::fmt::Display for [<Q $IBITS _ $FBITS>] {
                 fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                    const MAX_DIGITS: usize = 32;
-                    let sign = if self.0.is_negative() { "-" } else { "" };
-                    let ipart = (self.0.cast_unsigned() & Self::INTEGER_MASK)
-                        .cast_signed()
-                        .unsigned_abs()
-                        >> Self::DECIMAL_BITS;
-                    let mut fbits = self.0.cast_unsigned() & Self::DECIMAL_MASK;
-                    let mut buf = [MaybeUninit::uninit(); MAX_DIGITS];
-                    let mut buf_len = 0;
-                    for digit in buf.iter_mut().take(f.precision().unwrap_or(MAX_DIGITS)) {
+                    let magnitude = self.0.unsigned_abs();
+                    let mut ipart = magnitude >> Self::DECIMAL_BITS;
+                    let mut fbits = magnitude & Self::DECIMAL_MASK;
+                    let precision = f.precision();
+                    // A binary fraction always ends within as many decimal digits as it has
+                    // bits, so this stops on its own without a precision
+                    let mut digits = Vec::new();
+                    while precision.is_none_or(|precision| digits.len() < precision)
+                        && (fbits != 0 || (precision.is_none() && digits.is_empty()))
+                    {
                         fbits *= 10;
-                        digit.write(
-                            b'0' + u8::try_from((fbits / Self::DECIMAL_FACTOR_INT) % 10).unwrap(),
+                        digits.push(
+                            b'0' + u8::try_from(fbits >> Self::DECIMAL_BITS).unwrap(),
                         );
-                        buf_len += 1;
                         fbits &= Self::DECIMAL_MASK;
-                        if f.precision().is_none() && fbits == 0 {
-                            break;
+                    }
+                    if let Some(precision) = precision {
+                        digits.resize(precision, b'0');
+                    }
+
+                    // What's left decides rounding, compared to half of the next digit up
+                    let half = Self::DECIMAL_FACTOR_INT >> 1;
+                    let last_is_odd = digits.last().map_or(ipart & 1 == 1, |digit| digit & 1 == 1);
+                    if fbits > half || (fbits == half && last_is_odd) {
+                        let mut carry = true;
+                        for digit in digits.iter_mut().rev() {
+                            if *digit == b'9' {
+                                *digit = b'0';
+                            } else {
+                                *digit += 1;
+                                carry = false;
+                                break;
+                            }
+                        }
+                        if carry {
+                            ipart += 1;
                         }
                     }
-                    let fpart = unsafe { str::from_utf8_unchecked(buf[0..buf_len].assume_init_ref()) };
-                    write!(f, "{sign}{ipart}.{fpart}")
+
+                    let mut buf = ipart.to_string();
+                    if !digits.is_empty() {
+                        buf.push('.');
+                        buf.extend(digits.into_iter().map(char::from));
+                    }
+                    f.pad_integral(!self.0.is_negative(), "", &buf)
                 }
             }
 
@@ -97,12 +129,13 @@ macro_rules! define_fp {
                 pub const MIN: Self = Self($Repr::MIN);
                 /// The maximum expressible value
                 pub const MAX: Self = Self($Repr::MAX);
+                /// 0.5
+                pub const HALF: Self = Self(1 << (Self::DECIMAL_BITS - 1));
 
                 const DECIMAL_BITS: u32 = $FBITS;
                 const DECIMAL_FACTOR_INT: $URepr = 1 << Self::DECIMAL_BITS;
                 const DECIMAL_FACTOR_ISQRT: $URepr = Self::DECIMAL_FACTOR_INT.isqrt();
                 const DECIMAL_MASK: $URepr = Self::DECIMAL_FACTOR_INT - 1;
-                const INTEGER_MASK: $URepr = !Self::DECIMAL_MASK;
                 const DECIMAL_FACTOR: f64 = Self::DECIMAL_FACTOR_INT as f64;
                 const DECIMAL_INV_FACTOR: f64 = Self::DECIMAL_FACTOR.recip();
 
@@ -124,6 +157,19 @@ macro_rules! define_fp {
                     (self.0 >> Self::DECIMAL_BITS) as $IPart
                 }
 
+                /// Construct a fixed point from its underlying representation
+                #[inline]
+                pub const fn from_bits(bits: $Repr) -> Self {
+                    Self(bits)
+                }
+
+                /// The underlying representation of the fixed point, which is the value
+                /// multiplied by 2 to the power of the number of fractional bits
+                #[inline]
+                pub const fn to_bits(self) -> $Repr {
+                    self.0
+                }
+
                 /// Construct a fixed point from a floating point
                 #[inline]
                 pub const fn from_f32(value: f32) -> Self {
@@ -166,6 +212,24 @@ macro_rules! define_fp {
                     Self(((self.0 as i128 * rhs.0 as i128) >> Self::DECIMAL_BITS) as $Repr)
                 }
 
+                /// Divide `self` by `rhs`, rounding towards zero and saturating at [`Self::MIN`] and
+                /// [`Self::MAX`]
+                ///
+                /// # Panics
+                ///
+                /// If `rhs` is zero
+                #[inline]
+                pub const fn divide(self, rhs: Self) -> Self {
+                    let quotient = ((self.0 as i128) << Self::DECIMAL_BITS) / rhs.0 as i128;
+                    if quotient as $Repr as i128 == quotient {
+                        Self(quotient as $Repr)
+                    } else if quotient < 0 {
+                        Self::MIN
+                    } else {
+                        Self::MAX
+                    }
+                }
+
                 /// Calculate the square root of `self`
                 #[inline]
                 pub const fn sqrt(self) -> Self {
@@ -175,12 +239,187 @@ macro_rules! define_fp {
                 /// Compare `self` with `rhs` with [`Ord`]
                 #[inline]
                 pub const fn compare(self, rhs: Self) -> std::cmp::Ordering {
-                    match self.0 - rhs.0 {
-                        ..0 => std::cmp::Ordering::Less,
-                        0 => std::cmp::Ordering::Equal,
-                        _ => std::cmp::Ordering::Greater,
+                    // Not subtracting, which could overflow
+                    if self.0 < rhs.0 {
+                        std::cmp::Ordering::Less
+                    } else if self.0 == rhs.0 {
+                        std::cmp::Ordering::Equal
+                    } else {
+                        std::cmp::Ordering::Greater
                     }
                 }
+
+                /// `self == rhs`, but usable in const contexts
+                #[inline]
+                pub const fn equals(self, rhs: Self) -> bool {
+                    self.0 == rhs.0
+                }
+
+                /// `self < rhs`, but usable in const contexts
+                #[inline]
+                pub const fn less_than(self, rhs: Self) -> bool {
+                    self.0 < rhs.0
+                }
+
+                /// `self <= rhs`, but usable in const contexts
+                #[inline]
+                pub const fn less_or_equal(self, rhs: Self) -> bool {
+                    self.0 <= rhs.0
+                }
+
+                /// `self > rhs`, but usable in const contexts
+                #[inline]
+                pub const fn greater_than(self, rhs: Self) -> bool {
+                    self.0 > rhs.0
+                }
+
+                /// `self >= rhs`, but usable in const contexts
+                #[inline]
+                pub const fn greater_or_equal(self, rhs: Self) -> bool {
+                    self.0 >= rhs.0
+                }
+
+                /// The smaller of `self` and `rhs`, like [`Ord::min`] but usable in const
+                /// contexts
+                #[inline]
+                pub const fn min(self, rhs: Self) -> Self {
+                    if self.less_or_equal(rhs) { self } else { rhs }
+                }
+
+                /// The larger of `self` and `rhs`, like [`Ord::max`] but usable in const
+                /// contexts
+                #[inline]
+                pub const fn max(self, rhs: Self) -> Self {
+                    if self.greater_than(rhs) { self } else { rhs }
+                }
+
+                /// Restrict `self` to `[min, max]`, like [`Ord::clamp`] but usable in const
+                /// contexts
+                ///
+                /// # Panics
+                ///
+                /// If `min` is greater than `max`
+                #[inline]
+                pub const fn clamp(self, min: Self, max: Self) -> Self {
+                    assert!(min.less_or_equal(max), "min must not be greater than max");
+                    self.max(min).min(max)
+                }
+
+                /// -1 if `self` is negative, 0 if it's zero, and 1 if it's positive
+                #[inline]
+                pub const fn signum(self) -> Self {
+                    Self::[<from_ $IPart>](self.0.signum() as $IPart)
+                }
+
+                /// Whether `self` is less than zero
+                #[inline]
+                pub const fn is_negative(self) -> bool {
+                    self.0 < 0
+                }
+
+                /// Whether `self` is greater than zero
+                #[inline]
+                pub const fn is_positive(self) -> bool {
+                    self.0 > 0
+                }
+
+                /// Whether `self` is exactly zero
+                #[inline]
+                pub const fn is_zero(self) -> bool {
+                    self.0 == 0
+                }
+
+                /// Linearly interpolate from `self` at `t == 0` to `other` at `t == 1`
+                ///
+                /// `t` outside of `[0, 1]` extrapolates.
+                #[inline]
+                pub const fn lerp(self, other: Self, t: Self) -> Self {
+                    self.plus(other.minus(self).multiply(t))
+                }
+
+                /// How far `value` is from `from` to `to`, the inverse of [`Self::lerp`]
+                ///
+                /// # Panics
+                ///
+                /// If `from` equals `to`
+                #[inline]
+                pub const fn inverse_lerp(from: Self, to: Self, value: Self) -> Self {
+                    value.minus(from).divide(to.minus(from))
+                }
+
+                /// Map `self` from between `in_start` and `in_end` to the same place between
+                /// `out_start` and `out_end`
+                ///
+                /// # Panics
+                ///
+                /// If `in_start` equals `in_end`
+                #[inline]
+                pub const fn remap(
+                    self,
+                    in_start: Self,
+                    in_end: Self,
+                    out_start: Self,
+                    out_end: Self,
+                ) -> Self {
+                    out_start.lerp(out_end, Self::inverse_lerp(in_start, in_end, self))
+                }
+
+                /// Apply `easing` to `self` as a fraction of the way through, clamped to
+                /// `[0, 1]`
+                ///
+                /// The result is always in `[0, 1]` too, so it can be passed to
+                /// [`Self::lerp`].
+                pub const fn ease(self, easing: Easing) -> Self {
+                    let t = self.clamp(Self::ZERO, Self::ONE);
+                    let two = Self::[<from_ $IPart>](2);
+                    /// Mirrors an "in" curve into an "out" curve
+                    const fn flip(t: [<Q $IBITS _ $FBITS>]) -> [<Q $IBITS _ $FBITS>] {
+                        [<Q $IBITS _ $FBITS>]::ONE.minus(t)
+                    }
+                    const fn square(t: [<Q $IBITS _ $FBITS>]) -> [<Q $IBITS _ $FBITS>] {
+                        t.multiply(t)
+                    }
+                    const fn cube(t: [<Q $IBITS _ $FBITS>]) -> [<Q $IBITS _ $FBITS>] {
+                        t.multiply(t).multiply(t)
+                    }
+                    match easing {
+                        Easing::Linear => t,
+                        Easing::SmoothStep => square(t)
+                            .multiply(Self::[<from_ $IPart>](3).minus(two.multiply(t))),
+                        Easing::SmootherStep => cube(t).multiply(
+                            t.multiply(
+                                t.multiply(Self::[<from_ $IPart>](6))
+                                    .minus(Self::[<from_ $IPart>](15)),
+                            )
+                            .plus(Self::[<from_ $IPart>](10)),
+                        ),
+                        Easing::InQuad => square(t),
+                        Easing::OutQuad => flip(square(flip(t))),
+                        Easing::InOutQuad if t.less_than(Self::HALF) => {
+                            two.multiply(square(t))
+                        }
+                        Easing::InOutQuad => flip(two.multiply(square(flip(t)))),
+                        Easing::InCubic => cube(t),
+                        Easing::OutCubic => flip(cube(flip(t))),
+                        Easing::InOutCubic if t.less_than(Self::HALF) => {
+                            Self::[<from_ $IPart>](4).multiply(cube(t))
+                        }
+                        Easing::InOutCubic => {
+                            flip(Self::[<from_ $IPart>](4).multiply(cube(flip(t))))
+                        }
+                    }
+                }
+
+                /// 0 at `edge0` or before, 1 at `edge1` or after, and [`Easing::SmoothStep`]
+                /// in between, like GLSL's `smoothstep`
+                ///
+                /// # Panics
+                ///
+                /// If `edge0` equals `edge1`
+                #[inline]
+                pub const fn smoothstep(self, edge0: Self, edge1: Self) -> Self {
+                    Self::inverse_lerp(edge0, edge1, self).ease(Easing::SmoothStep)
+                }
             }
 
             impl Neg for [<Q $IBITS _ $FBITS>] {
@@ -352,7 +591,7 @@ macro_rules! define_fp {
 
                 #[inline]
                 fn mul(self, rhs: [<Q $IBITS _ $FBITS>]) -> Self::Output {
-                    [<Q $IBITS _ $FBITS>]::[<from_ $IPart>](self).sub(rhs)
+                    [<Q $IBITS _ $FBITS>]::[<from_ $IPart>](self).multiply(rhs)
                 }
             }
 
@@ -377,7 +616,7 @@ macro_rules! define_fp {
 
                 #[inline]
                 fn mul(self, rhs: [<Q $IBITS _ $FBITS>]) -> Self::Output {
-                    [<Q $IBITS _ $FBITS>]::from_f32(self).sub(rhs)
+                    [<Q $IBITS _ $FBITS>]::from_f32(self).multiply(rhs)
                 }
             }
 
@@ -387,6 +626,21 @@ macro_rules! define_fp {
                     *self = self.mul(rhs)
                 }
             }
+
+            impl Div for [<Q $IBITS _ $FBITS>] {
+                type Output = Self;
+
+                fn div(self, rhs: Self) -> Self::Output {
+                    self.divide(rhs)
+                }
+            }
+
+            impl DivAssign for [<Q $IBITS _ $FBITS>] {
+                #[inline]
+                fn div_assign(&mut self, rhs: Self) {
+                    *self = self.div(rhs)
+                }
+            }
         }
     };
 }
@@ -458,6 +712,23 @@ mod test_fixed_point {
         );
     }
 
+    #[test]
+    fn test_divide() {
+        let x = Q32_32::from_i32(3).divide(Q32_32::from_i32(4));
+        assert_eq!(x, Q32_32::from_f32(0.75));
+        assert_eq!(x.divide(Q32_32::from_f32(-0.25)), Q32_32::from_i32(-3));
+        assert_eq!(Q32_32::from_i32(-7) / Q32_32::ONE, Q32_32::from_i32(-7));
+        assert_eq!(
+            Q32_32::from_i32(1 << 30).divide(Q32_32::from_f32(0.001)),
+            Q32_32::MAX
+        );
+        assert_eq!(
+            Q32_32::from_i32(1 << 30).divide(Q32_32::from_f32(-0.001)),
+            Q32_32::MIN
+        );
+        assert!(Q32_32::MIN.compare(Q32_32::MAX).is_lt());
+    }
+
     #[test]
     fn test_f32_frac() {
         let epsilon = 0.001;
@@ -501,6 +772,92 @@ mod test_fixed_point {
         );
     }
 
+    #[test]
+    fn test_compare() {
+        const LIMIT: Q32_32 = Q32_32::from_i32(-1000).clamp(Q32_32::from_i32(-100), Q32_32::MAX);
+        assert_eq!(LIMIT, Q32_32::from_i32(-100));
+        assert!(LIMIT.is_negative() && !LIMIT.is_positive() && !LIMIT.is_zero());
+        assert_eq!(LIMIT.signum(), Q32_32::NEG_ONE);
+        assert_eq!(Q32_32::ZERO.signum(), Q32_32::ZERO);
+        assert_eq!(Q32_32::from_f32(0.25).signum(), Q32_32::ONE);
+        assert!(Q32_32::ZERO.is_zero());
+
+        let (a, b) = (Q16_16::from_f32(-0.5), Q16_16::from_f32(0.5));
+        assert!(a.less_than(b) && a.less_or_equal(b) && a.less_or_equal(a));
+        assert!(b.greater_than(a) && b.greater_or_equal(b) && !a.greater_than(a));
+        assert!(a.equals(a) && !a.equals(b));
+        assert_eq!((a.min(b), a.max(b)), (a, b));
+        assert_eq!(
+            Q64_64::from_i64(7).clamp(Q64_64::ZERO, Q64_64::ONE),
+            Q64_64::ONE
+        );
+    }
+
+    #[test]
+    #[should_panic = "min must not be greater than max"]
+    fn test_clamp_backwards() {
+        Q32_32::ZERO.clamp(Q32_32::ONE, Q32_32::NEG_ONE);
+    }
+
+    #[test]
+    fn test_lerp() {
+        let q = Q32_32::from_i32;
+        assert_eq!(q(10).lerp(q(20), Q32_32::HALF), q(15));
+        assert_eq!(q(10).lerp(q(20), q(2)), q(30), "should extrapolate");
+        assert_eq!(q(20).lerp(q(10), Q32_32::ONE), q(10));
+        assert_eq!(Q32_32::inverse_lerp(q(10), q(20), q(15)), Q32_32::HALF);
+        assert_eq!(Q32_32::inverse_lerp(q(20), q(10), q(25)), -Q32_32::HALF);
+        assert_eq!(q(15).remap(q(10), q(20), q(100), q(200)), q(150));
+        assert_eq!(q(-1).remap(q(0), q(-2), q(0), q(8)), q(4));
+
+        let quarter = Q32_32::from_f32(0.25);
+        for easing in Easing::ALL {
+            assert_eq!(
+                Q32_32::ZERO.ease(easing),
+                Q32_32::ZERO,
+                "{easing:?} should start at 0"
+            );
+            assert_eq!(
+                Q32_32::ONE.ease(easing),
+                Q32_32::ONE,
+                "{easing:?} should end at 1"
+            );
+            assert_eq!(q(-3).ease(easing), Q32_32::ZERO, "{easing:?} should clamp");
+            assert_eq!(q(3).ease(easing), Q32_32::ONE, "{easing:?} should clamp");
+            let mut previous = Q32_32::ZERO;
+            for step in 1..=64 {
+                let eased = Q32_32::from_i32(step).divide(q(64)).ease(easing);
+                assert!(
+                    eased.greater_or_equal(previous),
+                    "{easing:?} should only go up"
+                );
+                previous = eased;
+            }
+        }
+        for easing in [
+            Easing::Linear,
+            Easing::SmoothStep,
+            Easing::SmootherStep,
+            Easing::InOutQuad,
+            Easing::InOutCubic,
+        ] {
+            assert_eq!(
+                Q32_32::HALF.ease(easing),
+                Q32_32::HALF,
+                "{easing:?} is symmetric"
+            );
+        }
+        assert_eq!(Q16_16::HALF.ease(Easing::InQuad), Q16_16::from_f32(0.25));
+        assert_eq!(Q16_16::HALF.ease(Easing::OutQuad), Q16_16::from_f32(0.75));
+        assert_eq!(quarter.ease(Easing::InCubic), Q32_32::from_f32(1.0 / 64.0));
+        assert_eq!(
+            quarter.ease(Easing::InOutCubic),
+            Q32_32::from_f32(1.0 / 16.0)
+        );
+        assert_eq!(q(5).smoothstep(q(0), q(10)), Q32_32::HALF);
+        assert_eq!(q(-5).smoothstep(q(0), q(10)), Q32_32::ZERO);
+    }
+
     #[test]
     fn test_sqrt() {
         assert_eq!(Q32_32::from_i32(100).sqrt(), Q32_32::from_i32(10));
@@ -514,12 +871,75 @@ mod test_fixed_point {
             ((-100, 0), "-100.0"),
             ((5, (Q32_32::DECIMAL_FACTOR_INT / 2) as u32), "5.5"),
             ((1, 1), "1.00000000023283064365386962890625"),
-            ((-100, 645566574), "-100.1503076809458434581756591796875"),
+            ((-100, 645566574), "-99.8496923190541565418243408203125"),
         ] {
             let actual = Q32_32::new(ipart, fpart).to_string();
             assert_eq!(&actual, expect);
         }
-        let actual = format!("{:.3}", Q32_32::new(-100, 645566574));
+        let actual = format!("{:.3}", Q32_32::new(100, 645566574).negate());
         assert_eq!(&actual, "-100.150");
+        assert_eq!(
+            Q32_32::new(100, 645566574).negate().to_string(),
+            "-100.1503076809458434581756591796875"
+        );
+    }
+
+    #[test]
+    fn test_fmt_rounding() {
+        let q = Q32_32::from_f32;
+        for (value, precision, expect) in [
+            // Halves go to the even neighbor
+            (0.5, 0, "0"),
+            (1.5, 0, "2"),
+            (2.5, 0, "2"),
+            (-2.5, 0, "-2"),
+            (-3.5, 0, "-4"),
+            (0.125, 2, "0.12"),
+            (0.375, 2, "0.38"),
+            (-0.625, 2, "-0.62"),
+            // Anything past half goes up, carrying into the integer part
+            (0.126, 2, "0.13"),
+            (9.96875, 1, "10.0"),
+            (-99.998, 2, "-100.00"),
+            (0.75, 0, "1"),
+            // Anything under half goes down
+            (0.124, 2, "0.12"),
+            (1.25, 3, "1.250"),
+            (-0.25, 0, "-0"),
+            (7.0, 5, "7.00000"),
+        ] {
+            assert_eq!(
+                format!("{:.*}", precision, q(value)),
+                expect,
+                "{value} to {precision} places"
+            );
+        }
+        assert_eq!(Q16_16::new(1, 1).to_string(), "1.0000152587890625");
+        assert_eq!(
+            Q64_64::new(0, 1).to_string(),
+            "0.0000000000000000000542101086242752217003726400434970855712890625",
+            "every digit of the smallest fraction should be shown"
+        );
+        assert_eq!(format!("{:.1}", Q16_16::MIN), "-32768.0");
+        assert_eq!(format!("{:.0}", Q16_16::MAX), "32768");
+    }
+
+    #[test]
+    fn test_fmt_flags() {
+        let x = Q32_32::from_f32(1.5);
+        for (actual, expect) in [
+            (format!("{x:+}"), "+1.5"),
+            (format!("{:+}", -x), "-1.5"),
+            (format!("{:+.0}", Q32_32::ZERO), "+0"),
+            (format!("{x:8}"), "     1.5"),
+            (format!("{x:<8}"), "1.5     "),
+            (format!("{x:^8.2}"), "  1.50  "),
+            (format!("{x:*>8.2}"), "****1.50"),
+            (format!("{:08.2}", -x), "-0001.50"),
+            (format!("{x:+08.2}"), "+0001.50"),
+            (format!("{x:2}"), "1.5"),
+        ] {
+            assert_eq!(actual, expect);
+        }
     }
 }
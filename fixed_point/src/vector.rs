@@ -0,0 +1,358 @@
+//! Vectors of fixed point numbers
+
+use crate::{Q16_16, Q32_32, Q64_64};
+use std::ops::*;
+
+/// 2D vector of fixed point numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FpVec2<Q> {
+    /// The first component
+    pub x: Q,
+    /// The second component
+    pub y: Q,
+}
+
+/// 3D vector of fixed point numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FpVec3<Q> {
+    /// The first component
+    pub x: Q,
+    /// The second component
+    pub y: Q,
+    /// The third component
+    pub z: Q,
+}
+
+impl<Q> FpVec2<Q> {
+    /// Construct a vector from its components
+    #[inline]
+    pub const fn new(x: Q, y: Q) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<Q> FpVec3<Q> {
+    /// Construct a vector from its components
+    #[inline]
+    pub const fn new(x: Q, y: Q, z: Q) -> Self {
+        Self { x, y, z }
+    }
+}
+
+/// Everything both vector types have, which is all componentwise
+macro_rules! define_fp_vec_common {
+    ($Q:ty; $($c:ident),+) => {
+        /// &langle;0, 0, ...&rangle;
+        pub const ZERO: Self = Self::splat(<$Q>::ZERO);
+        /// &langle;1, 1, ...&rangle;
+        pub const ONE: Self = Self::splat(<$Q>::ONE);
+
+        /// A vector with every component set to `value`
+        #[inline]
+        pub const fn splat(value: $Q) -> Self {
+            Self { $($c: value),+ }
+        }
+
+        /// Componentwise absolute value
+        #[inline]
+        pub const fn abs(self) -> Self {
+            Self { $($c: self.$c.abs()),+ }
+        }
+
+        /// Negate a vector
+        #[inline]
+        pub const fn negate(self) -> Self {
+            Self { $($c: self.$c.negate()),+ }
+        }
+
+        /// Add a vector
+        #[inline]
+        pub const fn plus(self, rhs: Self) -> Self {
+            Self { $($c: self.$c.plus(rhs.$c)),+ }
+        }
+
+        /// Subtract a vector
+        #[inline]
+        pub const fn minus(self, rhs: Self) -> Self {
+            Self { $($c: self.$c.minus(rhs.$c)),+ }
+        }
+
+        /// Multiply all components by a single value
+        #[inline]
+        pub const fn scale(self, rhs: $Q) -> Self {
+            Self { $($c: self.$c.multiply(rhs)),+ }
+        }
+
+        /// Multiply vectors componentwise
+        #[inline]
+        pub const fn multiply(self, rhs: Self) -> Self {
+            Self { $($c: self.$c.multiply(rhs.$c)),+ }
+        }
+
+        /// The sum of the components
+        #[inline]
+        pub const fn sum(self) -> $Q {
+            <$Q>::ZERO$(.plus(self.$c))+
+        }
+
+        /// The product of the components
+        #[inline]
+        pub const fn product(self) -> $Q {
+            <$Q>::ONE$(.multiply(self.$c))+
+        }
+
+        /// Calculate the dot product between two vectors
+        #[inline]
+        pub const fn dot(self, rhs: Self) -> $Q {
+            self.multiply(rhs).sum()
+        }
+
+        /// Calculate the taxicab magnitude of a vector, which is cheaper than the Euclidian
+        /// length but does not represent a single straight line and depends on the rotation of
+        /// the grid
+        #[inline]
+        pub const fn length_taxi(self) -> $Q {
+            self.abs().sum()
+        }
+
+        /// Calculate the square of the Euclidian magnitude of a vector, which is cheaper than
+        /// the length due to not needing to sqrt but is only useful for comparisons and not
+        /// amounts
+        ///
+        /// Overflows if any component is past the square root of the maximum.
+        #[inline]
+        pub const fn length_sqr(self) -> $Q {
+            self.dot(self)
+        }
+
+        /// Calculate the Euclidian magnitude of a vector
+        ///
+        /// See also [`Self::length_sqr`]
+        #[inline]
+        pub const fn length(self) -> $Q {
+            self.length_sqr().sqrt()
+        }
+
+        /// Calculate the taxicab distance between two vectors, see [`Self::length_taxi`]
+        #[inline]
+        pub const fn distance_taxi(self, other: Self) -> $Q {
+            self.minus(other).length_taxi()
+        }
+
+        /// Calculate the square of the Euclidian distance between two vectors, see
+        /// [`Self::length_sqr`]
+        #[inline]
+        pub const fn distance_sqr(self, other: Self) -> $Q {
+            self.minus(other).length_sqr()
+        }
+
+        /// Calculate the Euclidian distance between two vectors
+        #[inline]
+        pub const fn distance(self, other: Self) -> $Q {
+            self.minus(other).length()
+        }
+
+        /// The vector in the same direction with a length of one, or [`None`] if it has no
+        /// direction because it's zero
+        ///
+        /// The length is only as precise as [`sqrt`](<$Q>::sqrt), so very short vectors may
+        /// come out a little long or short.
+        #[inline]
+        pub const fn normalize(self) -> Option<Self> {
+            let length = self.length();
+            if length.is_zero() {
+                None
+            } else {
+                Some(Self { $($c: self.$c.divide(length)),+ })
+            }
+        }
+
+        /// Linearly interpolate from `self` at `t == 0` to `rhs` at `t == 1`
+        ///
+        /// `t` outside of `[0, 1]` extrapolates.
+        #[inline]
+        pub const fn lerp(self, rhs: Self, t: $Q) -> Self {
+            self.plus(rhs.minus(self).scale(t))
+        }
+
+        /// The smaller of each component
+        #[inline]
+        pub const fn min(self, rhs: Self) -> Self {
+            Self {
+                $($c: self.$c.min(rhs.$c)),+
+            }
+        }
+
+        /// The larger of each component
+        #[inline]
+        pub const fn max(self, rhs: Self) -> Self {
+            Self {
+                $($c: self.$c.max(rhs.$c)),+
+            }
+        }
+    };
+}
+
+/// Operators for a vector type, in terms of its const methods
+macro_rules! define_fp_vec_ops {
+    ($Vec:ty, $Q:ty) => {
+        impl Neg for $Vec {
+            type Output = Self;
+
+            #[inline]
+            fn neg(self) -> Self::Output {
+                self.negate()
+            }
+        }
+
+        impl const Add for $Vec {
+            type Output = Self;
+
+            #[inline]
+            fn add(self, rhs: Self) -> Self::Output {
+                self.plus(rhs)
+            }
+        }
+
+        impl AddAssign for $Vec {
+            #[inline]
+            fn add_assign(&mut self, rhs: Self) {
+                *self = self.plus(rhs);
+            }
+        }
+
+        impl Sub for $Vec {
+            type Output = Self;
+
+            #[inline]
+            fn sub(self, rhs: Self) -> Self::Output {
+                self.minus(rhs)
+            }
+        }
+
+        impl SubAssign for $Vec {
+            #[inline]
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = self.minus(rhs);
+            }
+        }
+
+        impl Mul<$Q> for $Vec {
+            type Output = Self;
+
+            #[inline]
+            fn mul(self, rhs: $Q) -> Self::Output {
+                self.scale(rhs)
+            }
+        }
+
+        impl MulAssign<$Q> for $Vec {
+            #[inline]
+            fn mul_assign(&mut self, rhs: $Q) {
+                *self = self.scale(rhs);
+            }
+        }
+
+        impl Mul for $Vec {
+            type Output = Self;
+
+            #[inline]
+            fn mul(self, rhs: Self) -> Self::Output {
+                self.multiply(rhs)
+            }
+        }
+
+        impl MulAssign for $Vec {
+            #[inline]
+            fn mul_assign(&mut self, rhs: Self) {
+                *self = self.multiply(rhs);
+            }
+        }
+    };
+}
+
+macro_rules! define_fp_vec {
+    ($Q:ty) => {
+        impl FpVec2<$Q> {
+            define_fp_vec_common!($Q; x, y);
+
+            /// The z component of the cross product of `self` and `rhs` extended to 3D, which is
+            /// positive if `rhs` is counterclockwise from `self`
+            #[inline]
+            pub const fn cross(self, rhs: Self) -> $Q {
+                self.x.multiply(rhs.y).minus(self.y.multiply(rhs.x))
+            }
+        }
+
+        impl FpVec3<$Q> {
+            define_fp_vec_common!($Q; x, y, z);
+
+            /// Calculate the cross product of two vectors, which is perpendicular to both
+            #[inline]
+            pub const fn cross(self, rhs: Self) -> Self {
+                Self {
+                    x: self.y.multiply(rhs.z).minus(self.z.multiply(rhs.y)),
+                    y: self.z.multiply(rhs.x).minus(self.x.multiply(rhs.z)),
+                    z: self.x.multiply(rhs.y).minus(self.y.multiply(rhs.x)),
+                }
+            }
+        }
+
+        define_fp_vec_ops!(FpVec2<$Q>, $Q);
+        define_fp_vec_ops!(FpVec3<$Q>, $Q);
+    };
+}
+
+define_fp_vec!(Q16_16);
+define_fp_vec!(Q32_32);
+define_fp_vec!(Q64_64);
+
+#[cfg(test)]
+mod test_vector {
+    use super::*;
+
+    #[test]
+    fn test_vec3() {
+        let v = |x, y, z| {
+            FpVec3::new(
+                Q32_32::from_i32(x),
+                Q32_32::from_i32(y),
+                Q32_32::from_i32(z),
+            )
+        };
+        let x = v(1, 0, 0);
+        let y = v(0, 1, 0);
+        assert_eq!(x.cross(y), v(0, 0, 1));
+        assert_eq!(y.cross(x), v(0, 0, -1));
+        assert_eq!(x.dot(y), Q32_32::ZERO);
+        assert_eq!(v(1, 2, 3).dot(v(4, -5, 6)), Q32_32::from_i32(12));
+
+        assert_eq!(v(3, 4, 0).length(), Q32_32::from_i32(5));
+        assert_eq!(
+            v(-3, 4, 12).distance(FpVec3::<Q32_32>::ZERO),
+            Q32_32::from_i32(13)
+        );
+        assert_eq!(v(0, -8, 0).normalize(), Some(v(0, -1, 0)));
+        assert_eq!(FpVec3::<Q32_32>::ZERO.normalize(), None);
+        let unit = v(3, 4, 0).normalize().unwrap();
+        let fifths = |n| Q32_32::from_i32(n).divide(Q32_32::from_i32(5));
+        assert_eq!((unit.x, unit.y), (fifths(3), fifths(4)));
+
+        let half = Q32_32::from_f32(0.5);
+        assert_eq!(v(2, 4, -6).lerp(v(4, 0, 6), half), v(3, 2, 0));
+        assert_eq!(v(2, 4, -6).lerp(v(4, 0, 6), Q32_32::ONE), v(4, 0, 6));
+        assert_eq!(v(1, 5, -2).min(v(3, -5, 0)), v(1, -5, -2));
+        assert_eq!(-v(1, -2, 3) + v(1, 1, 1) * Q32_32::from_i32(2), v(1, 4, -1));
+    }
+
+    #[test]
+    fn test_vec2() {
+        let v = |x, y| FpVec2::new(Q16_16::from_i16(x), Q16_16::from_i16(y));
+        assert_eq!(v(1, 0).cross(v(0, 1)), Q16_16::ONE);
+        assert_eq!(v(0, 1).cross(v(1, 0)), Q16_16::NEG_ONE);
+        assert_eq!(v(6, 8).length(), Q16_16::from_i16(10));
+        assert_eq!(v(6, 8).length_taxi(), Q16_16::from_i16(14));
+        assert_eq!(v(0, 3).normalize(), Some(v(0, 1)));
+        assert_eq!(v(2, 3).product(), Q16_16::from_i16(6));
+    }
+}
@@ -0,0 +1,121 @@
+//! What has focus: the game, or something shown over it
+//!
+//! States are kept on a [`StateStack`], with [`AppState::Playing`] always at the bottom. Only
+//! while it's on top does the world get input, so nothing opened over the game leaves the
+//! player walking around behind it.
+
+use raylib::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    /// Walking around and building
+    Playing,
+    /// The pause menu is open, and the simulation is stopped unless playing with others
+    Paused,
+    /// A [`Panel`](crate::ui::Panel) is open
+    MenuOpen,
+    /// The [`Console`](crate::console::Console) is open
+    Console,
+    /// Flying the [`PhotoMode`](crate::photo_mode::PhotoMode) camera around, with the simulation
+    /// stopped unless playing with others
+    PhotoMode,
+}
+
+/// Every state the game is in, with the one on top having focus
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateStack {
+    /// Everything above [`AppState::Playing`], bottom to top
+    states: Vec<AppState>,
+    /// Whether the cursor has been released for the states on top
+    cursor_released: bool,
+}
+
+impl StateStack {
+    /// The state with focus
+    #[inline]
+    pub fn top(&self) -> AppState {
+        self.states.last().copied().unwrap_or(AppState::Playing)
+    }
+
+    /// Whether `state` is anywhere on the stack
+    pub fn contains(&self, state: AppState) -> bool {
+        state == AppState::Playing || self.states.contains(&state)
+    }
+
+    /// Whether the simulation is stopped, which it never is when `multiplayer` because the
+    /// others are still playing
+    pub fn is_paused(&self, multiplayer: bool) -> bool {
+        !multiplayer && (self.contains(AppState::Paused) || self.contains(AppState::PhotoMode))
+    }
+
+    /// Give `state` focus over the rest
+    ///
+    /// [`AppState::Playing`] is always at the bottom, so pushing it does nothing.
+    pub fn push(&mut self, state: AppState) {
+        if state != AppState::Playing {
+            self.states.push(state);
+        }
+    }
+
+    /// Take the topmost `state` off the stack, returning whether it was on it
+    ///
+    /// It doesn't have to be on top, so a menu can close from under the console.
+    pub fn remove(&mut self, state: AppState) -> bool {
+        let Some(idx) = self.states.iter().rposition(|&s| s == state) else {
+            return false;
+        };
+        self.states.remove(idx);
+        true
+    }
+
+    /// Release the cursor while anything is over the game, and capture it again for playing
+    /// or looking around in photo mode
+    pub fn sync_cursor(&mut self, rl: &mut RaylibHandle) {
+        let release = !matches!(self.top(), AppState::Playing | AppState::PhotoMode);
+        if release != self.cursor_released {
+            if release {
+                rl.enable_cursor();
+            } else {
+                rl.disable_cursor();
+            }
+            self.cursor_released = release;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_stack() {
+        let mut states = StateStack::default();
+        assert_eq!(states.top(), AppState::Playing);
+        assert!(
+            !states.remove(AppState::Playing),
+            "playing can't be removed"
+        );
+
+        states.push(AppState::Paused);
+        states.push(AppState::MenuOpen);
+        states.push(AppState::Console);
+        assert_eq!(states.top(), AppState::Console);
+        assert!(states.is_paused(false));
+        assert!(!states.is_paused(true), "multiplayer games keep going");
+
+        assert!(states.remove(AppState::MenuOpen));
+        assert_eq!(states.top(), AppState::Console);
+        assert!(!states.remove(AppState::MenuOpen));
+        assert!(states.remove(AppState::Console));
+        assert!(states.remove(AppState::Paused));
+        assert_eq!(states.top(), AppState::Playing);
+        assert!(!states.is_paused(false));
+
+        states.push(AppState::PhotoMode);
+        assert!(
+            states.is_paused(false),
+            "photo mode stops the simulation too"
+        );
+        assert!(!states.is_paused(true));
+    }
+}
@@ -0,0 +1,196 @@
+//! Music, interface sounds, and sounds coming from places in the world
+//!
+//! Sounds in the world are played by [`Emitter`]s, which are gathered each frame. Emitters of the
+//! same sound are mixed into one playing sound, as loud as all of them together and panned
+//! towards the loudest.
+
+use crate::{
+    math::coords::PlayerVector3, player::Player, region::factory::Factory, resource::AudioResources,
+};
+use raylib::prelude::*;
+
+/// Distance within which emitters are at full volume, in meters
+const REFERENCE_DISTANCE: f32 = 2.0;
+/// Distance beyond which emitters can't be heard, in meters
+pub const MAX_DISTANCE: f32 = 40.0;
+
+/// Sounds with no position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UiSound {
+    Click,
+    Error,
+}
+
+/// Looping sounds that come from somewhere in the world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AmbientSound {
+    ReactorHum,
+}
+
+impl AmbientSound {
+    pub const ALL: [Self; 1] = [Self::ReactorHum];
+}
+
+/// Something making an [`AmbientSound`] this frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Emitter {
+    pub sound: AmbientSound,
+    pub position: PlayerVector3,
+    /// How loud the emitter is up close, in `[0, 1]`
+    pub volume: f32,
+}
+
+/// How much quieter a sound is `distance` meters away
+///
+/// Falls off with the inverse of distance past [`REFERENCE_DISTANCE`], and fades out completely
+/// by [`MAX_DISTANCE`] so far away sounds don't need to be played at all.
+pub fn attenuation(distance: f32) -> f32 {
+    if distance <= REFERENCE_DISTANCE {
+        1.0
+    } else if distance >= MAX_DISTANCE {
+        0.0
+    } else {
+        let fade = 1.0 - (distance - REFERENCE_DISTANCE) / (MAX_DISTANCE - REFERENCE_DISTANCE);
+        REFERENCE_DISTANCE / distance * fade
+    }
+}
+
+/// Stereo pan for a sound `offset` from a listener whose right is `right`
+///
+/// 0 is fully left, 0.5 is centered, and 1 is fully right.
+pub fn pan(right: Vector3, offset: Vector3) -> f32 {
+    0.5 + 0.5 * right.normalize_or_zero().dot(offset.normalize_or_zero())
+}
+
+pub struct Audio<'aud> {
+    device: &'aud RaylibAudio,
+    sounds: AudioResources<'aud>,
+    music: Option<Music<'aud>>,
+    /// In `[0, 1]`
+    pub music_volume: f32,
+    /// Volume of sounds in the world, in `[0, 1]`
+    pub effects_volume: f32,
+    /// In `[0, 1]`
+    pub ui_volume: f32,
+    emitters: Vec<Emitter>,
+}
+
+impl<'aud> Audio<'aud> {
+    /// Load every sound
+    pub fn new(device: &'aud RaylibAudio) -> Result<Self, Error> {
+        Ok(Self {
+            device,
+            sounds: AudioResources::new(device)?,
+            music: None,
+            music_volume: 0.5,
+            effects_volume: 1.0,
+            ui_volume: 0.8,
+            emitters: Vec::new(),
+        })
+    }
+
+    const fn ambient(&self, sound: AmbientSound) -> &Sound<'aud> {
+        match sound {
+            AmbientSound::ReactorHum => &self.sounds.reactor_hum,
+        }
+    }
+
+    /// Set how loud everything is, in `[0, 1]`, on top of each kind of sound's own volume
+    pub fn set_master_volume(&self, volume: f32) {
+        self.device.set_master_volume(volume);
+    }
+
+    pub fn play_ui(&self, sound: UiSound) {
+        let sound = match sound {
+            UiSound::Click => &self.sounds.click,
+            UiSound::Error => &self.sounds.error,
+        };
+        sound.set_volume(self.ui_volume);
+        sound.play();
+    }
+
+    /// Stream the music file at `path` in place of any music already playing
+    pub fn play_music(&mut self, path: &str) -> Result<(), Error> {
+        if let Some(music) = &self.music {
+            music.stop_stream();
+        }
+        let music = self.device.new_music(path)?;
+        music.set_volume(self.music_volume);
+        music.play_stream();
+        self.music = Some(music);
+        Ok(())
+    }
+
+    pub fn stop_music(&mut self) {
+        if let Some(music) = self.music.take() {
+            music.stop_stream();
+        }
+    }
+
+    /// Make `emitter` heard this frame
+    pub fn emit(&mut self, emitter: Emitter) {
+        self.emitters.push(emitter);
+    }
+
+    /// Make every machine in `factory` that `listener` is close enough to hear heard this frame
+    pub fn emit_factory(&mut self, factory: &Factory, listener: &Player) {
+        self.emitters
+            .extend(factory.sound_emitters(listener.eye_pos()));
+    }
+
+    /// Mix this frame's emitters as heard by `listener` and keep the music streaming
+    ///
+    /// Should be called once per frame, after everything has emitted.
+    pub fn update(&mut self, listener: &Player) {
+        if let Some(music) = &self.music {
+            music.set_volume(self.music_volume);
+            music.update_stream();
+        }
+
+        let ears = listener.eye_pos();
+        let camera = &listener.camera;
+        let right = (camera.target - camera.position).cross(camera.up);
+        for kind in AmbientSound::ALL {
+            let (volume, weighted_pan) = self
+                .emitters
+                .iter()
+                .filter(|emitter| emitter.sound == kind)
+                .map(|emitter| {
+                    let offset = emitter.position.minus(ears).to_vec3();
+                    let gain = attenuation(offset.length()) * emitter.volume;
+                    (gain, gain * pan(right, offset))
+                })
+                .fold((0.0, 0.0), |(a, b), (gain, pan)| (a + gain, b + pan));
+
+            let sound = self.ambient(kind);
+            if volume > 0.0 {
+                sound.set_volume(volume.min(1.0) * self.effects_volume);
+                sound.set_pan(weighted_pan / volume);
+                if !sound.is_playing() {
+                    sound.play();
+                }
+            } else if sound.is_playing() {
+                sound.stop();
+            }
+        }
+        self.emitters.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attenuation() {
+        assert!((attenuation(0.0) - 1.0).abs() < f32::EPSILON);
+        assert!((attenuation(REFERENCE_DISTANCE) - 1.0).abs() < f32::EPSILON);
+        assert!(attenuation(MAX_DISTANCE).abs() < f32::EPSILON);
+        assert!(attenuation(5.0) > attenuation(10.0));
+
+        let right = Vector3::X;
+        assert!((pan(right, Vector3::new(3.0, 0.0, 0.0)) - 1.0).abs() < 1e-6);
+        assert!(pan(right, Vector3::new(-3.0, 0.0, 0.0)).abs() < 1e-6);
+        assert!((pan(right, Vector3::ZERO) - 0.5).abs() < 1e-6);
+    }
+}
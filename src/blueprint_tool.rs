@@ -0,0 +1,384 @@
+use crate::{
+    game::GameState,
+    input::{EventInput, Inputs},
+    locale::tr,
+    math::{
+        bounds::{Bounds, FactoryBounds, SpacialBounds},
+        coords::{FactoryVector3, VectorConstants},
+    },
+    ordinals::Cardinal2D,
+    palette::palette,
+    placement::Snap,
+    player::Player,
+    region::{
+        RegionId,
+        factory::{
+            Factory,
+            blueprint::{Blueprint, MirrorAxis, Orientation, StampError},
+        },
+    },
+    resource::{AssetId, Resources},
+    rl_helpers::{DynRaylibDraw3D, DynRender},
+};
+use engine::{
+    draw,
+    draw3d::{self, GhostValidity},
+};
+use raylib::prelude::*;
+
+/// The most blocks left between copies when repeating
+const MAX_SPACING: i16 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Mode {
+    #[default]
+    Off,
+    /// Choosing the corners of the area to copy
+    Select,
+    /// Choosing where to build the copied blueprint
+    Paste,
+}
+
+/// Copies part of a factory into [`GameState::clipboard`] and builds it somewhere else
+///
+/// In select mode, interacting with two blocks copies everything between them. In paste mode,
+/// the clipboard follows the block the player is looking at, snapped with [`Snap::MACHINES`] and
+/// flush against the side of a machine when looking at one, and interacting builds it there if
+/// nothing is in the way.
+///
+/// While sprinting in paste mode, the clipboard is instead placed next to the last copy built,
+/// in the direction the player is looking, so interacting again and again builds a line of
+/// copies. Scrolling changes how far apart they are.
+#[derive(Debug, Default)]
+pub struct BlueprintTool {
+    mode: Mode,
+    /// The index of the factory being copied from, and the first corner of the selection
+    corner: Option<(usize, FactoryVector3)>,
+    /// The block the player is looking at
+    target: Option<FactoryVector3>,
+    /// The flip and turn applied to the clipboard when pasting
+    orientation: Orientation,
+    /// The index of the factory the last copy was built in, where it was built, and how it was
+    /// oriented
+    last: Option<(usize, FactoryVector3, Orientation)>,
+    /// Blocks left between copies when repeating
+    spacing: i16,
+    /// Whether the preview is next to the last copy rather than at the target
+    repeating: bool,
+    /// Where the clipboard would be built and whether it fits there
+    ///
+    /// Only checked again when the target or orientation change.
+    preview: Option<(FactoryVector3, Orientation, Result<(), StampError>)>,
+}
+
+impl BlueprintTool {
+    /// Whether the tool is out, and so interacting is used by it
+    #[inline]
+    pub const fn is_active(&self) -> bool {
+        !matches!(self.mode, Mode::Off)
+    }
+
+    /// How what's being placed lines up, or [`None`] if nothing is
+    #[inline]
+    pub const fn snap(&self) -> Option<Snap> {
+        match self.mode {
+            Mode::Paste => Some(Snap::MACHINES),
+            Mode::Off | Mode::Select => None,
+        }
+    }
+
+    /// The blocks the clipboard would be built over, if pasting
+    pub const fn footprint(&self, clipboard: Option<&Blueprint>) -> Option<FactoryBounds> {
+        let (at, orientation, _) = self.preview.as_ref()?;
+        Some(clipboard?.area(*at, *orientation))
+    }
+
+    /// Put the tool away
+    pub fn close(&mut self) {
+        self.mode = Mode::Off;
+        self.corner = None;
+        self.target = None;
+        self.last = None;
+        self.preview = None;
+    }
+
+    /// Check whether the clipboard fits again, since the factory has changed under it
+    pub fn recheck(&mut self) {
+        self.preview = None;
+    }
+
+    /// Change the spacing between repeated copies with the inputs that would otherwise change
+    /// the hotbar slot, taking them so the slot stays put while repeating
+    pub fn scroll(&mut self, inputs: &mut Inputs) {
+        if self.mode != Mode::Paste || !inputs[EventInput::Sprint] {
+            return;
+        }
+        if std::mem::take(&mut inputs[EventInput::NextItem]) {
+            self.spacing = (self.spacing + 1).min(MAX_SPACING);
+        }
+        if std::mem::take(&mut inputs[EventInput::PrevItem]) {
+            self.spacing = (self.spacing - 1).max(0);
+        }
+    }
+
+    /// The block in `factory` the player is looking at, if it's within [`Player::REACH`]
+    ///
+    /// Looking at the side of a machine gives the empty block next to it.
+    fn looked_at(factory: &Factory, player: &Player) -> Option<FactoryVector3> {
+        let collision = factory
+            .get_ray_collision(factory.vision_ray(player))
+            .filter(|collision| collision.distance <= Player::REACH)?;
+        let inside = (collision.point + collision.normal * 0.5).floor();
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "factories are much smaller"
+        )]
+        let block = FactoryVector3::new(inside.x as i16, inside.y as i16, inside.z as i16);
+        Some(block)
+    }
+
+    /// Switch modes, and select, copy, or paste
+    pub fn update(&mut self, inputs: &Inputs, state: &mut GameState) {
+        if inputs[EventInput::ToggleBlueprintTool] {
+            self.mode = match self.mode {
+                Mode::Off => Mode::Select,
+                Mode::Select if state.clipboard.is_some() => Mode::Paste,
+                Mode::Select | Mode::Paste => Mode::Off,
+            };
+            self.corner = None;
+            self.preview = None;
+        }
+        let RegionId::Factory(idx) = state.current_region else {
+            self.corner = None;
+            self.target = None;
+            self.preview = None;
+            return;
+        };
+        if self.mode == Mode::Off {
+            return;
+        }
+        if self.corner.is_some_and(|(factory, _)| factory != idx) {
+            self.corner = None;
+        }
+        if inputs[EventInput::RotatePlacement] {
+            self.orientation.rotation = self.orientation.rotation.compose(Cardinal2D::North);
+        }
+        if inputs[EventInput::MirrorPlacement] {
+            self.orientation.mirror = match self.orientation.mirror {
+                None => Some(MirrorAxis::X),
+                Some(MirrorAxis::X) => Some(MirrorAxis::Z),
+                Some(MirrorAxis::Z) => None,
+            };
+        }
+        let factory = &state.factories[idx];
+        self.target = match self.snap() {
+            // Floors can't be aimed at on top of machines, but blueprints can still go there
+            Some(snap) => (snap.spot(factory, &state.player).map(|spot| spot.block))
+                .or_else(|| Self::looked_at(factory, &state.player)),
+            None => Self::looked_at(factory, &state.player),
+        };
+
+        match self.mode {
+            Mode::Off => {}
+            Mode::Select => self.update_select(inputs, state, idx),
+            Mode::Paste => self.update_paste(inputs, state, idx),
+        }
+    }
+
+    fn update_select(&mut self, inputs: &Inputs, state: &mut GameState, idx: usize) {
+        if !inputs[EventInput::Interact] {
+            return;
+        }
+        let Some(target) = self.target else {
+            self.corner = None;
+            return;
+        };
+        let Some((_, corner)) = self.corner else {
+            self.corner = Some((idx, target));
+            return;
+        };
+        let area = Self::selection(corner, target);
+        let blueprint = Blueprint::capture(&state.factories[idx], &area);
+        self.corner = None;
+        if !blueprint.is_empty() {
+            state.clipboard = Some(blueprint);
+            self.mode = Mode::Paste;
+        }
+    }
+
+    fn update_paste(&mut self, inputs: &Inputs, state: &mut GameState, idx: usize) {
+        let Some(blueprint) = &state.clipboard else {
+            self.mode = Mode::Select;
+            return;
+        };
+        let factory = &mut state.factories[idx];
+        let last =
+            (self.last).filter(|&(factory, ..)| factory == idx && inputs[EventInput::Sprint]);
+        self.repeating = last.is_some();
+        let (at, orientation) = if let Some((_, last, orientation)) = last {
+            let looking = factory.vision_ray(&state.player).direction;
+            let next = self.next_in_line(blueprint, last, orientation, looking);
+            (next, orientation)
+        } else if let Some(at) = self.target {
+            (at, self.orientation)
+        } else {
+            self.preview = None;
+            return;
+        };
+        // Research can change at any time, so this is checked every frame
+        let locked = state.research.locked_in(blueprint);
+        if let Some(unlock) = locked {
+            self.preview = Some((at, orientation, Err(StampError::Locked(unlock))));
+        } else if self
+            .preview
+            .as_ref()
+            .is_none_or(|(old_at, old_orientation, fits)| {
+                (*old_at, *old_orientation) != (at, orientation)
+                    || matches!(fits, Err(StampError::Locked(_)))
+            })
+        {
+            let fits = blueprint.check(factory, at, orientation);
+            self.preview = Some((at, orientation, fits));
+        }
+        if inputs[EventInput::Interact]
+            && locked.is_none()
+            && let Ok(built) = blueprint.stamp(factory, at, orientation)
+        {
+            state.history.record(idx, built);
+            // Everything there has changed
+            self.preview = None;
+            self.last = Some((idx, at, orientation));
+        }
+    }
+
+    /// Where the copy after the one at `last` goes, [`Self::spacing`] blocks past it along
+    /// whichever of east-west or north-south is closest to `looking`
+    fn next_in_line(
+        &self,
+        blueprint: &Blueprint,
+        last: FactoryVector3,
+        orientation: Orientation,
+        looking: Vector3,
+    ) -> FactoryVector3 {
+        let size = blueprint.area(last, orientation).size();
+        let sign = |component: f32| if component < 0.0 { -1 } else { 1 };
+        if looking.x.abs() >= looking.z.abs() {
+            last + FactoryVector3::new(sign(looking.x) * (size.x + self.spacing), 0, 0)
+        } else {
+            last + FactoryVector3::new(0, 0, sign(looking.z) * (size.z + self.spacing))
+        }
+    }
+
+    /// Every block from `a` to `b`, counting both
+    const fn selection(a: FactoryVector3, b: FactoryVector3) -> FactoryBounds {
+        FactoryBounds {
+            min: a.min(b),
+            max: a.max(b) + FactoryVector3::ONE,
+        }
+    }
+
+    /// Tells the player what to do next, or [`None`] if the tool isn't in use
+    #[must_use]
+    pub fn prompt(&self) -> Option<String> {
+        Some(match (self.mode, self.corner, &self.preview) {
+            (Mode::Off, ..) => return None,
+            (Mode::Select, None, _) => tr!("blueprint_tool.first_corner"),
+            (Mode::Select, Some(_), _) => tr!("blueprint_tool.second_corner"),
+            (Mode::Paste, _, None) => tr!("blueprint_tool.find_place"),
+            (Mode::Paste, _, Some((.., Ok(())))) if self.repeating => {
+                tr!("blueprint_tool.repeat", spacing = self.spacing)
+            }
+            (Mode::Paste, _, Some((.., Ok(())))) => tr!("blueprint_tool.build"),
+            (Mode::Paste, _, Some((.., Err(e)))) => tr!("blueprint_tool.blocked", reason = e),
+        })
+    }
+
+    /// Draw the selection or the blueprint being placed, in factory coordinates
+    ///
+    /// Each machine being placed is drawn as a ghost stretched over `ghost` if it's loaded, or
+    /// just outlined if it isn't.
+    fn draw_preview(
+        &self,
+        d: &mut draw3d::Renderer<'_>,
+        blueprint: Option<&Blueprint>,
+        ghost: Option<&Mesh>,
+    ) -> draw::Result {
+        let bbox = |bounds: FactoryBounds| BoundingBox {
+            min: bounds.min.as_vec3(),
+            max: bounds.max.as_vec3(),
+        };
+        match (self.mode, self.corner, self.target) {
+            (Mode::Select, Some((_, corner)), Some(target)) => {
+                d.draw_bounding_box(bbox(Self::selection(corner, target)), palette().selection)
+            }
+            (Mode::Select, None, Some(target)) => {
+                d.draw_bounding_box(bbox(Self::selection(target, target)), palette().selection)
+            }
+            (Mode::Paste, ..) => {
+                let (Some(blueprint), Some((at, orientation, fits))) = (blueprint, &self.preview)
+                else {
+                    return Ok(());
+                };
+                let placed = blueprint.placed(*at, *orientation);
+                let color = if fits.is_ok() {
+                    palette().valid
+                } else {
+                    Color::WHITE
+                };
+                d.draw_bounding_box(bbox(blueprint.area(*at, *orientation)), color)?;
+                let parts = (placed.reactors.iter().map(Bounds::bounds))
+                    .chain(placed.ladders.iter().map(Bounds::bounds));
+                for part in parts {
+                    let Some(ghost) = ghost else {
+                        d.draw_bounding_box(bbox(part), color)?;
+                        continue;
+                    };
+                    let validity = match fits {
+                        Ok(()) => GhostValidity::Valid,
+                        Err(StampError::Collisions(collisions))
+                            if !collisions.iter().any(|collision| collision.overlaps(&part)) =>
+                        {
+                            GhostValidity::Valid
+                        }
+                        Err(_) => GhostValidity::Colliding,
+                    };
+                    let BoundingBox { min, max } = bbox(part);
+                    let (size, mid) = (max - min, (min + max) * 0.5);
+                    let transform = Matrix::scale(size.x, size.y, size.z)
+                        * Matrix::translate(mid.x, mid.y, mid.z);
+                    d.draw_ghost(ghost, &transform, validity)?;
+                }
+                if let Err(StampError::Collisions(collisions)) = fits {
+                    for &collision in collisions {
+                        d.draw_bounding_box(bbox(collision), palette().invalid)?;
+                    }
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Draw the selection or the blueprint being placed in the 3D view
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D, resources: &Resources, state: &GameState) {
+        let RegionId::Factory(idx) = state.current_region else {
+            return;
+        };
+        if self.mode == Mode::Off {
+            return;
+        }
+        let origin = &state.factories[idx].origin;
+        let mut buf = DynRender(d);
+        let mut d = draw3d::Renderer::new(
+            &mut buf,
+            *draw3d::RenderingOptions::new()
+                .offset(FactoryVector3::ZERO.to_player_relative(&state.player.position, origin)),
+        );
+        // Can't fail when drawing directly to the screen
+        _ = self.draw_preview(
+            &mut d,
+            state.clipboard.as_ref(),
+            resources.mesh(AssetId::Ghost),
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Saving pictures of the game
+//!
+//! Pictures are rendered again into a texture rather than read back from the window, so they
+//! can be bigger than it (see [`Capture::scale`]). Screenshots are taken on demand, and a
+//! [`Timelapse`] saves an overview of a factory every so many minutes of game time.
+
+use crate::{player::Player, region::factory::Factory, time::WorldClock};
+use raylib::prelude::*;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Where screenshots are saved
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+/// Where timelapse frames are saved, in a folder for each factory
+pub const TIMELAPSE_DIR: &str = "timelapse";
+
+/// Taking an overview of a factory every [`Self::interval`] minutes of game time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timelapse {
+    /// Index of the factory
+    pub factory: usize,
+    /// Minutes of game time between frames
+    pub interval: f32,
+    /// When the next frame is due, in [`WorldClock::total_minutes`]
+    next: f64,
+    /// Number of the next frame
+    frame: u32,
+}
+
+impl Timelapse {
+    /// The shortest time between frames, in game minutes
+    const MIN_INTERVAL: f32 = 1.0;
+
+    /// Start a timelapse of the factory at `factory`, with the first frame due right away
+    pub fn new(factory: usize, interval: f32, clock: &WorldClock) -> Self {
+        Self {
+            factory,
+            interval: interval.max(Self::MIN_INTERVAL),
+            next: clock.total_minutes(),
+            frame: 0,
+        }
+    }
+
+    /// Where to save the next frame, if it's due at `clock`'s time
+    ///
+    /// Frames missed while the clock jumped ahead are skipped rather than all taken at once,
+    /// and if the clock was turned back, the next frame is due right away.
+    pub fn due(&mut self, clock: &WorldClock) -> Option<PathBuf> {
+        let now = clock.total_minutes();
+        let interval = f64::from(self.interval.max(Self::MIN_INTERVAL));
+        if self.next - now > interval {
+            self.next = now;
+        }
+        if now < self.next {
+            return None;
+        }
+        self.next += ((now - self.next) / interval)
+            .floor()
+            .mul_add(interval, interval);
+        let path = Path::new(TIMELAPSE_DIR)
+            .join(format!("factory_{}", self.factory))
+            .join(format!("{:05}.png", self.frame));
+        self.frame += 1;
+        Some(path)
+    }
+}
+
+/// Settings for taking pictures, and the timelapse being taken if any
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    /// How many times the size of the window pictures are
+    pub scale: u32,
+    /// Minutes of game time between timelapse frames, for the next timelapse started
+    pub timelapse_interval: f32,
+    pub timelapse: Option<Timelapse>,
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Self {
+            scale: 2,
+            timelapse_interval: 10.0,
+            timelapse: None,
+        }
+    }
+}
+
+impl Capture {
+    /// The size of pictures in pixels, for a window `screen` pixels in size
+    pub fn size(&self, screen: Vector2) -> (u32, u32) {
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "screen sizes are small and positive"
+        )]
+        let [width, height] = [screen.x, screen.y].map(|x| (x.max(1.0) as u32) * self.scale.max(1));
+        (width, height)
+    }
+
+    /// Start a timelapse of the factory at `factory`, or stop the one being taken
+    ///
+    /// Returns whether a timelapse is being taken now.
+    pub fn toggle_timelapse(&mut self, factory: Option<usize>, clock: &WorldClock) -> bool {
+        self.timelapse = match (self.timelapse.take(), factory) {
+            (None, Some(factory)) => Some(Timelapse::new(factory, self.timelapse_interval, clock)),
+            _ => None,
+        };
+        self.timelapse.is_some()
+    }
+}
+
+/// The first numbered screenshot in [`SCREENSHOT_DIR`] that doesn't exist yet
+pub fn next_screenshot_path() -> PathBuf {
+    (0..=u32::MAX)
+        .map(|n| Path::new(SCREENSHOT_DIR).join(format!("screenshot_{n:04}.png")))
+        .find(|path| !path.exists())
+        .expect("there aren't that many screenshots")
+}
+
+/// A camera looking down at the whole of `factory` from above one corner, relative to `player`
+/// like everything else that's drawn
+pub fn overview_camera(factory: &Factory, player: &Player) -> Camera3D {
+    const FOVY: f32 = 45.0;
+    let min = (factory.bounds.min).to_player_relative(&player.position, &factory.origin);
+    let max = (factory.bounds.max).to_player_relative(&player.position, &factory.origin);
+    let center = (min + max) * 0.5;
+    let radius = (max - min).length() * 0.5;
+    // Far enough back for the factory's bounding sphere to fit in view
+    let distance = radius / (FOVY.to_radians() * 0.5).sin();
+    let direction = Vector3::new(1.0, 1.5, 1.0).normalize();
+    Camera3D::perspective(center + direction * distance, center, Vector3::Y, FOVY)
+}
+
+#[derive(Debug)]
+pub enum CaptureError {
+    /// raylib couldn't make the texture to draw into or read it back
+    Render(Error),
+    /// The picture couldn't be written
+    Save { path: PathBuf, error: io::Error },
+}
+
+impl std::fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Render(error) => write!(f, "couldn't render picture: {error}"),
+            Self::Save { path, error } => write!(f, "couldn't save {}: {error}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Render(error) => Some(error),
+            Self::Save { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Draw a picture `size` pixels big with `draw`
+pub fn render(
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+    (width, height): (u32, u32),
+    draw: impl FnOnce(&mut RaylibTextureMode<'_, RaylibHandle>),
+) -> Result<Image, Error> {
+    let mut target = rl.load_render_texture(thread, width, height)?;
+    {
+        let mut d = rl.begin_texture_mode(thread, &mut target);
+        draw(&mut d);
+    }
+    let mut image = target.texture().load_image()?;
+    // Render textures are upside down
+    image.flip_vertical();
+    Ok(image)
+}
+
+/// Save `image` as a PNG at `path`, making its folder if it doesn't exist
+pub fn save(image: &Image, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    if image.export_image(&path.to_string_lossy()) {
+        Ok(())
+    } else {
+        Err(io::Error::other("raylib couldn't export the image"))
+    }
+}
+
+/// [`render`] a picture with `draw` and [`save`] it at `path`
+pub fn take(
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+    size: (u32, u32),
+    path: &Path,
+    draw: impl FnOnce(&mut RaylibTextureMode<'_, RaylibHandle>),
+) -> Result<(), CaptureError> {
+    let image = render(rl, thread, size, draw).map_err(CaptureError::Render)?;
+    save(&image, path).map_err(|error| CaptureError::Save {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timelapse() {
+        let mut clock = WorldClock::default();
+        // A real second is a game minute
+        clock.scale = 60.0;
+        let mut timelapse = Timelapse::new(3, 10.0, &clock);
+        assert_eq!(
+            timelapse.due(&clock),
+            Some(PathBuf::from("timelapse/factory_3/00000.png")),
+            "the first frame is taken right away"
+        );
+        assert_eq!(timelapse.due(&clock), None);
+        clock.tick(9.0);
+        assert_eq!(timelapse.due(&clock), None);
+        clock.tick(1.5);
+        assert!(timelapse.due(&clock).is_some());
+
+        clock.tick(35.0);
+        assert!(timelapse.due(&clock).is_some());
+        assert_eq!(timelapse.due(&clock), None, "missed frames are skipped");
+        clock.tick(5.0);
+        assert_eq!(
+            timelapse.due(&clock),
+            Some(PathBuf::from("timelapse/factory_3/00003.png")),
+            "frames stay on the interval"
+        );
+
+        clock.set_hours(0.0);
+        assert!(
+            timelapse.due(&clock).is_some(),
+            "turning the clock back shouldn't stop the timelapse"
+        );
+    }
+}
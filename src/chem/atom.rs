@@ -1,10 +1,12 @@
 use super::element::Element;
+use super::isotope::semi_empirical_mass;
+use super::orbital::ElectronConfig;
 use super::units::{ELECTRON_MASS, NEUTRON_MASS, PROTON_MASS};
 use crate::chem::fmt::Superscript;
 
 macro_rules! isotopes {
-    ($($element:ident $neutrons:literal),* $(,)?) => {
-        [$(Atom { element: Element::$element, neutrons: $neutrons, electrons: const { Element::$element.protons().get() } }),*]
+    ($($element:ident $mass_number:literal),* $(,)?) => {
+        [$(Atom { element: Element::$element, neutrons: const { $mass_number - Element::$element.protons().get() as u16 }, electrons: const { Element::$element.protons().get() } }),*]
     };
 }
 
@@ -397,6 +399,26 @@ impl Element {
     pub const fn atom(self) -> AtomBuilder {
         AtomBuilder::new(self)
     }
+
+    /// The neutral atom of this element's most abundant isotope
+    ///
+    /// Elements without known isotopes fall back to [`AtomBuilder::stable`].
+    pub fn most_abundant_isotope(self) -> Atom {
+        self.most_abundant_known_isotope()
+            .map(|isotope| {
+                self.atom()
+                    .neutral()
+                    .neutrons(isotope.mass_number - u16::from(self.protons().get()))
+                    .build()
+            })
+            .or_else(|| {
+                PRIMORDIAL_ISOTOPES
+                    .iter()
+                    .copied()
+                    .find(|atom| atom.element == self)
+            })
+            .unwrap_or_else(|| self.atom().neutral().stable().build())
+    }
 }
 
 impl Atom {
@@ -421,19 +443,53 @@ impl Atom {
         write!(f, "{}-{}", self.element.name(), self.neutrons)
     }
 
-    /// Mass of one atom in AMU
+    /// Protons + neutrons
+    pub const fn mass_number(self) -> u16 {
+        self.element.protons().get() as u16 + self.neutrons
+    }
+
+    /// Sum of the masses of the atom's particles in AMU
+    ///
+    /// Note: this overestimates the real mass, because it ignores nuclear binding energy.
+    /// See [`Self::exact_mass`].
     pub const fn mass(self) -> f64 {
         self.element.protons().get() as f64 * PROTON_MASS
             + self.neutrons as f64 * NEUTRON_MASS
             + self.electrons as f64 * ELECTRON_MASS
     }
 
+    /// Mass of one atom in AMU, accounting for nuclear binding energy
+    ///
+    /// Uses measured masses where known, otherwise [`semi_empirical_mass`].
+    pub fn exact_mass(self) -> f64 {
+        let protons = self.element.protons().get();
+        let mass_number = self.mass_number();
+        let neutral_mass = self
+            .element
+            .isotopes()
+            .find(|isotope| isotope.mass_number == mass_number)
+            .map_or_else(
+                || semi_empirical_mass(protons, mass_number),
+                |isotope| isotope.mass,
+            );
+        neutral_mass + f64::from(i16::from(self.electrons) - i16::from(protons)) * ELECTRON_MASS
+    }
+
     /// Charge of the atom
     /// - negative = more electrons
     /// - positive = fewer electrons
     pub const fn charge(self) -> i16 {
         self.element.protons().get() as i16 - self.electrons as i16
     }
+
+    /// Ground-state electron configuration of the atom
+    ///
+    /// # Panics
+    ///
+    /// If the atom has more than [`ElectronConfig::MAX_ELECTRONS`] electrons
+    pub const fn electron_config(self) -> ElectronConfig {
+        ElectronConfig::new(self.electrons)
+    }
 }
 
 impl std::fmt::Display for Atom {
@@ -466,4 +522,20 @@ mod tests {
         assert_eq!(He.atom().charge(1).unwrap().build().to_string(), "He⁺");
         assert_eq!(He.atom().charge(2).unwrap().build().to_string(), "He²⁺");
     }
+
+    #[test]
+    fn test_exact_mass() {
+        let carbon = Element::C.most_abundant_isotope();
+        assert_eq!(carbon.mass_number(), 12);
+        assert!((carbon.exact_mass() - 12.0).abs() < 1e-9);
+        assert!(carbon.mass() > carbon.exact_mass());
+
+        let iron = Element::Fe.most_abundant_isotope();
+        assert_eq!(iron.mass_number(), 56);
+        assert!((iron.exact_mass() - 55.934_936).abs() < 1e-6);
+
+        let tin = PRIMORDIAL_ISOTOPES[0];
+        assert_eq!((tin.element, tin.mass_number()), (Element::Sn, 120));
+        assert!((tin.exact_mass() - 119.902_2).abs() < 0.02);
+    }
 }
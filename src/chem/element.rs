@@ -1,3 +1,4 @@
+use super::units::Kelvin;
 #[allow(
     clippy::enum_glob_use,
     reason = "I am importing all of them and don't want to repeat all 118 names. They don't shadow anything else here."
@@ -192,6 +193,43 @@ static ELEMENT_INFO: [(&str, &str); 118] = [
     ("Og", "Oganesson"    ),
 ];
 
+/// Standard atomic weights in AMU (equivalently, molar mass in g/mol), abridged to 5 significant figures
+///
+/// Elements without stable isotopes use the mass number of their longest-lived isotope.
+#[rustfmt::skip]
+static STANDARD_ATOMIC_WEIGHTS: [f64; 118] = [
+    1.008, 4.0026, 6.94, 9.0122, 10.81, 12.011, 14.007, 15.999, 18.998, 20.180,
+    22.990, 24.305, 26.982, 28.085, 30.974, 32.06, 35.45, 39.95, 39.098, 40.078,
+    44.956, 47.867, 50.942, 51.996, 54.938, 55.845, 58.933, 58.693, 63.546, 65.38,
+    69.723, 72.630, 74.922, 78.971, 79.904, 83.798, 85.468, 87.62, 88.906, 91.224,
+    92.906, 95.95, 98.0, 101.07, 102.91, 106.42, 107.87, 112.41, 114.82, 118.71,
+    121.76, 127.60, 126.90, 131.29, 132.91, 137.33, 138.91, 140.12, 140.91, 144.24,
+    145.0, 150.36, 151.96, 157.25, 158.93, 162.50, 164.93, 167.26, 168.93, 173.05,
+    174.97, 178.49, 180.95, 183.84, 186.21, 190.23, 192.22, 195.08, 196.97, 200.59,
+    204.38, 207.2, 208.98, 209.0, 210.0, 222.0, 223.0, 226.0, 227.0, 232.04,
+    231.04, 238.03, 237.0, 244.0, 243.0, 247.0, 247.0, 251.0, 252.0, 257.0,
+    258.0, 259.0, 266.0, 267.0, 268.0, 269.0, 270.0, 269.0, 278.0, 281.0,
+    282.0, 285.0, 286.0, 289.0, 290.0, 293.0, 294.0, 294.0,
+];
+
+/// Pauling electronegativities, or 0 where there isn't one (the lighter noble gases, and elements
+/// too short-lived to measure)
+#[rustfmt::skip]
+static ELECTRONEGATIVITIES: [f64; 118] = [
+    2.20, 0.0, 0.98, 1.57, 2.04, 2.55, 3.04, 3.44, 3.98, 0.0,
+    0.93, 1.31, 1.61, 1.90, 2.19, 2.58, 3.16, 0.0, 0.82, 1.00,
+    1.36, 1.54, 1.63, 1.66, 1.55, 1.83, 1.88, 1.91, 1.90, 1.65,
+    1.81, 2.01, 2.18, 2.55, 2.96, 3.00, 0.82, 0.95, 1.22, 1.33,
+    1.6, 2.16, 1.9, 2.2, 2.28, 2.20, 1.93, 1.69, 1.78, 1.96,
+    2.05, 2.1, 2.66, 2.6, 0.79, 0.89, 1.10, 1.12, 1.13, 1.14,
+    1.13, 1.17, 1.2, 1.2, 1.1, 1.22, 1.23, 1.24, 1.25, 1.1,
+    1.27, 1.3, 1.5, 2.36, 1.9, 2.2, 2.20, 2.28, 2.54, 2.00,
+    1.62, 1.87, 2.02, 2.0, 2.2, 2.2, 0.79, 0.9, 1.1, 1.3,
+    1.5, 1.38, 1.36, 1.28, 1.13, 1.28, 1.3, 1.3, 1.3, 1.3,
+    1.3, 1.3, 1.3, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+    0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+];
+
 impl Element {
     const fn info(self) -> &'static (&'static str, &'static str) {
         // SAFETY: positive NonZero guaranteed not to underflow
@@ -207,11 +245,41 @@ impl Element {
         self.info().0
     }
 
+    /// Look up the element represented by `symbol` (case sensitive)
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        ELEMENT_LIST
+            .iter()
+            .copied()
+            .find(|element| element.symbol() == symbol)
+    }
+
     /// The common name of this element
     pub const fn name(self) -> &'static str {
         self.info().1
     }
 
+    /// The name of this element in the player's language, falling back to [`Self::name`]
+    pub fn local_name(self) -> String {
+        crate::locale::tr_or(&format!("element.{}", self.symbol()), self.name())
+    }
+
+    /// The average mass of the element's atoms as found in nature, in AMU
+    ///
+    /// Also the molar mass in grams per mole.
+    pub const fn standard_atomic_weight(self) -> f64 {
+        // SAFETY: positive NonZero guaranteed not to underflow
+        STANDARD_ATOMIC_WEIGHTS[unsafe { self.protons().get().unchecked_sub(1) } as usize]
+    }
+
+    /// How strongly the element's atoms pull on the electrons they share, on the Pauling scale
+    ///
+    /// [`None`] for elements that don't form bonds to measure it from, like neon.
+    pub const fn electronegativity(self) -> Option<f64> {
+        // SAFETY: positive NonZero guaranteed not to underflow
+        let value = ELECTRONEGATIVITIES[unsafe { self.protons().get().unchecked_sub(1) } as usize];
+        if value > 0.0 { Some(value) } else { None }
+    }
+
     /// The number of protons the element has
     ///
     /// A typical atom will also have this many neutrons and electrons
@@ -236,6 +304,60 @@ impl Element {
         matches!(self, H | N | O | F | Cl | Br | I)
     }
 
+    /// Where the element melts and boils at one atmosphere, in kelvins, for common elements
+    ///
+    /// Elements that sublime have the same temperature for both.
+    const fn phase_points(self) -> Option<(f64, f64)> {
+        Some(match self {
+            H => (14.01, 20.28),
+            C => (3915.0, 3915.0),
+            N => (63.15, 77.36),
+            O => (54.36, 90.19),
+            F => (53.48, 85.03),
+            Ne => (24.56, 27.07),
+            Na => (370.9, 1156.0),
+            Mg => (923.0, 1363.0),
+            Al => (933.5, 2792.0),
+            Si => (1687.0, 3538.0),
+            P => (317.3, 553.7),
+            S => (388.4, 717.8),
+            Cl => (171.6, 239.1),
+            Ar => (83.8, 87.3),
+            K => (336.7, 1032.0),
+            Ca => (1115.0, 1757.0),
+            Ti => (1941.0, 3560.0),
+            Fe => (1811.0, 3134.0),
+            Ni => (1728.0, 3186.0),
+            Cu => (1357.8, 2835.0),
+            Zn => (692.7, 1180.0),
+            Br => (265.8, 332.0),
+            Ag => (1234.9, 2435.0),
+            Sn => (505.1, 2875.0),
+            I => (386.9, 457.4),
+            Au => (1337.3, 3129.0),
+            Hg => (234.3, 629.9),
+            Pb => (600.6, 2022.0),
+            U => (1405.3, 4404.0),
+            _ => return None,
+        })
+    }
+
+    /// The temperature the element melts at, if it's known
+    pub const fn melting_point(self) -> Option<Kelvin> {
+        match self.phase_points() {
+            Some((melting, _)) => Kelvin::new(melting),
+            None => None,
+        }
+    }
+
+    /// The temperature the element boils at, if it's known
+    pub const fn boiling_point(self) -> Option<Kelvin> {
+        match self.phase_points() {
+            Some((_, boiling)) => Kelvin::new(boiling),
+            None => None,
+        }
+    }
+
     /// Elements that don't want to form compounds
     pub const fn is_noble_gas(self) -> bool {
         matches!(self, He | Ne | Ar | Kr | Xe | Rn | Og)
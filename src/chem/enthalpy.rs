@@ -0,0 +1,264 @@
+//! How much heat reactions give off or take in
+//!
+//! A reaction's enthalpy change is what its products took to form less what its reactants did:
+//! ΔH = ΣΔHf°(products) − ΣΔHf°(reactants), negative for reactions that give off heat. Standard
+//! enthalpies of formation come from a table of common compounds where they're known, and are
+//! otherwise estimated from average bond energies: the energy to pull a molecule apart into
+//! gaseous atoms, taken away from the energy it took to make those atoms from their elements.
+//!
+//! Estimates only work for molecules held together by covalent bonds, so they're not made for
+//! anything with a metal in it or a charge. Bonds are guessed at from each atom's valence, with
+//! hydrogens and halogens on the heavy atoms with the most bonds left, and then the heavy atoms
+//! bonded to each other, which gets simple molecules right and others roughly so.
+
+use super::{
+    element::Element::{self, Br, C, Cl, F, H, I, N, O, P, S, Si},
+    formula::Formula,
+    reaction::BalancedReaction,
+    units::Joule,
+};
+use std::{collections::BTreeMap, sync::LazyLock};
+
+/// Standard enthalpies of formation at 25 °C in kJ/mol, of each compound as it's usually found
+static FORMATION: LazyLock<Vec<(Formula, f64)>> = LazyLock::new(|| {
+    [
+        ("H2O", -285.8),
+        ("H2O2", -187.8),
+        ("CO", -110.5),
+        ("CO2", -393.5),
+        ("CH4", -74.8),
+        ("C2H6", -84.7),
+        ("C3H8", -103.8),
+        ("C2H6O", -277.7),
+        ("NH3", -45.9),
+        ("NO2", 33.2),
+        ("HCl", -92.3),
+        ("SO2", -296.8),
+        ("H2SO4", -814.0),
+        ("NaOH", -425.6),
+        ("NaCl", -411.2),
+        ("KCl", -436.5),
+        ("KNO3", -494.6),
+        ("CaO", -635.1),
+        ("Ca(OH)2", -985.2),
+        ("CaCO3", -1206.9),
+        ("CaCl2", -795.4),
+        ("Na2CO3", -1130.7),
+        ("Fe2O3", -824.2),
+        ("Al2O3", -1675.7),
+        ("CuO", -157.3),
+        ("SiO2", -910.7),
+    ]
+    .into_iter()
+    .map(|(formula, kj)| (formula.parse().unwrap(), kj))
+    .collect()
+});
+
+/// kJ/mol to turn each element into gaseous atoms, for the elements estimates can be made with
+const fn atomization(element: Element) -> Option<f64> {
+    Some(match element {
+        H => 218.0,
+        C => 716.7,
+        N => 472.7,
+        O => 249.2,
+        F => 79.4,
+        Si => 450.0,
+        P => 314.6,
+        S => 277.2,
+        Cl => 121.3,
+        Br => 111.9,
+        I => 106.8,
+        _ => return None,
+    })
+}
+
+/// Average kJ/mol to break a bond of `order` between `a` and `b`, in either order
+fn bond_energy(a: Element, b: Element, order: u8) -> f64 {
+    let (a, b) = if a <= b { (a, b) } else { (b, a) };
+    let known = match (a, b, order) {
+        (H, H, 1) => 436.0,
+        (H, C, 1) => 413.0,
+        (H, N, 1) => 391.0,
+        (H, O, 1) => 463.0,
+        (H, F, 1) => 567.0,
+        (H, S, 1) => 339.0,
+        (H, Cl, 1) => 431.0,
+        (C, C, 1) => 348.0,
+        (C, C, 2) => 614.0,
+        (C, C, 3) => 839.0,
+        (C, N, 1) => 293.0,
+        (C, N, 2) => 615.0,
+        (C, N, 3) => 891.0,
+        (C, O, 1) => 358.0,
+        (C, O, 2) => 799.0,
+        (C, O, 3) => 1072.0,
+        (C, Cl, 1) => 328.0,
+        (N, N, 1) => 163.0,
+        (N, N, 2) => 418.0,
+        (N, N, 3) => 941.0,
+        (N, O, 1) => 201.0,
+        (N, O, 2) => 607.0,
+        (O, O, 1) => 146.0,
+        (O, O, 2) => 495.0,
+        (O, S, 2) => 523.0,
+        (F, F, 1) => 155.0,
+        (Cl, Cl, 1) => 242.0,
+        _ => 0.0,
+    };
+    if known > 0.0 {
+        return known;
+    }
+    // The mean of the two elements' single bonds to themselves, for every bond in the order
+    let single = |element| match element {
+        H => 436.0,
+        C => 348.0,
+        N => 163.0,
+        O => 146.0,
+        F => 155.0,
+        Si => 226.0,
+        P => 201.0,
+        S => 266.0,
+        Cl => 242.0,
+        Br => 193.0,
+        I => 151.0,
+        _ => 0.0,
+    };
+    f64::sqrt(single(a) * single(b)) * f64::from(order)
+}
+
+/// kJ/mol to break every bond in `formula`, guessing at what's bonded to what
+///
+/// Returns [`None`] if it isn't a neutral molecule of elements that bond covalently.
+fn total_bond_energy(formula: &Formula) -> Option<f64> {
+    if formula.charge != 0 {
+        return None;
+    }
+    // Each atom, and how many more bonds it can make
+    let mut heavy = Vec::<(Element, u8)>::new();
+    let mut terminal = Vec::<Element>::new();
+    for (element, count) in formula.element_counts() {
+        atomization(element)?;
+        let valence = element
+            .most_abundant_isotope()
+            .electron_config()
+            .available();
+        for _ in 0..count {
+            match valence {
+                0 => return None,
+                1 => terminal.push(element),
+                _ => heavy.push((element, valence)),
+            }
+        }
+    }
+    let mut energy = 0.0;
+    // Hydrogens and halogens on the heavy atoms with the most bonds left
+    if heavy.is_empty() {
+        // Only hydrogens and halogens, like H₂ or HCl, which pair off
+        for pair in terminal.chunks_exact(2) {
+            energy += bond_energy(pair[0], pair[1], 1);
+        }
+    } else {
+        for element in terminal {
+            let (center, free) = heavy.iter_mut().max_by_key(|(_, free)| *free)?;
+            *free = free.checked_sub(1)?;
+            energy += bond_energy(element, *center, 1);
+        }
+    }
+    // Then the heavy atoms to each other, a bond at a time, adding up to multiple bonds
+    let mut orders = BTreeMap::<(usize, usize), u8>::new();
+    loop {
+        let mut free = (heavy.iter().enumerate())
+            .filter(|(_, (_, free))| *free > 0)
+            .collect::<Vec<_>>();
+        free.sort_by_key(|&(idx, &(_, free))| (std::cmp::Reverse(free), idx));
+        let [(a, _), (b, _), ..] = free[..] else {
+            break;
+        };
+        heavy[a].1 -= 1;
+        heavy[b].1 -= 1;
+        *orders.entry((a.min(b), a.max(b))).or_default() += 1;
+    }
+    for ((a, b), order) in orders {
+        energy += bond_energy(heavy[a].0, heavy[b].0, order);
+    }
+    Some(energy)
+}
+
+/// The standard enthalpy of formation of `formula` in kJ/mol, from the table if it's there and
+/// estimated from bond energies if not
+///
+/// Elements as they're usually found, like O₂ and Fe, take nothing to form.
+pub fn formation(formula: &Formula) -> Option<f64> {
+    let counts = formula.element_counts();
+    if let [(&element, &count)] = counts.iter().collect::<Vec<_>>()[..]
+        && formula.charge == 0
+        && count == if element.is_diatomic() { 2 } else { 1 }
+    {
+        return Some(0.0);
+    }
+    if let Some(&(_, kj)) = FORMATION.iter().find(|(known, _)| known == formula) {
+        return Some(kj);
+    }
+    let atoms = (counts.iter())
+        .map(|(&element, &count)| Some(atomization(element)? * f64::from(count)))
+        .sum::<Option<f64>>()?;
+    Some(atoms - total_bond_energy(formula)?)
+}
+
+impl BalancedReaction {
+    /// Heat absorbed by running the reaction once with each coefficient in moles, which is
+    /// negative if it gives off heat instead
+    ///
+    /// Returns [`None`] if the enthalpy of formation of anything in it isn't known and can't be
+    /// estimated.
+    #[must_use]
+    pub fn enthalpy(&self) -> Option<Joule> {
+        let side = |side: &[(std::num::NonZeroU32, Formula)]| {
+            (side.iter())
+                .map(|(n, formula)| Some(f64::from(n.get()) * formation(formula)?))
+                .sum::<Option<f64>>()
+        };
+        let kj = side(self.products())? - side(self.reactants())?;
+        Joule::new(kj * 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chem::reaction::Reaction;
+
+    fn enthalpy(reaction: &str) -> Option<f64> {
+        let reaction = reaction.parse::<Reaction>().unwrap().balance().unwrap();
+        reaction.enthalpy().map(|joules| joules.get() / 1000.0)
+    }
+
+    #[test]
+    fn test_enthalpy() {
+        // Burning methane, from the table
+        let combustion = enthalpy("CH4 + O2 -> CO2 + H2O").unwrap();
+        assert!((combustion + 890.3).abs() < 0.1);
+        // Taking in heat to break down limestone
+        assert!(enthalpy("CaCO3 -> CaO + CO2").unwrap() > 0.0);
+        assert_eq!(
+            enthalpy("Cu + Si -> Cu2Si"),
+            None,
+            "metals can't be estimated"
+        );
+
+        // Estimated from bond energies, within about 15% of what's measured
+        for (formula, measured) in [("CH4", -74.8), ("C3H8", -103.8), ("NH3", -45.9)] {
+            let bonds = total_bond_energy(&formula.parse().unwrap()).unwrap();
+            let atoms = (formula.parse::<Formula>().unwrap().element_counts().iter())
+                .map(|(&element, &count)| atomization(element).unwrap() * f64::from(count))
+                .sum::<f64>();
+            let estimate = atoms - bonds;
+            assert!(
+                (estimate - measured).abs() < 0.15 * measured.abs() + 15.0,
+                "{formula}: estimated {estimate} kJ/mol but measured {measured}"
+            );
+        }
+        assert_eq!(formation(&"O2".parse().unwrap()), Some(0.0));
+        assert!(formation(&"C4H10".parse().unwrap()).is_some());
+    }
+}
@@ -158,6 +158,20 @@ pub trait SubSupScript: Sized {
     /// Returns [`None`] if the character has no subscript version
     #[must_use]
     fn to_subscript(self) -> Option<Self::Output>;
+
+    /// Convert a superscript unicode character back to its normal equivalent
+    ///
+    /// Returns [`None`] if the character is not a superscript
+    #[must_use]
+    #[allow(clippy::wrong_self_convention, reason = "inverse of `to_superscript`")]
+    fn from_superscript(self) -> Option<Self::Output>;
+
+    /// Convert a subscript unicode character back to its normal equivalent
+    ///
+    /// Returns [`None`] if the character is not a subscript
+    #[must_use]
+    #[allow(clippy::wrong_self_convention, reason = "inverse of `to_subscript`")]
+    fn from_subscript(self) -> Option<Self::Output>;
 }
 
 /// Only works for `0`-`9`, `+`, `-`, `=`, `(`, and `)`
@@ -207,6 +221,50 @@ impl const SubSupScript for char {
             _ => None,
         }
     }
+
+    #[inline]
+    fn from_superscript(self) -> Option<Self::Output> {
+        match self {
+            SUP_0 => Some('0'),
+            SUP_1 => Some('1'),
+            SUP_2 => Some('2'),
+            SUP_3 => Some('3'),
+            SUP_4 => Some('4'),
+            SUP_5 => Some('5'),
+            SUP_6 => Some('6'),
+            SUP_7 => Some('7'),
+            SUP_8 => Some('8'),
+            SUP_9 => Some('9'),
+            SUP_PLUS => Some('+'),
+            SUP_HYPHEN => Some('-'),
+            SUP_EQUAL => Some('='),
+            SUP_LPAREN => Some('('),
+            SUP_RPAREN => Some(')'),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn from_subscript(self) -> Option<Self::Output> {
+        match self {
+            SUB_0 => Some('0'),
+            SUB_1 => Some('1'),
+            SUB_2 => Some('2'),
+            SUB_3 => Some('3'),
+            SUB_4 => Some('4'),
+            SUB_5 => Some('5'),
+            SUB_6 => Some('6'),
+            SUB_7 => Some('7'),
+            SUB_8 => Some('8'),
+            SUB_9 => Some('9'),
+            SUB_PLUS => Some('+'),
+            SUB_HYPHEN => Some('-'),
+            SUB_EQUAL => Some('='),
+            SUB_LPAREN => Some('('),
+            SUB_RPAREN => Some(')'),
+            _ => None,
+        }
+    }
 }
 
 pub trait DisplaySuperscript {
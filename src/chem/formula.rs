@@ -0,0 +1,456 @@
+//! Parsing chemical formulas like `Fe2(SO4)3`, `CuSO4·5H2O`, or `SO4^2-`
+//!
+//! Grammar:
+//! - Elements are written by symbol (case sensitive)
+//! - Counts follow elements and groups, as ASCII digits or subscripts (`H2O`, `H₂O`)
+//! - Groups are wrapped in `()` or `[]` and may be nested
+//! - Hydrates and adducts are separated by `·`, `•`, `.`, or `*`, and may have a leading
+//!   coefficient (`5H2O`)
+//! - A trailing charge is written as `^2-`, as superscripts (`²⁻`), or as repeated signs (`Fe++`).
+//!   Note that `Fe3+` is read as three iron atoms with a charge of 1+; write `Fe^3+` instead.
+
+use super::{
+    element::Element,
+    fmt::{SubSupScript, Superscript},
+    molecule::Compound,
+};
+use std::{collections::BTreeMap, num::NonZeroU8, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseFormulaError {
+    /// The formula was empty
+    Empty,
+    /// A symbol that does not name any element
+    UnknownElement(String),
+    /// A character that doesn't belong at this position
+    UnexpectedChar { ch: char, at: usize },
+    /// A group was opened but never closed
+    UnclosedGroup { at: usize },
+    /// A group with nothing in it, like `()`
+    EmptyGroup { at: usize },
+    /// A count that is zero or too large
+    InvalidCount { at: usize },
+    /// A charge that the atom cannot have, like `H^2+`
+    InvalidCharge,
+    /// A charge was given to something other than a single atom
+    ///
+    /// Only returned when parsing a [`Compound`], which has nowhere to store polyatomic charges.
+    PolyatomicCharge,
+}
+
+impl std::fmt::Display for ParseFormulaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => f.write_str("formula is empty"),
+            Self::UnknownElement(symbol) => write!(f, "unknown element `{symbol}`"),
+            Self::UnexpectedChar { ch, at } => write!(f, "unexpected `{ch}` at {at}"),
+            Self::UnclosedGroup { at } => write!(f, "group opened at {at} is never closed"),
+            Self::EmptyGroup { at } => write!(f, "empty group at {at}"),
+            Self::InvalidCount { at } => write!(f, "invalid count at {at}"),
+            Self::InvalidCharge => f.write_str("atom cannot have that charge"),
+            Self::PolyatomicCharge => f.write_str("compounds cannot hold a polyatomic charge"),
+        }
+    }
+}
+
+impl std::error::Error for ParseFormulaError {}
+
+/// A parsed chemical formula
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Formula {
+    /// Each dot-separated part and its coefficient, e.g. `CuSO4` ×1 and `H2O` ×5
+    pub parts: Vec<(NonZeroU8, Compound)>,
+    /// Net charge of the whole formula
+    pub charge: i16,
+}
+
+impl Formula {
+    /// Combine all parts into a single [`Compound`], ignoring charge
+    #[must_use]
+    pub fn compound(&self) -> Compound {
+        match self.parts.as_slice() {
+            [(n, compound)] if n.get() == 1 => compound.clone(),
+            parts => {
+                let mut tree = BTreeMap::new();
+                for (n, compound) in parts {
+                    tree.entry(compound.clone())
+                        .and_modify(|count: &mut NonZeroU8| *count = count.saturating_add(n.get()))
+                        .or_insert(*n);
+                }
+                Compound::Tree(tree)
+            }
+        }
+    }
+
+    /// Total number of atoms of each element
+    ///
+    /// Counts stop at [`u32::MAX`], which parsed formulas never reach.
+    #[must_use]
+    pub fn element_counts(&self) -> BTreeMap<Element, u32> {
+        element_counts(&self.parts)
+    }
+
+    /// Mass of one mole of the formula in grams, using standard atomic weights
+    #[must_use]
+    pub fn molar_mass(&self) -> f64 {
+        self.element_counts()
+            .into_iter()
+            .map(|(element, n)| element.standard_atomic_weight() * f64::from(n))
+            .sum()
+    }
+}
+
+impl FromStr for Formula {
+    type Err = ParseFormulaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { src: s, pos: 0 };
+        parser.skip_whitespace();
+        if parser.peek().is_none() {
+            return Err(ParseFormulaError::Empty);
+        }
+
+        let mut parts = Vec::new();
+        loop {
+            let at = parser.pos;
+            parts.push(parser.part()?);
+            // Saturating, so reaching the limit means the real total is at least that
+            if element_counts(&parts)
+                .values()
+                .any(|&count| count == u32::MAX)
+            {
+                return Err(ParseFormulaError::InvalidCount { at });
+            }
+            if !matches!(parser.peek(), Some('·' | '•' | '.' | '*')) {
+                break;
+            }
+            parser.bump();
+        }
+        let charge = parser.charge()?;
+        parser.skip_whitespace();
+
+        match parser.peek() {
+            None => Ok(Self { parts, charge }),
+            Some(ch) => Err(ParseFormulaError::UnexpectedChar { ch, at: parser.pos }),
+        }
+    }
+}
+
+/// The formula of a single, uncharged `compound`
+impl From<Compound> for Formula {
+    fn from(compound: Compound) -> Self {
+        Self {
+            parts: vec![(NonZeroU8::MIN, compound)],
+            charge: 0,
+        }
+    }
+}
+
+impl std::fmt::Display for Formula {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (n, compound)) in self.parts.iter().enumerate() {
+            if i > 0 {
+                f.write_str("·")?;
+            }
+            if n.get() > 1 {
+                write!(f, "{n}")?;
+            }
+            write!(f, "{compound}")?;
+        }
+        let sign = Superscript(if self.charge.is_negative() { '-' } else { '+' });
+        match self.charge.unsigned_abs() {
+            0 => Ok(()),
+            1 => write!(f, "{sign}"),
+            mag @ 2.. => write!(f, "{}{sign}", Superscript(mag)),
+        }
+    }
+}
+
+/// Total number of atoms of each element in `parts`, stopping at [`u32::MAX`]
+fn element_counts(parts: &[(NonZeroU8, Compound)]) -> BTreeMap<Element, u32> {
+    let mut counts = BTreeMap::<Element, u32>::new();
+    for (n, compound) in parts {
+        for (element, count) in compound.element_counts() {
+            let total = counts.entry(element).or_default();
+            *total = total.saturating_add(count.saturating_mul(n.get().into()));
+        }
+    }
+    counts
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    /// Read a run of digits, with `normalize` converting each character to an ASCII digit
+    fn digits(&mut self, normalize: impl Fn(char) -> Option<char>) -> Option<u32> {
+        let mut value = None::<u32>;
+        while let Some(digit) = self
+            .peek()
+            .and_then(&normalize)
+            .and_then(|ch| ch.to_digit(10))
+        {
+            self.bump();
+            value = Some(value.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        }
+        value
+    }
+
+    /// An optional count; plain or subscript digits
+    fn count(&mut self) -> Result<NonZeroU8, ParseFormulaError> {
+        let at = self.pos;
+        let count = self
+            .digits(|ch| Some(ch).filter(char::is_ascii_digit))
+            .or_else(|| self.digits(SubSupScript::from_subscript));
+        match count {
+            None => Ok(NonZeroU8::MIN),
+            Some(n) => u8::try_from(n)
+                .ok()
+                .and_then(NonZeroU8::new)
+                .ok_or(ParseFormulaError::InvalidCount { at }),
+        }
+    }
+
+    /// A coefficient followed by a sequence of groups
+    fn part(&mut self) -> Result<(NonZeroU8, Compound), ParseFormulaError> {
+        self.skip_whitespace();
+        let coefficient = self.count()?;
+        let at = self.pos;
+        let compound = self.sequence(None)?;
+        compound
+            .map(|compound| (coefficient, compound))
+            .ok_or(match self.peek() {
+                Some(ch) => ParseFormulaError::UnexpectedChar { ch, at },
+                None => ParseFormulaError::Empty,
+            })
+    }
+
+    /// Elements and groups until `close` (or until something else if `close` is [`None`])
+    fn sequence(
+        &mut self,
+        close: Option<(char, usize)>,
+    ) -> Result<Option<Compound>, ParseFormulaError> {
+        let mut tree = BTreeMap::<Compound, NonZeroU8>::new();
+        loop {
+            let item = match self.peek() {
+                Some(open @ ('(' | '[')) => {
+                    let at = self.pos;
+                    self.bump();
+                    let close = if open == '(' { ')' } else { ']' };
+                    self.sequence(Some((close, at)))?
+                        .ok_or(ParseFormulaError::EmptyGroup { at })?
+                }
+                Some(ch) if ch.is_ascii_uppercase() => self.element()?,
+                Some(ch) if close.is_some_and(|(close, _)| close == ch) => {
+                    self.bump();
+                    break;
+                }
+                _ => {
+                    if let Some((_, at)) = close {
+                        return Err(ParseFormulaError::UnclosedGroup { at });
+                    }
+                    break;
+                }
+            };
+            let at = self.pos;
+            let count = self.count()?;
+            match tree.get_mut(&item) {
+                Some(existing) => {
+                    *existing = existing
+                        .checked_add(count.get())
+                        .ok_or(ParseFormulaError::InvalidCount { at })?;
+                }
+                None => {
+                    tree.insert(item, count);
+                }
+            }
+        }
+
+        let mut entries = tree.iter();
+        Ok(match (entries.next(), entries.next()) {
+            (None, _) => None,
+            (Some((Compound::Atom(atom), n)), None) if n.get() == 1 => Some(Compound::Atom(*atom)),
+            _ => Some(Compound::Tree(tree)),
+        })
+    }
+
+    fn element(&mut self) -> Result<Compound, ParseFormulaError> {
+        let start = self.pos;
+        self.bump();
+        if self.peek().is_some_and(|ch| ch.is_ascii_lowercase()) {
+            self.bump();
+        }
+        let symbol = &self.src[start..self.pos];
+        Element::from_symbol(symbol)
+            .map(|element| Compound::Atom(element.most_abundant_isotope()))
+            .ok_or_else(|| ParseFormulaError::UnknownElement(symbol.to_owned()))
+    }
+
+    fn charge(&mut self) -> Result<i16, ParseFormulaError> {
+        let caret = self.peek() == Some('^');
+        if caret {
+            self.bump();
+        }
+        let at = self.pos;
+
+        // Superscript charge
+        if !caret {
+            let magnitude = self.digits(SubSupScript::from_superscript);
+            let sign = match self.peek().and_then(SubSupScript::from_superscript) {
+                Some('+') => 1,
+                Some('-') => -1,
+                _ if magnitude.is_some() => {
+                    return Err(ParseFormulaError::InvalidCount { at });
+                }
+                _ => 0,
+            };
+            if sign != 0 {
+                self.bump();
+                let magnitude = magnitude.unwrap_or(1);
+                return i16::try_from(magnitude)
+                    .map(|magnitude| sign * magnitude)
+                    .map_err(|_| ParseFormulaError::InvalidCount { at });
+            }
+        }
+
+        let magnitude = self.digits(|ch| Some(ch).filter(char::is_ascii_digit));
+        let mut charge: i16 = 0;
+        while let Some(sign @ ('+' | '-')) = self.peek() {
+            self.bump();
+            charge += if sign == '+' { 1 } else { -1 };
+            if magnitude.is_some() {
+                break;
+            }
+        }
+        match (magnitude, charge) {
+            (None, 0) if caret => match self.peek() {
+                Some(ch) => Err(ParseFormulaError::UnexpectedChar { ch, at }),
+                None => Err(ParseFormulaError::InvalidCount { at }),
+            },
+            (None, _) => Ok(charge),
+            (Some(_), 0) => Err(ParseFormulaError::InvalidCount { at }),
+            (Some(magnitude), _) => i16::try_from(magnitude)
+                .map(|magnitude| charge * magnitude)
+                .map_err(|_| ParseFormulaError::InvalidCount { at }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Element::{Cu, Fe, H, N, O, S};
+
+    fn counts(formula: &str) -> Vec<(Element, u32)> {
+        formula
+            .parse::<Formula>()
+            .unwrap()
+            .element_counts()
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_formula() {
+        assert_eq!(counts("H2O"), [(H, 2), (O, 1)]);
+        assert_eq!(counts("H₂O"), [(H, 2), (O, 1)]);
+        assert_eq!(counts("Fe2(SO4)3"), [(O, 12), (S, 3), (Fe, 2)]);
+        assert_eq!(counts("CH3COOH"), [(H, 4), (Element::C, 2), (O, 2)]);
+        assert_eq!(counts("CuSO4·5H2O"), [(H, 10), (O, 9), (S, 1), (Cu, 1)]);
+        assert_eq!(
+            counts("[Cu(NH3)4]SO4"),
+            [(H, 12), (N, 4), (O, 4), (S, 1), (Cu, 1)]
+        );
+    }
+
+    #[test]
+    fn test_molar_mass() {
+        let molar_mass = |s: &str| s.parse::<Formula>().unwrap().molar_mass();
+        assert!((molar_mass("H2O") - 18.015).abs() < 1e-3);
+        assert!((molar_mass("Fe2(SO4)3") - 399.86).abs() < 1e-2);
+        assert!((molar_mass("CuSO4·5H2O") - 249.68).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_parse_charge() {
+        let charge = |s: &str| s.parse::<Formula>().unwrap().charge;
+        assert_eq!(charge("SO4^2-"), -2);
+        assert_eq!(charge("SO₄²⁻"), -2);
+        assert_eq!(charge("NH4+"), 1);
+        assert_eq!(charge("Fe^3+"), 3);
+        assert_eq!(charge("Fe++"), 2);
+        assert_eq!(charge("Cl-"), -1);
+        assert_eq!(charge("Fe"), 0);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!("".parse::<Formula>(), Err(ParseFormulaError::Empty));
+        assert_eq!(
+            "Xx2".parse::<Formula>(),
+            Err(ParseFormulaError::UnknownElement("Xx".to_owned()))
+        );
+        assert_eq!(
+            "Fe2(SO4".parse::<Formula>(),
+            Err(ParseFormulaError::UnclosedGroup { at: 3 })
+        );
+        assert_eq!(
+            "H0".parse::<Formula>(),
+            Err(ParseFormulaError::InvalidCount { at: 1 })
+        );
+        assert_eq!(
+            "((((H255)255)255)255)255".parse::<Formula>(),
+            Err(ParseFormulaError::InvalidCount { at: 0 }),
+            "more atoms than can be counted"
+        );
+        assert_eq!(
+            "((((H255)255)255)255).((((H255)255)255)255)".parse::<Formula>(),
+            Err(ParseFormulaError::InvalidCount { at: 22 }),
+            "only too many once added up"
+        );
+        assert_eq!(
+            "((((H255)255)255)255)"
+                .parse::<Compound>()
+                .map(|c| c.element_counts()[&H]),
+            Ok(255u32.pow(4))
+        );
+        assert_eq!(
+            "SO4^2-".parse::<Compound>(),
+            Err(ParseFormulaError::PolyatomicCharge)
+        );
+        assert_eq!(
+            "H^2+".parse::<Compound>(),
+            Err(ParseFormulaError::InvalidCharge)
+        );
+    }
+
+    #[test]
+    fn test_display_formula() {
+        let display = |s: &str| s.parse::<Formula>().unwrap().to_string();
+        assert_eq!(display("H2O"), "H₂O");
+        assert_eq!(display("CH4"), "CH₄");
+        assert_eq!(display("Fe2(SO4)3"), "Fe₂(O₄S)₃");
+        assert_eq!(display("CuSO4·5H2O"), "CuO₄S·5H₂O");
+        assert_eq!(display("SO4^2-"), "O₄S²⁻");
+        assert_eq!(display("NH4+"), "H₄N⁺");
+        assert_eq!("Fe^3+".parse::<Compound>().unwrap().to_string(), "Fe³⁺");
+    }
+}
@@ -0,0 +1,199 @@
+use super::{
+    element::Element,
+    units::{HYDROGEN_ATOM_MASS, MEV_PER_AMU, NEUTRON_MASS},
+};
+
+/// A naturally occurring isotope
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Isotope {
+    pub element: Element,
+    /// Protons + neutrons
+    pub mass_number: u16,
+    /// Mass of the neutral atom in AMU (including electrons and binding energy)
+    pub mass: f64,
+    /// Fraction of the element's naturally occurring atoms that are this isotope
+    pub abundance: f64,
+}
+
+macro_rules! isotopes {
+    ($($element:ident $mass_number:literal $mass:literal $abundance:literal),* $(,)?) => {
+        [$(Isotope { element: Element::$element, mass_number: $mass_number, mass: $mass, abundance: $abundance }),*]
+    };
+}
+
+/// Common isotopes with measured masses and natural abundances, sorted by element
+///
+/// Isotopes not listed here fall back to [`semi_empirical_mass`].
+#[rustfmt::skip]
+pub static ISOTOPES: [Isotope; 90] = isotopes![
+    H  1   1.007_825_032_23  0.999_885,
+    H  2   2.014_101_778_12  0.000_115,
+    He 3   3.016_029_320_1   0.000_001_34,
+    He 4   4.002_603_254_13  0.999_998_66,
+    Li 6   6.015_122_887_4   0.0759,
+    Li 7   7.016_003_436_6   0.9241,
+    Be 9   9.012_183_065     1.0,
+    B  10  10.012_936_95     0.199,
+    B  11  11.009_305_36     0.801,
+    C  12  12.0              0.9893,
+    C  13  13.003_354_835_07 0.0107,
+    N  14  14.003_074_004_43 0.996_36,
+    N  15  15.000_108_898_88 0.003_64,
+    O  16  15.994_914_619_57 0.997_57,
+    O  17  16.999_131_756_5  0.000_38,
+    O  18  17.999_159_612_86 0.002_05,
+    F  19  18.998_403_162_73 1.0,
+    Ne 20  19.992_440_176_2  0.9048,
+    Ne 21  20.993_846_685    0.0027,
+    Ne 22  21.991_385_114    0.0925,
+    Na 23  22.989_769_282    1.0,
+    Mg 24  23.985_041_697    0.7899,
+    Mg 25  24.985_836_976    0.1000,
+    Mg 26  25.982_592_968    0.1101,
+    Al 27  26.981_538_53     1.0,
+    Si 28  27.976_926_534_65 0.922_23,
+    Si 29  28.976_494_664_9  0.046_85,
+    Si 30  29.973_770_136    0.030_92,
+    P  31  30.973_761_998_42 1.0,
+    S  32  31.972_071_174_4  0.9499,
+    S  33  32.971_458_909_8  0.0075,
+    S  34  33.967_867_004    0.0425,
+    S  36  35.967_080_71     0.0001,
+    Cl 35  34.968_852_682    0.7576,
+    Cl 37  36.965_902_602    0.2424,
+    Ar 36  35.967_545_105    0.003_336,
+    Ar 38  37.962_732_11     0.000_629,
+    Ar 40  39.962_383_123_7  0.996_035,
+    K  39  38.963_706_486_4  0.932_581,
+    K  40  39.963_998_166    0.000_117,
+    K  41  40.961_825_257_9  0.067_302,
+    Ca 40  39.962_590_863    0.969_41,
+    Ca 42  41.958_617_83     0.006_47,
+    Ca 43  42.958_766_44     0.001_35,
+    Ca 44  43.955_481_56     0.020_86,
+    Ca 46  45.953_689        0.000_04,
+    Ca 48  47.952_522_76     0.001_87,
+    Sc 45  44.955_908_28     1.0,
+    Ti 46  45.952_627_72     0.0825,
+    Ti 47  46.951_758_79     0.0744,
+    Ti 48  47.947_941_98     0.7372,
+    Ti 49  48.947_865_68     0.0541,
+    Ti 50  49.944_786_89     0.0518,
+    V  50  49.947_156_01     0.0025,
+    V  51  50.943_957_04     0.9975,
+    Cr 50  49.946_041_83     0.043_45,
+    Cr 52  51.940_506_23     0.837_89,
+    Cr 53  52.940_648_15     0.095_01,
+    Cr 54  53.938_879_16     0.023_65,
+    Mn 55  54.938_043_91     1.0,
+    Fe 54  53.939_608_99     0.058_45,
+    Fe 56  55.934_936_33     0.917_54,
+    Fe 57  56.935_392_84     0.021_19,
+    Fe 58  57.933_274_43     0.002_82,
+    Co 59  58.933_194_29     1.0,
+    Ni 58  57.935_342_41     0.680_77,
+    Ni 60  59.930_785_88     0.262_23,
+    Ni 61  60.931_055_57     0.011_399,
+    Ni 62  61.928_345_37     0.036_346,
+    Ni 64  63.927_966_82     0.009_255,
+    Cu 63  62.929_597_72     0.6915,
+    Cu 65  64.927_789_7      0.3085,
+    Zn 64  63.929_142_01     0.4917,
+    Zn 66  65.926_033_81     0.2773,
+    Zn 67  66.927_127_75     0.0404,
+    Zn 68  67.924_844_55     0.1845,
+    Zn 70  69.925_319_2      0.0061,
+    Br 79  78.918_337_6      0.5069,
+    Br 81  80.916_289_7      0.4931,
+    Ag 107 106.905_091_6     0.518_39,
+    Ag 109 108.904_755_3     0.481_61,
+    I  127 126.904_471_9     1.0,
+    Au 197 196.966_568_79    1.0,
+    Pb 204 203.973_044       0.014,
+    Pb 206 205.974_465_7     0.241,
+    Pb 207 206.975_897_3     0.221,
+    Pb 208 207.976_652_5     0.524,
+    U  234 234.040_952_3     0.000_054,
+    U  235 235.043_930_1     0.007_204,
+    U  238 238.050_788_4     0.992_742,
+];
+
+/// Estimate the mass (in AMU) of a neutral atom with `protons` and `mass_number` nucleons,
+/// using the semi-empirical (liquid drop) mass formula for the nuclear binding energy
+///
+/// Typically accurate to within 0.01 AMU for medium and heavy nuclei.
+pub fn semi_empirical_mass(protons: u8, mass_number: u16) -> f64 {
+    // Coefficients in MeV
+    const VOLUME: f64 = 15.75;
+    const SURFACE: f64 = 17.8;
+    const COULOMB: f64 = 0.711;
+    const ASYMMETRY: f64 = 23.7;
+    const PAIRING: f64 = 11.18;
+
+    let z = f64::from(protons);
+    let a = f64::from(mass_number);
+    let n = a - z;
+    let pairing = match (protons % 2, (mass_number - u16::from(protons)) % 2) {
+        (0, 0) => PAIRING / a.sqrt(),
+        (1, 1) => -PAIRING / a.sqrt(),
+        _ => 0.0,
+    };
+    let binding_energy = VOLUME * a
+        - SURFACE * a.powf(2.0 / 3.0)
+        - COULOMB * z * (z - 1.0) / a.cbrt()
+        - ASYMMETRY * (a - 2.0 * z).powi(2) / a
+        + pairing;
+    z * HYDROGEN_ATOM_MASS + n * NEUTRON_MASS - binding_energy / MEV_PER_AMU
+}
+
+impl Element {
+    /// All isotopes of `self` with known masses and abundances
+    pub fn isotopes(self) -> impl Iterator<Item = &'static Isotope> {
+        ISOTOPES
+            .iter()
+            .filter(move |isotope| isotope.element == self)
+    }
+
+    /// The measured isotope with the highest natural abundance, if any are known
+    pub fn most_abundant_known_isotope(self) -> Option<&'static Isotope> {
+        self.isotopes()
+            .max_by(|a, b| a.abundance.total_cmp(&b.abundance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abundances_sum_to_one() {
+        for element in [
+            Element::H,
+            Element::O,
+            Element::Fe,
+            Element::Cu,
+            Element::Pb,
+        ] {
+            let total = element
+                .isotopes()
+                .map(|isotope| isotope.abundance)
+                .sum::<f64>();
+            assert!((total - 1.0).abs() < 1e-3, "{element}: {total}");
+        }
+    }
+
+    #[test]
+    fn test_semi_empirical_mass() {
+        for isotope in ISOTOPES.iter().filter(|isotope| isotope.mass_number >= 40) {
+            let estimate =
+                semi_empirical_mass(isotope.element.protons().get(), isotope.mass_number);
+            assert!(
+                (estimate - isotope.mass).abs() < 0.02,
+                "{}-{}: {estimate} vs {}",
+                isotope.element,
+                isotope.mass_number,
+                isotope.mass
+            );
+        }
+    }
+}
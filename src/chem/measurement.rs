@@ -0,0 +1,449 @@
+//! Measured quantities, which know how precisely they were measured
+//!
+//! A [`Measurement`] keeps the place of its last significant digit along with its value and
+//! [`Unit`], and arithmetic on measurements keeps only as much precision as the operands had:
+//!
+//! - Sums and differences are as precise as the operand with the fewest decimal places, so
+//!   `12.1 g + 0.35 g` is `12.5 g`.
+//! - Products and quotients have as many significant figures as the operand with the fewest, so
+//!   `2.0 cm × 3.14 cm` is `6.3 cm²`.
+//! - [Exact](Measurement::exact) numbers, like counts and defined constants, never limit the
+//!   precision of the result.
+//!
+//! Values are only rounded when they're written, so rounding errors don't build up over several
+//! steps.
+//!
+//! Significant figures are counted from how the number was written: leading zeros never count,
+//! trailing zeros after a decimal point do, and trailing zeros without one don't. `1200` has two,
+//! and should be written as `1.20e3` to have three.
+
+use super::fmt::Superscript;
+use std::{collections::BTreeMap, str::FromStr};
+
+/// Power of ten of the first significant digit of `value`, or 0 if it's zero
+#[allow(
+    clippy::cast_possible_truncation,
+    reason = "f64 exponents are well within i32"
+)]
+fn magnitude(value: f64) -> i32 {
+    if value == 0.0 || !value.is_finite() {
+        0
+    } else {
+        value.abs().log10().floor() as i32
+    }
+}
+
+/// A product of base units raised to powers, like `g·mL⁻¹`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Unit {
+    /// The power of each base unit, by symbol, leaving out any that cancel out
+    powers: BTreeMap<String, i8>,
+}
+
+impl Unit {
+    /// No unit, for counts and ratios
+    pub const fn dimensionless() -> Self {
+        Self {
+            powers: BTreeMap::new(),
+        }
+    }
+
+    /// Just the unit written as `symbol`
+    pub fn base(symbol: &str) -> Self {
+        Self {
+            powers: BTreeMap::from([(symbol.to_string(), 1)]),
+        }
+    }
+
+    pub fn is_dimensionless(&self) -> bool {
+        self.powers.is_empty()
+    }
+
+    /// This unit with each power multiplied by `sign` of `rhs`'s added to it
+    fn combine(&self, rhs: &Self, sign: i8) -> Self {
+        let mut powers = self.powers.clone();
+        for (symbol, &power) in &rhs.powers {
+            let total = powers.entry(symbol.clone()).or_default();
+            *total = total.saturating_add(sign.saturating_mul(power));
+            if *total == 0 {
+                powers.remove(symbol);
+            }
+        }
+        Self { powers }
+    }
+}
+
+impl std::ops::Mul for &Unit {
+    type Output = Unit;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.combine(rhs, 1)
+    }
+}
+
+impl std::ops::Div for &Unit {
+    type Output = Unit;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        self.combine(rhs, -1)
+    }
+}
+
+impl std::fmt::Display for Unit {
+    /// Positive powers first, and then negative ones after a `/`, or with negative exponents if
+    /// there are no positive ones
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let write_factors = |f: &mut std::fmt::Formatter<'_>, negative: bool, flip: bool| {
+            let factors = (self.powers.iter()).filter(|&(_, &power)| (power < 0) == negative);
+            for (idx, (symbol, &power)) in factors.enumerate() {
+                if idx > 0 {
+                    f.write_str("·")?;
+                }
+                f.write_str(symbol)?;
+                let power = if flip { -power } else { power };
+                if power != 1 {
+                    write!(f, "{}", Superscript(power.to_string().as_str()))?;
+                }
+            }
+            Ok(())
+        };
+        let has_positive = self.powers.values().any(|&power| power > 0);
+        let has_negative = self.powers.values().any(|&power| power < 0);
+        if has_positive {
+            write_factors(f, false, false)?;
+            if has_negative {
+                f.write_str("/")?;
+                write_factors(f, true, true)?;
+            }
+            Ok(())
+        } else {
+            write_factors(f, true, false)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseUnitError {
+    /// A factor with no symbol, like in `g·/mL`
+    MissingSymbol,
+    /// A power that isn't a whole number, or is too large
+    InvalidPower(String),
+}
+
+impl std::fmt::Display for ParseUnitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingSymbol => f.write_str("unit is missing a symbol"),
+            Self::InvalidPower(power) => write!(f, "invalid power `{power}`"),
+        }
+    }
+}
+
+impl std::error::Error for ParseUnitError {}
+
+impl FromStr for Unit {
+    type Err = ParseUnitError;
+
+    /// Symbols separated by `·` or `*`, each optionally raised to a power with `^`, with any
+    /// after a `/` divided by, like `g/mL` or `kg·m^2/s^2`
+    ///
+    /// An empty string is [dimensionless](Self::dimensionless).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(Self::dimensionless());
+        }
+        let (over, under) = s.split_once('/').unwrap_or((s, ""));
+        let mut unit = Self::dimensionless();
+        for (part, sign) in [(over, 1), (under, -1)] {
+            // Nothing over the `/`, like in `1/s`
+            if part.is_empty() || part.trim() == "1" {
+                continue;
+            }
+            for factor in part.split(['·', '*']) {
+                let (symbol, power) = factor.trim().split_once('^').unwrap_or((factor, "1"));
+                let symbol = symbol.trim();
+                if symbol.is_empty() {
+                    return Err(ParseUnitError::MissingSymbol);
+                }
+                let power = (power.trim().parse::<i8>())
+                    .map_err(|_| ParseUnitError::InvalidPower(power.to_string()))?;
+                let base = Self {
+                    powers: BTreeMap::from([(symbol.to_string(), power)]),
+                };
+                unit = unit.combine(&base, sign);
+            }
+        }
+        Ok(unit)
+    }
+}
+
+/// Added or subtracted measurements that weren't in the same unit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitMismatch {
+    pub lhs: Unit,
+    pub rhs: Unit,
+}
+
+impl std::fmt::Display for UnitMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "can't combine `{}` with `{}`", self.lhs, self.rhs)
+    }
+}
+
+impl std::error::Error for UnitMismatch {}
+
+/// A value, how precisely it's known, and what it's in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub value: f64,
+    /// The power of ten of the last significant digit, like -2 for hundredths, or `None` if the
+    /// value is exact
+    pub last_place: Option<i32>,
+    pub unit: Unit,
+}
+
+impl Measurement {
+    /// `value` known to the place `10^last_place`
+    pub const fn new(value: f64, last_place: i32, unit: Unit) -> Self {
+        Self {
+            value,
+            last_place: Some(last_place),
+            unit,
+        }
+    }
+
+    /// `value` to `figures` significant figures
+    pub fn with_figures(value: f64, figures: u32, unit: Unit) -> Self {
+        let figures = i32::try_from(figures).unwrap_or(i32::MAX);
+        Self::new(value, magnitude(value).saturating_sub(figures) + 1, unit)
+    }
+
+    /// A count or defined constant, which doesn't limit the precision of anything it's used with
+    pub const fn exact(value: f64, unit: Unit) -> Self {
+        Self {
+            value,
+            last_place: None,
+            unit,
+        }
+    }
+
+    /// How many significant figures it has, at least one, or `None` if it's exact
+    pub fn figures(&self) -> Option<u32> {
+        let last_place = self.last_place?;
+        let figures = magnitude(self.value).saturating_sub(last_place) + 1;
+        Some(u32::try_from(figures).unwrap_or(0).max(1))
+    }
+
+    /// How many decimal places it's written with, or `None` if it's exact
+    pub fn decimals(&self) -> Option<u32> {
+        let last_place = self.last_place?;
+        Some(u32::try_from(-last_place).unwrap_or(0))
+    }
+
+    /// Half of one in the last significant place, which the real value is within
+    pub fn uncertainty(&self) -> f64 {
+        self.last_place
+            .map_or(0.0, |last_place| 0.5 * 10f64.powi(last_place))
+    }
+
+    /// The less precise of two last places, for sums and differences
+    fn coarsest(a: Option<i32>, b: Option<i32>) -> Option<i32> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (place, None) | (None, place) => place,
+        }
+    }
+
+    /// The sum, as precise as the fewest decimal places
+    ///
+    /// # Errors
+    ///
+    /// If the two aren't in the same unit
+    pub fn plus(&self, rhs: &Self) -> Result<Self, UnitMismatch> {
+        if self.unit != rhs.unit {
+            return Err(UnitMismatch {
+                lhs: self.unit.clone(),
+                rhs: rhs.unit.clone(),
+            });
+        }
+        Ok(Self {
+            value: self.value + rhs.value,
+            last_place: Self::coarsest(self.last_place, rhs.last_place),
+            unit: self.unit.clone(),
+        })
+    }
+
+    /// The difference, as precise as the fewest decimal places
+    ///
+    /// # Errors
+    ///
+    /// If the two aren't in the same unit
+    pub fn minus(&self, rhs: &Self) -> Result<Self, UnitMismatch> {
+        self.plus(&Self {
+            value: -rhs.value,
+            ..rhs.clone()
+        })
+    }
+
+    /// `value` in `unit`, with as many significant figures as the one of `self` and `rhs` with
+    /// the fewest
+    fn with_fewest_figures(&self, rhs: &Self, value: f64, unit: Unit) -> Self {
+        match (self.figures(), rhs.figures()) {
+            (None, None) => Self::exact(value, unit),
+            (Some(a), Some(b)) => Self::with_figures(value, a.min(b), unit),
+            (Some(figures), None) | (None, Some(figures)) => {
+                Self::with_figures(value, figures, unit)
+            }
+        }
+    }
+
+    /// The value rounded to the last significant place, with halves rounded away from zero as
+    /// they're rounded by hand
+    pub fn rounded(&self) -> f64 {
+        self.last_place.map_or(self.value, |last_place| {
+            let scale = 10f64.powi(-last_place);
+            (self.value * scale).round() / scale
+        })
+    }
+
+    /// The product, with as many significant figures as the fewest
+    pub fn times(&self, rhs: &Self) -> Self {
+        self.with_fewest_figures(rhs, self.value * rhs.value, &self.unit * &rhs.unit)
+    }
+
+    /// The quotient, with as many significant figures as the fewest
+    pub fn divided_by(&self, rhs: &Self) -> Self {
+        self.with_fewest_figures(rhs, self.value / rhs.value, &self.unit / &rhs.unit)
+    }
+}
+
+impl std::fmt::Display for Measurement {
+    /// Rounded to the last significant place, in scientific notation if that's left of the ones
+    /// place so the zeros holding places aren't mistaken for significant ones
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.last_place, self.figures()) {
+            (Some(last_place), Some(figures)) if last_place > 0 => {
+                let written = format!("{:.*e}", figures as usize - 1, self.rounded());
+                let (mantissa, exponent) = written.split_once('e').unwrap_or((&written, "0"));
+                write!(f, "{mantissa}×10{}", Superscript(exponent))?;
+            }
+            (Some(_), _) => {
+                let decimals = self.decimals().unwrap_or(0) as usize;
+                write!(f, "{:.*}", decimals, self.rounded())?;
+            }
+            (None, _) => write!(f, "{}", self.value)?,
+        }
+        if !self.unit.is_dimensionless() {
+            write!(f, " {}", self.unit)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMeasurementError {
+    /// There was nothing to parse
+    Empty,
+    /// The value isn't a number
+    InvalidNumber(String),
+    Unit(ParseUnitError),
+}
+
+impl std::fmt::Display for ParseMeasurementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => f.write_str("measurement is empty"),
+            Self::InvalidNumber(number) => write!(f, "`{number}` is not a number"),
+            Self::Unit(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ParseMeasurementError {}
+
+impl From<ParseUnitError> for ParseMeasurementError {
+    fn from(value: ParseUnitError) -> Self {
+        Self::Unit(value)
+    }
+}
+
+impl FromStr for Measurement {
+    type Err = ParseMeasurementError;
+
+    /// A number, optionally in `e` notation, followed by a [`Unit`] after a space, like `12.35 cm`
+    /// or `1.20e3 g`
+    ///
+    /// How precise it is comes from how the number is written, see the [module docs](self).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (number, unit) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        if number.is_empty() {
+            return Err(ParseMeasurementError::Empty);
+        }
+        let invalid = || ParseMeasurementError::InvalidNumber(number.to_string());
+        let value = number.parse::<f64>().map_err(|_| invalid())?;
+        if !value.is_finite() {
+            return Err(invalid());
+        }
+        let (mantissa, exponent) = number.split_once(['e', 'E']).unwrap_or((number, "0"));
+        let exponent = exponent.parse::<i32>().map_err(|_| invalid())?;
+        let digits = mantissa.trim_start_matches(['-', '+']);
+        let last_place = match digits.split_once('.') {
+            Some((_, fraction)) => -i32::try_from(fraction.len()).map_err(|_| invalid())?,
+            None if digits.trim_start_matches('0').is_empty() => 0,
+            // Trailing zeros of a whole number only hold places
+            None => {
+                let zeros = digits.len() - digits.trim_end_matches('0').len();
+                i32::try_from(zeros).map_err(|_| invalid())?
+            }
+        };
+        Ok(Self::new(
+            value,
+            last_place.saturating_add(exponent),
+            unit.parse()?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Measurement {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_significant_figures() {
+        assert_eq!(parse("0.0450").figures(), Some(3));
+        assert_eq!(parse("1200").figures(), Some(2));
+        assert_eq!(parse("1.20e3").figures(), Some(3));
+        assert_eq!(parse("100.0").figures(), Some(4));
+        assert_eq!(parse("12.35 cm").decimals(), Some(2));
+        assert_eq!(parse("12.35 cm").unit, Unit::base("cm"));
+
+        let sum = parse("12.1 g").plus(&parse("0.35 g")).unwrap();
+        assert_eq!(sum.to_string(), "12.5 g", "to the fewest decimal places");
+        assert!(parse("1 g").plus(&parse("1 mL")).is_err());
+        let area = parse("2.0 cm").times(&parse("3.14 cm"));
+        assert_eq!(area.to_string(), "6.3 cm²", "to the fewest figures");
+        let density = parse("25.34 g").divided_by(&parse("10.0 mL"));
+        assert_eq!(density.to_string(), "2.53 g/mL");
+        let doubled = parse("1.5 g").times(&Measurement::exact(2.0, Unit::dimensionless()));
+        assert_eq!(
+            doubled.to_string(),
+            "3.0 g",
+            "exact numbers don't limit precision"
+        );
+        let big = parse("1230 g").times(&parse("1.00"));
+        assert_eq!(big.to_string(), "1.23×10³ g");
+
+        assert_eq!(
+            "kg·m^2/s^2".parse::<Unit>().unwrap().to_string(),
+            "kg·m²/s²"
+        );
+        assert_eq!("1/s".parse::<Unit>().unwrap().to_string(), "s⁻¹");
+        assert!("12.3.4 g".parse::<Measurement>().is_err());
+    }
+}
@@ -1,8 +1,15 @@
 pub mod atom;
 pub mod element;
+pub mod enthalpy;
 pub mod fmt;
+pub mod formula;
+pub mod isotope;
+pub mod measurement;
 pub mod molecule;
 pub mod orbital;
+pub mod phase;
+pub mod reaction;
+pub mod solution;
 pub mod units;
 
 // S: Spherical
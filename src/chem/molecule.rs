@@ -1,5 +1,10 @@
-use super::atom::Atom;
-use std::{collections::BTreeMap, num::NonZeroU8};
+use super::{
+    atom::Atom,
+    element::Element,
+    fmt::Subscript,
+    formula::{Formula, ParseFormulaError},
+};
+use std::{collections::BTreeMap, num::NonZeroU8, str::FromStr};
 
 // Dashed line = London Dispersion Force (LDF)
 
@@ -10,11 +15,98 @@ pub enum Compound {
     Tree(BTreeMap<Compound, NonZeroU8>),
 }
 
+impl Compound {
+    /// Total number of atoms of each element
+    ///
+    /// Counts stop at [`u32::MAX`], which parsed formulas never reach.
+    #[must_use]
+    pub fn element_counts(&self) -> BTreeMap<Element, u32> {
+        let mut counts = BTreeMap::new();
+        self.add_element_counts(&mut counts, 1);
+        counts
+    }
+
+    /// Mass of one mole of the compound in grams, using standard atomic weights
+    #[must_use]
+    pub fn molar_mass(&self) -> f64 {
+        self.element_counts()
+            .into_iter()
+            .map(|(element, n)| element.standard_atomic_weight() * f64::from(n))
+            .sum()
+    }
+
+    fn add_element_counts(&self, counts: &mut BTreeMap<Element, u32>, multiplier: u32) {
+        match self {
+            Self::Atom(atom) => {
+                let count = counts.entry(atom.element).or_default();
+                *count = count.saturating_add(multiplier);
+            }
+            Self::Tree(tree) => {
+                for (compound, n) in tree {
+                    compound.add_element_counts(counts, multiplier.saturating_mul(n.get().into()));
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Compound {
+    type Err = ParseFormulaError;
+
+    /// Parse a formula like `Fe2(SO4)3`
+    ///
+    /// See [`Formula`] for the syntax. Only single atoms may be charged.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let formula = s.parse::<Formula>()?;
+        match (formula.compound(), formula.charge) {
+            (compound, 0) => Ok(compound),
+            (Self::Atom(atom), charge) => {
+                let electrons = i16::from(atom.element.protons().get()) - charge;
+                let electrons =
+                    u8::try_from(electrons).map_err(|_| ParseFormulaError::InvalidCharge)?;
+                Ok(Self::Atom(Atom { electrons, ..atom }))
+            }
+            (Self::Tree(_), _) => Err(ParseFormulaError::PolyatomicCharge),
+        }
+    }
+}
+
+/// Hill system: carbon, then hydrogen, then everything else alphabetically.
+/// Without carbon, everything (including hydrogen) is alphabetical.
+const fn hill_order(has_carbon: bool, element: Element) -> (u8, &'static str) {
+    match element {
+        Element::C if has_carbon => (0, ""),
+        Element::H if has_carbon => (1, ""),
+        _ => (2, element.symbol()),
+    }
+}
+
 impl std::fmt::Display for Compound {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Atom(atom) => std::fmt::Display::fmt(atom, f),
-            Self::Tree(btree_map) => todo!(),
+            Self::Tree(btree_map) => {
+                let has_carbon = btree_map
+                    .keys()
+                    .any(|c| matches!(c, Self::Atom(atom) if atom.element == Element::C));
+                let mut items = btree_map.iter().collect::<Vec<_>>();
+                // Atoms in Hill order, then groups
+                items.sort_by_key(|(compound, _)| match compound {
+                    Self::Atom(atom) => Some(hill_order(has_carbon, atom.element)),
+                    Self::Tree(_) => None,
+                });
+                items.sort_by_key(|(compound, _)| matches!(compound, Self::Tree(_)));
+                for (compound, n) in items {
+                    match compound {
+                        Self::Atom(atom) => write!(f, "{atom}")?,
+                        Self::Tree(_) => write!(f, "({compound})")?,
+                    }
+                    if n.get() > 1 {
+                        write!(f, "{}", Subscript(n.get()))?;
+                    }
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -1,6 +1,11 @@
-use crate::{resource::Resources, rl_helpers::DynRaylibDraw3D};
+use crate::{
+    chem::fmt::Superscript,
+    resource::{AssetId, Resources},
+    rl_helpers::DynRaylibDraw3D,
+};
+use arrayvec::ArrayVec;
 use raylib::prelude::*;
-use std::num::NonZeroU8;
+use std::{fmt::Write, num::NonZeroU8};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Orbital {
@@ -20,11 +25,13 @@ impl Orbital {
         energy_level: u8,
     ) {
         let scale = energy_level.into();
-        let model = match self {
-            Self::S => &resources.orbital_s,
-            Self::P => &resources.orbital_p,
-            Self::D => &resources.orbital_d,
-            Self::F => &resources.orbital_f,
+        let Some(model) = resources.model(match self {
+            Self::S => AssetId::OrbitalS,
+            Self::P => AssetId::OrbitalP,
+            Self::D => AssetId::OrbitalD,
+            Self::F => AssetId::OrbitalF,
+        }) else {
+            return;
         };
         d.draw_mesh(
             *model.meshes()[0],
@@ -46,6 +53,11 @@ pub enum SubLevel {
 }
 
 impl SubLevel {
+    /// The spectroscopic letter of `self`
+    pub const fn symbol(self) -> char {
+        b"spdfghi"[self as usize] as char
+    }
+
     pub const fn orbitals(self) -> NonZeroU8 {
         // SAFETY: +1 guarantees nonzero
         unsafe { NonZeroU8::new_unchecked(2 * (self as u8) + 1) }
@@ -85,168 +97,197 @@ const _: () = {
     assert!(SubLevel::sublevels_at_energy(7) == 4);
 };
 
+/// A [`SubLevel`] at a particular energy level, e.g. 3d
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Subshell {
+    /// The principal quantum number (n)
+    pub energy_level: u8,
+    pub sublevel: SubLevel,
+}
+
+impl Subshell {
+    pub const fn new(energy_level: u8, sublevel: SubLevel) -> Self {
+        Self {
+            energy_level,
+            sublevel,
+        }
+    }
+
+    pub const fn capacity(self) -> NonZeroU8 {
+        self.sublevel.capacity()
+    }
+}
+
+impl std::fmt::Display for Subshell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.energy_level, self.sublevel.symbol())
+    }
+}
+
+/// Subshells in the order they are filled (Madelung rule), enough for all 118 elements
+#[rustfmt::skip]
+pub const AUFBAU_ORDER: [Subshell; 19] = {
+    use SubLevel::{D, F, P, S};
+    const fn ss(n: u8, l: SubLevel) -> Subshell { Subshell::new(n, l) }
+    [
+        ss(1, S),
+        ss(2, S),                       ss(2, P),
+        ss(3, S),                       ss(3, P),
+        ss(4, S),             ss(3, D), ss(4, P),
+        ss(5, S),             ss(4, D), ss(5, P),
+        ss(6, S),   ss(4, F), ss(5, D), ss(6, P),
+        ss(7, S),   ss(5, F), ss(6, D), ss(7, P),
+    ]
+};
+
+/// The ground-state electron configuration of `n` electrons, filled in [`AUFBAU_ORDER`]
+///
+/// Note: this is the idealized configuration, and does not account for exceptions like
+/// chromium (3d⁵ 4s¹) or copper (3d¹⁰ 4s¹).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ElectronConfig(u8);
 
 impl ElectronConfig {
+    /// The most electrons [`AUFBAU_ORDER`] can hold
+    pub const MAX_ELECTRONS: u8 = 118;
+
+    /// # Panics
+    ///
+    /// If `electrons` exceeds [`Self::MAX_ELECTRONS`]
     pub const fn new(electrons: u8) -> Self {
+        assert!(electrons <= Self::MAX_ELECTRONS, "too many electrons");
         Self(electrons)
     }
 
-    pub fn available(self) -> u8 {
-        todo!()
+    /// Total number of electrons
+    pub const fn electrons(self) -> u8 {
+        self.0
+    }
+
+    /// Number of electrons occupying `AUFBAU_ORDER[index]`
+    const fn occupancy(self, index: usize) -> u8 {
+        let mut remaining = self.0;
+        let mut i = 0;
+        while i < index {
+            let capacity = AUFBAU_ORDER[i].capacity().get();
+            if remaining <= capacity {
+                return 0;
+            }
+            remaining -= capacity;
+            i += 1;
+        }
+        let capacity = AUFBAU_ORDER[index].capacity().get();
+        if remaining < capacity {
+            remaining
+        } else {
+            capacity
+        }
+    }
+
+    /// Each occupied subshell and the number of electrons in it, in filling order
+    pub fn subshells(self) -> impl Iterator<Item = (Subshell, u8)> {
+        AUFBAU_ORDER
+            .iter()
+            .enumerate()
+            .map(move |(i, &subshell)| (subshell, self.occupancy(i)))
+            .take_while(|&(_, electrons)| electrons > 0)
+    }
+
+    /// The highest occupied energy level (0 if there are no electrons)
+    pub const fn valence_shell(self) -> u8 {
+        let mut n = 0;
+        let mut i = 0;
+        while i < AUFBAU_ORDER.len() && self.occupancy(i) > 0 {
+            if AUFBAU_ORDER[i].energy_level > n {
+                n = AUFBAU_ORDER[i].energy_level;
+            }
+            i += 1;
+        }
+        n
+    }
+
+    /// Total electrons at the highest occupied energy level
+    pub const fn valence_electrons(self) -> u8 {
+        let shell = self.valence_shell();
+        let mut electrons = 0;
+        let mut i = 0;
+        while i < AUFBAU_ORDER.len() {
+            if AUFBAU_ORDER[i].energy_level == shell {
+                electrons += self.occupancy(i);
+            }
+            i += 1;
+        }
+        electrons
+    }
+
+    /// Number of electrons available for forming bonds
+    ///
+    /// The lesser of the electrons that can be given up and the electrons needed to fill the
+    /// valence shell (a duet for the first shell, otherwise an octet).
+    pub const fn available(self) -> u8 {
+        let capacity = if self.valence_shell() <= 1 { 2 } else { 8 };
+        let electrons = self.valence_electrons();
+        assert!(
+            electrons <= capacity,
+            "number of electrons in a given shell cannot exceed that shell's capacity"
+        );
+        let missing = capacity - electrons;
+        if electrons < missing {
+            electrons
+        } else {
+            missing
+        }
     }
 }
 
-// #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-// #[rustfmt::skip]
-// pub enum SubLevel {
-//     _1S = 1 << 2,
-//     _2S = 2 << 2, _2P = (2 << 2) | 1,
-//     _3S = 3 << 2, _3P = (3 << 2) | 1, _3D = (3 << 2) | 2,
-//     _4S = 4 << 2, _4P = (4 << 2) | 1, _4D = (4 << 2) | 2, _4F = (4 << 2) | 3,
-//     _5S = 5 << 2, _5P = (5 << 2) | 1, _5D = (5 << 2) | 2, _5F = (5 << 2) | 3,
-//     _6S = 6 << 2, _6P = (6 << 2) | 1, _6D = (6 << 2) | 2,
-//     _7S = 7 << 2, _7P = (7 << 2) | 1,
-// }
-// #[allow(
-//     clippy::enum_glob_use,
-//     reason = "I'm using all of them and don't want to repeat them"
-// )]
-// use SubLevel::*;
-
-// impl std::fmt::Display for SubLevel {
-//     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-//         write!(f, "{}{}", self.energy_level(), self.symbol())
-//     }
-// }
-
-// #[rustfmt::skip]
-// static ORBITALS: [SubLevel; 19] = [
-//     _1S,
-//     _2S,           _2P,
-//     _3S,           _3P,
-//     _4S,      _3D, _4P,
-//     _5S,      _4D, _5P,
-//     _6S, _4F, _5D, _6P,
-//     _7S, _5F, _6D, _7P,
-// ];
-
-// impl SubLevel {
-//     const fn index(self) -> u8 {
-//         self as u8 & 3
-//     }
-
-//     pub const fn energy_level(self) -> u8 {
-//         self as u8 >> 2
-//     }
-
-//     pub const fn symbol(self) -> char {
-//         b"spdf"[self.index() as usize] as char
-//     }
-
-//     pub const fn orbitals(self) -> NonZeroU8 {
-//         let n = self.index();
-//         // SAFETY: Highest index is 6, which can be shl'd to 12 without overflowing.
-//         let n = unsafe { n.unchecked_shl(1) };
-//         // SAFETY: Highest valid is 12, which can be incremented to 13 without overflowing.
-//         let n = unsafe { n.unchecked_add(1) };
-//         // SAFETY: Adding 1 guarantees non-zero.
-//         unsafe { NonZeroU8::new_unchecked(n) }
-//     }
-
-//     pub const fn capacity(self) -> NonZeroU8 {
-//         // SAFETY: The highest orbital is `I` with 13.
-//         // 13 << 1 = 26, which does not overflow.
-//         let n = unsafe { self.orbitals().get().unchecked_shl(1) };
-//         // SAFETY: nonzero multiplied by nonzero is nonzero, given no overflow
-//         unsafe { NonZeroU8::new_unchecked(n) }
-//     }
-// }
-
-// #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-// pub struct ElectronConfig {
-//     levels: u8,
-//     /// Electrons in the outermost sublevel
-//     outermost: u8,
-// }
-
-// impl ElectronConfig {
-//     pub const fn new(mut electrons: u8) -> ElectronConfig {
-//         let mut i = 0;
-//         loop {
-//             let cap = ORBITALS[i as usize].capacity().get();
-//             if electrons > cap {
-//                 electrons -= cap;
-//                 i += 1;
-//                 assert!((i as usize) < ORBITALS.len(), "too many electrons");
-//             } else {
-//                 break ElectronConfig {
-//                     levels: i + (electrons > 0) as u8,
-//                     outermost: electrons,
-//                 };
-//             }
-//         }
-//     }
-
-//     pub const fn sublevels(self) -> &'static [SubLevel] {
-//         ORBITALS.split_at(self.levels as usize).0
-//     }
-
-//     /// Total electrons at highest occupied energy level
-//     pub const fn valance_electrons(self) -> u8 {
-//         self.outermost
-//     }
-
-//     /// Number of electrons available for forming bonds
-//     pub const fn available(self) -> u8 {
-//         let capacity = match self.valance_capacity() {
-//             Some(n) => n.get(),
-//             None => 0,
-//         };
-//         let electrons = self.valance_electrons();
-//         assert!(
-//             electrons <= capacity,
-//             "number of electrons in a given shell cannot exceed that shell's capacity"
-//         );
-//         capacity - electrons
-//     }
-// }
-
-// impl std::fmt::Display for ElectronConfig {
-//     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-//         fn superscript(buf: &mut (String, String), n: u8) -> std::fmt::Result {
-//             use std::fmt::Write;
-//             buf.0.clear();
-//             write!(buf.0, "{n}")?;
-//             buf.1.clear();
-//             buf.1
-//                 .extend(buf.0.chars().map(|ch| ch.to_superscript().unwrap()));
-//             Ok(())
-//         }
-//         let mut sublevels = self
-//             .sublevels()
-//             .iter()
-//             .map(|o| (o, o.capacity().get()))
-//             .collect::<Vec<_>>();
-//         if let Some((_, n)) = sublevels.last_mut() {
-//             *n = self.outermost;
-//         }
-//         sublevels.sort_by_key(|lv| lv.0.energy_level());
-//         let total = sublevels.len();
-//         let mut buf0 = String::new();
-//         let mut buf1 = String::new();
-//         for (n, (orbital, electrons)) in sublevels.into_iter().enumerate() {
-//             use std::fmt::Write;
-//             buf0.clear();
-//             write!(buf0, "{electrons}")?;
-//             buf1.clear();
-//             buf1.extend(buf0.chars().map(|ch| ch.to_superscript().unwrap()));
-//             write!(f, "{orbital}{buf1}")?;
-//             if n < total - 1 {
-//                 write!(f, " ")?;
-//             }
-//         }
-//         Ok(())
-//     }
-// }
+impl std::fmt::Display for ElectronConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut subshells = self.subshells().collect::<ArrayVec<_, 19>>();
+        subshells.sort_by_key(|(subshell, _)| subshell.energy_level);
+        for (n, (subshell, electrons)) in subshells.into_iter().enumerate() {
+            if n > 0 {
+                f.write_char(' ')?;
+            }
+            write!(f, "{subshell}{}", Superscript(electrons))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_electron_config_display() {
+        assert_eq!(ElectronConfig::new(0).to_string(), "");
+        assert_eq!(ElectronConfig::new(1).to_string(), "1s¹");
+        assert_eq!(ElectronConfig::new(10).to_string(), "1s² 2s² 2p⁶");
+        assert_eq!(
+            ElectronConfig::new(26).to_string(),
+            "1s² 2s² 2p⁶ 3s² 3p⁶ 3d⁶ 4s²"
+        );
+    }
+
+    #[test]
+    fn test_electron_config_valence() {
+        // (electrons, valence shell, valence electrons, available)
+        for (electrons, shell, valence, available) in [
+            (1, 1, 1, 1),   // H
+            (2, 1, 2, 0),   // He
+            (6, 2, 4, 4),   // C
+            (7, 2, 5, 3),   // N
+            (8, 2, 6, 2),   // O
+            (10, 2, 8, 0),  // Ne
+            (11, 3, 1, 1),  // Na
+            (17, 3, 7, 1),  // Cl
+            (26, 4, 2, 2),  // Fe
+            (118, 7, 8, 0), // Og
+        ] {
+            let config = ElectronConfig::new(electrons);
+            assert_eq!(config.valence_shell(), shell, "{config}");
+            assert_eq!(config.valence_electrons(), valence, "{config}");
+            assert_eq!(config.available(), available, "{config}");
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Whether things are solid, liquid, or gas at some temperature
+//!
+//! Melting and boiling points are at one atmosphere. Compounds that sublime have the same
+//! temperature for both, and ones that break down before they'd boil have no boiling point.
+
+use super::{molecule::Compound, units::Kelvin};
+use std::sync::LazyLock;
+
+/// Where each compound melts and boils, in kelvins
+static PHASE_POINTS: LazyLock<Vec<(Compound, f64, Option<f64>)>> = LazyLock::new(|| {
+    [
+        ("H2O", 273.15, Some(373.15)),
+        ("H2O2", 272.7, Some(423.4)),
+        ("CO", 68.1, Some(81.6)),
+        ("CO2", 194.7, Some(194.7)),
+        ("CH4", 90.7, Some(111.7)),
+        ("C3H8", 85.5, Some(231.0)),
+        ("C2H6O", 159.0, Some(351.4)),
+        ("NH3", 195.4, Some(239.8)),
+        ("NO2", 261.9, Some(294.3)),
+        ("HCl", 158.9, Some(188.1)),
+        ("SO2", 201.0, Some(263.1)),
+        ("H2SO4", 283.5, Some(610.0)),
+        ("NH4Cl", 611.0, Some(611.0)),
+        ("NaOH", 591.0, Some(1661.0)),
+        ("NaCl", 1074.0, Some(1686.0)),
+        ("KCl", 1044.0, Some(1693.0)),
+        ("CaCl2", 1045.0, Some(2208.0)),
+        ("CaO", 2886.0, Some(3123.0)),
+        ("SiO2", 1986.0, Some(2503.0)),
+        ("Al2O3", 2345.0, Some(3250.0)),
+        ("KNO3", 607.0, None),
+        ("NaNO3", 581.0, None),
+        ("CaCO3", 1098.0, None),
+        ("Na2CO3", 1124.0, None),
+        ("Fe2O3", 1839.0, None),
+        ("CuO", 1599.0, None),
+    ]
+    .into_iter()
+    .map(|(formula, melting, boiling)| (formula.parse().unwrap(), melting, boiling))
+    .collect()
+});
+
+/// A state of matter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    Solid,
+    Liquid,
+    Gas,
+}
+
+impl Compound {
+    /// Where the compound melts and boils, from the table or its element's if it's just the
+    /// element as it's usually found, like O₂
+    fn phase_points(&self) -> Option<(Kelvin, Option<Kelvin>)> {
+        if let Some(&(_, melting, boiling)) = PHASE_POINTS.iter().find(|(known, ..)| known == self)
+        {
+            return Some((Kelvin::new(melting)?, boiling.and_then(Kelvin::new)));
+        }
+        let counts = self.element_counts();
+        let [(&element, &count)] = counts.iter().collect::<Vec<_>>()[..] else {
+            return None;
+        };
+        if count != if element.is_diatomic() { 2 } else { 1 } {
+            return None;
+        }
+        Some((element.melting_point()?, element.boiling_point()))
+    }
+
+    /// The temperature the compound melts at, if it's known
+    pub fn melting_point(&self) -> Option<Kelvin> {
+        self.phase_points().map(|(melting, _)| melting)
+    }
+
+    /// The temperature the compound boils at, if it's known and it doesn't break down first
+    pub fn boiling_point(&self) -> Option<Kelvin> {
+        self.phase_points().and_then(|(_, boiling)| boiling)
+    }
+
+    /// Whether it's solid, liquid, or gas at `temperature`, if its melting point is known
+    ///
+    /// Right at its boiling point it's a gas, and without one it never stops being liquid.
+    pub fn phase(&self, temperature: Kelvin) -> Option<Phase> {
+        let (melting, boiling) = self.phase_points()?;
+        Some(if boiling.is_some_and(|boiling| temperature >= boiling) {
+            Phase::Gas
+        } else if temperature >= melting {
+            Phase::Liquid
+        } else {
+            Phase::Solid
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase() {
+        let water = "H2O".parse::<Compound>().unwrap();
+        let [ice, room, steam] = [-10.0, 20.0, 100.0].map(|c| Kelvin::from_celsius(c).unwrap());
+        assert_eq!(water.phase(ice), Some(Phase::Solid));
+        assert_eq!(water.phase(room), Some(Phase::Liquid));
+        assert_eq!(water.phase(steam), Some(Phase::Gas));
+
+        let dry_ice = "CO2".parse::<Compound>().unwrap();
+        assert_eq!(dry_ice.phase(ice), Some(Phase::Gas), "it sublimes");
+        let oxygen = "O2".parse::<Compound>().unwrap();
+        assert_eq!(oxygen.boiling_point(), Some(Kelvin::new(90.19).unwrap()));
+        assert_eq!("O3".parse::<Compound>().unwrap().melting_point(), None);
+        assert_eq!(
+            "Fe".parse::<Compound>().unwrap().phase(room),
+            Some(Phase::Solid)
+        );
+        assert_eq!("CaCO3".parse::<Compound>().unwrap().boiling_point(), None);
+    }
+}
@@ -0,0 +1,435 @@
+use super::{
+    element::Element,
+    formula::{Formula, ParseFormulaError},
+};
+use std::{collections::BTreeSet, num::NonZeroU32, str::FromStr};
+
+/// An exact fraction, always stored in lowest terms with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Ratio {
+    num: i128,
+    den: i128,
+}
+
+const fn gcd(mut a: i128, mut b: i128) -> i128 {
+    a = a.abs();
+    b = b.abs();
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Unwraps an [`Option`] in a const fn, returning [`None`] from it if there's nothing
+macro_rules! checked {
+    ($value:expr) => {
+        match $value {
+            Some(value) => value,
+            None => return None,
+        }
+    };
+}
+
+impl Ratio {
+    const ZERO: Self = Self { num: 0, den: 1 };
+
+    /// `num / den` in lowest terms, or [`None`] if either is too large to keep exact
+    const fn new(num: i128, den: i128) -> Option<Self> {
+        assert!(den != 0, "denominator cannot be zero");
+        // Their magnitudes have to fit to be reduced
+        if num == i128::MIN || den == i128::MIN {
+            return None;
+        }
+        let g = gcd(num, den);
+        let sign = den.signum();
+        Some(Self {
+            num: sign * num / g,
+            den: sign * den / g,
+        })
+    }
+
+    const fn from_int(n: i128) -> Self {
+        Self { num: n, den: 1 }
+    }
+
+    const fn is_zero(self) -> bool {
+        self.num == 0
+    }
+
+    /// `self - rhs`, or [`None`] if it's too large to keep exact
+    const fn minus(self, rhs: Self) -> Option<Self> {
+        let lhs = checked!(self.num.checked_mul(rhs.den));
+        let rhs_num = checked!(rhs.num.checked_mul(self.den));
+        Self::new(
+            checked!(lhs.checked_sub(rhs_num)),
+            checked!(self.den.checked_mul(rhs.den)),
+        )
+    }
+
+    /// `self * rhs`, or [`None`] if it's too large to keep exact
+    const fn multiply(self, rhs: Self) -> Option<Self> {
+        Self::new(
+            checked!(self.num.checked_mul(rhs.num)),
+            checked!(self.den.checked_mul(rhs.den)),
+        )
+    }
+
+    /// `self / rhs`, or [`None`] if it's too large to keep exact
+    const fn divide(self, rhs: Self) -> Option<Self> {
+        Self::new(
+            checked!(self.num.checked_mul(rhs.den)),
+            checked!(self.den.checked_mul(rhs.num)),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseReactionError {
+    /// There is no `->`, `→`, or `=` separating reactants from products
+    MissingArrow,
+    /// One of the species is not a valid formula
+    Formula(ParseFormulaError),
+}
+
+impl std::fmt::Display for ParseReactionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingArrow => f.write_str("reaction is missing an arrow"),
+            Self::Formula(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ParseReactionError {}
+
+impl From<ParseFormulaError> for ParseReactionError {
+    fn from(value: ParseFormulaError) -> Self {
+        Self::Formula(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceError {
+    /// One side of the reaction has nothing on it
+    MissingSpecies,
+    /// No positive coefficients conserve every element and charge
+    Impossible,
+    /// More than one independent set of coefficients balances the reaction
+    Ambiguous,
+    /// The coefficients are too large to represent
+    Overflow,
+}
+
+impl std::fmt::Display for BalanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingSpecies => f.write_str("reaction needs both reactants and products"),
+            Self::Impossible => f.write_str("reaction cannot be balanced"),
+            Self::Ambiguous => f.write_str("reaction has more than one way to balance"),
+            Self::Overflow => f.write_str("coefficients are too large"),
+        }
+    }
+}
+
+impl std::error::Error for BalanceError {}
+
+/// A possibly unbalanced chemical reaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reaction {
+    pub reactants: Vec<Formula>,
+    pub products: Vec<Formula>,
+}
+
+impl FromStr for Reaction {
+    type Err = ParseReactionError;
+
+    /// Parse a reaction like `H2 + O2 -> H2O`
+    ///
+    /// Species are separated by ` + ` (with spaces, so that charges like `Na+` aren't split).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (lhs, rhs) = ["->", "→", "="]
+            .into_iter()
+            .find_map(|arrow| s.split_once(arrow))
+            .ok_or(ParseReactionError::MissingArrow)?;
+        let side = |side: &str| {
+            side.split(" + ")
+                .map(str::parse)
+                .collect::<Result<Vec<Formula>, _>>()
+        };
+        Ok(Self {
+            reactants: side(lhs)?,
+            products: side(rhs)?,
+        })
+    }
+}
+
+impl Reaction {
+    /// Every element appearing anywhere in the reaction
+    #[must_use]
+    pub fn elements(&self) -> BTreeSet<Element> {
+        self.reactants
+            .iter()
+            .chain(&self.products)
+            .flat_map(|species| species.element_counts().into_keys())
+            .collect()
+    }
+
+    /// Find the smallest positive integer coefficients that conserve every element and charge
+    ///
+    /// Solves for the null space of the element-count matrix using exact rational arithmetic.
+    ///
+    /// # Errors
+    ///
+    /// If there isn't exactly one smallest set of coefficients that fits
+    pub fn balance(&self) -> Result<BalancedReaction, BalanceError> {
+        if self.reactants.is_empty() || self.products.is_empty() {
+            return Err(BalanceError::MissingSpecies);
+        }
+
+        // Rows are conserved quantities, columns are species (products negated)
+        let species = self
+            .reactants
+            .iter()
+            .map(|s| (1, s))
+            .chain(self.products.iter().map(|s| (-1, s)))
+            .collect::<Vec<_>>();
+        let elements = self.elements();
+        let mut matrix = elements
+            .iter()
+            .map(|element| {
+                species
+                    .iter()
+                    .map(|(sign, s)| {
+                        let count = s.element_counts().get(element).copied().unwrap_or(0);
+                        Ratio::from_int(sign * i128::from(count))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .chain(std::iter::once(
+                species
+                    .iter()
+                    .map(|(sign, s)| Ratio::from_int(sign * i128::from(s.charge)))
+                    .collect(),
+            ))
+            .collect::<Vec<_>>();
+
+        // Reduced row echelon form
+        let cols = species.len();
+        let mut pivots = Vec::new();
+        let mut row = 0;
+        for col in 0..cols {
+            let Some(pivot) = (row..matrix.len()).find(|&r| !matrix[r][col].is_zero()) else {
+                continue;
+            };
+            matrix.swap(row, pivot);
+            let lead = matrix[row][col];
+            for x in &mut matrix[row] {
+                *x = x.divide(lead).ok_or(BalanceError::Overflow)?;
+            }
+            let pivot_row = matrix[row].clone();
+            for (r, other) in matrix.iter_mut().enumerate() {
+                let factor = other[col];
+                if r != row && !factor.is_zero() {
+                    for (x, p) in other.iter_mut().zip(&pivot_row) {
+                        *x = (factor.multiply(*p))
+                            .and_then(|product| x.minus(product))
+                            .ok_or(BalanceError::Overflow)?;
+                    }
+                }
+            }
+            pivots.push(col);
+            row += 1;
+        }
+
+        let free = (0..cols)
+            .filter(|col| !pivots.contains(col))
+            .collect::<Vec<_>>();
+        let free = match free.as_slice() {
+            [] => return Err(BalanceError::Impossible),
+            [free] => *free,
+            [_, _, ..] => return Err(BalanceError::Ambiguous),
+        };
+
+        let mut solution = vec![Ratio::ZERO; cols];
+        solution[free] = Ratio::from_int(1);
+        for (row, &col) in pivots.iter().enumerate() {
+            solution[col] = (Ratio::ZERO.minus(matrix[row][free])).ok_or(BalanceError::Overflow)?;
+        }
+
+        // Scale to the smallest integers
+        let lcm = solution
+            .iter()
+            .try_fold(1i128, |lcm, x| lcm.checked_mul(x.den / gcd(lcm, x.den)))
+            .ok_or(BalanceError::Overflow)?;
+        let ints = solution
+            .iter()
+            .map(|x| x.num.checked_mul(lcm / x.den))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(BalanceError::Overflow)?;
+        let divisor = ints.iter().copied().fold(0, gcd);
+        let coefficients = ints
+            .into_iter()
+            .map(|n| {
+                u32::try_from(n / divisor)
+                    .map_err(|_| {
+                        if n < 0 {
+                            BalanceError::Impossible
+                        } else {
+                            BalanceError::Overflow
+                        }
+                    })
+                    .and_then(|n| NonZeroU32::new(n).ok_or(BalanceError::Impossible))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (lhs, rhs) = coefficients.split_at(self.reactants.len());
+        Ok(BalancedReaction {
+            reactants: lhs.iter().copied().zip(self.reactants.clone()).collect(),
+            products: rhs.iter().copied().zip(self.products.clone()).collect(),
+        })
+    }
+}
+
+/// A reaction whose coefficients are known to conserve every element and charge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalancedReaction {
+    reactants: Vec<(NonZeroU32, Formula)>,
+    products: Vec<(NonZeroU32, Formula)>,
+}
+
+impl BalancedReaction {
+    /// Check that the given coefficients balance the reaction
+    ///
+    /// Returns [`None`] if any element or charge is not conserved.
+    #[must_use]
+    pub fn try_new(
+        reactants: Vec<(NonZeroU32, Formula)>,
+        products: Vec<(NonZeroU32, Formula)>,
+    ) -> Option<Self> {
+        let total = |side: &[(NonZeroU32, Formula)]| {
+            // Wide enough that no number of u32 products can overflow it in practice
+            let mut counts = std::collections::BTreeMap::<Option<Element>, i128>::new();
+            for (n, species) in side {
+                let n = i128::from(n.get());
+                for (element, count) in species.element_counts() {
+                    *counts.entry(Some(element)).or_default() += n * i128::from(count);
+                }
+                *counts.entry(None).or_default() += n * i128::from(species.charge);
+            }
+            counts.retain(|_, count| *count != 0);
+            counts
+        };
+        (!reactants.is_empty() && !products.is_empty() && total(&reactants) == total(&products))
+            .then_some(Self {
+                reactants,
+                products,
+            })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn reactants(&self) -> &[(NonZeroU32, Formula)] {
+        &self.reactants
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn products(&self) -> &[(NonZeroU32, Formula)] {
+        &self.products
+    }
+}
+
+impl std::fmt::Display for BalancedReaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let side = |f: &mut std::fmt::Formatter<'_>, side: &[(NonZeroU32, Formula)]| {
+            for (i, (n, species)) in side.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(" + ")?;
+                }
+                if n.get() > 1 {
+                    write!(f, "{n}")?;
+                }
+                write!(f, "{species}")?;
+            }
+            Ok(())
+        };
+        side(f, &self.reactants)?;
+        f.write_str(" → ")?;
+        side(f, &self.products)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coefficients(reaction: &str) -> Result<Vec<u32>, BalanceError> {
+        let balanced = reaction.parse::<Reaction>().unwrap().balance()?;
+        Ok(balanced
+            .reactants()
+            .iter()
+            .chain(balanced.products())
+            .map(|(n, _)| n.get())
+            .collect())
+    }
+
+    #[test]
+    fn test_balance() {
+        assert_eq!(coefficients("H2 + O2 -> H2O"), Ok(vec![2, 1, 2]));
+        assert_eq!(coefficients("Fe + O2 -> Fe2O3"), Ok(vec![4, 3, 2]));
+        assert_eq!(coefficients("C3H8 + O2 -> CO2 + H2O"), Ok(vec![1, 5, 3, 4]));
+        assert_eq!(
+            coefficients("Cu + Ag^+ -> Cu^2+ + Ag"),
+            Ok(vec![1, 2, 1, 2])
+        );
+        assert_eq!(coefficients("H2 -> O2"), Err(BalanceError::Impossible));
+        assert_eq!(
+            coefficients("H2 + O2 -> H2O + H2O2"),
+            Err(BalanceError::Ambiguous)
+        );
+    }
+
+    #[test]
+    fn test_balance_overflow() {
+        // Counts in the billions, all coprime, push the exact fractions past i128
+        assert_eq!(
+            coefficients(
+                "((((H251)241)239)233)((((O229)227)223)211) \
+                 + ((((C199)197)193)191)((((N181)179)173)167) \
+                 -> ((((H163)157)151)149)((((C139)137)131)127) \
+                 + ((((O113)109)107)103)((((N101)97)89)83)"
+            ),
+            Err(BalanceError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_display_balanced() {
+        let balanced = "H2 + O2 -> H2O"
+            .parse::<Reaction>()
+            .unwrap()
+            .balance()
+            .unwrap();
+        assert_eq!(balanced.to_string(), "2H₂ + O₂ → 2H₂O");
+    }
+
+    #[test]
+    fn test_try_new() {
+        let f = |s: &str| s.parse::<Formula>().unwrap();
+        let n = |n| NonZeroU32::new(n).unwrap();
+        assert!(
+            BalancedReaction::try_new(
+                vec![(n(2), f("H2")), (n(1), f("O2"))],
+                vec![(n(2), f("H2O"))]
+            )
+            .is_some()
+        );
+        assert!(
+            BalancedReaction::try_new(
+                vec![(n(1), f("H2")), (n(1), f("O2"))],
+                vec![(n(1), f("H2O"))]
+            )
+            .is_none()
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! Substances dissolved in a liquid, and how much of them it can hold
+
+use super::{
+    molecule::Compound,
+    units::{Kelvin, Liter, Molar, Mole},
+};
+use std::sync::LazyLock;
+
+/// Temperatures in [`SOLUBILITY`], in degrees Celsius
+const SOLUBILITY_CELSIUS: [f64; 6] = [0.0, 20.0, 40.0, 60.0, 80.0, 100.0];
+
+/// Grams of each solute that dissolve in 100 grams of water at each of [`SOLUBILITY_CELSIUS`]
+static SOLUBILITY: LazyLock<Vec<(Compound, [f64; 6])>> = LazyLock::new(|| {
+    [
+        ("NaCl", [35.7, 35.9, 36.4, 37.1, 38.0, 39.2]),
+        ("KCl", [27.6, 34.0, 40.0, 45.5, 51.1, 56.7]),
+        ("KNO3", [13.3, 31.6, 63.9, 110.0, 169.0, 246.0]),
+        ("NaNO3", [73.0, 88.0, 104.0, 124.0, 148.0, 180.0]),
+        ("NH4Cl", [29.4, 37.2, 45.8, 55.3, 65.6, 77.3]),
+        ("CuSO4", [14.3, 20.7, 28.5, 40.0, 55.0, 75.4]),
+        ("CaCl2", [59.5, 74.5, 128.0, 137.0, 147.0, 159.0]),
+        ("Na2CO3", [7.0, 21.5, 49.0, 46.0, 45.1, 44.7]),
+        // Less soluble the hotter it gets
+        ("Ca(OH)2", [0.189, 0.173, 0.141, 0.121, 0.094, 0.076]),
+        ("CaCO3", [0.0013; 6]),
+    ]
+    .into_iter()
+    .map(|(formula, grams)| (formula.parse().unwrap(), grams))
+    .collect()
+});
+
+/// The most of `solute` that stays dissolved in `solvent` at `temperature`, or [`None`] if
+/// that isn't known
+///
+/// Only solubilities in water are known. They're interpolated between every 20 °C from 0 °C to
+/// 100 °C, and held at the nearest end outside of that. A liter of water is taken to weigh a
+/// kilogram and not to swell as things dissolve in it.
+pub fn solubility(solute: &Compound, solvent: &Compound, temperature: Kelvin) -> Option<Molar> {
+    if *solvent != Solution::water_molecule() {
+        return None;
+    }
+    let (_, grams) = SOLUBILITY.iter().find(|(known, _)| known == solute)?;
+    let celsius = temperature.to_celsius();
+    let above = (SOLUBILITY_CELSIUS.iter())
+        .position(|&point| point > celsius)
+        .unwrap_or(SOLUBILITY_CELSIUS.len());
+    let per_100g = match above {
+        0 => grams[0],
+        n if n == SOLUBILITY_CELSIUS.len() => grams[n - 1],
+        n => {
+            let (t0, t1) = (SOLUBILITY_CELSIUS[n - 1], SOLUBILITY_CELSIUS[n]);
+            let t = (celsius - t0) / (t1 - t0);
+            grams[n - 1] + (grams[n] - grams[n - 1]) * t
+        }
+    };
+    // 100 g of water is a tenth of a liter
+    Molar::new(per_100g * 10.0 / solute.molar_mass())
+}
+
+/// Why solutions couldn't be combined or changed
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolutionError {
+    /// Mixed solutions of two different solvents, which don't mix
+    DifferentSolvents { lhs: Compound, rhs: Compound },
+    /// Diluted to less than the solution's volume, which would take removing solvent
+    Concentrating { volume: Liter, target: Liter },
+}
+
+impl std::fmt::Display for SolutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DifferentSolvents { lhs, rhs } => {
+                write!(f, "solutions in {lhs} and {rhs} don't mix")
+            }
+            Self::Concentrating { volume, target } => {
+                write!(
+                    f,
+                    "can't dilute {volume:.3} of solution down to {target:.3}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolutionError {}
+
+/// Volume to dilute `volume` of solution at `concentration` to for it to be at `target`
+///
+/// The moles of solute stay the same, so M₁V₁ = M₂V₂. Returns [`None`] if `target` is zero, or is
+/// more than `concentration`, which diluting can't reach.
+pub fn dilution_volume(concentration: Molar, volume: Liter, target: Molar) -> Option<Liter> {
+    if target.get() <= 0.0 || target > concentration {
+        return None;
+    }
+    Liter::new(concentration.get() * volume.get() / target.get())
+}
+
+/// Some amount of things dissolved in a liquid
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    /// What everything is dissolved in
+    pub solvent: Compound,
+    /// How much of each thing is dissolved, each only once
+    pub solutes: Vec<(Compound, Mole)>,
+    /// The volume of the whole solution
+    pub volume: Liter,
+}
+
+impl Solution {
+    /// `volume` of just `solvent`, with nothing dissolved in it yet
+    pub const fn new(solvent: Compound, volume: Liter) -> Self {
+        Self {
+            solvent,
+            solutes: Vec::new(),
+            volume,
+        }
+    }
+
+    /// The molecule of water, which most things are dissolved in
+    pub fn water_molecule() -> Compound {
+        "H2O".parse().unwrap()
+    }
+
+    /// `volume` of pure water
+    pub fn water(volume: Liter) -> Self {
+        Self::new(Self::water_molecule(), volume)
+    }
+
+    /// Dissolve `moles` more of `solute`, without changing the volume
+    pub fn dissolve(&mut self, solute: Compound, moles: Mole) {
+        if let Some((_, dissolved)) = self.solutes.iter_mut().find(|(s, _)| *s == solute) {
+            *dissolved += moles;
+        } else {
+            self.solutes.push((solute, moles));
+        }
+    }
+
+    /// Moles of `solute` dissolved in it
+    pub fn moles(&self, solute: &Compound) -> Mole {
+        (self.solutes.iter())
+            .find(|(s, _)| s == solute)
+            .map_or(Mole::ZERO, |&(_, moles)| moles)
+    }
+
+    /// Take up to `moles` of `solute` back out, returning how much there was to take
+    pub fn remove(&mut self, solute: &Compound, moles: Mole) -> Mole {
+        let Some(idx) = self.solutes.iter().position(|(s, _)| s == solute) else {
+            return Mole::ZERO;
+        };
+        let dissolved = &mut self.solutes[idx].1;
+        let taken = if moles < *dissolved {
+            moles
+        } else {
+            *dissolved
+        };
+        *dissolved -= taken;
+        if *dissolved == Mole::ZERO {
+            self.solutes.remove(idx);
+        }
+        taken
+    }
+
+    /// Concentration of `solute`, or [`None`] if there's no solution at all
+    pub fn molarity(&self, solute: &Compound) -> Option<Molar> {
+        self.moles(solute).per(self.volume)
+    }
+
+    /// Add solvent until it's `volume` liters, which lowers every concentration in proportion
+    ///
+    /// # Errors
+    ///
+    /// If `volume` is less than it already is
+    pub fn dilute(&mut self, volume: Liter) -> Result<(), SolutionError> {
+        if volume < self.volume {
+            return Err(SolutionError::Concentrating {
+                volume: self.volume,
+                target: volume,
+            });
+        }
+        self.volume = volume;
+        Ok(())
+    }
+
+    /// Pour `other` in, adding up the volumes and what's dissolved in each
+    ///
+    /// # Errors
+    ///
+    /// If the two aren't in the same solvent
+    pub fn mix(&mut self, other: Self) -> Result<(), SolutionError> {
+        if self.solvent != other.solvent {
+            return Err(SolutionError::DifferentSolvents {
+                lhs: self.solvent.clone(),
+                rhs: other.solvent,
+            });
+        }
+        self.volume += other.volume;
+        for (solute, moles) in other.solutes {
+            self.dissolve(solute, moles);
+        }
+        Ok(())
+    }
+
+    /// Pour out up to `volume` liters, with the same concentrations as the rest
+    pub fn split(&mut self, volume: Liter) -> Self {
+        let volume = if volume < self.volume {
+            volume
+        } else {
+            self.volume
+        };
+        let fraction = if self.volume.get() > 0.0 {
+            volume / self.volume
+        } else {
+            0.0
+        };
+        let solutes = (self.solutes.iter_mut())
+            .map(|(solute, moles)| {
+                let taken = *moles * fraction;
+                *moles -= taken;
+                (solute.clone(), taken)
+            })
+            .collect();
+        self.volume -= volume;
+        Self {
+            solvent: self.solvent.clone(),
+            solutes,
+            volume,
+        }
+    }
+
+    /// Moles of each solute over what stays dissolved at `temperature`
+    ///
+    /// Solutes whose [`solubility`] isn't known never come out of solution.
+    pub fn excess(&self, temperature: Kelvin) -> Vec<(Compound, Mole)> {
+        (self.solutes.iter())
+            .filter_map(|(solute, moles)| {
+                let limit = solubility(solute, &self.solvent, temperature)?;
+                let over = moles.checked_sub(limit.in_volume(self.volume))?;
+                (over > Mole::ZERO).then(|| (solute.clone(), over))
+            })
+            .collect()
+    }
+
+    /// Whether anything's dissolved past what stays dissolved at `temperature`
+    pub fn is_supersaturated(&self, temperature: Kelvin) -> bool {
+        !self.excess(temperature).is_empty()
+    }
+
+    /// Take out whatever won't stay dissolved at `temperature`, as solid crystals
+    pub fn precipitate(&mut self, temperature: Kelvin) -> Vec<(Compound, Mole)> {
+        let excess = self.excess(temperature);
+        for (solute, over) in &excess {
+            if let Some((_, moles)) = self.solutes.iter_mut().find(|(s, _)| s == solute) {
+                *moles -= *over;
+            }
+        }
+        excess
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solution() {
+        let salt = "NaCl".parse::<Compound>().unwrap();
+        let mut brine = Solution::water(Liter::new(0.5).unwrap());
+        brine.dissolve(salt.clone(), Mole::new(1.0).unwrap());
+        assert!((brine.molarity(&salt).unwrap().get() - 2.0).abs() < 1e-9);
+
+        // Diluting 0.5 L of 2 M down to 0.5 M takes 2 L in all
+        let volume = dilution_volume(
+            Molar::new(2.0).unwrap(),
+            brine.volume,
+            Molar::new(0.5).unwrap(),
+        )
+        .unwrap();
+        assert!((volume.get() - 2.0).abs() < 1e-9);
+        brine.dilute(volume).unwrap();
+        assert!((brine.molarity(&salt).unwrap().get() - 0.5).abs() < 1e-9);
+        assert!(brine.dilute(Liter::new(1.0).unwrap()).is_err());
+
+        let half = brine.split(Liter::new(1.0).unwrap());
+        assert_eq!(half.moles(&salt), Mole::new(0.5).unwrap());
+        assert_eq!(half.molarity(&salt), brine.molarity(&salt));
+        brine.mix(half).unwrap();
+        assert_eq!(brine.moles(&salt), Mole::new(1.0).unwrap());
+        let ethanol = Solution::new("C2H6O".parse().unwrap(), Liter::new(1.0).unwrap());
+        assert!(brine.mix(ethanol).is_err());
+
+        // About 6.1 M of salt dissolves in cold water, and a bit more in hot
+        let cold = Kelvin::from_celsius(0.0).unwrap();
+        let hot = Kelvin::from_celsius(90.0).unwrap();
+        let water = Solution::water_molecule();
+        let limit = solubility(&salt, &water, cold).unwrap();
+        assert!((limit.get() - 6.11).abs() < 0.01);
+        assert!(solubility(&salt, &water, hot).unwrap() > limit);
+
+        let saltpeter = "KNO3".parse::<Compound>().unwrap();
+        let mut hot_solution = Solution::water(Liter::new(1.0).unwrap());
+        hot_solution.dissolve(saltpeter.clone(), Mole::new(10.0).unwrap());
+        assert!(!hot_solution.is_supersaturated(hot));
+        let crystals = hot_solution.precipitate(cold);
+        assert_eq!(crystals.len(), 1, "cooling it should crystallize some out");
+        let left = hot_solution.molarity(&saltpeter).unwrap();
+        assert!((left.get() - solubility(&saltpeter, &water, cold).unwrap().get()).abs() < 1e-9);
+    }
+}
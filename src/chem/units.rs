@@ -12,6 +12,418 @@ pub const M_PER_PM: f64 = PM_PER_M.recip();
 /// Mass of a single proton in AMU
 pub const PROTON_MASS: f64 = 1.007_276_466_879_91;
 /// Mass of a single neutron in AMU
-pub const NEUTRON_MASS: f64 = 1.008_106;
+pub const NEUTRON_MASS: f64 = 1.008_664_915_95;
 /// Mass of a single electron in AMU
 pub const ELECTRON_MASS: f64 = 5.485_799_090_701_6e-4;
+/// Mass of a neutral hydrogen-1 atom in AMU
+pub const HYDROGEN_ATOM_MASS: f64 = 1.007_825_032_23;
+
+/// Energy in mega-electronvolts equivalent to one AMU of mass
+pub const MEV_PER_AMU: f64 = 931.494_102_42;
+
+/// Ideal gas constant in joules per mole kelvin (J/(mol·K))
+pub const GAS_CONSTANT: f64 = 8.314_462_618_153_24;
+/// Pascals (Pa) per standard atmosphere (atm)
+pub const PA_PER_ATM: f64 = 101_325.0;
+/// Kelvin at 0 degrees Celsius
+pub const ZERO_CELSIUS: f64 = 273.15;
+/// Liters (L) per cubic meter (m³)
+pub const L_PER_M3: f64 = 1000.0;
+
+/// SI prefixes from pico to tera, as (exponent, symbol)
+const SI_PREFIXES: [(i32, &str); 9] = [
+    (-12, "p"),
+    (-9, "n"),
+    (-6, "µ"),
+    (-3, "m"),
+    (0, ""),
+    (3, "k"),
+    (6, "M"),
+    (9, "G"),
+    (12, "T"),
+];
+
+/// Write `value` scaled to the SI prefix that keeps it within `[1, 1000)` where possible
+fn fmt_si(value: f64, symbol: &str, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let magnitude = value.abs();
+    let (exp, prefix) = if magnitude == 0.0 || !magnitude.is_finite() {
+        (0, "")
+    } else {
+        SI_PREFIXES
+            .iter()
+            .rev()
+            .copied()
+            .find(|&(exp, _)| magnitude >= 10f64.powi(exp))
+            .unwrap_or(SI_PREFIXES[0])
+    };
+    let scaled = value / 10f64.powi(exp);
+    if let Some(precision) = f.precision() {
+        write!(f, "{scaled:.precision$} {prefix}{symbol}")
+    } else {
+        write!(f, "{scaled} {prefix}{symbol}")
+    }
+}
+
+macro_rules! units {
+    ($(
+        $(#[$meta:meta])*
+        $Unit:ident($symbol:literal, non_negative: $non_negative:literal);
+    )*) => {$(
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+        pub struct $Unit(f64);
+
+        impl $Unit {
+            pub const ZERO: Self = Self(0.0);
+
+            /// Whether it can't be negative, in which case taking too much away leaves zero
+            pub const NON_NEGATIVE: bool = $non_negative;
+
+            /// The unit symbol, without any prefix
+            pub const SYMBOL: &str = $symbol;
+
+            /// Returns [`None`] if `value` is not finite, or is negative for a unit that can't be
+            #[inline]
+            pub const fn new(value: f64) -> Option<Self> {
+                if value.is_finite() && !($non_negative && value < 0.0) {
+                    Some(Self(value))
+                } else {
+                    None
+                }
+            }
+
+            #[inline]
+            pub const fn get(self) -> f64 {
+                self.0
+            }
+
+            #[inline]
+            pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+                Self::new(self.0 + rhs.0)
+            }
+
+            #[inline]
+            pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+                Self::new(self.0 - rhs.0)
+            }
+
+            #[inline]
+            pub const fn checked_mul(self, rhs: f64) -> Option<Self> {
+                Self::new(self.0 * rhs)
+            }
+
+            #[inline]
+            pub const fn checked_div(self, rhs: f64) -> Option<Self> {
+                Self::new(self.0 / rhs)
+            }
+
+            /// `value`, or zero if it's below zero or not a number and the unit can't be negative
+            #[inline]
+            const fn saturating(value: f64) -> Self {
+                if Self::NON_NEGATIVE && (value.is_nan() || value < 0.0) {
+                    Self::ZERO
+                } else {
+                    Self(value)
+                }
+            }
+        }
+
+        impl std::ops::Add for $Unit {
+            type Output = Self;
+
+            #[inline]
+            fn add(self, rhs: Self) -> Self::Output {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl std::ops::AddAssign for $Unit {
+            #[inline]
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl std::ops::Sub for $Unit {
+            type Output = Self;
+
+            #[inline]
+            fn sub(self, rhs: Self) -> Self::Output {
+                Self::saturating(self.0 - rhs.0)
+            }
+        }
+
+        impl std::ops::SubAssign for $Unit {
+            #[inline]
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl std::ops::Mul<f64> for $Unit {
+            type Output = Self;
+
+            #[inline]
+            fn mul(self, rhs: f64) -> Self::Output {
+                Self::saturating(self.0 * rhs)
+            }
+        }
+
+        impl std::ops::Div<f64> for $Unit {
+            type Output = Self;
+
+            #[inline]
+            fn div(self, rhs: f64) -> Self::Output {
+                Self::saturating(self.0 / rhs)
+            }
+        }
+
+        /// The ratio between two quantities of the same unit
+        impl std::ops::Div for $Unit {
+            type Output = f64;
+
+            #[inline]
+            fn div(self, rhs: Self) -> Self::Output {
+                self.0 / rhs.0
+            }
+        }
+
+        impl std::iter::Sum for $Unit {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                Self(iter.map(|x| x.0).sum())
+            }
+        }
+
+        impl std::fmt::Display for $Unit {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                fmt_si(self.0, Self::SYMBOL, f)
+            }
+        }
+    )*};
+}
+
+units! {
+    /// Amount of substance
+    Mole("mol", non_negative: true);
+    /// Absolute temperature
+    Kelvin("K", non_negative: true);
+    /// Pressure
+    Pascal("Pa", non_negative: true);
+    /// Energy (may be negative, e.g. for exothermic changes)
+    Joule("J", non_negative: false);
+    /// Volume
+    Liter("L", non_negative: true);
+    /// Mass
+    Gram("g", non_negative: true);
+    /// Concentration, in moles per liter
+    Molar("M", non_negative: true);
+    /// Power, in joules per second
+    Watt("W", non_negative: true);
+}
+
+impl Mole {
+    /// A mole for each of `count` items, which is how much of a substance one item is
+    #[inline]
+    pub fn from_count(count: u32) -> Self {
+        Self(f64::from(count))
+    }
+
+    /// Number of particles
+    #[inline]
+    pub const fn particles(self) -> f64 {
+        // AVOGADROS_NUMBER is per kilomole
+        self.0 * AVOGADROS_NUMBER * 1e-3
+    }
+
+    /// Concentration of this many moles dissolved in `volume`
+    ///
+    /// Returns [`None`] if `volume` is zero.
+    #[inline]
+    pub const fn per(self, volume: Liter) -> Option<Molar> {
+        Molar::new(self.0 / volume.0)
+    }
+
+    /// Mass of this many moles of a substance with `molar_mass` grams per mole
+    #[inline]
+    pub const fn to_grams(self, molar_mass: f64) -> Gram {
+        Gram(self.0 * molar_mass)
+    }
+}
+
+impl Gram {
+    /// Moles of a substance with `molar_mass` grams per mole
+    ///
+    /// Returns [`None`] if `molar_mass` is not positive.
+    #[inline]
+    pub const fn to_moles(self, molar_mass: f64) -> Option<Mole> {
+        if molar_mass > 0.0 {
+            Mole::new(self.0 / molar_mass)
+        } else {
+            None
+        }
+    }
+}
+
+impl Molar {
+    /// Moles in `volume` of solution at this concentration
+    #[inline]
+    pub const fn in_volume(self, volume: Liter) -> Mole {
+        Mole(self.0 * volume.0)
+    }
+}
+
+impl Joule {
+    /// Power to deliver this much energy over `seconds`
+    ///
+    /// Returns [`None`] if the energy is negative or `seconds` isn't positive.
+    #[inline]
+    pub const fn over(self, seconds: f64) -> Option<Watt> {
+        if seconds > 0.0 {
+            Watt::new(self.0 / seconds)
+        } else {
+            None
+        }
+    }
+}
+
+impl Kelvin {
+    /// Returns [`None`] if below absolute zero
+    #[inline]
+    pub const fn from_celsius(celsius: f64) -> Option<Self> {
+        Self::new(celsius + ZERO_CELSIUS)
+    }
+
+    #[inline]
+    pub const fn to_celsius(self) -> f64 {
+        self.0 - ZERO_CELSIUS
+    }
+}
+
+impl Pascal {
+    #[inline]
+    pub const fn from_atm(atm: f64) -> Option<Self> {
+        Self::new(atm * PA_PER_ATM)
+    }
+
+    #[inline]
+    pub const fn to_atm(self) -> f64 {
+        self.0 / PA_PER_ATM
+    }
+}
+
+impl Liter {
+    #[inline]
+    pub const fn from_cubic_meters(m3: f64) -> Option<Self> {
+        Self::new(m3 * L_PER_M3)
+    }
+
+    #[inline]
+    pub const fn to_cubic_meters(self) -> f64 {
+        self.0 / L_PER_M3
+    }
+}
+
+/// Solve the ideal gas law (PV = nRT) for whichever quantity is [`None`]
+///
+/// Returns [`None`] if not exactly one quantity is missing, or if the result is not physical
+/// (e.g. dividing by a zero volume).
+pub const fn ideal_gas(
+    pressure: Option<Pascal>,
+    volume: Option<Liter>,
+    moles: Option<Mole>,
+    temperature: Option<Kelvin>,
+) -> Option<IdealGas> {
+    let (pressure, volume, moles, temperature) = match (pressure, volume, moles, temperature) {
+        (None, Some(v), Some(n), Some(t)) => {
+            match Pascal::new(n.0 * GAS_CONSTANT * t.0 / v.to_cubic_meters()) {
+                Some(p) => (p, v, n, t),
+                None => return None,
+            }
+        }
+        (Some(p), None, Some(n), Some(t)) => {
+            match Liter::from_cubic_meters(n.0 * GAS_CONSTANT * t.0 / p.0) {
+                Some(v) => (p, v, n, t),
+                None => return None,
+            }
+        }
+        (Some(p), Some(v), None, Some(t)) => {
+            match Mole::new(p.0 * v.to_cubic_meters() / (GAS_CONSTANT * t.0)) {
+                Some(n) => (p, v, n, t),
+                None => return None,
+            }
+        }
+        (Some(p), Some(v), Some(n), None) => {
+            match Kelvin::new(p.0 * v.to_cubic_meters() / (GAS_CONSTANT * n.0)) {
+                Some(t) => (p, v, n, t),
+                None => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(IdealGas {
+        pressure,
+        volume,
+        moles,
+        temperature,
+    })
+}
+
+/// The state of an ideal gas
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct IdealGas {
+    pub pressure: Pascal,
+    pub volume: Liter,
+    pub moles: Mole,
+    pub temperature: Kelvin,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_units() {
+        assert_eq!(Mole::new(-1.0), None);
+        assert_eq!(Joule::new(-1.0), Some(Joule(-1.0)));
+        assert_eq!(Kelvin(10.0).checked_sub(Kelvin(20.0)), None);
+        assert_eq!(Gram(1.0).checked_div(0.0), None);
+        assert_eq!(Kelvin::from_celsius(-300.0), None);
+
+        assert_eq!(Mole(1.0) - Mole(3.0), Mole::ZERO);
+        assert_eq!(Liter(2.0) * -1.0, Liter::ZERO);
+        assert_eq!(Molar(1.0) / f64::NAN, Molar::ZERO);
+        assert_eq!(Joule(1.0) - Joule(3.0), Joule(-2.0));
+        let mut moles = Mole(1.0);
+        moles -= Mole(1.5);
+        assert_eq!(moles, Mole::ZERO);
+    }
+
+    #[test]
+    fn test_si_display() {
+        assert_eq!(Pascal(101_325.0).to_string(), "101.325 kPa");
+        assert_eq!(format!("{:.1}", Liter(0.0025)), "2.5 mL");
+        assert_eq!(format!("{:.2}", Joule(-4_500_000.0)), "-4.50 MJ");
+        assert_eq!(Gram(0.0).to_string(), "0 g");
+    }
+
+    #[test]
+    fn test_ideal_gas() {
+        // One mole at STP is about 22.4 liters
+        let gas = ideal_gas(
+            Pascal::from_atm(1.0),
+            None,
+            Some(Mole(1.0)),
+            Kelvin::from_celsius(0.0),
+        )
+        .unwrap();
+        assert!((gas.volume.get() - 22.414).abs() < 1e-3);
+
+        let gas = ideal_gas(Some(gas.pressure), Some(gas.volume), Some(Mole(2.0)), None).unwrap();
+        assert!((gas.temperature.to_celsius() + ZERO_CELSIUS / 2.0).abs() < 1e-9);
+
+        assert_eq!(
+            ideal_gas(None, None, Some(Mole(1.0)), Some(Kelvin(1.0))),
+            None
+        );
+    }
+}
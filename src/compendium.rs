@@ -0,0 +1,152 @@
+//! What the player has found out about chemistry
+//!
+//! The [spectrometer](crate::region::lab::Spectrometer) scans samples, and each new item it scans
+//! is [discovered](Compendium::discover) along with the elements in it. Undiscovered elements are
+//! hidden on the [periodic table](crate::region::lab::table_panel::PeriodicTablePanel), and
+//! recipes can't be set until everything that goes into them has been discovered.
+//!
+//! The [panel](panel::CompendiumPanel) has a page for everything discovered, and for each
+//! reaction that can be run with it and each machine that's been unlocked.
+//!
+//! The compendium is saved as text, with each item discovered by formula:
+//!
+//! ```text
+//! discovered Fe₂O₃ H₂O
+//! ```
+
+pub mod panel;
+
+use crate::{
+    chem::{element::Element, formula::ParseFormulaError, reaction::BalancedReaction},
+    container::Item,
+    region::factory::recipe::Recipe,
+};
+use std::{collections::BTreeSet, str::FromStr};
+
+/// The items that have been discovered
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Compendium {
+    discovered: BTreeSet<Item>,
+}
+
+impl Compendium {
+    /// Add `item`, returning whether it's new
+    pub fn discover(&mut self, item: &Item) -> bool {
+        !self.discovered.contains(item) && self.discovered.insert(item.clone())
+    }
+
+    #[inline]
+    pub fn is_discovered(&self, item: &Item) -> bool {
+        self.discovered.contains(item)
+    }
+
+    /// Whether something with `element` in it has been discovered
+    pub fn knows_element(&self, element: Element) -> bool {
+        (self.discovered.iter()).any(|item| item.element_counts().contains_key(&element))
+    }
+
+    /// Every element in something that's been discovered
+    pub fn elements(&self) -> BTreeSet<Element> {
+        (self.discovered.iter())
+            .flat_map(|item| item.element_counts().into_keys())
+            .collect()
+    }
+
+    /// Every item discovered
+    pub fn items(&self) -> impl Iterator<Item = &Item> {
+        self.discovered.iter()
+    }
+
+    /// Whether everything that goes into `reaction` has been discovered
+    pub fn knows_reaction(&self, reaction: &BalancedReaction) -> bool {
+        (reaction.reactants().iter()).all(|(_, item)| self.is_discovered(item))
+    }
+
+    /// Whether everything that goes into `recipe` has been discovered
+    #[inline]
+    pub fn knows_recipe(&self, recipe: &Recipe) -> bool {
+        self.knows_reaction(recipe.reaction())
+    }
+}
+
+impl std::fmt::Display for Compendium {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("discovered")?;
+        for item in &self.discovered {
+            write!(f, " {item}")?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Why text couldn't be read as a [`Compendium`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCompendiumError {
+    /// Doesn't start with `discovered`
+    MissingHeader,
+    /// Contains the formula that couldn't be read, and why
+    InvalidItem(String, ParseFormulaError),
+}
+
+impl std::fmt::Display for ParseCompendiumError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader => f.write_str("not a compendium"),
+            Self::InvalidItem(formula, e) => write!(f, "invalid item `{formula}`: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseCompendiumError {}
+
+impl FromStr for Compendium {
+    type Err = ParseCompendiumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        if words.next() != Some("discovered") {
+            return Err(ParseCompendiumError::MissingHeader);
+        }
+        let discovered = words
+            .map(|formula| {
+                (formula.parse())
+                    .map_err(|e| ParseCompendiumError::InvalidItem(formula.to_string(), e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { discovered })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::factory::recipe;
+
+    #[test]
+    fn test_compendium() {
+        let mut compendium = Compendium::default();
+        let rust = "Fe2O3".parse().unwrap();
+        assert!(compendium.discover(&rust));
+        assert!(!compendium.discover(&rust), "already discovered");
+        assert!(compendium.knows_element(Element::O));
+        assert!(!compendium.knows_element(Element::H));
+        assert_eq!(
+            compendium.elements().into_iter().collect::<Vec<_>>(),
+            [Element::O, Element::Fe]
+        );
+
+        let circuit_board = recipe::find("Circuit board").unwrap();
+        assert!(!compendium.knows_recipe(circuit_board));
+        compendium.discover(&"Cu".parse().unwrap());
+        compendium.discover(&"Si".parse().unwrap());
+        assert!(compendium.knows_recipe(circuit_board));
+
+        assert_eq!(compendium.to_string(), "discovered Si Cu Fe₂O₃\n");
+        assert_eq!(compendium.to_string().parse(), Ok(compendium));
+        assert_eq!("discovered".parse(), Ok(Compendium::default()));
+        assert!(matches!(
+            "discovered Xx2".parse::<Compendium>(),
+            Err(ParseCompendiumError::InvalidItem(..))
+        ));
+    }
+}
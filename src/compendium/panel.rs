@@ -0,0 +1,668 @@
+//! The compendium panel, an encyclopedia of everything that's been discovered
+
+use super::Compendium;
+use crate::{
+    chem::{
+        element::Element, orbital::ElectronConfig, phase::Phase, reaction::BalancedReaction,
+        units::Kelvin,
+    },
+    container::Item,
+    game::GameState,
+    input::Inputs,
+    locale::tr,
+    plugin,
+    region::factory::{
+        MachineKind,
+        electrolysis::{self, Electrolysis},
+        recipe::{self, Recipe},
+        thermal,
+    },
+    research::{Tech, Unlock},
+    ui::Panel,
+};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer, RenderingOptions},
+};
+use raylib::prelude::*;
+
+const MARGIN: f32 = 16.0;
+const FONT_SIZE: f32 = 20.0;
+const ROW_HEIGHT: f32 = FONT_SIZE + 8.0;
+const LIST_WIDTH: f32 = 280.0;
+const PAGE_WIDTH: f32 = 520.0;
+/// How many entries are listed at once, scrolling to keep the selected one in view
+const VISIBLE_ROWS: usize = 16;
+
+const BACKGROUND: Color = Color::new(16, 16, 24, 230);
+const HOVERED: Color = Color::new(255, 255, 255, 32);
+const SELECTED: Color = Color::new(96, 160, 224, 255);
+const LINK: Color = Color::new(128, 192, 255, 255);
+
+/// Something the compendium has a page for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    Element(Element),
+    Item(Item),
+    /// The [`Recipe`] with this [name](Recipe::name)
+    Recipe(&'static str),
+    /// The [electrolysis](electrolysis::built_in) with this name
+    Electrolysis(&'static str),
+    Machine(MachineKind),
+}
+
+impl Entry {
+    /// What the entry is listed as, and searched by
+    pub fn title(&self) -> String {
+        match self {
+            Self::Element(element) => format!("{} ({})", element.local_name(), element.symbol()),
+            Self::Item(item) => item.to_string(),
+            Self::Recipe(name) => (*name).to_string(),
+            Self::Electrolysis(name) => tr!("compendium_panel.electrolysis", name = name),
+            Self::Machine(kind) => kind.name(),
+        }
+    }
+}
+
+/// One line of a page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    /// Starts a list of links
+    Heading(String),
+    Text(String),
+    /// Goes to the entry's page when clicked
+    Link(Entry),
+}
+
+/// Recipes whose reactants have all been discovered
+fn known_recipes(compendium: &Compendium) -> impl Iterator<Item = &'static Recipe> + '_ {
+    (recipe::built_in().iter())
+        .chain(plugin::registry().recipes())
+        .filter(|recipe| compendium.knows_recipe(recipe))
+}
+
+/// Electrolyses whose reactants have all been discovered
+fn known_electrolyses(
+    compendium: &Compendium,
+) -> impl Iterator<Item = &'static (&'static str, Electrolysis)> + '_ {
+    (electrolysis::built_in().iter())
+        .filter(|(_, electrolysis)| compendium.knows_reaction(electrolysis.reaction()))
+}
+
+/// Every reaction that's been discovered, with its entry and the machine that runs it
+fn known_reactions(
+    compendium: &Compendium,
+) -> impl Iterator<Item = (Entry, &'static BalancedReaction, MachineKind)> + '_ {
+    let recipes = known_recipes(compendium).map(|recipe| {
+        let entry = Entry::Recipe(recipe.name());
+        (entry, recipe.reaction(), MachineKind::Assembler)
+    });
+    let electrolyses = known_electrolyses(compendium).map(|(name, electrolysis)| {
+        let entry = Entry::Electrolysis(name);
+        (entry, electrolysis.reaction(), MachineKind::Electrolyzer)
+    });
+    recipes.chain(electrolyses)
+}
+
+/// Machines unlocked by research, in the order the tech tree unlocks them
+fn unlocked_machines(state: &GameState) -> impl Iterator<Item = MachineKind> + '_ {
+    (Tech::ALL.iter())
+        .filter(|tech| state.research.is_unlocked(tech.id))
+        .flat_map(|tech| tech.unlocks)
+        .filter_map(|unlock| match *unlock {
+            Unlock::Machine(kind) => Some(kind),
+            Unlock::Belt(_) | Unlock::Recipe(_) | Unlock::Circuits => None,
+        })
+}
+
+/// Everything the compendium has a page for: elements, items, reactions, then machines
+pub fn entries(state: &GameState) -> Vec<Entry> {
+    let compendium = &state.compendium;
+    (compendium.elements().into_iter().map(Entry::Element))
+        .chain(compendium.items().cloned().map(Entry::Item))
+        .chain(known_reactions(compendium).map(|(entry, ..)| entry))
+        .chain(unlocked_machines(state).map(Entry::Machine))
+        .collect()
+}
+
+/// Add `heading` and a line for each of `lines` to `page`, unless there aren't any
+fn section(page: &mut Vec<Line>, heading: &str, lines: impl IntoIterator<Item = Line>) {
+    let mut lines = lines.into_iter().peekable();
+    if lines.peek().is_some() {
+        page.push(Line::Heading(tr!(heading)));
+        page.extend(lines);
+    }
+}
+
+/// A link to `item`'s page if it's been discovered, or just its formula
+fn item_line(item: &Item, compendium: &Compendium) -> Line {
+    if compendium.is_discovered(item) {
+        Line::Link(Entry::Item(item.clone()))
+    } else {
+        Line::Text(item.to_string())
+    }
+}
+
+/// Where something melts and boils, for whichever of them are known
+fn phase_lines(melting: Option<Kelvin>, boiling: Option<Kelvin>) -> impl Iterator<Item = Line> {
+    let melting = melting.map(|t| tr!("compendium_panel.melting_point", temperature = t));
+    let boiling = boiling.map(|t| tr!("compendium_panel.boiling_point", temperature = t));
+    melting.into_iter().chain(boiling).map(Line::Text)
+}
+
+fn element_page(element: Element, compendium: &Compendium) -> Vec<Line> {
+    let category = tr!(if element.is_noble_gas() {
+        "table_panel.noble_gas"
+    } else if element.is_metal() {
+        "table_panel.metal"
+    } else {
+        "table_panel.nonmetal"
+    });
+    let weight = format!("{:.3}", element.standard_atomic_weight());
+    let config = ElectronConfig::new(element.protons().get());
+    let mut page = vec![
+        Line::Text(category),
+        Line::Text(tr!(
+            "compendium_panel.atomic_number",
+            number = element.protons()
+        )),
+        Line::Text(tr!("compendium_panel.atomic_weight", weight = weight)),
+        Line::Text(tr!("compendium_panel.electron_config", config = config)),
+    ];
+    page.extend(phase_lines(
+        element.melting_point(),
+        element.boiling_point(),
+    ));
+    let found_in = (compendium.items())
+        .filter(|item| item.element_counts().contains_key(&element))
+        .map(|item| Line::Link(Entry::Item(item.clone())));
+    section(&mut page, "compendium_panel.found_in", found_in);
+    page
+}
+
+fn item_page(item: &Item, compendium: &Compendium) -> Vec<Line> {
+    let mass = format!("{:.3}", item.molar_mass());
+    let mut page = vec![Line::Text(tr!("compendium_panel.molar_mass", mass = mass))];
+    let compound = item.compound();
+    if let Some(phase) = compound.phase(thermal::AMBIENT) {
+        let phase = tr!(match phase {
+            Phase::Solid => "compendium_panel.solid",
+            Phase::Liquid => "compendium_panel.liquid",
+            Phase::Gas => "compendium_panel.gas",
+        });
+        page.push(Line::Text(tr!("compendium_panel.phase", phase = phase)));
+    }
+    page.extend(phase_lines(
+        compound.melting_point(),
+        compound.boiling_point(),
+    ));
+    let elements = (item.element_counts().into_keys()).map(|e| Line::Link(Entry::Element(e)));
+    section(&mut page, "compendium_panel.elements", elements);
+    let has = |side: &[(_, Item)]| side.iter().any(|(_, species)| species == item);
+    let made_by = (known_reactions(compendium))
+        .filter(|(_, reaction, _)| has(reaction.products()))
+        .map(|(entry, ..)| Line::Link(entry));
+    section(&mut page, "compendium_panel.made_by", made_by);
+    let used_by = (known_reactions(compendium))
+        .filter(|(_, reaction, _)| has(reaction.reactants()))
+        .map(|(entry, ..)| Line::Link(entry));
+    section(&mut page, "compendium_panel.used_by", used_by);
+    page
+}
+
+/// The page of a reaction, with `about` it after its equation
+fn reaction_page(
+    reaction: &BalancedReaction,
+    about: Vec<Line>,
+    machine: MachineKind,
+    compendium: &Compendium,
+) -> Vec<Line> {
+    let mut page = vec![Line::Text(reaction.to_string())];
+    page.extend(about);
+    let side = |side: &[(_, Item)]| {
+        (side.iter())
+            .map(|(_, item)| item_line(item, compendium))
+            .collect::<Vec<_>>()
+    };
+    section(
+        &mut page,
+        "compendium_panel.reactants",
+        side(reaction.reactants()),
+    );
+    section(
+        &mut page,
+        "compendium_panel.products",
+        side(reaction.products()),
+    );
+    // Balanced, so the products are made of the same elements
+    let elements = (reaction.reactants().iter())
+        .flat_map(|(_, item)| item.element_counts().into_keys())
+        .collect::<std::collections::BTreeSet<_>>();
+    section(
+        &mut page,
+        "compendium_panel.elements",
+        (elements.into_iter()).map(|element| Line::Link(Entry::Element(element))),
+    );
+    section(
+        &mut page,
+        "compendium_panel.made_in",
+        [Line::Link(Entry::Machine(machine))],
+    );
+    page
+}
+
+fn machine_page(kind: MachineKind, compendium: &Compendium) -> Vec<Line> {
+    let mut page = Vec::new();
+    if let Some(tech) =
+        (Tech::ALL.iter()).find(|tech| tech.unlocks.contains(&Unlock::Machine(kind)))
+    {
+        page.push(Line::Text(tr!(
+            "compendium_panel.unlocked_by",
+            tech = tech.name
+        )));
+    }
+    let runs = (known_reactions(compendium))
+        .filter(|&(_, _, machine)| machine == kind)
+        .map(|(entry, ..)| Line::Link(entry));
+    section(&mut page, "compendium_panel.runs", runs);
+    page
+}
+
+/// What's on `entry`'s page, under its title
+pub fn page(entry: &Entry, compendium: &Compendium) -> Vec<Line> {
+    match entry {
+        &Entry::Element(element) => element_page(element, compendium),
+        Entry::Item(item) => item_page(item, compendium),
+        Entry::Recipe(name) => recipe::find(name).map_or_else(Vec::new, |recipe| {
+            let about = vec![
+                Line::Text(tr!(
+                    "compendium_panel.duration",
+                    seconds = recipe.duration.as_secs_f64()
+                )),
+                Line::Text(tr!("compendium_panel.heat", heat = recipe.heat)),
+            ];
+            reaction_page(recipe.reaction(), about, MachineKind::Assembler, compendium)
+        }),
+        Entry::Electrolysis(name) => electrolysis::find(name).map_or_else(Vec::new, |process| {
+            let about = vec![Line::Text(tr!(
+                "compendium_panel.power",
+                power = process.power()
+            ))];
+            reaction_page(
+                process.reaction(),
+                about,
+                MachineKind::Electrolyzer,
+                compendium,
+            )
+        }),
+        &Entry::Machine(kind) => machine_page(kind, compendium),
+    }
+}
+
+/// A list of everything discovered, and the page of the selected entry, whose links go to the
+/// pages of what they name
+///
+/// Typing filters the list by name or formula, and backspace with nothing typed goes back to the
+/// last page. Up and down select an entry too.
+#[derive(Debug, Clone, Default)]
+pub struct CompendiumPanel {
+    /// Filters which entries are listed
+    pub search: String,
+    /// The entries matching the search
+    entries: Vec<Entry>,
+    /// The entry whose page is shown, if there are any
+    selected: Option<Entry>,
+    page: Vec<Line>,
+    /// Pages gone to from links, the last one most recently
+    back: Vec<Entry>,
+    /// The first entry listed
+    scroll: usize,
+    /// The row of the list under the mouse cursor
+    hovered_row: Option<usize>,
+    /// The line of the page under the mouse cursor
+    hovered_line: Option<usize>,
+    /// Screen-space area of the whole panel
+    rect: Rectangle,
+}
+
+impl CompendiumPanel {
+    /// An empty search, showing the first entry
+    pub fn new(rl: &RaylibHandle, state: &GameState) -> Self {
+        let mut panel = Self::default();
+        panel.refresh(state);
+        panel.layout(rl);
+        panel
+    }
+
+    /// Whether `entry` matches [`Self::search`]
+    ///
+    /// An empty search matches everything. Otherwise it matches anywhere in the title, ignoring
+    /// case, or an item with the same formula however it's written, like `H2O` for H₂O.
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let search = self.search.trim();
+        if search.is_empty() {
+            return true;
+        }
+        if let Entry::Item(item) = entry
+            && search.parse::<Item>().as_ref() == Ok(item)
+        {
+            return true;
+        }
+        (entry.title().to_lowercase()).contains(&search.to_lowercase())
+    }
+
+    /// List the entries in `state` matching the search, and copy out the selected one's page
+    fn refresh(&mut self, state: &GameState) {
+        self.entries = (entries(state).into_iter())
+            .filter(|entry| self.matches(entry))
+            .collect();
+        if self.selected.is_none() {
+            self.selected = self.entries.first().cloned();
+        }
+        self.page =
+            (self.selected.as_ref()).map_or_else(Vec::new, |entry| page(entry, &state.compendium));
+    }
+
+    /// Show `entry`'s page, going back to the one shown now on backspace
+    fn open(&mut self, entry: Entry) {
+        if let Some(current) = self.selected.replace(entry)
+            && self.selected.as_ref() != Some(&current)
+        {
+            self.back.push(current);
+        }
+    }
+
+    /// The index of the selected entry in the list
+    fn selected_row(&self) -> Option<usize> {
+        let selected = self.selected.as_ref()?;
+        self.entries.iter().position(|entry| entry == selected)
+    }
+
+    /// Select the entry in `row`, scrolling it into view
+    fn select(&mut self, row: usize) {
+        let Some(entry) = self.entries.get(row) else {
+            return;
+        };
+        self.selected = Some(entry.clone());
+        self.scroll = self
+            .scroll
+            .clamp((row + 1).saturating_sub(VISIBLE_ROWS), row);
+    }
+
+    /// Center the panel on the screen
+    #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+    fn layout(&mut self, rl: &RaylibHandle) {
+        let width = LIST_WIDTH + PAGE_WIDTH + 3.0 * MARGIN;
+        let height = 2.0 * ROW_HEIGHT + VISIBLE_ROWS as f32 * ROW_HEIGHT + 4.0 * MARGIN;
+        self.rect = Rectangle::new(
+            0.5 * (rl.get_screen_width() as f32 - width),
+            0.5 * (rl.get_screen_height() as f32 - height),
+            width,
+            height,
+        );
+    }
+
+    /// Screen-space area of the search box, under the title
+    fn search_rect(&self) -> Rectangle {
+        Rectangle::new(
+            self.rect.x + MARGIN,
+            self.rect.y + 2.0 * MARGIN + ROW_HEIGHT,
+            LIST_WIDTH,
+            ROW_HEIGHT,
+        )
+    }
+
+    /// The top of the list and page
+    const fn body_top(&self) -> f32 {
+        self.rect.y + 3.0 * MARGIN + 2.0 * ROW_HEIGHT
+    }
+
+    /// Screen-space area of `row` in the list, if it's scrolled into view
+    #[allow(clippy::cast_precision_loss, reason = "there are only a few rows")]
+    fn row_rect(&self, row: usize) -> Option<Rectangle> {
+        let shown = row.checked_sub(self.scroll).filter(|&i| i < VISIBLE_ROWS)?;
+        Some(Rectangle::new(
+            self.rect.x + MARGIN,
+            self.body_top() + shown as f32 * ROW_HEIGHT,
+            LIST_WIDTH,
+            ROW_HEIGHT,
+        ))
+    }
+
+    /// Screen-space area of the page, below its title
+    #[allow(clippy::cast_precision_loss, reason = "there are only a few rows")]
+    fn page_rect(&self) -> Rectangle {
+        Rectangle::new(
+            self.rect.x + 2.0 * MARGIN + LIST_WIDTH,
+            self.rect.y + 2.0 * MARGIN + ROW_HEIGHT,
+            PAGE_WIDTH,
+            (VISIBLE_ROWS + 1) as f32 * ROW_HEIGHT + MARGIN,
+        )
+    }
+
+    /// Screen-space area of `line` of the page
+    #[allow(clippy::cast_precision_loss, reason = "pages are short")]
+    fn line_rect(&self, line: usize) -> Rectangle {
+        let page = self.page_rect();
+        Rectangle::new(
+            page.x,
+            page.y + (line + 1) as f32 * ROW_HEIGHT,
+            page.width,
+            ROW_HEIGHT,
+        )
+    }
+
+    /// Type into the search, or go back a page on backspace if there's nothing to delete
+    fn type_search(&mut self, rl: &mut RaylibHandle) {
+        while let Some(ch) = rl.get_char_pressed() {
+            if !ch.is_control() {
+                self.search.push(ch);
+                self.scroll = 0;
+            }
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE)
+            || rl.is_key_pressed_repeat(KeyboardKey::KEY_BACKSPACE)
+        {
+            if self.search.pop().is_some() {
+                self.scroll = 0;
+            } else if let Some(entry) = self.back.pop() {
+                self.selected = Some(entry);
+            }
+        }
+    }
+
+    /// Draw the search box and the entries matching it
+    fn draw_list(&self, d: &mut Renderer<'_>) -> draw::Result {
+        let search = self.search_rect();
+        d.draw_rectangle_lines(search, Some(2.0), Color::LIGHTGRAY)?;
+        let (search_text, search_color) = if self.search.is_empty() {
+            (tr!("compendium_panel.search"), Color::GRAY)
+        } else {
+            (self.search.clone(), Color::WHITE)
+        };
+        let text_y = |rect: Rectangle| rect.y + 0.5 * (rect.height - FONT_SIZE);
+        d.draw_text(
+            &search_text,
+            Vector2::new(search.x + 8.0, text_y(search)),
+            FONT_SIZE,
+            0.0,
+            search_color,
+        )?;
+        if self.entries.is_empty() {
+            let position = Vector2::new(self.rect.x + MARGIN, self.body_top());
+            let empty = tr!("compendium_panel.empty");
+            return d.draw_text(&empty, position, FONT_SIZE, 0.0, Color::GRAY);
+        }
+        let selected = self.selected_row();
+        for (row, entry) in self.entries.iter().enumerate() {
+            let Some(rect) = self.row_rect(row) else {
+                continue;
+            };
+            if selected == Some(row) {
+                d.draw_rectangle(rect, SELECTED)?;
+            } else if self.hovered_row == Some(row) {
+                d.draw_rectangle(rect, HOVERED)?;
+            }
+            d.draw_text(
+                &entry.title(),
+                Vector2::new(rect.x + 8.0, text_y(rect)),
+                FONT_SIZE,
+                0.0,
+                Color::WHITE,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draw the selected entry's title and page, with its links underlined
+    fn draw_page(&self, d: &mut Renderer<'_>) -> draw::Result {
+        let Some(entry) = &self.selected else {
+            return Ok(());
+        };
+        let page = self.page_rect();
+        // Long pages are cut off rather than spilling out of the panel
+        d.push_layer(RenderingOptions::new().clip(page));
+        d.draw_text(
+            &entry.title(),
+            Vector2::new(page.x, page.y),
+            1.25 * FONT_SIZE,
+            0.0,
+            Color::WHITE,
+        )?;
+        let font = d.font().ok_or(draw::Error)?;
+        for (idx, line) in self.page.iter().enumerate() {
+            let rect = self.line_rect(idx);
+            let position = Vector2::new(rect.x, rect.y + 0.5 * (rect.height - FONT_SIZE));
+            match line {
+                Line::Heading(text) => {
+                    d.draw_text(text, position, FONT_SIZE, 0.0, Color::GRAY)?;
+                }
+                Line::Text(text) => {
+                    d.draw_text(text, position, FONT_SIZE, 0.0, Color::WHITE)?;
+                }
+                Line::Link(entry) => {
+                    if self.hovered_line == Some(idx) {
+                        d.draw_rectangle(rect, HOVERED)?;
+                    }
+                    let title = entry.title();
+                    let indented = position + Vector2::new(MARGIN, 0.0);
+                    d.draw_text(&title, indented, FONT_SIZE, 0.0, LINK)?;
+                    let width = font.measure_text(&title, FONT_SIZE, 0.0).x;
+                    let under = indented + Vector2::new(0.0, FONT_SIZE);
+                    d.draw_line(under, under + Vector2::new(width, 0.0), Some(1.0), LINK)?;
+                }
+            }
+        }
+        d.pop_layer()
+    }
+}
+
+impl Panel for CompendiumPanel {
+    /// Handle typing, selecting entries, and following links
+    ///
+    /// Interacting doesn't close the panel, since it's also typed into the search.
+    fn update(&mut self, rl: &mut RaylibHandle, _inputs: &Inputs, state: &mut GameState) -> bool {
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            return false;
+        }
+        self.type_search(rl);
+        self.layout(rl);
+        self.refresh(state);
+
+        let selected = self.selected_row().unwrap_or(0);
+        if rl.is_key_pressed(KeyboardKey::KEY_DOWN)
+            || rl.is_key_pressed_repeat(KeyboardKey::KEY_DOWN)
+        {
+            self.select(selected + 1);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_UP) || rl.is_key_pressed_repeat(KeyboardKey::KEY_UP) {
+            self.select(selected.saturating_sub(1));
+        }
+
+        let mouse = rl.get_mouse_position();
+        self.hovered_row = (0..self.entries.len()).find(|&row| {
+            self.row_rect(row)
+                .is_some_and(|rect| rect.check_collision_point_rec(mouse))
+        });
+        self.hovered_line = (0..self.page.len()).find(|&idx| {
+            matches!(self.page[idx], Line::Link(_))
+                && self.line_rect(idx).check_collision_point_rec(mouse)
+                && self.page_rect().check_collision_point_rec(mouse)
+        });
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            if let Some(row) = self.hovered_row {
+                self.select(row);
+            } else if let Some(idx) = self.hovered_line
+                && let Line::Link(entry) = self.page[idx].clone()
+            {
+                // So the page it goes to is listed
+                self.search.clear();
+                self.open(entry);
+            }
+        }
+        true
+    }
+}
+
+impl Draw for CompendiumPanel {
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        d.draw_rectangle(self.rect, BACKGROUND)?;
+        d.draw_text(
+            &tr!("compendium_panel.title"),
+            Vector2::new(self.rect.x + MARGIN, self.rect.y + MARGIN),
+            FONT_SIZE,
+            0.0,
+            Color::WHITE,
+        )?;
+        self.draw_list(d)?;
+        self.draw_page(d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::coords::{PlayerVector3, VectorConstants},
+        player::Player,
+    };
+
+    #[test]
+    fn test_pages() {
+        let mut state = GameState::new(Player::new(PlayerVector3::ZERO, 0.0, 0.0, 45.0, 1.0));
+        for formula in ["Cu", "Si", "H2O"] {
+            state.compendium.discover(&formula.parse().unwrap());
+        }
+        let entries = entries(&state);
+        assert!(entries.contains(&Entry::Element(Element::Cu)));
+        assert!(entries.contains(&Entry::Recipe("Circuit board")));
+        assert!(entries.contains(&Entry::Electrolysis("water")));
+        assert!(!entries.contains(&Entry::Electrolysis("brine")), "no salt");
+        assert!(entries.contains(&Entry::Machine(MachineKind::Reactor)));
+        assert!(!entries.contains(&Entry::Machine(MachineKind::Assembler)));
+
+        let board = page(&Entry::Recipe("Circuit board"), &state.compendium);
+        assert!(board.contains(&Line::Link(Entry::Item("Cu".parse().unwrap()))));
+        assert!(board.contains(&Line::Link(Entry::Element(Element::Si))));
+        assert!(
+            board.contains(&Line::Text("Cu₂Si".to_string())),
+            "not discovered yet"
+        );
+        let water = page(&Entry::Item("H2O".parse().unwrap()), &state.compendium);
+        assert!(water.contains(&Line::Link(Entry::Element(Element::H))));
+        assert!(water.contains(&Line::Link(Entry::Electrolysis("water"))));
+
+        let mut panel = CompendiumPanel {
+            search: "H2O".to_string(),
+            ..CompendiumPanel::default()
+        };
+        assert!(panel.matches(&Entry::Item("H2O".parse().unwrap())));
+        panel.search = "cop".to_string();
+        assert!(panel.matches(&Entry::Element(Element::Cu)));
+        assert!(!panel.matches(&Entry::Item("Cu".parse().unwrap())));
+        panel.refresh(&state);
+        assert_eq!(panel.entries, [Entry::Element(Element::Cu)]);
+        panel.open(Entry::Element(Element::Si));
+        assert_eq!(panel.back, [Entry::Element(Element::Cu)]);
+    }
+}
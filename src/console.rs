@@ -0,0 +1,1375 @@
+use crate::{
+    chem::{formula::Formula, reaction::Reaction, units::Kelvin},
+    container::{Container, Item, ItemStack},
+    game::GameState,
+    math::{
+        bounds::Bounds,
+        coords::{PlayerVector3, VectorConstants},
+    },
+    milestone::{Milestone, Milestones, Progress},
+    ordinals::{Cardinal2D, Cardinal3D},
+    player::GasMask,
+    plugin,
+    region::{
+        RegionId,
+        factory::{
+            BeltSide, Floor, Ladder, Machine, MachineKind, Merger, Reactor, Side, SplitMode,
+            Splitter, StationError,
+            assembler::Assembler,
+            blueprint::Blueprint,
+            circuit::{
+                Arithmetic, Behavior, CircuitError, Compare, Control, Node, NodeId, Pin, Port,
+            },
+            controller::Controller,
+            drone::ChargingPad,
+            electrolysis::{self, Electrolysis, Electrolyzer},
+            gas::Vent,
+            history::Change,
+            plugin::PluginMachine,
+            pressure::ReliefValve,
+            recipe,
+            script::Script,
+            separation::{Crystallizer, Distiller},
+            station::CargoRule,
+            tank::Tank,
+        },
+        lab::{Laboratory, Spectrometer},
+    },
+    research::{Research, Tech, Unlock},
+    tutorial::Tutorial,
+    weather::Weather,
+};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer, RenderingOptions},
+};
+use raylib::prelude::*;
+use std::{collections::BTreeMap, num::NonZeroU8, ops::Bound, path::Path};
+
+const FONT_SIZE: f32 = 20.0;
+const LINE_HEIGHT: f32 = 22.0;
+const MAX_LOG_LINES: usize = 200;
+const BACKGROUND: Color = Color::new(0, 0, 0, 200);
+/// Meters, for ladders spawned with `spawn ladder`
+const LADDER_HEIGHT: NonZeroU8 = NonZeroU8::new(4).unwrap();
+/// Seconds of breathing gas, for gas masks spawned with `spawn gas_mask`
+const GAS_MASK_FILTER: f32 = 120.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandError {
+    /// No command is registered with this name
+    Unknown(String),
+    /// The arguments don't fit the command; contains the command's usage string
+    Usage(&'static str),
+    /// The command could not be carried out
+    Failed(String),
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(f, "unknown command `{name}`, try `help`"),
+            Self::Usage(usage) => write!(f, "usage: {usage}"),
+            Self::Failed(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Runs a command with its arguments (not including the command name), returning a message to log
+pub type CommandFn = fn(&mut GameState, &[&str]) -> Result<String, CommandError>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Command {
+    pub name: &'static str,
+    /// Shown by `help` and when the arguments are wrong, e.g. `teleport <x> <y> <z>`
+    pub usage: &'static str,
+    pub run: CommandFn,
+}
+
+/// Every command the console knows, by name
+#[derive(Debug, Clone, Default)]
+pub struct CommandRegistry {
+    commands: BTreeMap<&'static str, Command>,
+}
+
+impl CommandRegistry {
+    /// A registry containing all of the built-in commands
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::default();
+        for command in BUILTINS {
+            registry.register(command);
+        }
+        registry
+    }
+
+    /// Add a command, replacing any existing command with the same name
+    pub fn register(&mut self, command: Command) -> Option<Command> {
+        self.commands.insert(command.name, command)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.get(name)
+    }
+
+    /// Names of commands starting with `prefix`, in alphabetical order
+    pub fn completions<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'static str> + 'a {
+        self.commands
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .map(|(&name, _)| name)
+            .take_while(move |name| name.starts_with(prefix))
+    }
+
+    /// Parse and run a line of input
+    pub fn execute(&self, state: &mut GameState, line: &str) -> Result<String, CommandError> {
+        let mut words = line.split_whitespace();
+        let Some(name) = words.next() else {
+            return Ok(String::new());
+        };
+        let args = words.collect::<Vec<_>>();
+        if name == "help" {
+            return Ok(self
+                .commands
+                .values()
+                .map(|command| command.usage)
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+        let command = self
+            .get(name)
+            .ok_or_else(|| CommandError::Unknown(name.to_string()))?;
+        (command.run)(state, &args)
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(arg: &str, usage: &'static str) -> Result<T, CommandError> {
+    arg.parse().map_err(|_| CommandError::Usage(usage))
+}
+
+const CIRCUIT_USAGE: &str = "circuit sensor input|output [item] | circuit sensor fluid|temperature|working | circuit constant <n> | circuit +|-|*|/|%|=|!=|<|<=|>|>=|latch | circuit wire <from> <to> a|b | circuit enable <node> | circuit filter <node> <rule> <item>... | circuit signal <node> | circuit clear";
+
+/// Build onto the circuit of the factory the player is in, with sensors and controls on the
+/// machine they're looking at
+fn circuit(state: &mut GameState, args: &[&str]) -> Result<String, CommandError> {
+    const USAGE: &str = CIRCUIT_USAGE;
+    if !state.research.allows(Unlock::Circuits) {
+        return Err(CommandError::Failed(
+            "circuits haven't been researched".to_string(),
+        ));
+    }
+    let RegionId::Factory(idx) = state.current_region else {
+        return Err(CommandError::Failed("not inside a factory".to_string()));
+    };
+    let looked_at = state.looked_at_machine_id().map(|(_, id)| id);
+    let machine =
+        || looked_at.ok_or_else(|| CommandError::Failed("not looking at a machine".to_string()));
+    let circuit = state.factories[idx].circuit_mut();
+    let failed = |e: CircuitError| CommandError::Failed(e.to_string());
+    let node = match *args {
+        ["sensor", port, ref item @ ..] => {
+            let item = match item {
+                [] => None,
+                [item] => Some(parse_arg::<Item>(item, USAGE)?),
+                _ => return Err(CommandError::Usage(USAGE)),
+            };
+            let port = match (port, item) {
+                ("input", item) => Port::Count(BeltSide::Input, item),
+                ("output", item) => Port::Count(BeltSide::Output, item),
+                ("fluid", None) => Port::Fluid,
+                ("temperature", None) => Port::Temperature,
+                ("working", None) => Port::Working,
+                _ => return Err(CommandError::Usage(USAGE)),
+            };
+            Node::Sensor {
+                machine: machine()?,
+                port,
+            }
+        }
+        ["constant", n] => Node::Constant(parse_arg(n, USAGE)?),
+        ["wire", from, to, pin] => {
+            let pin = match pin {
+                "a" => Pin::A,
+                "b" => Pin::B,
+                _ => return Err(CommandError::Usage(USAGE)),
+            };
+            (circuit.connect(parse_arg(from, USAGE)?, parse_arg(to, USAGE)?, pin))
+                .map_err(failed)?;
+            return Ok(format!("wired {from} into {to}"));
+        }
+        ["enable", node] => {
+            (circuit.add_control(Control {
+                signal: parse_arg(node, USAGE)?,
+                machine: machine()?,
+                behavior: Behavior::Enable,
+            }))
+            .map_err(failed)?;
+            return Ok(format!("the machine runs while {node} isn't 0"));
+        }
+        ["filter", node, rule, ref items @ ..] => {
+            let items = (items.iter())
+                .map(|item| parse_arg(item, USAGE))
+                .collect::<Result<Vec<Item>, _>>()?;
+            (circuit.add_control(Control {
+                signal: parse_arg(node, USAGE)?,
+                machine: machine()?,
+                behavior: Behavior::Filter {
+                    rule: parse_arg(rule, USAGE)?,
+                    items,
+                },
+            }))
+            .map_err(failed)?;
+            return Ok(format!("{node} picks what cargo rule {rule} moves"));
+        }
+        ["signal", node] => {
+            let id = parse_arg::<NodeId>(node, USAGE)?;
+            let signal = circuit
+                .signal(id)
+                .ok_or(failed(CircuitError::UnknownNode(id)))?;
+            return Ok(format!("{node} is {signal}"));
+        }
+        ["clear"] => {
+            circuit.clear();
+            return Ok("cleared the circuit".to_string());
+        }
+        ["latch"] => Node::Latch,
+        [op] => match op {
+            "+" => Node::Arithmetic(Arithmetic::Add),
+            "-" => Node::Arithmetic(Arithmetic::Sub),
+            "*" => Node::Arithmetic(Arithmetic::Mul),
+            "/" => Node::Arithmetic(Arithmetic::Div),
+            "%" => Node::Arithmetic(Arithmetic::Rem),
+            "=" => Node::Compare(Compare::Eq),
+            "!=" => Node::Compare(Compare::Ne),
+            "<" => Node::Compare(Compare::Lt),
+            "<=" => Node::Compare(Compare::Le),
+            ">" => Node::Compare(Compare::Gt),
+            ">=" => Node::Compare(Compare::Ge),
+            _ => return Err(CommandError::Usage(USAGE)),
+        },
+        _ => return Err(CommandError::Usage(USAGE)),
+    };
+    Ok(format!("added node {}", circuit.add(node)))
+}
+
+/// Send the drone of the charging pad closest to the player between the machines they look at
+fn drone(state: &mut GameState, args: &[&str]) -> Result<String, CommandError> {
+    const USAGE: &str = "drone from|to|stop";
+    let &[which] = args else {
+        return Err(CommandError::Usage(USAGE));
+    };
+    let RegionId::Factory(idx) = state.current_region else {
+        return Err(CommandError::Failed("not inside a factory".to_string()));
+    };
+    let looked_at = state.looked_at_machine_id().map(|(_, id)| id);
+    let factory = &mut state.factories[idx];
+    let position = (state.player.position)
+        .to_factory(&factory.origin)
+        .map_err(|e| CommandError::Failed(e.to_string()))?;
+    let pad = (factory.nearest_charging_pad(position))
+        .ok_or_else(|| CommandError::Failed("there are no charging pads here".to_string()))?;
+    let machine = || {
+        (looked_at.filter(|&id| id != pad))
+            .ok_or_else(|| CommandError::Failed("not looking at a machine".to_string()))
+    };
+    let name = |id| factory.machine(id).map(Machine::name);
+    let (machine, name) = match which {
+        "from" | "to" => {
+            let machine = machine()?;
+            (Some(machine), name(machine).unwrap_or_default())
+        }
+        "stop" => (None, String::new()),
+        _ => return Err(CommandError::Usage(USAGE)),
+    };
+    let Some(pad) = factory.charging_pad_mut(pad) else {
+        return Err(CommandError::Failed(
+            "there are no charging pads here".to_string(),
+        ));
+    };
+    Ok(match which {
+        "from" => {
+            pad.source = machine;
+            format!("the drone picks up from the {name}")
+        }
+        "to" => {
+            pad.destination = machine;
+            format!("the drone drops off at the {name}")
+        }
+        _ => {
+            pad.source = None;
+            pad.destination = None;
+            "the drone goes home".to_string()
+        }
+    })
+}
+
+const BUILTINS: [Command; 20] = [
+    Command {
+        name: "help",
+        usage: "help",
+        // Listing commands needs the registry, so `CommandRegistry::execute` handles this itself
+        run: |_, _| Ok(String::new()),
+    },
+    Command {
+        name: "teleport",
+        usage: "teleport <x> <y> <z>",
+        run: |state, args| {
+            const USAGE: &str = "teleport <x> <y> <z>";
+            let &[x, y, z] = args else {
+                return Err(CommandError::Usage(USAGE));
+            };
+            let (x, y, z) = (
+                parse_arg::<f32>(x, USAGE)?,
+                parse_arg::<f32>(y, USAGE)?,
+                parse_arg::<f32>(z, USAGE)?,
+            );
+            state.player.position = PlayerVector3::from_f32(x, y, z);
+            state.player.velocity = PlayerVector3::ZERO;
+            Ok(format!("teleported to ({x}, {y}, {z})"))
+        },
+    },
+    Command {
+        name: "give",
+        usage: "give <formula> [count]",
+        run: |state, args| {
+            const USAGE: &str = "give <formula> [count]";
+            let (item, count) = match *args {
+                [item] => (item, 1),
+                [item, count] => (item, parse_arg(count, USAGE)?),
+                _ => return Err(CommandError::Usage(USAGE)),
+            };
+            let item = item
+                .parse::<Formula>()
+                .map_err(|e| CommandError::Failed(e.to_string()))?;
+            let msg = format!("gave {count} {item}");
+            match state.player.give(ItemStack::new(item, count)) {
+                None => Ok(msg),
+                Some(rest) => Err(CommandError::Failed(format!(
+                    "only had room for {} {}",
+                    count - rest.count,
+                    rest.item
+                ))),
+            }
+        },
+    },
+    Command {
+        name: "spawn",
+        usage: "spawn reactor|miner|splitter|merger|station|vent|assembler|tank|charging_pad|relief_valve|distiller|crystallizer|electrolyzer|ladder|gas_mask|<plugin machine>",
+        run: |state, args| {
+            const USAGE: &str = "spawn reactor|miner|splitter|merger|station|vent|assembler|tank|charging_pad|relief_valve|distiller|crystallizer|electrolyzer|ladder|gas_mask|<plugin machine>";
+            let &[kind] = args else {
+                return Err(CommandError::Usage(USAGE));
+            };
+            if kind == "gas_mask" {
+                state.player.gas_mask = Some(GasMask::new(GAS_MASK_FILTER));
+                return Ok("equipped gas mask".to_string());
+            }
+            let machine = match kind {
+                "reactor" => Some(MachineKind::Reactor),
+                "miner" => Some(MachineKind::Miner),
+                "splitter" => Some(MachineKind::Splitter),
+                "merger" => Some(MachineKind::Merger),
+                "station" => Some(MachineKind::Station),
+                "vent" => Some(MachineKind::Vent),
+                "assembler" => Some(MachineKind::Assembler),
+                "tank" => Some(MachineKind::Tank),
+                "charging_pad" => Some(MachineKind::ChargingPad),
+                "relief_valve" => Some(MachineKind::ReliefValve),
+                "distiller" => Some(MachineKind::Distiller),
+                "crystallizer" => Some(MachineKind::Crystallizer),
+                "electrolyzer" => Some(MachineKind::Electrolyzer),
+                _ => None,
+            };
+            if let Some(machine) = machine
+                && !state.research.allows(Unlock::Machine(machine))
+            {
+                return Err(CommandError::Failed(format!(
+                    "{kind}s haven't been researched"
+                )));
+            }
+            let position = state.player.position;
+            let RegionId::Factory(idx) = state.current_region else {
+                return Err(CommandError::Failed("not inside a factory".to_string()));
+            };
+            let factory = &mut state.factories[idx];
+            let mut position = position
+                .to_factory(&factory.origin)
+                .map_err(|e| CommandError::Failed(e.to_string()))?;
+            position.y = factory.level_at(position);
+            let built = match kind {
+                "reactor" => {
+                    let reactor = Reactor {
+                        position,
+                        rotation: Cardinal2D::default(),
+                    };
+                    if !factory.is_vacant(&reactor.bounds()) {
+                        return Err(CommandError::Failed(
+                            "no room for a reactor here".to_string(),
+                        ));
+                    }
+                    Change::machine(factory.add_reactor(reactor))
+                }
+                "miner" => {
+                    let id = state
+                        .add_miner(idx, position, Cardinal2D::default())
+                        .map_err(|e| CommandError::Failed(e.to_string()))?;
+                    Change::machine(id)
+                }
+                "splitter" => {
+                    let splitter =
+                        Splitter::new(position, Cardinal2D::default(), SplitMode::default());
+                    if !factory.is_vacant(&splitter.bounds()) {
+                        return Err(CommandError::Failed(
+                            "no room for a splitter here".to_string(),
+                        ));
+                    }
+                    Change::machine(factory.add_splitter(splitter))
+                }
+                "merger" => {
+                    let merger = Merger::new(position, Cardinal2D::default());
+                    if !factory.is_vacant(&merger.bounds()) {
+                        return Err(CommandError::Failed(
+                            "no room for a merger here".to_string(),
+                        ));
+                    }
+                    Change::machine(factory.add_merger(merger))
+                }
+                "station" => {
+                    // Unload everything, and send off anything brought to it
+                    let rules = vec![CargoRule::unload(None, 0), CargoRule::load(None, u32::MAX)];
+                    // Face out of whichever wall it's against
+                    let facings = [
+                        Cardinal2D::East,
+                        Cardinal2D::North,
+                        Cardinal2D::West,
+                        Cardinal2D::South,
+                    ];
+                    let mut best = StationError::NotAtWall;
+                    let built = facings.into_iter().find_map(|rotation| {
+                        (state.add_station(idx, position, rotation, rules.clone()))
+                            .map_err(|error| best = best.max(error))
+                            .ok()
+                    });
+                    let Some(id) = built else {
+                        return Err(CommandError::Failed(best.to_string()));
+                    };
+                    Change::machine(id)
+                }
+                "vent" => {
+                    let vent = Vent::new(position, Cardinal2D::default());
+                    if !factory.is_vacant(&vent.bounds()) {
+                        return Err(CommandError::Failed("no room for a vent here".to_string()));
+                    }
+                    Change::machine(factory.add_vent(vent))
+                }
+                "assembler" => {
+                    let assembler = Assembler::new(position, Cardinal2D::default());
+                    if !factory.is_vacant(&assembler.bounds()) {
+                        return Err(CommandError::Failed(
+                            "no room for an assembler here".to_string(),
+                        ));
+                    }
+                    Change::machine(factory.add_assembler(assembler))
+                }
+                "tank" => {
+                    let tank = Tank::new(position, Cardinal2D::default());
+                    if !factory.is_vacant(&tank.bounds()) {
+                        return Err(CommandError::Failed("no room for a tank here".to_string()));
+                    }
+                    Change::machine(factory.add_tank(tank))
+                }
+                "charging_pad" => {
+                    let pad = ChargingPad::new(position, Cardinal2D::default());
+                    if !factory.is_vacant(&pad.bounds()) {
+                        return Err(CommandError::Failed(
+                            "no room for a charging pad here".to_string(),
+                        ));
+                    }
+                    Change::machine(factory.add_charging_pad(pad))
+                }
+                "relief_valve" => {
+                    let valve = ReliefValve::new(position, Cardinal2D::default());
+                    if !factory.is_vacant(&valve.bounds()) {
+                        return Err(CommandError::Failed(
+                            "no room for a relief valve here".to_string(),
+                        ));
+                    }
+                    Change::machine(factory.add_relief_valve(valve))
+                }
+                "distiller" => {
+                    let distiller = Distiller::new(position, Cardinal2D::default());
+                    if !factory.is_vacant(&distiller.bounds()) {
+                        return Err(CommandError::Failed(
+                            "no room for a distiller here".to_string(),
+                        ));
+                    }
+                    Change::machine(factory.add_distiller(distiller))
+                }
+                "crystallizer" => {
+                    let crystallizer = Crystallizer::new(position, Cardinal2D::default());
+                    if !factory.is_vacant(&crystallizer.bounds()) {
+                        return Err(CommandError::Failed(
+                            "no room for a crystallizer here".to_string(),
+                        ));
+                    }
+                    Change::machine(factory.add_crystallizer(crystallizer))
+                }
+                "electrolyzer" => {
+                    let electrolyzer = Electrolyzer::new(position, Cardinal2D::default());
+                    if !factory.is_vacant(&electrolyzer.bounds()) {
+                        return Err(CommandError::Failed(
+                            "no room for an electrolyzer here".to_string(),
+                        ));
+                    }
+                    Change::machine(factory.add_electrolyzer(electrolyzer))
+                }
+                "ladder" => {
+                    let ladder = Ladder {
+                        position,
+                        height: LADDER_HEIGHT,
+                        facing: Cardinal2D::default(),
+                    };
+                    factory.add_ladder(ladder);
+                    Change::Built {
+                        entities: Vec::new(),
+                        ladders: vec![ladder],
+                    }
+                }
+                name => {
+                    let Some(kind) = plugin::registry().machine(name) else {
+                        return Err(CommandError::Usage(USAGE));
+                    };
+                    let machine = PluginMachine::new(kind, position, Cardinal2D::default());
+                    if !factory.is_vacant(&machine.bounds()) {
+                        return Err(CommandError::Failed(format!("no room for a {name} here")));
+                    }
+                    Change::machine(factory.add_plugin_machine(machine))
+                }
+            };
+            state.history.record(idx, built);
+            Ok(format!(
+                "spawned {kind} at ({}, {}, {})",
+                position.x, position.y, position.z
+            ))
+        },
+    },
+    Command {
+        name: "expand",
+        usage: "expand east|west|north|south|up <meters>",
+        run: |state, args| {
+            const USAGE: &str = "expand east|west|north|south|up <meters>";
+            let &[side, blocks] = args else {
+                return Err(CommandError::Usage(USAGE));
+            };
+            let side = match side {
+                "east" => Cardinal3D::East,
+                "west" => Cardinal3D::West,
+                "north" => Cardinal3D::North,
+                "south" => Cardinal3D::South,
+                "up" => Cardinal3D::Up,
+                _ => return Err(CommandError::Usage(USAGE)),
+            };
+            let blocks = parse_arg(blocks, USAGE)?;
+            let RegionId::Factory(idx) = state.current_region else {
+                return Err(CommandError::Failed("not inside a factory".to_string()));
+            };
+            let area = state
+                .expand_factory(idx, side, blocks)
+                .map_err(|e| CommandError::Failed(e.to_string()))?;
+            Ok(format!("expanded the factory by {area} square meters"))
+        },
+    },
+    Command {
+        name: "floor",
+        usage: "floor <height>",
+        run: |state, args| {
+            const USAGE: &str = "floor <height>";
+            let &[height] = args else {
+                return Err(CommandError::Usage(USAGE));
+            };
+            let height = parse_arg(height, USAGE)?;
+            let factory = state
+                .current_factory_mut()
+                .ok_or_else(|| CommandError::Failed("not inside a factory".to_string()))?;
+            // Across the whole factory
+            if !factory.add_floor(Floor::new(height, &factory.bounds)) {
+                return Err(CommandError::Failed(format!(
+                    "no room for a floor at {height}"
+                )));
+            }
+            Ok(format!("built a floor at {height}"))
+        },
+    },
+    Command {
+        name: "set_time",
+        usage: "set_time <hours>",
+        run: |state, args| {
+            const USAGE: &str = "set_time <hours>";
+            let &[hours] = args else {
+                return Err(CommandError::Usage(USAGE));
+            };
+            let hours = parse_arg::<f32>(hours, USAGE)?;
+            if !hours.is_finite() {
+                return Err(CommandError::Usage(USAGE));
+            }
+            state.clock.set_hours(hours);
+            Ok(format!("time set to {:.2}h", state.clock.hours()))
+        },
+    },
+    Command {
+        name: "blueprint",
+        usage: "blueprint save|load <file>",
+        run: |state, args| {
+            const USAGE: &str = "blueprint save|load <file>";
+            let &[action, path] = args else {
+                return Err(CommandError::Usage(USAGE));
+            };
+            match action {
+                "save" => {
+                    let blueprint = state.clipboard.as_ref().ok_or_else(|| {
+                        CommandError::Failed("nothing copied to save".to_string())
+                    })?;
+                    std::fs::write(path, blueprint.to_string())
+                        .map_err(|e| CommandError::Failed(e.to_string()))?;
+                    Ok(format!("saved blueprint to {path}"))
+                }
+                "load" => {
+                    let blueprint = std::fs::read_to_string(path)
+                        .map_err(|e| CommandError::Failed(e.to_string()))?
+                        .parse::<Blueprint>()
+                        .map_err(|e| CommandError::Failed(e.to_string()))?;
+                    state.clipboard = Some(blueprint);
+                    Ok(format!("loaded blueprint from {path}"))
+                }
+                _ => Err(CommandError::Usage(USAGE)),
+            }
+        },
+    },
+    Command {
+        name: "research",
+        usage: "research [<tech>|save <file>|load <file>]",
+        run: |state, args| {
+            const USAGE: &str = "research [<tech>|save <file>|load <file>]";
+            match *args {
+                [] => {
+                    let available = (state.research.available())
+                        .map(|tech| format!("{} ({})", tech.name, tech.cost))
+                        .collect::<Vec<_>>();
+                    Ok(format!(
+                        "{} research points, can research: {}",
+                        state.research.points,
+                        if available.is_empty() {
+                            "nothing".to_string()
+                        } else {
+                            available.join(", ")
+                        }
+                    ))
+                }
+                ["save", path] => {
+                    std::fs::write(path, state.research.to_string())
+                        .map_err(|e| CommandError::Failed(e.to_string()))?;
+                    Ok(format!("saved research to {path}"))
+                }
+                ["load", path] => {
+                    state.research = std::fs::read_to_string(path)
+                        .map_err(|e| CommandError::Failed(e.to_string()))?
+                        .parse::<Research>()
+                        .map_err(|e| CommandError::Failed(e.to_string()))?;
+                    Ok(format!("loaded research from {path}"))
+                }
+                [name] => {
+                    let tech = Tech::find(name)
+                        .ok_or_else(|| CommandError::Failed(format!("unknown tech `{name}`")))?;
+                    (state.research.research(tech.id))
+                        .map_err(|e| CommandError::Failed(format!("can't research {name}: {e}")))?;
+                    let unlocks = (tech.unlocks.iter())
+                        .map(Unlock::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    Ok(format!("researched {name}, unlocking {unlocks}"))
+                }
+                _ => Err(CommandError::Usage(USAGE)),
+            }
+        },
+    },
+    Command {
+        name: "milestones",
+        usage: "milestones [save <file>|load <file>]",
+        run: |state, args| {
+            const USAGE: &str = "milestones [save <file>|load <file>]";
+            match *args {
+                [] => {
+                    let progress = Progress {
+                        factories: &state.factories,
+                        research: &state.research,
+                        traveled: state.world.rail.traveled(),
+                    };
+                    let lines = (Milestone::ALL.iter())
+                        .map(|milestone| {
+                            if state.milestones.is_reached(milestone.id) {
+                                format!("{}: reached", milestone.name)
+                            } else {
+                                let percent = milestone.goal.progress(&progress) * 100.0;
+                                format!("{}: {percent:.0}%", milestone.name)
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    Ok(lines.join("\n"))
+                }
+                ["save", path] => {
+                    std::fs::write(path, state.milestones.to_string())
+                        .map_err(|e| CommandError::Failed(e.to_string()))?;
+                    Ok(format!("saved milestones to {path}"))
+                }
+                ["load", path] => {
+                    state.milestones = std::fs::read_to_string(path)
+                        .map_err(|e| CommandError::Failed(e.to_string()))?
+                        .parse::<Milestones>()
+                        .map_err(|e| CommandError::Failed(e.to_string()))?;
+                    Ok(format!("loaded milestones from {path}"))
+                }
+                _ => Err(CommandError::Usage(USAGE)),
+            }
+        },
+    },
+    Command {
+        name: "tutorial",
+        usage: "tutorial [skip|restart|load <file>]",
+        run: |state, args| {
+            const USAGE: &str = "tutorial [skip|restart|load <file>]";
+            match *args {
+                [] => {}
+                ["skip"] => state.tutorial.skip(),
+                ["restart"] => state.tutorial.restart(),
+                ["load", path] => {
+                    state.tutorial = Tutorial::load(Path::new(path))
+                        .map_err(|e| CommandError::Failed(e.to_string()))?;
+                }
+                _ => return Err(CommandError::Usage(USAGE)),
+            }
+            let (done, total) = state.tutorial.progress();
+            Ok(match state.tutorial.current() {
+                Some(objective) => {
+                    format!("objective {} of {total}: {}", done + 1, objective.label())
+                }
+                None => format!("all {total} objectives done"),
+            })
+        },
+    },
+    Command {
+        name: "analyze",
+        usage: "analyze [count]",
+        run: |state, args| {
+            const USAGE: &str = "analyze [count]";
+            let count = match *args {
+                [] => u32::MAX,
+                [count] => parse_arg(count, USAGE)?,
+                _ => return Err(CommandError::Usage(USAGE)),
+            };
+            if state.current_region != RegionId::Lab {
+                return Err(CommandError::Failed("not inside the lab".to_string()));
+            }
+            let slot = state.player.hotbar_slot;
+            let sample = (state.player.inventory.take(slot, count))
+                .ok_or_else(|| CommandError::Failed("not holding anything".to_string()))?;
+            let (count, item) = (sample.count, sample.item.clone());
+            match state.lab.samples.insert(sample) {
+                None => Ok(format!("left {count} {item} for analysis")),
+                Some(rest) => {
+                    let msg = format!(
+                        "the lab only had room for {} {item}, it holds {} kinds of sample",
+                        count - rest.count,
+                        Laboratory::SAMPLE_SLOTS
+                    );
+                    // It just came out of the inventory, so there's room for it
+                    _ = state.player.give(rest);
+                    Err(CommandError::Failed(msg))
+                }
+            }
+        },
+    },
+    Command {
+        name: "probe",
+        usage: "probe [count]",
+        run: |state, args| {
+            const USAGE: &str = "probe [count]";
+            let count = match *args {
+                [] => 1,
+                [count] => parse_arg(count, USAGE)?,
+                _ => return Err(CommandError::Usage(USAGE)),
+            };
+            if state.current_region != RegionId::Lab {
+                return Err(CommandError::Failed("not inside the lab".to_string()));
+            }
+            let slot = state.player.hotbar_slot;
+            let sample = (state.player.inventory.take(slot, count))
+                .ok_or_else(|| CommandError::Failed("not holding anything".to_string()))?;
+            let (count, item) = (sample.count, sample.item.clone());
+            match state.lab.spectrometer.samples.insert(sample) {
+                None => Ok(format!("left {count} {item} in the spectrometer")),
+                Some(rest) => {
+                    let msg = format!(
+                        "the spectrometer only had room for {} {item}, it holds {} kinds of sample",
+                        count - rest.count,
+                        Spectrometer::SLOTS
+                    );
+                    // It just came out of the inventory, so there's room for it
+                    _ = state.player.give(rest);
+                    Err(CommandError::Failed(msg))
+                }
+            }
+        },
+    },
+    Command {
+        name: "weather",
+        usage: "weather [clear|rain|fog|storm|save <file>|load <file>]",
+        run: |state, args| {
+            const USAGE: &str = "weather [clear|rain|fog|storm|save <file>|load <file>]";
+            match *args {
+                [] => {}
+                ["save", path] => {
+                    std::fs::write(path, state.weather.to_string())
+                        .map_err(|e| CommandError::Failed(e.to_string()))?;
+                    return Ok(format!("saved weather to {path}"));
+                }
+                ["load", path] => {
+                    state.weather = std::fs::read_to_string(path)
+                        .map_err(|e| CommandError::Failed(e.to_string()))?
+                        .parse::<Weather>()
+                        .map_err(|e| CommandError::Failed(e.to_string()))?;
+                }
+                [kind] => state.weather.set(parse_arg(kind, USAGE)?),
+                _ => return Err(CommandError::Usage(USAGE)),
+            }
+            Ok(format!("the weather is {}", state.weather.kind().name()))
+        },
+    },
+    Command {
+        name: "assembler",
+        usage: "assembler <recipe>|none [<input side> <output side>]",
+        run: |state, args| {
+            const USAGE: &str = "assembler <recipe>|none [<input side> <output side>]";
+            let (recipe, sides) = match *args {
+                [recipe] => (recipe, None),
+                [recipe, input, output] => (
+                    recipe,
+                    Some((parse_arg::<Side>(input, USAGE)?, parse_arg(output, USAGE)?)),
+                ),
+                _ => return Err(CommandError::Usage(USAGE)),
+            };
+            // Recipe names can have spaces, which are written as underscores
+            let recipe = recipe.replace('_', " ");
+            let recipe = match recipe.as_str() {
+                "none" => None,
+                name => Some(
+                    (recipe::find(name))
+                        .ok_or_else(|| CommandError::Failed(format!("no recipe called {name}")))?,
+                ),
+            };
+            if let Some(recipe) = recipe
+                && !state.research.allows_recipe(recipe)
+            {
+                return Err(CommandError::Failed(format!(
+                    "{} hasn't been researched",
+                    recipe.name()
+                )));
+            }
+            if let Some(recipe) = recipe
+                && !state.compendium.knows_recipe(recipe)
+            {
+                return Err(CommandError::Failed(format!(
+                    "something that goes into {} hasn't been discovered",
+                    recipe.name()
+                )));
+            }
+            let (idx, id) = (state.looked_at_machine_id())
+                .ok_or_else(|| CommandError::Failed("not looking at a machine".to_string()))?;
+            let assembler = (state.factories[idx].assembler_mut(id))
+                .ok_or_else(|| CommandError::Failed("that isn't an assembler".to_string()))?;
+            if let Some((input, output)) = sides
+                && !assembler.set_sides(input, output)
+            {
+                return Err(CommandError::Failed(
+                    "input and output can't be on the same side".to_string(),
+                ));
+            }
+            if assembler.recipe() != recipe {
+                assembler.set_recipe(recipe.cloned());
+            }
+            Ok(match recipe {
+                Some(recipe) => format!("the assembler makes {}", recipe.name()),
+                None => "the assembler makes nothing".to_string(),
+            })
+        },
+    },
+    Command {
+        name: "thermostat",
+        usage: "thermostat <celsius>",
+        run: |state, args| {
+            const USAGE: &str = "thermostat <celsius>";
+            let &[celsius] = args else {
+                return Err(CommandError::Usage(USAGE));
+            };
+            let setpoint = Kelvin::from_celsius(parse_arg(celsius, USAGE)?)
+                .ok_or_else(|| CommandError::Failed("that's below absolute zero".to_string()))?;
+            let (idx, id) = (state.looked_at_machine_id())
+                .ok_or_else(|| CommandError::Failed("not looking at a machine".to_string()))?;
+            let factory = &mut state.factories[idx];
+            if let Some(distiller) = factory.distiller_mut(id) {
+                distiller.set_setpoint(setpoint);
+                Ok(format!(
+                    "the distiller boils off what boils by {celsius} °C"
+                ))
+            } else if let Some(crystallizer) = factory.crystallizer_mut(id) {
+                crystallizer.set_setpoint(setpoint);
+                Ok(format!("the crystallizer cools to {celsius} °C"))
+            } else {
+                Err(CommandError::Failed(
+                    "that isn't a distiller or crystallizer".to_string(),
+                ))
+            }
+        },
+    },
+    Command {
+        name: "electrolyzer",
+        usage: "electrolyzer water|brine|none|<reaction>",
+        run: |state, args| {
+            const USAGE: &str = "electrolyzer water|brine|none|<reaction>";
+            // Reactions have spaces in them, so they're split over several arguments
+            let built_in = match *args {
+                [name] => electrolysis::find(name),
+                _ => None,
+            };
+            let process = match *args {
+                [] => return Err(CommandError::Usage(USAGE)),
+                ["none"] => None,
+                [_] if built_in.is_some() => built_in.cloned(),
+                _ => {
+                    let reaction = (args.join(" ").parse::<Reaction>())
+                        .map_err(|e| CommandError::Failed(e.to_string()))?;
+                    Some(
+                        Electrolysis::new(&reaction)
+                            .map_err(|e| CommandError::Failed(e.to_string()))?,
+                    )
+                }
+            };
+            let (idx, id) = (state.looked_at_machine_id())
+                .ok_or_else(|| CommandError::Failed("not looking at a machine".to_string()))?;
+            let electrolyzer = (state.factories[idx].electrolyzer_mut(id))
+                .ok_or_else(|| CommandError::Failed("that isn't an electrolyzer".to_string()))?;
+            let msg = match &process {
+                Some(process) => format!("the electrolyzer runs {}", process.reaction()),
+                None => "the electrolyzer runs nothing".to_string(),
+            };
+            electrolyzer.set_process(process);
+            Ok(msg)
+        },
+    },
+    Command {
+        name: "circuit",
+        usage: CIRCUIT_USAGE,
+        run: circuit,
+    },
+    Command {
+        name: "drone",
+        usage: "drone from|to|stop",
+        run: drone,
+    },
+    Command {
+        name: "controller",
+        usage: "controller <file>",
+        run: |state, args| {
+            let &[path] = args else {
+                return Err(CommandError::Usage("controller <file>"));
+            };
+            let script = std::fs::read_to_string(path)
+                .map_err(|e| CommandError::Failed(e.to_string()))?
+                .parse::<Script>()
+                .map_err(|e| CommandError::Failed(format!("{path}: {e}")))?;
+            if !state
+                .research
+                .allows(Unlock::Machine(MachineKind::Controller))
+            {
+                return Err(CommandError::Failed(
+                    "controllers haven't been researched".to_string(),
+                ));
+            }
+            let RegionId::Factory(idx) = state.current_region else {
+                return Err(CommandError::Failed("not inside a factory".to_string()));
+            };
+            let factory = &mut state.factories[idx];
+            let mut position = (state.player.position)
+                .to_factory(&factory.origin)
+                .map_err(|e| CommandError::Failed(e.to_string()))?;
+            position.y = factory.level_at(position);
+            // Reprogram the controller already there, if there is one
+            if let Some(controller) = factory.controller_at_mut(position) {
+                controller.set_script(script);
+                return Ok(format!("loaded {path} into the controller"));
+            }
+            let controller = Controller::new(position, Cardinal2D::default(), script);
+            if !factory.is_vacant(&controller.bounds()) {
+                return Err(CommandError::Failed(
+                    "no room for a controller here".to_string(),
+                ));
+            }
+            factory.add_controller(controller);
+            Ok(format!(
+                "placed a controller running {path} at ({}, {}, {})",
+                position.x, position.y, position.z
+            ))
+        },
+    },
+];
+
+/// A single line of text being edited, with a cursor
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineEdit {
+    text: String,
+    /// Byte index into `text`, always on a char boundary
+    cursor: usize,
+}
+
+impl LineEdit {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub const fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Replace the text, moving the cursor to the end
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    /// Clear the line, returning what it held
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    pub fn insert(&mut self, ch: char) {
+        self.text.insert(self.cursor, ch);
+        self.cursor += ch.len_utf8();
+    }
+
+    /// Delete the char before the cursor
+    pub fn backspace(&mut self) {
+        if let Some(ch) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= ch.len_utf8();
+            self.text.remove(self.cursor);
+        }
+    }
+
+    /// Delete the char after the cursor
+    pub fn delete(&mut self) {
+        if self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+        }
+    }
+
+    pub fn left(&mut self) {
+        if let Some(ch) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= ch.len_utf8();
+        }
+    }
+
+    pub fn right(&mut self) {
+        if let Some(ch) = self.text[self.cursor..].chars().next() {
+            self.cursor += ch.len_utf8();
+        }
+    }
+
+    pub const fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub const fn end(&mut self) {
+        self.cursor = self.text.len();
+    }
+}
+
+/// Developer console for running commands against the [`GameState`]
+#[derive(Debug, Clone)]
+pub struct Console {
+    pub is_open: bool,
+    pub commands: CommandRegistry,
+    pub input: LineEdit,
+    /// Previously executed lines, oldest first
+    history: Vec<String>,
+    /// Index into `history` while browsing it with up/down
+    history_pos: Option<usize>,
+    /// Output, oldest first
+    log: Vec<String>,
+    /// Screen-space area of the console
+    rect: Rectangle,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::new(CommandRegistry::with_builtins())
+    }
+}
+
+impl Console {
+    pub const fn new(commands: CommandRegistry) -> Self {
+        Self {
+            is_open: false,
+            commands,
+            input: LineEdit {
+                text: String::new(),
+                cursor: 0,
+            },
+            history: Vec::new(),
+            history_pos: None,
+            log: Vec::new(),
+            rect: Rectangle::new(0.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Open the console, fit to the screen
+    pub fn open(&mut self, rl: &RaylibHandle) {
+        self.is_open = true;
+        self.layout(rl);
+    }
+
+    pub const fn close(&mut self) {
+        self.is_open = false;
+    }
+
+    /// Cover the top part of the screen
+    #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+    fn layout(&mut self, rl: &RaylibHandle) {
+        let width = rl.get_screen_width() as f32;
+        let height = (0.4 * rl.get_screen_height() as f32).max(3.0 * LINE_HEIGHT);
+        self.rect = Rectangle::new(0.0, 0.0, width, height);
+    }
+
+    pub fn log(&self) -> &[String] {
+        &self.log
+    }
+
+    pub fn print(&mut self, text: &str) {
+        self.log.extend(text.lines().map(str::to_string));
+        let excess = self.log.len().saturating_sub(MAX_LOG_LINES);
+        self.log.drain(..excess);
+    }
+
+    /// Run the current input line and record it in the history
+    pub fn submit(&mut self, state: &mut GameState) {
+        let line = self.input.take();
+        self.history_pos = None;
+        if line.trim().is_empty() {
+            return;
+        }
+        self.print(&format!("> {line}"));
+        match self.commands.execute(state, &line) {
+            Ok(msg) => self.print(&msg),
+            Err(e) => self.print(&e.to_string()),
+        }
+        if self.history.last() != Some(&line) {
+            self.history.push(line);
+        }
+    }
+
+    /// Step backward (`older = true`) or forward through the history
+    pub fn browse_history(&mut self, older: bool) {
+        let pos = match (self.history_pos, older) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => None,
+            (Some(pos), true) => Some(pos.saturating_sub(1)),
+            (Some(pos), false) => Some(pos + 1).filter(|&pos| pos < self.history.len()),
+        };
+        self.history_pos = pos;
+        match pos {
+            Some(pos) => self.input.set(self.history[pos].clone()),
+            None => _ = self.input.take(),
+        }
+    }
+
+    /// Complete the command name being typed
+    ///
+    /// A unique match is filled in; otherwise the input is extended to the longest common prefix
+    /// and the candidates are listed.
+    pub fn complete(&mut self) {
+        let text = self.input.as_str();
+        if text.contains(char::is_whitespace) {
+            return;
+        }
+        let candidates = self.commands.completions(text).collect::<Vec<_>>();
+        match candidates.as_slice() {
+            [] => {}
+            [name] => self.input.set(format!("{name} ")),
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(first.len(), |len, name| {
+                    first
+                        .bytes()
+                        .zip(name.bytes())
+                        .take(len)
+                        .take_while(|(a, b)| a == b)
+                        .count()
+                });
+                let prefix = first[..common].to_string();
+                self.print(&candidates.join("  "));
+                self.input.set(prefix);
+            }
+        }
+    }
+
+    /// Handle typing and editing keys
+    ///
+    /// Returns `false` once the console has been dismissed with escape.
+    pub fn update(&mut self, rl: &mut RaylibHandle, state: &mut GameState) -> bool {
+        use KeyboardKey::{
+            KEY_BACKSPACE, KEY_DELETE, KEY_DOWN, KEY_END, KEY_ENTER, KEY_ESCAPE, KEY_HOME,
+            KEY_KP_ENTER, KEY_LEFT, KEY_RIGHT, KEY_TAB, KEY_UP,
+        };
+
+        if rl.is_key_pressed(KEY_ESCAPE) {
+            return false;
+        }
+        self.layout(rl);
+
+        while let Some(ch) = rl.get_char_pressed() {
+            if !ch.is_control() {
+                self.input.insert(ch);
+            }
+        }
+        let pressed = |key| rl.is_key_pressed(key) || rl.is_key_pressed_repeat(key);
+        if pressed(KEY_BACKSPACE) {
+            self.input.backspace();
+        }
+        if pressed(KEY_DELETE) {
+            self.input.delete();
+        }
+        if pressed(KEY_LEFT) {
+            self.input.left();
+        }
+        if pressed(KEY_RIGHT) {
+            self.input.right();
+        }
+        if pressed(KEY_HOME) {
+            self.input.home();
+        }
+        if pressed(KEY_END) {
+            self.input.end();
+        }
+        if pressed(KEY_UP) {
+            self.browse_history(true);
+        }
+        if pressed(KEY_DOWN) {
+            self.browse_history(false);
+        }
+        if pressed(KEY_TAB) {
+            self.complete();
+        }
+        if pressed(KEY_ENTER) || pressed(KEY_KP_ENTER) {
+            self.submit(state);
+        }
+        true
+    }
+}
+
+impl Draw for Console {
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        d.draw_rectangle(self.rect, BACKGROUND)?;
+        let input_y = self.rect.y + self.rect.height - LINE_HEIGHT;
+        d.draw_line(
+            Vector2::new(self.rect.x, input_y - 1.0),
+            Vector2::new(self.rect.x + self.rect.width, input_y - 1.0),
+            None,
+            Color::DARKGRAY,
+        )?;
+
+        let font = d.font().ok_or(draw::Error)?;
+        let prompt = format!("> {}", self.input.as_str());
+        d.draw_text(
+            &prompt,
+            Vector2::new(4.0, input_y),
+            FONT_SIZE,
+            0.0,
+            Color::WHITE,
+        )?;
+        let cursor_x = 4.0
+            + font
+                .measure_text(&prompt[..2 + self.input.cursor()], FONT_SIZE, 0.0)
+                .x;
+        d.draw_line(
+            Vector2::new(cursor_x, input_y),
+            Vector2::new(cursor_x, input_y + FONT_SIZE),
+            None,
+            Color::WHITE,
+        )?;
+        // Clip the log so the oldest visible line can be cut off at the top
+        d.push_layer(RenderingOptions::new().clip(Rectangle::new(
+            self.rect.x,
+            self.rect.y,
+            self.rect.width,
+            input_y - 1.0 - self.rect.y,
+        )));
+        let mut y = input_y;
+        for line in self.log.iter().rev() {
+            y -= LINE_HEIGHT;
+            if y + LINE_HEIGHT <= self.rect.y {
+                break;
+            }
+            d.draw_text(line, Vector2::new(4.0, y), FONT_SIZE, 0.0, Color::LIGHTGRAY)?;
+        }
+        d.pop_layer()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_edit() {
+        let mut line = LineEdit::default();
+        for ch in "tèleport".chars() {
+            line.insert(ch);
+        }
+        line.home();
+        line.right();
+        line.right();
+        line.backspace();
+        line.insert('e');
+        assert_eq!(line.as_str(), "teleport");
+        line.end();
+        line.left();
+        line.delete();
+        assert_eq!(line.as_str(), "telepor");
+    }
+
+    #[test]
+    fn test_completion() {
+        let mut console = Console::default();
+        console.input.set("s");
+        console.complete();
+        assert_eq!(console.input.as_str(), "s");
+        assert_eq!(console.log(), ["set_time  spawn"]);
+        console.input.set("tel");
+        console.complete();
+        assert_eq!(console.input.as_str(), "teleport ");
+    }
+
+    #[test]
+    fn test_history() {
+        let mut console = Console {
+            history: vec!["help".to_string(), "spawn reactor".to_string()],
+            ..Console::default()
+        };
+        console.browse_history(true);
+        assert_eq!(console.input.as_str(), "spawn reactor");
+        console.browse_history(true);
+        console.browse_history(true);
+        assert_eq!(console.input.as_str(), "help");
+        console.browse_history(false);
+        assert_eq!(console.input.as_str(), "spawn reactor");
+        console.browse_history(false);
+        assert_eq!(console.input.as_str(), "");
+    }
+}
@@ -0,0 +1,329 @@
+//! Items and fluids, and the things that hold them
+//!
+//! Belts, pipes, machines, and the player all hold their contents in [`ItemSlots`] and
+//! [`FluidTank`]s, so moving things between any two of them is just a [`transfer`].
+
+use crate::chem::{formula::Formula, molecule::Compound};
+use fixed_point::Q32_32;
+
+/// Something solid, carried in whole units
+pub type Item = Formula;
+
+/// Something that flows, measured in liters
+pub type Molecule = Compound;
+
+/// Some number of the same item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item: Item,
+    pub count: u32,
+}
+
+impl ItemStack {
+    #[inline]
+    pub const fn new(item: Item, count: u32) -> Self {
+        Self { item, count }
+    }
+
+    /// Whether `other` can go in the same stack
+    #[inline]
+    pub fn stacks_with(&self, other: &Self) -> bool {
+        self.item == other.item
+    }
+
+    /// Move as much of `other` into `self` as fits without going over `limit`, returning the
+    /// rest of `other` if there is any
+    pub fn merge(&mut self, mut other: Self, limit: u32) -> Option<Self> {
+        if self.stacks_with(&other) {
+            let moved = other.count.min(limit.saturating_sub(self.count));
+            self.count += moved;
+            other.count -= moved;
+        }
+        (other.count > 0).then_some(other)
+    }
+
+    /// Take up to `count` off the top of the stack, if there's anything to take
+    pub fn split(&mut self, count: u32) -> Option<Self> {
+        let count = count.min(self.count);
+        self.count -= count;
+        (count > 0).then(|| Self::new(self.item.clone(), count))
+    }
+}
+
+/// Some amount of the same fluid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FluidVolume {
+    pub molecule: Molecule,
+    pub liters: Q32_32,
+}
+
+impl FluidVolume {
+    #[inline]
+    pub const fn new(molecule: Molecule, liters: Q32_32) -> Self {
+        Self { molecule, liters }
+    }
+
+    /// Whether `other` can be held together with `self`
+    ///
+    /// Fluids don't mix yet, so only the same fluid can.
+    #[inline]
+    pub fn mixes_with(&self, other: &Self) -> bool {
+        self.molecule == other.molecule
+    }
+
+    /// Move as much of `other` into `self` as fits without going over `capacity`, returning the
+    /// rest of `other` if there is any
+    pub fn merge(&mut self, mut other: Self, capacity: Q32_32) -> Option<Self> {
+        if self.mixes_with(&other) {
+            let room = capacity.minus(self.liters).max(Q32_32::ZERO);
+            let moved = other.liters.min(room);
+            self.liters = self.liters.plus(moved);
+            other.liters = other.liters.minus(moved);
+        }
+        (other.liters > Q32_32::ZERO).then_some(other)
+    }
+
+    /// Take up to `liters` out, if there's anything to take
+    pub fn split(&mut self, liters: Q32_32) -> Option<Self> {
+        let liters = liters.min(self.liters);
+        self.liters = self.liters.minus(liters);
+        (liters > Q32_32::ZERO).then(|| Self::new(self.molecule.clone(), liters))
+    }
+}
+
+/// Holds items or fluids, up to some capacity
+pub trait Container {
+    /// What goes in and comes out, like an [`ItemStack`]
+    type Contents;
+    /// How much of the contents there is, like a number of items
+    type Amount: Copy + PartialEq;
+
+    /// How much is in the container
+    fn stored(&self) -> Self::Amount;
+
+    /// Put in as much of `contents` as fits, returning whatever doesn't
+    fn insert(&mut self, contents: Self::Contents) -> Option<Self::Contents>;
+
+    /// Take out up to `max` of whatever comes out first
+    fn extract(&mut self, max: Self::Amount) -> Option<Self::Contents>;
+}
+
+/// Move up to `max` out of `from` and into `to`, leaving whatever `to` can't hold in `from`
+///
+/// Returns `true` if anything moved.
+pub fn transfer<T, A: Copy + PartialEq>(
+    from: &mut impl Container<Contents = T, Amount = A>,
+    to: &mut impl Container<Contents = T, Amount = A>,
+    max: A,
+) -> bool {
+    let before = from.stored();
+    let Some(taken) = from.extract(max) else {
+        return false;
+    };
+    if let Some(rest) = to.insert(taken) {
+        // It just came out of `from`, so there's room for it
+        _ = from.insert(rest);
+    }
+    from.stored() != before
+}
+
+/// A fixed number of slots, each holding a stack of up to some number of items
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemSlots {
+    /// Never empty stacks, in the order they were first put in
+    stacks: Vec<ItemStack>,
+    slots: usize,
+    stack_limit: u32,
+}
+
+impl ItemSlots {
+    pub const fn new(slots: usize, stack_limit: u32) -> Self {
+        Self {
+            stacks: Vec::new(),
+            slots,
+            stack_limit,
+        }
+    }
+
+    /// The stack in each slot that isn't empty
+    #[inline]
+    pub fn stacks(&self) -> &[ItemStack] {
+        &self.stacks
+    }
+
+    /// How many stacks fit, including empty slots
+    #[inline]
+    pub const fn slot_count(&self) -> usize {
+        self.slots
+    }
+
+    /// Take up to `max` from the stack in `slot`, emptying the slot if that's all of it
+    pub fn take(&mut self, slot: usize, max: u32) -> Option<ItemStack> {
+        let stack = self.stacks.get_mut(slot)?;
+        let taken = stack.split(max);
+        if stack.count == 0 {
+            self.stacks.remove(slot);
+        }
+        taken
+    }
+
+    /// Take out every one of `stacks`, returning `false`, changing nothing, unless they're all
+    /// there
+    pub fn take_all(&mut self, stacks: &[ItemStack]) -> bool {
+        let mut left = self.clone();
+        for wanted in stacks {
+            let mut count = wanted.count;
+            while count > 0
+                && let Some(slot) = (left.stacks.iter()).position(|stack| stack.item == wanted.item)
+                && let Some(taken) = left.take(slot, count)
+            {
+                count -= taken.count;
+            }
+            if count > 0 {
+                return false;
+            }
+        }
+        *self = left;
+        true
+    }
+}
+
+impl Container for ItemSlots {
+    type Contents = ItemStack;
+    type Amount = u32;
+
+    fn stored(&self) -> u32 {
+        (self.stacks.iter()).fold(0, |total, stack| total.saturating_add(stack.count))
+    }
+
+    /// Tops up stacks of the same item before starting new ones
+    fn insert(&mut self, mut contents: ItemStack) -> Option<ItemStack> {
+        if contents.count == 0 {
+            return None;
+        }
+        for stack in &mut self.stacks {
+            contents = stack.merge(contents, self.stack_limit)?;
+        }
+        while self.stacks.len() < self.slots && self.stack_limit > 0 {
+            let mut stack = ItemStack::new(contents.item.clone(), 0);
+            let rest = stack.merge(contents, self.stack_limit);
+            self.stacks.push(stack);
+            contents = rest?;
+        }
+        Some(contents)
+    }
+
+    /// Takes from the first stack
+    fn extract(&mut self, max: u32) -> Option<ItemStack> {
+        self.take(0, max)
+    }
+}
+
+/// Holds up to some number of liters of a single fluid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FluidTank {
+    contents: Option<FluidVolume>,
+    capacity: Q32_32,
+}
+
+impl FluidTank {
+    pub const fn new(capacity: Q32_32) -> Self {
+        Self {
+            contents: None,
+            capacity,
+        }
+    }
+
+    /// The fluid in the tank, if it isn't empty
+    #[inline]
+    pub const fn contents(&self) -> Option<&FluidVolume> {
+        self.contents.as_ref()
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> Q32_32 {
+        self.capacity
+    }
+}
+
+impl Container for FluidTank {
+    type Contents = FluidVolume;
+    type Amount = Q32_32;
+
+    fn stored(&self) -> Q32_32 {
+        self.contents
+            .as_ref()
+            .map_or(Q32_32::ZERO, |fluid| fluid.liters)
+    }
+
+    /// Only takes the fluid already in the tank, or anything if it's empty
+    fn insert(&mut self, contents: FluidVolume) -> Option<FluidVolume> {
+        let fluid = self
+            .contents
+            .get_or_insert_with(|| FluidVolume::new(contents.molecule.clone(), Q32_32::ZERO));
+        let rest = fluid.merge(contents, self.capacity);
+        if fluid.liters == Q32_32::ZERO {
+            self.contents = None;
+        }
+        rest
+    }
+
+    fn extract(&mut self, max: Q32_32) -> Option<FluidVolume> {
+        let fluid = self.contents.as_mut()?;
+        let taken = fluid.split(max);
+        if fluid.liters == Q32_32::ZERO {
+            self.contents = None;
+        }
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_slots() {
+        let water = || "H2O".parse::<Item>().unwrap();
+        let salt = || "NaCl".parse::<Item>().unwrap();
+        let mut slots = ItemSlots::new(2, 10);
+        assert_eq!(slots.insert(ItemStack::new(water(), 15)), None);
+        assert_eq!(
+            slots.insert(ItemStack::new(salt(), 1)),
+            Some(ItemStack::new(salt(), 1)),
+            "both slots should be taken by water"
+        );
+        assert_eq!(slots.stored(), 15);
+
+        let mut other = ItemSlots::new(1, 4);
+        assert!(transfer(&mut slots, &mut other, 6));
+        assert_eq!(other.stacks(), [ItemStack::new(water(), 4)]);
+        assert_eq!(slots.stored(), 11, "what didn't fit should go back");
+        assert!(!transfer(&mut slots, &mut other, 6));
+    }
+
+    #[test]
+    fn test_fluid_tank() {
+        let water = || "H2O".parse::<Molecule>().unwrap();
+        let liters = Q32_32::from_i32;
+        let mut tank = FluidTank::new(liters(100));
+        let rest = tank.insert(FluidVolume::new(water(), liters(120)));
+        assert_eq!(rest, Some(FluidVolume::new(water(), liters(20))));
+        let brine = FluidVolume::new("NaCl".parse().unwrap(), liters(5));
+        assert_eq!(
+            tank.insert(brine.clone()),
+            Some(brine),
+            "fluids shouldn't mix"
+        );
+
+        let mut pipe = FluidTank::new(liters(30));
+        assert!(transfer(&mut tank, &mut pipe, liters(50)));
+        assert_eq!(pipe.stored(), liters(30));
+        assert_eq!(tank.stored(), liters(70));
+        assert_eq!(
+            pipe.extract(liters(30)).map(|fluid| fluid.liters),
+            Some(liters(30))
+        );
+        assert_eq!(pipe.extract(liters(1)), None);
+    }
+}
@@ -0,0 +1,320 @@
+//! Deliveries asked for in exchange for research points and credits
+//!
+//! Every so often a new [`Contract`] is offered on the contract board in the lab, asking for some
+//! of an item the factories already make to be delivered to a stop before time runs out. Once
+//! accepted, what trains unload there is counted from the factories'
+//! [`Statistics`](crate::stats::Statistics), and the [`Reward`] is paid as soon as enough has
+//! arrived. Contracts that run out of time pay nothing.
+
+use crate::{
+    container::Item,
+    region::{factory::Factory, rail::network::StopId},
+    rng::Rng,
+};
+use std::collections::{BTreeSet, VecDeque};
+
+pub mod panel;
+
+/// Seconds between new offers
+const OFFER_INTERVAL: f32 = 180.0;
+/// Contracts ask for a multiple of this many items
+const BATCH: u32 = 50;
+/// The most batches a contract asks for
+const MAX_BATCHES: u32 = 10;
+/// Seconds given for each item asked for, on top of [`BASE_TIME`]
+const TIME_PER_ITEM: f32 = 2.4;
+/// Seconds given for every contract, to build the route
+const BASE_TIME: f32 = 300.0;
+
+/// What a contract pays once it's fulfilled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Reward {
+    pub research_points: u32,
+    pub credits: u32,
+}
+
+impl std::ops::AddAssign for Reward {
+    fn add_assign(&mut self, rhs: Self) {
+        self.research_points = self.research_points.saturating_add(rhs.research_points);
+        self.credits = self.credits.saturating_add(rhs.credits);
+    }
+}
+
+/// A request for `count` of `item`, unloaded at `stop` within `time_limit` seconds of accepting
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contract {
+    pub item: Item,
+    pub count: u32,
+    pub stop: StopId,
+    pub time_limit: f32,
+    pub reward: Reward,
+}
+
+/// A contract being worked on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Accepted {
+    pub contract: Contract,
+    /// Seconds until it expires
+    pub time_left: f32,
+    /// How many have been delivered since it was accepted
+    pub delivered: u32,
+    /// How many had ever been delivered to the stop when it was accepted, which don't count
+    baseline: u64,
+}
+
+impl Accepted {
+    /// How close the contract is to being fulfilled, from 0 to 1
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "contracts ask for far fewer than 2^24 items"
+    )]
+    pub fn progress(&self) -> f32 {
+        (self.delivered as f32 / self.contract.count.max(1) as f32).min(1.0)
+    }
+}
+
+/// How a contract ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Everything was delivered in time, and the reward paid
+    Fulfilled,
+    /// Time ran out first
+    Expired,
+}
+
+/// Why a contract couldn't be accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcceptError {
+    /// There's no offer there
+    NoOffer,
+    /// [`Contracts::MAX_ACCEPTED`] are already being worked on
+    TooMany,
+}
+
+impl std::fmt::Display for AcceptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoOffer => f.write_str("there's no such offer"),
+            Self::TooMany => write!(
+                f,
+                "only {} contracts can be taken at once",
+                Contracts::MAX_ACCEPTED
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AcceptError {}
+
+/// The contracts on offer, the ones being worked on, and how the last few ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contracts {
+    offers: Vec<Contract>,
+    accepted: Vec<Accepted>,
+    /// Most recent first
+    finished: VecDeque<(Contract, Outcome)>,
+    /// Seconds since the last offer
+    since_offer: f32,
+    /// Picks what each offer asks for
+    rng: Rng,
+}
+
+impl Contracts {
+    /// The most offers on the board at once
+    pub const MAX_OFFERS: usize = 3;
+    /// The most contracts worked on at once
+    pub const MAX_ACCEPTED: usize = 3;
+    /// How many finished contracts are remembered
+    pub const HISTORY: usize = 5;
+
+    /// No contracts yet, with offers picked by `rng`
+    pub const fn new(rng: Rng) -> Self {
+        Self {
+            offers: Vec::new(),
+            accepted: Vec::new(),
+            finished: VecDeque::new(),
+            since_offer: 0.0,
+            rng,
+        }
+    }
+
+    #[inline]
+    pub fn offers(&self) -> &[Contract] {
+        &self.offers
+    }
+
+    #[inline]
+    pub fn accepted(&self) -> &[Accepted] {
+        &self.accepted
+    }
+
+    /// The last few contracts that ended, and how, most recent first
+    pub fn finished(&self) -> impl Iterator<Item = &(Contract, Outcome)> {
+        self.finished.iter()
+    }
+
+    /// Start working on the offer at `idx`, counting deliveries from now
+    pub fn accept(&mut self, idx: usize, factories: &[Factory]) -> Result<(), AcceptError> {
+        if idx >= self.offers.len() {
+            return Err(AcceptError::NoOffer);
+        }
+        if self.accepted.len() >= Self::MAX_ACCEPTED {
+            return Err(AcceptError::TooMany);
+        }
+        let contract = self.offers.remove(idx);
+        self.accepted.push(Accepted {
+            time_left: contract.time_limit,
+            delivered: 0,
+            baseline: total_delivered(factories, &contract),
+            contract,
+        });
+        Ok(())
+    }
+
+    /// Advance `dt` seconds, offering a new contract for one of `stops` every so often, and
+    /// returning what was earned by contracts fulfilled in the meantime
+    pub fn tick(&mut self, dt: f32, factories: &[Factory], stops: &[StopId]) -> Reward {
+        self.since_offer += dt;
+        if self.since_offer >= OFFER_INTERVAL {
+            self.since_offer -= OFFER_INTERVAL;
+            let items = (factories.iter())
+                .flat_map(|factory| factory.statistics().items())
+                .filter(|(_, rates)| rates.total_produced > 0)
+                .map(|(item, _)| item.clone())
+                .collect::<BTreeSet<_>>();
+            self.offer(&items.into_iter().collect::<Vec<_>>(), stops);
+        }
+        self.settle(dt, |contract| total_delivered(factories, contract))
+    }
+
+    /// Offer a contract for one of `items` at one of `stops`, if there's room on the board and
+    /// anything to ask for
+    fn offer(&mut self, items: &[Item], stops: &[StopId]) {
+        if self.offers.len() >= Self::MAX_OFFERS {
+            return;
+        }
+        let (Some(item), Some(&stop)) = (self.rng.pick(items), self.rng.pick(stops)) else {
+            return;
+        };
+        let count = BATCH * (1 + self.rng.below(MAX_BATCHES));
+        #[allow(
+            clippy::cast_precision_loss,
+            reason = "contracts ask for far fewer than 2^24 items"
+        )]
+        let time_limit = BASE_TIME + count as f32 * TIME_PER_ITEM;
+        self.offers.push(Contract {
+            item: item.clone(),
+            count,
+            stop,
+            time_limit,
+            reward: Reward {
+                research_points: count / 10,
+                credits: count * 3,
+            },
+        });
+    }
+
+    /// Count down the contracts being worked on, ending the ones that have had everything
+    /// `delivered` or run out of time, and returning what was earned
+    fn settle(&mut self, dt: f32, delivered: impl Fn(&Contract) -> u64) -> Reward {
+        let mut earned = Reward::default();
+        let mut ended = Vec::new();
+        self.accepted.retain_mut(|accepted| {
+            let since = delivered(&accepted.contract).saturating_sub(accepted.baseline);
+            accepted.delivered = u32::try_from(since).unwrap_or(u32::MAX);
+            accepted.time_left -= dt;
+            let outcome = if accepted.delivered >= accepted.contract.count {
+                earned += accepted.contract.reward;
+                Outcome::Fulfilled
+            } else if accepted.time_left <= 0.0 {
+                Outcome::Expired
+            } else {
+                return true;
+            };
+            ended.push((accepted.contract.clone(), outcome));
+            false
+        });
+        for ended in ended {
+            self.finished.push_front(ended);
+        }
+        self.finished.truncate(Self::HISTORY);
+        earned
+    }
+}
+
+/// How many of `contract`'s item have ever been unloaded at its stop, in every factory together
+fn total_delivered(factories: &[Factory], contract: &Contract) -> u64 {
+    (factories.iter())
+        .map(|factory| (factory.statistics()).total_delivered(contract.stop, &contract.item))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::coords::RailVector3,
+        region::rail::network::{RailNetwork, Track},
+    };
+
+    #[test]
+    fn test_contracts() {
+        let water = "H2O".parse::<Item>().unwrap();
+        let iron = "Fe".parse::<Item>().unwrap();
+        let mut rail = RailNetwork::new(vec![Track {
+            points: vec![RailVector3::new(0, 0, 0), RailVector3::new(10, 0, 0)],
+        }]);
+        _ = rail.stop_at(RailVector3::new(2, 0, 0));
+        _ = rail.stop_at(RailVector3::new(8, 0, 0));
+        let stops = rail.stop_ids().collect::<Vec<_>>();
+        let mut contracts = Contracts::new(Rng::new(0));
+        contracts.offer(&[], &stops);
+        assert!(contracts.offers().is_empty(), "nothing's made yet");
+        for _ in 0..5 {
+            contracts.offer(&[water.clone(), iron.clone()], &stops);
+        }
+        assert_eq!(contracts.offers().len(), Contracts::MAX_OFFERS);
+        assert_ne!(contracts.offers()[0], contracts.offers()[1]);
+        for offer in contracts.offers() {
+            assert_eq!(offer.count % BATCH, 0);
+            assert!((BATCH..=BATCH * MAX_BATCHES).contains(&offer.count));
+        }
+
+        let first = contracts.offers()[0].clone();
+        contracts.accept(0, &[]).unwrap();
+        contracts.accept(0, &[]).unwrap();
+        assert_eq!(contracts.accept(5, &[]), Err(AcceptError::NoOffer));
+        contracts.accept(0, &[]).unwrap();
+        assert!(contracts.offers().is_empty());
+        contracts.offer(&[water], &stops);
+        assert_eq!(contracts.accept(0, &[]), Err(AcceptError::TooMany));
+
+        let delivered = |contract: &Contract| {
+            if *contract == first {
+                u64::from(first.count)
+            } else {
+                1
+            }
+        };
+        let earned = contracts.settle(1.0, delivered);
+        assert_eq!(earned, first.reward);
+        assert_eq!(contracts.accepted().len(), 2);
+        assert_eq!(contracts.accepted()[0].delivered, 1);
+        assert!(contracts.accepted()[0].progress() > 0.0);
+
+        let earned = contracts.settle(BASE_TIME + TIME_PER_ITEM * 500.0, delivered);
+        assert_eq!(
+            earned,
+            Reward::default(),
+            "nothing's paid for late contracts"
+        );
+        assert!(contracts.accepted().is_empty());
+        let outcomes = (contracts.finished())
+            .map(|(_, outcome)| *outcome)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            [Outcome::Expired, Outcome::Expired, Outcome::Fulfilled]
+        );
+    }
+}
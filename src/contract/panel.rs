@@ -0,0 +1,273 @@
+//! The contract board, opened from the board in the lab
+
+use super::{AcceptError, Contract, Contracts, Outcome};
+use crate::{game::GameState, input::Inputs, locale::tr, ui::Panel};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer},
+};
+use raylib::prelude::*;
+
+const MARGIN: f32 = 16.0;
+const FONT_SIZE: f32 = 20.0;
+const ROW_HEIGHT: f32 = FONT_SIZE + 8.0;
+const WIDTH: f32 = 760.0;
+/// Rows for the title, the three headings, and the notice at the bottom
+const FIXED_ROWS: usize = 5;
+
+const BACKGROUND: Color = Color::new(16, 16, 24, 230);
+const HOVERED: Color = Color::new(255, 255, 255, 32);
+const SELECTED: Color = Color::new(96, 160, 224, 255);
+const PROGRESS: Color = Color::new(96, 192, 96, 96);
+const FULFILLED: Color = Color::new(96, 192, 96, 255);
+const EXPIRED: Color = Color::new(224, 96, 64, 255);
+
+/// Whole minutes in `seconds`, rounding up so there's never 0 minutes left
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "contracts last much less than u32::MAX minutes"
+)]
+fn minutes(seconds: f32) -> u32 {
+    (seconds / 60.0).ceil().max(0.0) as u32
+}
+
+/// Where `contract`'s stop is, for reading out
+fn stop_label(state: &GameState, contract: &Contract) -> String {
+    (state.world.rail.stop(contract.stop)).map_or_else(String::new, |stop| {
+        format!("{}, {}, {}", stop.cell.x, stop.cell.y, stop.cell.z)
+    })
+}
+
+/// The contracts on offer, which can be clicked to take them, the ones being worked on with how
+/// far along they are, and how the last few ended
+///
+/// Up and down select an offer, and enter takes it.
+#[derive(Debug, Clone, Default)]
+pub struct ContractPanel {
+    title: String,
+    offers: Vec<String>,
+    /// Each contract being worked on, and how close it is to being fulfilled
+    accepted: Vec<(String, f32)>,
+    finished: Vec<(String, Outcome)>,
+    /// The offer to take on enter
+    selected: usize,
+    /// The offer under the mouse cursor
+    hovered: Option<usize>,
+    /// Why the last offer couldn't be taken
+    notice: Option<String>,
+    /// Screen-space area of the whole panel
+    rect: Rectangle,
+}
+
+impl ContractPanel {
+    pub fn new(rl: &RaylibHandle, state: &GameState) -> Self {
+        let mut panel = Self::default();
+        panel.refresh(state);
+        panel.layout(rl);
+        panel
+    }
+
+    /// Describe the contracts in `state`
+    fn refresh(&mut self, state: &GameState) {
+        let contracts = &state.contracts;
+        self.title = tr!("contract_panel.title", credits = state.credits);
+        self.offers = (contracts.offers().iter())
+            .map(|offer| {
+                tr!(
+                    "contract_panel.offer",
+                    count = offer.count,
+                    item = offer.item,
+                    stop = stop_label(state, offer),
+                    minutes = minutes(offer.time_limit),
+                    points = offer.reward.research_points,
+                    credits = offer.reward.credits,
+                )
+            })
+            .collect();
+        self.accepted = (contracts.accepted().iter())
+            .map(|accepted| {
+                let contract = &accepted.contract;
+                let text = tr!(
+                    "contract_panel.progress",
+                    delivered = accepted.delivered,
+                    count = contract.count,
+                    item = contract.item,
+                    stop = stop_label(state, contract),
+                    minutes = minutes(accepted.time_left),
+                );
+                (text, accepted.progress())
+            })
+            .collect();
+        self.finished = (contracts.finished())
+            .map(|(contract, outcome)| {
+                let key = match outcome {
+                    Outcome::Fulfilled => "contract_panel.fulfilled",
+                    Outcome::Expired => "contract_panel.expired",
+                };
+                let text = tr!(key, count = contract.count, item = contract.item);
+                (text, *outcome)
+            })
+            .collect();
+        self.selected = self.selected.min(self.offers.len().saturating_sub(1));
+    }
+
+    /// Center the panel on the screen, tall enough for the most contracts there can be
+    #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+    fn layout(&mut self, rl: &RaylibHandle) {
+        let rows =
+            FIXED_ROWS + Contracts::MAX_OFFERS + Contracts::MAX_ACCEPTED + Contracts::HISTORY;
+        let height = rows as f32 * ROW_HEIGHT + 2.0 * MARGIN;
+        self.rect = Rectangle::new(
+            0.5 * (rl.get_screen_width() as f32 - WIDTH),
+            0.5 * (rl.get_screen_height() as f32 - height),
+            WIDTH,
+            height,
+        );
+    }
+
+    /// Screen-space area of the `row`th row, counting from the title
+    #[allow(clippy::cast_precision_loss, reason = "there are only a few rows")]
+    fn row_rect(&self, row: usize) -> Rectangle {
+        Rectangle::new(
+            self.rect.x + MARGIN,
+            self.rect.y + MARGIN + row as f32 * ROW_HEIGHT,
+            WIDTH - 2.0 * MARGIN,
+            ROW_HEIGHT,
+        )
+    }
+
+    /// The row the offer at `idx` is in, under the title and the heading
+    const fn offer_row(idx: usize) -> usize {
+        2 + idx
+    }
+
+    /// Take the offer at `idx`, or say why it couldn't be taken
+    fn accept(&mut self, idx: usize, state: &mut GameState) {
+        self.notice = (state.contracts.accept(idx, &state.factories).err()).map(|err| {
+            tr!(match err {
+                AcceptError::NoOffer => "contract_panel.no_offer",
+                AcceptError::TooMany => "contract_panel.too_many",
+            })
+        });
+        self.refresh(state);
+    }
+
+    fn draw_text(
+        &self,
+        d: &mut Renderer<'_>,
+        row: usize,
+        text: &str,
+        color: Color,
+    ) -> draw::Result {
+        let rect = self.row_rect(row);
+        d.draw_text(
+            text,
+            Vector2::new(rect.x + 8.0, rect.y + 0.5 * (rect.height - FONT_SIZE)),
+            FONT_SIZE,
+            0.0,
+            color,
+        )
+    }
+}
+
+impl Panel for ContractPanel {
+    /// Closes on escape
+    fn update(&mut self, rl: &mut RaylibHandle, _inputs: &Inputs, state: &mut GameState) -> bool {
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            return false;
+        }
+        self.layout(rl);
+        self.refresh(state);
+
+        if rl.is_key_pressed(KeyboardKey::KEY_DOWN) {
+            self.selected = (self.selected + 1).min(self.offers.len().saturating_sub(1));
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_UP) {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) && !self.offers.is_empty() {
+            self.accept(self.selected, state);
+        }
+        let mouse = rl.get_mouse_position();
+        self.hovered = (0..self.offers.len())
+            .find(|&idx| (self.row_rect(Self::offer_row(idx))).check_collision_point_rec(mouse));
+        if let Some(idx) = self.hovered
+            && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+        {
+            self.selected = idx;
+            self.accept(idx, state);
+        }
+        true
+    }
+}
+
+impl Draw for ContractPanel {
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        d.draw_rectangle(self.rect, BACKGROUND)?;
+        self.draw_text(d, 0, &self.title, Color::WHITE)?;
+
+        self.draw_text(d, 1, &tr!("contract_panel.offers"), Color::LIGHTGRAY)?;
+        if self.offers.is_empty() {
+            self.draw_text(d, 2, &tr!("contract_panel.no_offers"), Color::GRAY)?;
+        }
+        for (idx, offer) in self.offers.iter().enumerate() {
+            let row = Self::offer_row(idx);
+            if idx == self.selected {
+                d.draw_rectangle(self.row_rect(row), SELECTED)?;
+            } else if self.hovered == Some(idx) {
+                d.draw_rectangle(self.row_rect(row), HOVERED)?;
+            }
+            self.draw_text(d, row, offer, Color::WHITE)?;
+        }
+
+        let heading = Self::offer_row(Contracts::MAX_OFFERS);
+        self.draw_text(
+            d,
+            heading,
+            &tr!("contract_panel.accepted"),
+            Color::LIGHTGRAY,
+        )?;
+        if self.accepted.is_empty() {
+            self.draw_text(
+                d,
+                heading + 1,
+                &tr!("contract_panel.none_accepted"),
+                Color::GRAY,
+            )?;
+        }
+        for (idx, (text, progress)) in self.accepted.iter().enumerate() {
+            let row = heading + 1 + idx;
+            let rect = self.row_rect(row);
+            d.draw_rectangle(
+                Rectangle {
+                    width: rect.width * progress,
+                    ..rect
+                },
+                PROGRESS,
+            )?;
+            self.draw_text(d, row, text, Color::WHITE)?;
+        }
+
+        let heading = heading + 1 + Contracts::MAX_ACCEPTED;
+        self.draw_text(
+            d,
+            heading,
+            &tr!("contract_panel.finished"),
+            Color::LIGHTGRAY,
+        )?;
+        for (idx, (text, outcome)) in self.finished.iter().enumerate() {
+            let color = match outcome {
+                Outcome::Fulfilled => FULFILLED,
+                Outcome::Expired => EXPIRED,
+            };
+            self.draw_text(d, heading + 1 + idx, text, color)?;
+        }
+
+        if let Some(notice) = &self.notice {
+            let row = heading + 1 + Contracts::HISTORY;
+            self.draw_text(d, row, notice, EXPIRED)?;
+        }
+        Ok(())
+    }
+}
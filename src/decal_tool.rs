@@ -0,0 +1,166 @@
+use crate::{
+    game::GameState,
+    input::{EventInput, Inputs},
+    locale::tr,
+    math::coords::{FactoryVector3, VectorConstants},
+    ordinals::Cardinal2D,
+    palette::palette,
+    placement::Spot,
+    region::{
+        RegionId,
+        factory::decal::{Decal, DecalKind},
+    },
+    rl_helpers::{DynRaylibDraw3D, DynRender},
+};
+use engine::{draw, draw3d};
+use raylib::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Mode {
+    #[default]
+    Off,
+    /// Painting decals on the floor
+    Paint,
+    /// Clearing decals off the floor
+    Erase,
+}
+
+/// Paints [decals](crate::region::factory::decal) on factory floors, and erases them
+///
+/// In paint mode, interacting paints the chosen decal on the floor the player is looking at,
+/// scrolling picks another, and rotating turns it. Where it's painted follows the player's
+/// [snapping](crate::placement::Snap), so it can be off the grid or flush against a machine. In
+/// erase mode, interacting clears whatever's painted on that block.
+#[derive(Debug, Default)]
+pub struct DecalTool {
+    mode: Mode,
+    /// What's painted next
+    kind: DecalKind,
+    /// The turn of what's painted next
+    rotation: Cardinal2D,
+    /// Where on the floor the player is looking, snapped
+    target: Option<Spot>,
+}
+
+impl DecalTool {
+    /// Whether the tool is out, and so interacting is used by it
+    #[inline]
+    pub const fn is_active(&self) -> bool {
+        !matches!(self.mode, Mode::Off)
+    }
+
+    /// Put the tool away
+    pub const fn close(&mut self) {
+        self.mode = Mode::Off;
+        self.target = None;
+    }
+
+    /// Pick the next or previous decal with the inputs that would otherwise change the hotbar
+    /// slot, taking them so the slot stays put while painting
+    pub fn scroll(&mut self, inputs: &mut Inputs) {
+        if self.mode != Mode::Paint {
+            return;
+        }
+        if std::mem::take(&mut inputs[EventInput::NextItem]) {
+            self.kind = self.kind.next();
+        }
+        if std::mem::take(&mut inputs[EventInput::PrevItem]) {
+            self.kind = self.kind.prev();
+        }
+    }
+
+    /// Switch modes, and paint or erase
+    pub fn update(&mut self, inputs: &Inputs, state: &mut GameState) {
+        if inputs[EventInput::ToggleDecalTool] {
+            self.mode = match self.mode {
+                Mode::Off => Mode::Paint,
+                Mode::Paint => Mode::Erase,
+                Mode::Erase => Mode::Off,
+            };
+        }
+        let RegionId::Factory(idx) = state.current_region else {
+            self.target = None;
+            return;
+        };
+        if self.mode == Mode::Off {
+            self.target = None;
+            return;
+        }
+        if inputs[EventInput::RotatePlacement] {
+            self.rotation = self.rotation.compose(Cardinal2D::North);
+        }
+        let factory = &mut state.factories[idx];
+        self.target = state.player.snap.spot(factory, &state.player);
+        let Some(target) = self.target else {
+            return;
+        };
+        if !inputs[EventInput::Interact] {
+            return;
+        }
+        match self.mode {
+            Mode::Off => {}
+            Mode::Paint => {
+                let decal = Decal {
+                    offset: target.offset,
+                    ..Decal::new(target.block, self.rotation, self.kind)
+                };
+                _ = factory.paint_decal(decal);
+            }
+            Mode::Erase => _ = factory.erase_decal(target.block),
+        }
+    }
+
+    /// Tells the player what to do next, or [`None`] if the tool isn't in use
+    #[must_use]
+    pub fn prompt(&self) -> Option<String> {
+        Some(match (self.mode, self.target) {
+            (Mode::Off, _) => return None,
+            (Mode::Paint, None) => tr!("decal_tool.find_floor"),
+            (Mode::Paint, Some(_)) => tr!("decal_tool.paint", decal = self.kind.name()),
+            (Mode::Erase, None) => tr!("decal_tool.find_decal"),
+            (Mode::Erase, Some(_)) => tr!("decal_tool.erase"),
+        })
+    }
+
+    /// Outline where the decal would be painted, or the block being erased, in factory
+    /// coordinates
+    fn draw_target(&self, d: &mut draw3d::Renderer<'_>, target: Spot) -> draw::Result {
+        let (color, center) = match self.mode {
+            Mode::Erase => (
+                palette().invalid,
+                target.block.as_vec3() + Vector3::new(0.5, 0.0, 0.5),
+            ),
+            Mode::Off | Mode::Paint => (Color::WHITE, target.center()),
+        };
+        d.draw_bounding_box(
+            BoundingBox {
+                min: center - Vector3::new(0.5, 0.0, 0.5),
+                max: center + Vector3::new(0.5, 0.05, 0.5),
+            },
+            color,
+        )?;
+        if self.mode == Mode::Paint {
+            // Which way the decal would face
+            let center = center + Vector3::new(0.0, 0.05, 0.0);
+            let facing = self.rotation.rotate_vector(FactoryVector3::new(1, 0, 0));
+            d.draw_line(center, center + facing.as_vec3() * 0.5, None, color)?;
+        }
+        Ok(())
+    }
+
+    /// Highlight where the tool would paint or erase in the 3D view
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D, state: &GameState) {
+        let (Some(target), RegionId::Factory(idx)) = (self.target, state.current_region) else {
+            return;
+        };
+        let origin = &state.factories[idx].origin;
+        let mut buf = DynRender(d);
+        let mut d = draw3d::Renderer::new(
+            &mut buf,
+            *draw3d::RenderingOptions::new()
+                .offset(FactoryVector3::ZERO.to_player_relative(&state.player.position, origin)),
+        );
+        // Can't fail when drawing directly to the screen
+        _ = self.draw_target(&mut d, target);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{
+    game::GameState,
+    input::{EventInput, Inputs},
+    locale::tr,
+    math::coords::{FactoryVector3, VectorConstants},
+    palette::palette,
+    region::{
+        RegionId,
+        factory::{MachineId, block_center, routing},
+    },
+    rl_helpers::{DynRaylibDraw3D, DynRender},
+};
+use engine::{draw, draw3d};
+use raylib::prelude::*;
+
+/// Tears down machines, along with the belts and pipes connected to them
+///
+/// Whatever they were holding goes into the player's inventory.
+#[derive(Debug, Default)]
+pub struct DemolishTool {
+    pub is_active: bool,
+    /// The factory and machine the player is looking at
+    target: Option<(usize, MachineId)>,
+    /// How many items didn't fit in the inventory last time something was demolished
+    lost: u32,
+}
+
+impl DemolishTool {
+    /// Put the tool away
+    pub const fn close(&mut self) {
+        self.is_active = false;
+        self.target = None;
+        self.lost = 0;
+    }
+
+    /// Toggle the tool, and pick or demolish a machine
+    pub fn update(&mut self, inputs: &Inputs, state: &mut GameState) {
+        if inputs[EventInput::ToggleDemolishTool] {
+            if self.is_active {
+                self.close();
+            } else {
+                self.is_active = true;
+            }
+        }
+        if !self.is_active {
+            return;
+        }
+        let target = state.looked_at_machine_id();
+        if target != self.target {
+            self.lost = 0;
+        }
+        self.target = target;
+        if inputs[EventInput::Interact]
+            && let Some((idx, id)) = self.target.take()
+            && let Some(lost) = state.demolish(idx, id)
+        {
+            self.lost = lost;
+        }
+    }
+
+    /// Tells the player what to do next, or [`None`] if the tool isn't in use
+    #[must_use]
+    pub fn prompt(&self) -> Option<String> {
+        if !self.is_active {
+            return None;
+        }
+        Some(match (self.target, self.lost) {
+            (Some(_), _) => tr!("demolish_tool.demolish"),
+            (None, 0) => tr!("demolish_tool.find_machine"),
+            (None, lost) => tr!("demolish_tool.lost", lost = lost),
+        })
+    }
+
+    /// Draw the machine that would be demolished and everything connected to it, in factory
+    /// coordinates
+    fn draw_target(
+        d: &mut draw3d::Renderer<'_>,
+        state: &GameState,
+        idx: usize,
+        id: MachineId,
+    ) -> draw::Result {
+        const EXPAND: Vector3 = Vector3::splat(0.025);
+        let factory = &state.factories[idx];
+        let Some(machine) = factory.machine(id) else {
+            return Ok(());
+        };
+        let bounds = machine.bounds();
+        d.draw_bounding_box(
+            BoundingBox {
+                min: bounds.min.as_vec3() - EXPAND,
+                max: bounds.max.as_vec3() + EXPAND,
+            },
+            palette().invalid,
+        )?;
+        for path in factory.attached_paths(id) {
+            for cell in routing::route_cells(path) {
+                d.draw_bounding_box(
+                    BoundingBox {
+                        min: cell.as_vec3(),
+                        max: (cell + FactoryVector3::ONE).as_vec3(),
+                    },
+                    palette().invalid,
+                )?;
+            }
+            let path = path.iter().copied().map(block_center).collect::<Vec<_>>();
+            d.draw_line_strip(&path, None, palette().invalid)?;
+        }
+        Ok(())
+    }
+
+    /// Highlight what would be demolished in the 3D view
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D, state: &GameState) {
+        let Some((idx, id)) = self.target else {
+            return;
+        };
+        if state.current_region != RegionId::Factory(idx) {
+            return;
+        }
+        let origin = &state.factories[idx].origin;
+        let mut buf = DynRender(d);
+        let mut d = draw3d::Renderer::new(
+            &mut buf,
+            *draw3d::RenderingOptions::new()
+                .offset(FactoryVector3::ZERO.to_player_relative(&state.player.position, origin)),
+        );
+        // Can't fail when drawing directly to the screen
+        _ = Self::draw_target(&mut d, state, idx, id);
+    }
+}
@@ -0,0 +1,220 @@
+//! A small entity store
+//!
+//! An [`Entity`] is only an id. What it is comes from the components attached to it, each kind
+//! kept in its own [`Components`] store, so a new kind of thing is a new combination of
+//! components rather than a new list everywhere that handles things.
+//!
+//! Ids are generational: once an entity is despawned, an id kept from before won't find
+//! whatever reuses its slot.
+
+/// Identifies something made of components
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+/// Hands out [`Entity`] ids, reusing the slots of despawned ones
+#[derive(Debug, Clone, Default)]
+pub struct Entities {
+    /// The current generation of each slot, and whether it's in use
+    slots: Vec<(u32, bool)>,
+    /// Slots not in use
+    free: Vec<u32>,
+}
+
+impl Entities {
+    pub const fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// How many entities are alive
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// A new entity, with no components yet
+    pub fn spawn(&mut self) -> Entity {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.1 = true;
+            Entity {
+                index,
+                generation: slot.0,
+            }
+        } else {
+            let index = u32::try_from(self.slots.len()).expect("too many entities");
+            self.slots.push((0, true));
+            Entity {
+                index,
+                generation: 0,
+            }
+        }
+    }
+
+    /// Free `entity`'s id for reuse, returning `false` if it was already despawned
+    ///
+    /// Its components aren't removed, that's up to whoever owns the stores.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        let slot = &mut self.slots[entity.index as usize];
+        *slot = (slot.0.wrapping_add(1), false);
+        self.free.push(entity.index);
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.slots.get(entity.index as usize) == Some(&(entity.generation, true))
+    }
+}
+
+/// One kind of component, for any number of entities
+///
+/// Components are packed together in the order they were inserted, except that removing one
+/// moves the last into its place, like [`Vec::swap_remove`].
+#[derive(Debug, Clone)]
+pub struct Components<T> {
+    /// Where each entity's component is in `values`, by entity index
+    sparse: Vec<Option<usize>>,
+    /// The entity each of `values` belongs to
+    entities: Vec<Entity>,
+    values: Vec<T>,
+}
+
+impl<T> Default for Components<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Components<T> {
+    pub const fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            entities: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Where `entity`'s component is in [`Self::as_slice`]
+    pub fn position(&self, entity: Entity) -> Option<usize> {
+        let idx = (*self.sparse.get(entity.index as usize)?)?;
+        (self.entities[idx] == entity).then_some(idx)
+    }
+
+    #[inline]
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.position(entity).is_some()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.position(entity).map(|idx| &self.values[idx])
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.position(entity).map(|idx| &mut self.values[idx])
+    }
+
+    /// Attach `value` to `entity`, returning the component it replaces
+    pub fn insert(&mut self, entity: Entity, value: T) -> Option<T> {
+        if let Some(idx) = self.position(entity) {
+            return Some(std::mem::replace(&mut self.values[idx], value));
+        }
+        let slot = entity.index as usize;
+        if self.sparse.len() <= slot {
+            self.sparse.resize(slot + 1, None);
+        }
+        self.sparse[slot] = Some(self.values.len());
+        self.entities.push(entity);
+        self.values.push(value);
+        None
+    }
+
+    /// Detach `entity`'s component
+    ///
+    /// Note: the last component takes the removed component's place
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let idx = self.position(entity)?;
+        self.sparse[entity.index as usize] = None;
+        self.entities.swap_remove(idx);
+        if let Some(moved) = self.entities.get(idx) {
+            self.sparse[moved.index as usize] = Some(idx);
+        }
+        Some(self.values.swap_remove(idx))
+    }
+
+    /// Every component, in order
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        &self.values
+    }
+
+    /// The entity each component in [`Self::as_slice`] belongs to
+    #[inline]
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entities.iter().copied().zip(&self.values)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.entities.iter().copied().zip(&mut self.values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entities() {
+        let mut entities = Entities::new();
+        let mut names = Components::new();
+        let [a, b, c] = [(); 3].map(|()| entities.spawn());
+        for (entity, name) in [(a, "a"), (b, "b"), (c, "c")] {
+            assert_eq!(names.insert(entity, name), None);
+        }
+
+        assert_eq!(names.remove(a), Some("a"));
+        assert!(entities.despawn(a));
+        assert!(!entities.despawn(a), "already despawned");
+        assert_eq!(
+            names.as_slice(),
+            ["c", "b"],
+            "the last should take its place"
+        );
+        assert_eq!(names.get(c), Some(&"c"));
+
+        let d = entities.spawn();
+        assert_ne!(d, a, "reused slots should be a new generation");
+        assert!(!entities.is_alive(a));
+        assert_eq!(names.get(a), None);
+        assert_eq!(names.insert(d, "d"), None);
+        assert_eq!(names.get(a), None, "old ids shouldn't find the new entity");
+        assert_eq!(entities.len(), 3);
+        assert_eq!(names.entities(), [c, b, d]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Things that happen in the game, for whatever needs to react to them
+//!
+//! The simulation [publishes](EventBus::publish) events as it ticks, and each subscriber keeps a
+//! [`Reader`] of its own and [reads](EventBus::read) what's new whenever it's ready to, so every
+//! subscriber sees every event once, in order. Each type of event has its own [`Channel`], picked
+//! by the type through [`Event`], so subscribers only see the events they asked for.
+//!
+//! Channels only keep the last [`Channel::CAPACITY`] events. Readers that fall further behind
+//! than that skip the ones they missed.
+
+use crate::{
+    container::Item, math::coords::FactoryVector3, milestone::MilestoneId,
+    region::events::RegionEvent,
+};
+use std::{collections::VecDeque, marker::PhantomData};
+
+/// Something that happened in the game, outside of any one region
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    /// A controller in the factory at index `factory` printed `text`, or stopped with it as the
+    /// reason
+    ScriptOutput {
+        factory: usize,
+        text: String,
+    },
+    MilestoneReached(MilestoneId),
+    /// The spectrometer scanned an item for the first time
+    Discovered(Item),
+    /// A vessel in the factory at index `factory` burst at `position`
+    VesselRuptured {
+        factory: usize,
+        position: FactoryVector3,
+    },
+}
+
+/// A type of event with a [`Channel`] in the [`EventBus`]
+pub trait Event: Sized + 'static {
+    fn channel(bus: &EventBus) -> &Channel<Self>;
+
+    fn channel_mut(bus: &mut EventBus) -> &mut Channel<Self>;
+}
+
+impl Event for GameEvent {
+    #[inline]
+    fn channel(bus: &EventBus) -> &Channel<Self> {
+        &bus.game
+    }
+
+    #[inline]
+    fn channel_mut(bus: &mut EventBus) -> &mut Channel<Self> {
+        &mut bus.game
+    }
+}
+
+impl Event for RegionEvent {
+    #[inline]
+    fn channel(bus: &EventBus) -> &Channel<Self> {
+        &bus.region
+    }
+
+    #[inline]
+    fn channel_mut(bus: &mut EventBus) -> &mut Channel<Self> {
+        &mut bus.region
+    }
+}
+
+/// The most recent events of one type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Channel<T> {
+    events: VecDeque<T>,
+    /// How many events were published before the first one still kept
+    dropped: u64,
+}
+
+impl<T> Default for Channel<T> {
+    fn default() -> Self {
+        Self {
+            events: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+}
+
+impl<T> Channel<T> {
+    /// Events kept for readers that haven't caught up yet
+    pub const CAPACITY: usize = 256;
+
+    fn push(&mut self, event: T) {
+        if self.events.len() == Self::CAPACITY {
+            self.events.pop_front();
+            self.dropped += 1;
+        }
+        self.events.push_back(event);
+    }
+
+    /// How many events have ever been published
+    fn published(&self) -> u64 {
+        self.dropped + self.events.len() as u64
+    }
+}
+
+/// How far one subscriber has read through the [`Channel`] of events of type `T`
+#[derive(Debug)]
+pub struct Reader<T> {
+    /// How many events had been published when it last read
+    read: u64,
+    _event: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for Reader<T> {
+    fn default() -> Self {
+        Self {
+            read: 0,
+            _event: PhantomData,
+        }
+    }
+}
+
+/// Every [`Channel`], one for each type of [`Event`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EventBus {
+    game: Channel<GameEvent>,
+    region: Channel<RegionEvent>,
+}
+
+impl EventBus {
+    /// Tell every subscriber to `E` about `event`
+    pub fn publish<E: Event>(&mut self, event: E) {
+        E::channel_mut(self).push(event);
+    }
+
+    /// The events of type `E` published since `reader` last read, oldest first
+    pub fn read<'a, E: Event>(
+        &'a self,
+        reader: &mut Reader<E>,
+    ) -> impl Iterator<Item = &'a E> + use<'a, E> {
+        let channel = E::channel(self);
+        let skip = reader.read.saturating_sub(channel.dropped);
+        reader.read = channel.published();
+        channel
+            .events
+            .iter()
+            .skip(usize::try_from(skip).unwrap_or(usize::MAX))
+    }
+
+    /// A reader that only sees what's published from now on
+    pub fn subscribe<E: Event>(&self) -> Reader<E> {
+        Reader {
+            read: E::channel(self).published(),
+            _event: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_bus() {
+        let mut bus = EventBus::default();
+        let mut early = Reader::<GameEvent>::default();
+        bus.publish(GameEvent::MilestoneReached(MilestoneId::FirstOre));
+        let mut late = bus.subscribe::<GameEvent>();
+        let mut regions = bus.subscribe::<RegionEvent>();
+        bus.publish(GameEvent::MilestoneReached(MilestoneId::Combustion));
+
+        assert_eq!(
+            bus.read(&mut early).cloned().collect::<Vec<_>>(),
+            [
+                GameEvent::MilestoneReached(MilestoneId::FirstOre),
+                GameEvent::MilestoneReached(MilestoneId::Combustion),
+            ]
+        );
+        assert_eq!(bus.read(&mut early).count(), 0, "already read");
+        assert_eq!(
+            bus.read(&mut late).cloned().collect::<Vec<_>>(),
+            [GameEvent::MilestoneReached(MilestoneId::Combustion)]
+        );
+        assert_eq!(bus.read(&mut regions).count(), 0, "a different channel");
+
+        for _ in 0..Channel::<GameEvent>::CAPACITY + 10 {
+            bus.publish(GameEvent::MilestoneReached(MilestoneId::RailNetwork));
+        }
+        assert_eq!(
+            bus.read(&mut early).count(),
+            Channel::<GameEvent>::CAPACITY,
+            "the oldest ones are dropped"
+        );
+    }
+}
@@ -0,0 +1,705 @@
+use crate::{
+    compendium::Compendium,
+    container::ItemSlots,
+    contract::Contracts,
+    event_bus::{EventBus, GameEvent},
+    health::{Damage, DamageSource, Health},
+    math::{
+        bounds::{FactoryBounds, LabBounds, PlayerBounds, SpacialBounds},
+        coords::{
+            FactoryVector3, LabVector3, PlayerCoord, PlayerVector3, RailVector3, VectorConstants,
+        },
+    },
+    milestone::{Milestones, Progress},
+    ordinals::{Cardinal2D, Cardinal3D},
+    player::{Attachment, MovementState, Player},
+    region::{
+        RegionId,
+        events::RegionEvent,
+        factory::{
+            ExpandError, Factory, FactoryCollision, Machine, MachineId, Miner, MinerError, Reactor,
+            StationError,
+            blueprint::Blueprint,
+            history::{self, Change, History, UndoError},
+            station::{CargoRule, Station},
+        },
+        lab::{
+            Bed, ContractBoard, Laboratory, MeasuringBench, PeriodTableVariable, PeriodicTable,
+            Spectrometer, instrument::Instrument,
+        },
+        rail::{
+            World,
+            deposit::Deposits,
+            handcar::Handcar,
+            network::{RailNetwork, Track, Train},
+            terrain::{Pad, Terrain},
+        },
+    },
+    research::Research,
+    rng::{Rng, Stream},
+    time::WorldClock,
+    tutorial::Tutorial,
+    weather::Weather,
+};
+use raylib::prelude::{Color, Vector3};
+
+/// What pressing interact does, see [`GameState::interaction`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interaction {
+    /// Step off the handcar the player is riding, if there's room
+    Dismount,
+    /// Ride the handcar at the index
+    Board(usize),
+    /// Open the periodic table panel
+    PeriodicTable,
+    /// Open the contract board
+    ContractBoard,
+    /// Take a measurement with a bench's instrument
+    Bench(Instrument),
+    /// Open the panel of a machine, in the factory at the index
+    Machine(usize, MachineId),
+}
+
+/// The world and everything in it, independent of input and rendering
+///
+/// Subsystems like the console mutate the game through this rather than through `main`'s locals.
+pub struct GameState {
+    pub player: Player,
+    pub factories: Vec<Factory>,
+    pub lab: Laboratory,
+    pub world: World,
+    pub current_region: RegionId,
+    /// The time of day, which moves the sun
+    pub clock: WorldClock,
+    /// Seconds the world has been running for, not counting while paused
+    pub playtime: f64,
+    /// Rain, fog, or storms outside, following the clock
+    pub weather: Weather,
+    /// The last blueprint copied, ready to paste
+    pub clipboard: Option<Blueprint>,
+    /// What's been built and demolished in factories, to undo
+    pub history: History,
+    /// Research points and unlocked techs
+    pub research: Research,
+    /// Items the spectrometer has scanned
+    pub compendium: Compendium,
+    /// Milestones reached, and the ones being announced
+    pub milestones: Milestones,
+    /// Deliveries on offer and being worked on
+    pub contracts: Contracts,
+    /// Earned by fulfilling contracts
+    pub credits: u64,
+    /// The objective the player is being walked through
+    pub tutorial: Tutorial,
+    /// What killed the player last, and how many seconds ago
+    pub last_death: Option<(DamageSource, f32)>,
+    /// Reported since the last tick, see [`Self::hurt`]
+    damage: Vec<Damage>,
+    /// What's happened, for subsystems that react to it
+    pub events: EventBus,
+}
+
+impl GameState {
+    /// What all of the world's randomness comes from, see [`Rng::stream`]
+    ///
+    /// Every game is the same until worlds can be made with a seed of their own.
+    const SEED: u64 = 0x7e44_a1d5;
+    /// Health lost right next to a vessel that ruptures
+    const RUPTURE_DAMAGE: f32 = 80.0;
+    /// Meters from a rupture the player is safe past
+    const RUPTURE_RADIUS: f32 = 12.0;
+
+    /// A new game with `player` in it
+    pub fn new(player: Player) -> Self {
+        let factories: Vec<Factory> = [
+            RailVector3 { x: 0, y: 0, z: 0 },
+            RailVector3 {
+                x: 300,
+                y: 0,
+                z: 50,
+            },
+        ]
+        .map(|origin| {
+            let mut factory = Factory::new(
+                origin,
+                FactoryBounds {
+                    min: FactoryVector3::new(-30, 0, -30),
+                    max: FactoryVector3::new(30, 30, 30),
+                },
+            );
+            factory.add_reactor(Reactor {
+                position: FactoryVector3 { x: 5, y: 0, z: -6 },
+                rotation: Cardinal2D::default(),
+            });
+            factory.add_reactor(Reactor {
+                position: FactoryVector3 { x: -3, y: 0, z: -9 },
+                rotation: Cardinal2D::default(),
+            });
+            factory
+        })
+        .into();
+
+        let lab = Self::starting_lab();
+
+        // Past the first factory's south wall and along the second's west wall
+        let track = Track {
+            points: vec![
+                RailVector3::new(-60, 0, 30),
+                RailVector3::new(269, 0, 30),
+                RailVector3::new(269, 0, 100),
+            ],
+        };
+
+        // Flatten the ground under every building and along the rails
+        let rail_pads = (track.points.windows(2)).map(|run| {
+            let (a, b) = (run[0], run[1]);
+            Pad {
+                min: RailVector3::new(a.x.min(b.x) - 2, a.y.min(b.y), a.z.min(b.z) - 2),
+                max: RailVector3::new(a.x.max(b.x) + 3, a.y.max(b.y), a.z.max(b.z) + 3),
+            }
+        });
+        let pads = (factories.iter())
+            .map(|factory| Pad {
+                min: factory.bounds.min.to_rail(factory.origin),
+                max: factory.bounds.max.to_rail(factory.origin),
+            })
+            .chain([Pad {
+                min: lab.bounds.min.to_player(&lab.origin).to_rail(),
+                max: lab.bounds.max.to_player(&lab.origin).to_rail(),
+            }])
+            .chain(rail_pads)
+            .collect();
+
+        let rail = Self::starting_rail(track);
+
+        Self {
+            player,
+            factories,
+            lab,
+            world: World {
+                sky_tint: Color::WHITE,
+                terrain: Terrain::new(Rng::stream(Self::SEED, Stream::Terrain).next_u32(), pads),
+                deposits: Deposits::default(),
+                rail,
+            },
+            current_region: RegionId::Rail,
+            clock: WorldClock::default(),
+            playtime: 0.0,
+            weather: Weather::default(),
+            clipboard: None,
+            history: History::new(),
+            research: Research::default(),
+            compendium: Compendium::default(),
+            milestones: Milestones::default(),
+            contracts: Contracts::new(Rng::stream(Self::SEED, Stream::Contracts)),
+            credits: 0,
+            tutorial: Tutorial::default(),
+            last_death: None,
+            damage: Vec::new(),
+            events: EventBus::default(),
+        }
+    }
+
+    /// The lab the player starts with, and wakes up in
+    fn starting_lab() -> Laboratory {
+        Laboratory {
+            origin: PlayerVector3::from_i32(5, 0, -30),
+            bounds: LabBounds {
+                min: LabVector3::from_i16(-10, 0, -10),
+                max: LabVector3::from_i16(10, 10, 10),
+            },
+            periodic_tables: vec![PeriodicTable {
+                position: LabVector3::from_i16(0, 0, 0),
+                variable: PeriodTableVariable::Protons,
+            }],
+            bed: Bed {
+                position: LabVector3::from_i16(-8, 0, -8),
+            },
+            contract_board: ContractBoard {
+                position: LabVector3::from_i16(-4, 0, 9),
+            },
+            // Along the east wall
+            benches: (Instrument::ALL.into_iter().zip(-3..))
+                .map(|(instrument, z)| MeasuringBench {
+                    position: LabVector3::from_i16(8, 0, 2 * z),
+                    instrument,
+                })
+                .collect(),
+            spectrometer: Spectrometer::new(LabVector3::from_i16(-8, 0, 4)),
+            challenges: Rng::stream(Self::SEED, Stream::Measurements),
+            samples: ItemSlots::new(Laboratory::SAMPLE_SLOTS, u32::MAX),
+            analysis_timer: 0.0,
+        }
+    }
+
+    /// `track` with signals along it, and the trains and handcar that start on it
+    fn starting_rail(track: Track) -> RailNetwork {
+        let mut rail = RailNetwork::new(vec![track]);
+        // Signals split the track into blocks, so the trains wait for each other to pass
+        for cell in [
+            RailVector3::new(60, 0, 30),
+            RailVector3::new(160, 0, 30),
+            RailVector3::new(269, 0, 60),
+        ] {
+            _ = rail.signal_at(cell);
+        }
+        rail.add_train(Train::new(0, 8.0));
+        let mut train = Train::new(0, 6.0);
+        train.distance = rail.tracks()[0].length();
+        train.forward = false;
+        rail.add_train(train);
+        // Down the track from the first factory's corner
+        rail.add_handcar(Handcar::new(0, PlayerCoord::from_i32(20)));
+        rail
+    }
+
+    /// Advance everything that happens on its own by `dt` seconds
+    ///
+    /// Doesn't touch input or rendering, so it runs the same with or without a window.
+    pub fn tick(&mut self, dt: f32) {
+        self.playtime += f64::from(dt);
+        self.clock.tick(dt);
+        self.weather.tick(dt, &self.clock);
+        let daylight = self.clock.daylight() * self.weather.sunlight();
+        self.world.rail.set_slip(self.weather.rail_slip());
+        self.world.rail.tick(dt);
+        self.carry_rider();
+        for (idx, factory) in self.factories.iter_mut().enumerate() {
+            factory.lod.viewer = Some(self.player.eye_pos());
+            factory.tick(dt, daylight, &mut self.world.deposits, &mut self.world.rail);
+            for text in factory.take_script_log() {
+                (self.events).publish(GameEvent::ScriptOutput { factory: idx, text });
+            }
+        }
+        for idx in 0..self.factories.len() {
+            for position in self.factories[idx].take_ruptures() {
+                self.rupture(idx, position);
+            }
+        }
+        self.breathe(dt);
+        self.burn(dt);
+        self.apply_damage(dt);
+        self.world.sky_tint = self.weather.sky(&self.clock).tint;
+        let points = self.lab.analyze(dt);
+        self.research.points = self.research.points.saturating_add(points);
+        for item in self.lab.spectrometer.scan(dt) {
+            if self.compendium.discover(&item) {
+                self.events.publish(GameEvent::Discovered(item));
+            }
+        }
+        let reached = self.milestones.check(&Progress {
+            factories: &self.factories,
+            research: &self.research,
+            traveled: self.world.rail.traveled(),
+        });
+        for id in reached {
+            self.events.publish(GameEvent::MilestoneReached(id));
+        }
+        self.milestones.tick(dt);
+        let stops = self.world.rail.stop_ids().collect::<Vec<_>>();
+        let reward = self.contracts.tick(dt, &self.factories, &stops);
+        self.research.points = (self.research.points).saturating_add(reward.research_points);
+        self.credits = self.credits.saturating_add(reward.credits.into());
+        let mut tutorial = std::mem::take(&mut self.tutorial);
+        tutorial.update(self);
+        self.tutorial = tutorial;
+    }
+
+    /// Move the player along with the handcar they're riding, if they are
+    fn carry_rider(&mut self) {
+        let Some(attachment) = self.player.attachment else {
+            return;
+        };
+        match self.world.rail.handcar_position(attachment.handcar) {
+            Some((deck, heading)) => self.player.follow(deck, heading),
+            None => self.player.attachment = None,
+        }
+    }
+
+    /// The index of the closest handcar within [`Player::REACH`] of the player's eyes, if
+    /// they're outside and not already riding one
+    pub fn handcar_in_reach(&self) -> Option<usize> {
+        if self.current_region != RegionId::Rail || self.player.attachment.is_some() {
+            return None;
+        }
+        let eye = self.player.eye_pos();
+        (0..self.world.rail.handcars().len())
+            .filter_map(|idx| {
+                let (deck, _) = self.world.rail.handcar_position(idx)?;
+                Some((idx, deck.distance(eye).to_f32()))
+            })
+            .filter(|&(_, distance)| distance <= Player::REACH)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(idx, _)| idx)
+    }
+
+    /// Start riding the handcar at `idx`
+    pub fn board(&mut self, idx: usize) {
+        let Some((deck, heading)) = self.world.rail.handcar_position(idx) else {
+            return;
+        };
+        self.player.attachment = Some(Attachment {
+            handcar: idx,
+            heading,
+        });
+        self.player.movement_state = MovementState::default();
+        self.player.follow(deck, heading);
+    }
+
+    /// Drive the handcar the player is riding, with `throttle` from -1 to 1 towards where
+    /// they're looking
+    pub fn drive(&mut self, throttle: f32, is_braking: bool) {
+        let Some(attachment) = self.player.attachment else {
+            return;
+        };
+        // Looking back along the track, pushing ahead goes towards its start
+        let is_facing_back = (self.player.yaw - attachment.heading).cos() < 0.0;
+        let throttle = if is_facing_back { -throttle } else { throttle };
+        if let Some(handcar) = self.world.rail.handcar_mut(attachment.handcar) {
+            handcar.drive(throttle, is_braking);
+        }
+    }
+
+    /// Where the player would step off the handcar they're riding, if there's room beside it or
+    /// at either end
+    ///
+    /// Tracks run right past factory walls, so the sides are tried first and anywhere inside a
+    /// building is skipped.
+    pub fn dismount_point(&self) -> Option<PlayerVector3> {
+        const STEP: f32 = 1.5;
+        let attachment = self.player.attachment?;
+        let (deck, heading) = self.world.rail.handcar_position(attachment.handcar)?;
+        let (sin, cos) = heading.sin_cos();
+        let ahead = Vector3::new(-sin, 0.0, -cos) * STEP;
+        let right = Vector3::new(cos, 0.0, -sin) * STEP;
+        let buildings = (self.factories.iter())
+            .map(|factory| PlayerBounds {
+                min: factory.bounds.min.to_player(&factory.origin),
+                max: factory.bounds.max.to_player(&factory.origin),
+            })
+            .chain([PlayerBounds {
+                min: self.lab.bounds.min.to_player(&self.lab.origin),
+                max: self.lab.bounds.max.to_player(&self.lab.origin),
+            }])
+            .collect::<Vec<_>>();
+        [right, -right, -ahead, ahead]
+            .into_iter()
+            .find_map(|offset| {
+                let Vector3 { x, z, .. } = deck.to_vec3() + offset;
+                let spot = PlayerVector3::from_f32(x, self.world.terrain.height_at(x, z), z);
+                let bounds = Player::bounds_at(spot);
+                (!buildings.iter().any(|building| {
+                    building
+                        .intersection(&bounds)
+                        .is_some_and(|shared| shared.volume().is_positive())
+                }))
+                .then_some(spot)
+            })
+    }
+
+    /// Step off the handcar the player is riding, leaving it to coast to a stop
+    ///
+    /// Returns `false` if there's nowhere to step off, see [`Self::dismount_point`].
+    pub fn dismount(&mut self) -> bool {
+        let Some(spot) = self.dismount_point() else {
+            return false;
+        };
+        self.detach();
+        self.player.position = spot;
+        true
+    }
+
+    /// Stop riding, letting go of the controls
+    fn detach(&mut self) {
+        if let Some(attachment) = self.player.attachment.take()
+            && let Some(handcar) = self.world.rail.handcar_mut(attachment.handcar)
+        {
+            handcar.release();
+        }
+    }
+
+    /// Hurt the player for `dt` seconds of breathing whatever gas is in the factory they're in
+    fn breathe(&mut self, dt: f32) {
+        let RegionId::Factory(idx) = self.current_region else {
+            return;
+        };
+        let factory = &self.factories[idx];
+        let Ok(head) = self.player.eye_pos().to_factory(&factory.origin) else {
+            return;
+        };
+        if let Some(damage) = self.player.breathe(factory.gas().damage(head), dt) {
+            self.hurt(damage);
+        }
+    }
+
+    /// Hurt the player for `dt` seconds of touching hot machines in the factory they're in
+    fn burn(&mut self, dt: f32) {
+        let RegionId::Factory(idx) = self.current_region else {
+            return;
+        };
+        let burn = self.factories[idx].burn_damage(&self.player.bounds());
+        if burn > 0.0 {
+            self.hurt(Damage::new(DamageSource::Machinery, burn * dt));
+        }
+    }
+
+    /// Hurt the player if they're near where a vessel burst at `position` in the factory at
+    /// `idx`, more the closer they are
+    fn rupture(&mut self, idx: usize, position: FactoryVector3) {
+        self.events.publish(GameEvent::VesselRuptured {
+            factory: idx,
+            position,
+        });
+        if self.current_region != RegionId::Factory(idx) {
+            return;
+        }
+        let center = position.to_player(&self.factories[idx].origin);
+        let distance = self.player.eye_pos().minus(center).to_vec3().length();
+        let damage = Self::RUPTURE_DAMAGE * (1.0 - distance / Self::RUPTURE_RADIUS);
+        if damage > 0.0 {
+            self.hurt(Damage::new(DamageSource::Rupture, damage));
+        }
+    }
+
+    /// Report that something hurt the player, to be taken off their health next tick
+    pub fn hurt(&mut self, damage: Damage) {
+        self.damage.push(damage);
+    }
+
+    /// Take everything reported since the last tick off the player's health, and let them heal
+    /// for `dt` seconds if they've gone long enough without being hurt
+    ///
+    /// If it kills them, they respawn, see [`Self::respawn`].
+    fn apply_damage(&mut self, dt: f32) {
+        if let Some((_, since)) = &mut self.last_death {
+            *since += dt;
+        }
+        let mut killed_by = None;
+        for damage in self.damage.drain(..) {
+            if self.player.health.apply(damage) {
+                killed_by.get_or_insert(damage.source);
+            }
+        }
+        self.player.health.regenerate(dt);
+        if let Some(source) = killed_by {
+            self.respawn(source);
+        }
+    }
+
+    /// Bring the player back at full health beside the bed in the lab, after `source` killed
+    /// them
+    pub fn respawn(&mut self, source: DamageSource) {
+        self.detach();
+        self.player.position = self.lab.bed.spawn_point(&self.lab.origin);
+        self.player.velocity = PlayerVector3::ZERO;
+        self.player.movement_state = MovementState::default();
+        self.player.health = Health::new();
+        self.last_death = Some((source, 0.0));
+    }
+
+    /// The factory the player is in, if any
+    pub fn current_factory_mut(&mut self) -> Option<&mut Factory> {
+        match self.current_region {
+            RegionId::Factory(idx) => self.factories.get_mut(idx),
+            RegionId::Rail | RegionId::Lab => None,
+        }
+    }
+
+    /// What pressing interact would do right now, if anything
+    ///
+    /// Riding a handcar comes first, then boarding one, then whatever the player is looking at.
+    pub fn interaction(&self) -> Option<Interaction> {
+        if self.player.attachment.is_some() {
+            return Some(Interaction::Dismount);
+        }
+        if let Some(handcar) = self.handcar_in_reach() {
+            return Some(Interaction::Board(handcar));
+        }
+        if self.current_region == RegionId::Lab {
+            let player = &self.player;
+            if self.lab.looked_at_periodic_table(player).is_some() {
+                return Some(Interaction::PeriodicTable);
+            }
+            if self.lab.looked_at_contract_board(player).is_some() {
+                return Some(Interaction::ContractBoard);
+            }
+            if let Some(bench) = self.lab.looked_at_bench(player) {
+                return Some(Interaction::Bench(bench.instrument));
+            }
+        }
+        let (factory, machine) = self.looked_at_machine_id()?;
+        Some(Interaction::Machine(factory, machine))
+    }
+
+    /// The machine the player is looking at, if one is within [`Player::REACH`]
+    pub fn looked_at_machine(&self) -> Option<&dyn Machine> {
+        self.looked_at_in_factory()?.1.target
+    }
+
+    /// The index of the factory the player is in and the machine they're looking at in it, if
+    /// it's within [`Player::REACH`]
+    pub fn looked_at_machine_id(&self) -> Option<(usize, MachineId)> {
+        let (idx, collision) = self.looked_at_in_factory()?;
+        Some((idx, collision.id?))
+    }
+
+    /// What the player is looking at in the factory they're in, if it's within [`Player::REACH`]
+    fn looked_at_in_factory(&self) -> Option<(usize, FactoryCollision<'_>)> {
+        let RegionId::Factory(idx) = self.current_region else {
+            return None;
+        };
+        let factory = self.factories.get(idx)?;
+        let collision = factory
+            .get_ray_collision(factory.vision_ray(&self.player))
+            .filter(|collision| collision.distance <= Player::REACH)?;
+        Some((idx, collision))
+    }
+
+    /// Grow the factory at `idx` by `blocks` meters out of its `side`, unless another region is
+    /// in the way
+    ///
+    /// Returns how many square meters of floor space were added, see [`Factory::expand`].
+    pub fn expand_factory(
+        &mut self,
+        idx: usize,
+        side: Cardinal3D,
+        blocks: u16,
+    ) -> Result<u32, ExpandError> {
+        let in_world = |factory: &Factory, bounds: &FactoryBounds| PlayerBounds {
+            min: bounds.min.to_player(&factory.origin),
+            max: bounds.max.to_player(&factory.origin),
+        };
+        let factory = &self.factories[idx];
+        let expanded = in_world(factory, &factory.expanded_bounds(side, blocks)?);
+        // Touching is fine
+        let overlaps = |other: &PlayerBounds| {
+            expanded
+                .intersection(other)
+                .is_some_and(|shared| shared.volume().is_positive())
+        };
+        let lab = PlayerBounds {
+            min: self.lab.bounds.min.to_player(&self.lab.origin),
+            max: self.lab.bounds.max.to_player(&self.lab.origin),
+        };
+        let blocked = overlaps(&lab)
+            || (self.factories.iter().enumerate()).any(|(other_idx, other)| {
+                other_idx != idx && overlaps(&in_world(other, &other.bounds))
+            });
+        if blocked {
+            return Err(ExpandError::Overlaps);
+        }
+        self.factories[idx].expand(side, blocks)
+    }
+
+    /// Build a miner at `position` facing `rotation` in the factory at `idx`, mining whichever
+    /// deposit is under it
+    pub fn add_miner(
+        &mut self,
+        idx: usize,
+        position: FactoryVector3,
+        rotation: Cardinal2D,
+    ) -> Result<MachineId, MinerError> {
+        let factory = &self.factories[idx];
+        let footprint = Miner::footprint(position, rotation);
+        if footprint.min.y != factory.bounds.min.y {
+            return Err(MinerError::NotOnGround);
+        }
+        if !factory.is_vacant(&footprint) {
+            return Err(MinerError::Occupied);
+        }
+        let min = footprint.min.to_rail(factory.origin);
+        let max = footprint.max.to_rail(factory.origin);
+        let site = (self.world.terrain.deposit_sites_near(min, 1).into_iter())
+            .find(|site| {
+                (min.x..max.x).contains(&site.position.x)
+                    && (min.z..max.z).contains(&site.position.z)
+            })
+            .ok_or(MinerError::NoDeposit)?;
+        let deposit = self.world.deposits.find(site).position;
+        Ok(self.factories[idx].add_miner(Miner::new(position, rotation, deposit)))
+    }
+
+    /// Build a station at `position` facing `rotation` in the factory at `idx`, moving cargo as
+    /// `rules` say
+    ///
+    /// It has to be against a wall, facing out onto rails.
+    pub fn add_station(
+        &mut self,
+        idx: usize,
+        position: FactoryVector3,
+        rotation: Cardinal2D,
+        rules: Vec<CargoRule>,
+    ) -> Result<MachineId, StationError> {
+        let factory = &self.factories[idx];
+        let FactoryBounds { min, max } = factory.bounds;
+        let inside = |cell: FactoryVector3| {
+            (min.x..max.x).contains(&cell.x) && (min.z..max.z).contains(&cell.z)
+        };
+        let footprint = Station::footprint(position, rotation);
+        let front = Station::front(position, rotation);
+        if !footprint.cells().all(inside) || front.into_iter().any(inside) {
+            return Err(StationError::NotAtWall);
+        }
+        if !factory.is_vacant(&footprint) {
+            return Err(StationError::Occupied);
+        }
+        let origin = factory.origin;
+        let stop = (front.into_iter())
+            .find_map(|cell| self.world.rail.stop_at(cell.to_rail(origin)))
+            .ok_or(StationError::NoRails)?;
+        let station = Station::new(position, rotation, stop, rules);
+        Ok(self.factories[idx].add_station(station))
+    }
+
+    /// Demolish the machine identified by `id` in the factory at `idx`, giving the player
+    /// everything it and its belts were holding
+    ///
+    /// Returns how many items didn't fit in the inventory and were lost, or [`None`] if there's
+    /// no such machine. It can be [undone](Self::undo).
+    pub fn demolish(&mut self, idx: usize, id: MachineId) -> Option<u32> {
+        let rows = self.factories.get_mut(idx)?.take_machine(id)?;
+        let (refunded, lost) = history::refund(&rows, &mut self.player.inventory);
+        self.history.record(
+            idx,
+            Change::Demolished {
+                rows,
+                ladders: Vec::new(),
+                refunded,
+            },
+        );
+        Some(lost)
+    }
+
+    /// Take back the last thing built or demolished in a factory, returning `false` if there's
+    /// nothing to undo
+    pub fn undo(&mut self) -> Result<bool, UndoError> {
+        (self.history).undo(&mut self.factories, &mut self.player.inventory)
+    }
+
+    /// Build or demolish again whatever was last undone, returning `false` if there's nothing
+    /// to redo
+    pub fn redo(&mut self) -> Result<bool, UndoError> {
+        (self.history).redo(&mut self.factories, &mut self.player.inventory)
+    }
+
+    /// Recompute [`Self::current_region`] from the player's position
+    ///
+    /// If the region has changed, publishes exiting the old region and then entering the new one
+    /// as [`RegionEvent`]s.
+    pub fn update_region(&mut self) {
+        let from = self.current_region;
+        if !self.current_region.update(
+            &self.player.eye_pos(),
+            &self.factories,
+            &self.lab,
+            &self.world,
+        ) {
+            return;
+        }
+        let to = self.current_region;
+        self.events
+            .publish(RegionEvent::Exited { region: from, to });
+        self.events
+            .publish(RegionEvent::Entered { region: to, from });
+    }
+}
@@ -0,0 +1,182 @@
+//! Running the game without a window, for dedicated servers and benchmarks
+
+use crate::{
+    game::GameState,
+    math::{
+        bounds::FactoryBounds,
+        coords::{FactoryVector3, PlayerVector3, RailVector3, VectorConstants},
+    },
+    net::Server,
+    ordinals::Cardinal2D,
+    player::Player,
+    region::factory::{
+        Belt, BeltLevel, Factory, Machine, Reactor,
+        routing::{self, RouteRules},
+    },
+    time::TICK_RATE,
+};
+use std::{
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+
+/// Blocks between the corners of neighboring reactors in [`bench`], leaving room for belts
+const BENCH_SPACING: FactoryVector3 = FactoryVector3::new(4, 0, 6);
+
+/// Stands in for the player, since no one is playing
+fn observer() -> Player {
+    Player::new(PlayerVector3::ZERO, 0.0, 0.0, 45.0, 1.0)
+}
+
+/// Run the game without a window until the process is stopped, hosting a server on `port` if
+/// there is one
+pub fn run(port: Option<u16>) {
+    let mut state = GameState::new(observer());
+    let mut server = match port.map(|port| Server::dedicated(port, &state)) {
+        Some(Ok(server)) => Some(server),
+        Some(Err(e)) => {
+            eprintln!("failed to host: {e}");
+            return;
+        }
+        None => None,
+    };
+
+    let tick = Duration::from_secs(1) / TICK_RATE;
+    let mut next_tick = Instant::now();
+    loop {
+        if let Some(server) = &mut server {
+            server.update(&mut state);
+        }
+        state.tick(tick.as_secs_f32());
+        next_tick += tick;
+        match next_tick.checked_duration_since(Instant::now()) {
+            Some(wait) => std::thread::sleep(wait),
+            // Running behind, so don't try to catch up all at once
+            None => next_tick = Instant::now(),
+        }
+    }
+}
+
+/// A factory with `reactors` reactors in a grid and up to `belts` belts from each reactor to
+/// the next, returning the factory and how many belts fit
+fn bench_factory(reactors: usize, belts: usize) -> Option<(Factory, usize)> {
+    let columns = reactors.isqrt().max(1);
+    let rows = reactors.div_ceil(columns);
+    let grid = FactoryVector3::new(i16::try_from(columns).ok()?, 1, i16::try_from(rows).ok()?);
+    let mut factory = Factory::new(
+        RailVector3::default(),
+        FactoryBounds {
+            min: FactoryVector3::new(-2, 0, -2),
+            max: (grid * BENCH_SPACING).plus(FactoryVector3::new(2, 8, 2)),
+        },
+    );
+    for (row, column) in (0..grid.z).flat_map(|row| (0..grid.x).map(move |column| (row, column))) {
+        if factory.reactors().len() == reactors {
+            break;
+        }
+        factory.add_reactor(Reactor {
+            position: FactoryVector3::new(column, 0, row) * BENCH_SPACING,
+            rotation: Cardinal2D::East,
+        });
+    }
+
+    let mut placed = 0;
+    for i in 0..belts {
+        let reactors = factory.reactors();
+        let src = reactors[i % reactors.len()].belt_outputs()[0];
+        let dst = reactors[(i + 1) % reactors.len()].belt_inputs()[0];
+        if let Some(path) =
+            routing::find_route(&factory, src.0.position, dst.0.position, RouteRules::BELT)
+        {
+            factory.add_belt(Belt {
+                level: BeltLevel::Mk1,
+                src,
+                dst,
+                path,
+            });
+            placed += 1;
+        }
+    }
+    Some((factory, placed))
+}
+
+/// Tick `state` as fast as possible for `duration`, returning how many ticks that was and how many
+/// it did each second
+fn tick_rate(state: &mut GameState, duration: Duration) -> (u64, f64) {
+    let dt = (Duration::from_secs(1) / TICK_RATE).as_secs_f32();
+    let start = Instant::now();
+    let mut ticks = 0u64;
+    while start.elapsed() < duration {
+        state.tick(dt);
+        ticks += 1;
+    }
+    #[allow(clippy::cast_precision_loss, reason = "only for display")]
+    let rate = ticks as f64 / start.elapsed().as_secs_f64();
+    (ticks, rate)
+}
+
+/// Build `reactors` reactors and `belts` belts between them, then tick them as fast as possible
+/// for `duration` and print how fast that was
+///
+/// It's run once on one thread and again on twice as many each time up to as many as there are,
+/// to show how ticking machines on more threads scales.
+pub fn bench(reactors: usize, belts: usize, duration: Duration) {
+    if reactors == 0 {
+        eprintln!("need at least one reactor");
+        return;
+    }
+    let start = Instant::now();
+    let Some((factory, placed)) = bench_factory(reactors, belts) else {
+        eprintln!("too many reactors to fit in a factory");
+        return;
+    };
+    println!(
+        "built {reactors} reactors and {placed} of {belts} belts in {:.3}s",
+        start.elapsed().as_secs_f64()
+    );
+
+    let mut state = GameState::new(observer());
+    state.factories = vec![factory];
+    let most = std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let mut threads = NonZeroUsize::MIN;
+    let mut single = None;
+    loop {
+        state.factories[0].threads = threads;
+        let (ticks, rate) = tick_rate(&mut state, duration);
+        let speedup = rate / *single.get_or_insert(rate);
+        println!(
+            "{threads} threads: ran {ticks} ticks in {:.3}s ({rate:.0} ticks/s, {speedup:.2}x)",
+            duration.as_secs_f64()
+        );
+        if threads >= most {
+            break;
+        }
+        threads = threads
+            .saturating_mul(NonZeroUsize::new(2).unwrap())
+            .min(most);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_factory() {
+        let (factory, placed) = bench_factory(5, 5).unwrap();
+        assert_eq!(factory.reactors().len(), 5);
+        assert_eq!(placed, 5, "every reactor has room for a belt to the next");
+        assert_eq!(factory.belts().len(), placed);
+
+        let mut state = GameState::new(observer());
+        state.factories = vec![factory];
+        state.factories[0].threads = NonZeroUsize::new(2).unwrap();
+        let dt = (Duration::from_secs(1) / TICK_RATE).as_secs_f32();
+        for _ in 0..10 {
+            state.tick(dt);
+        }
+        assert_eq!(state.factories[0].reactors().len(), 5);
+        assert_eq!(state.factories[0].belts().len(), placed);
+        assert!(bench_factory(usize::MAX, 0).is_none(), "too many to fit");
+    }
+}
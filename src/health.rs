@@ -0,0 +1,140 @@
+//! How hurt the player is, and what hurts them
+//!
+//! Anything that hurts the player reports it as [`Damage`] with
+//! [`GameState::hurt`](crate::game::GameState::hurt), instead of taking health away itself. The
+//! damage is all applied at once on the next tick, so dying and respawning are handled in one
+//! place no matter what did it.
+
+use crate::locale::tr;
+
+/// What hurt the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageSource {
+    /// Hitting the ground too fast
+    Fall,
+    /// Breathing gas leaked into a factory
+    Gas,
+    /// Touching machines, like ones hot enough to burn
+    Machinery,
+    /// Standing too close to a vessel that ruptured
+    Rupture,
+}
+
+impl DamageSource {
+    /// Why the player died, if it was this that killed them
+    pub fn death_message(self) -> String {
+        match self {
+            Self::Fall => tr!("health.died.fall"),
+            Self::Gas => tr!("health.died.gas"),
+            Self::Machinery => tr!("health.died.machinery"),
+            Self::Rupture => tr!("health.died.rupture"),
+        }
+    }
+}
+
+/// Health taken from the player by one thing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Damage {
+    pub source: DamageSource,
+    pub amount: f32,
+}
+
+impl Damage {
+    #[inline]
+    pub const fn new(source: DamageSource, amount: f32) -> Self {
+        Self { source, amount }
+    }
+}
+
+/// How much more the player can be hurt before dying
+///
+/// Health comes back slowly once the player hasn't been hurt for a while.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Health {
+    /// From [`Self::MAX`] down to 0
+    current: f32,
+    /// Seconds since the player was last hurt
+    since_hurt: f32,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Health {
+    pub const MAX: f32 = 100.0;
+    /// Seconds without being hurt before health starts coming back
+    pub const REGEN_DELAY: f32 = 5.0;
+    /// Health regained per second once it does
+    pub const REGEN_RATE: f32 = 2.0;
+
+    /// Full health
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            current: Self::MAX,
+            since_hurt: Self::REGEN_DELAY,
+        }
+    }
+
+    #[inline]
+    pub const fn current(self) -> f32 {
+        self.current
+    }
+
+    /// How full health is, from 0 to 1
+    #[inline]
+    pub const fn fraction(self) -> f32 {
+        self.current / Self::MAX
+    }
+
+    #[inline]
+    pub const fn is_dead(self) -> bool {
+        self.current <= 0.0
+    }
+
+    /// Take `damage`, returning whether it killed the player
+    pub const fn apply(&mut self, damage: Damage) -> bool {
+        if damage.amount > 0.0 {
+            self.current = (self.current - damage.amount).max(0.0);
+            self.since_hurt = 0.0;
+        }
+        self.is_dead()
+    }
+
+    /// Heal for `dt` seconds, if it's been long enough since the player was last hurt
+    pub const fn regenerate(&mut self, dt: f32) {
+        self.since_hurt += dt;
+        if self.since_hurt >= Self::REGEN_DELAY && !self.is_dead() {
+            self.current = (self.current + Self::REGEN_RATE * dt).min(Self::MAX);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health() {
+        let mut health = Health::new();
+        assert!(!health.apply(Damage::new(DamageSource::Fall, 30.0)));
+        assert!((health.current() - 70.0).abs() < f32::EPSILON);
+
+        health.regenerate(Health::REGEN_DELAY - 1.0);
+        assert!(
+            (health.current() - 70.0).abs() < f32::EPSILON,
+            "health only comes back after a while"
+        );
+        health.regenerate(2.0);
+        assert!((health.current() - 74.0).abs() < 1e-4);
+        health.regenerate(1000.0);
+        assert!((health.fraction() - 1.0).abs() < f32::EPSILON);
+
+        assert!(health.apply(Damage::new(DamageSource::Gas, 150.0)));
+        health.regenerate(1000.0);
+        assert!(health.is_dead(), "the dead don't heal");
+    }
+}
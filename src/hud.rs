@@ -0,0 +1,455 @@
+use crate::{
+    game::{GameState, Interaction},
+    locale::tr,
+    palette::palette,
+    player::Player,
+    region::factory::Machine,
+};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer},
+};
+use raylib::prelude::*;
+
+const FONT_SIZE: f32 = 20.0;
+const CROSSHAIR_RADIUS: f32 = 8.0;
+const SLOT_SIZE: f32 = 56.0;
+const SLOT_GAP: f32 = 4.0;
+const MARGIN: f32 = 16.0;
+const PANEL: Color = Color::new(0, 0, 0, 160);
+/// Length of the arrow pointing at a waypoint, in pixels
+const WAYPOINT_ARROW: f32 = 24.0;
+const HEALTH_BAR: Vector2 = Vector2::new(240.0, 16.0);
+/// Seconds the reason the player died is shown for after they respawn
+const DEATH_NOTICE: f32 = 5.0;
+
+/// Where a waypoint is shown on the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaypointMarker {
+    /// Where the arrow points, kept inside the screen
+    pub position: Vector2,
+    /// Which way the arrow points, down at the waypoint if it's on screen and toward it
+    /// otherwise
+    pub direction: Vector2,
+    /// Meters from the player
+    pub distance: f32,
+}
+
+impl WaypointMarker {
+    /// Show `point`, relative to the player, on a `screen` seen through `camera` with the
+    /// `aspect` ratio (width / height)
+    ///
+    /// Points behind the camera or off the sides of the screen are shown at the edge nearest
+    /// them.
+    pub fn new(camera: &Camera3D, aspect: f32, point: Vector3, screen: Vector2) -> Self {
+        let forward = (camera.target - camera.position).normalize_or(Vector3::NEG_Z);
+        let right = forward.cross(camera.up).normalize_or(Vector3::X);
+        let up = right.cross(forward);
+        let offset = point - camera.position;
+        let depth = offset.dot(forward);
+        let tan = (0.5 * camera.fovy.to_radians()).tan();
+        // Where it is on the screen from the center, with the edges at 1, if it's in front
+        let toward = Vector2::new(offset.dot(right) / (tan * aspect), -offset.dot(up) / tan);
+        let half = 0.5 * screen;
+        let distance = point.length();
+        if depth > 0.0 {
+            let ndc = toward / depth;
+            if ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0 {
+                return Self {
+                    position: half + ndc * half,
+                    direction: Vector2::Y,
+                    distance,
+                };
+            }
+        }
+        let direction = toward.try_normalize().unwrap_or(Vector2::Y);
+        let inside = half - Vector2::splat(MARGIN + WAYPOINT_ARROW);
+        let edge = toward / toward.abs().max_element().max(f32::EPSILON);
+        Self {
+            position: half + edge * inside,
+            direction,
+            distance,
+        }
+    }
+
+    /// The arrow's points, counter-clockwise from its tip
+    fn arrow(&self) -> [Vector2; 3] {
+        let base = self.position - self.direction * WAYPOINT_ARROW;
+        let side = Vector2::new(self.direction.y, -self.direction.x) * (0.4 * WAYPOINT_ARROW);
+        [self.position, base + side, base - side]
+    }
+}
+
+/// What the HUD says pressing interact will do
+fn prompt(state: &GameState, interaction: Interaction) -> String {
+    let name = match interaction {
+        Interaction::Dismount if state.dismount_point().is_some() => return tr!("hud.dismount"),
+        Interaction::Dismount => return tr!("hud.dismount_blocked"),
+        Interaction::Board(_) => return tr!("hud.board"),
+        Interaction::PeriodicTable => tr!("lab.periodic_table"),
+        Interaction::ContractBoard => tr!("lab.contract_board"),
+        Interaction::Bench(instrument) => instrument.name(),
+        Interaction::Machine(factory, machine) => (state.factories.get(factory))
+            .and_then(|factory| factory.machine_ui(machine))
+            .map(Machine::name)
+            .unwrap_or_default(),
+    };
+    tr!("hud.open", name = name)
+}
+
+/// Everything drawn over the 3D view during normal play
+///
+/// Built fresh each frame from the [`GameState`].
+#[derive(Debug, Clone)]
+pub struct Hud {
+    /// Size of the screen in pixels
+    pub screen: Vector2,
+    /// Label and count of each hotbar slot
+    pub hotbar: [Option<(String, u32)>; Player::HOTBAR_SLOTS],
+    pub selected_slot: usize,
+    /// Tells the player what interacting will do, e.g. "Press E to open Reactor"
+    pub prompt: Option<String>,
+    /// Details about the machine being looked at
+    pub tooltip: Option<String>,
+    /// How full the player's health is, from 0 to 1
+    pub health: f32,
+    /// Why the player just died, e.g. "You fell too far"
+    pub death: Option<String>,
+    /// The milestone just reached, e.g. "Milestone reached: First ore"
+    pub milestone: Option<String>,
+    /// What the tutorial asks the player to do next
+    pub objective: Option<String>,
+    /// Where the tutorial points the player to
+    pub waypoint: Option<WaypointMarker>,
+    /// How what's being placed lines up with the grid, e.g. "Snapping: 0.5 m grid"
+    pub snap: Option<String>,
+}
+
+impl Hud {
+    pub fn new(state: &GameState, screen: Vector2) -> Self {
+        let player = &state.player;
+        let machine = state.looked_at_machine();
+        let objective = state.tutorial.current();
+        Self {
+            screen,
+            hotbar: std::array::from_fn(|i| {
+                (player.inventory.stacks().get(i))
+                    .map(|stack| (stack.item.to_string(), stack.count))
+            }),
+            selected_slot: player.hotbar_slot,
+            prompt: state
+                .interaction()
+                .map(|interaction| prompt(state, interaction)),
+            tooltip: machine.map(|machine| format!("{}\n{}", machine.name(), machine.status())),
+            health: player.health.fraction(),
+            death: (state.last_death)
+                .filter(|&(_, since)| since < DEATH_NOTICE)
+                .map(|(source, _)| source.death_message()),
+            milestone: (state.milestones.announcement())
+                .map(|milestone| tr!("hud.milestone", name = milestone.title())),
+            objective: objective.map(|objective| tr!("hud.objective", text = objective.label())),
+            waypoint: (objective.and_then(|objective| objective.waypoint))
+                .and_then(|waypoint| waypoint.position(state))
+                .map(|position| {
+                    WaypointMarker::new(
+                        &player.camera,
+                        player.aspect_ratio,
+                        position.minus(player.position).to_vec3(),
+                        screen,
+                    )
+                }),
+            snap: None,
+        }
+    }
+
+    fn center(&self) -> Vector2 {
+        self.screen * 0.5
+    }
+
+    #[allow(clippy::cast_precision_loss, reason = "there are only a few slots")]
+    fn slot_rect(&self, slot: usize) -> Rectangle {
+        let width = Player::HOTBAR_SLOTS as f32 * (SLOT_SIZE + SLOT_GAP) - SLOT_GAP;
+        Rectangle::new(
+            0.5 * (self.screen.x - width) + slot as f32 * (SLOT_SIZE + SLOT_GAP),
+            self.screen.y - MARGIN - SLOT_SIZE,
+            SLOT_SIZE,
+            SLOT_SIZE,
+        )
+    }
+
+    /// Just above the left end of the hotbar
+    fn health_rect(&self) -> Rectangle {
+        let hotbar = self.slot_rect(0);
+        Rectangle::new(
+            hotbar.x,
+            hotbar.y - SLOT_GAP - HEALTH_BAR.y,
+            HEALTH_BAR.x,
+            HEALTH_BAR.y,
+        )
+    }
+
+    fn tooltip_rect(&self) -> Rectangle {
+        let Vector2 { x, y } = self.center();
+        Rectangle::new(
+            x + 2.0 * CROSSHAIR_RADIUS,
+            y + 2.0 * CROSSHAIR_RADIUS,
+            220.0,
+            2.0 * FONT_SIZE + 16.0,
+        )
+    }
+
+    /// Draw how placement snaps just above the right end of the hotbar, level with the health bar
+    fn draw_snap(&self, d: &mut Renderer<'_>) -> draw::Result {
+        let Some(snap) = &self.snap else {
+            return Ok(());
+        };
+        let font = d.font().ok_or(draw::Error)?;
+        let size = font.measure_text(snap, 0.75 * FONT_SIZE, 0.0);
+        let hotbar = self.slot_rect(Player::HOTBAR_SLOTS - 1);
+        let health = self.health_rect();
+        d.draw_text(
+            snap,
+            Vector2::new(
+                hotbar.x + hotbar.width - size.x,
+                health.y + 0.5 * (health.height - size.y),
+            ),
+            0.75 * FONT_SIZE,
+            0.0,
+            Color::WHITE,
+        )
+    }
+
+    /// Draw the tutorial's objective, and the arrow pointing at its waypoint
+    fn draw_tutorial(&self, d: &mut Renderer<'_>) -> draw::Result {
+        let font = d.font().ok_or(draw::Error)?;
+        if let Some(objective) = &self.objective {
+            let size = font.measure_text(objective, FONT_SIZE, 0.0);
+            let rect = Rectangle::new(MARGIN, 0.2 * self.screen.y, size.x + 16.0, size.y + 16.0);
+            d.draw_rectangle(rect, PANEL)?;
+            d.draw_text(
+                objective,
+                Vector2::new(rect.x + 8.0, rect.y + 8.0),
+                FONT_SIZE,
+                0.0,
+                palette().waypoint,
+            )?;
+        }
+
+        if let Some(waypoint) = &self.waypoint {
+            d.draw_triangle(&waypoint.arrow(), palette().waypoint)?;
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "waypoints are much closer than i32::MAX meters"
+            )]
+            let meters = waypoint.distance.round() as i32;
+            let distance = tr!("hud.distance", meters = meters);
+            let size = font.measure_text(&distance, FONT_SIZE, 0.0);
+            // Behind the arrow, so it doesn't cover the waypoint
+            let center =
+                waypoint.position - waypoint.direction * (WAYPOINT_ARROW + 4.0 + 0.5 * size.y);
+            d.draw_text(
+                &distance,
+                center - 0.5 * size,
+                FONT_SIZE,
+                0.0,
+                palette().waypoint,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Draw for Hud {
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        let center = self.center();
+        for offset in [Vector2::X, Vector2::Y] {
+            d.draw_line(
+                center - offset * CROSSHAIR_RADIUS,
+                center + offset * CROSSHAIR_RADIUS,
+                Some(2.0),
+                Color::WHITE,
+            )?;
+        }
+
+        for slot in 0..Player::HOTBAR_SLOTS {
+            let rect = self.slot_rect(slot);
+            d.draw_rectangle(rect, PANEL)?;
+            let (thick, color) = if slot == self.selected_slot {
+                (3.0, Color::WHITE)
+            } else {
+                (1.0, Color::GRAY)
+            };
+            d.draw_rectangle_lines(rect, Some(thick), color)?;
+        }
+
+        let rect = self.health_rect();
+        d.draw_rectangle(rect, PANEL)?;
+        d.draw_rectangle(
+            Rectangle {
+                width: rect.width * self.health.clamp(0.0, 1.0),
+                ..rect
+            },
+            palette().health,
+        )?;
+        d.draw_rectangle_lines(rect, Some(1.0), Color::GRAY)?;
+
+        let font = d.font().ok_or(draw::Error)?;
+        for (slot, contents) in self.hotbar.iter().enumerate() {
+            let Some((label, count)) = contents else {
+                continue;
+            };
+            let rect = self.slot_rect(slot);
+            d.draw_text(
+                label,
+                Vector2::new(rect.x + 4.0, rect.y + 4.0),
+                0.75 * FONT_SIZE,
+                0.0,
+                Color::WHITE,
+            )?;
+            let count = count.to_string();
+            let count_size = font.measure_text(&count, 0.75 * FONT_SIZE, 0.0);
+            d.draw_text(
+                &count,
+                Vector2::new(rect.x + rect.width, rect.y + rect.height) - count_size - 4.0,
+                0.75 * FONT_SIZE,
+                0.0,
+                Color::WHITE,
+            )?;
+        }
+
+        self.draw_snap(d)?;
+
+        if let Some(prompt) = &self.prompt {
+            let size = font.measure_text(prompt, FONT_SIZE, 0.0);
+            d.draw_text(
+                prompt,
+                Vector2::new(
+                    0.5 * (self.screen.x - size.x),
+                    self.health_rect().y - MARGIN - size.y,
+                ),
+                FONT_SIZE,
+                0.0,
+                Color::WHITE,
+            )?;
+        }
+
+        if let Some(death) = &self.death {
+            let size = font.measure_text(death, 2.0 * FONT_SIZE, 0.0);
+            d.draw_text(
+                death,
+                Vector2::new(0.5 * (self.screen.x - size.x), 0.25 * self.screen.y),
+                2.0 * FONT_SIZE,
+                0.0,
+                palette().health,
+            )?;
+        }
+
+        if let Some(milestone) = &self.milestone {
+            let size = font.measure_text(milestone, 1.5 * FONT_SIZE, 0.0);
+            d.draw_text(
+                milestone,
+                Vector2::new(0.5 * (self.screen.x - size.x), 0.15 * self.screen.y),
+                1.5 * FONT_SIZE,
+                0.0,
+                palette().milestone,
+            )?;
+        }
+
+        self.draw_tutorial(d)?;
+
+        if let Some(tooltip) = &self.tooltip {
+            let rect = self.tooltip_rect();
+            d.draw_rectangle(rect, PANEL)?;
+            d.draw_text(
+                tooltip,
+                Vector2::new(rect.x + 8.0, rect.y + 8.0),
+                FONT_SIZE,
+                0.0,
+                Color::WHITE,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::coords::{LabVector3, PlayerVector3, VectorConstants},
+        region::RegionId,
+    };
+
+    #[test]
+    fn test_waypoint_marker() {
+        let camera = Camera3D::perspective(Vector3::ZERO, Vector3::NEG_Z, Vector3::Y, 90.0);
+        let screen = Vector2::new(800.0, 600.0);
+        let ahead = WaypointMarker::new(&camera, 1.0, Vector3::new(0.0, 0.0, -10.0), screen);
+        assert_eq!(ahead.position, Vector2::new(400.0, 300.0));
+        assert_eq!(
+            ahead.direction,
+            Vector2::Y,
+            "on screen, it points down at it"
+        );
+        assert!((ahead.distance - 10.0).abs() < 1e-6);
+
+        let up_right = WaypointMarker::new(&camera, 1.0, Vector3::new(5.0, 5.0, -10.0), screen);
+        assert!((up_right.position - Vector2::new(600.0, 150.0)).length() < 1e-3);
+
+        let behind = WaypointMarker::new(&camera, 1.0, Vector3::new(-1.0, 0.0, 10.0), screen);
+        assert_eq!(
+            behind.direction,
+            Vector2::NEG_X,
+            "behind, it points the way to turn"
+        );
+        assert!(
+            (behind.position.x - (MARGIN + WAYPOINT_ARROW)).abs() < 1e-3,
+            "kept at the edge of the screen"
+        );
+    }
+
+    #[test]
+    fn test_prompt() {
+        let screen = Vector2::new(800.0, 600.0);
+        let mut state = GameState::new(Player::new(PlayerVector3::ZERO, 0.0, 0.0, 45.0, 1.0));
+        assert_eq!(Hud::new(&state, screen).prompt, None);
+
+        // In front of the first reactor, looking at its middle
+        let factory = &state.factories[0];
+        let reactor = factory.reactor_id(0).unwrap();
+        let machine = factory.machine_ui(reactor).unwrap();
+        let bounds = machine.bounds();
+        let min = bounds.min.to_player(&factory.origin).to_vec3();
+        let max = bounds.max.to_player(&factory.origin).to_vec3();
+        let center = 0.5 * (min + max);
+        let eye = min.y + Player::EYE_HEIGHT;
+        let pitch = ((center.y - eye) / 2.0).atan();
+        let name = machine.name();
+        state.player = Player::new(
+            PlayerVector3::from_f32(center.x, min.y, max.z + 2.0),
+            0.0,
+            pitch,
+            45.0,
+            1.0,
+        );
+        state.current_region = RegionId::Factory(0);
+        assert_eq!(state.interaction(), Some(Interaction::Machine(0, reactor)));
+        assert_eq!(
+            Hud::new(&state, screen).prompt,
+            Some(tr!("hud.open", name = name))
+        );
+
+        // In front of the spectrometer, which has nothing to open
+        state.player = Player::new(
+            LabVector3::from_f32(-7.5, 0.0, 6.5).to_player(&state.lab.origin),
+            0.0,
+            -0.3,
+            45.0,
+            1.0,
+        );
+        state.current_region = RegionId::Lab;
+        assert!(state.lab.looked_at_spectrometer(&state.player).is_some());
+        assert_eq!(state.interaction(), None);
+        assert_eq!(Hud::new(&state, screen).prompt, None);
+    }
+}
@@ -96,6 +96,30 @@ impl ButtonStateExt for (i32, GamepadButton) {
 
 pub type Gamepad = i32;
 
+/// How an input follows what it's bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputMode {
+    /// Happens once when pressed
+    Press,
+    /// Lasts as long as it's held
+    Hold,
+    /// Turns on when pressed, and off when pressed again
+    Toggle,
+}
+
+impl InputMode {
+    pub const ALL: [Self; 3] = [Self::Press, Self::Hold, Self::Toggle];
+
+    /// What the mode is called in the settings file
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Press => "press",
+            Self::Hold => "hold",
+            Self::Toggle => "toggle",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum EventSource {
     Constant(bool),
@@ -107,6 +131,8 @@ pub enum EventSource {
     Xor(Box<(EventSource, EventSource)>),
     Xnor(Box<(EventSource, EventSource)>),
     Toggle(Box<EventSource>, bool),
+    /// True only when the source has just become true, remembering whether it was last time
+    Rising(Box<EventSource>, bool),
     Eq(Box<(AxisSource, AxisSource, AxisSource)>),
     Ne(Box<(AxisSource, AxisSource, AxisSource)>),
     Gt(Box<(AxisSource, AxisSource)>),
@@ -125,6 +151,44 @@ impl From<bool> for EventSource {
 }
 
 impl EventSource {
+    /// The same source, but true for as long as it's held rather than once when it's pressed
+    ///
+    /// Toggles and edges are taken off, so it's back to what's bound underneath.
+    fn held(self) -> Self {
+        let held = |sources: Vec<Self>| sources.into_iter().map(Self::held).collect();
+        match self {
+            Self::KeyboardKey(KeyState::Pressed | KeyState::PressedRepeat, key) => {
+                Self::KeyboardKey(KeyState::Down, key)
+            }
+            Self::MouseButton(ButtonState::Pressed, button) => {
+                Self::MouseButton(ButtonState::Down, button)
+            }
+            Self::GamepadButton(ButtonState::Pressed, gamepad, button) => {
+                Self::GamepadButton(ButtonState::Down, gamepad, button)
+            }
+            Self::Toggle(src, _) | Self::Rising(src, _) => src.held(),
+            Self::Not(src) => Self::Not(Box::new(src.held())),
+            Self::And(src) => Self::And(held(src)),
+            Self::Nand(src) => Self::Nand(held(src)),
+            Self::Or(src) => Self::Or(held(src)),
+            Self::Nor(src) => Self::Nor(held(src)),
+            Self::Xor(src) => Self::Xor(Box::new((src.0.held(), src.1.held()))),
+            Self::Xnor(src) => Self::Xnor(Box::new((src.0.held(), src.1.held()))),
+            other => other,
+        }
+    }
+
+    /// The same source, following what's bound underneath in `mode`
+    #[must_use]
+    pub fn with_mode(self, mode: InputMode) -> Self {
+        let held = self.held();
+        match mode {
+            InputMode::Press => Self::Rising(Box::new(held), false),
+            InputMode::Hold => held,
+            InputMode::Toggle => Self::Toggle(Box::new(Self::Rising(Box::new(held), false)), false),
+        }
+    }
+
     fn check(&mut self, rl: &RaylibHandle) -> bool {
         match self {
             Self::Constant(val) => *val,
@@ -141,6 +205,12 @@ impl EventSource {
                 }
                 *mem
             }
+            Self::Rising(src, was) => {
+                let is = src.check(rl);
+                let rising = is && !*was;
+                *was = is;
+                rising
+            }
             Self::Eq(src) => (src.0.check(rl) - src.1.check(rl)).abs() <= src.2.check(rl),
             Self::Ne(src) => (src.0.check(rl) - src.1.check(rl)).abs() > src.2.check(rl),
             Self::Gt(src) => src.0.check(rl) > src.1.check(rl),
@@ -458,10 +528,149 @@ pub enum EventInput {
     Jump,
     NextItem,
     PrevItem,
+    Interact,
+    ToggleConsole,
+    /// Switch between first and third person
+    ToggleCamera,
+    /// Narrow the field of view while held
+    Zoom,
+    /// Switch between the minimap and the fullscreen map
+    ToggleMap,
+    /// Start or stop routing belts and pipes between machines
+    ToggleRouteTool,
+    /// Switch between selecting a blueprint, pasting it, and neither
+    ToggleBlueprintTool,
+    /// Start or stop picking machines to tear down
+    ToggleDemolishTool,
+    /// Switch between painting decals on the floor, erasing them, and neither
+    ToggleDecalTool,
+    /// Turn what's being placed a quarter turn
+    RotatePlacement,
+    /// Flip what's being placed east to west, then north to south, then back
+    MirrorPlacement,
+    /// Switch between snapping to the grid, to every half meter, and placing freely
+    CycleSnap,
+    /// Show or hide how long each part of the frame takes
+    ToggleProfiler,
+    /// Show or hide where belts, pipes, and trains carry things
+    ToggleRouteOverlay,
+    /// Save the recent frame timings as a Chrome trace
+    ExportTrace,
+    /// Save a picture of what's on screen, HUD included
+    Screenshot,
+    /// Save a picture of the world without the HUD
+    ScreenshotWithoutHud,
+    /// Start or stop saving an overview of the factory every so often
+    ToggleTimelapse,
+    /// Stop everything and fly the camera around to take pictures, or go back to playing
+    TogglePhotoMode,
+    /// Open the settings menu
+    OpenSettings,
+    /// Open the graphs of what's been made and used up
+    OpenStatistics,
+    /// Open the pages about everything that's been discovered
+    OpenCompendium,
+    /// Take back the last thing built or demolished in a factory
+    Undo,
+    /// Build or demolish again whatever was last undone
+    Redo,
+    /// Slow down the vehicle being ridden while held
+    Brake,
+}
+
+impl EventInput {
+    pub const ALL: [Self; 29] = [
+        Self::Sprint,
+        Self::Jump,
+        Self::NextItem,
+        Self::PrevItem,
+        Self::Interact,
+        Self::ToggleConsole,
+        Self::ToggleCamera,
+        Self::Zoom,
+        Self::ToggleMap,
+        Self::ToggleRouteTool,
+        Self::ToggleBlueprintTool,
+        Self::ToggleDemolishTool,
+        Self::ToggleDecalTool,
+        Self::RotatePlacement,
+        Self::MirrorPlacement,
+        Self::CycleSnap,
+        Self::ToggleProfiler,
+        Self::ToggleRouteOverlay,
+        Self::ExportTrace,
+        Self::Screenshot,
+        Self::ScreenshotWithoutHud,
+        Self::ToggleTimelapse,
+        Self::TogglePhotoMode,
+        Self::OpenSettings,
+        Self::OpenStatistics,
+        Self::OpenCompendium,
+        Self::Undo,
+        Self::Redo,
+        Self::Brake,
+    ];
+
+    /// What the input is called in menus, in the player's language
+    pub fn label(self) -> String {
+        let english = match self {
+            Self::Sprint => "Sprint",
+            Self::Jump => "Jump",
+            Self::NextItem => "Next item",
+            Self::PrevItem => "Previous item",
+            Self::Interact => "Interact",
+            Self::ToggleConsole => "Console",
+            Self::ToggleCamera => "Switch camera",
+            Self::Zoom => "Zoom",
+            Self::ToggleMap => "Map",
+            Self::ToggleRouteTool => "Route tool",
+            Self::ToggleBlueprintTool => "Blueprint tool",
+            Self::ToggleDemolishTool => "Demolish tool",
+            Self::ToggleDecalTool => "Decal tool",
+            Self::RotatePlacement => "Rotate",
+            Self::MirrorPlacement => "Mirror",
+            Self::CycleSnap => "Snapping",
+            Self::ToggleProfiler => "Profiler",
+            Self::ToggleRouteOverlay => "Route overlay",
+            Self::ExportTrace => "Save frame timings",
+            Self::Screenshot => "Screenshot",
+            Self::ScreenshotWithoutHud => "Screenshot without HUD",
+            Self::ToggleTimelapse => "Timelapse",
+            Self::TogglePhotoMode => "Photo mode",
+            Self::OpenSettings => "Settings",
+            Self::OpenStatistics => "Statistics",
+            Self::OpenCompendium => "Compendium",
+            Self::Undo => "Undo",
+            Self::Redo => "Redo",
+            Self::Brake => "Brake",
+        };
+        crate::locale::tr_or(&format!("input.{self:?}"), english)
+    }
+
+    /// Whether the input lasts as long as its key is held, rather than happening once when
+    /// it's pressed
+    pub const fn is_held(self) -> bool {
+        matches!(self, Self::Sprint | Self::Zoom | Self::Brake)
+    }
+
+    /// How the input follows its key, unless it's been set to something else
+    pub const fn default_mode(self) -> InputMode {
+        if self.is_held() {
+            InputMode::Hold
+        } else {
+            InputMode::Press
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum AxisInput {}
+pub enum AxisInput {
+    /// Positive to zoom the map in, negative to zoom it out
+    MapZoom,
+    /// Positive to drive the vehicle being ridden the way the player is looking, negative to
+    /// drive it the other way
+    Throttle,
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum VectorInput {
@@ -471,8 +680,8 @@ pub enum VectorInput {
 
 #[derive(Debug)]
 pub struct Bindings {
-    event: [EventSource; 4],
-    axis: [AxisSource; 0],
+    event: [EventSource; 29],
+    axis: [AxisSource; 2],
     vector: [VectorSource; 2],
 }
 
@@ -535,8 +744,8 @@ impl FromStr for Bindings {
 impl Default for Bindings {
     fn default() -> Self {
         Self {
-            event: [const { EventSource::Constant(false) }; 4],
-            axis: [const { AxisSource::Constant(0.0) }; 0],
+            event: [const { EventSource::Constant(false) }; 29],
+            axis: [const { AxisSource::Constant(0.0) }; 2],
             vector: [const { VectorSource::Constant(Vector2::ZERO) }; 2],
         }
     }
@@ -557,9 +766,44 @@ impl Bindings {
         result[EventInput::Jump] = KEY_SPACE.pressed();
         result[EventInput::NextItem] = VectorSource::MouseWheel.max_magnitude().gt(0.0);
         result[EventInput::PrevItem] = VectorSource::MouseWheel.max_magnitude().lt(0.0);
+        result[EventInput::Interact] = KEY_E.pressed();
+        result[EventInput::ToggleConsole] = KEY_GRAVE.pressed();
+        result[EventInput::ToggleCamera] = KEY_F5.pressed();
+        result[EventInput::Zoom] = KEY_C.down();
+        result[EventInput::ToggleMap] = KEY_M.pressed();
+        result[EventInput::ToggleRouteTool] = KEY_R.pressed();
+        result[EventInput::ToggleBlueprintTool] = KEY_B.pressed();
+        result[EventInput::ToggleDemolishTool] = KEY_X.pressed();
+        result[EventInput::ToggleDecalTool] = KEY_V.pressed();
+        result[EventInput::RotatePlacement] = KEY_Q.pressed();
+        result[EventInput::MirrorPlacement] = KEY_F.pressed();
+        result[EventInput::CycleSnap] = KEY_G.pressed();
+        result[EventInput::ToggleProfiler] = KEY_F3.pressed();
+        result[EventInput::ExportTrace] = KEY_F4.pressed();
+        let shift = || KEY_LEFT_SHIFT.down() | KEY_RIGHT_SHIFT.down();
+        result[EventInput::Screenshot] = KEY_F2.pressed() & !shift();
+        result[EventInput::ScreenshotWithoutHud] = KEY_F2.pressed() & shift();
+        result[EventInput::ToggleTimelapse] = KEY_F6.pressed();
+        result[EventInput::ToggleRouteOverlay] = KEY_F7.pressed();
+        result[EventInput::TogglePhotoMode] = KEY_F8.pressed();
+        result[EventInput::OpenSettings] = KEY_F1.pressed();
+        result[EventInput::OpenStatistics] = KEY_P.pressed();
+        result[EventInput::OpenCompendium] = KEY_J.pressed();
+        let ctrl = || KEY_LEFT_CONTROL.down() | KEY_RIGHT_CONTROL.down();
+        result[EventInput::Undo] = ctrl() & KEY_Z.pressed() & !shift();
+        result[EventInput::Redo] = ctrl() & (KEY_Y.pressed() | (KEY_Z.pressed() & shift()));
+        result[EventInput::Brake] = KEY_SPACE.down();
+        result[AxisInput::MapZoom] = KEY_EQUAL.down() - KEY_MINUS.down();
+        result[AxisInput::Throttle] = KEY_W.down() - KEY_S.down();
         result
     }
 
+    /// Make `input` follow what it's bound to in `mode` instead
+    pub fn set_mode(&mut self, input: EventInput, mode: InputMode) {
+        let source = std::mem::replace(&mut self[input], EventSource::Constant(false));
+        self[input] = source.with_mode(mode);
+    }
+
     pub fn check(&mut self, rl: &RaylibHandle) -> Inputs {
         Inputs {
             event: std::array::from_fn(|idx| self.event[idx].check(rl)),
@@ -571,8 +815,8 @@ impl Bindings {
 
 #[derive(Debug, Default)]
 pub struct Inputs {
-    event: [bool; 4],
-    axis: [f32; 0],
+    event: [bool; 29],
+    axis: [f32; 2],
     vector: [Vector2; 2],
 }
 
@@ -632,4 +876,48 @@ mod tests {
     fn test0() {
         dbg!(Bindings::default_binds());
     }
+
+    #[test]
+    fn test_default_binds() {
+        let bindings = Bindings::default_binds();
+        for input in EventInput::ALL {
+            assert!(
+                !matches!(bindings[input], EventSource::Constant(false)),
+                "{input:?} has no default binding"
+            );
+        }
+    }
+
+    #[test]
+    fn test_input_modes() {
+        use KeyboardKey::{KEY_E, KEY_LEFT_SHIFT, KEY_RIGHT_SHIFT};
+
+        let held = KEY_E.pressed().with_mode(InputMode::Hold);
+        assert!(matches!(
+            held,
+            EventSource::KeyboardKey(KeyState::Down, KEY_E)
+        ));
+
+        let toggle = (KEY_LEFT_SHIFT.down() | KEY_RIGHT_SHIFT.down()).with_mode(InputMode::Toggle);
+        assert!(matches!(
+            &toggle,
+            EventSource::Toggle(src, false) if matches!(**src, EventSource::Rising(_, false))
+        ));
+        let pressed = toggle.with_mode(InputMode::Press);
+        assert!(
+            matches!(
+                &pressed,
+                EventSource::Rising(src, false)
+                    if matches!(**src, EventSource::Or(ref keys) if keys.len() == 2)
+            ),
+            "switching modes again starts from the keys, not the last mode"
+        );
+
+        let mut bindings = Bindings::default_binds();
+        bindings.set_mode(EventInput::Zoom, InputMode::Toggle);
+        assert!(matches!(
+            bindings[EventInput::Zoom],
+            EventSource::Toggle(..)
+        ));
+    }
 }
@@ -0,0 +1,115 @@
+//! What items look like out in the world, like on belts
+//!
+//! [`ItemVisuals`] decides how each item is drawn. Items can be registered with a visual of
+//! their own; otherwise pure elements are drawn as an icon in a color of their own, and
+//! everything else as a crate.
+
+use crate::{chem::element::Element, container::Item, resource::AssetId};
+use raylib::prelude::Color;
+use std::collections::BTreeMap;
+
+/// How an item is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemVisual {
+    /// A small mesh, drawn in a single instanced draw call with every other item using the
+    /// same model and material
+    Mesh {
+        /// Only the first mesh is used
+        model: AssetId,
+        /// Must support instancing
+        material: AssetId,
+    },
+    /// A texture that always faces the camera
+    Billboard { texture: AssetId, tint: Color },
+}
+
+impl ItemVisual {
+    /// How big an item looks, in meters
+    pub const SIZE: f32 = 0.25;
+
+    /// What items without a visual of their own look like
+    pub const CRATE: Self = Self::Mesh {
+        model: AssetId::ItemCrate,
+        material: AssetId::ItemCrateInstanced,
+    };
+
+    /// Assets used by the visuals items get when they aren't registered
+    pub const ASSETS: [AssetId; 3] = [
+        AssetId::ItemCrate,
+        AssetId::ItemCrateInstanced,
+        AssetId::ItemIcon,
+    ];
+
+    /// An icon for a pure element
+    pub fn element(element: Element) -> Self {
+        // Stepping around the color wheel by the golden angle keeps neighbors apart
+        let hue = (f32::from(element.protons().get()) * 137.508) % 360.0;
+        Self::Billboard {
+            texture: AssetId::ItemIcon,
+            tint: Color::color_from_hsv(hue, 0.6, 0.9),
+        }
+    }
+}
+
+/// Which [`ItemVisual`] each item is drawn with
+#[derive(Debug, Clone, Default)]
+pub struct ItemVisuals {
+    registered: BTreeMap<Item, ItemVisual>,
+}
+
+impl ItemVisuals {
+    pub const fn new() -> Self {
+        Self {
+            registered: BTreeMap::new(),
+        }
+    }
+
+    /// Draw `item` as `visual` from now on, returning the visual it was registered with before
+    ///
+    /// Regions only load the assets of the default visuals, so `visual`'s need to be acquired
+    /// separately.
+    pub fn register(&mut self, item: Item, visual: ItemVisual) -> Option<ItemVisual> {
+        self.registered.insert(item, visual)
+    }
+
+    /// How `item` is drawn
+    pub fn get(&self, item: &Item) -> ItemVisual {
+        if let Some(&visual) = self.registered.get(item) {
+            return visual;
+        }
+        let elements = item.element_counts();
+        match elements.keys().next() {
+            Some(&element) if elements.len() == 1 => ItemVisual::element(element),
+            _ => ItemVisual::CRATE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_visuals() {
+        let mut visuals = ItemVisuals::new();
+        let water = "H2O".parse().unwrap();
+        let iron = "Fe".parse().unwrap();
+        assert_eq!(visuals.get(&water), ItemVisual::CRATE);
+        assert_eq!(visuals.get(&iron), ItemVisual::element(Element::Fe));
+        assert!(
+            matches!(
+                visuals.get(&"O2".parse().unwrap()),
+                ItemVisual::Billboard { .. }
+            ),
+            "molecules of one element are still that element"
+        );
+
+        let blue = ItemVisual::Billboard {
+            texture: AssetId::ItemIcon,
+            tint: Color::SKYBLUE,
+        };
+        assert_eq!(visuals.register(water.clone(), blue), None);
+        assert_eq!(visuals.get(&water), blue);
+        assert_eq!(visuals.register(water, ItemVisual::CRATE), Some(blue));
+    }
+}
@@ -0,0 +1,888 @@
+#![warn(clippy::pedantic)]
+#![warn(clippy::all)]
+#![warn(clippy::style)]
+#![deny(clippy::perf, clippy::multiple_unsafe_ops_per_block)]
+#![allow(dead_code, reason = "under development")]
+#![forbid(clippy::missing_safety_doc, clippy::undocumented_unsafe_blocks)]
+#![forbid(
+    clippy::missing_const_for_fn,
+    reason = "a const fn not marked as const denies callers the opportunity to be const"
+)]
+#![warn(
+    clippy::unnecessary_safety_doc,
+    clippy::unnecessary_safety_comment,
+    clippy::allow_attributes_without_reason,
+    clippy::must_use_candidate
+)]
+#![feature(
+    const_trait_impl,
+    new_range_api,
+    unchecked_shifts,
+    const_ops,
+    stmt_expr_attributes,
+    custom_inner_attributes,
+    assert_matches,
+    const_try,
+    const_range_bounds,
+    associated_type_defaults
+)]
+
+mod app_state;
+mod audio;
+mod blueprint_tool;
+mod capture;
+mod chem;
+mod compendium;
+mod console;
+mod container;
+mod contract;
+mod decal_tool;
+mod demolish_tool;
+mod ecs;
+mod event_bus;
+mod game;
+mod headless;
+mod health;
+mod hud;
+mod input;
+mod item_visual;
+mod lighting;
+mod locale;
+mod math;
+mod milestone;
+mod minimap;
+mod net;
+mod ordinals;
+mod palette;
+mod pause_menu;
+mod photo_mode;
+mod placement;
+mod player;
+pub mod plugin;
+mod profiler;
+#[cfg(test)]
+mod prop;
+mod region;
+mod research;
+mod resource;
+mod rl_helpers;
+mod rlights;
+mod rng;
+mod route_overlay;
+mod route_tool;
+mod save;
+mod settings;
+mod stats;
+mod time;
+mod tutorial;
+mod ui;
+mod weather;
+
+use std::{cell::RefCell, path::Path, rc::Rc, time::Duration};
+
+use crate::{
+    app_state::{AppState, StateStack},
+    audio::{Audio, UiSound},
+    blueprint_tool::BlueprintTool,
+    capture::Capture,
+    compendium::panel::CompendiumPanel,
+    console::Console,
+    contract::panel::ContractPanel,
+    decal_tool::DecalTool,
+    demolish_tool::DemolishTool,
+    event_bus::GameEvent,
+    game::{GameState, Interaction},
+    hud::Hud,
+    input::{AxisInput, EventInput, Inputs},
+    lighting::LightingManager,
+    locale::tr,
+    minimap::Minimap,
+    net::{Client, Server, Session},
+    pause_menu::{PauseAction, PauseMenu},
+    photo_mode::PhotoMode,
+    profiler::{Profiler, Section, Timer},
+    region::{
+        Region, RegionId,
+        events::{RegionContext, RegionSystems},
+        exterior,
+        factory::machine_ui::MachinePanel,
+        lab::{instrument_panel::MeasuringPanel, table_panel::PeriodicTablePanel},
+    },
+    rl_helpers::DynRaylibDraw3D,
+    route_overlay::RouteOverlay,
+    route_tool::RouteTool,
+    save::{Autosave, LoadError, SaveData, SaveSlot, panel::RecoveryPanel},
+    settings::{Settings, panel::SettingsPanel},
+    stats::panel::StatsPanel,
+    time::FixedTimestep,
+    tutorial::{TUTORIAL_PATH, Tutorial},
+    ui::UiStack,
+};
+use engine::draw2d::{Draw, Renderer, RenderingOptions};
+use math::coords::VectorConstants;
+use raylib::prelude::*;
+use {
+    input::Bindings,
+    math::coords::player::PlayerVector3,
+    player::{CameraMode, Player},
+    resource::Resources,
+};
+
+/// Draw the world from `region` as `viewer` sees it, without any tools or HUD
+fn draw_world(
+    d: &mut dyn DynRaylibDraw3D,
+    thread: &RaylibThread,
+    resources: &Resources,
+    state: &GameState,
+    viewer: &Player,
+    region: RegionId,
+    session: Option<&Session>,
+) {
+    let GameState {
+        factories,
+        lab,
+        world,
+        clock,
+        weather,
+        ..
+    } = state;
+    exterior::draw_exteriors(d, viewer, factories, lab, region);
+    region
+        .to_region(factories, lab, world)
+        .draw(d, thread, resources, viewer);
+    viewer.draw(d);
+    if let Some(session) = session {
+        net::draw_peers(d, viewer, session.peers());
+    }
+    if region == RegionId::Rail {
+        weather.draw(d, viewer.position, clock);
+    }
+}
+
+/// Haze a view `size` pixels across over what's been drawn, if it's outside in fog or rain
+fn draw_haze(d: &mut impl RaylibDraw, state: &GameState, size: Vector2) {
+    if state.current_region == RegionId::Rail {
+        d.draw_rectangle_v(Vector2::ZERO, size, state.weather.haze());
+    }
+}
+
+/// Played on a loop if it exists
+const MUSIC_PATH: &str = "assets/music.ogg";
+
+/// Where [`EventInput::ExportTrace`] saves the profiler's trace
+const TRACE_PATH: &str = "trace.json";
+
+/// Size of the picture kept with each save, in pixels
+const THUMBNAIL_SIZE: (u32, u32) = (320, 180);
+
+const BENCH_USAGE: &str = "usage: bench [reactors] [belts] [seconds]";
+
+/// The command line argument following `flag`, if there is one
+fn arg_after(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != flag);
+    args.next().and(args.next())
+}
+
+/// Run `bench [reactors] [belts] [seconds]`, see [`headless::bench`]
+fn run_bench(args: &[String]) {
+    let mut args = args.iter().map(|arg| arg.parse::<usize>());
+    let mut next = |default| args.next().unwrap_or(Ok(default));
+    let (Ok(reactors), Ok(belts), Ok(seconds)) = (next(100), next(50), next(5)) else {
+        eprintln!("{BENCH_USAGE}");
+        return;
+    };
+    headless::bench(reactors, belts, Duration::from_secs(seconds as u64));
+}
+
+#[allow(clippy::too_many_lines, reason = "don't care")]
+/// Start the game with `plugins` installed, as told to by the command line
+///
+/// The game's own binary starts it with none. A crate adding machines of its own depends on this
+/// one and calls this from its `main` with its [`Plugin`](plugin::Plugin)s instead.
+///
+/// # Panics
+///
+/// If the game's font can't be loaded.
+pub fn run(plugins: &[&dyn plugin::Plugin]) {
+    let args = std::env::args().collect::<Vec<_>>();
+    for e in plugin::install(plugins) {
+        eprintln!("left out of the game: {e}");
+    }
+    if args.get(1).is_some_and(|arg| arg == "bench") {
+        run_bench(&args[2..]);
+        return;
+    }
+    if args.iter().any(|arg| arg == "--headless") {
+        let port = arg_after("--host").map(|port| port.parse());
+        match port {
+            Some(Err(e)) => eprintln!("invalid port: {e}"),
+            Some(Ok(port)) => headless::run(Some(port)),
+            None => headless::run(None),
+        }
+        return;
+    }
+
+    let (mut rl, thread) = init()
+        .title("chemical factory game")
+        .resizable()
+        .msaa_4x()
+        .build();
+
+    rl.maximize_window();
+    rl.hide_cursor();
+    rl.disable_cursor();
+    // Escape pauses instead, and the game is quit from the pause menu
+    rl.set_exit_key(None);
+
+    let mut resources = Resources::new();
+    if args.iter().any(|arg| arg == "--hot-reload") {
+        resources = resources.with_hot_reload("assets");
+    }
+    // Kept for the whole game, since plugin machines and items can turn up anywhere
+    plugin::registry().register_items(&mut resources.item_visuals);
+    if let Err(e) = resources.acquire_all(&mut rl, &thread, &plugin::registry().assets()) {
+        eprintln!("failed to load plugin models: {e}");
+    }
+
+    // The game is playable without sound, so don't give up if there's no audio device
+    let audio_device = RaylibAudio::init_audio_device()
+        .inspect_err(|e| eprintln!("audio disabled: {e}"))
+        .ok();
+    let mut audio = audio_device.as_ref().and_then(|device| {
+        Audio::new(device)
+            .inspect_err(|e| eprintln!("audio disabled: {e}"))
+            .ok()
+    });
+    if let Some(audio) = &mut audio
+        && Path::new(MUSIC_PATH).is_file()
+        && let Err(e) = audio.play_music(MUSIC_PATH)
+    {
+        eprintln!("failed to play music: {e}");
+    }
+
+    let font = rl
+        .load_font_from_memory(
+            &thread,
+            ".ttf",
+            include_bytes!("../assets/FiraCode-Regular.ttf"),
+            20,
+            Some(&locale::glyphs()),
+        )
+        .unwrap();
+
+    let mut bindings = Bindings::default_binds();
+
+    let player = Player::spawn(&mut rl, &thread, PlayerVector3::ZERO, 0.0, 0.0, 45.0);
+
+    let mut state = GameState::new(player);
+    state.tutorial = Tutorial::load(Path::new(TUTORIAL_PATH)).unwrap_or_else(|e| {
+        eprintln!("using the built-in tutorial, couldn't read {TUTORIAL_PATH}: {e}");
+        Tutorial::built_in()
+    });
+
+    let loaded = Settings::path().map(|path| {
+        Settings::load(&path).map_err(|e| format!("couldn't read {}: {e}", path.display()))
+    });
+    let settings = match loaded {
+        Some(Ok((settings, errors))) => {
+            for e in errors {
+                eprintln!("ignoring setting on {e}");
+            }
+            settings
+        }
+        Some(Err(e)) => {
+            eprintln!("using default settings: {e}");
+            Settings::default()
+        }
+        None => Settings::default(),
+    };
+    settings.apply(&mut rl, &mut bindings, &mut state.player, audio.as_mut());
+    // Shared with the settings menu, and applied again whenever it changes them
+    let settings = Rc::new(RefCell::new(settings));
+    let mut applied_settings = settings.borrow().clone();
+
+    let save_slot = SaveSlot::default_slot();
+    // Opened once the UI is up
+    let mut recovery = None;
+    if let Some(slot) = &save_slot {
+        match slot.load() {
+            Ok(Some(data)) => data.restore(&mut state),
+            Ok(None) => {}
+            Err(LoadError::Damaged) if !slot.backups().is_empty() => {
+                recovery = Some(RecoveryPanel::new(&rl, slot.clone()));
+            }
+            Err(e) => eprintln!(
+                "starting a new game, couldn't load {}: {e}",
+                slot.path().display()
+            ),
+        }
+    }
+
+    let name = arg_after("--name").unwrap_or_else(|| "player".to_string());
+    let mut session = if let Some(port) = arg_after("--host") {
+        port.parse()
+            .map_err(|e| format!("invalid port: {e}"))
+            .and_then(|port| Server::host(port, name, &state).map_err(|e| e.to_string()))
+            .map(Session::Host)
+            .inspect_err(|e| eprintln!("failed to host: {e}"))
+            .ok()
+    } else if let Some(address) = arg_after("--join") {
+        address
+            .parse()
+            .map_err(|e| format!("invalid address: {e}"))
+            .and_then(|address| Client::join(address, name, &state).map_err(|e| e.to_string()))
+            .map(Session::Client)
+            .inspect_err(|e| eprintln!("failed to join: {e}"))
+            .ok()
+    } else {
+        None
+    };
+
+    let mut region_systems = RegionSystems::new();
+    let region = state.current_region;
+    region_systems.enter(
+        &mut RegionContext {
+            rl: &mut rl,
+            thread: &thread,
+            resources: &mut resources,
+            state: &mut state,
+        },
+        region,
+    );
+
+    let mut lighting = LightingManager::new();
+    let mut minimap = Minimap::new();
+    let mut route_tool = RouteTool::default();
+    let mut blueprint_tool = BlueprintTool::default();
+    let mut demolish_tool = DemolishTool::default();
+    let mut decal_tool = DecalTool::default();
+    let mut capture = Capture::default();
+    let mut console = Console::default();
+    let mut ui = UiStack::default();
+    let mut states = StateStack::default();
+    let mut pause_menu = PauseMenu::default();
+    let mut timestep = FixedTimestep::default();
+    let mut profiler = Profiler::default();
+    let mut route_overlay = RouteOverlay::default();
+    let mut photo_mode = None::<PhotoMode>;
+    let mut autosave = Autosave::default();
+    // What's happened that the console shows
+    let mut game_events = state.events.subscribe::<GameEvent>();
+    if let Some(recovery) = recovery {
+        ui.push(recovery);
+    }
+
+    while !rl.window_should_close() {
+        let input_timer = Timer::start(Section::Input);
+        let mut inputs = bindings.check(&rl);
+        if inputs[EventInput::ToggleProfiler] {
+            profiler.is_visible = !profiler.is_visible;
+        }
+        if inputs[EventInput::ToggleRouteOverlay] {
+            route_overlay.is_visible = !route_overlay.is_visible;
+        }
+        if inputs[EventInput::ExportTrace] {
+            match profiler.export_trace(TRACE_PATH) {
+                Ok(()) => console.print(&format!("saved frame timings to {TRACE_PATH}")),
+                Err(e) => console.print(&format!("failed to save frame timings: {e}")),
+            }
+        }
+
+        let top = states.top();
+        let mut take_photo = false;
+        let mut save_now = false;
+        if inputs[EventInput::ToggleConsole] && top != AppState::MenuOpen {
+            if console.is_open {
+                console.close();
+                states.remove(AppState::Console);
+            } else {
+                console.open(&rl);
+                states.push(AppState::Console);
+            }
+        } else if top == AppState::Console {
+            if !console.update(&mut rl, &mut state) {
+                console.close();
+                states.remove(AppState::Console);
+            }
+        } else if top == AppState::MenuOpen {
+            ui.update(&mut rl, &inputs, &mut state);
+            if !ui.is_open() {
+                states.remove(AppState::MenuOpen);
+            }
+        } else if top == AppState::Paused {
+            match pause_menu.update(&rl) {
+                Some(PauseAction::Resume) => {
+                    states.remove(AppState::Paused);
+                }
+                Some(PauseAction::Save) if matches!(session, Some(Session::Client(_))) => {
+                    pause_menu.notice = Some(tr!("pause.save_client"));
+                }
+                Some(PauseAction::Save) if save_slot.is_none() => {
+                    pause_menu.notice = Some(tr!("pause.save_unavailable"));
+                }
+                Some(PauseAction::Save) => save_now = true,
+                Some(PauseAction::Settings) => {
+                    ui.push(SettingsPanel::new(&rl, Rc::clone(&settings)));
+                }
+                Some(PauseAction::Quit) => break,
+                None => {}
+            }
+        } else if top == AppState::PhotoMode {
+            if inputs[EventInput::TogglePhotoMode] || rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+                photo_mode = None;
+                states.remove(AppState::PhotoMode);
+            } else if let Some(photo_mode) = &mut photo_mode {
+                photo_mode.update(&rl, &inputs, &state.player, &mut capture);
+                // The HUD is hidden, so there's no picture to take with it
+                take_photo =
+                    inputs[EventInput::Screenshot] || inputs[EventInput::ScreenshotWithoutHud];
+            }
+        } else if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            pause_menu.notice = None;
+            states.push(AppState::Paused);
+        } else if inputs[EventInput::TogglePhotoMode] {
+            photo_mode = Some(PhotoMode::new(&state.player));
+            states.push(AppState::PhotoMode);
+        } else if inputs[EventInput::OpenSettings] {
+            ui.push(SettingsPanel::new(&rl, Rc::clone(&settings)));
+        } else if inputs[EventInput::OpenStatistics] {
+            ui.push(StatsPanel::new(&rl, &state));
+        } else if inputs[EventInput::OpenCompendium] {
+            ui.push(CompendiumPanel::new(&rl, &state));
+        } else if inputs[EventInput::Interact]
+            && !route_tool.is_active
+            && !blueprint_tool.is_active()
+            && !demolish_tool.is_active
+            && !decal_tool.is_active()
+        {
+            match state.interaction() {
+                // Stays on if there's nowhere to step off, which the HUD says
+                Some(Interaction::Dismount) => _ = state.dismount(),
+                Some(Interaction::Board(handcar)) => state.board(handcar),
+                Some(Interaction::PeriodicTable) => {
+                    ui.push(PeriodicTablePanel::new(&rl, &state));
+                }
+                Some(Interaction::ContractBoard) => ui.push(ContractPanel::new(&rl, &state)),
+                Some(Interaction::Bench(instrument)) => {
+                    ui.push(MeasuringPanel::new(&rl, &mut state, instrument));
+                }
+                Some(Interaction::Machine(factory, machine)) => {
+                    if let Some(panel) = MachinePanel::new(&rl, &state, factory, machine) {
+                        ui.push(panel);
+                    }
+                }
+                // Nothing to use, so put on whatever's held if it can be worn
+                None => _ = state.player.equip(),
+            }
+        }
+        if ui.is_open() && !states.contains(AppState::MenuOpen) {
+            states.push(AppState::MenuOpen);
+        }
+        states.sync_cursor(&mut rl);
+        if top != AppState::Playing || states.top() != AppState::Playing {
+            // Something else has focus, or just took it; don't walk around or look while
+            // using it, or also do anything else with the input that opened it
+            inputs = Inputs::default();
+        }
+
+        if *settings.borrow() != applied_settings {
+            applied_settings = settings.borrow().clone();
+            applied_settings.apply(&mut rl, &mut bindings, &mut state.player, audio.as_mut());
+        }
+
+        let landing = state.player.do_movement(
+            &mut rl,
+            &thread,
+            &inputs,
+            state
+                .current_region
+                .to_region(&state.factories, &state.lab, &state.world),
+        );
+        if let Some(damage) = landing {
+            state.hurt(damage);
+        }
+        state.drive(inputs[AxisInput::Throttle], inputs[EventInput::Brake]);
+
+        profiler.stop(input_timer);
+
+        let simulation_timer = Timer::start(Section::Simulation);
+        minimap.update(&inputs, rl.get_frame_time());
+        if !states.is_paused(session.is_some()) {
+            for _ in 0..timestep.advance(rl.get_frame_time()) {
+                state.tick(FixedTimestep::STEP);
+            }
+            for event in state.events.read(&mut game_events) {
+                match event {
+                    GameEvent::ScriptOutput { factory, text } => {
+                        console.print(&format!("[factory {}] {text}", factory + 1));
+                    }
+                    GameEvent::MilestoneReached(id) => {
+                        console.print(&tr!("hud.milestone", name = id.milestone().title()));
+                    }
+                    GameEvent::Discovered(item) => {
+                        console.print(&tr!("hud.discovered", name = item));
+                    }
+                    GameEvent::VesselRuptured { factory, .. } => {
+                        console.print(&tr!("hud.vessel_ruptured", factory = factory + 1));
+                    }
+                }
+            }
+            // Clients' worlds are the host's, so only the host saves them
+            save_now |=
+                !matches!(session, Some(Session::Client(_))) && autosave.due(rl.get_frame_time());
+        }
+
+        state.update_region();
+        {
+            let mut ctx = RegionContext {
+                rl: &mut rl,
+                thread: &thread,
+                resources: &mut resources,
+                state: &mut state,
+            };
+            region_systems.dispatch(&mut ctx);
+            region_systems.tick(&mut ctx);
+        }
+        for e in resources.poll_hot_reload(&mut rl, &thread) {
+            eprintln!("failed to reload asset: {e}");
+        }
+
+        // Scrolling picks what to paint or how far apart to repeat blueprints instead of the
+        // hotbar slot while using those tools
+        decal_tool.scroll(&mut inputs);
+        blueprint_tool.scroll(&mut inputs);
+        let hotbar_slot = state.player.hotbar_slot;
+        state.player.do_actions(
+            &mut rl,
+            &thread,
+            &inputs,
+            state.current_region.to_mut_region(
+                &mut state.factories,
+                &mut state.lab,
+                &mut state.world,
+            ),
+        );
+
+        // Only one tool at a time, since they're all used by interacting
+        if inputs[EventInput::ToggleRouteTool] {
+            blueprint_tool.close();
+            demolish_tool.close();
+            decal_tool.close();
+        }
+        if inputs[EventInput::ToggleBlueprintTool] {
+            route_tool.is_active = false;
+            route_tool.cancel();
+            demolish_tool.close();
+            decal_tool.close();
+        }
+        if inputs[EventInput::ToggleDemolishTool] {
+            route_tool.is_active = false;
+            route_tool.cancel();
+            blueprint_tool.close();
+            decal_tool.close();
+        }
+        if inputs[EventInput::ToggleDecalTool] {
+            route_tool.is_active = false;
+            route_tool.cancel();
+            blueprint_tool.close();
+            demolish_tool.close();
+        }
+        let edited = if inputs[EventInput::Undo] {
+            Some((state.undo(), "hud.nothing_to_undo", "hud.undo_failed"))
+        } else if inputs[EventInput::Redo] {
+            Some((state.redo(), "hud.nothing_to_redo", "hud.redo_failed"))
+        } else {
+            None
+        };
+        match edited {
+            Some((Ok(true), ..)) => {
+                // What the tools worked out about the factory may not be true anymore
+                route_tool.recheck();
+                blueprint_tool.recheck();
+            }
+            Some((Ok(false), nothing, _)) => console.print(&tr!(nothing)),
+            Some((Err(e), _, failed)) => console.print(&tr!(failed, error = e)),
+            None => {}
+        }
+        route_tool.update(&inputs, &mut state);
+        blueprint_tool.update(&inputs, &mut state);
+        demolish_tool.update(&inputs, &mut state);
+        decal_tool.update(&inputs, &mut state);
+        let snap = if decal_tool.is_active() {
+            Some(state.player.snap)
+        } else {
+            blueprint_tool.snap()
+        };
+        if let RegionId::Factory(idx) = state.current_region
+            && let Some(grid) = &mut state.factories[idx].grid
+        {
+            grid.subdivisions = snap.unwrap_or_default().lines_per_meter();
+            grid.footprint = blueprint_tool.footprint(state.clipboard.as_ref());
+        }
+
+        if let Some(net) = &mut session
+            && let Err(e) = net.update(&mut state)
+        {
+            eprintln!("left the game: {e}");
+            session = None;
+        }
+
+        if let Some(audio) = &mut audio {
+            if state.player.hotbar_slot != hotbar_slot {
+                audio.play_ui(UiSound::Click);
+            }
+            for factory in &state.factories {
+                audio.emit_factory(factory, &state.player);
+            }
+            audio.update(&state.player);
+        }
+
+        lighting.set_sky(
+            (state.current_region == RegionId::Rail).then(|| state.weather.sky(&state.clock)),
+        );
+        if let RegionId::Factory(idx) = state.current_region {
+            lighting.attach(state.factories[idx].light_sources());
+        }
+        // Photo mode looks from somewhere else, but everything is still drawn relative to the
+        // player
+        let camera = (photo_mode.as_ref()).map_or(state.player.camera, |photo_mode| {
+            photo_mode.camera.camera(state.player.position)
+        });
+        let show_body = photo_mode.is_some() && state.player.camera_mode == CameraMode::FirstPerson;
+        lighting.apply(resources.lit_shaders_mut(), &state.player, &camera);
+        profiler.stop(simulation_timer);
+
+        let render_timer = Timer::start(Section::Render);
+        #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+        let screen = Vector2::new(rl.get_screen_width() as f32, rl.get_screen_height() as f32);
+        if let Err(e) = minimap.render(&mut rl, &thread, &state, screen) {
+            eprintln!("failed to render map: {e}");
+        }
+
+        let hud = || {
+            let mut hud = Hud::new(&state, screen);
+            if let Some(prompt) = route_tool.prompt() {
+                hud.prompt = Some(prompt);
+            }
+            if let Some(prompt) = blueprint_tool.prompt() {
+                hud.prompt = Some(prompt);
+            }
+            if let Some(prompt) = demolish_tool.prompt() {
+                hud.prompt = Some(prompt);
+            }
+            if let Some(prompt) = decal_tool.prompt() {
+                hud.prompt = Some(prompt);
+            }
+            if let Some(snap) = snap {
+                hud.snap = Some(tr!("hud.snap", mode = snap.name()));
+            }
+            hud
+        };
+
+        if take_photo || inputs[EventInput::Screenshot] || inputs[EventInput::ScreenshotWithoutHud]
+        {
+            let with_hud = inputs[EventInput::Screenshot];
+            let path = capture::next_screenshot_path();
+            #[allow(clippy::cast_precision_loss, reason = "scales are small")]
+            let scale = capture.scale as f32;
+            let size = capture.size(screen);
+            #[allow(clippy::cast_precision_loss, reason = "screenshots are small")]
+            let haze_size = Vector2::new(size.0 as f32, size.1 as f32);
+            let taken = capture::take(&mut rl, &thread, size, &path, |d| {
+                d.clear_background(Color::BLACK);
+                {
+                    let mut d = d.begin_mode3D(camera);
+                    let (player, region) = (&state.player, state.current_region);
+                    draw_world(
+                        &mut d,
+                        &thread,
+                        &resources,
+                        &state,
+                        player,
+                        region,
+                        session.as_ref(),
+                    );
+                    if show_body {
+                        Player::draw_body(&mut d);
+                    }
+                    if with_hud {
+                        route_overlay.draw(&mut d, &state);
+                        route_tool.draw(&mut d, &state);
+                        blueprint_tool.draw(&mut d, &resources, &state);
+                        demolish_tool.draw(&mut d, &state);
+                        decal_tool.draw(&mut d, &state);
+                    }
+                }
+                draw_haze(d, &state, haze_size);
+                if with_hud {
+                    // Drawn at the window's size and scaled up, so it looks the same as on screen
+                    let options = *RenderingOptions::new().scale(scale);
+                    // Can't fail when drawing directly to a texture
+                    _ = hud().draw(&mut Renderer::new(d, options).with_font(&font));
+                }
+            });
+            match taken {
+                Ok(()) => console.print(&format!("saved screenshot to {}", path.display())),
+                Err(e) => console.print(&format!("failed to take screenshot: {e}")),
+            }
+        }
+
+        if save_now && let Some(slot) = &save_slot {
+            autosave.reset();
+            let saved = slot.save(&SaveData::new(&state));
+            let thumbnail = capture::render(&mut rl, &thread, THUMBNAIL_SIZE, |d| {
+                d.clear_background(Color::BLACK);
+                let mut d = d.begin_mode3D(state.player.camera);
+                let (player, region) = (&state.player, state.current_region);
+                draw_world(&mut d, &thread, &resources, &state, player, region, None);
+            });
+            match thumbnail.map(|image| slot.save_thumbnail(&image)) {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => eprintln!("failed to save thumbnail: {e}"),
+                Err(e) => eprintln!("failed to render thumbnail: {e}"),
+            }
+            let notice = match &saved {
+                Ok(()) => tr!("pause.saved"),
+                Err(e) => tr!("pause.save_failed", error = e),
+            };
+            if states.contains(AppState::Paused) {
+                pause_menu.notice = Some(notice);
+            } else if saved.is_err() {
+                console.print(&notice);
+            }
+        }
+
+        if inputs[EventInput::ToggleTimelapse] {
+            let stopped = capture
+                .timelapse
+                .as_ref()
+                .map(|timelapse| timelapse.factory);
+            if let Some(idx) = stopped {
+                resources.release_all(state.factories[idx].assets());
+                console.print(&format!(
+                    "stopped timelapse, frames are in {}",
+                    capture::TIMELAPSE_DIR
+                ));
+            }
+            let factory = match state.current_region {
+                RegionId::Factory(idx) => Some(idx),
+                _ => None,
+            };
+            if capture.toggle_timelapse(factory, &state.clock)
+                && let Some(idx) = factory
+            {
+                // Keep the factory's assets loaded even after leaving it
+                match resources.acquire_all(&mut rl, &thread, state.factories[idx].assets()) {
+                    Ok(()) => console.print("started timelapse of this factory"),
+                    Err(e) => {
+                        capture.timelapse = None;
+                        console.print(&format!("failed to start timelapse: {e}"));
+                    }
+                }
+            } else if stopped.is_none() {
+                console.print("go into a factory to start a timelapse");
+            }
+        }
+
+        if let Some(timelapse) = &mut capture.timelapse
+            && let Some(path) = timelapse.due(&state.clock)
+        {
+            let idx = timelapse.factory;
+            let mut viewer = state.player.clone();
+            viewer.camera = capture::overview_camera(&state.factories[idx], &viewer);
+            let region = RegionId::Factory(idx);
+            let taken = capture::take(&mut rl, &thread, capture.size(screen), &path, |d| {
+                d.clear_background(Color::BLACK);
+                let mut d = d.begin_mode3D(viewer.camera);
+                draw_world(&mut d, &thread, &resources, &state, &viewer, region, None);
+            });
+            if let Err(e) = taken {
+                console.print(&format!("failed to save timelapse frame: {e}"));
+            }
+        }
+
+        let mut d = rl.begin_drawing(&thread);
+        d.clear_background(Color::BLACK);
+
+        {
+            let mut d = d.begin_mode3D(camera);
+            let (player, region) = (&state.player, state.current_region);
+            draw_world(
+                &mut d,
+                &thread,
+                &resources,
+                &state,
+                player,
+                region,
+                session.as_ref(),
+            );
+            if show_body {
+                Player::draw_body(&mut d);
+            }
+            if photo_mode.is_none() {
+                route_overlay.draw(&mut d, &state);
+                route_tool.draw(&mut d, &state);
+                blueprint_tool.draw(&mut d, &resources, &state);
+                demolish_tool.draw(&mut d, &state);
+                decal_tool.draw(&mut d, &state);
+            }
+        }
+        draw_haze(&mut d, &state, screen);
+
+        if let Some(photo_mode) = &photo_mode {
+            // Can't fail when drawing directly to the screen
+            _ = photo_mode
+                .draw(&mut Renderer::new(&mut d, RenderingOptions::new()).with_font(&font));
+        } else {
+            let player = &state.player;
+            d.draw_fps(0, 0);
+            d.draw_text_ex(
+                &font,
+                &format!(
+                    "player position: ({:.3}, {:.3}, {:.3})\n\
+                    player velocity: ({:.3}, {:.3}, {:.3})\n\
+                    player direction: ({:.3}, {:.3})",
+                    player.position.x,
+                    player.position.y,
+                    player.position.z,
+                    player.velocity.x,
+                    player.velocity.y,
+                    player.velocity.z,
+                    player.yaw,
+                    player.pitch,
+                ),
+                Vector2::new(0.0, 20.0),
+                20.0,
+                0.0,
+                Color::MAGENTA,
+            );
+
+            // Can't fail when drawing directly to the screen
+            _ = hud().draw(&mut Renderer::new(&mut d, RenderingOptions::new()).with_font(&font));
+            minimap.draw(&mut d, screen);
+        }
+
+        if states.contains(AppState::Paused) {
+            // Can't fail when drawing directly to the screen
+            _ = pause_menu
+                .draw(&mut Renderer::new(&mut d, RenderingOptions::new()).with_font(&font));
+        }
+
+        // Can't fail when drawing directly to the screen
+        _ = ui.draw(&mut Renderer::new(&mut d, RenderingOptions::new()).with_font(&font));
+
+        if console.is_open {
+            // Can't fail when drawing directly to the screen
+            _ = console.draw(&mut Renderer::new(&mut d, RenderingOptions::new()).with_font(&font));
+        }
+
+        profiler.screen = screen;
+        // Can't fail when drawing directly to the screen
+        _ = profiler.draw(&mut Renderer::new(&mut d, RenderingOptions::new()).with_font(&font));
+
+        // Finish drawing, which waits for the next frame
+        drop(d);
+        profiler.stop(render_timer);
+        profiler.end_frame();
+    }
+}
@@ -0,0 +1,366 @@
+//! Dynamic lights shared by every lit shader
+//!
+//! The lighting shader has [`MAX_LIGHTS`] slots. Lights added to the manager keep their slot until
+//! they're removed, and each frame the slots left over go to the lights attached to machines that
+//! are nearest the player.
+
+use crate::{
+    math::coords::{PlayerCoord, PlayerVector3},
+    player::Player,
+    rlights::{LightType, MAX_LIGHTS},
+};
+use raylib::prelude::*;
+use std::{collections::BTreeMap, f32::consts::TAU};
+
+/// Ambient light in the factory and lab, which have no windows
+const INDOOR_AMBIENT: Color = Color::new(51, 51, 51, 255);
+/// Ambient light outside at noon
+const DAY_AMBIENT: Color = Color::new(64, 64, 72, 255);
+/// Ambient light outside at midnight
+const NIGHT_AMBIENT: Color = Color::new(10, 12, 24, 255);
+const SUN_COLOR: Color = Color::WHITE;
+const SUNSET_COLOR: Color = Color::new(255, 150, 80, 255);
+const MOON_COLOR: Color = Color::new(40, 50, 80, 255);
+/// Multiplies the skybox at midnight, which is drawn as is at noon
+const NIGHT_SKY_TINT: Color = Color::new(20, 24, 48, 255);
+/// Multiplies the light and skybox under full cloud cover
+const OVERCAST_TINT: Color = Color::new(110, 112, 124, 255);
+/// Multiplies the ambient light when everything outside is soaked, for a cool, dark sheen
+const WET_TINT: Color = Color::new(190, 205, 240, 255);
+/// How far away directional lights are placed, since the shader wants a position
+const DIRECTIONAL_DISTANCE: f32 = 50.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    /// Shines from infinitely far away, towards `direction`
+    Directional { direction: Vector3 },
+    /// Shines outwards from `position`
+    Point { position: PlayerVector3 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightSource {
+    pub kind: LightKind,
+    pub color: Color,
+    /// Multiplies the color, usually in `[0, 1]`
+    pub intensity: f32,
+}
+
+/// Identifies a light added with [`LightingManager::add`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LightId(usize);
+
+/// What the sky looks like at some time of day
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sky {
+    /// Direction the sun (or moon) shines in
+    pub direction: Vector3,
+    pub light: Color,
+    pub ambient: Color,
+    /// Multiplies the skybox
+    pub tint: Color,
+}
+
+impl Sky {
+    /// The sky `hours` after midnight
+    ///
+    /// The sun rises in the east at 6:00 and sets in the west at 18:00. At night the moon gives
+    /// a dim blue light from the opposite side.
+    pub fn at(hours: f32) -> Self {
+        let angle = (hours - 6.0) / 24.0 * TAU;
+        let (elevation, east) = angle.sin_cos();
+        // Tilted south a little so the sun is never exactly overhead
+        let towards_sun = Vector3::new(east, elevation, 0.3).normalize();
+        let daylight = (elevation * 4.0).clamp(0.0, 1.0);
+        let (towards_light, light) = if elevation >= 0.0 {
+            (towards_sun, lerp_color(SUNSET_COLOR, SUN_COLOR, daylight))
+        } else {
+            (-towards_sun, MOON_COLOR)
+        };
+        Self {
+            direction: -towards_light,
+            light,
+            ambient: lerp_color(
+                NIGHT_AMBIENT,
+                DAY_AMBIENT,
+                (elevation + 0.25).clamp(0.0, 1.0),
+            ),
+            tint: lerp_color(
+                NIGHT_SKY_TINT,
+                Color::WHITE,
+                (elevation * 4.0 + 0.5).clamp(0.0, 1.0),
+            ),
+        }
+    }
+
+    /// The same sky under `cover` of clouds, from 0 for clear to 1 for fully overcast
+    pub fn overcast(self, cover: f32) -> Self {
+        Self {
+            light: lerp_color(self.light, multiply_color(self.light, OVERCAST_TINT), cover),
+            tint: lerp_color(self.tint, multiply_color(self.tint, OVERCAST_TINT), cover),
+            ..self
+        }
+    }
+
+    /// The same sky over ground `wetness` wet, from 0 for dry to 1 for soaked
+    pub fn wet(self, wetness: f32) -> Self {
+        Self {
+            ambient: lerp_color(
+                self.ambient,
+                multiply_color(self.ambient, WET_TINT),
+                wetness,
+            ),
+            ..self
+        }
+    }
+}
+
+/// The color `amount` of the way from `from` to `to`
+pub fn lerp_color(from: Color, to: Color, amount: f32) -> Color {
+    let [from, to] = [from, to].map(|color| {
+        Vector4::new(
+            color.r.into(),
+            color.g.into(),
+            color.b.into(),
+            color.a.into(),
+        )
+    });
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "interpolating between bytes stays within a byte"
+    )]
+    let [red, green, blue, alpha] = from
+        .lerp(to, amount)
+        .round()
+        .to_array()
+        .map(|channel| channel as u8);
+    Color::new(red, green, blue, alpha)
+}
+
+/// `a` filtered through `b`, channel by channel
+fn multiply_color(a: Color, b: Color) -> Color {
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "the product of two bytes over 255 stays within a byte"
+    )]
+    let channel = |a: u8, b: u8| (u16::from(a) * u16::from(b) / 255) as u8;
+    Color::new(
+        channel(a.r, b.r),
+        channel(a.g, b.g),
+        channel(a.b, b.b),
+        channel(a.a, b.a),
+    )
+}
+
+const fn color_vec4(color: Color, intensity: f32) -> Vector4 {
+    Vector4::new(
+        color.r as f32 / 255.0 * intensity,
+        color.g as f32 / 255.0 * intensity,
+        color.b as f32 / 255.0 * intensity,
+        color.a as f32 / 255.0,
+    )
+}
+
+/// Uniform locations of one lighting shader
+#[derive(Debug)]
+struct ShaderLocations {
+    ambient: i32,
+    view_pos: i32,
+    /// `enabled`, `type`, `position`, `target`, and `color` of each slot
+    lights: [[i32; 5]; MAX_LIGHTS],
+}
+
+impl ShaderLocations {
+    fn new(shader: &WeakShader) -> Self {
+        Self {
+            ambient: shader.get_shader_location("ambient"),
+            view_pos: shader.get_shader_location("viewPos"),
+            lights: std::array::from_fn(|i| {
+                ["enabled", "type", "position", "target", "color"]
+                    .map(|field| shader.get_shader_location(&format!("lights[{i}].{field}")))
+            }),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LightingManager {
+    lights: [Option<LightSource>; MAX_LIGHTS],
+    /// Lights for this frame only, like the ones attached to machines
+    attached: Vec<LightSource>,
+    pub ambient: Color,
+    sun: LightId,
+    /// By shader id
+    locations: BTreeMap<u32, ShaderLocations>,
+}
+
+impl Default for LightingManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LightingManager {
+    /// Indoor lighting, with the sun shining straight down
+    pub const fn new() -> Self {
+        let mut lights = [None; MAX_LIGHTS];
+        lights[0] = Some(LightSource {
+            kind: LightKind::Directional {
+                direction: Vector3::NEG_Y,
+            },
+            color: Color::WHITE,
+            intensity: 1.0,
+        });
+        Self {
+            lights,
+            attached: Vec::new(),
+            ambient: INDOOR_AMBIENT,
+            sun: LightId(0),
+            locations: BTreeMap::new(),
+        }
+    }
+
+    /// Give `light` a slot until it's removed, or [`None`] if every slot is taken
+    pub fn add(&mut self, light: LightSource) -> Option<LightId> {
+        let slot = self.lights.iter().position(Option::is_none)?;
+        self.lights[slot] = Some(light);
+        Some(LightId(slot))
+    }
+
+    pub const fn remove(&mut self, id: LightId) -> Option<LightSource> {
+        self.lights[id.0].take()
+    }
+
+    pub const fn get_mut(&mut self, id: LightId) -> Option<&mut LightSource> {
+        self.lights[id.0].as_mut()
+    }
+
+    /// Light the scene this frame with `lights`, if there are slots left for them
+    pub fn attach(&mut self, lights: impl IntoIterator<Item = LightSource>) {
+        self.attached.extend(lights);
+    }
+
+    /// Light the scene as outside under `sky`, or as indoors if [`None`]
+    pub const fn set_sky(&mut self, sky: Option<Sky>) {
+        let (direction, color, ambient) = match sky {
+            Some(sky) => (sky.direction, sky.light, sky.ambient),
+            None => (Vector3::NEG_Y, Color::WHITE, INDOOR_AMBIENT),
+        };
+        self.ambient = ambient;
+        if let Some(sun) = self.get_mut(self.sun) {
+            sun.kind = LightKind::Directional { direction };
+            sun.color = color;
+        }
+    }
+
+    /// The added lights in their slots, with the free slots filled by the attached lights nearest
+    /// `player_pos`
+    fn slots(&self, player_pos: &PlayerVector3) -> [Option<LightSource>; MAX_LIGHTS] {
+        let distance = |light: &LightSource| match light.kind {
+            LightKind::Directional { .. } => PlayerCoord::ZERO,
+            LightKind::Point { position } => position.distance_sqr(*player_pos),
+        };
+        let mut attached = self.attached.clone();
+        attached.sort_by_key(distance);
+        let mut attached = attached.into_iter();
+        let mut slots = self.lights;
+        for slot in slots.iter_mut().filter(|slot| slot.is_none()) {
+            *slot = attached.next();
+        }
+        slots
+    }
+
+    /// Send the lights to every shader in `shaders`, for a scene drawn relative to `player` and
+    /// seen through `camera`, then forget the attached lights
+    ///
+    /// Should be called once per frame, after everything has attached its lights.
+    pub fn apply<'a>(
+        &mut self,
+        shaders: impl IntoIterator<Item = &'a mut WeakShader>,
+        player: &Player,
+        camera: &Camera3D,
+    ) {
+        let slots = self.slots(&player.position);
+        for shader in shaders {
+            let locations = self
+                .locations
+                .entry(shader.id)
+                .or_insert_with(|| ShaderLocations::new(shader));
+            shader.set_shader_value(locations.ambient, color_vec4(self.ambient, 1.0));
+            shader.set_shader_value(locations.view_pos, camera.position);
+            for (slot, [enabled, ty, position, target, color]) in slots.iter().zip(locations.lights)
+            {
+                shader.set_shader_value(enabled, i32::from(slot.is_some()));
+                let Some(light) = slot else {
+                    continue;
+                };
+                // Rendering is relative to the player
+                let (light_type, from, to) = match light.kind {
+                    LightKind::Directional { direction } => (
+                        LightType::Directional,
+                        -direction * DIRECTIONAL_DISTANCE,
+                        Vector3::ZERO,
+                    ),
+                    LightKind::Point { position } => {
+                        let position = position.minus(player.position).to_vec3();
+                        (LightType::Point, position, position)
+                    }
+                };
+                shader.set_shader_value(ty, light_type as i32);
+                shader.set_shader_value(position, from);
+                shader.set_shader_value(target, to);
+                shader.set_shader_value(color, color_vec4(light.color, light.intensity));
+            }
+        }
+        self.attached.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::coords::VectorConstants;
+
+    #[test]
+    fn test_sky() {
+        let noon = Sky::at(12.0);
+        let midnight = Sky::at(0.0);
+        assert!(noon.direction.y < 0.0, "sun should shine down at noon");
+        assert!(
+            midnight.direction.y < 0.0,
+            "moon should shine down at midnight"
+        );
+        assert_eq!(noon.light, SUN_COLOR);
+        assert_eq!(midnight.light, MOON_COLOR);
+        assert!(noon.ambient.r > midnight.ambient.r);
+        let stormy = noon.overcast(1.0).wet(1.0);
+        assert!(stormy.light.r < noon.light.r && stormy.tint.g < noon.tint.g);
+        assert!(stormy.ambient.r < noon.ambient.r && stormy.ambient.b > stormy.ambient.r);
+        assert_eq!(noon.overcast(0.0).wet(0.0), noon);
+    }
+
+    #[test]
+    fn test_slots() {
+        let mut lighting = LightingManager::new();
+        let lamp = |x: i32| LightSource {
+            kind: LightKind::Point {
+                position: PlayerVector3::from_i32(x, 0, 0),
+            },
+            color: Color::ORANGE,
+            intensity: 1.0,
+        };
+        let count = i32::try_from(MAX_LIGHTS).unwrap();
+        let added = lighting.add(lamp(1000)).unwrap();
+        lighting.attach((0..count).rev().map(lamp));
+        let slots = lighting.slots(&PlayerVector3::ZERO);
+        assert!(matches!(
+            slots[0].unwrap().kind,
+            LightKind::Directional { .. }
+        ));
+        assert_eq!(slots[added.0], Some(lamp(1000)));
+        // The two farthest attached lights don't fit
+        assert_eq!(slots[2], Some(lamp(0)));
+        assert_eq!(slots[MAX_LIGHTS - 1], Some(lamp(count - 3)));
+    }
+}
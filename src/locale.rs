@@ -0,0 +1,288 @@
+//! Text shown to the player, in their language
+//!
+//! Strings are looked up with [`tr!`] by stable keys like `hud.open`, in a [`StringTable`]
+//! loaded from [`LANG_DIR`]. Anything missing from the player's language falls back to English,
+//! which is built into the game, and anything missing from that shows its key so it stands out.
+//!
+//! Things that already have an English name in the code, like elements and inputs, aren't in
+//! the English table. They're looked up with [`tr_or`] and fall back to that name instead.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+    sync::{LazyLock, PoisonError, RwLock},
+};
+
+/// Where language files are loaded from, each named for its language like `de.lang`
+pub const LANG_DIR: &str = "assets/lang";
+
+/// The language built into the game, which every other falls back to
+pub const ENGLISH: &str = "en";
+
+/// The English table, built in so there's always something to show
+const ENGLISH_TABLE: &str = include_str!("../assets/lang/en.lang");
+
+/// The language being shown
+static LOCALE: LazyLock<RwLock<Locale>> = LazyLock::new(|| RwLock::new(Locale::english()));
+
+/// A line of a language file that couldn't be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseStringTableError {
+    /// Counting from 1
+    pub line: usize,
+}
+
+impl std::fmt::Display for ParseStringTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: expected `key = text`", self.line)
+    }
+}
+
+impl std::error::Error for ParseStringTableError {}
+
+#[derive(Debug)]
+pub enum LocaleError {
+    /// The language file couldn't be read
+    Io { path: PathBuf, error: io::Error },
+    /// The language file isn't a [`StringTable`]
+    Parse {
+        path: PathBuf,
+        error: ParseStringTableError,
+    },
+}
+
+impl std::fmt::Display for LocaleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "couldn't read {}: {error}", path.display()),
+            Self::Parse { path, error } => write!(f, "{}: {error}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for LocaleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { error, .. } => Some(error),
+            Self::Parse { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Text for each key in one language
+///
+/// Language files have a `key = text` line for each string. Lines starting with `#` are
+/// comments, and `\n` in the text is a line break. Text can have `{name}` placeholders, which
+/// [`tr!`] fills in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringTable {
+    strings: HashMap<String, String>,
+}
+
+impl std::str::FromStr for StringTable {
+    type Err = ParseStringTableError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut strings = HashMap::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, text) = (line.split_once('='))
+                .filter(|(key, _)| !key.trim().is_empty())
+                .ok_or(ParseStringTableError { line: i + 1 })?;
+            strings.insert(key.trim().to_string(), text.trim().replace("\\n", "\n"));
+        }
+        Ok(Self { strings })
+    }
+}
+
+impl StringTable {
+    /// The text for `key`, if the table has it
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(String::as_str)
+    }
+}
+
+/// A language, falling back to English for anything it's missing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    language: String,
+    strings: StringTable,
+    english: StringTable,
+}
+
+impl Locale {
+    /// The built in English table
+    pub fn english() -> Self {
+        let english = ENGLISH_TABLE
+            .parse::<StringTable>()
+            .expect("the English table should be valid");
+        Self {
+            language: ENGLISH.to_string(),
+            strings: StringTable::default(),
+            english,
+        }
+    }
+
+    /// `language`, falling back to English
+    pub fn new(language: &str, strings: StringTable) -> Self {
+        Self {
+            language: language.to_string(),
+            strings,
+            ..Self::english()
+        }
+    }
+
+    /// Read `language`'s file from [`LANG_DIR`]
+    pub fn load(language: &str) -> Result<Self, LocaleError> {
+        if language == ENGLISH {
+            return Ok(Self::english());
+        }
+        let path = Path::new(LANG_DIR).join(format!("{language}.lang"));
+        let text = std::fs::read_to_string(&path).map_err(|error| LocaleError::Io {
+            path: path.clone(),
+            error,
+        })?;
+        let strings = text
+            .parse()
+            .map_err(|error| LocaleError::Parse { path, error })?;
+        Ok(Self::new(language, strings))
+    }
+
+    /// The code of the language, like `en`
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// The text for `key`, or `key` itself if there's no text for it even in English
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.get_or(key, self.english.get(key).unwrap_or(key))
+    }
+
+    /// The text for `key` in this language, or `english` if it isn't translated
+    pub fn get_or<'a>(&'a self, key: &str, english: &'a str) -> &'a str {
+        self.strings.get(key).unwrap_or(english)
+    }
+}
+
+/// Show text in `language` from now on
+///
+/// Keeps the language already being shown if `language` can't be loaded.
+pub fn set_language(language: &str) -> Result<(), LocaleError> {
+    let locale = Locale::load(language)?;
+    *LOCALE.write().unwrap_or_else(PoisonError::into_inner) = locale;
+    Ok(())
+}
+
+/// The code of the language being shown
+pub fn language() -> String {
+    let locale = LOCALE.read().unwrap_or_else(PoisonError::into_inner);
+    locale.language().to_string()
+}
+
+/// Codes of every language there's a file for, and English, sorted
+pub fn available() -> Vec<String> {
+    let files = std::fs::read_dir(LANG_DIR).into_iter().flatten().flatten();
+    let mut languages = (files.map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lang"))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .chain([ENGLISH.to_string()])
+        .collect::<Vec<_>>();
+    languages.sort();
+    languages.dedup();
+    languages
+}
+
+/// Every character the font is loaded with
+///
+/// Printable ASCII and the Latin-1 Supplement and Latin Extended-A blocks, which covers most
+/// languages written in the Latin alphabet.
+pub fn glyphs() -> String {
+    (' '..='~').chain('\u{a0}'..='\u{17f}').collect()
+}
+
+/// The text for `key` in the language being shown, see [`Locale::get`]
+///
+/// Prefer [`tr!`], which can also fill in placeholders.
+pub fn lookup(key: &str) -> String {
+    let locale = LOCALE.read().unwrap_or_else(PoisonError::into_inner);
+    locale.get(key).to_string()
+}
+
+/// The text for `key` in the language being shown, or `english` if it isn't translated
+pub fn tr_or(key: &str, english: &str) -> String {
+    let locale = LOCALE.read().unwrap_or_else(PoisonError::into_inner);
+    locale.get_or(key, english).to_string()
+}
+
+/// Replace each `{name}` in `text` with its value in `args`
+///
+/// Placeholders without a value are left as they are.
+pub fn fill(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = text.to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    text
+}
+
+/// The text for a key in the language being shown, with any `name = value` placeholders
+/// filled in
+///
+/// ```ignore
+/// let prompt = tr!("hud.open", name = machine.name());
+/// ```
+macro_rules! tr {
+    ($key:expr $(,)?) => {
+        $crate::locale::lookup($key)
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::locale::fill(
+            &$crate::locale::lookup($key),
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+pub(crate) use tr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback() {
+        let strings = "# A partial translation\n\
+            \n\
+            hud.open = {name} öffnen (E)\n\
+            element.H = Wasserstoff\n"
+            .parse::<StringTable>()
+            .unwrap();
+        let locale = Locale::new("de", strings);
+        assert_eq!(locale.get("hud.open"), "{name} öffnen (E)");
+        assert_eq!(
+            locale.get("machine.reactor"),
+            "Reactor",
+            "missing strings fall back to English"
+        );
+        assert_eq!(locale.get("no.such.key"), "no.such.key");
+        assert_eq!(locale.get_or("element.H", "Hydrogen"), "Wasserstoff");
+        assert_eq!(locale.get_or("element.He", "Helium"), "Helium");
+
+        assert_eq!(
+            fill(locale.get("hud.open"), &[("name", &"Reaktor")]),
+            "Reaktor öffnen (E)"
+        );
+        assert_eq!(
+            "oops".parse::<StringTable>(),
+            Err(ParseStringTableError { line: 1 })
+        );
+        assert_eq!(
+            "a = 1\\n2".parse::<StringTable>().unwrap().get("a"),
+            Some("1\n2")
+        );
+    }
+}
@@ -2,13 +2,16 @@ use raylib::prelude::*;
 
 use crate::math::coords::{PlayerCoord, PlayerVector3, lab::LabCoord};
 
-use super::coords::{FactoryVector3, LabVector3};
+use super::coords::{FactoryVector3, LabVector3, VectorConstants};
 
 #[const_trait]
-pub trait SpacialBounds {
+pub trait SpacialBounds: Sized {
     /// The vector space type of this bounding box
     type Vector;
 
+    /// The type of [`Self::volume`]
+    type Volume;
+
     /// The minimum coordinate in `self`
     #[must_use]
     fn min(&self) -> Self::Vector;
@@ -32,6 +35,29 @@ pub trait SpacialBounds {
     /// Check if `self` and `other` are colliding
     #[must_use]
     fn overlaps(&self, other: &Self) -> bool;
+
+    /// The space both `self` and `other` cover, or [`None`] if they don't [overlap](Self::overlaps)
+    ///
+    /// Boxes that only touch have an intersection with no volume.
+    #[must_use]
+    fn intersection(&self, other: &Self) -> Option<Self>;
+
+    /// The smallest box covering both `self` and `other`
+    #[must_use]
+    fn union(&self, other: &Self) -> Self;
+
+    /// `self` grown by `margin` on every side, or shrunk if `margin` is negative
+    #[must_use]
+    fn expand(&self, margin: Self::Vector) -> Self;
+
+    /// The product of the components of [`Self::size`]
+    #[must_use]
+    fn volume(&self) -> Self::Volume;
+
+    /// Every corner of `self`, with bits 0, 1, and 2 of the index choosing [`Self::max`]'s x, y,
+    /// and z over [`Self::min`]'s
+    #[must_use]
+    fn corners(&self) -> [Self::Vector; 8];
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -40,6 +66,19 @@ pub struct FactoryBounds {
     pub max: FactoryVector3,
 }
 
+impl FactoryBounds {
+    /// Every block in `self`, counting `min` but not `max` like the space a machine takes up
+    ///
+    /// Goes along x, then z, then y, so each layer is finished before the one above it.
+    pub fn cells(&self) -> impl Iterator<Item = FactoryVector3> + use<> {
+        let Self { min, max } = *self;
+        (min.y..max.y).flat_map(move |y| {
+            (min.z..max.z)
+                .flat_map(move |z| (min.x..max.x).map(move |x| FactoryVector3 { x, y, z }))
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct LabBounds {
     pub min: LabVector3,
@@ -52,8 +91,80 @@ pub struct PlayerBounds {
     pub max: PlayerVector3,
 }
 
+impl PlayerBounds {
+    /// How far `self` can move along `motion` without going into any of `obstacles`, sliding
+    /// along whatever it hits
+    ///
+    /// Moves along y, then x, then z, stopping each flush against the nearest obstacle in the way.
+    /// Obstacles `self` is already inside of are ignored so it can always get back out.
+    #[must_use]
+    pub fn sweep(&self, motion: PlayerVector3, obstacles: &[PlayerBounds]) -> PlayerVector3 {
+        /// Components of a vector along `axis`, then the other two
+        const fn axes(v: PlayerVector3, axis: usize) -> [PlayerCoord; 3] {
+            match axis {
+                0 => [v.x, v.y, v.z],
+                1 => [v.y, v.z, v.x],
+                _ => [v.z, v.x, v.y],
+            }
+        }
+
+        let mut moving = *self;
+        let mut resolved = PlayerVector3::ZERO;
+        for axis in [1, 0, 2] {
+            let [mut distance, ..] = axes(motion, axis);
+            let [min, min_a, min_b] = axes(moving.min, axis);
+            let [max, max_a, max_b] = axes(moving.max, axis);
+            for obstacle in obstacles {
+                let [other_min, other_min_a, other_min_b] = axes(obstacle.min, axis);
+                let [other_max, other_max_a, other_max_b] = axes(obstacle.max, axis);
+                let is_in_path = min_a < other_max_a
+                    && other_min_a < max_a
+                    && min_b < other_max_b
+                    && other_min_b < max_b;
+                if !is_in_path {
+                    continue;
+                }
+                if distance > PlayerCoord::ZERO && max <= other_min {
+                    distance = distance.min(other_min - max);
+                } else if distance < PlayerCoord::ZERO && other_max <= min {
+                    distance = distance.max(other_max - min);
+                }
+            }
+            let step = match axis {
+                0 => PlayerVector3::new(distance, PlayerCoord::ZERO, PlayerCoord::ZERO),
+                1 => PlayerVector3::new(PlayerCoord::ZERO, distance, PlayerCoord::ZERO),
+                _ => PlayerVector3::new(PlayerCoord::ZERO, PlayerCoord::ZERO, distance),
+            };
+            moving.min += step;
+            moving.max += step;
+            resolved += step;
+        }
+        resolved
+    }
+}
+
+/// Pick between the components of `min` and `max` by the bits of `corner`, see
+/// [`SpacialBounds::corners`]
+macro_rules! corners {
+    ($min:expr, $max:expr, $Vector:ident) => {{
+        let (min, max) = ($min, $max);
+        let mut corners = [min; 8];
+        let mut corner = 0;
+        while corner < 8 {
+            corners[corner] = $Vector {
+                x: if corner & 1 == 0 { min.x } else { max.x },
+                y: if corner & 2 == 0 { min.y } else { max.y },
+                z: if corner & 4 == 0 { min.z } else { max.z },
+            };
+            corner += 1;
+        }
+        corners
+    }};
+}
+
 impl const SpacialBounds for BoundingBox {
     type Vector = Vector3;
+    type Volume = f32;
     #[inline]
     fn min(&self) -> Self::Vector {
         self.min
@@ -65,9 +176,9 @@ impl const SpacialBounds for BoundingBox {
     #[inline]
     fn mid(&self) -> Self::Vector {
         Vector3 {
-            x: 0.5 * self.max.x + self.min.x,
-            y: 0.5 * self.max.y + self.min.y,
-            z: 0.5 * self.max.z + self.min.z,
+            x: 0.5 * (self.min.x + self.max.x),
+            y: 0.5 * (self.min.y + self.max.y),
+            z: 0.5 * (self.min.z + self.max.z),
         }
     }
     #[inline]
@@ -88,10 +199,66 @@ impl const SpacialBounds for BoundingBox {
             && ((self.max.y >= other.min.y) && (self.min.y <= other.max.y))
             && ((self.max.z >= other.min.z) && (self.min.z <= other.max.z))
     }
+    fn intersection(&self, other: &Self) -> Option<Self> {
+        if self.overlaps(other) {
+            Some(BoundingBox {
+                min: Vector3 {
+                    x: self.min.x.max(other.min.x),
+                    y: self.min.y.max(other.min.y),
+                    z: self.min.z.max(other.min.z),
+                },
+                max: Vector3 {
+                    x: self.max.x.min(other.max.x),
+                    y: self.max.y.min(other.max.y),
+                    z: self.max.z.min(other.max.z),
+                },
+            })
+        } else {
+            None
+        }
+    }
+    fn union(&self, other: &Self) -> Self {
+        BoundingBox {
+            min: Vector3 {
+                x: self.min.x.min(other.min.x),
+                y: self.min.y.min(other.min.y),
+                z: self.min.z.min(other.min.z),
+            },
+            max: Vector3 {
+                x: self.max.x.max(other.max.x),
+                y: self.max.y.max(other.max.y),
+                z: self.max.z.max(other.max.z),
+            },
+        }
+    }
+    fn expand(&self, margin: Self::Vector) -> Self {
+        BoundingBox {
+            min: Vector3 {
+                x: self.min.x - margin.x,
+                y: self.min.y - margin.y,
+                z: self.min.z - margin.z,
+            },
+            max: Vector3 {
+                x: self.max.x + margin.x,
+                y: self.max.y + margin.y,
+                z: self.max.z + margin.z,
+            },
+        }
+    }
+    #[inline]
+    fn volume(&self) -> Self::Volume {
+        let Vector3 { x, y, z } = self.size();
+        x * y * z
+    }
+    fn corners(&self) -> [Self::Vector; 8] {
+        corners!(self.min, self.max, Vector3)
+    }
 }
 
 impl const SpacialBounds for FactoryBounds {
     type Vector = FactoryVector3;
+    /// Enough for the largest factory, which is 2^16 meters on each side
+    type Volume = i64;
     #[inline]
     fn min(&self) -> Self::Vector {
         self.min
@@ -102,11 +269,11 @@ impl const SpacialBounds for FactoryBounds {
     }
     #[inline]
     fn mid(&self) -> Self::Vector {
-        FactoryVector3 {
-            x: self.max.x / 2 + self.min.x,
-            y: self.max.y / 2 + self.min.y,
-            z: self.max.z / 2 + self.min.z,
-        }
+        self.min.plus(FactoryVector3 {
+            x: (self.max.x - self.min.x) / 2,
+            y: (self.max.y - self.min.y) / 2,
+            z: (self.max.z - self.min.z) / 2,
+        })
     }
     #[inline]
     fn size(&self) -> Self::Vector {
@@ -126,10 +293,45 @@ impl const SpacialBounds for FactoryBounds {
             && ((self.max.y >= other.min.y) && (self.min.y <= other.max.y))
             && ((self.max.z >= other.min.z) && (self.min.z <= other.max.z))
     }
+    fn intersection(&self, other: &Self) -> Option<Self> {
+        if self.overlaps(other) {
+            Some(FactoryBounds {
+                min: self.min.max(other.min),
+                max: self.max.min(other.max),
+            })
+        } else {
+            None
+        }
+    }
+    #[inline]
+    fn union(&self, other: &Self) -> Self {
+        FactoryBounds {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+    #[inline]
+    fn expand(&self, margin: Self::Vector) -> Self {
+        FactoryBounds {
+            min: self.min.minus(margin),
+            max: self.max.plus(margin),
+        }
+    }
+    #[inline]
+    fn volume(&self) -> Self::Volume {
+        // Subtract in i64 so huge boxes don't overflow
+        (self.max.x as i64 - self.min.x as i64)
+            * (self.max.y as i64 - self.min.y as i64)
+            * (self.max.z as i64 - self.min.z as i64)
+    }
+    fn corners(&self) -> [Self::Vector; 8] {
+        corners!(self.min, self.max, FactoryVector3)
+    }
 }
 
 impl const SpacialBounds for LabBounds {
     type Vector = LabVector3;
+    type Volume = LabCoord;
     #[inline]
     fn min(&self) -> Self::Vector {
         self.min
@@ -140,10 +342,11 @@ impl const SpacialBounds for LabBounds {
     }
     #[inline]
     fn mid(&self) -> Self::Vector {
+        let half = LabCoord::from_f32(0.5);
         LabVector3 {
-            x: self.max.x.multiply(LabCoord::from_f32(0.5)) + self.min.x,
-            y: self.max.y.multiply(LabCoord::from_f32(0.5)) + self.min.y,
-            z: self.max.z.multiply(LabCoord::from_f32(0.5)) + self.min.z,
+            x: self.max.x.minus(self.min.x).multiply(half).plus(self.min.x),
+            y: self.max.y.minus(self.min.y).multiply(half).plus(self.min.y),
+            z: self.max.z.minus(self.min.z).multiply(half).plus(self.min.z),
         }
     }
     #[inline]
@@ -155,21 +358,52 @@ impl const SpacialBounds for LabBounds {
         }
     }
     fn contains(&self, point: &Self::Vector) -> bool {
-        ((self.min.x.compare(point.x).is_le()) && (point.x.compare(self.max.x).is_le()))
-            && ((self.min.y.compare(point.y).is_le()) && (point.y.compare(self.max.y).is_le()))
-            && ((self.min.z.compare(point.z).is_le()) && (point.z.compare(self.max.z).is_le()))
+        (self.min.x.less_or_equal(point.x) && point.x.less_or_equal(self.max.x))
+            && (self.min.y.less_or_equal(point.y) && point.y.less_or_equal(self.max.y))
+            && (self.min.z.less_or_equal(point.z) && point.z.less_or_equal(self.max.z))
     }
     fn overlaps(&self, other: &Self) -> bool {
-        ((self.max.x.compare(other.min.x).is_ge()) && (self.min.x.compare(other.max.x).is_le()))
-            && ((self.max.y.compare(other.min.y).is_ge())
-                && (self.min.y.compare(other.max.y).is_le()))
-            && ((self.max.z.compare(other.min.z).is_ge())
-                && (self.min.z.compare(other.max.z).is_le()))
+        (self.max.x.greater_or_equal(other.min.x) && self.min.x.less_or_equal(other.max.x))
+            && (self.max.y.greater_or_equal(other.min.y) && self.min.y.less_or_equal(other.max.y))
+            && (self.max.z.greater_or_equal(other.min.z) && self.min.z.less_or_equal(other.max.z))
+    }
+    fn intersection(&self, other: &Self) -> Option<Self> {
+        if self.overlaps(other) {
+            Some(LabBounds {
+                min: self.min.max(other.min),
+                max: self.max.min(other.max),
+            })
+        } else {
+            None
+        }
+    }
+    #[inline]
+    fn union(&self, other: &Self) -> Self {
+        LabBounds {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+    #[inline]
+    fn expand(&self, margin: Self::Vector) -> Self {
+        LabBounds {
+            min: self.min.minus(margin),
+            max: self.max.plus(margin),
+        }
+    }
+    #[inline]
+    fn volume(&self) -> Self::Volume {
+        let LabVector3 { x, y, z } = self.size();
+        x.multiply(y).multiply(z)
+    }
+    fn corners(&self) -> [Self::Vector; 8] {
+        corners!(self.min, self.max, LabVector3)
     }
 }
 
 impl const SpacialBounds for PlayerBounds {
     type Vector = PlayerVector3;
+    type Volume = PlayerCoord;
     #[inline]
     fn min(&self) -> Self::Vector {
         self.min
@@ -180,23 +414,53 @@ impl const SpacialBounds for PlayerBounds {
     }
     #[inline]
     fn mid(&self) -> Self::Vector {
-        self.max.minus(self.min)
+        self.min
+            .plus(self.max.minus(self.min).scale(PlayerCoord::from_f32(0.5)))
     }
     #[inline]
     fn size(&self) -> Self::Vector {
-        self.min.plus(self.max.scale(PlayerCoord::from_f32(0.5)))
+        self.max.minus(self.min)
     }
     fn contains(&self, point: &Self::Vector) -> bool {
-        ((self.min.x.compare(point.x).is_le()) && (point.x.compare(self.max.x).is_le()))
-            && ((self.min.y.compare(point.y).is_le()) && (point.y.compare(self.max.y).is_le()))
-            && ((self.min.z.compare(point.z).is_le()) && (point.z.compare(self.max.z).is_le()))
+        (self.min.x.less_or_equal(point.x) && point.x.less_or_equal(self.max.x))
+            && (self.min.y.less_or_equal(point.y) && point.y.less_or_equal(self.max.y))
+            && (self.min.z.less_or_equal(point.z) && point.z.less_or_equal(self.max.z))
     }
     fn overlaps(&self, other: &Self) -> bool {
-        ((self.max.x.compare(other.min.x).is_ge()) && (self.min.x.compare(other.max.x).is_le()))
-            && ((self.max.y.compare(other.min.y).is_ge())
-                && (self.min.y.compare(other.max.y).is_le()))
-            && ((self.max.z.compare(other.min.z).is_ge())
-                && (self.min.z.compare(other.max.z).is_le()))
+        (self.max.x.greater_or_equal(other.min.x) && self.min.x.less_or_equal(other.max.x))
+            && (self.max.y.greater_or_equal(other.min.y) && self.min.y.less_or_equal(other.max.y))
+            && (self.max.z.greater_or_equal(other.min.z) && self.min.z.less_or_equal(other.max.z))
+    }
+    fn intersection(&self, other: &Self) -> Option<Self> {
+        if self.overlaps(other) {
+            Some(PlayerBounds {
+                min: self.min.max(other.min),
+                max: self.max.min(other.max),
+            })
+        } else {
+            None
+        }
+    }
+    #[inline]
+    fn union(&self, other: &Self) -> Self {
+        PlayerBounds {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+    #[inline]
+    fn expand(&self, margin: Self::Vector) -> Self {
+        PlayerBounds {
+            min: self.min.minus(margin),
+            max: self.max.plus(margin),
+        }
+    }
+    #[inline]
+    fn volume(&self) -> Self::Volume {
+        self.size().product()
+    }
+    fn corners(&self) -> [Self::Vector; 8] {
+        corners!(self.min, self.max, PlayerVector3)
     }
 }
 
@@ -209,3 +473,107 @@ pub trait Bounds<V> {
     /// The bounding box of the object in the coordinate system of `V`
     fn bounds(&self) -> Self::BoundingBox;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_factory_bounds() {
+        let a = FactoryBounds {
+            min: FactoryVector3::new(0, 0, 0),
+            max: FactoryVector3::new(4, 2, 3),
+        };
+        let b = FactoryBounds {
+            min: FactoryVector3::new(2, 1, -1),
+            max: FactoryVector3::new(6, 5, 1),
+        };
+        let touching = FactoryBounds {
+            min: FactoryVector3::new(4, 0, 0),
+            max: FactoryVector3::new(5, 1, 1),
+        };
+        assert_eq!(
+            a.intersection(&b),
+            Some(FactoryBounds {
+                min: FactoryVector3::new(2, 1, 0),
+                max: FactoryVector3::new(4, 2, 1),
+            })
+        );
+        assert_eq!(
+            a.intersection(&touching).map(|shared| shared.volume()),
+            Some(0)
+        );
+        assert_eq!(a.expand(FactoryVector3::ONE).volume(), 6 * 4 * 5);
+        assert_eq!(a.expand(FactoryVector3::NEG_ONE).volume(), 0);
+        assert_eq!(
+            a.union(&b),
+            FactoryBounds {
+                min: FactoryVector3::new(0, 0, -1),
+                max: FactoryVector3::new(6, 5, 3),
+            }
+        );
+        assert_eq!(a.volume(), 24);
+        assert_eq!(a.mid(), FactoryVector3::new(2, 1, 1));
+        assert_eq!(a.cells().count(), 24);
+        assert!(a.cells().all(|cell| a.contains(&cell) && cell != a.max));
+        assert_eq!(a.corners()[0], a.min);
+        assert_eq!(a.corners()[7], a.max);
+        assert_eq!(a.corners()[5], FactoryVector3::new(4, 0, 3));
+    }
+
+    #[test]
+    fn test_player_bounds() {
+        let wall = PlayerBounds {
+            min: PlayerVector3::from_i32(2, 0, -5),
+            max: PlayerVector3::from_i32(3, 2, 5),
+        };
+        // Far enough from the origin that f32 would round off the fraction
+        let far = PlayerVector3::from_i32(1 << 28, 0, 0);
+        let far_wall = PlayerBounds {
+            min: wall.min.plus(far),
+            max: wall.max.plus(far),
+        };
+
+        let origin = PlayerVector3::new(
+            PlayerCoord::from_i32(1 << 28),
+            PlayerCoord::from_f32(0.5),
+            PlayerCoord::ZERO,
+        );
+        assert_eq!(
+            origin.ray_intersect_aabb(PlayerVector3::X.scale(PlayerCoord::from_i32(4)), &far_wall),
+            Some(PlayerCoord::from_f32(0.5))
+        );
+        assert_eq!(
+            origin.ray_intersect_aabb(PlayerVector3::NEG_X, &far_wall),
+            None
+        );
+        assert_eq!(origin.ray_intersect_aabb(PlayerVector3::Z, &far_wall), None);
+        assert_eq!(
+            origin.segment_intersect_aabb(origin.plus(PlayerVector3::from_i32(1, 0, 0)), &far_wall),
+            None
+        );
+        assert_eq!(
+            origin.segment_intersect_aabb(origin.plus(PlayerVector3::from_i32(4, 0, 0)), &far_wall),
+            Some(PlayerCoord::from_f32(0.5))
+        );
+
+        let player = PlayerBounds {
+            min: PlayerVector3::ZERO,
+            max: PlayerVector3::ONE,
+        };
+        // Stops against the wall but keeps sliding along it
+        assert_eq!(
+            player.sweep(PlayerVector3::from_i32(3, 0, 2), &[wall]),
+            PlayerVector3::from_i32(1, 0, 2)
+        );
+        // Goes over the top of it
+        assert_eq!(
+            player.sweep(PlayerVector3::from_i32(3, 2, 0), &[wall]),
+            PlayerVector3::from_i32(3, 2, 0)
+        );
+        assert_eq!(
+            player.sweep(PlayerVector3::NEG_ONE, &[wall]),
+            PlayerVector3::NEG_ONE
+        );
+    }
+}
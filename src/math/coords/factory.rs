@@ -99,6 +99,32 @@ impl FactoryVector3 {
             z: self.z * rhs.z,
         }
     }
+
+    /// The smaller of each component
+    #[inline]
+    pub const fn min(self, rhs: Self) -> Self {
+        const fn min(a: i16, b: i16) -> i16 {
+            if a < b { a } else { b }
+        }
+        Self {
+            x: min(self.x, rhs.x),
+            y: min(self.y, rhs.y),
+            z: min(self.z, rhs.z),
+        }
+    }
+
+    /// The larger of each component
+    #[inline]
+    pub const fn max(self, rhs: Self) -> Self {
+        const fn max(a: i16, b: i16) -> i16 {
+            if a > b { a } else { b }
+        }
+        Self {
+            x: max(self.x, rhs.x),
+            y: max(self.y, rhs.y),
+            z: max(self.z, rhs.z),
+        }
+    }
 }
 
 impl const std::ops::Add for FactoryVector3 {
@@ -58,4 +58,30 @@ impl LabVector3 {
             z: self.z.to_f32(),
         }
     }
+
+    #[inline]
+    pub const fn plus(self, rhs: Self) -> Self {
+        Self::new(self.x.plus(rhs.x), self.y.plus(rhs.y), self.z.plus(rhs.z))
+    }
+
+    #[inline]
+    pub const fn minus(self, rhs: Self) -> Self {
+        Self::new(
+            self.x.minus(rhs.x),
+            self.y.minus(rhs.y),
+            self.z.minus(rhs.z),
+        )
+    }
+
+    /// The smaller of each component
+    #[inline]
+    pub const fn min(self, rhs: Self) -> Self {
+        Self::new(self.x.min(rhs.x), self.y.min(rhs.y), self.z.min(rhs.z))
+    }
+
+    /// The larger of each component
+    #[inline]
+    pub const fn max(self, rhs: Self) -> Self {
+        Self::new(self.x.max(rhs.x), self.y.max(rhs.y), self.z.max(rhs.z))
+    }
 }
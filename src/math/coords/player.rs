@@ -1,7 +1,10 @@
-use crate::math::coords::{LabVector3, lab::LabCoord};
+use crate::math::{
+    bounds::PlayerBounds,
+    coords::{LabVector3, lab::LabCoord},
+};
 
 use super::{FactoryVector3, TryFromFactoryVectorError, VectorConstants, rail::RailVector3};
-use fixed_point::Q32_32;
+use fixed_point::{Easing, FpVec3, Q32_32};
 use raylib::prelude::Vector3;
 
 pub type PlayerCoord = Q32_32;
@@ -74,6 +77,18 @@ impl PlayerVector3 {
         }
     }
 
+    /// The same vector as an [`FpVec3`], which the componentwise math is done with
+    #[inline]
+    pub const fn to_fp(self) -> FpVec3<PlayerCoord> {
+        FpVec3::new(self.x, self.y, self.z)
+    }
+
+    /// Convert from an [`FpVec3`]
+    #[inline]
+    pub const fn from_fp(value: FpVec3<PlayerCoord>) -> Self {
+        Self::new(value.x, value.y, value.z)
+    }
+
     /// Convert to world vector
     ///
     /// Note: Truncates the submeter position
@@ -111,79 +126,55 @@ impl PlayerVector3 {
     /// Componentwise absolute value
     #[inline]
     pub const fn abs(self) -> Self {
-        Self {
-            x: self.x.abs(),
-            y: self.y.abs(),
-            z: self.z.abs(),
-        }
+        Self::from_fp(self.to_fp().abs())
     }
 
     /// Negate a vector
     #[inline]
     pub const fn negate(self) -> Self {
-        Self {
-            x: self.x.negate(),
-            y: self.y.negate(),
-            z: self.z.negate(),
-        }
+        Self::from_fp(self.to_fp().negate())
     }
 
     /// Add a vector
     #[inline]
     pub const fn plus(self, rhs: Self) -> Self {
-        Self {
-            x: self.x.plus(rhs.x),
-            y: self.y.plus(rhs.y),
-            z: self.z.plus(rhs.z),
-        }
+        Self::from_fp(self.to_fp().plus(rhs.to_fp()))
     }
 
     /// Subtract a vector
     #[inline]
     pub const fn minus(self, rhs: Self) -> Self {
-        Self {
-            x: self.x.minus(rhs.x),
-            y: self.y.minus(rhs.y),
-            z: self.z.minus(rhs.z),
-        }
+        Self::from_fp(self.to_fp().minus(rhs.to_fp()))
     }
 
     /// Multiply all components by a single value
     #[inline]
     pub const fn scale(self, rhs: PlayerCoord) -> Self {
-        Self {
-            x: self.x.multiply(rhs),
-            y: self.y.multiply(rhs),
-            z: self.z.multiply(rhs),
-        }
+        Self::from_fp(self.to_fp().scale(rhs))
     }
 
     /// Multiply vectors component-wise
     #[inline]
     pub const fn multiply(self, rhs: Self) -> Self {
-        Self {
-            x: self.x.multiply(rhs.x),
-            y: self.y.multiply(rhs.y),
-            z: self.z.multiply(rhs.z),
-        }
+        Self::from_fp(self.to_fp().multiply(rhs.to_fp()))
     }
 
     /// The sum of the components
     #[inline]
     pub const fn sum(self) -> PlayerCoord {
-        self.x.plus(self.y).plus(self.z)
+        self.to_fp().sum()
     }
 
     /// The product of the components
     #[inline]
     pub const fn product(self) -> PlayerCoord {
-        self.x.multiply(self.y).multiply(self.z)
+        self.to_fp().product()
     }
 
     /// Calculate the dot product between two vectors
     #[inline]
     pub const fn dot(self, rhs: Self) -> PlayerCoord {
-        self.multiply(rhs).sum()
+        self.to_fp().dot(rhs.to_fp())
     }
 
     /// Calculate the taxicab magnitude of a vector, which is cheaper
@@ -207,7 +198,7 @@ impl PlayerVector3 {
     /// See also [`Self::length_squared`]
     #[inline]
     pub const fn length(self) -> PlayerCoord {
-        self.length_sqr().sqrt()
+        self.to_fp().length()
     }
 
     /// Calculate the taxicab distance between two vectors, which is cheaper
@@ -232,6 +223,174 @@ impl PlayerVector3 {
     pub const fn distance(self, other: Self) -> PlayerCoord {
         self.minus(other).length()
     }
+
+    /// Calculate the cross product of two vectors, which is perpendicular to both
+    ///
+    /// Like [`PlayerCoord::multiply`], this wraps if any of the products don't fit, which takes
+    /// components past about 2<sup>15</sup> meters.
+    #[inline]
+    pub const fn cross(self, rhs: Self) -> Self {
+        Self::from_fp(self.to_fp().cross(rhs.to_fp()))
+    }
+
+    /// The vector in the same direction with a length of one, or [`None`] if it's zero
+    ///
+    /// The vector is scaled so its largest component is one before its length is taken, so even
+    /// vectors too short for their squared length to be anything but zero have a direction, and
+    /// the length comes out within about 2<sup>-16</sup> of one however short or long the vector
+    /// was, which is as precise as [`PlayerCoord::sqrt`] is near one.
+    pub const fn normalize(self) -> Option<Self> {
+        let largest = self.x.abs().max(self.y.abs()).max(self.z.abs());
+        if largest.is_zero() {
+            return None;
+        }
+        let scaled = Self::new(
+            self.x.divide(largest),
+            self.y.divide(largest),
+            self.z.divide(largest),
+        );
+        let length = scaled.length();
+        Some(Self::new(
+            scaled.x.divide(length),
+            scaled.y.divide(length),
+            scaled.z.divide(length),
+        ))
+    }
+
+    /// The part of `self` along `onto`, or [`None`] if `onto` is zero and has no direction
+    ///
+    /// Only the direction of `onto` matters, which is found with [`Self::normalize`], so it's
+    /// just as precise for short `onto`s as long ones.
+    #[inline]
+    pub const fn project_onto(self, onto: Self) -> Option<Self> {
+        match onto.normalize() {
+            Some(direction) => Some(direction.scale(self.dot(direction))),
+            None => None,
+        }
+    }
+
+    /// The part of `self` perpendicular to `from`, or [`None`] if `from` is zero and has no
+    /// direction
+    ///
+    /// Together with [`Self::project_onto`] it adds back up to exactly `self`.
+    #[inline]
+    pub const fn reject_from(self, from: Self) -> Option<Self> {
+        match self.project_onto(from) {
+            Some(along) => Some(self.minus(along)),
+            None => None,
+        }
+    }
+
+    /// The smaller of each component
+    #[inline]
+    pub const fn min(self, rhs: Self) -> Self {
+        Self::from_fp(self.to_fp().min(rhs.to_fp()))
+    }
+
+    /// The larger of each component
+    #[inline]
+    pub const fn max(self, rhs: Self) -> Self {
+        Self::from_fp(self.to_fp().max(rhs.to_fp()))
+    }
+
+    /// Linearly interpolate from `self` at `t == 0` to `other` at `t == 1`
+    ///
+    /// `t` outside of `[0, 1]` extrapolates.
+    #[inline]
+    pub const fn lerp(self, other: Self, t: PlayerCoord) -> Self {
+        Self::from_fp(self.to_fp().lerp(other.to_fp(), t))
+    }
+
+    /// Interpolate from `self` to `other` along `easing`, with `t` clamped to `[0, 1]`
+    #[inline]
+    pub const fn ease(self, other: Self, t: PlayerCoord, easing: Easing) -> Self {
+        self.lerp(other, t.ease(easing))
+    }
+
+    /// How far `value` is along the line from `from` to `to`, the inverse of [`Self::lerp`]
+    ///
+    /// Points off the line give the `t` of the closest point on it.
+    ///
+    /// # Panics
+    ///
+    /// If `from` equals `to`
+    #[inline]
+    pub const fn inverse_lerp(from: Self, to: Self, value: Self) -> PlayerCoord {
+        let line = to.minus(from);
+        value.minus(from).dot(line).divide(line.length_sqr())
+    }
+
+    /// How many `direction`s along the ray from `self` it first touches `bounds`
+    ///
+    /// Returns zero if `self` is already inside `bounds`, and [`None`] if the ray misses it or it's
+    /// behind the ray. Done entirely in fixed point, so it's just as precise far from the origin.
+    #[must_use]
+    pub const fn ray_intersect_aabb(
+        self,
+        direction: Self,
+        bounds: &PlayerBounds,
+    ) -> Option<PlayerCoord> {
+        /// Narrow `range` down to the part of the ray between `min` and `max` along one axis
+        const fn clip(
+            origin: PlayerCoord,
+            direction: PlayerCoord,
+            min: PlayerCoord,
+            max: PlayerCoord,
+            range: (PlayerCoord, PlayerCoord),
+        ) -> Option<(PlayerCoord, PlayerCoord)> {
+            let (mut enter, mut exit) = range;
+            if direction.is_zero() {
+                // Parallel to the slab, so either always in it or never
+                return if min.less_or_equal(origin) && origin.less_or_equal(max) {
+                    Some(range)
+                } else {
+                    None
+                };
+            }
+            let (mut near, mut far) = (
+                min.minus(origin).divide(direction),
+                max.minus(origin).divide(direction),
+            );
+            if near.greater_than(far) {
+                (near, far) = (far, near);
+            }
+            enter = enter.max(near);
+            exit = exit.min(far);
+            if enter.less_or_equal(exit) {
+                Some((enter, exit))
+            } else {
+                None
+            }
+        }
+
+        let range = (PlayerCoord::ZERO, PlayerCoord::MAX);
+        let Some(range) = clip(self.x, direction.x, bounds.min.x, bounds.max.x, range) else {
+            return None;
+        };
+        let Some(range) = clip(self.y, direction.y, bounds.min.y, bounds.max.y, range) else {
+            return None;
+        };
+        match clip(self.z, direction.z, bounds.min.z, bounds.max.z, range) {
+            Some((enter, _)) => Some(enter),
+            None => None,
+        }
+    }
+
+    /// How far along the line from `self` to `end` it first touches `bounds`, from zero at `self`
+    /// to one at `end`
+    ///
+    /// Returns [`None`] if the line misses `bounds`. See [`Self::ray_intersect_aabb`].
+    #[must_use]
+    pub const fn segment_intersect_aabb(
+        self,
+        end: Self,
+        bounds: &PlayerBounds,
+    ) -> Option<PlayerCoord> {
+        match self.ray_intersect_aabb(end.minus(self), bounds) {
+            Some(t) if t.less_or_equal(PlayerCoord::ONE) => Some(t),
+            _ => None,
+        }
+    }
 }
 
 impl From<Vector3> for PlayerVector3 {
@@ -241,6 +400,20 @@ impl From<Vector3> for PlayerVector3 {
     }
 }
 
+impl From<FpVec3<PlayerCoord>> for PlayerVector3 {
+    #[inline]
+    fn from(value: FpVec3<PlayerCoord>) -> Self {
+        PlayerVector3::from_fp(value)
+    }
+}
+
+impl From<PlayerVector3> for FpVec3<PlayerCoord> {
+    #[inline]
+    fn from(value: PlayerVector3) -> Self {
+        value.to_fp()
+    }
+}
+
 impl From<PlayerVector3> for Vector3 {
     #[inline]
     fn from(value: PlayerVector3) -> Self {
@@ -0,0 +1,119 @@
+use raylib::prelude::*;
+
+/// A plane in normal-distance form, where points `p` with `normal.dot(p) + distance >= 0`
+/// are on the inside.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Plane {
+    /// The unit normal, pointing towards the inside
+    pub normal: Vector3,
+    /// The signed distance of the plane from the origin along [`Self::normal`]
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Construct a plane passing through `point` facing `normal`
+    #[inline]
+    #[must_use]
+    pub fn from_point_normal(point: Vector3, normal: Vector3) -> Self {
+        Self {
+            normal,
+            distance: -normal.dot(point),
+        }
+    }
+
+    /// The signed distance of `point` from `self`; negative when outside
+    #[inline]
+    #[must_use]
+    pub fn signed_distance(&self, point: Vector3) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+/// The volume visible to a perspective camera
+///
+/// Note: coordinates are in whatever space the camera is in (usually player-relative)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Frustum {
+    /// Near, far, left, right, bottom, top
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Distance of the near clipping plane, matching raylib's `RL_CULL_DISTANCE_NEAR`
+    pub const NEAR: f32 = 0.01;
+    /// Distance of the far clipping plane, matching raylib's `RL_CULL_DISTANCE_FAR`
+    pub const FAR: f32 = 1000.0;
+
+    /// Construct the frustum of a perspective `camera` with the given `aspect` ratio (width / height)
+    #[must_use]
+    pub fn from_camera(camera: &Camera3D, aspect: f32) -> Self {
+        let forward = (camera.target - camera.position).normalize_or(Vector3::NEG_Z);
+        let right = forward.cross(camera.up).normalize_or(Vector3::X);
+        let up = right.cross(forward);
+
+        let half_v = camera.fovy.to_radians() * 0.5;
+        let half_h = (half_v.tan() * aspect).atan();
+        let (sin_v, cos_v) = half_v.sin_cos();
+        let (sin_h, cos_h) = half_h.sin_cos();
+
+        let pos = camera.position;
+        Self {
+            planes: [
+                Plane::from_point_normal(pos + forward * Self::NEAR, forward),
+                Plane::from_point_normal(pos + forward * Self::FAR, -forward),
+                Plane::from_point_normal(pos, forward * sin_h + right * cos_h),
+                Plane::from_point_normal(pos, forward * sin_h - right * cos_h),
+                Plane::from_point_normal(pos, forward * sin_v + up * cos_v),
+                Plane::from_point_normal(pos, forward * sin_v - up * cos_v),
+            ],
+        }
+    }
+
+    /// Check if any part of `bbox` may be visible
+    ///
+    /// Conservative: may return `true` for some boxes just outside the corners of the frustum.
+    #[must_use]
+    pub fn intersects_box(&self, bbox: &BoundingBox) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner furthest along the plane normal
+            let positive = Vector3::select(plane.normal.cmpge(Vector3::ZERO), bbox.max, bbox.min);
+            plane.signed_distance(positive) >= 0.0
+        })
+    }
+
+    /// Check if any part of the sphere may be visible
+    #[must_use]
+    pub fn intersects_sphere(&self, center: Vector3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(center) >= -radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frustum_culls_behind_camera() {
+        let camera = Camera3D::perspective(Vector3::ZERO, Vector3::NEG_Z, Vector3::Y, 45.0);
+        let frustum = Frustum::from_camera(&camera, 16.0 / 9.0);
+        let ahead = BoundingBox {
+            min: Vector3::new(-1.0, -1.0, -6.0),
+            max: Vector3::new(1.0, 1.0, -4.0),
+        };
+        let behind = BoundingBox {
+            min: Vector3::new(-1.0, -1.0, 4.0),
+            max: Vector3::new(1.0, 1.0, 6.0),
+        };
+        let beside = BoundingBox {
+            min: Vector3::new(20.0, -1.0, -2.0),
+            max: Vector3::new(22.0, 1.0, -1.0),
+        };
+        assert!(frustum.intersects_box(&ahead));
+        assert!(!frustum.intersects_box(&behind));
+        assert!(!frustum.intersects_box(&beside));
+        assert!(frustum.intersects_sphere(Vector3::new(0.0, 0.0, -10.0), 1.0));
+        assert!(!frustum.intersects_sphere(Vector3::new(0.0, 0.0, 10.0), 1.0));
+    }
+}
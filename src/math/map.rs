@@ -0,0 +1,127 @@
+use raylib::prelude::*;
+
+use super::{
+    bounds::{FactoryBounds, PlayerBounds},
+    coords::{FactoryVector3, PlayerVector3, RailVector3},
+};
+
+/// A top-down view of the world onto a rectangle of pixels
+///
+/// East (+x) is to the right and north (-z) is up, so z grows downwards like screen y does.
+/// Height is ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapProjection {
+    /// The position shown at the middle of [`Self::viewport`]
+    pub center: PlayerVector3,
+    /// Pixels per meter
+    pub scale: f32,
+    /// Where the map is drawn, in pixels
+    pub viewport: Rectangle,
+}
+
+impl MapProjection {
+    /// The middle of [`Self::viewport`], where [`Self::center`] is drawn
+    #[inline]
+    #[must_use]
+    pub const fn viewport_center(&self) -> Vector2 {
+        Vector2::new(
+            self.viewport.x + 0.5 * self.viewport.width,
+            self.viewport.y + 0.5 * self.viewport.height,
+        )
+    }
+
+    /// Where `position` is drawn on the map
+    #[must_use]
+    pub fn player_to_map(&self, position: PlayerVector3) -> Vector2 {
+        let Vector3 { x, z, .. } = position.minus(self.center).to_vec3();
+        self.viewport_center() + Vector2::new(x, z) * self.scale
+    }
+
+    /// Where the corner of the block at `position` in the factory at `origin` is drawn on the
+    /// map
+    #[inline]
+    #[must_use]
+    pub fn factory_to_map(&self, position: FactoryVector3, origin: &RailVector3) -> Vector2 {
+        self.player_to_map(position.to_player(origin))
+    }
+
+    /// The area `bounds` covers on the map
+    #[must_use]
+    pub fn player_bounds_to_map(&self, bounds: PlayerBounds) -> Rectangle {
+        let min = self.player_to_map(bounds.min);
+        let max = self.player_to_map(bounds.max);
+        let (min, max) = (min.min(max), min.max(max));
+        Rectangle::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    /// The area `bounds` in the factory at `origin` covers on the map
+    #[inline]
+    #[must_use]
+    pub fn factory_bounds_to_map(&self, bounds: FactoryBounds, origin: &RailVector3) -> Rectangle {
+        self.player_bounds_to_map(PlayerBounds {
+            min: bounds.min.to_player(origin),
+            max: bounds.max.to_player(origin),
+        })
+    }
+
+    /// The position drawn at `point` on the map, at the height of [`Self::center`]
+    #[must_use]
+    pub fn map_to_player(&self, point: Vector2) -> PlayerVector3 {
+        let Vector2 { x, y } = (point - self.viewport_center()) / self.scale;
+        self.center
+            .plus(PlayerVector3::from_vec3(Vector3::new(x, 0.0, y)))
+    }
+
+    /// Whether any of `rect` is inside [`Self::viewport`], so it's worth drawing
+    #[must_use]
+    pub fn is_visible(&self, rect: Rectangle) -> bool {
+        self.viewport.check_collision_recs(&rect)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::coords::VectorConstants;
+
+    #[test]
+    fn test_map_projection() {
+        let projection = MapProjection {
+            center: PlayerVector3::from_i32(10, 5, 10),
+            scale: 4.0,
+            viewport: Rectangle::new(100.0, 100.0, 200.0, 200.0),
+        };
+        assert_eq!(
+            projection.player_to_map(projection.center),
+            Vector2::new(200.0, 200.0)
+        );
+        // North is up
+        assert_eq!(
+            projection.player_to_map(PlayerVector3::from_i32(10, 0, 0)),
+            Vector2::new(200.0, 160.0)
+        );
+
+        let origin = RailVector3::new(10, 0, 10);
+        let bounds = FactoryBounds {
+            min: FactoryVector3::new(-5, 0, -5),
+            max: FactoryVector3::new(5, 3, 0),
+        };
+        assert_eq!(
+            projection.factory_bounds_to_map(bounds, &origin),
+            Rectangle::new(180.0, 180.0, 40.0, 20.0)
+        );
+        assert!(projection.is_visible(projection.factory_bounds_to_map(bounds, &origin)));
+        assert!(!projection.is_visible(Rectangle::new(0.0, 0.0, 50.0, 50.0)));
+
+        let point = projection.map_to_player(Vector2::new(240.0, 180.0));
+        assert_eq!(point.y, projection.center.y);
+        assert_eq!(
+            point.minus(projection.center),
+            PlayerVector3::from_i32(10, 0, -5)
+        );
+        assert_eq!(
+            projection.player_to_map(FactoryVector3::ZERO.to_player(&origin)),
+            projection.factory_to_map(FactoryVector3::ZERO, &origin)
+        );
+    }
+}
@@ -2,3 +2,5 @@
 
 pub mod bounds;
 pub mod coords;
+pub mod frustum;
+pub mod map;
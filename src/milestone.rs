@@ -0,0 +1,367 @@
+//! Goals the player reaches along the way, like mining their first ore
+//!
+//! Each [`Milestone`] has a [`Goal`] measured against what factories have made, what's been
+//! researched, and how far trains have run. [`Milestones`] checks them every tick, and announces
+//! the ones just reached in the [HUD](crate::hud::Hud) one at a time.
+//!
+//! Milestones are saved as text, with each one reached by name:
+//!
+//! ```text
+//! milestones first-ore faster-belts
+//! ```
+
+use crate::{
+    container::Item,
+    locale::tr,
+    region::factory::Factory,
+    research::{Research, TechId},
+};
+use std::{
+    collections::{BTreeSet, VecDeque},
+    str::FromStr,
+};
+
+/// Seconds each milestone is announced for
+const ANNOUNCEMENT: f32 = 5.0;
+
+/// What has to happen to reach a [`Milestone`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    /// Make `count` of the item with the formula `item`, in every factory together
+    Produce {
+        item: &'static str,
+        count: u64,
+    },
+    Research(TechId),
+    /// Run trains this many meters, added up
+    TrainDistance(u32),
+}
+
+/// What [`Goal`]s are measured against
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'a> {
+    pub factories: &'a [Factory],
+    pub research: &'a Research,
+    /// Meters every train has run, added up
+    pub traveled: f64,
+}
+
+impl Goal {
+    /// How close the goal is to being reached, from 0 to 1
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        reason = "only the fraction matters, not the exact count"
+    )]
+    pub fn progress(&self, progress: &Progress<'_>) -> f32 {
+        let fraction = match *self {
+            Self::Produce { item, count } => {
+                let Ok(item) = item.parse::<Item>() else {
+                    return 0.0;
+                };
+                let made = (progress.factories.iter())
+                    .map(|factory| factory.statistics().total_produced(&item))
+                    .sum::<u64>();
+                made as f64 / count.max(1) as f64
+            }
+            Self::Research(id) => {
+                if progress.research.is_unlocked(id) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Self::TrainDistance(meters) => progress.traveled / f64::from(meters.max(1)),
+        };
+        fraction.clamp(0.0, 1.0) as f32
+    }
+}
+
+/// Identifies a [`Milestone`] in [`Milestone::ALL`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MilestoneId {
+    FirstOre,
+    IronStockpile,
+    FasterBelts,
+    Combustion,
+    RailNetwork,
+}
+
+impl MilestoneId {
+    pub const fn milestone(self) -> &'static Milestone {
+        &Milestone::ALL[self as usize]
+    }
+
+    /// The [`tr!`] key of the milestone's name
+    pub const fn name_key(self) -> &'static str {
+        match self {
+            Self::FirstOre => "milestone.first_ore",
+            Self::IronStockpile => "milestone.iron_stockpile",
+            Self::FasterBelts => "milestone.faster_belts",
+            Self::Combustion => "milestone.combustion",
+            Self::RailNetwork => "milestone.rail_network",
+        }
+    }
+}
+
+/// Something worth celebrating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Milestone {
+    pub id: MilestoneId,
+    /// Used to save milestones and to refer to them in the console
+    pub name: &'static str,
+    pub goal: Goal,
+}
+
+impl Milestone {
+    /// Every milestone, in the same order as [`MilestoneId`]
+    pub const ALL: [Self; 5] = [
+        Self::new(
+            MilestoneId::FirstOre,
+            "first-ore",
+            Goal::Produce {
+                item: "Fe2O3",
+                count: 1,
+            },
+        ),
+        Self::new(
+            MilestoneId::IronStockpile,
+            "iron-stockpile",
+            Goal::Produce {
+                item: "Fe2O3",
+                count: 1000,
+            },
+        ),
+        Self::new(
+            MilestoneId::FasterBelts,
+            "faster-belts",
+            Goal::Research(TechId::BeltMk2),
+        ),
+        Self::new(
+            MilestoneId::Combustion,
+            "combustion",
+            Goal::Research(TechId::Combustion),
+        ),
+        Self::new(
+            MilestoneId::RailNetwork,
+            "rail-network",
+            Goal::TrainDistance(10_000),
+        ),
+    ];
+
+    const fn new(id: MilestoneId, name: &'static str, goal: Goal) -> Self {
+        Self { id, name, goal }
+    }
+
+    /// The milestone called `name`, if there is one
+    pub fn find(name: &str) -> Option<&'static Self> {
+        Self::ALL.iter().find(|milestone| milestone.name == name)
+    }
+
+    /// The name shown to the player
+    pub fn title(&self) -> String {
+        tr!(self.id.name_key())
+    }
+}
+
+/// The milestones reached so far, and the ones waiting to be announced
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Milestones {
+    reached: BTreeSet<MilestoneId>,
+    /// Reached but not done being announced, oldest first
+    announcements: VecDeque<MilestoneId>,
+    /// Seconds the first announcement has been shown
+    shown_for: f32,
+}
+
+impl Milestones {
+    #[inline]
+    pub fn is_reached(&self, id: MilestoneId) -> bool {
+        self.reached.contains(&id)
+    }
+
+    /// Mark every milestone whose goal has been met as reached, and announce the new ones
+    ///
+    /// Returns the ones just reached.
+    pub fn check(&mut self, progress: &Progress<'_>) -> Vec<MilestoneId> {
+        let mut reached = Vec::new();
+        for milestone in &Milestone::ALL {
+            if !self.is_reached(milestone.id) && milestone.goal.progress(progress) >= 1.0 {
+                self.reached.insert(milestone.id);
+                self.announcements.push_back(milestone.id);
+                reached.push(milestone.id);
+            }
+        }
+        reached
+    }
+
+    /// Show the first announcement for `dt` more seconds, moving on to the next once it's been
+    /// shown long enough
+    pub fn tick(&mut self, dt: f32) {
+        if self.announcements.is_empty() {
+            return;
+        }
+        self.shown_for += dt;
+        if self.shown_for >= ANNOUNCEMENT {
+            self.announcements.pop_front();
+            self.shown_for = 0.0;
+        }
+    }
+
+    /// The milestone being announced, if any
+    pub fn announcement(&self) -> Option<&'static Milestone> {
+        (self.announcements.front()).map(|&id| id.milestone())
+    }
+}
+
+impl std::fmt::Display for Milestones {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("milestones")?;
+        for id in &self.reached {
+            write!(f, " {}", id.milestone().name)?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Why text couldn't be read as [`Milestones`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMilestonesError {
+    /// Doesn't start with `milestones`
+    MissingHeader,
+    /// Contains the name that isn't a milestone
+    UnknownMilestone(String),
+}
+
+impl std::fmt::Display for ParseMilestonesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader => f.write_str("not milestones"),
+            Self::UnknownMilestone(name) => write!(f, "unknown milestone `{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for ParseMilestonesError {}
+
+impl FromStr for Milestones {
+    type Err = ParseMilestonesError;
+
+    /// Milestones that were already reached aren't announced again
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        if words.next() != Some("milestones") {
+            return Err(ParseMilestonesError::MissingHeader);
+        }
+        let reached = words
+            .map(|name| {
+                Milestone::find(name)
+                    .map(|milestone| milestone.id)
+                    .ok_or_else(|| ParseMilestonesError::UnknownMilestone(name.to_string()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            reached,
+            ..Self::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chem::element::Element,
+        math::{
+            bounds::FactoryBounds,
+            coords::{FactoryVector3, RailVector3},
+        },
+        ordinals::Cardinal2D,
+        region::{
+            factory::Miner,
+            rail::{deposit::Deposits, network::RailNetwork, terrain::DepositSite},
+        },
+    };
+
+    #[test]
+    fn test_milestones() {
+        for (idx, milestone) in Milestone::ALL.iter().enumerate() {
+            assert_eq!(
+                milestone.id as usize, idx,
+                "{} is out of order",
+                milestone.name
+            );
+        }
+
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-2, 0, -2),
+                max: FactoryVector3::new(10, 8, 10),
+            },
+        );
+        let mut deposits = Deposits::default();
+        deposits.find(DepositSite {
+            position: RailVector3::new(1, 0, 1),
+            element: Element::Fe,
+            richness: 1.0,
+        });
+        _ = factory.add_miner(Miner::new(
+            FactoryVector3::new(0, 0, 0),
+            Cardinal2D::East,
+            RailVector3::new(1, 0, 1),
+        ));
+        let mut research = Research::default();
+        research.points = 50;
+        let mut milestones = Milestones::default();
+
+        factory.tick(2.0, 1.0, &mut deposits, &mut RailNetwork::default());
+        let factories = [factory];
+        let progress = Progress {
+            factories: &factories,
+            research: &research,
+            traveled: 2500.0,
+        };
+        let rail = MilestoneId::RailNetwork
+            .milestone()
+            .goal
+            .progress(&progress);
+        assert!((rail - 0.25).abs() < 1e-6);
+        milestones.check(&progress);
+        assert!(milestones.is_reached(MilestoneId::FirstOre));
+        assert!(!milestones.is_reached(MilestoneId::IronStockpile));
+        assert_eq!(
+            milestones.announcement().map(|milestone| milestone.id),
+            Some(MilestoneId::FirstOre)
+        );
+
+        let mut researched = research.clone();
+        researched.research(TechId::BeltMk2).unwrap();
+        let progress = Progress {
+            research: &researched,
+            ..progress
+        };
+        milestones.check(&progress);
+        milestones.check(&progress);
+        milestones.tick(ANNOUNCEMENT);
+        assert_eq!(
+            milestones.announcement().map(|milestone| milestone.id),
+            Some(MilestoneId::FasterBelts),
+            "each milestone is announced once, in order"
+        );
+        milestones.tick(ANNOUNCEMENT);
+        assert_eq!(milestones.announcement(), None);
+
+        let text = milestones.to_string();
+        assert_eq!(text, "milestones first-ore faster-belts\n");
+        let loaded = text.parse::<Milestones>().unwrap();
+        assert!(loaded.is_reached(MilestoneId::FasterBelts));
+        assert_eq!(loaded.announcement(), None, "loading doesn't announce");
+        assert_eq!(
+            "milestones moon-landing".parse::<Milestones>(),
+            Err(ParseMilestonesError::UnknownMilestone(
+                "moon-landing".to_string()
+            ))
+        );
+    }
+}
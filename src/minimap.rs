@@ -0,0 +1,247 @@
+use crate::{
+    game::GameState,
+    input::{AxisInput, EventInput, Inputs},
+    math::{
+        bounds::{Bounds, FactoryBounds, PlayerBounds},
+        coords::{FactoryVector3, VectorConstants},
+        map::MapProjection,
+    },
+    region::{
+        factory::{Factory, Machine},
+        lab::Laboratory,
+    },
+};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer, RenderingOptions},
+};
+use raylib::prelude::*;
+
+/// Width and height of the minimap in the corner of the screen, in pixels
+const MINIMAP_SIZE: f32 = 200.0;
+const MARGIN: f32 = 16.0;
+const BACKGROUND: Color = Color::new(0, 0, 0, 160);
+const FACTORY_FLOOR: Color = Color::new(0, 80, 0, 200);
+/// Distance from the middle of the player marker to its tip, in pixels
+const PLAYER_MARKER_SIZE: f32 = 8.0;
+/// How many times the zoom is multiplied by each second [`AxisInput::MapZoom`] is held
+const ZOOM_RATE: f32 = 4.0;
+
+/// Everything shown on the map, seen from above
+///
+/// Built fresh each frame from the [`GameState`].
+#[derive(Debug, Clone, Copy)]
+pub struct MapView<'a> {
+    pub projection: MapProjection,
+    pub factories: &'a [Factory],
+    pub lab: &'a Laboratory,
+    /// Which way the player is facing, see [`crate::player::Player::yaw`]
+    pub yaw: f32,
+}
+
+impl MapView<'_> {
+    fn draw_factory(&self, d: &mut Renderer<'_>, factory: &Factory) -> draw::Result {
+        let projection = &self.projection;
+        let origin = &factory.origin;
+        let floor = projection.factory_bounds_to_map(factory.bounds, origin);
+        if !projection.is_visible(floor) {
+            return Ok(());
+        }
+        d.draw_rectangle(floor, FACTORY_FLOOR)?;
+        d.draw_rectangle_lines(floor, None, Color::GREEN)?;
+
+        for reactor in factory.reactors() {
+            let footprint = projection.factory_bounds_to_map(reactor.bounds(), origin);
+            d.draw_rectangle(footprint, Color::GRAY)?;
+            d.draw_rectangle_lines(footprint, None, Color::LIGHTGRAY)?;
+        }
+        // todo: other machines
+
+        for ladder in factory.ladders() {
+            d.draw_rectangle(
+                projection.factory_bounds_to_map(ladder.bounds(), origin),
+                Color::BROWN,
+            )?;
+        }
+
+        // Same colors as in the factory
+        let belt_nodes = factory.reactors().iter().flat_map(|reactor| {
+            (reactor.belt_inputs().into_iter())
+                .map(|node| (node.0.position, Color::ORANGE))
+                .chain(
+                    reactor
+                        .belt_outputs()
+                        .into_iter()
+                        .map(|node| (node.0.position, Color::GREEN)),
+                )
+        });
+        for (position, color) in belt_nodes {
+            let block = FactoryBounds {
+                min: position,
+                max: position + FactoryVector3::ONE,
+            };
+            d.draw_rectangle(projection.factory_bounds_to_map(block, origin), color)?;
+        }
+        Ok(())
+    }
+
+    fn draw_player_marker(&self, d: &mut Renderer<'_>) -> draw::Result {
+        let center = self.projection.viewport_center();
+        // The player's forward on the map, where north is up
+        let (sin, cos) = self.yaw.sin_cos();
+        let forward = Vector2::new(-sin, -cos);
+        let left = Vector2::new(forward.y, -forward.x);
+        d.draw_triangle(
+            &[
+                center + forward * PLAYER_MARKER_SIZE,
+                center + (left - forward) * (0.6 * PLAYER_MARKER_SIZE),
+                center - (left + forward) * (0.6 * PLAYER_MARKER_SIZE),
+            ],
+            Color::WHITE,
+        )
+    }
+}
+
+impl Draw for MapView<'_> {
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        let projection = &self.projection;
+        d.draw_rectangle(projection.viewport, BACKGROUND)?;
+        for factory in self.factories {
+            self.draw_factory(d, factory)?;
+        }
+        let lab = projection.player_bounds_to_map(PlayerBounds {
+            min: self.lab.bounds.min.to_player(&self.lab.origin),
+            max: self.lab.bounds.max.to_player(&self.lab.origin),
+        });
+        if projection.is_visible(lab) {
+            d.draw_rectangle_lines(lab, None, Color::ORANGE)?;
+        }
+        self.draw_player_marker(d)
+    }
+}
+
+/// A top-down map of the area around the player, in a corner of the screen or covering all of it
+///
+/// The map is drawn to a render texture, which is then drawn over the 3D view.
+#[derive(Debug)]
+pub struct Minimap {
+    /// Pixels per meter
+    pub zoom: f32,
+    /// Whether the map covers the screen instead of a corner of it
+    pub is_fullscreen: bool,
+    /// What the map was last drawn to, and its width and height
+    target: Option<(RenderTexture2D, (u32, u32))>,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Minimap {
+    pub const MIN_ZOOM: f32 = 0.25;
+    pub const MAX_ZOOM: f32 = 16.0;
+
+    /// A minimap in the corner of the screen showing a meter as 2 pixels
+    pub const fn new() -> Self {
+        Self {
+            zoom: 2.0,
+            is_fullscreen: false,
+            target: None,
+        }
+    }
+
+    /// Where the map goes on a screen `screen` pixels in size
+    pub const fn rect(&self, screen: Vector2) -> Rectangle {
+        if self.is_fullscreen {
+            Rectangle::new(
+                MARGIN,
+                MARGIN,
+                screen.x - 2.0 * MARGIN,
+                screen.y - 2.0 * MARGIN,
+            )
+        } else {
+            Rectangle::new(
+                screen.x - MARGIN - MINIMAP_SIZE,
+                MARGIN,
+                MINIMAP_SIZE,
+                MINIMAP_SIZE,
+            )
+        }
+    }
+
+    /// Toggle fullscreen and zoom in or out, `dt` seconds after the last update
+    pub fn update(&mut self, inputs: &Inputs, dt: f32) {
+        if inputs[EventInput::ToggleMap] {
+            self.is_fullscreen = !self.is_fullscreen;
+        }
+        self.zoom = (self.zoom * ZOOM_RATE.powf(inputs[AxisInput::MapZoom] * dt))
+            .clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+    }
+
+    /// Draw the map of `state` to the render texture, remaking it first if the map changed size
+    ///
+    /// Must be called outside of drawing, before [`Self::draw`].
+    pub fn render(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        state: &GameState,
+        screen: Vector2,
+    ) -> Result<(), Error> {
+        let rect = self.rect(screen);
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "the map is no bigger than the screen"
+        )]
+        let size = (rect.width.max(1.0) as u32, rect.height.max(1.0) as u32);
+        let target = match self.target.take() {
+            Some((target, target_size)) if target_size == size => target,
+            _ => rl.load_render_texture(thread, size.0, size.1)?,
+        };
+        let (target, _) = self.target.insert((target, size));
+
+        #[allow(
+            clippy::cast_precision_loss,
+            reason = "the map is no bigger than the screen"
+        )]
+        let view = MapView {
+            projection: MapProjection {
+                center: state.player.position,
+                scale: self.zoom,
+                viewport: Rectangle::new(0.0, 0.0, size.0 as f32, size.1 as f32),
+            },
+            factories: &state.factories,
+            lab: &state.lab,
+            yaw: state.player.yaw,
+        };
+        let mut d = rl.begin_texture_mode(thread, target);
+        d.clear_background(Color::BLANK);
+        // Can't fail when drawing directly to a texture
+        _ = view.draw(&mut Renderer::new(&mut d, RenderingOptions::new()));
+        Ok(())
+    }
+
+    /// Draw the map last rendered with [`Self::render`] onto a screen `screen` pixels in size
+    pub fn draw(&self, d: &mut impl RaylibDraw, screen: Vector2) {
+        let Some((target, (width, height))) = &self.target else {
+            return;
+        };
+        let rect = self.rect(screen);
+        #[allow(
+            clippy::cast_precision_loss,
+            reason = "the map is no bigger than the screen"
+        )]
+        // Render textures are upside down
+        let source = Rectangle::new(0.0, 0.0, *width as f32, -(*height as f32));
+        d.draw_texture_rec(
+            target.texture(),
+            source,
+            Vector2::new(rect.x, rect.y),
+            Color::WHITE,
+        );
+        d.draw_rectangle_lines_ex(rect, 1.0, Color::GRAY);
+    }
+}
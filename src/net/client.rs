@@ -0,0 +1,247 @@
+use super::{Connection, NetError, Peer, Synced, set_belts, set_reactors, transform_of};
+use crate::{
+    game::GameState,
+    math::coords::{PlayerVector3, VectorConstants},
+    net::protocol::{Message, PROTOCOL_VERSION, PlayerId, Transform},
+};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    net::{SocketAddr, TcpStream},
+    time::Duration,
+};
+
+/// How long to wait for the server to answer when joining
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Moves the player as soon as they move, without waiting for the server to agree
+///
+/// Every move sent to the server is remembered until the server says where the move actually
+/// got the player. If that's somewhere else, the moves the server hasn't gotten to yet are
+/// replayed from where the server says the player is.
+#[derive(Debug, Clone, Default)]
+pub struct Prediction {
+    next_sequence: u32,
+    /// Moves the server hasn't answered yet, oldest first
+    pending: VecDeque<(u32, PlayerVector3)>,
+}
+
+impl Prediction {
+    /// Remember moving by `delta`, returning the number to send it with
+    pub fn predict(&mut self, delta: PlayerVector3) -> u32 {
+        let sequence = self.next_sequence;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        self.pending.push_back((sequence, delta));
+        sequence
+    }
+
+    /// Where the player should be after every move sent, now that the server says it moved them
+    /// to `position` with the move numbered `sequence`
+    pub fn reconcile(&mut self, sequence: u32, position: PlayerVector3) -> PlayerVector3 {
+        while let Some(&(pending, _)) = self.pending.front()
+            // Older, even if the sequence has wrapped around
+            && sequence.wrapping_sub(pending) < u32::MAX / 2
+        {
+            self.pending.pop_front();
+        }
+        (self.pending.iter()).fold(position, |position, &(_, delta)| position.plus(delta))
+    }
+}
+
+/// Plays on someone else's [`Server`](super::Server)
+///
+/// Changes to factories are sent to the server and undone until the server says they happened.
+#[derive(Debug)]
+pub struct Client {
+    connection: Connection,
+    /// Set once the server lets the client join
+    id: Option<PlayerId>,
+    peers: BTreeMap<PlayerId, Peer>,
+    prediction: Prediction,
+    /// Where the server will have the player once it's gotten every move sent, if it agrees
+    /// with all of them
+    sent: Transform,
+    /// What the server says is in each factory
+    synced: Vec<Synced>,
+}
+
+impl Client {
+    /// Connect to the server at `address` and ask to join as `name`
+    ///
+    /// The client has joined once [`Self::update`] receives the server's answer.
+    pub fn join(address: SocketAddr, name: String, state: &GameState) -> Result<Self, NetError> {
+        let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+        let mut connection = Connection::new(stream)?;
+        connection.send(&Message::Join {
+            version: PROTOCOL_VERSION,
+            name,
+        });
+        Ok(Self {
+            connection,
+            id: None,
+            peers: BTreeMap::new(),
+            prediction: Prediction::default(),
+            sent: transform_of(&state.player),
+            synced: state.factories.iter().map(Synced::of).collect(),
+        })
+    }
+
+    /// Everyone else who has joined
+    pub fn peers(&self) -> impl Iterator<Item = &Peer> {
+        self.peers.values()
+    }
+
+    /// Send the player's moves and changes to factories, and act on what the server says
+    pub fn update(&mut self, state: &mut GameState) -> Result<(), NetError> {
+        if self.id.is_some() {
+            self.send_changes(state);
+        }
+        for message in self.connection.receive()? {
+            self.handle(message, state)?;
+        }
+        for (synced, factory) in self.synced.iter_mut().zip(&state.factories) {
+            *synced = Synced::of(factory);
+        }
+        self.connection.flush()
+    }
+
+    /// Ask the server to make the changes made since last time, and undo them until it does
+    fn send_changes(&mut self, state: &mut GameState) {
+        let transform = transform_of(&state.player);
+        if transform != self.sent {
+            let delta = transform.position.minus(self.sent.position);
+            self.connection.send(&Message::Move {
+                sequence: self.prediction.predict(delta),
+                delta,
+                yaw: transform.yaw,
+                pitch: transform.pitch,
+            });
+            self.sent = transform;
+        }
+
+        for ((index, synced), factory) in (0..).zip(&self.synced).zip(&mut state.factories) {
+            for message in synced.changes(index, factory) {
+                match message {
+                    Message::AddReactor { .. }
+                    | Message::RemoveReactor { .. }
+                    | Message::AddBelt { .. } => self.connection.send(&message),
+                    // Only the server can make other changes
+                    _ => {}
+                }
+            }
+            synced.restore(factory);
+        }
+    }
+
+    fn handle(&mut self, message: Message, state: &mut GameState) -> Result<(), NetError> {
+        match message {
+            Message::Welcome { player, transform } => {
+                self.id = Some(player);
+                let player = &mut state.player;
+                player.position = transform.position;
+                player.velocity = PlayerVector3::ZERO;
+                (player.yaw, player.target_yaw) = (transform.yaw, transform.yaw);
+                (player.pitch, player.target_pitch) = (transform.pitch, transform.pitch);
+                self.sent = transform;
+            }
+            Message::Rejected { reason } => return Err(NetError::Rejected(reason)),
+            Message::PlayerJoined {
+                player,
+                name,
+                transform,
+            } => _ = self.peers.insert(player, Peer { name, transform }),
+            Message::PlayerLeft { player } => _ = self.peers.remove(&player),
+            Message::MoveAck { sequence, position } => {
+                // Keep any movement since the last move was sent
+                let unsent = state.player.position.minus(self.sent.position);
+                self.sent.position = self.prediction.reconcile(sequence, position);
+                state.player.position = self.sent.position.plus(unsent);
+            }
+            Message::PlayerMoved {
+                player,
+                delta,
+                yaw,
+                pitch,
+            } => {
+                if let Some(peer) = self.peers.get_mut(&player) {
+                    peer.transform = Transform {
+                        position: peer.transform.position.plus(delta),
+                        yaw,
+                        pitch,
+                    };
+                }
+            }
+            Message::AddReactor { factory, reactor } => {
+                if let Some(factory) = state.factories.get_mut(usize::from(factory)) {
+                    factory.add_reactor(reactor);
+                }
+            }
+            Message::RemoveReactor { factory, index } => {
+                if let Some(factory) = state.factories.get_mut(usize::from(factory)) {
+                    factory.remove_reactor(index as usize);
+                }
+            }
+            Message::AddBelt { factory, belt } => {
+                if let Some(factory) = state.factories.get_mut(usize::from(factory)) {
+                    factory.add_belt(belt);
+                }
+            }
+            Message::Reactors { factory, reactors } => {
+                if let Some(factory) = state.factories.get_mut(usize::from(factory)) {
+                    set_reactors(factory, &reactors);
+                }
+            }
+            Message::Belts { factory, belts } => {
+                if let Some(factory) = state.factories.get_mut(usize::from(factory)) {
+                    set_belts(factory, &belts);
+                }
+            }
+            // Only sent to the server
+            Message::Join { .. } | Message::Move { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::coords::PlayerCoord;
+
+    #[test]
+    fn test_prediction() {
+        let step = PlayerVector3::new(PlayerCoord::ONE, PlayerCoord::ZERO, PlayerCoord::ZERO);
+        let mut prediction = Prediction::default();
+        let first = prediction.predict(step);
+        let second = prediction.predict(step);
+        let _third = prediction.predict(step);
+        assert_eq!(prediction.pending.len(), 3);
+
+        // The server agrees with the first move
+        assert_eq!(
+            prediction.reconcile(first, step),
+            step.scale(PlayerCoord::from_i32(3))
+        );
+        assert_eq!(prediction.pending.len(), 2);
+
+        // The server stopped the second move at a wall, so the third is replayed from there
+        assert_eq!(
+            prediction.reconcile(second, step),
+            step.scale(PlayerCoord::from_i32(2))
+        );
+        assert_eq!(prediction.pending.len(), 1);
+
+        // Answers wrap around with the sequence numbers
+        let mut prediction = Prediction {
+            next_sequence: u32::MAX,
+            pending: VecDeque::new(),
+        };
+        let last = prediction.predict(step);
+        prediction.predict(step);
+        assert_eq!(prediction.reconcile(last, PlayerVector3::ZERO), step);
+        assert_eq!(
+            prediction.reconcile(0, PlayerVector3::ZERO),
+            PlayerVector3::ZERO
+        );
+        assert_eq!(prediction.pending.len(), 0);
+    }
+}
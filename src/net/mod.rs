@@ -0,0 +1,296 @@
+//! Playing together over a local network
+//!
+//! One player hosts with a [`Server`], which owns the real game state. Everyone else joins with
+//! a [`Client`], which moves its own player right away and corrects it when the server disagrees
+//! (see [`Prediction`](client::Prediction)), and asks the server to make any changes to factories.
+
+use crate::{
+    game::GameState,
+    math::coords::VectorConstants,
+    player::Player,
+    region::factory::{Belt, Factory, Reactor},
+    rl_helpers::DynRaylibDraw3D,
+};
+use protocol::{DecodeError, MAX_MESSAGE_LEN, Message, Transform};
+use raylib::prelude::*;
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+};
+
+mod client;
+pub mod protocol;
+mod server;
+
+pub use client::Client;
+pub use server::Server;
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    /// The other side sent something that isn't a message
+    Decode(DecodeError),
+    /// The other side sent a message longer than [`MAX_MESSAGE_LEN`]
+    TooLong(usize),
+    /// The other side closed the connection
+    Disconnected,
+    /// The server refused to let the client join
+    Rejected(String),
+}
+
+impl std::fmt::Display for NetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => error.fmt(f),
+            Self::Decode(error) => write!(f, "bad message: {error}"),
+            Self::TooLong(len) => write!(f, "message is too long ({len} bytes)"),
+            Self::Disconnected => f.write_str("disconnected"),
+            Self::Rejected(reason) => write!(f, "couldn't join: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for NetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Decode(error) => Some(error),
+            Self::TooLong(_) | Self::Disconnected | Self::Rejected(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for NetError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Sends and receives [`Message`]s without waiting for the network
+#[derive(Debug)]
+struct Connection {
+    stream: TcpStream,
+    /// Bytes received that aren't a whole message yet
+    incoming: Vec<u8>,
+    /// Bytes waiting for room to send
+    outgoing: Vec<u8>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        // Moves are tiny and frequent, so don't wait to fill packets
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+        })
+    }
+
+    /// Queue `message` to be sent by [`Self::flush`]
+    fn send(&mut self, message: &Message) {
+        self.outgoing.extend(message.encode());
+    }
+
+    /// Send as much as possible without waiting
+    fn flush(&mut self) -> Result<(), NetError> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(NetError::Disconnected),
+                Ok(n) => _ = self.outgoing.drain(..n),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Every whole message received since last time
+    fn receive(&mut self) -> Result<Vec<Message>, NetError> {
+        let mut buf = [0; 4096];
+        loop {
+            match self.stream.read(&mut buf) {
+                Ok(0) => return Err(NetError::Disconnected),
+                Ok(n) => self.incoming.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let mut messages = Vec::new();
+        let mut start = 0;
+        while let Some((len, rest)) = self.incoming[start..].split_first_chunk() {
+            let len = u32::from_le_bytes(*len) as usize;
+            if len > MAX_MESSAGE_LEN {
+                return Err(NetError::TooLong(len));
+            }
+            let Some(bytes) = rest.get(..len) else {
+                break;
+            };
+            messages.push(Message::decode(bytes).map_err(NetError::Decode)?);
+            start += size_of::<u32>() + len;
+        }
+        self.incoming.drain(..start);
+        Ok(messages)
+    }
+}
+
+/// Someone else in the game
+#[derive(Debug, Clone, PartialEq)]
+pub struct Peer {
+    pub name: String,
+    pub transform: Transform,
+}
+
+/// Draw everyone else in the game, relative to `player`
+pub fn draw_peers<'a>(
+    d: &mut dyn DynRaylibDraw3D,
+    player: &Player,
+    peers: impl IntoIterator<Item = &'a Peer>,
+) {
+    for peer in peers {
+        let base = peer.transform.position.minus(player.position).to_vec3();
+        d.draw_capsule(
+            base + Vector3::UP * Player::RADIUS,
+            base + Vector3::UP * (Player::HEIGHT - Player::RADIUS),
+            Player::RADIUS,
+            8,
+            4,
+            Color::ORANGE,
+        );
+    }
+}
+
+/// Playing with other people, either hosting or joined
+#[derive(Debug)]
+pub enum Session {
+    Host(Server),
+    Client(Client),
+}
+
+impl Session {
+    /// Send and receive everything that's happened since last frame
+    ///
+    /// Errors with the connection to a client only disconnect that client, but errors with the
+    /// connection to the server end the session.
+    pub fn update(&mut self, state: &mut GameState) -> Result<(), NetError> {
+        match self {
+            Self::Host(server) => {
+                server.update(state);
+                Ok(())
+            }
+            Self::Client(client) => client.update(state),
+        }
+    }
+
+    /// Everyone else in the game
+    pub fn peers(&self) -> Box<dyn Iterator<Item = &Peer> + '_> {
+        match self {
+            Self::Host(server) => Box::new(server.peers()),
+            Self::Client(client) => Box::new(client.peers()),
+        }
+    }
+}
+
+/// Where `player` is and which way they're looking
+const fn transform_of(player: &Player) -> Transform {
+    Transform {
+        position: player.position,
+        yaw: player.yaw,
+        pitch: player.pitch,
+    }
+}
+
+/// Replace every reactor in `factory` with `reactors`
+fn set_reactors(factory: &mut Factory, reactors: &[Reactor]) {
+    while factory.remove_reactor(0).is_some() {}
+    for reactor in reactors {
+        factory.add_reactor(reactor.clone());
+    }
+}
+
+/// Replace every belt in `factory` with `belts`
+fn set_belts(factory: &mut Factory, belts: &[Belt]) {
+    while factory.remove_belt(0).is_some() {}
+    for belt in belts {
+        factory.add_belt(belt.clone());
+    }
+}
+
+/// The machines in a factory as of the last time they were sent or received
+#[derive(Debug, Clone, Default)]
+struct Synced {
+    reactors: Vec<Reactor>,
+    belts: Vec<Belt>,
+}
+
+impl Synced {
+    fn of(factory: &Factory) -> Self {
+        Self {
+            reactors: factory.reactors().to_vec(),
+            belts: factory.belts().to_vec(),
+        }
+    }
+
+    /// Put `factory` back the way it was
+    fn restore(&self, factory: &mut Factory) {
+        if factory.reactors() != self.reactors {
+            set_reactors(factory, &self.reactors);
+        }
+        if factory.belts() != self.belts {
+            set_belts(factory, &self.belts);
+        }
+    }
+
+    /// Messages that change the factory at `index` from `self` to `factory`
+    ///
+    /// Uses the smaller add and remove messages when they're enough, and otherwise replaces
+    /// everything.
+    fn changes(&self, index: u16, factory: &Factory) -> Vec<Message> {
+        let mut messages = Vec::new();
+        let reactors = factory.reactors();
+        if let Some(added) = reactors.strip_prefix(self.reactors.as_slice()) {
+            messages.extend(added.iter().map(|reactor| Message::AddReactor {
+                factory: index,
+                reactor: reactor.clone(),
+            }));
+        } else if let Some(removed) = swap_removed(&self.reactors, reactors) {
+            messages.push(Message::RemoveReactor {
+                factory: index,
+                index: u32::try_from(removed).expect("factories have fewer reactors than that"),
+            });
+        } else {
+            messages.push(Message::Reactors {
+                factory: index,
+                reactors: reactors.to_vec(),
+            });
+        }
+        let belts = factory.belts();
+        if let Some(added) = belts.strip_prefix(self.belts.as_slice()) {
+            messages.extend(added.iter().map(|belt| Message::AddBelt {
+                factory: index,
+                belt: belt.clone(),
+            }));
+        } else {
+            messages.push(Message::Belts {
+                factory: index,
+                belts: belts.to_vec(),
+            });
+        }
+        messages
+    }
+}
+
+/// The index `old` had [`Vec::swap_remove`] called with to make `new`, if that's how it was made
+fn swap_removed<T: Clone + PartialEq>(old: &[T], new: &[T]) -> Option<usize> {
+    if new.len() + 1 != old.len() {
+        return None;
+    }
+    let idx = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let mut removed = old.to_vec();
+    removed.swap_remove(idx);
+    (removed == new).then_some(idx)
+}
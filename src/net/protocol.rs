@@ -0,0 +1,527 @@
+//! The messages sent between the server and its clients
+//!
+//! Every message is sent as its length in bytes as a little-endian `u32`, followed by a byte
+//! saying what kind of message it is and then its fields, little-endian and in order.
+
+use crate::{
+    math::coords::{FactoryVector3, PlayerCoord, PlayerVector3},
+    ordinals::{Cardinal2D, Ordinal2D},
+    region::factory::{Belt, BeltInputNode, BeltLevel, BeltNode, BeltOutputNode, Reactor},
+};
+
+/// Changes whenever messages change, so old clients can't join newer servers or vice versa
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// The longest a message can be, so a bad length can't make the receiver wait forever
+pub const MAX_MESSAGE_LEN: usize = 1 << 20;
+
+/// Identifies a player for as long as they're connected
+pub type PlayerId = u16;
+
+/// Where a player is and which way they're looking
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Transform {
+    pub position: PlayerVector3,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// Client to server, the first thing a client sends
+    Join { version: u16, name: String },
+    /// Server to client, accepting [`Self::Join`] with the client's id and where they start
+    Welcome {
+        player: PlayerId,
+        transform: Transform,
+    },
+    /// Server to client, refusing [`Self::Join`] before disconnecting
+    Rejected { reason: String },
+    /// Server to client, someone else is playing
+    PlayerJoined {
+        player: PlayerId,
+        name: String,
+        transform: Transform,
+    },
+    /// Server to client, someone else stopped playing
+    PlayerLeft { player: PlayerId },
+    /// Client to server, the client moved by `delta` since its last move
+    ///
+    /// Numbered so the client knows which of its moves [`Self::MoveAck`] is about.
+    Move {
+        sequence: u32,
+        delta: PlayerVector3,
+        yaw: f32,
+        pitch: f32,
+    },
+    /// Server to client, where the client actually is after the move numbered `sequence`
+    MoveAck {
+        sequence: u32,
+        position: PlayerVector3,
+    },
+    /// Server to client, someone else moved by `delta`
+    PlayerMoved {
+        player: PlayerId,
+        delta: PlayerVector3,
+        yaw: f32,
+        pitch: f32,
+    },
+    /// Client to server asks to build a reactor, server to client says one was built
+    AddReactor { factory: u16, reactor: Reactor },
+    /// Client to server asks to remove a reactor, server to client says one was removed
+    ///
+    /// Follows [`Factory::remove_reactor`](crate::region::factory::Factory::remove_reactor) in
+    /// moving the last reactor to `index`.
+    RemoveReactor { factory: u16, index: u32 },
+    /// Client to server asks to build a belt, server to client says one was built
+    AddBelt { factory: u16, belt: Belt },
+    /// Server to client, every reactor in a factory
+    Reactors {
+        factory: u16,
+        reactors: Vec<Reactor>,
+    },
+    /// Server to client, every belt in a factory
+    Belts { factory: u16, belts: Vec<Belt> },
+}
+
+/// Why bytes couldn't be read as a [`Message`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The message ended in the middle of a field
+    UnexpectedEnd,
+    /// The message has bytes left over after its last field
+    TrailingBytes,
+    /// The first byte of the message isn't a kind of message
+    UnknownMessage(u8),
+    /// A field has a value it can't have, like a direction that doesn't exist
+    InvalidValue,
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEnd => f.write_str("message ended early"),
+            Self::TrailingBytes => f.write_str("message is longer than expected"),
+            Self::UnknownMessage(tag) => write!(f, "unknown message {tag}"),
+            Self::InvalidValue => f.write_str("message contains an invalid value"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// The bytes of a message that haven't been read yet
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        let (bytes, rest) = self
+            .0
+            .split_first_chunk()
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        self.0 = rest;
+        Ok(*bytes)
+    }
+}
+
+/// Something that can be part of a message
+trait Field: Sized {
+    fn write(&self, buf: &mut Vec<u8>);
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError>;
+}
+
+macro_rules! impl_field_le {
+    ($($T:ty),* $(,)?) => {$(
+        impl Field for $T {
+            fn write(&self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+                r.take().map(Self::from_le_bytes)
+            }
+        }
+    )*};
+}
+
+impl_field_le!(u8, u16, u32, i16, i64, f32);
+
+impl<T: Field> Field for Vec<T> {
+    fn write(&self, buf: &mut Vec<u8>) {
+        u32::try_from(self.len())
+            .expect("messages are shorter than MAX_MESSAGE_LEN")
+            .write(buf);
+        for item in self {
+            item.write(buf);
+        }
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let len = u32::read(r)? as usize;
+        // Every field is at least a byte, so don't trust lengths longer than what's left
+        if len > r.0.len() {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        (0..len).map(|_| T::read(r)).collect()
+    }
+}
+
+impl Field for String {
+    fn write(&self, buf: &mut Vec<u8>) {
+        self.as_bytes().to_vec().write(buf);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Self::from_utf8(Vec::read(r)?).map_err(|_| DecodeError::InvalidValue)
+    }
+}
+
+impl Field for FactoryVector3 {
+    fn write(&self, buf: &mut Vec<u8>) {
+        self.x.write(buf);
+        self.y.write(buf);
+        self.z.write(buf);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Ok(Self::new(i16::read(r)?, i16::read(r)?, i16::read(r)?))
+    }
+}
+
+/// Sent exactly, so the server and clients agree on where everyone is
+impl Field for PlayerVector3 {
+    fn write(&self, buf: &mut Vec<u8>) {
+        self.x.to_bits().write(buf);
+        self.y.to_bits().write(buf);
+        self.z.to_bits().write(buf);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let mut coord = || i64::read(r).map(PlayerCoord::from_bits);
+        Ok(Self::new(coord()?, coord()?, coord()?))
+    }
+}
+
+impl Field for Ordinal2D {
+    fn write(&self, buf: &mut Vec<u8>) {
+        (*self as u8).write(buf);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Self::ALL
+            .get(usize::from(u8::read(r)?))
+            .copied()
+            .ok_or(DecodeError::InvalidValue)
+    }
+}
+
+impl Field for Cardinal2D {
+    fn write(&self, buf: &mut Vec<u8>) {
+        self.as_ordinal().write(buf);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Ordinal2D::read(r)?
+            .try_as_cardinal()
+            .ok_or(DecodeError::InvalidValue)
+    }
+}
+
+impl Field for Transform {
+    fn write(&self, buf: &mut Vec<u8>) {
+        self.position.write(buf);
+        self.yaw.write(buf);
+        self.pitch.write(buf);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Ok(Self {
+            position: PlayerVector3::read(r)?,
+            yaw: f32::read(r)?,
+            pitch: f32::read(r)?,
+        })
+    }
+}
+
+impl Field for Reactor {
+    fn write(&self, buf: &mut Vec<u8>) {
+        self.position.write(buf);
+        self.rotation.write(buf);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Ok(Self {
+            position: FactoryVector3::read(r)?,
+            rotation: Cardinal2D::read(r)?,
+        })
+    }
+}
+
+impl Field for BeltNode {
+    fn write(&self, buf: &mut Vec<u8>) {
+        self.position.write(buf);
+        self.rotation.write(buf);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Ok(Self {
+            position: FactoryVector3::read(r)?,
+            rotation: Ordinal2D::read(r)?,
+        })
+    }
+}
+
+impl Field for Belt {
+    fn write(&self, buf: &mut Vec<u8>) {
+        self.level.number().write(buf);
+        self.src.0.write(buf);
+        self.dst.0.write(buf);
+        self.path.write(buf);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        Ok(Self {
+            level: BeltLevel::from_number(u8::read(r)?).ok_or(DecodeError::InvalidValue)?,
+            src: BeltOutputNode(BeltNode::read(r)?),
+            dst: BeltInputNode(BeltNode::read(r)?),
+            path: Vec::read(r)?,
+        })
+    }
+}
+
+impl Message {
+    /// The message with its length in front, ready to send
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![0; size_of::<u32>()];
+        match self {
+            Self::Join { version, name } => {
+                0u8.write(&mut buf);
+                version.write(&mut buf);
+                name.write(&mut buf);
+            }
+            Self::Welcome { player, transform } => {
+                1u8.write(&mut buf);
+                player.write(&mut buf);
+                transform.write(&mut buf);
+            }
+            Self::Rejected { reason } => {
+                2u8.write(&mut buf);
+                reason.write(&mut buf);
+            }
+            Self::PlayerJoined {
+                player,
+                name,
+                transform,
+            } => {
+                3u8.write(&mut buf);
+                player.write(&mut buf);
+                name.write(&mut buf);
+                transform.write(&mut buf);
+            }
+            Self::PlayerLeft { player } => {
+                4u8.write(&mut buf);
+                player.write(&mut buf);
+            }
+            Self::Move {
+                sequence,
+                delta,
+                yaw,
+                pitch,
+            } => {
+                5u8.write(&mut buf);
+                sequence.write(&mut buf);
+                delta.write(&mut buf);
+                yaw.write(&mut buf);
+                pitch.write(&mut buf);
+            }
+            Self::MoveAck { sequence, position } => {
+                6u8.write(&mut buf);
+                sequence.write(&mut buf);
+                position.write(&mut buf);
+            }
+            Self::PlayerMoved {
+                player,
+                delta,
+                yaw,
+                pitch,
+            } => {
+                7u8.write(&mut buf);
+                player.write(&mut buf);
+                delta.write(&mut buf);
+                yaw.write(&mut buf);
+                pitch.write(&mut buf);
+            }
+            Self::AddReactor { factory, reactor } => {
+                8u8.write(&mut buf);
+                factory.write(&mut buf);
+                reactor.write(&mut buf);
+            }
+            Self::RemoveReactor { factory, index } => {
+                9u8.write(&mut buf);
+                factory.write(&mut buf);
+                index.write(&mut buf);
+            }
+            Self::AddBelt { factory, belt } => {
+                10u8.write(&mut buf);
+                factory.write(&mut buf);
+                belt.write(&mut buf);
+            }
+            Self::Reactors { factory, reactors } => {
+                11u8.write(&mut buf);
+                factory.write(&mut buf);
+                reactors.write(&mut buf);
+            }
+            Self::Belts { factory, belts } => {
+                12u8.write(&mut buf);
+                factory.write(&mut buf);
+                belts.write(&mut buf);
+            }
+        }
+        let len = u32::try_from(buf.len() - size_of::<u32>())
+            .expect("messages are shorter than MAX_MESSAGE_LEN");
+        buf[..size_of::<u32>()].copy_from_slice(&len.to_le_bytes());
+        buf
+    }
+
+    /// Read a message without its length in front
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let r = &mut Reader(bytes);
+        let message = match u8::read(r)? {
+            0 => Self::Join {
+                version: Field::read(r)?,
+                name: Field::read(r)?,
+            },
+            1 => Self::Welcome {
+                player: Field::read(r)?,
+                transform: Field::read(r)?,
+            },
+            2 => Self::Rejected {
+                reason: Field::read(r)?,
+            },
+            3 => Self::PlayerJoined {
+                player: Field::read(r)?,
+                name: Field::read(r)?,
+                transform: Field::read(r)?,
+            },
+            4 => Self::PlayerLeft {
+                player: Field::read(r)?,
+            },
+            5 => Self::Move {
+                sequence: Field::read(r)?,
+                delta: Field::read(r)?,
+                yaw: Field::read(r)?,
+                pitch: Field::read(r)?,
+            },
+            6 => Self::MoveAck {
+                sequence: Field::read(r)?,
+                position: Field::read(r)?,
+            },
+            7 => Self::PlayerMoved {
+                player: Field::read(r)?,
+                delta: Field::read(r)?,
+                yaw: Field::read(r)?,
+                pitch: Field::read(r)?,
+            },
+            8 => Self::AddReactor {
+                factory: Field::read(r)?,
+                reactor: Field::read(r)?,
+            },
+            9 => Self::RemoveReactor {
+                factory: Field::read(r)?,
+                index: Field::read(r)?,
+            },
+            10 => Self::AddBelt {
+                factory: Field::read(r)?,
+                belt: Field::read(r)?,
+            },
+            11 => Self::Reactors {
+                factory: Field::read(r)?,
+                reactors: Field::read(r)?,
+            },
+            12 => Self::Belts {
+                factory: Field::read(r)?,
+                belts: Field::read(r)?,
+            },
+            tag => return Err(DecodeError::UnknownMessage(tag)),
+        };
+        if r.0.is_empty() {
+            Ok(message)
+        } else {
+            Err(DecodeError::TrailingBytes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::coords::VectorConstants;
+
+    #[test]
+    fn test_round_trip() {
+        let belt = Belt {
+            level: BeltLevel::Mk3,
+            src: BeltOutputNode(BeltNode {
+                position: FactoryVector3::new(-1, 0, 2),
+                rotation: Ordinal2D::Southwest,
+            }),
+            dst: BeltInputNode(BeltNode::default()),
+            path: vec![FactoryVector3::new(-1, 0, 2), FactoryVector3::ZERO],
+        };
+        let messages = [
+            Message::Join {
+                version: PROTOCOL_VERSION,
+                name: "ünïcödé".to_string(),
+            },
+            Message::Move {
+                sequence: 7,
+                // Fractions that don't fit in an f32
+                delta: PlayerVector3::new(
+                    PlayerCoord::from_bits(-1),
+                    PlayerCoord::MAX,
+                    PlayerCoord::from_bits(0x1234_5678_9abc),
+                ),
+                yaw: 1.5,
+                pitch: -0.25,
+            },
+            Message::Reactors {
+                factory: 1,
+                reactors: vec![Reactor {
+                    position: FactoryVector3::new(3, 0, -4),
+                    rotation: Cardinal2D::South,
+                }],
+            },
+            Message::Belts {
+                factory: 0,
+                belts: vec![belt],
+            },
+        ];
+        for message in messages {
+            let bytes = message.encode();
+            let len = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+            assert_eq!(len as usize, bytes.len() - 4);
+            assert_eq!(Message::decode(&bytes[4..]), Ok(message));
+        }
+
+        assert_eq!(Message::decode(&[]), Err(DecodeError::UnexpectedEnd));
+        assert_eq!(
+            Message::decode(&[200]),
+            Err(DecodeError::UnknownMessage(200))
+        );
+        assert_eq!(
+            Message::decode(&[4, 1, 0, 0]),
+            Err(DecodeError::TrailingBytes)
+        );
+        // A reactor facing northeast
+        assert_eq!(
+            Message::decode(&[8, 0, 0, 0, 0, 0, 0, 0, 0, 1]),
+            Err(DecodeError::InvalidValue)
+        );
+        // A list claiming to be longer than the message
+        assert_eq!(
+            Message::decode(&[11, 0, 0, 255, 255, 255, 255]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+    }
+}
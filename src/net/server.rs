@@ -0,0 +1,432 @@
+use super::{Connection, NetError, Peer, Synced, transform_of};
+use crate::{
+    game::GameState,
+    math::{
+        bounds::{Bounds, PlayerBounds, SpacialBounds},
+        coords::{PlayerCoord, PlayerVector3},
+    },
+    net::protocol::{Message, PROTOCOL_VERSION, PlayerId, Transform},
+    player::Player,
+    region::RegionId,
+    time::{FixedTimestep, MAX_TICKS_PER_FRAME},
+};
+use std::{
+    io,
+    net::{Ipv4Addr, TcpListener},
+};
+
+/// The player hosting the server
+pub const HOST: PlayerId = 0;
+
+/// Farthest a client can move in one [`Message::Move`]
+///
+/// Clients send one a frame, so this is as far as [`Player::MAX_SPEED`] goes in the longest frame
+/// the game runs ticks for. Anything farther is cut short rather than trusted.
+#[allow(clippy::cast_precision_loss, reason = "the tick count is small")]
+const MAX_STEP: PlayerCoord =
+    PlayerCoord::from_f32(Player::MAX_SPEED * FixedTimestep::STEP * MAX_TICKS_PER_FRAME as f32);
+
+#[derive(Debug)]
+struct RemoteClient {
+    connection: Connection,
+    /// Once they've joined, who they are
+    joined: Option<(PlayerId, Peer)>,
+}
+
+/// Hosts a game on the local network, keeping the real game state and telling clients about it
+#[derive(Debug)]
+pub struct Server {
+    listener: TcpListener,
+    /// The host's name, shown to clients, or [`None`] if no one is playing on the server
+    name: Option<String>,
+    clients: Vec<RemoteClient>,
+    next_id: PlayerId,
+    /// Where clients think the host is
+    host: Transform,
+    /// What clients think is in each factory
+    synced: Vec<Synced>,
+}
+
+impl Server {
+    /// Start accepting clients on `port`, playing as `name`
+    pub fn host(port: u16, name: String, state: &GameState) -> io::Result<Self> {
+        Self::bind(port, Some(name), state)
+    }
+
+    /// Start accepting clients on `port` without playing
+    pub fn dedicated(port: u16, state: &GameState) -> io::Result<Self> {
+        Self::bind(port, None, state)
+    }
+
+    fn bind(port: u16, name: Option<String>, state: &GameState) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            name,
+            clients: Vec::new(),
+            next_id: HOST + 1,
+            host: transform_of(&state.player),
+            synced: state.factories.iter().map(Synced::of).collect(),
+        })
+    }
+
+    /// Everyone who has joined
+    pub fn peers(&self) -> impl Iterator<Item = &Peer> {
+        (self.clients.iter()).filter_map(|client| client.joined.as_ref().map(|(_, peer)| peer))
+    }
+
+    /// Send `message` to everyone who has joined, except `except`
+    fn broadcast(&mut self, message: &Message, except: Option<PlayerId>) {
+        for client in &mut self.clients {
+            if let Some((id, _)) = client.joined
+                && Some(id) != except
+            {
+                client.connection.send(message);
+            }
+        }
+    }
+
+    /// Accept new clients, act on their messages, and tell everyone what changed
+    pub fn update(&mut self, state: &mut GameState) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, address)) => match Connection::new(stream) {
+                    Ok(connection) => self.clients.push(RemoteClient {
+                        connection,
+                        joined: None,
+                    }),
+                    Err(e) => eprintln!("failed to accept {address}: {e}"),
+                },
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    eprintln!("failed to accept client: {e}");
+                    break;
+                }
+            }
+        }
+
+        let mut disconnected = Vec::new();
+        for idx in 0..self.clients.len() {
+            let result = self.clients[idx].connection.receive().and_then(|messages| {
+                messages
+                    .into_iter()
+                    .try_for_each(|message| self.handle(idx, message, state))
+            });
+            if let Err(e) = result {
+                if !matches!(e, NetError::Disconnected) {
+                    eprintln!("disconnecting client: {e}");
+                }
+                disconnected.push(idx);
+            }
+        }
+
+        let host = transform_of(&state.player);
+        if self.name.is_some() && host != self.host {
+            let message = Message::PlayerMoved {
+                player: HOST,
+                delta: host.position.minus(self.host.position),
+                yaw: host.yaw,
+                pitch: host.pitch,
+            };
+            self.broadcast(&message, None);
+            self.host = host;
+        }
+        for (index, factory) in (0..).zip(&state.factories) {
+            let synced = &mut self.synced[usize::from(index)];
+            let changes = synced.changes(index, factory);
+            if !changes.is_empty() {
+                *synced = Synced::of(factory);
+                for message in changes {
+                    self.broadcast(&message, None);
+                }
+            }
+        }
+
+        for (idx, client) in self.clients.iter_mut().enumerate() {
+            if let Err(e) = client.connection.flush() {
+                if !matches!(e, NetError::Disconnected) {
+                    eprintln!("disconnecting client: {e}");
+                }
+                disconnected.push(idx);
+            }
+        }
+        disconnected.sort_unstable();
+        disconnected.dedup();
+        for idx in disconnected.into_iter().rev() {
+            if let Some((player, _)) = self.clients.remove(idx).joined {
+                self.broadcast(&Message::PlayerLeft { player }, None);
+            }
+        }
+    }
+
+    /// Act on a message from the client at `idx`
+    ///
+    /// Returns an error if the client should be disconnected.
+    fn handle(
+        &mut self,
+        idx: usize,
+        message: Message,
+        state: &mut GameState,
+    ) -> Result<(), NetError> {
+        let joined = self.clients[idx].joined.as_ref().map(|(id, _)| *id);
+        match (message, joined) {
+            (Message::Join { version, name }, None) => self.join(idx, version, name)?,
+            (
+                Message::Move {
+                    sequence,
+                    delta,
+                    yaw,
+                    pitch,
+                },
+                Some(id),
+            ) if yaw.is_finite() && pitch.is_finite() => {
+                let Some((_, peer)) = &mut self.clients[idx].joined else {
+                    unreachable!("the client has joined");
+                };
+                let delta = resolve_move(state, peer.transform.position, limit_step(delta));
+                peer.transform = Transform {
+                    position: peer.transform.position.plus(delta),
+                    yaw,
+                    pitch,
+                };
+                let position = peer.transform.position;
+                self.clients[idx]
+                    .connection
+                    .send(&Message::MoveAck { sequence, position });
+                let moved = Message::PlayerMoved {
+                    player: id,
+                    delta,
+                    yaw,
+                    pitch,
+                };
+                self.broadcast(&moved, Some(id));
+            }
+            (Message::AddReactor { factory, reactor }, Some(_)) => {
+                // Checked first, since its bounds could overflow far outside
+                if let Some(factory) = state.factories.get_mut(usize::from(factory))
+                    && factory.bounds.contains(&reactor.position)
+                    && factory.is_vacant(&reactor.bounds())
+                {
+                    factory.add_reactor(reactor);
+                }
+            }
+            (Message::RemoveReactor { factory, index }, Some(_)) => {
+                if let Some(factory) = state.factories.get_mut(usize::from(factory)) {
+                    factory.remove_reactor(index as usize);
+                }
+            }
+            (Message::AddBelt { factory, belt }, Some(_)) => {
+                if let Some(factory) = state.factories.get_mut(usize::from(factory))
+                    && factory.is_route_vacant(&belt.path)
+                {
+                    factory.add_belt(belt);
+                }
+            }
+            // Clients can't do anything else, can only join once, and can't look in directions
+            // that aren't numbers
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Let the client at `idx` join, and tell them everything they need to know
+    fn join(&mut self, idx: usize, version: u16, name: String) -> Result<(), NetError> {
+        if version != PROTOCOL_VERSION {
+            let reason = format!("the server is on version {PROTOCOL_VERSION}, not {version}");
+            let connection = &mut self.clients[idx].connection;
+            connection.send(&Message::Rejected {
+                reason: reason.clone(),
+            });
+            // Try to say why before disconnecting
+            _ = connection.flush();
+            return Err(NetError::Rejected(reason));
+        }
+
+        let player = self.next_id;
+        self.next_id = self.next_id.checked_add(1).unwrap_or(HOST + 1);
+        // Everyone starts where the host is
+        let transform = self.host;
+        let mut welcome = vec![Message::Welcome { player, transform }];
+        welcome.extend(self.name.clone().map(|name| Message::PlayerJoined {
+            player: HOST,
+            name,
+            transform: self.host,
+        }));
+        welcome.extend(self.clients.iter().filter_map(|client| {
+            let (id, peer) = client.joined.as_ref()?;
+            Some(Message::PlayerJoined {
+                player: *id,
+                name: peer.name.clone(),
+                transform: peer.transform,
+            })
+        }));
+        for (factory, synced) in (0..).zip(&self.synced) {
+            welcome.push(Message::Reactors {
+                factory,
+                reactors: synced.reactors.clone(),
+            });
+            welcome.push(Message::Belts {
+                factory,
+                belts: synced.belts.clone(),
+            });
+        }
+        for message in &welcome {
+            self.clients[idx].connection.send(message);
+        }
+
+        let joined = Message::PlayerJoined {
+            player,
+            name: name.clone(),
+            transform,
+        };
+        self.broadcast(&joined, None);
+        self.clients[idx].joined = Some((player, Peer { name, transform }));
+        Ok(())
+    }
+}
+
+/// `delta` cut short to [`MAX_STEP`], keeping its direction
+fn limit_step(delta: PlayerVector3) -> PlayerVector3 {
+    // Each part first, so the length can't overflow
+    let limit = PlayerVector3::new(MAX_STEP, MAX_STEP, MAX_STEP);
+    let delta = delta.max(limit.negate()).min(limit);
+    let length = delta.length();
+    if length > MAX_STEP {
+        delta.scale(MAX_STEP / length)
+    } else {
+        delta
+    }
+}
+
+/// How far a player at `from` actually moves when they try to move by `motion`, stopping at
+/// anything in the way
+fn resolve_move(state: &GameState, from: PlayerVector3, motion: PlayerVector3) -> PlayerVector3 {
+    let region = RegionId::containing(&from, &state.factories, &state.lab, &state.world).to_region(
+        &state.factories,
+        &state.lab,
+        &state.world,
+    );
+    let bounds = Player::bounds_at(from);
+    let obstacles = region.obstacles(&bounds.union(&PlayerBounds {
+        min: bounds.min.plus(motion),
+        max: bounds.max.plus(motion),
+    }));
+    bounds.sweep(motion, &obstacles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::coords::{FactoryVector3, VectorConstants},
+        ordinals::Cardinal2D,
+        region::factory::{Belt, BeltInputNode, BeltLevel, BeltNode, BeltOutputNode, Reactor},
+    };
+    use std::net::TcpStream;
+
+    /// A server with one client who has joined as player 1 at the origin
+    fn joined_server(state: &GameState) -> (Server, TcpStream) {
+        let mut server = Server::dedicated(0, state).unwrap();
+        let stream = TcpStream::connect(server.listener.local_addr().unwrap()).unwrap();
+        let (accepted, _) = loop {
+            match server.listener.accept() {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::yield_now(),
+                result => break result.unwrap(),
+            }
+        };
+        let transform = Transform {
+            position: PlayerVector3::ZERO,
+            yaw: 0.0,
+            pitch: 0.0,
+        };
+        server.clients.push(RemoteClient {
+            connection: Connection::new(accepted).unwrap(),
+            joined: Some((
+                1,
+                Peer {
+                    name: "client".to_string(),
+                    transform,
+                },
+            )),
+        });
+        (server, stream)
+    }
+
+    fn peer_position(server: &Server) -> PlayerVector3 {
+        server.peers().next().unwrap().transform.position
+    }
+
+    #[test]
+    fn test_move_limit() {
+        let mut state = GameState::new(Player::new(PlayerVector3::ZERO, 0.0, 0.0, 45.0, 1.0));
+        let (mut server, _stream) = joined_server(&state);
+        let huge = Message::Move {
+            sequence: 0,
+            delta: PlayerVector3::new(PlayerCoord::MAX, PlayerCoord::MAX, PlayerCoord::MIN),
+            yaw: 0.0,
+            pitch: 0.0,
+        };
+        server.handle(0, huge, &mut state).unwrap();
+        let moved = peer_position(&server);
+        assert!(
+            moved.length() <= MAX_STEP,
+            "moved {} when the most is {MAX_STEP}",
+            moved.length()
+        );
+
+        let turned = Message::Move {
+            sequence: 1,
+            delta: PlayerVector3::ZERO,
+            yaw: f32::NAN,
+            pitch: 0.0,
+        };
+        server.handle(0, turned, &mut state).unwrap();
+        assert!(server.peers().next().unwrap().transform.yaw.is_finite());
+    }
+
+    #[test]
+    fn test_belt_overlap() {
+        let mut state = GameState::new(Player::new(PlayerVector3::ZERO, 0.0, 0.0, 45.0, 1.0));
+        let (mut server, _stream) = joined_server(&state);
+        let belt = |from: FactoryVector3, to: FactoryVector3| Message::AddBelt {
+            factory: 0,
+            belt: Belt {
+                level: BeltLevel::Mk1,
+                src: BeltOutputNode(BeltNode::default()),
+                dst: BeltInputNode(BeltNode::default()),
+                path: vec![from, to],
+            },
+        };
+        // Straight through the reactor at 5,0,-6
+        let through = belt(
+            FactoryVector3::new(0, 0, -6),
+            FactoryVector3::new(12, 0, -6),
+        );
+        server.handle(0, through, &mut state).unwrap();
+        assert!(state.factories[0].belts().is_empty());
+
+        let far = belt(
+            FactoryVector3::new(0, 0, 10),
+            FactoryVector3::new(i16::MIN, 0, 10),
+        );
+        server.handle(0, far, &mut state).unwrap();
+        let edge = belt(FactoryVector3::MAX, FactoryVector3::MAX);
+        server.handle(0, edge, &mut state).unwrap();
+        let reactors = state.factories[0].reactors().len();
+        let reactor = Message::AddReactor {
+            factory: 0,
+            reactor: Reactor {
+                position: FactoryVector3::MAX,
+                rotation: Cardinal2D::East,
+            },
+        };
+        server.handle(0, reactor, &mut state).unwrap();
+        assert!(state.factories[0].belts().is_empty());
+        assert_eq!(state.factories[0].reactors().len(), reactors);
+
+        let clear = belt(FactoryVector3::new(0, 0, 10), FactoryVector3::new(4, 0, 10));
+        server.handle(0, clear, &mut state).unwrap();
+        assert_eq!(state.factories[0].belts().len(), 1);
+    }
+}
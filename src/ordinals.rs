@@ -1,4 +1,5 @@
 #![rustfmt::skip]
+use crate::math::coords::FactoryVector3;
 use raylib::prelude::*;
 
 /// A 2D cardinal direction
@@ -60,6 +61,63 @@ impl Cardinal2D {
         // SAFETY: Cardinal add/sub guaranteed to result in a cardinal
         unsafe { value.as_cardinal_unchecked() }
     }
+
+    /// The rotation of turning by `self` and then by `rhs`
+    #[inline]
+    pub const fn compose(self, rhs: Self) -> Self {
+        self.plus(rhs)
+    }
+
+    /// The rotation that undoes `self`
+    #[inline]
+    pub const fn inverse(self) -> Self {
+        Self::East.minus(self)
+    }
+
+    /// Rotate `v` about the vertical axis through the origin
+    ///
+    /// The same rotation as [`Ordinal2D::matrix`], but exact.
+    #[inline]
+    pub const fn rotate_vector(self, v: FactoryVector3) -> FactoryVector3 {
+        let FactoryVector3 { x, y, z } = v;
+        match self {
+            Self::East  => FactoryVector3::new( x, y,  z),
+            Self::North => FactoryVector3::new( z, y, -x),
+            Self::West  => FactoryVector3::new(-x, y, -z),
+            Self::South => FactoryVector3::new(-z, y,  x),
+        }
+    }
+
+    /// The minimum corner of the block at `position` after rotating the block about the vertical
+    /// axis through the origin
+    ///
+    /// Unlike [`Self::rotate_vector`], this accounts for the block being a meter across, so the
+    /// result is where the rotated block actually is.
+    #[inline]
+    pub const fn rotate_block(self, position: FactoryVector3) -> FactoryVector3 {
+        let near = self.rotate_vector(position);
+        let far = self.rotate_vector(position.plus(FactoryVector3::new(1, 0, 1)));
+        near.min(far)
+    }
+
+    /// Mirror east and west, the same as [`Ordinal2D::flip_x`]
+    #[inline]
+    pub const fn flip_x(self) -> Self {
+        Self::West.minus(self)
+    }
+
+    /// Mirror north and south, the same as [`Ordinal2D::flip_z`]
+    #[inline]
+    pub const fn flip_z(self) -> Self {
+        self.inverse()
+    }
+}
+
+impl From<Cardinal2D> for Quaternion {
+    #[inline]
+    fn from(value: Cardinal2D) -> Self {
+        value.as_ordinal().into()
+    }
 }
 
 impl std::ops::Sub for Cardinal2D {
@@ -85,6 +143,12 @@ pub enum Ordinal2D {
 }
 
 impl Ordinal2D {
+    /// Every ordinal, in the order of their discriminants
+    pub const ALL: [Self; 8] = [
+        Self::East, Self::Northeast, Self::North, Self::Northwest,
+        Self::West, Self::Southwest, Self::South, Self::Southeast,
+    ];
+
     #[inline]
     pub const fn try_as_cardinal(self) -> Option<Cardinal2D> {
         match self {
@@ -123,7 +187,7 @@ impl Ordinal2D {
     /// Converts orientation to an angle in radians
     #[inline]
     pub const fn radians(self) -> f32 {
-        self as u8 as f32 * std::f32::consts::FRAC_PI_8
+        self as u8 as f32 * std::f32::consts::FRAC_PI_4
     }
 
     /// Calculates the sine, cosine, and tangent of the orientation simultaneously
@@ -186,6 +250,50 @@ impl Ordinal2D {
         // SAFETY: `n` is masked to within enum discriminant range
         unsafe { std::mem::transmute::<u8, Self>(n) }
     }
+
+    /// The rotation of turning by `self` and then by `rhs`
+    #[inline]
+    pub const fn compose(self, rhs: Self) -> Self {
+        self.plus(rhs)
+    }
+
+    /// The rotation that undoes `self`
+    #[inline]
+    pub const fn inverse(self) -> Self {
+        Self::East.minus(self)
+    }
+
+    /// Rotate `v` about the vertical axis through the origin, the same as [`Self::matrix`]
+    #[inline]
+    pub const fn rotate_vector(self, v: Vector3) -> Vector3 {
+        let (cos, sin, _) = self.cos_sin_tan();
+        Vector3::new(
+             cos * v.x + sin * v.z,
+             v.y,
+            -sin * v.x + cos * v.z,
+        )
+    }
+
+    /// Mirror east and west, leaving north and south as they are
+    #[inline]
+    pub const fn flip_x(self) -> Self {
+        Self::West.minus(self)
+    }
+
+    /// Mirror north and south, leaving east and west as they are
+    ///
+    /// This negates the angle, so it's the same as [`Self::inverse`].
+    #[inline]
+    pub const fn flip_z(self) -> Self {
+        self.inverse()
+    }
+}
+
+impl From<Ordinal2D> for Quaternion {
+    #[inline]
+    fn from(value: Ordinal2D) -> Self {
+        Self::from_rotation_y(value.radians())
+    }
 }
 
 /// A 3D cardinal direction
@@ -224,6 +332,16 @@ pub enum Ordinal3D {
 }
 
 impl Ordinal3D {
+    /// Every ordinal, in the order of their discriminants
+    pub const ALL: [Self; 18] = [
+        Self::Down,
+        Self::EastDown, Self::NorthDown, Self::WestDown, Self::SouthDown,
+        Self::East, Self::Northeast, Self::North, Self::Northwest,
+        Self::West, Self::Southwest, Self::South, Self::Southeast,
+        Self::EastUp, Self::NorthUp, Self::WestUp, Self::SouthUp,
+        Self::Up,
+    ];
+
     /// The direction of the ordinal
     #[inline]
     pub const fn direction(self) -> Vector3 {
@@ -249,4 +367,131 @@ impl Ordinal3D {
             Self::Up        => Vector3::new(           0.0,            1.0,            0.0),
         }
     }
+
+    /// Turn about the vertical axis by `rotation`, the same as [`Ordinal2D::compose`]
+    #[inline]
+    pub const fn rotate_y(self, rotation: Cardinal2D) -> Self {
+        const fn tilt(direction: Cardinal2D, is_up: bool) -> Ordinal3D {
+            match (direction, is_up) {
+                (Cardinal2D::East,  false) => Ordinal3D::EastDown,
+                (Cardinal2D::North, false) => Ordinal3D::NorthDown,
+                (Cardinal2D::West,  false) => Ordinal3D::WestDown,
+                (Cardinal2D::South, false) => Ordinal3D::SouthDown,
+                (Cardinal2D::East,  true ) => Ordinal3D::EastUp,
+                (Cardinal2D::North, true ) => Ordinal3D::NorthUp,
+                (Cardinal2D::West,  true ) => Ordinal3D::WestUp,
+                (Cardinal2D::South, true ) => Ordinal3D::SouthUp,
+            }
+        }
+        let turn = rotation.as_ordinal();
+        match self {
+            Self::Down | Self::Up => self,
+            Self::EastDown  => tilt(Cardinal2D::East .compose(rotation), false),
+            Self::NorthDown => tilt(Cardinal2D::North.compose(rotation), false),
+            Self::WestDown  => tilt(Cardinal2D::West .compose(rotation), false),
+            Self::SouthDown => tilt(Cardinal2D::South.compose(rotation), false),
+            Self::EastUp    => tilt(Cardinal2D::East .compose(rotation), true ),
+            Self::NorthUp   => tilt(Cardinal2D::North.compose(rotation), true ),
+            Self::WestUp    => tilt(Cardinal2D::West .compose(rotation), true ),
+            Self::SouthUp   => tilt(Cardinal2D::South.compose(rotation), true ),
+            Self::East      => Ordinal2D::East     .compose(turn).as_3d(),
+            Self::Northeast => Ordinal2D::Northeast.compose(turn).as_3d(),
+            Self::North     => Ordinal2D::North    .compose(turn).as_3d(),
+            Self::Northwest => Ordinal2D::Northwest.compose(turn).as_3d(),
+            Self::West      => Ordinal2D::West     .compose(turn).as_3d(),
+            Self::Southwest => Ordinal2D::Southwest.compose(turn).as_3d(),
+            Self::South     => Ordinal2D::South    .compose(turn).as_3d(),
+            Self::Southeast => Ordinal2D::Southeast.compose(turn).as_3d(),
+        }
+    }
+
+    /// Mirror east and west, the same as [`Ordinal2D::flip_x`]
+    #[inline]
+    pub const fn flip_x(self) -> Self {
+        match self {
+            Self::EastDown  => Self::WestDown,
+            Self::WestDown  => Self::EastDown,
+            Self::EastUp    => Self::WestUp,
+            Self::WestUp    => Self::EastUp,
+            Self::Down | Self::NorthDown | Self::SouthDown
+            | Self::Up | Self::NorthUp | Self::SouthUp => self,
+            Self::East      => Ordinal2D::East     .flip_x().as_3d(),
+            Self::Northeast => Ordinal2D::Northeast.flip_x().as_3d(),
+            Self::North     => Ordinal2D::North    .flip_x().as_3d(),
+            Self::Northwest => Ordinal2D::Northwest.flip_x().as_3d(),
+            Self::West      => Ordinal2D::West     .flip_x().as_3d(),
+            Self::Southwest => Ordinal2D::Southwest.flip_x().as_3d(),
+            Self::South     => Ordinal2D::South    .flip_x().as_3d(),
+            Self::Southeast => Ordinal2D::Southeast.flip_x().as_3d(),
+        }
+    }
+
+    /// Mirror north and south, the same as [`Ordinal2D::flip_z`]
+    #[inline]
+    pub const fn flip_z(self) -> Self {
+        match self {
+            Self::NorthDown => Self::SouthDown,
+            Self::SouthDown => Self::NorthDown,
+            Self::NorthUp   => Self::SouthUp,
+            Self::SouthUp   => Self::NorthUp,
+            Self::Down | Self::EastDown | Self::WestDown
+            | Self::Up | Self::EastUp | Self::WestUp => self,
+            Self::East      => Ordinal2D::East     .flip_z().as_3d(),
+            Self::Northeast => Ordinal2D::Northeast.flip_z().as_3d(),
+            Self::North     => Ordinal2D::North    .flip_z().as_3d(),
+            Self::Northwest => Ordinal2D::Northwest.flip_z().as_3d(),
+            Self::West      => Ordinal2D::West     .flip_z().as_3d(),
+            Self::Southwest => Ordinal2D::Southwest.flip_z().as_3d(),
+            Self::South     => Ordinal2D::South    .flip_z().as_3d(),
+            Self::Southeast => Ordinal2D::Southeast.flip_z().as_3d(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::coords::VectorConstants;
+
+    #[test]
+    fn test_rotation() {
+        let all = [Cardinal2D::East, Cardinal2D::North, Cardinal2D::West, Cardinal2D::South];
+        let v = FactoryVector3::new(2, 1, 3);
+        for a in all {
+            assert_eq!(a.compose(a.inverse()), Cardinal2D::East);
+            assert_eq!(a.inverse().rotate_vector(a.rotate_vector(v)), v);
+            let rotated = a.rotate_vector(v).as_vec3();
+            assert!(Quaternion::from(a).mul_vec3(v.as_vec3()).abs_diff_eq(rotated, 1e-5));
+            assert!(a.as_ordinal().rotate_vector(v.as_vec3()).abs_diff_eq(rotated, 1e-5));
+            for b in all {
+                assert_eq!(a.compose(b).rotate_vector(v), b.rotate_vector(a.rotate_vector(v)));
+            }
+            // The rotated block covers the same space as rotating its corners
+            let block = a.rotate_block(v);
+            let corner = a.rotate_vector(v);
+            let far = a.rotate_vector(v + FactoryVector3::new(1, 0, 1));
+            assert_eq!(block, corner.min(far));
+            assert_eq!(block + FactoryVector3::new(1, 0, 1), corner.max(far));
+        }
+        assert_eq!(Cardinal2D::North.rotate_vector(FactoryVector3::X), FactoryVector3::NEG_Z);
+    }
+
+    #[test]
+    fn test_flip() {
+        let flip_x = |v: Vector3| Vector3::new(-v.x, v.y,  v.z);
+        let flip_z = |v: Vector3| Vector3::new( v.x, v.y, -v.z);
+        for a in Ordinal2D::ALL {
+            assert_eq!(a.flip_x().flip_x(), a);
+            assert!(a.flip_x().direction3().abs_diff_eq(flip_x(a.direction3()), 1e-5));
+            assert!(a.flip_z().direction3().abs_diff_eq(flip_z(a.direction3()), 1e-5));
+            if let Some(cardinal) = a.try_as_cardinal() {
+                assert_eq!(cardinal.flip_x().as_ordinal(), a.flip_x());
+                assert_eq!(cardinal.flip_z().as_ordinal(), a.flip_z());
+            }
+        }
+        for a in Ordinal3D::ALL {
+            assert!(a.flip_x().direction().abs_diff_eq(flip_x(a.direction()), 1e-5), "{a:?}");
+            assert!(a.flip_z().direction().abs_diff_eq(flip_z(a.direction()), 1e-5), "{a:?}");
+        }
+    }
 }
@@ -0,0 +1,152 @@
+//! Colors that say something, with choices for colorblind players
+//!
+//! Highlights and the HUD pick their colors from the current [`Palette`] by what they mean, like
+//! [`Palette::invalid`] for a belt that can't be placed, rather than by name. The palette is
+//! chosen with a [`ColorVision`] in the settings, and changing it takes effect the next frame.
+
+use raylib::prelude::*;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Index of the current [`ColorVision`] in [`ColorVision::ALL`]
+static COLOR_VISION: AtomicU8 = AtomicU8::new(0);
+
+/// The kinds of color vision there's a [`Palette`] for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColorVision {
+    #[default]
+    Normal,
+    /// Red and green look alike, from weak green cones
+    Deuteranopia,
+    /// Red and green look alike and red looks dark, from weak red cones
+    Protanopia,
+    /// Blue and yellow look alike, from weak blue cones
+    Tritanopia,
+}
+
+impl ColorVision {
+    pub const ALL: [Self; 4] = [
+        Self::Normal,
+        Self::Deuteranopia,
+        Self::Protanopia,
+        Self::Tritanopia,
+    ];
+
+    /// What the kind is called in the settings file
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Deuteranopia => "deuteranopia",
+            Self::Protanopia => "protanopia",
+            Self::Tritanopia => "tritanopia",
+        }
+    }
+
+    /// Colors that can be told apart with this kind of color vision
+    ///
+    /// The colorblind palettes are picked from the Okabe-Ito colors, which stay distinct for
+    /// most kinds of colorblindness.
+    pub const fn palette(self) -> Palette {
+        match self {
+            Self::Normal => Palette {
+                highlight: Color::new(253, 249, 0, 255),
+                valid: Color::new(0, 158, 47, 255),
+                invalid: Color::new(230, 41, 55, 255),
+                warning: Color::new(255, 161, 0, 255),
+                selection: Color::new(102, 191, 255, 255),
+                health: Color::new(230, 41, 55, 255),
+                milestone: Color::new(255, 208, 64, 255),
+                waypoint: Color::new(96, 200, 255, 255),
+            },
+            Self::Deuteranopia => Palette {
+                highlight: Color::new(240, 228, 66, 255),
+                valid: Color::new(0, 114, 178, 255),
+                invalid: Color::new(213, 94, 0, 255),
+                warning: Color::new(204, 121, 167, 255),
+                selection: Color::new(86, 180, 233, 255),
+                health: Color::new(213, 94, 0, 255),
+                milestone: Color::new(240, 228, 66, 255),
+                waypoint: Color::new(86, 180, 233, 255),
+            },
+            Self::Protanopia => Palette {
+                highlight: Color::new(240, 228, 66, 255),
+                valid: Color::new(0, 114, 178, 255),
+                invalid: Color::new(230, 159, 0, 255),
+                warning: Color::new(204, 121, 167, 255),
+                selection: Color::new(86, 180, 233, 255),
+                health: Color::new(230, 159, 0, 255),
+                milestone: Color::new(240, 228, 66, 255),
+                waypoint: Color::new(86, 180, 233, 255),
+            },
+            Self::Tritanopia => Palette {
+                highlight: Color::new(255, 255, 255, 255),
+                valid: Color::new(0, 158, 115, 255),
+                invalid: Color::new(213, 94, 0, 255),
+                warning: Color::new(204, 121, 167, 255),
+                selection: Color::new(0, 190, 190, 255),
+                health: Color::new(213, 94, 0, 255),
+                milestone: Color::new(204, 121, 167, 255),
+                waypoint: Color::new(0, 158, 115, 255),
+            },
+        }
+    }
+}
+
+/// Colors by what they mean
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    /// Whatever the player is looking at or starting from
+    pub highlight: Color,
+    /// Something that can be done, or is going well
+    pub valid: Color,
+    /// Something that can't be done, is in the way, or is about to be destroyed
+    pub invalid: Color,
+    /// Something in between, like a block that's reserved but not yet occupied
+    pub warning: Color,
+    /// An area that's been picked out
+    pub selection: Color,
+    pub health: Color,
+    pub milestone: Color,
+    pub waypoint: Color,
+}
+
+/// The palette for the current [`ColorVision`]
+pub fn palette() -> Palette {
+    color_vision().palette()
+}
+
+pub fn color_vision() -> ColorVision {
+    let idx = COLOR_VISION.load(Ordering::Relaxed);
+    (ColorVision::ALL.get(usize::from(idx)).copied()).unwrap_or_default()
+}
+
+/// Draw with the palette for `vision` from now on
+pub fn set_color_vision(vision: ColorVision) {
+    let idx = (ColorVision::ALL.iter())
+        .position(|&v| v == vision)
+        .and_then(|idx| u8::try_from(idx).ok())
+        .unwrap_or_default();
+    COLOR_VISION.store(idx, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palettes() {
+        for vision in ColorVision::ALL {
+            let palette = vision.palette();
+            let meanings = [palette.valid, palette.invalid, palette.warning];
+            for (i, a) in meanings.iter().enumerate() {
+                for b in &meanings[i + 1..] {
+                    assert_ne!(
+                        a,
+                        b,
+                        "{} has colors that mean different things",
+                        vision.name()
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! The menu shown when the game is paused with escape
+
+use crate::locale::tr;
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer},
+};
+use raylib::prelude::*;
+
+const MARGIN: f32 = 16.0;
+const FONT_SIZE: f32 = 20.0;
+const TITLE_SIZE: f32 = 32.0;
+const BUTTON_HEIGHT: f32 = FONT_SIZE + 16.0;
+const WIDTH: f32 = 280.0;
+
+const SHADE: Color = Color::new(0, 0, 0, 128);
+const BACKGROUND: Color = Color::new(16, 16, 24, 230);
+const HOVERED: Color = Color::new(255, 255, 255, 32);
+
+/// A button on the [`PauseMenu`], and what the game should do when it's clicked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PauseAction {
+    Resume,
+    Save,
+    Settings,
+    Quit,
+}
+
+impl PauseAction {
+    /// Top to bottom
+    pub const ALL: [Self; 4] = [Self::Resume, Self::Save, Self::Settings, Self::Quit];
+
+    pub fn label(self) -> String {
+        tr!(match self {
+            Self::Resume => "pause.resume",
+            Self::Save => "pause.save",
+            Self::Settings => "pause.settings",
+            Self::Quit => "pause.quit",
+        })
+    }
+}
+
+/// Buttons for leaving the pause, over the shaded game
+#[derive(Debug, Clone, Default)]
+pub struct PauseMenu {
+    /// A line shown under the buttons, like how saving went
+    pub notice: Option<String>,
+    /// The button under the mouse cursor
+    hovered: Option<PauseAction>,
+    /// Size of the screen, for shading it
+    screen: Vector2,
+    /// Screen-space area of the menu
+    rect: Rectangle,
+}
+
+impl PauseMenu {
+    /// Fit the menu to the screen
+    #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+    fn layout(&mut self, rl: &RaylibHandle) {
+        self.screen = Vector2::new(rl.get_screen_width() as f32, rl.get_screen_height() as f32);
+        let buttons = PauseAction::ALL.len() as f32;
+        let height = TITLE_SIZE + buttons * (BUTTON_HEIGHT + MARGIN) + FONT_SIZE + 2.0 * MARGIN;
+        self.rect = Rectangle::new(
+            0.5 * (self.screen.x - WIDTH),
+            0.5 * (self.screen.y - height),
+            WIDTH,
+            height,
+        );
+    }
+
+    /// Screen-space area of the `idx`th button
+    #[allow(clippy::cast_precision_loss, reason = "there are only a few buttons")]
+    fn button_rect(&self, idx: usize) -> Rectangle {
+        Rectangle::new(
+            self.rect.x + MARGIN,
+            self.rect.y + MARGIN + TITLE_SIZE + MARGIN + idx as f32 * (BUTTON_HEIGHT + MARGIN),
+            self.rect.width - 2.0 * MARGIN,
+            BUTTON_HEIGHT,
+        )
+    }
+
+    /// The button that was clicked this frame, if any
+    ///
+    /// Escape resumes, like clicking [`PauseAction::Resume`].
+    pub fn update(&mut self, rl: &RaylibHandle) -> Option<PauseAction> {
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            return Some(PauseAction::Resume);
+        }
+        self.layout(rl);
+        let mouse = rl.get_mouse_position();
+        self.hovered = (PauseAction::ALL.into_iter().enumerate())
+            .find(|&(idx, _)| self.button_rect(idx).check_collision_point_rec(mouse))
+            .map(|(_, action)| action);
+        self.hovered
+            .filter(|_| rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT))
+    }
+}
+
+impl Draw for PauseMenu {
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        let font = d.font().ok_or(draw::Error)?;
+        d.draw_rectangle(
+            Rectangle::new(0.0, 0.0, self.screen.x, self.screen.y),
+            SHADE,
+        )?;
+        d.draw_rectangle(self.rect, BACKGROUND)?;
+
+        let title = tr!("pause.title");
+        let size = font.measure_text(&title, TITLE_SIZE, 0.0);
+        d.draw_text(
+            &title,
+            Vector2::new(
+                self.rect.x + 0.5 * (self.rect.width - size.x),
+                self.rect.y + MARGIN,
+            ),
+            TITLE_SIZE,
+            0.0,
+            Color::WHITE,
+        )?;
+
+        for (idx, action) in PauseAction::ALL.into_iter().enumerate() {
+            let rect = self.button_rect(idx);
+            if self.hovered == Some(action) {
+                d.draw_rectangle(rect, HOVERED)?;
+            }
+            d.draw_rectangle_lines(rect, Some(1.0), Color::GRAY)?;
+            let label = action.label();
+            let size = font.measure_text(&label, FONT_SIZE, 0.0);
+            d.draw_text(
+                &label,
+                Vector2::new(
+                    rect.x + 0.5 * (rect.width - size.x),
+                    rect.y + 0.5 * (rect.height - size.y),
+                ),
+                FONT_SIZE,
+                0.0,
+                Color::WHITE,
+            )?;
+        }
+
+        if let Some(notice) = &self.notice {
+            let rect = self.button_rect(PauseAction::ALL.len());
+            d.draw_text(
+                notice,
+                Vector2::new(rect.x, rect.y),
+                FONT_SIZE,
+                0.0,
+                Color::LIGHTGRAY,
+            )?;
+        }
+        Ok(())
+    }
+}
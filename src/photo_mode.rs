@@ -0,0 +1,222 @@
+//! Taking pictures from anywhere near the player
+//!
+//! Photo mode stops the simulation and hides the HUD, and flies a [`FreeCamera`] around on its
+//! own while the player stays where they were. Pictures are taken with the usual screenshot
+//! inputs, at [`Capture::scale`] times the size of the window, which page up and page down change
+//! while in photo mode.
+
+use crate::{
+    capture::Capture,
+    input::{EventInput, Inputs, VectorInput},
+    locale::tr,
+    math::coords::{PlayerVector3, VectorConstants},
+    player::Player,
+};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer},
+};
+use raylib::prelude::{
+    glam::{EulerRot, Quat},
+    *,
+};
+use std::f32::consts::FRAC_PI_2;
+
+const FONT_SIZE: f32 = 20.0;
+const MARGIN: f32 = 16.0;
+/// Seconds the controls are shown for after entering photo mode, or the notice after changing
+/// the size of pictures
+const HINT_TIME: f32 = 5.0;
+/// The biggest [`Capture::scale`] photo mode goes up to
+const MAX_SCALE: u32 = 8;
+
+/// A camera that flies anywhere near the player, apart from the player's own
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FreeCamera {
+    pub position: PlayerVector3,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Radians the view is tilted clockwise
+    pub roll: f32,
+    /// Vertical field of view in degrees
+    pub fovy: f32,
+}
+
+impl FreeCamera {
+    /// Meters per second
+    const SPEED: f32 = 4.0;
+    /// How many times faster the camera flies while sprinting
+    const SPRINT: f32 = 4.0;
+    /// Radians per second the view tilts by
+    const ROLL_SPEED: f32 = 1.0;
+    /// Degrees the field of view changes by for each notch of the mouse wheel
+    const FOVY_STEP: f32 = 5.0;
+    const MIN_FOVY: f32 = 10.0;
+    const MAX_FOVY: f32 = 120.0;
+    /// How far the camera can fly from the player, so it stays where the world is loaded
+    const MAX_DISTANCE: f32 = 64.0;
+
+    /// Looking the same way from the same place as `player`'s camera
+    pub const fn new(player: &Player) -> Self {
+        Self {
+            position: (player.position).plus(PlayerVector3::from_vec3(player.camera.position)),
+            yaw: player.yaw,
+            pitch: player.pitch,
+            roll: 0.0,
+            fovy: player.camera.fovy,
+        }
+    }
+
+    fn rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, -self.roll)
+    }
+
+    /// The camera to draw with, relative to `origin` like everything else that's drawn
+    pub fn camera(&self, origin: PlayerVector3) -> Camera3D {
+        let rotation = self.rotation();
+        let position = self.position.minus(origin).to_vec3();
+        Camera3D::perspective(
+            position,
+            position + rotation.mul_vec3(Vector3::FORWARD),
+            rotation.mul_vec3(Vector3::UP),
+            self.fovy,
+        )
+    }
+
+    /// Look with the look input, and fly for `dt` seconds with the walk input, space, and
+    /// control, keeping within [`Self::MAX_DISTANCE`] of `player`
+    ///
+    /// Q and E tilt the view, the mouse wheel changes the field of view, and R puts both back
+    /// to how `player` sees.
+    fn update(&mut self, rl: &RaylibHandle, dt: f32, inputs: &Inputs, player: &Player) {
+        let default_fovy = player.camera_settings.fovy;
+        // Turn slower while zoomed in, like the player does
+        let pan = -inputs[VectorInput::Look] * (self.fovy / default_fovy);
+        self.yaw = (self.yaw + pan.x) % std::f32::consts::TAU;
+        self.pitch = (self.pitch + pan.y).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+
+        let tilt = f32::from(u8::from(rl.is_key_down(KeyboardKey::KEY_E)))
+            - f32::from(u8::from(rl.is_key_down(KeyboardKey::KEY_Q)));
+        self.roll += tilt * Self::ROLL_SPEED * dt;
+        self.fovy = (self.fovy - rl.get_mouse_wheel_move() * Self::FOVY_STEP)
+            .clamp(Self::MIN_FOVY, Self::MAX_FOVY);
+        if rl.is_key_pressed(KeyboardKey::KEY_R) {
+            self.roll = 0.0;
+            self.fovy = default_fovy;
+        }
+
+        let walk = inputs[VectorInput::Walk];
+        let rise = f32::from(u8::from(rl.is_key_down(KeyboardKey::KEY_SPACE)))
+            - f32::from(u8::from(rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)));
+        let rotation = self.rotation();
+        let direction = rotation.mul_vec3(Vector3::RIGHT) * walk.x
+            + rotation.mul_vec3(Vector3::FORWARD) * walk.y
+            + Vector3::UP * rise;
+        let speed = if inputs[EventInput::Sprint] {
+            Self::SPEED * Self::SPRINT
+        } else {
+            Self::SPEED
+        };
+        let offset = self.position.minus(player.position).to_vec3() + direction * speed * dt;
+        let offset = offset.clamp_length_max(Self::MAX_DISTANCE);
+        self.position = (player.position).plus(PlayerVector3::from_vec3(offset));
+    }
+}
+
+/// Flying the [`FreeCamera`] around to take pictures, with the controls shown at first
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhotoMode {
+    pub camera: FreeCamera,
+    /// The controls, or how big pictures are after changing it
+    hint: String,
+    /// Seconds left to show the hint for
+    hint_time: f32,
+    screen: Vector2,
+}
+
+impl PhotoMode {
+    /// Start from where `player` is looking
+    pub fn new(player: &Player) -> Self {
+        Self {
+            camera: FreeCamera::new(player),
+            hint: tr!("photo_mode.controls"),
+            hint_time: HINT_TIME,
+            screen: Vector2::ZERO,
+        }
+    }
+
+    /// Fly the camera around near `player`, and change how many times the size of the window
+    /// `capture` takes pictures at with page up and page down
+    #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+    pub fn update(
+        &mut self,
+        rl: &RaylibHandle,
+        inputs: &Inputs,
+        player: &Player,
+        capture: &mut Capture,
+    ) {
+        let dt = rl.get_frame_time();
+        self.screen = Vector2::new(rl.get_screen_width() as f32, rl.get_screen_height() as f32);
+        self.camera.update(rl, dt, inputs, player);
+        self.hint_time = (self.hint_time - dt).max(0.0);
+
+        let scale = if rl.is_key_pressed(KeyboardKey::KEY_PAGE_UP) {
+            capture.scale.saturating_add(1)
+        } else if rl.is_key_pressed(KeyboardKey::KEY_PAGE_DOWN) {
+            capture.scale.saturating_sub(1)
+        } else {
+            return;
+        };
+        capture.scale = scale.clamp(1, MAX_SCALE);
+        let (width, height) = capture.size(self.screen);
+        self.hint = tr!("photo_mode.size", width = width, height = height);
+        self.hint_time = HINT_TIME;
+    }
+}
+
+impl Draw for PhotoMode {
+    /// The hint along the bottom of the screen, fading out before it goes
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        if self.hint_time <= 0.0 {
+            return Ok(());
+        }
+        let font = d.font().ok_or(draw::Error)?;
+        let size = font.measure_text(&self.hint, FONT_SIZE, 0.0);
+        d.draw_text(
+            &self.hint,
+            Vector2::new(
+                0.5 * (self.screen.x - size.x),
+                self.screen.y - MARGIN - size.y,
+            ),
+            FONT_SIZE,
+            0.0,
+            Color::WHITE.alpha(0.8 * self.hint_time.min(1.0)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_free_camera() {
+        let player = Player::new(PlayerVector3::from_i32(5, 0, -3), 0.5, 0.0, 45.0, 1.0);
+        let free = FreeCamera::new(&player);
+        let camera = free.camera(player.position);
+        assert!(
+            (camera.position - player.camera.position).length() < 1e-4,
+            "starts at the player's eyes"
+        );
+        let forward = (camera.target - camera.position).normalize();
+        let looking = (player.camera.target - player.camera.position).normalize();
+        assert!((forward - looking).length() < 1e-4, "looking the same way");
+
+        let tilted = FreeCamera {
+            roll: FRAC_PI_2,
+            ..free
+        };
+        let up = tilted.camera(player.position).up;
+        assert!(up.y.abs() < 1e-4, "on its side");
+    }
+}
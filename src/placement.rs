@@ -0,0 +1,174 @@
+//! How what's placed on factory floors lines up with the grid
+//!
+//! The player picks a [`Snap`] for how closely placement follows where they're aiming: to the
+//! middle of each block, to every half meter, or not at all. Anything placed off the grid keeps
+//! where it is in its block as a fixed-point offset, like positions everywhere else, so it
+//! doesn't drift.
+//!
+//! Aiming at the side of a machine places flush against that face instead, on the floor in front
+//! of it, snapped only along the face.
+//!
+//! Machines only go on whole blocks, so they're placed with [`Snap::MACHINES`] whatever the
+//! player picked.
+
+use crate::{
+    locale::tr,
+    math::coords::{FactoryVector3, PlayerCoord},
+    player::Player,
+    region::factory::Factory,
+};
+use raylib::prelude::*;
+use std::num::NonZeroU8;
+
+/// How finely placement follows where the player is aiming
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Snap {
+    /// To the middle of a block
+    #[default]
+    Grid,
+    /// To every half meter
+    Half,
+    /// Exactly where the player is aiming
+    Free,
+}
+
+impl Snap {
+    /// How machines line up, since they can't be placed any finer than a block
+    pub const MACHINES: Self = Self::Grid;
+
+    /// The mode after this one, going back to the first after the last
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Grid => Self::Half,
+            Self::Half => Self::Free,
+            Self::Free => Self::Grid,
+        }
+    }
+
+    /// What the mode is called in the player's language
+    pub fn name(self) -> String {
+        match self {
+            Self::Grid => tr!("snap.grid"),
+            Self::Half => tr!("snap.half"),
+            Self::Free => tr!("snap.free"),
+        }
+    }
+
+    /// How many grid lines to show per meter while placing with the mode
+    pub const fn lines_per_meter(self) -> NonZeroU8 {
+        match self {
+            Self::Half => NonZeroU8::MIN.saturating_add(1),
+            Self::Grid | Self::Free => NonZeroU8::MIN,
+        }
+    }
+
+    /// `coord` moved to the closest place the mode allows
+    pub fn snap(self, coord: PlayerCoord) -> PlayerCoord {
+        match self {
+            Self::Grid => floor(coord) + PlayerCoord::HALF,
+            Self::Half => floor(coord * 2 + PlayerCoord::HALF) * PlayerCoord::HALF,
+            Self::Free => coord,
+        }
+    }
+
+    /// Where on a floor in `factory` the player is aiming, moved as the mode allows, if it's
+    /// within [`Player::REACH`]
+    ///
+    /// Aiming at the side of a machine gives the spot in front of it with its edge against that
+    /// face.
+    pub fn spot(self, factory: &Factory, player: &Player) -> Option<Spot> {
+        let collision = factory
+            .get_ray_collision(factory.vision_ray(player))
+            .filter(|collision| collision.distance <= Player::REACH)?;
+        let (point, normal) = (collision.point, collision.normal);
+        let coord = PlayerCoord::from_f32;
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "factories are much smaller"
+        )]
+        let spot = if collision.target.is_none() && normal.y > 0.0 {
+            Spot::at(
+                self.snap(coord(point.x)),
+                point.y.round() as i16,
+                self.snap(coord(point.z)),
+            )
+        } else if collision.target.is_some() && normal.y == 0.0 {
+            // Machines' sides are on whole meters, so this is exact even if the hit isn't
+            let out = |along: f32, normal: f32| coord(along.round() + 0.5 * normal.signum());
+            let (x, z) = if normal.x.abs() > normal.z.abs() {
+                (out(point.x, normal.x), self.snap(coord(point.z)))
+            } else {
+                (self.snap(coord(point.x)), out(point.z, normal.z))
+            };
+            let level = factory.level_at(Spot::at(x, point.y.floor() as i16, z).block);
+            Spot::at(x, level, z)
+        } else {
+            return None;
+        };
+        factory.is_floor(spot.block).then_some(spot)
+    }
+}
+
+/// The whole meters at or below `coord`
+const fn floor(coord: PlayerCoord) -> PlayerCoord {
+    PlayerCoord::from_i32(coord.to_i32())
+}
+
+/// A point on the floor of a factory something is placed at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Spot {
+    /// The block whose floor it's on
+    pub block: FactoryVector3,
+    /// From the middle of the block along x and z, each at least -0.5 and under 0.5 meters
+    pub offset: [PlayerCoord; 2],
+}
+
+impl Spot {
+    /// The spot at `x` and `z` on the floor at height `y`
+    pub fn at(x: PlayerCoord, y: i16, z: PlayerCoord) -> Self {
+        let (min_x, min_z) = (floor(x), floor(z));
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "factories are much smaller"
+        )]
+        let block = FactoryVector3::new(min_x.to_i32() as i16, y, min_z.to_i32() as i16);
+        Self {
+            block,
+            offset: [x - min_x - PlayerCoord::HALF, z - min_z - PlayerCoord::HALF],
+        }
+    }
+
+    /// Where the spot is in factory coordinates
+    pub fn center(self) -> Vector3 {
+        let [x, z] = self.offset;
+        self.block.as_vec3() + Vector3::new(0.5 + x.to_f32(), 0.0, 0.5 + z.to_f32())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snap() {
+        let coord = PlayerCoord::from_f32;
+        assert_eq!(Snap::Grid.snap(coord(3.9)), coord(3.5));
+        assert_eq!(Snap::Grid.snap(coord(-0.2)), coord(-0.5));
+        assert_eq!(Snap::Half.snap(coord(3.7)), coord(3.5));
+        assert_eq!(Snap::Half.snap(coord(3.8)), coord(4.0));
+        assert_eq!(Snap::Half.snap(coord(-0.2)), coord(0.0));
+        assert_eq!(Snap::Free.snap(coord(3.7)), coord(3.7));
+        assert_eq!(Snap::Free.next(), Snap::Grid);
+        assert_eq!(Snap::Half.lines_per_meter().get(), 2);
+
+        let spot = Spot::at(coord(4.0), 2, coord(-1.25));
+        assert_eq!(spot.block, FactoryVector3::new(4, 2, -2));
+        assert_eq!(spot.offset, [coord(-0.5), coord(0.25)]);
+        assert_eq!(spot.center(), Vector3::new(4.0, 2.0, -1.25));
+        assert_eq!(
+            Spot::at(Snap::Grid.snap(coord(4.2)), 0, coord(0.5)).offset,
+            [PlayerCoord::ZERO; 2],
+            "snapped to the grid, it's in the middle of its block"
+        );
+    }
+}
@@ -1,33 +1,320 @@
 use crate::{
+    container::{Container, Item, ItemSlots, ItemStack},
+    health::{Damage, DamageSource, Health},
     input::{self, Inputs},
-    math::coords::{
-        VectorConstants,
-        player::{PlayerCoord, PlayerVector3},
+    math::{
+        bounds::{PlayerBounds, SpacialBounds},
+        coords::{
+            VectorConstants,
+            player::{PlayerCoord, PlayerVector3},
+        },
+        frustum::Frustum,
     },
+    placement::Snap,
     region::Region,
+    rl_helpers::DynRaylibDraw3D,
 };
 use raylib::prelude::{
     glam::{EulerRot, Quat},
     *,
 };
-use std::{f32::consts::PI, time::Instant};
+use std::f32::consts::PI;
 
 /// Meters per second per second
 const GRAVITY: PlayerCoord = PlayerCoord::from_f32(9.807);
 const JUMP_DURATION: PlayerCoord = PlayerCoord::from_f32(40.0);
 const FRICTION: PlayerCoord = PlayerCoord::from_f32(0.0005);
 const AIR_MOBILITY_FACTOR: f32 = 0.1;
+/// Climbing speed as a fraction of walking or running speed
+const CLIMB_SPEED_FACTOR: f32 = 0.6;
+/// How far the third person camera stays from walls, in meters
+const CAMERA_RADIUS: f32 = 0.2;
+/// Seconds for the third person camera to move most of the way back out after an obstruction
+const BOOM_RETURN_TIME: f32 = 0.25;
 
+/// How the player is moving, which decides how inputs and gravity affect them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MovementState {
+    /// Walking on the floor or on top of a machine
+    #[default]
+    Grounded,
+    /// Jumping or falling
+    Airborne,
+    /// On a ladder, moving up and down instead of forward and back
+    Climbing,
+    /// Using a [`Jetpack`], moving wherever the player is looking
+    Flying,
+}
+
+impl MovementState {
+    /// The state to move in this tick, given the state moved in last tick
+    ///
+    /// - Jumping leaves the floor, and pushes off of ladders.
+    /// - Walking forward into a ladder starts climbing it, and walking back off the bottom or
+    ///   leaving the ladder stops.
+    /// - Jumping again in midair starts flying if there is fuel left, and jumping while flying
+    ///   stops.
+    #[must_use]
+    #[allow(
+        clippy::fn_params_excessive_bools,
+        reason = "each is a separate condition for changing state"
+    )]
+    pub const fn next(
+        self,
+        is_on_floor: bool,
+        can_climb: bool,
+        walk: Vector2,
+        jump: bool,
+        has_fuel: bool,
+    ) -> Self {
+        let fall = if is_on_floor {
+            Self::Grounded
+        } else {
+            Self::Airborne
+        };
+        match self {
+            Self::Climbing if jump => Self::Airborne,
+            Self::Climbing if can_climb && !(is_on_floor && walk.y < 0.0) => Self::Climbing,
+            Self::Flying if jump || !has_fuel => Self::Airborne,
+            Self::Flying if is_on_floor => Self::Grounded,
+            Self::Flying => Self::Flying,
+            Self::Grounded | Self::Airborne | Self::Climbing if can_climb && walk.y > 0.0 => {
+                Self::Climbing
+            }
+            Self::Grounded if is_on_floor && jump => Self::Airborne,
+            Self::Airborne if jump && has_fuel => Self::Flying,
+            Self::Grounded | Self::Airborne | Self::Climbing => fall,
+        }
+    }
+
+    /// How the player accelerates in this state
+    #[must_use]
+    pub const fn params(self) -> MovementParams {
+        match self {
+            Self::Grounded => MovementParams {
+                acceleration: 6.0,
+                drag: 6.3,
+                gravity: 0.0,
+                soft_cap: true,
+            },
+            Self::Airborne => MovementParams {
+                acceleration: 6.0 * AIR_MOBILITY_FACTOR,
+                drag: 0.0,
+                gravity: 1.0,
+                soft_cap: false,
+            },
+            Self::Climbing => MovementParams {
+                acceleration: 8.0,
+                drag: 8.0,
+                gravity: 0.0,
+                soft_cap: false,
+            },
+            Self::Flying => MovementParams {
+                acceleration: 4.0,
+                drag: 1.5,
+                gravity: 0.0,
+                soft_cap: true,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MovementParams {
+    /// Multiple of the player's move speed gained per second while moving
+    ///
+    /// While climbing, how many times per second the climbing speed catches up to the input.
+    pub acceleration: f32,
+    /// Rate at which the player slows down while not trying to move, per second
+    pub drag: f32,
+    /// Multiple of [`GRAVITY`] pulling the player down
+    pub gravity: f32,
+    /// Whether quadratic friction keeps the player from speeding up forever
+    pub soft_cap: bool,
+}
+
+/// Late game equipment that lets the player fly
+///
+/// It's made in an assembler with the `Jetpack` recipe, which research unlocks late, and put on
+/// from the inventory with [`Player::equip`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Jetpack {
+    /// Seconds of flight left
+    pub fuel: f32,
+    /// Seconds of flight when full
+    pub capacity: f32,
+}
+
+impl Jetpack {
+    /// Seconds of flight regained per second spent on the ground
+    pub const REFUEL_RATE: f32 = 0.5;
+
+    /// Seconds of flight when full
+    pub const CAPACITY: f32 = 10.0;
+
+    /// What a jetpack is in the inventory, a reactor kit's frame with more boards to steer it
+    pub fn item() -> Item {
+        "Fe8Cu8Si4".parse().unwrap()
+    }
+
+    /// A full jetpack
+    #[inline]
+    pub const fn new(capacity: f32) -> Self {
+        Self {
+            fuel: capacity,
+            capacity,
+        }
+    }
+
+    #[inline]
+    pub const fn has_fuel(self) -> bool {
+        self.fuel > 0.0
+    }
+}
+
+/// Equipment that filters out most of the gas the player breathes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasMask {
+    /// Seconds of breathing gas the filter has left
+    pub filter: f32,
+}
+
+impl GasMask {
+    /// Fraction of the damage from gas the mask keeps off while its filter lasts
+    pub const PROTECTION: f32 = 0.9;
+
+    /// A mask with a fresh filter that lasts `filter` seconds
+    #[inline]
+    pub const fn new(filter: f32) -> Self {
+        Self { filter }
+    }
+}
+
+/// A vehicle the player is riding, which carries them along with it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attachment {
+    /// The index of the handcar in
+    /// [`RailNetwork::handcars`](crate::region::rail::network::RailNetwork::handcars)
+    pub handcar: usize,
+    /// The way the vehicle faced when it last moved the player, see [`Player::follow`]
+    pub heading: f32,
+}
+
+/// Where the camera is relative to the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CameraMode {
+    #[default]
+    FirstPerson,
+    /// Orbiting the player's head, looking over it
+    ThirdPerson,
+}
+
+impl CameraMode {
+    #[inline]
+    #[must_use]
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::FirstPerson => Self::ThirdPerson,
+            Self::ThirdPerson => Self::FirstPerson,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraSettings {
+    /// Seconds for the view to turn most of the way to where the player is looking, or 0 to turn
+    /// instantly
+    pub look_smoothing: f32,
+    /// Seconds for the field of view to change most of the way when zooming, or 0 to change
+    /// instantly
+    pub zoom_smoothing: f32,
+    /// Vertical field of view in degrees
+    pub fovy: f32,
+    /// Vertical field of view in degrees while zooming
+    pub zoom_fovy: f32,
+    /// How far the third person camera is from the player's head when nothing is in the way, in
+    /// meters
+    pub boom_length: f32,
+}
+
+impl CameraSettings {
+    #[inline]
+    pub const fn new(fovy: f32) -> Self {
+        Self {
+            look_smoothing: 0.0,
+            zoom_smoothing: 0.1,
+            fovy,
+            zoom_fovy: fovy / 3.0,
+            boom_length: 4.0,
+        }
+    }
+}
+
+/// Move `current` towards `target`, most of the way after `smoothing` seconds
+///
+/// Frame rate independent, and jumps straight to `target` if `smoothing` is not positive.
+#[inline]
+fn smooth(current: f32, target: f32, smoothing: f32, dt: f32) -> f32 {
+    if smoothing > 0.0 {
+        current + (target - current) * (1.0 - (-dt / smoothing).exp())
+    } else {
+        target
+    }
+}
+
+/// Like [`smooth`], but for angles in radians, turning whichever way is shorter
+#[inline]
+fn smooth_angle(current: f32, target: f32, smoothing: f32, dt: f32) -> f32 {
+    let difference = (target - current + PI).rem_euclid(2.0 * PI) - PI;
+    smooth(current, current + difference, smoothing, dt)
+}
+
+#[derive(Clone)]
 pub struct Player {
     /// Meters
     pub position: PlayerVector3,
     /// Meters per second
     pub velocity: PlayerVector3,
+    /// Current pitch of the view, which lags behind [`Self::target_pitch`] with smoothing
     pub pitch: f32,
+    /// Current yaw of the view, which lags behind [`Self::target_yaw`] with smoothing
     pub yaw: f32,
+    /// Pitch the player is turning towards
+    pub target_pitch: f32,
+    /// Yaw the player is turning towards
+    pub target_yaw: f32,
     pub is_running: bool,
     pub camera: Camera3D,
-    pub region_last_changed: Instant,
+    pub camera_mode: CameraMode,
+    pub camera_settings: CameraSettings,
+    pub movement_state: MovementState,
+    /// Lets the player fly, once they have one
+    pub jetpack: Option<Jetpack>,
+    /// Protects the player from gas, once they have one
+    pub gas_mask: Option<GasMask>,
+    /// What the player is riding, if anything; they can look around but not walk while they are
+    pub attachment: Option<Attachment>,
+    pub health: Health,
+    /// Current distance of the third person camera from the player's head
+    boom: f32,
+    /// Width / height of the screen the camera renders to
+    pub aspect_ratio: f32,
+    /// Substances the player is carrying, one stack per hotbar slot
+    pub inventory: ItemSlots,
+    /// Index of the selected hotbar slot, less than [`Self::HOTBAR_SLOTS`]
+    pub hotbar_slot: usize,
+    /// How placing things lines up with the grid
+    pub snap: Snap,
+}
+
+#[inline]
+fn screen_aspect_ratio(rl: &RaylibHandle) -> f32 {
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "screen dimensions are nowhere near 2^24"
+    )]
+    let (width, height) = (rl.get_screen_width() as f32, rl.get_screen_height() as f32);
+    if height > 0.0 { width / height } else { 1.0 }
 }
 
 #[inline]
@@ -42,16 +329,40 @@ fn camera_helper(pitch: f32, yaw: f32) -> (Vector3, Vector3) {
 
 impl Player {
     pub const HEIGHT: f32 = 1.75;
+    /// Half the width of the player's body
+    pub const RADIUS: f32 = 0.3;
     pub const EYE_HEIGHT: f32 = Self::HEIGHT - 0.15;
+    /// How far away the player can interact with things, in meters
+    pub const REACH: f32 = 4.0;
+    /// Fastest the server lets a player move in meters per second, well above running or a long fall
+    pub const MAX_SPEED: f32 = 50.0;
+    /// Number of inventory slots shown in the hotbar
+    pub const HOTBAR_SLOTS: usize = 9;
+    /// Meters per second the player can hit the ground at without getting hurt, about what
+    /// falling five meters gets up to
+    pub const SAFE_FALL_SPEED: f32 = 10.0;
+    /// Health lost for each meter per second over [`Self::SAFE_FALL_SPEED`] the player lands at
+    pub const FALL_DAMAGE: f32 = 10.0;
 
     /// Spawn the player at the specified location
     pub fn spawn(
-        _rl: &mut RaylibHandle,
+        rl: &mut RaylibHandle,
         _thread: &RaylibThread,
         position: PlayerVector3,
         yaw: f32,
         pitch: f32,
         fovy: f32,
+    ) -> Self {
+        Self::new(position, yaw, pitch, fovy, screen_aspect_ratio(rl))
+    }
+
+    /// A player that isn't drawn to a window yet, see [`Self::spawn`]
+    pub fn new(
+        position: PlayerVector3,
+        yaw: f32,
+        pitch: f32,
+        fovy: f32,
+        aspect_ratio: f32,
     ) -> Self {
         let (camera_offset, camera_target) = camera_helper(pitch, yaw);
         Self {
@@ -59,103 +370,375 @@ impl Player {
             velocity: PlayerVector3::ZERO,
             yaw,
             pitch,
+            target_yaw: yaw,
+            target_pitch: pitch,
             is_running: false,
             camera: Camera3D::perspective(camera_offset, camera_target, Vector3::UP, fovy),
-            region_last_changed: Instant::now(),
+            camera_mode: CameraMode::default(),
+            camera_settings: CameraSettings::new(fovy),
+            movement_state: MovementState::default(),
+            jetpack: None,
+            gas_mask: None,
+            attachment: None,
+            health: Health::new(),
+            boom: 0.0,
+            aspect_ratio,
+            inventory: ItemSlots::new(Self::HOTBAR_SLOTS, u32::MAX),
+            hotbar_slot: 0,
+            snap: Snap::default(),
         }
     }
 
     /// Tick the player (handle movement)
+    ///
+    /// Returns the damage from landing, if the player hit something hard enough to get hurt.
     pub fn do_movement(
         &mut self,
         rl: &mut RaylibHandle,
         _thread: &RaylibThread,
         inputs: &Inputs,
         current_region: &dyn Region,
-    ) {
+    ) -> Option<Damage> {
         #[allow(unused_imports, clippy::enum_glob_use, reason = "no reason")]
         use input::{AxisInput::*, EventInput::*, VectorInput::*};
 
         let dt = rl.get_frame_time();
+        self.aspect_ratio = screen_aspect_ratio(rl);
 
         // Looking around
         {
-            let pan = -inputs[Look];
-            self.yaw += pan.x;
-            self.yaw %= 2.0 * PI;
-            self.pitch += pan.y;
-            self.pitch = self.pitch.clamp(-PI, PI);
-            (self.camera.position, self.camera.target) = camera_helper(self.pitch, self.yaw);
+            let settings = self.camera_settings;
+            if inputs[ToggleCamera] {
+                self.camera_mode = self.camera_mode.toggled();
+            }
+            let fovy = if inputs[Zoom] {
+                settings.zoom_fovy
+            } else {
+                settings.fovy
+            };
+            self.camera.fovy = smooth(self.camera.fovy, fovy, settings.zoom_smoothing, dt);
+
+            // Turn slower while zoomed in, so the view moves about as fast across the screen
+            let pan = -inputs[Look] * (self.camera.fovy / settings.fovy);
+            self.target_yaw += pan.x;
+            self.target_yaw %= 2.0 * PI;
+            self.target_pitch += pan.y;
+            self.target_pitch = self.target_pitch.clamp(-PI, PI);
+            self.yaw =
+                smooth_angle(self.yaw, self.target_yaw, settings.look_smoothing, dt) % (2.0 * PI);
+            self.pitch = smooth(self.pitch, self.target_pitch, settings.look_smoothing, dt);
         }
 
-        // Movement
-        {
-            const WORLD_FLOOR_HEIGHT: PlayerCoord = PlayerCoord::from_i32(0);
+        // Riders are moved by what they're riding, see `Self::follow`
+        let landing = if self.attachment.is_some() {
+            None
+        } else {
+            self.update_movement(dt, inputs, current_region)
+        };
+        self.update_camera(dt, current_region);
+        landing
+    }
 
-            let local_floor = current_region
-                .local_floor(self)
-                .unwrap_or(WORLD_FLOOR_HEIGHT);
+    /// Move to `deck`, where the vehicle the player is attached to now carries them, turning
+    /// the view as much as the vehicle turned to face `heading`
+    ///
+    /// Does nothing if the player isn't riding anything.
+    pub fn follow(&mut self, deck: PlayerVector3, heading: f32) {
+        let Some(attachment) = &mut self.attachment else {
+            return;
+        };
+        let turn = heading - attachment.heading;
+        attachment.heading = heading;
+        self.yaw += turn;
+        self.target_yaw += turn;
+        self.position = deck;
+        self.velocity = PlayerVector3::ZERO;
+    }
 
-            let is_on_floor = self.position.y <= local_floor;
-            if is_on_floor {
-                self.velocity.y = PlayerCoord::ZERO;
-                self.position.y = local_floor;
-            }
+    /// The damage from landing at `speed` meters per second, if it's fast enough to hurt
+    pub fn fall_damage(speed: f32) -> Option<Damage> {
+        let amount = (speed - Self::SAFE_FALL_SPEED) * Self::FALL_DAMAGE;
+        (amount > 0.0).then_some(Damage::new(DamageSource::Fall, amount))
+    }
 
-            let mut force = PlayerVector3::ZERO;
+    /// Move by `motion`, stopping against obstacles in `current_region` and losing velocity
+    /// into them
+    fn move_by(&mut self, motion: PlayerVector3, current_region: &dyn Region) {
+        let bounds = self.bounds();
+        let obstacles = current_region.obstacles(&bounds.union(&PlayerBounds {
+            min: bounds.min.plus(motion),
+            max: bounds.max.plus(motion),
+        }));
+        let resolved = bounds.sweep(motion, &obstacles);
+        // Stop moving into whatever was hit
+        if resolved.x != motion.x {
+            self.velocity.x = PlayerCoord::ZERO;
+        }
+        if resolved.y != motion.y {
+            self.velocity.y = PlayerCoord::ZERO;
+        }
+        if resolved.z != motion.z {
+            self.velocity.z = PlayerCoord::ZERO;
+        }
+        self.position += resolved;
+    }
+
+    /// Accelerate and move the player according to their [`MovementState`], returning the
+    /// damage from landing, if any
+    fn update_movement(
+        &mut self,
+        dt: f32,
+        inputs: &Inputs,
+        current_region: &dyn Region,
+    ) -> Option<Damage> {
+        #[allow(clippy::enum_glob_use, reason = "no reason")]
+        use input::{EventInput::*, VectorInput::*};
+
+        const WORLD_FLOOR_HEIGHT: PlayerCoord = PlayerCoord::from_i32(0);
+
+        let local_floor = current_region
+            .local_floor(self)
+            .unwrap_or(WORLD_FLOOR_HEIGHT);
 
-            // convert from polar coords, making a unit vector for the facing angle.
-            let move_dir = Vector2::from_angle(self.yaw);
-            let mut movement = inputs[Walk].normalize_or_zero().rotate(move_dir);
-            if is_on_floor {
-                if movement.length_squared() < 0.01 {
-                    self.velocity -= self.velocity.scale(PlayerCoord::from_f32(0.1));
+        let is_on_floor = self.position.y <= local_floor;
+        // Meters per second the player was falling at when something stopped them
+        let mut impact: f32 = 0.0;
+        if is_on_floor {
+            impact = -self.velocity.y.to_f32();
+            self.velocity.y = PlayerCoord::ZERO;
+            self.position.y = local_floor;
+        }
+
+        let walk = inputs[Walk].normalize_or_zero();
+        let previous_state = self.movement_state;
+        self.movement_state = previous_state.next(
+            is_on_floor,
+            current_region.can_climb(self),
+            walk,
+            inputs[Jump],
+            self.jetpack.is_some_and(Jetpack::has_fuel),
+        );
+        let params = self.movement_state.params();
+
+        if let Some(jetpack) = &mut self.jetpack {
+            match self.movement_state {
+                MovementState::Flying => jetpack.fuel = (jetpack.fuel - dt).max(0.0),
+                MovementState::Grounded => {
+                    jetpack.fuel = (jetpack.fuel + Jetpack::REFUEL_RATE * dt).min(jetpack.capacity);
                 }
-            } else {
-                force += PlayerVector3::from_vec3(Vector3::DOWN) * GRAVITY;
-                movement *= AIR_MOBILITY_FACTOR;
+                MovementState::Airborne | MovementState::Climbing => {}
             }
+        }
 
-            // Measured in meters per second
-            let move_speed = if inputs[Sprint] {
-                self.run_speed()
-            } else {
-                self.walk_speed()
-            };
+        // Measured in meters per second
+        let move_speed = if inputs[Sprint] {
+            self.run_speed()
+        } else {
+            self.walk_speed()
+        };
+
+        // convert from polar coords, making a unit vector for the facing angle.
+        let move_dir = Vector2::from_angle(self.yaw);
+        let movement = match self.movement_state {
+            MovementState::Grounded | MovementState::Airborne => {
+                let movement = walk.rotate(move_dir);
+                Vector3::RIGHT * movement.x + Vector3::FORWARD * movement.y
+            }
+            // Forward and back climb up and down instead
+            MovementState::Climbing => Vector3::ZERO,
+            // Fly wherever the player is looking
+            MovementState::Flying => {
+                let (eye, target) = camera_helper(self.pitch, self.yaw);
+                let forward = (target - eye).normalize_or(Vector3::FORWARD);
+                let right = forward.cross(Vector3::UP).normalize_or_zero();
+                forward * walk.y + right * walk.x
+            }
+        };
+
+        let mut force = PlayerVector3::from_vec3(movement * move_speed * params.acceleration)
+            + PlayerVector3::from_vec3(Vector3::DOWN) * (GRAVITY * params.gravity);
 
-            if inputs[Jump] && is_on_floor {
+        match (previous_state, self.movement_state) {
+            (MovementState::Grounded, MovementState::Airborne) if inputs[Jump] => {
                 force += PlayerVector3::from_vec3(Vector3::UP) * GRAVITY * JUMP_DURATION;
             }
+            // Push off away from the ladder
+            (MovementState::Climbing, MovementState::Airborne) if inputs[Jump] => {
+                let facing = Vector2::Y.rotate(move_dir);
+                let push = Vector3::UP - (Vector3::RIGHT * facing.x + Vector3::FORWARD * facing.y);
+                force += PlayerVector3::from_vec3(push)
+                    * GRAVITY
+                    * (JUMP_DURATION * PlayerCoord::from_f32(0.5));
+            }
+            _ => {}
+        }
+
+        self.velocity += force.scale(PlayerCoord::from_f32(dt));
 
-            let movement_force =
-                ((Vector3::RIGHT * movement.x + Vector3::FORWARD * movement.y) * move_speed * 6.0)
-                    .into();
-            force += movement_force;
+        if movement.length_squared() < 0.01 {
+            self.velocity *= PlayerCoord::from_f32((-params.drag * dt).exp());
+        }
+        if self.movement_state == MovementState::Climbing {
+            let climb_speed = walk.y * move_speed * CLIMB_SPEED_FACTOR;
+            self.velocity.y = PlayerCoord::from_f32(smooth(
+                self.velocity.y.to_f32(),
+                climb_speed,
+                params.acceleration.recip(),
+                dt,
+            ));
+        }
 
-            self.velocity += force.scale(PlayerCoord::from_f32(dt));
+        let vel_len_sq = self.velocity.length_sqr();
+        if vel_len_sq < PlayerCoord::from_f32(0.0001) {
+            // velocity dead zone
+            self.velocity = PlayerVector3::ZERO;
+        } else if params.soft_cap {
+            // quadratic friction for soft speed cap
+            self.velocity *= PlayerCoord::ONE - vel_len_sq * FRICTION;
+        }
 
-            let vel_len_sq = self.velocity.length_sqr();
-            if vel_len_sq < PlayerCoord::from_f32(0.0001) {
-                // velocity dead zone
-                self.velocity = PlayerVector3::ZERO;
-            } else if is_on_floor {
-                // quadratic friction for soft speed cap
-                self.velocity *= PlayerCoord::ONE - vel_len_sq * FRICTION;
+        let falling = -self.velocity.y.to_f32();
+        self.move_by(
+            self.velocity.scale(PlayerCoord::from_f32(dt)),
+            current_region,
+        );
+        // Landed on top of something
+        if self.velocity.y == PlayerCoord::ZERO {
+            impact = impact.max(falling);
+        }
+        if self.position.y < local_floor {
+            self.position.y = local_floor;
+        }
+        Self::fall_damage(impact)
+    }
+
+    /// Move the camera to match the view direction and [`Self::camera_mode`]
+    ///
+    /// The third person camera is pulled in towards the player's head when something is between
+    /// them, and eases back out once it's clear.
+    fn update_camera(&mut self, dt: f32, current_region: &dyn Region) {
+        let (eye, target) = camera_helper(self.pitch, self.yaw);
+        match self.camera_mode {
+            CameraMode::FirstPerson => {
+                self.boom = 0.0;
+                (self.camera.position, self.camera.target) = (eye, target);
+            }
+            CameraMode::ThirdPerson => {
+                let max_length = self.camera_settings.boom_length;
+                let backward = eye - target;
+                let length = current_region
+                    .raycast_from_eye(self, backward, max_length + CAMERA_RADIUS)
+                    .map_or(max_length, |distance| (distance - CAMERA_RADIUS).max(0.0));
+                self.boom = if length < self.boom {
+                    length
+                } else {
+                    smooth(self.boom, length, BOOM_RETURN_TIME, dt)
+                };
+                (self.camera.position, self.camera.target) = (eye + backward * self.boom, eye);
             }
+        }
+    }
+
+    /// Draw the player's body, which is only visible in third person
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D) {
+        if self.camera_mode == CameraMode::ThirdPerson {
+            Self::draw_body(d);
+        }
+    }
+
+    /// Draw the player's body even in first person, for when it's seen from somewhere else
+    pub fn draw_body(d: &mut dyn DynRaylibDraw3D) {
+        d.draw_capsule(
+            Vector3::UP * Self::RADIUS,
+            Vector3::UP * (Self::HEIGHT - Self::RADIUS),
+            Self::RADIUS,
+            8,
+            4,
+            Color::SKYBLUE,
+        );
+    }
+
+    /// Add `stack` to the inventory, stacking with any of the same item already carried
+    ///
+    /// Returns whatever doesn't fit.
+    pub fn give(&mut self, stack: ItemStack) -> Option<ItemStack> {
+        self.inventory.insert(stack)
+    }
 
-            self.position += self.velocity.scale(PlayerCoord::from_f32(dt));
+    /// Put on the equipment in the selected hotbar slot, using it up, and return whether there
+    /// was any
+    ///
+    /// A jetpack isn't put on over one the player is already wearing.
+    pub fn equip(&mut self) -> bool {
+        let held = self.held_item().map(|stack| &stack.item);
+        if self.jetpack.is_some() || held != Some(&Jetpack::item()) {
+            return false;
         }
+        _ = self.inventory.take(self.hotbar_slot, 1);
+        self.jetpack = Some(Jetpack::new(Jetpack::CAPACITY));
+        true
     }
 
     /// Tick player actions
     pub fn do_actions(
-        &self,
+        &mut self,
         _rl: &mut RaylibHandle,
         _thread: &RaylibThread,
-        _inputs: &Inputs,
+        inputs: &Inputs,
         _current_region: &mut dyn Region,
     ) {
-        _ = self.pitch.sin();
+        use input::EventInput::{CycleSnap, NextItem, PrevItem};
+
+        if inputs[NextItem] {
+            self.hotbar_slot = (self.hotbar_slot + 1) % Self::HOTBAR_SLOTS;
+        }
+        if inputs[PrevItem] {
+            self.hotbar_slot = (self.hotbar_slot + Self::HOTBAR_SLOTS - 1) % Self::HOTBAR_SLOTS;
+        }
+        if inputs[CycleSnap] {
+            self.snap = self.snap.next();
+        }
+    }
+
+    /// Breathe air that takes `damage` health per second for `dt` seconds, returning how much
+    /// the player is hurt by it
+    ///
+    /// A gas mask keeps most of it off, using up its filter while there's any gas to filter.
+    pub fn breathe(&mut self, damage: f32, dt: f32) -> Option<Damage> {
+        if damage <= 0.0 {
+            return None;
+        }
+        // Only for as long as the filter lasts
+        let filtered = self.gas_mask.as_mut().map_or(0.0, |mask| {
+            let filtered = mask.filter.min(dt);
+            mask.filter -= filtered;
+            filtered
+        });
+        let lost = damage * (dt - filtered * GasMask::PROTECTION);
+        Some(Damage::new(DamageSource::Gas, lost))
+    }
+
+    /// The inventory entry in the selected hotbar slot, if any
+    pub fn held_item(&self) -> Option<&ItemStack> {
+        self.inventory.stacks().get(self.hotbar_slot)
+    }
+
+    /// The box around the player's body, standing on [`Self::position`]
+    pub const fn bounds(&self) -> PlayerBounds {
+        Self::bounds_at(self.position)
+    }
+
+    /// The box around a player's body standing on `position`
+    pub const fn bounds_at(position: PlayerVector3) -> PlayerBounds {
+        let radius = PlayerCoord::from_f32(Self::RADIUS);
+        PlayerBounds {
+            min: position.minus(PlayerVector3::new(radius, PlayerCoord::ZERO, radius)),
+            max: position.plus(PlayerVector3::new(
+                radius,
+                PlayerCoord::from_f32(Self::HEIGHT),
+                radius,
+            )),
+        }
     }
 
     pub const fn eye_pos(&self) -> PlayerVector3 {
@@ -163,10 +746,16 @@ impl Player {
             .plus(PlayerVector3::UP.scale(PlayerCoord::from_f32(Self::EYE_HEIGHT)))
     }
 
+    /// The volume visible to [`Self::camera`], in player-relative coordinates
+    pub fn view_frustum(&self) -> Frustum {
+        Frustum::from_camera(&self.camera, self.aspect_ratio)
+    }
+
     pub fn vision_ray(&self) -> Ray {
+        let (eye, target) = camera_helper(self.pitch, self.yaw);
         Ray {
             position: self.eye_pos().to_vec3(),
-            direction: (self.camera.target - self.camera.position).normalize_or(Vector3::FORWARD),
+            direction: (target - eye).normalize_or(Vector3::FORWARD),
         }
     }
 
@@ -179,3 +768,91 @@ impl Player {
         8.6
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smooth_angle() {
+        // Turns the short way across the wrap
+        let angle = smooth_angle(0.1, 2.0 * PI - 0.1, 1.0, 1.0);
+        assert!((-0.1..0.1).contains(&angle), "{angle}");
+        assert!((smooth(1.0, 3.0, 0.0, 1.0 / 60.0) - 3.0).abs() < f32::EPSILON);
+        assert!((smooth(1.0, 3.0, 1.0, 1.0) - (3.0 - 2.0 / std::f32::consts::E)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_movement_state() {
+        use MovementState::*;
+        let (idle, forward, back) = (Vector2::ZERO, Vector2::Y, Vector2::NEG_Y);
+
+        assert_eq!(Grounded.next(true, false, idle, true, false), Airborne);
+        assert_eq!(Airborne.next(true, false, idle, false, false), Grounded);
+        assert_eq!(Airborne.next(false, false, idle, true, false), Airborne);
+
+        // Ladders
+        assert_eq!(Grounded.next(true, true, idle, false, false), Grounded);
+        assert_eq!(Grounded.next(true, true, forward, false, false), Climbing);
+        assert_eq!(Climbing.next(false, true, idle, false, false), Climbing);
+        assert_eq!(Climbing.next(true, true, back, false, false), Grounded);
+        assert_eq!(Climbing.next(false, true, idle, true, false), Airborne);
+        assert_eq!(Climbing.next(false, false, forward, false, false), Airborne);
+
+        // Jetpack
+        assert_eq!(Airborne.next(false, false, idle, true, true), Flying);
+        assert_eq!(Flying.next(false, false, forward, false, true), Flying);
+        assert_eq!(Flying.next(false, false, idle, false, false), Airborne);
+        assert_eq!(Flying.next(false, false, idle, true, true), Airborne);
+        assert_eq!(Flying.next(true, false, idle, false, true), Grounded);
+    }
+
+    #[test]
+    fn test_gas_mask() {
+        let mut player = Player::new(PlayerVector3::ZERO, 0.0, 0.0, 45.0, 1.0);
+        let lost = |player: &mut Player, damage: f32| {
+            player.breathe(damage, 1.0).map_or(0.0, |damage| {
+                assert_eq!(damage.source, DamageSource::Gas);
+                damage.amount
+            })
+        };
+        assert!((lost(&mut player, 10.0) - 10.0).abs() < 1e-6);
+
+        player.gas_mask = Some(GasMask::new(1.5));
+        assert!((lost(&mut player, 10.0) - 1.0).abs() < 1e-4);
+        assert!(lost(&mut player, 0.0).abs() < f32::EPSILON);
+        assert!(
+            (player.gas_mask.unwrap().filter - 0.5).abs() < 1e-6,
+            "filters only get used up in gas"
+        );
+        assert!(
+            (lost(&mut player, 10.0) - 5.5).abs() < 1e-4,
+            "filters only help while they last"
+        );
+        assert!((lost(&mut player, 10.0) - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_equip() {
+        let mut player = Player::new(PlayerVector3::ZERO, 0.0, 0.0, 45.0, 1.0);
+        _ = player.give(ItemStack::new("Fe".parse().unwrap(), 1));
+        assert!(!player.equip(), "iron isn't equipment");
+        _ = player.give(ItemStack::new(Jetpack::item(), 2));
+        player.hotbar_slot = 1;
+        assert!(player.equip());
+        assert_eq!(player.jetpack, Some(Jetpack::new(Jetpack::CAPACITY)));
+        assert_eq!(player.held_item().unwrap().count, 1);
+        assert!(!player.equip(), "one jetpack at a time");
+
+        let recipe = crate::region::factory::recipe::find("Jetpack").unwrap();
+        assert_eq!(recipe.reaction().products()[0].1, Jetpack::item());
+    }
+
+    #[test]
+    fn test_fall_damage() {
+        assert_eq!(Player::fall_damage(Player::SAFE_FALL_SPEED), None);
+        let damage = Player::fall_damage(Player::SAFE_FALL_SPEED + 2.0).unwrap();
+        assert_eq!(damage.source, DamageSource::Fall);
+        assert!((damage.amount - 2.0 * Player::FALL_DAMAGE).abs() < 1e-4);
+    }
+}
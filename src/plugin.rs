@@ -0,0 +1,471 @@
+//! Adding machine types, recipes, and items without changing the game's own code
+//!
+//! A [`Plugin`] adds what it brings to a [`Registry`] when it's [installed](install), and the
+//! game reads the registry back with [`registry`] for as long as it runs. Plugins are compiled in:
+//! a crate depending on this one passes its own to [`run`](crate::run), and the game's binary
+//! passes none.
+//!
+//! Machine types are built as [`PluginMachine`]s, which run their recipe with whatever comes in
+//! on their input belt. Models are loaded into [`Resources`](crate::resource::Resources) as
+//! [`AssetId::PluginModel`] and [`AssetId::PluginMaterial`], and acquired for the whole game by
+//! [`Registry::assets`].
+//!
+//! [`PluginMachine`]: crate::region::factory::plugin::PluginMachine
+
+pub use crate::{
+    chem::reaction::{BalancedReaction, Reaction},
+    container::Item,
+    region::factory::{
+        MachineSize, pressure::VesselProperties, recipe::Recipe, thermal::ThermalProperties,
+    },
+};
+use crate::{
+    item_visual::{ItemVisual, ItemVisuals},
+    region::factory::recipe,
+    resource::AssetId,
+};
+use raylib::prelude::*;
+use std::sync::OnceLock;
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+/// Names of the machines built into the game, which plugins can't reuse
+const BUILT_IN_MACHINES: [&str; 15] = [
+    "reactor",
+    "miner",
+    "splitter",
+    "merger",
+    "station",
+    "vent",
+    "ladder",
+    "controller",
+    "assembler",
+    "tank",
+    "charging_pad",
+    "relief_valve",
+    "distiller",
+    "crystallizer",
+    "electrolyzer",
+];
+
+/// Something that adds to the game at startup
+pub trait Plugin: Sync {
+    /// Shown in errors about what it registers
+    fn name(&self) -> &'static str;
+
+    /// Add everything the plugin brings to `registry`
+    fn register(&self, registry: &mut Registry);
+}
+
+/// Identifies a [`PluginModel`] in the [`Registry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModelId(u16);
+
+/// What a plugin's machine or item looks like
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PluginModel {
+    /// A model file raylib can load, like a `.glb`, drawn in a single `color`
+    ///
+    /// The path is relative to the working directory, or to the asset directory while hot
+    /// reloading if it's there. Machine models should have the machine's corner at the origin,
+    /// and item models their middle.
+    File { path: &'static str, color: Color },
+    /// A box of `size` meters with its middle at `center`
+    Box {
+        size: Vector3,
+        center: Vector3,
+        color: Color,
+    },
+}
+
+impl PluginModel {
+    /// A box filling a machine of `size`, which is placed from its corner
+    #[must_use]
+    pub fn machine_box(size: MachineSize, color: Color) -> Self {
+        let size = Vector3::new(
+            size.width.get().into(),
+            size.height.get().into(),
+            size.length.get().into(),
+        );
+        Self::Box {
+            size,
+            center: size * 0.5,
+            color,
+        }
+    }
+
+    /// The color the model is drawn in
+    #[must_use]
+    pub const fn color(&self) -> Color {
+        match *self {
+            Self::File { color, .. } | Self::Box { color, .. } => color,
+        }
+    }
+}
+
+/// How a plugin's item is drawn out in the world
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemLook {
+    /// The item icon, tinted
+    Icon(Color),
+    Model(ModelId),
+}
+
+impl ItemLook {
+    #[must_use]
+    pub const fn visual(self) -> ItemVisual {
+        match self {
+            Self::Icon(tint) => ItemVisual::Billboard {
+                texture: AssetId::ItemIcon,
+                tint,
+            },
+            Self::Model(id) => ItemVisual::Mesh {
+                model: AssetId::PluginModel(id),
+                material: AssetId::PluginMaterial(id),
+            },
+        }
+    }
+}
+
+/// A type of machine added by a plugin
+#[derive(Debug, Clone, PartialEq)]
+pub struct MachineType {
+    /// Used to spawn it in the console and to tell it apart from other types
+    pub name: &'static str,
+    /// The name shown to the player if the language has no `machine.<name>` of its own
+    pub label: &'static str,
+    pub size: MachineSize,
+    /// What it makes out of what's brought to it, if anything
+    pub recipe: Option<Recipe>,
+    /// How it heats up, if it does
+    pub thermal: Option<ThermalProperties>,
+    /// How it holds gas in, if it's sealed
+    pub vessel: Option<VesselProperties>,
+    /// Machines without a model are only drawn with
+    /// [`DebugVis`](engine::draw3d::DebugVis)
+    pub model: Option<ModelId>,
+}
+
+/// Why something a plugin registered was left out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterError {
+    /// A machine type has the name of another one, or of a built in machine
+    DuplicateMachine {
+        plugin: &'static str,
+        name: &'static str,
+    },
+    /// A recipe has the name of another one with a different reaction
+    DuplicateRecipe {
+        plugin: &'static str,
+        name: &'static str,
+    },
+    /// An item was given a look by an earlier plugin
+    DuplicateItem { plugin: &'static str, item: Item },
+    /// Something used a model that was never added
+    UnknownModel { plugin: &'static str },
+    /// More models were added than an [`AssetId`] can tell apart
+    TooManyModels { plugin: &'static str },
+}
+
+impl std::fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DuplicateMachine { plugin, name } => {
+                write!(f, "{plugin}: there's already a machine called {name}")
+            }
+            Self::DuplicateRecipe { plugin, name } => {
+                write!(
+                    f,
+                    "{plugin}: there's already a different recipe called {name}"
+                )
+            }
+            Self::DuplicateItem { plugin, item } => {
+                write!(f, "{plugin}: {item} already has a look")
+            }
+            Self::UnknownModel { plugin } => write!(f, "{plugin}: used a model it didn't add"),
+            Self::TooManyModels { plugin } => write!(f, "{plugin}: too many models"),
+        }
+    }
+}
+
+impl std::error::Error for RegisterError {}
+
+/// Everything added by plugins
+#[derive(Debug, Default)]
+pub struct Registry {
+    machines: Vec<MachineType>,
+    recipes: Vec<Recipe>,
+    items: Vec<(Item, ItemLook)>,
+    models: Vec<PluginModel>,
+    /// The plugin being installed, which errors are blamed on
+    plugin: &'static str,
+    errors: Vec<RegisterError>,
+}
+
+impl Registry {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            machines: Vec::new(),
+            recipes: Vec::new(),
+            items: Vec::new(),
+            models: Vec::new(),
+            plugin: "",
+            errors: Vec::new(),
+        }
+    }
+
+    /// A registry with everything from each of `plugins`, and why anything was left out
+    pub fn build(plugins: &[&dyn Plugin]) -> (Self, Vec<RegisterError>) {
+        let mut registry = Self::new();
+        for plugin in plugins {
+            registry.plugin = plugin.name();
+            plugin.register(&mut registry);
+        }
+        let errors = std::mem::take(&mut registry.errors);
+        (registry, errors)
+    }
+
+    /// Add a model for machines and items to use
+    ///
+    /// Returns [`None`] if there are already too many.
+    pub fn add_model(&mut self, model: PluginModel) -> Option<ModelId> {
+        let Ok(id) = u16::try_from(self.models.len()) else {
+            self.errors.push(RegisterError::TooManyModels {
+                plugin: self.plugin,
+            });
+            return None;
+        };
+        self.models.push(model);
+        Some(ModelId(id))
+    }
+
+    /// Add a recipe, which can be shared by several machine types
+    ///
+    /// Adding the same recipe again does nothing, but recipes can't reuse the names of ones built
+    /// into the game.
+    pub fn add_recipe(&mut self, recipe: Recipe) {
+        let built_in = (recipe::built_in().iter()).any(|existing| existing.name() == recipe.name());
+        match self.recipe(recipe.name()) {
+            Some(existing) if *existing == recipe => {}
+            _ if built_in => self.errors.push(RegisterError::DuplicateRecipe {
+                plugin: self.plugin,
+                name: recipe.name(),
+            }),
+            Some(_) => self.errors.push(RegisterError::DuplicateRecipe {
+                plugin: self.plugin,
+                name: recipe.name(),
+            }),
+            None => self.recipes.push(recipe),
+        }
+    }
+
+    /// Add a type of machine, along with its recipe
+    pub fn add_machine(&mut self, machine: MachineType) {
+        if BUILT_IN_MACHINES.contains(&machine.name) || self.machine(machine.name).is_some() {
+            self.errors.push(RegisterError::DuplicateMachine {
+                plugin: self.plugin,
+                name: machine.name,
+            });
+            return;
+        }
+        if !self.has_model(machine.model) {
+            self.errors.push(RegisterError::UnknownModel {
+                plugin: self.plugin,
+            });
+            return;
+        }
+        if let Some(recipe) = &machine.recipe {
+            let errors = self.errors.len();
+            self.add_recipe(recipe.clone());
+            if self.errors.len() > errors {
+                return;
+            }
+        }
+        self.machines.push(machine);
+    }
+
+    /// Draw `item` with `look` out in the world
+    pub fn add_item(&mut self, item: Item, look: ItemLook) {
+        let model = match look {
+            ItemLook::Icon(_) => None,
+            ItemLook::Model(id) => Some(id),
+        };
+        if !self.has_model(model) {
+            self.errors.push(RegisterError::UnknownModel {
+                plugin: self.plugin,
+            });
+        } else if self.items.iter().any(|(other, _)| *other == item) {
+            self.errors.push(RegisterError::DuplicateItem {
+                plugin: self.plugin,
+                item,
+            });
+        } else {
+            self.items.push((item, look));
+        }
+    }
+
+    fn has_model(&self, model: Option<ModelId>) -> bool {
+        model.is_none_or(|ModelId(idx)| usize::from(idx) < self.models.len())
+    }
+
+    /// The machine type called `name`
+    #[must_use]
+    pub fn machine(&self, name: &str) -> Option<&MachineType> {
+        self.machines.iter().find(|machine| machine.name == name)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn machines(&self) -> &[MachineType] {
+        &self.machines
+    }
+
+    /// The recipe called `name`
+    #[must_use]
+    pub fn recipe(&self, name: &str) -> Option<&Recipe> {
+        self.recipes.iter().find(|recipe| recipe.name() == name)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn recipes(&self) -> &[Recipe] {
+        &self.recipes
+    }
+
+    #[must_use]
+    pub fn model(&self, ModelId(idx): ModelId) -> Option<&PluginModel> {
+        self.models.get(usize::from(idx))
+    }
+
+    /// Every plugin model and its material, which stay acquired while the game runs
+    pub fn assets(&self) -> Vec<AssetId> {
+        (0..self.models.len())
+            .filter_map(|idx| u16::try_from(idx).ok())
+            .map(ModelId)
+            .flat_map(|id| [AssetId::PluginModel(id), AssetId::PluginMaterial(id)])
+            .collect()
+    }
+
+    /// Draw each item with the look a plugin gave it
+    pub fn register_items(&self, visuals: &mut ItemVisuals) {
+        for (item, look) in &self.items {
+            visuals.register(item.clone(), look.visual());
+        }
+    }
+}
+
+/// Install every plugin in `plugins`, returning why anything they added was left out
+///
+/// Only the first call does anything, and it has to come before anything reads [`registry`].
+pub fn install(plugins: &[&dyn Plugin]) -> Vec<RegisterError> {
+    let (registry, errors) = Registry::build(plugins);
+    _ = REGISTRY.set(registry);
+    errors
+}
+
+/// Everything the installed plugins added, or nothing if they haven't been installed
+pub fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(Registry::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chem::reaction::Reaction;
+    use std::time::Duration;
+
+    struct Kiln;
+
+    impl Plugin for Kiln {
+        fn name(&self) -> &'static str {
+            "kiln"
+        }
+
+        fn register(&self, registry: &mut Registry) {
+            let size = MachineSize::new(2, 2, 2).unwrap();
+            let model = registry.add_model(PluginModel::machine_box(size, Color::ORANGE));
+            let reaction = "CaCO3 -> CaO + CO2".parse::<Reaction>().unwrap();
+            registry.add_machine(MachineType {
+                name: "kiln",
+                label: "Kiln",
+                size,
+                recipe: Some(Recipe::new(
+                    "Calcination",
+                    reaction.balance().unwrap(),
+                    Duration::from_secs(4),
+                )),
+                thermal: None,
+                vessel: None,
+                model,
+            });
+            registry.add_item("CaO".parse().unwrap(), ItemLook::Icon(Color::WHITE));
+        }
+    }
+
+    struct Copycat;
+
+    impl Plugin for Copycat {
+        fn name(&self) -> &'static str {
+            "copycat"
+        }
+
+        fn register(&self, registry: &mut Registry) {
+            let size = MachineSize::new(1, 1, 1).unwrap();
+            for name in ["kiln", "vent"] {
+                registry.add_machine(MachineType {
+                    name,
+                    label: "Copy",
+                    size,
+                    recipe: None,
+                    thermal: None,
+                    vessel: None,
+                    model: None,
+                });
+            }
+            registry.add_item("CaO".parse().unwrap(), ItemLook::Icon(Color::BLACK));
+            registry.add_item("Fe".parse().unwrap(), ItemLook::Model(ModelId(7)));
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let (registry, errors) = Registry::build(&[&Kiln, &Copycat]);
+        assert_eq!(registry.machines().len(), 1);
+        let kiln = registry.machine("kiln").unwrap();
+        assert_eq!(kiln.label, "Kiln");
+        assert!(registry.recipe("Calcination").is_some());
+        assert_eq!(
+            registry.assets(),
+            [
+                AssetId::PluginModel(ModelId(0)),
+                AssetId::PluginMaterial(ModelId(0))
+            ]
+        );
+        assert_eq!(
+            errors,
+            [
+                RegisterError::DuplicateMachine {
+                    plugin: "copycat",
+                    name: "kiln"
+                },
+                RegisterError::DuplicateMachine {
+                    plugin: "copycat",
+                    name: "vent"
+                },
+                RegisterError::DuplicateItem {
+                    plugin: "copycat",
+                    item: "CaO".parse().unwrap()
+                },
+                RegisterError::UnknownModel { plugin: "copycat" },
+            ]
+        );
+
+        let mut visuals = ItemVisuals::new();
+        registry.register_items(&mut visuals);
+        assert_eq!(
+            visuals.get(&"CaO".parse().unwrap()),
+            ItemLook::Icon(Color::WHITE).visual()
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! Timing how long each part of a frame takes
+//!
+//! Parts of the frame are timed with [`Timer::start`] and [`Profiler::stop`], and
+//! [`Profiler::end_frame`] keeps the last [`HISTORY`] frames. Those are drawn as an overlay,
+//! and can be exported as a [Chrome trace] to look at in `chrome://tracing` or Perfetto.
+//!
+//! [Chrome trace]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer},
+};
+use raylib::prelude::*;
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// How many frames are kept
+pub const HISTORY: usize = 240;
+
+/// How many of the slowest frames are listed
+const WORST_FRAMES: usize = 3;
+
+const FONT_SIZE: f32 = 20.0;
+const MARGIN: f32 = 16.0;
+const WIDTH: f32 = 360.0;
+const GRAPH_HEIGHT: f32 = 80.0;
+const BACKGROUND: Color = Color::new(0, 0, 0, 180);
+/// Frames taking this long fill the graph, 30 FPS
+const GRAPH_MAX: Duration = Duration::from_micros(33_333);
+/// The frame time to stay under, 60 FPS
+const BUDGET: Duration = Duration::from_micros(16_667);
+
+/// A part of the frame that gets timed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    /// Reading input and moving the player
+    Input,
+    /// Ticking the game, and everything else that happens between input and drawing
+    Simulation,
+    /// Drawing, including waiting for the next frame
+    Render,
+}
+
+impl Section {
+    pub const ALL: [Self; 3] = [Self::Input, Self::Simulation, Self::Render];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Input => "Input",
+            Self::Simulation => "Simulation",
+            Self::Render => "Render",
+        }
+    }
+
+    const fn color(self) -> Color {
+        match self {
+            Self::Input => Color::SKYBLUE,
+            Self::Simulation => Color::ORANGE,
+            Self::Render => Color::LIME,
+        }
+    }
+}
+
+/// Started by [`Timer::start`], and recorded when passed to [`Profiler::stop`]
+#[must_use = "nothing is recorded until the timer is stopped"]
+#[derive(Debug)]
+pub struct Timer {
+    section: Section,
+    start: Instant,
+}
+
+/// One timed part of a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    section: Section,
+    start: Instant,
+    duration: Duration,
+}
+
+/// Everything timed in one frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Frame {
+    start: Instant,
+    duration: Duration,
+    spans: Vec<Span>,
+}
+
+impl Frame {
+    /// Time spent in `section`, adding up each time it was timed
+    fn total(&self, section: Section) -> Duration {
+        (self.spans.iter())
+            .filter(|span| span.section == section)
+            .map(|span| span.duration)
+            .sum()
+    }
+}
+
+/// Times each [`Section`] of the last [`HISTORY`] frames
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    /// When the profiler was made, which traces are relative to
+    epoch: Instant,
+    history: VecDeque<Frame>,
+    current: Frame,
+    /// Whether the overlay is drawn
+    pub is_visible: bool,
+    /// Size of the screen in pixels, for placing the overlay
+    pub screen: Vector2,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        let now = Instant::now();
+        Self {
+            epoch: now,
+            history: VecDeque::with_capacity(HISTORY),
+            current: Frame {
+                start: now,
+                duration: Duration::ZERO,
+                spans: Vec::new(),
+            },
+            is_visible: false,
+            screen: Vector2::ZERO,
+        }
+    }
+}
+
+impl Timer {
+    /// Start timing `section`
+    pub fn start(section: Section) -> Self {
+        Self {
+            section,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Profiler {
+    /// Record how long it's been since `timer` was started
+    #[allow(
+        clippy::needless_pass_by_value,
+        reason = "taking the timer keeps it from being stopped twice"
+    )]
+    pub fn stop(&mut self, timer: Timer) {
+        self.current.spans.push(Span {
+            section: timer.section,
+            start: timer.start,
+            duration: timer.start.elapsed(),
+        });
+    }
+
+    /// Finish the frame and start the next one, forgetting the oldest if there are more than
+    /// [`HISTORY`]
+    pub fn end_frame(&mut self) {
+        let now = Instant::now();
+        let mut frame = std::mem::replace(
+            &mut self.current,
+            Frame {
+                start: now,
+                duration: Duration::ZERO,
+                spans: Vec::new(),
+            },
+        );
+        frame.duration = now - frame.start;
+        if self.history.len() == HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(frame);
+    }
+
+    /// Average time spent in `section` over the kept frames
+    pub fn average(&self, section: Section) -> Duration {
+        let total = (self.history.iter())
+            .map(|frame| frame.total(section))
+            .sum::<Duration>();
+        total / u32::try_from(self.history.len().max(1)).unwrap_or(u32::MAX)
+    }
+
+    /// How long the slowest kept frames took, slowest first
+    pub fn worst_frames(&self, count: usize) -> Vec<Duration> {
+        let mut durations = (self.history.iter())
+            .map(|frame| frame.duration)
+            .collect::<Vec<_>>();
+        durations.sort_unstable_by(|a, b| b.cmp(a));
+        durations.truncate(count);
+        durations
+    }
+
+    /// Write the kept frames as a Chrome trace, with an event for each frame and each span in
+    /// it, in microseconds since the profiler was made
+    pub fn write_trace(&self, out: &mut impl Write) -> io::Result<()> {
+        let micros = |instant: Instant| (instant - self.epoch).as_secs_f64() * 1e6;
+        let event = |out: &mut dyn Write, name: &str, start: Instant, duration: Duration| {
+            write!(
+                out,
+                r#"{{"name":"{name}","ph":"X","ts":{:.3},"dur":{:.3},"pid":1,"tid":1}}"#,
+                micros(start),
+                duration.as_secs_f64() * 1e6,
+            )
+        };
+        out.write_all(br#"{"traceEvents":["#)?;
+        let mut first = true;
+        for frame in &self.history {
+            let spans =
+                (frame.spans.iter()).map(|span| (span.section.name(), span.start, span.duration));
+            for (name, start, duration) in
+                std::iter::once(("Frame", frame.start, frame.duration)).chain(spans)
+            {
+                if !first {
+                    out.write_all(b",")?;
+                }
+                first = false;
+                event(out, name, start, duration)?;
+            }
+        }
+        out.write_all(br#"],"displayTimeUnit":"ms"}"#)?;
+        out.flush()
+    }
+
+    /// Write the kept frames as a Chrome trace to `path`, see [`Self::write_trace`]
+    pub fn export_trace(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_trace(&mut file)
+    }
+
+    fn rect(&self) -> Rectangle {
+        #[allow(clippy::cast_precision_loss, reason = "there are only a few sections")]
+        let lines = (Section::ALL.len() + 1) as f32;
+        Rectangle::new(
+            self.screen.x - MARGIN - WIDTH,
+            MARGIN,
+            WIDTH,
+            GRAPH_HEIGHT + lines * FONT_SIZE + 3.0 * MARGIN,
+        )
+    }
+}
+
+impl Draw for Profiler {
+    /// The time each section took in each kept frame as stacked bars, with averages and the
+    /// slowest frames below
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        if !self.is_visible {
+            return Ok(());
+        }
+        let rect = self.rect();
+        d.draw_rectangle(rect, BACKGROUND)?;
+
+        let graph = Rectangle::new(
+            rect.x + MARGIN,
+            rect.y + MARGIN,
+            rect.width - 2.0 * MARGIN,
+            GRAPH_HEIGHT,
+        );
+        #[allow(clippy::cast_precision_loss, reason = "the history is short")]
+        let bar_width = graph.width / HISTORY as f32;
+        let height = |duration: Duration| {
+            (duration.as_secs_f32() / GRAPH_MAX.as_secs_f32()).min(1.0) * graph.height
+        };
+        for (i, frame) in self.history.iter().enumerate() {
+            #[allow(clippy::cast_precision_loss, reason = "the history is short")]
+            let x = graph.x + i as f32 * bar_width;
+            let mut bottom = graph.y + graph.height;
+            for section in Section::ALL {
+                let bar = height(frame.total(section)).min(bottom - graph.y);
+                bottom -= bar;
+                d.draw_rectangle(Rectangle::new(x, bottom, bar_width, bar), section.color())?;
+            }
+        }
+        let budget = graph.y + graph.height - height(BUDGET);
+        d.draw_line(
+            Vector2::new(graph.x, budget),
+            Vector2::new(graph.x + graph.width, budget),
+            Some(1.0),
+            Color::WHITE,
+        )?;
+
+        let mut y = graph.y + graph.height + MARGIN;
+        for section in Section::ALL {
+            let average = self.average(section).as_secs_f64() * 1e3;
+            d.draw_text(
+                &format!("{}: {average:.2} ms", section.name()),
+                Vector2::new(graph.x, y),
+                FONT_SIZE,
+                0.0,
+                section.color(),
+            )?;
+            y += FONT_SIZE;
+        }
+        let worst = (self.worst_frames(WORST_FRAMES).iter())
+            .map(|duration| format!("{:.1}", duration.as_secs_f64() * 1e3))
+            .collect::<Vec<_>>()
+            .join(", ");
+        d.draw_text(
+            &format!("Worst frames: {worst} ms"),
+            Vector2::new(graph.x, y),
+            FONT_SIZE,
+            0.0,
+            Color::WHITE,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiler() {
+        let mut profiler = Profiler::default();
+        for _ in 0..HISTORY + 2 {
+            let timer = Timer::start(Section::Simulation);
+            profiler.stop(timer);
+            profiler.end_frame();
+        }
+        assert_eq!(profiler.history.len(), HISTORY);
+        assert_eq!(profiler.average(Section::Render), Duration::ZERO);
+        assert_eq!(profiler.worst_frames(WORST_FRAMES).len(), WORST_FRAMES);
+
+        let mut trace = Vec::new();
+        profiler.write_trace(&mut trace).unwrap();
+        let trace = String::from_utf8(trace).unwrap();
+        assert!(trace.starts_with(r#"{"traceEvents":[{"name":"Frame""#));
+        assert_eq!(trace.matches(r#""name":"Simulation""#).count(), HISTORY);
+        assert!(trace.ends_with(r#"}],"displayTimeUnit":"ms"}"#));
+    }
+}
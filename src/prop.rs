@@ -0,0 +1,285 @@
+//! Property tests, checking that laws hold for many random inputs instead of a few picked ones
+//!
+//! Inputs are drawn from an [`Rng`] split off for each case by its number, so a failure names the
+//! case and the input that broke it, and fails the same way every run. [`fuzz`] does the same for
+//! parsers, feeding them random text and only checking that they don't panic.
+
+use crate::{
+    input::Bindings,
+    math::{
+        bounds::{Bounds, FactoryBounds},
+        coords::{FactoryVector3, PlayerVector3, RailVector3, VectorConstants},
+    },
+    ordinals::Cardinal2D,
+    region::factory::{Clearance, MachineSize, OrientedFootprint, Reactor},
+    rng::Rng,
+};
+use fixed_point::Q32_32;
+use std::{collections::HashSet, fmt::Debug, panic::AssertUnwindSafe};
+
+/// Inputs tried for each property
+const CASES: u64 = 512;
+
+/// Where every case's inputs come from
+const SEED: u64 = 0x9e0_9e47;
+
+/// Check that `property` holds for [`CASES`] inputs made by `arbitrary`
+///
+/// # Panics
+///
+/// With the case and its input, if the property doesn't hold for one
+fn check<T: Debug>(arbitrary: impl Fn(&mut Rng) -> T, property: impl Fn(&T) -> bool) {
+    let base = Rng::new(SEED);
+    for case in 0..CASES {
+        let input = arbitrary(&mut base.split(case));
+        assert!(property(&input), "case {case} doesn't hold for {input:?}");
+    }
+}
+
+/// Feed `target` [`CASES`] random strings, made mostly of `alphabet` so they get further than the
+/// first character
+///
+/// # Panics
+///
+/// With the case and its text, if `target` panics on one
+fn fuzz(alphabet: &str, target: impl Fn(&str)) {
+    let alphabet = alphabet.chars().collect::<Vec<_>>();
+    let base = Rng::new(SEED);
+    for case in 0..CASES {
+        let mut rng = base.split(case);
+        let len = rng.below(64);
+        let text = (0..len)
+            .map(|_| match rng.below(8) {
+                0 => char::from_u32(rng.below(0x11_0000)).unwrap_or(char::REPLACEMENT_CHARACTER),
+                _ => *rng.pick(&alphabet).unwrap_or(&' '),
+            })
+            .collect::<String>();
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| target(&text)));
+        assert!(result.is_ok(), "case {case} panicked on {text:?}");
+    }
+}
+
+/// Up to about ±32768 with every fractional bit, so sums and products of two don't overflow
+fn q32(rng: &mut Rng) -> Q32_32 {
+    Q32_32::from_bits(rng.next_u64().cast_signed() >> 16)
+}
+
+fn i32_within(rng: &mut Rng, limit: u32) -> i32 {
+    (rng.next_u32().cast_signed()) % limit.cast_signed()
+}
+
+fn rail(rng: &mut Rng, limit: u32) -> RailVector3 {
+    RailVector3::new(
+        i32_within(rng, limit),
+        i32_within(rng, limit),
+        i32_within(rng, limit),
+    )
+}
+
+fn factory(rng: &mut Rng) -> FactoryVector3 {
+    let mut i16 = || {
+        #[allow(clippy::cast_possible_truncation, reason = "any 16 bits will do")]
+        (rng.next_u32() as i16)
+    };
+    FactoryVector3::new(i16(), i16(), i16())
+}
+
+fn rotation(rng: &mut Rng) -> Cardinal2D {
+    let all = [
+        Cardinal2D::East,
+        Cardinal2D::North,
+        Cardinal2D::West,
+        Cardinal2D::South,
+    ];
+    *rng.pick(&all).unwrap()
+}
+
+#[test]
+fn test_fixed_point_laws() {
+    check(
+        |rng| (q32(rng), q32(rng)),
+        |&(a, b)| {
+            a + b == b + a
+                && a * b == b * a
+                && (a + b) - b == a
+                && a.lerp(b, Q32_32::ZERO) == a
+                && a.lerp(b, Q32_32::ONE) == b
+                && a.min(b) <= a.max(b)
+        },
+    );
+    check(
+        |rng| (rng.next_u32().cast_signed(), q32(rng)),
+        |&(n, a)| {
+            // f32 keeps 24 significant bits
+            let error = (Q32_32::from_f32(a.to_f32()) - a).abs();
+            Q32_32::from_i32(n).to_i32() == n
+                && Q32_32::from_bits(a.to_bits()) == a
+                && error <= Q32_32::from_bits((a.abs().to_bits() >> 23) + 1)
+        },
+    );
+    check(
+        |rng| (rng.next_u32().cast_signed(), q32(rng).to_f32(), q32(rng)),
+        |&(n, f, a)| n * a == a * n && f * a == a * f,
+    );
+}
+
+#[test]
+fn test_coordinate_round_trips() {
+    check(
+        |rng| rail(rng, u32::MAX >> 1),
+        |&v| v.to_player().to_rail() == v,
+    );
+    check(
+        |rng| (rail(rng, 1 << 30), factory(rng)),
+        |&(origin, v)| v.to_rail(origin).to_factory(&origin) == Ok(v),
+    );
+    check(
+        |rng| (rail(rng, 1 << 30), rail(rng, 1 << 17)),
+        |&(origin, offset)| {
+            let in_range = [offset.x, offset.y, offset.z]
+                .iter()
+                .all(|&c| i16::try_from(c).is_ok());
+            (origin + offset).to_factory(&origin).is_ok() == in_range
+        },
+    );
+    check(
+        |rng| PlayerVector3::new(q32(rng), q32(rng), q32(rng)),
+        |&v| {
+            // Truncates down to the block the point is in
+            let offset = v - v.to_rail().to_player();
+            [offset.x, offset.y, offset.z]
+                .iter()
+                .all(|&c| Q32_32::ZERO <= c && c < Q32_32::ONE)
+        },
+    );
+}
+
+/// Up to ±256 meters, so cross products and their dot products don't overflow
+fn short_vector(rng: &mut Rng) -> PlayerVector3 {
+    let mut coord = || Q32_32::from_bits(rng.next_u64().cast_signed() >> 23);
+    PlayerVector3::new(coord(), coord(), coord())
+}
+
+/// Whether `a` is within `tolerance` of `b`
+fn near(a: Q32_32, b: Q32_32, tolerance: Q32_32) -> bool {
+    (a - b).abs() <= tolerance
+}
+
+#[test]
+fn test_vector_ops() {
+    let tolerance = Q32_32::from_bits(1 << 20);
+    check(
+        |rng| (short_vector(rng), short_vector(rng)),
+        |&(a, b)| {
+            let cross = a.cross(b);
+            let scale = a.length() * b.length() * tolerance;
+            near(cross.dot(a), Q32_32::ZERO, scale) && near(cross.dot(b), Q32_32::ZERO, scale)
+        },
+    );
+    check(
+        |rng| {
+            // From a fraction of a millimeter up, so short vectors are covered too
+            let shift = rng.below(20);
+            let mut coord = || Q32_32::from_bits(rng.next_u64().cast_signed() >> (23 + shift));
+            PlayerVector3::new(coord(), coord(), coord())
+        },
+        |&v| match v.normalize() {
+            Some(unit) => {
+                near(unit.length(), Q32_32::ONE, tolerance) && unit.dot(v) >= Q32_32::ZERO
+            }
+            None => v == PlayerVector3::ZERO,
+        },
+    );
+    check(
+        |rng| (short_vector(rng), short_vector(rng)),
+        |&(v, onto)| match (v.project_onto(onto), v.reject_from(onto)) {
+            (Some(along), Some(across)) => {
+                along + across == v
+                    && near(
+                        across.dot(onto),
+                        Q32_32::ZERO,
+                        v.length() * onto.length() * tolerance,
+                    )
+                    && near(
+                        along.cross(onto).length(),
+                        Q32_32::ZERO,
+                        v.length() * onto.length() * tolerance,
+                    )
+            }
+            _ => onto == PlayerVector3::ZERO,
+        },
+    );
+    let tiny = PlayerVector3::new(Q32_32::ZERO, Q32_32::from_bits(-1), Q32_32::ZERO);
+    assert_eq!(tiny.length_sqr(), Q32_32::ZERO);
+    assert_eq!(
+        tiny.normalize(),
+        Some(PlayerVector3::NEG_Y),
+        "too short to square, but it still has a direction"
+    );
+}
+
+/// Whether `bounds` are exactly the blocks of a machine of `size` at `position` facing
+/// `rotation`, placed the way its belt and pipe nodes are
+fn covers_blocks(
+    bounds: FactoryBounds,
+    position: FactoryVector3,
+    rotation: Cardinal2D,
+    size: MachineSize,
+) -> bool {
+    let MachineSize {
+        width,
+        height,
+        length,
+    } = size;
+    let [width, height, length] = [width, height, length].map(|n| i16::from(n.get()));
+    let blocks = (0..height)
+        .flat_map(|y| (0..length).flat_map(move |z| (0..width).map(move |x| (x, y, z))))
+        .map(|(x, y, z)| position.plus(rotation.rotate_block(FactoryVector3::new(x, y, z))))
+        .collect::<HashSet<_>>();
+    bounds.cells().collect::<HashSet<_>>() == blocks
+}
+
+fn position(rng: &mut Rng) -> FactoryVector3 {
+    let mut coord = || i16::try_from(i32_within(rng, 1000)).unwrap();
+    FactoryVector3::new(coord(), coord(), coord())
+}
+
+#[test]
+fn test_footprint_bounds() {
+    check(
+        |rng| {
+            let mut edge = || u8::try_from(1 + rng.below(8)).unwrap();
+            let size = MachineSize::new(edge(), edge(), edge()).unwrap();
+            (position(rng), OrientedFootprint::new(size, rotation(rng)))
+        },
+        |&(position, footprint)| {
+            let bounds = footprint.bounds_at(position);
+            covers_blocks(bounds, position, footprint.rotation, footprint.size)
+        },
+    );
+    check(
+        |rng| Reactor {
+            position: position(rng),
+            rotation: rotation(rng),
+        },
+        |reactor| {
+            covers_blocks(
+                reactor.bounds(),
+                reactor.position,
+                reactor.rotation,
+                reactor.clearance(),
+            )
+        },
+    );
+}
+
+#[test]
+#[ignore = "Bindings can't be read from text yet"]
+fn test_fuzz_bindings() {
+    fuzz(
+        "abcdefghijklmnopqrstuvwxyz_ =+-|&!()., 0123456789\n",
+        |text| {
+            _ = text.parse::<Bindings>();
+        },
+    );
+}
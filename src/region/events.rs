@@ -0,0 +1,236 @@
+//! Reacting to the player moving between regions
+//!
+//! [`GameState::update_region`] publishes each move as a pair of [`RegionEvent`]s on the
+//! [event bus](crate::event_bus), which [`RegionSystems`] hands to whichever [`RegionSystem`]s were
+//! registered for the kinds of region involved.
+
+use crate::{
+    event_bus::Reader,
+    game::GameState,
+    region::{
+        RegionId,
+        factory::grid_vis::{GridPlane, GridVisualizer},
+        rail::terrain::ChunkPos,
+    },
+    resource::{AssetId, Resources},
+};
+use raylib::prelude::*;
+use std::time::Instant;
+
+/// Something that happened when the player moved between regions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegionEvent {
+    /// The player left `region` for `to`
+    Exited { region: RegionId, to: RegionId },
+    /// The player came into `region` from `from`
+    Entered { region: RegionId, from: RegionId },
+}
+
+impl RegionEvent {
+    /// The region the event happened to
+    pub const fn region(self) -> RegionId {
+        match self {
+            Self::Exited { region, .. } | Self::Entered { region, .. } => region,
+        }
+    }
+}
+
+/// [`RegionId`] without the index, for registering systems with every region of a kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegionKind {
+    Rail,
+    Factory,
+    Lab,
+}
+
+impl RegionKind {
+    pub const ALL: [Self; 3] = [Self::Rail, Self::Factory, Self::Lab];
+}
+
+impl RegionId {
+    pub const fn kind(self) -> RegionKind {
+        match self {
+            Self::Rail => RegionKind::Rail,
+            Self::Factory(_) => RegionKind::Factory,
+            Self::Lab => RegionKind::Lab,
+        }
+    }
+}
+
+/// Everything a [`RegionSystem`] may touch
+pub struct RegionContext<'a> {
+    pub rl: &'a mut RaylibHandle,
+    pub thread: &'a RaylibThread,
+    pub resources: &'a mut Resources,
+    pub state: &'a mut GameState,
+}
+
+/// Effects tied to the player being in a region
+///
+/// Every hook does nothing by default.
+pub trait RegionSystem {
+    /// The player came into `region`
+    fn enter(&mut self, _ctx: &mut RegionContext<'_>, _region: RegionId) {}
+
+    /// The player left `region`
+    fn exit(&mut self, _ctx: &mut RegionContext<'_>, _region: RegionId) {}
+
+    /// Once per frame while the player is in `region`
+    fn tick(&mut self, _ctx: &mut RegionContext<'_>, _region: RegionId) {}
+}
+
+/// The [`RegionSystem`]s for each kind of region
+#[derive(Default)]
+pub struct RegionSystems {
+    systems: Vec<(&'static [RegionKind], Box<dyn RegionSystem>)>,
+    /// How far through the region events [`Self::dispatch`] has got
+    events: Reader<RegionEvent>,
+}
+
+impl RegionSystems {
+    /// The systems the game always has
+    pub fn new() -> Self {
+        let mut systems = Self::default();
+        systems.register(&RegionKind::ALL, RegionAssets::default());
+        systems.register(&[RegionKind::Factory], FactoryGrid);
+        systems.register(&[RegionKind::Rail], TerrainStreaming);
+        systems
+    }
+
+    /// Run `system`'s hooks for regions of the kinds in `kinds`, after any already registered
+    pub fn register(&mut self, kinds: &'static [RegionKind], system: impl RegionSystem + 'static) {
+        self.systems.push((kinds, Box::new(system)));
+    }
+
+    fn for_kind(&mut self, kind: RegionKind) -> impl Iterator<Item = &mut Box<dyn RegionSystem>> {
+        (self.systems.iter_mut())
+            .filter(move |(kinds, _)| kinds.contains(&kind))
+            .map(|(_, system)| system)
+    }
+
+    /// Run the enter hooks for `region`, like when the game starts with the player in it
+    pub fn enter(&mut self, ctx: &mut RegionContext<'_>, region: RegionId) {
+        for system in self.for_kind(region.kind()) {
+            system.enter(ctx, region);
+        }
+    }
+
+    /// Run the exit hooks for `region`
+    pub fn exit(&mut self, ctx: &mut RegionContext<'_>, region: RegionId) {
+        for system in self.for_kind(region.kind()) {
+            system.exit(ctx, region);
+        }
+    }
+
+    /// Run the tick hooks for the region the player is in
+    pub fn tick(&mut self, ctx: &mut RegionContext<'_>) {
+        let region = ctx.state.current_region;
+        for system in self.for_kind(region.kind()) {
+            system.tick(ctx, region);
+        }
+    }
+
+    /// Run the hooks for each region event published since the last dispatch
+    ///
+    /// Regions are entered before the ones left are exited, so anything both need (like assets)
+    /// is never let go of in between.
+    pub fn dispatch(&mut self, ctx: &mut RegionContext<'_>) {
+        let events = (ctx.state.events.read(&mut self.events))
+            .copied()
+            .collect::<Vec<_>>();
+        for event in &events {
+            if let RegionEvent::Entered { region, .. } = *event {
+                self.enter(ctx, region);
+            }
+        }
+        for event in &events {
+            if let RegionEvent::Exited { region, .. } = *event {
+                self.exit(ctx, region);
+            }
+        }
+    }
+}
+
+/// Keeps loaded the [`assets`](super::Region::assets) the current region draws with
+#[derive(Debug, Default)]
+struct RegionAssets {
+    /// What was acquired for each region the player is in, which is nothing if it failed to load
+    acquired: Vec<(RegionId, &'static [AssetId])>,
+}
+
+impl RegionSystem for RegionAssets {
+    fn enter(&mut self, ctx: &mut RegionContext<'_>, region: RegionId) {
+        let state = &ctx.state;
+        let assets = region
+            .to_region(&state.factories, &state.lab, &state.world)
+            .assets();
+        let acquired = match ctx.resources.acquire_all(ctx.rl, ctx.thread, assets) {
+            Ok(()) => assets,
+            Err(e) => {
+                eprintln!("failed to load assets: {e}");
+                &[]
+            }
+        };
+        self.acquired.push((region, acquired));
+    }
+
+    fn exit(&mut self, ctx: &mut RegionContext<'_>, region: RegionId) {
+        if let Some(idx) = self.acquired.iter().position(|(id, _)| *id == region) {
+            let (_, acquired) = self.acquired.swap_remove(idx);
+            ctx.resources.release_all(acquired);
+        }
+    }
+}
+
+/// Shows a [`GridVisualizer`] in the factory the player is in
+#[derive(Debug)]
+struct FactoryGrid;
+
+impl RegionSystem for FactoryGrid {
+    fn enter(&mut self, ctx: &mut RegionContext<'_>, region: RegionId) {
+        if let RegionId::Factory(idx) = region {
+            ctx.state.factories[idx].grid = Some(GridVisualizer::new(Instant::now()));
+        }
+    }
+
+    fn exit(&mut self, ctx: &mut RegionContext<'_>, region: RegionId) {
+        if let RegionId::Factory(idx) = region {
+            ctx.state.factories[idx].grid = None;
+        }
+    }
+
+    fn tick(&mut self, ctx: &mut RegionContext<'_>, region: RegionId) {
+        let RegionId::Factory(idx) = region else {
+            return;
+        };
+        let factory = &ctx.state.factories[idx];
+        let player = &ctx.state.player;
+        // Show the level the player is on, unless they're aiming at a machine to build against
+        let level = (player.position.to_factory(&factory.origin))
+            .map_or(0, |position| factory.level_at(position));
+        let plane = factory
+            .get_ray_collision(factory.vision_ray(player))
+            .filter(|lookat| lookat.target.is_some())
+            .map_or(GridPlane::Xz { y: level }, |lookat| {
+                GridPlane::from_collision(&lookat)
+            });
+        if let Some(grid) = &mut ctx.state.factories[idx].grid {
+            grid.plane = plane;
+        }
+    }
+}
+
+/// Meshes the chunks of terrain around the player while they're outside
+#[derive(Debug)]
+struct TerrainStreaming;
+
+impl RegionSystem for TerrainStreaming {
+    fn exit(&mut self, ctx: &mut RegionContext<'_>, _region: RegionId) {
+        ctx.resources.terrain.clear();
+    }
+
+    fn tick(&mut self, ctx: &mut RegionContext<'_>, _region: RegionId) {
+        let center = ChunkPos::containing(ctx.state.player.position.to_rail());
+        (ctx.resources.terrain).update(ctx.thread, &ctx.state.world.terrain, center);
+    }
+}
@@ -0,0 +1,221 @@
+//! How buildings look from outside
+//!
+//! Only the region the player is in is drawn in full. Every other building is drawn as an
+//! [`Exterior`], with less detail the further away it is (see [`Lod`]), so the world still
+//! looks whole from the rails.
+
+use crate::{
+    math::{coords::VectorConstants, frustum::Frustum},
+    player::Player,
+    region::{RegionId, factory::Factory, lab::Laboratory},
+    rl_helpers::DynRaylibDraw3D,
+};
+use raylib::prelude::*;
+
+/// How much of an [`Exterior`] is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Lod {
+    /// Walls, an overhanging roof, and a line around the outside at each floor
+    Shell,
+    /// The walls as a solid box
+    Block,
+    /// A flat rectangle facing the player, the size of the building's silhouette
+    Billboard,
+}
+
+impl Lod {
+    /// Buildings closer than this are drawn as a [`Self::Shell`]
+    const SHELL_DISTANCE: f32 = 64.0;
+    /// Buildings closer than this are drawn as a [`Self::Block`]
+    const BLOCK_DISTANCE: f32 = 256.0;
+
+    /// The detail to draw a building `distance` meters away with, or [`None`] if it's past
+    /// the far clipping plane
+    pub fn at_distance(distance: f32) -> Option<Self> {
+        if distance < Self::SHELL_DISTANCE {
+            Some(Self::Shell)
+        } else if distance < Self::BLOCK_DISTANCE {
+            Some(Self::Block)
+        } else if distance < Frustum::FAR {
+            Some(Self::Billboard)
+        } else {
+            None
+        }
+    }
+}
+
+/// A building as seen from outside, in player-relative coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exterior {
+    pub bbox: BoundingBox,
+    pub wall: Color,
+    pub roof: Color,
+    /// Heights of the floors above the ground, marked around the walls of a [`Lod::Shell`]
+    pub floors: Vec<f32>,
+}
+
+impl Exterior {
+    /// How far out the roof of a [`Lod::Shell`] reaches past the walls
+    const OVERHANG: f32 = 0.5;
+    /// How thick the roof of a [`Lod::Shell`] is
+    const ROOF_THICKNESS: f32 = 0.5;
+
+    /// The exterior of `factory`, as seen by `player`
+    pub fn of_factory(factory: &Factory, player: &Player) -> Self {
+        let bounds = factory.bounds;
+        let mut floors = (factory.floors().iter())
+            .map(|floor| f32::from(floor.level() - bounds.min.y))
+            .collect::<Vec<_>>();
+        floors.sort_by(f32::total_cmp);
+        floors.dedup();
+        Self {
+            bbox: BoundingBox {
+                min: (bounds.min).to_player_relative(&player.position, &factory.origin),
+                max: (bounds.max).to_player_relative(&player.position, &factory.origin),
+            },
+            wall: Color::LIGHTGRAY,
+            roof: Color::GREEN,
+            floors,
+        }
+    }
+
+    /// The exterior of `lab`, as seen by `player`
+    pub fn of_lab(lab: &Laboratory, player: &Player) -> Self {
+        Self {
+            bbox: BoundingBox {
+                min: (lab.bounds.min).to_player_relative(&player.position, &lab.origin),
+                max: (lab.bounds.max).to_player_relative(&player.position, &lab.origin),
+            },
+            wall: Color::BEIGE,
+            roof: Color::ORANGE,
+            floors: Vec::new(),
+        }
+    }
+
+    /// How far the nearest part of the building is from the player
+    pub fn distance(&self) -> f32 {
+        Vector3::ZERO.clamp(self.bbox.min, self.bbox.max).length()
+    }
+
+    /// The detail to draw the building with, if it's drawn at all
+    pub fn lod(&self) -> Option<Lod> {
+        Lod::at_distance(self.distance())
+    }
+
+    /// Draw the building with as much detail as it's close enough for, unless it's out of
+    /// view
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D, frustum: &Frustum) {
+        if !frustum.intersects_box(&self.bbox) {
+            return;
+        }
+        let Some(lod) = self.lod() else {
+            return;
+        };
+        let size = self.bbox.max - self.bbox.min;
+        let center = self.bbox.min + size * 0.5;
+        match lod {
+            Lod::Shell => {
+                d.draw_cube_v(center, size, self.wall);
+                d.draw_cube_wires_v(center, size, self.roof);
+                let roof = Vector3::new(
+                    size.x + 2.0 * Self::OVERHANG,
+                    Self::ROOF_THICKNESS,
+                    size.z + 2.0 * Self::OVERHANG,
+                );
+                let roof_center = Vector3 {
+                    y: self.bbox.max.y + Self::ROOF_THICKNESS * 0.5,
+                    ..center
+                };
+                d.draw_cube_v(roof_center, roof, self.roof);
+                for &height in &self.floors {
+                    let y = self.bbox.min.y + height;
+                    let (min, max) = (self.bbox.min, self.bbox.max);
+                    let corners = [
+                        Vector3::new(min.x, y, min.z),
+                        Vector3::new(max.x, y, min.z),
+                        Vector3::new(max.x, y, max.z),
+                        Vector3::new(min.x, y, max.z),
+                    ];
+                    for (i, &start) in corners.iter().enumerate() {
+                        d.draw_line3D(start, corners[(i + 1) % corners.len()], self.roof);
+                    }
+                }
+            }
+            Lod::Block => d.draw_cube_v(center, size, self.wall),
+            Lod::Billboard => {
+                let forward = Vector3::new(center.x, 0.0, center.z).normalize_or(Vector3::FORWARD);
+                let right = forward.cross(Vector3::UP);
+                // The width of the footprint across the view
+                let half_width = ((size.x * right.x).abs() + (size.z * right.z).abs()) * 0.5;
+                let (bottom, top) = (self.bbox.min.y, self.bbox.max.y);
+                let at = |side: f32, y: f32| Vector3 {
+                    y,
+                    ..center + right * (side * half_width)
+                };
+                let [bottom_left, bottom_right, top_right, top_left] = [
+                    at(-1.0, bottom),
+                    at(1.0, bottom),
+                    at(1.0, top),
+                    at(-1.0, top),
+                ];
+                // Counterclockwise from the player's side, so it isn't culled
+                d.draw_triangle3D(bottom_left, bottom_right, top_right, self.wall);
+                d.draw_triangle3D(bottom_left, top_right, top_left, self.wall);
+            }
+        }
+    }
+}
+
+/// Draw every building the player isn't in from outside, and outline the one they are in
+pub fn draw_exteriors(
+    d: &mut dyn DynRaylibDraw3D,
+    player: &Player,
+    factories: &[Factory],
+    lab: &Laboratory,
+    current_region: RegionId,
+) {
+    let frustum = player.view_frustum();
+    let buildings = (factories.iter().enumerate())
+        .map(|(idx, factory)| {
+            (
+                RegionId::Factory(idx),
+                Exterior::of_factory(factory, player),
+            )
+        })
+        .chain([(RegionId::Lab, Exterior::of_lab(lab, player))]);
+    for (region, exterior) in buildings {
+        if region == current_region {
+            d.draw_bounding_box(exterior.bbox, exterior.roof);
+        } else {
+            exterior.draw(d, &frustum);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lod() {
+        assert_eq!(Lod::at_distance(0.0), Some(Lod::Shell));
+        assert_eq!(Lod::at_distance(100.0), Some(Lod::Block));
+        assert_eq!(Lod::at_distance(500.0), Some(Lod::Billboard));
+        assert_eq!(Lod::at_distance(Frustum::FAR), None);
+
+        let exterior = Exterior {
+            bbox: BoundingBox {
+                min: Vector3::new(3.0, -1.0, -2.0),
+                max: Vector3::new(10.0, 5.0, 4.0),
+            },
+            wall: Color::WHITE,
+            roof: Color::WHITE,
+            floors: Vec::new(),
+        };
+        assert!(
+            (exterior.distance() - 3.0).abs() < 1e-6,
+            "the nearest side is 3 meters away"
+        );
+        assert_eq!(exterior.lod(), Some(Lod::Shell));
+    }
+}
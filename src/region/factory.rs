@@ -1,21 +1,83 @@
 use crate::{
+    audio::{self, AmbientSound, Emitter},
+    chem::units::{Joule, Kelvin, Liter, Pascal, Watt},
+    container::{Container, FluidTank, FluidVolume, ItemSlots, ItemStack, transfer},
+    ecs::{Components, Entities, Entity},
+    item_visual::ItemVisual,
+    lighting::{LightKind, LightSource},
+    locale::tr,
     math::{
-        bounds::{Bounds, FactoryBounds, SpacialBounds},
+        bounds::{Bounds, FactoryBounds, PlayerBounds, SpacialBounds},
         coords::{FactoryVector3, PlayerCoord, PlayerVector3, RailVector3, VectorConstants},
+        frustum::Frustum,
     },
-    ordinals::{Cardinal2D, Ordinal2D, Ordinal3D},
+    ordinals::{Cardinal2D, Cardinal3D, Ordinal2D, Ordinal3D},
+    palette::palette,
     player::Player,
-    region::factory::grid_vis::GridVisualizer,
-    resource::Resources,
-    rl_helpers::DynRaylibDraw3D,
+    region::{
+        factory::{grid_vis::GridVisualizer, machine_ui::MachineUi, spatial::SpatialIndex},
+        rail::{deposit::Deposits, network::RailNetwork},
+    },
+    resource::{AssetId, Resources},
+    rl_helpers::{DynRaylibDraw3D, DynRender},
+    stats::Statistics,
 };
 use arrayvec::ArrayVec;
+use engine::{
+    animation::{self, Animator},
+    draw,
+    draw3d::{self, DebugVis},
+};
+use fixed_point::Q32_32;
 use raylib::prelude::*;
-use std::num::NonZeroU8;
+use std::{
+    collections::{BTreeMap, HashSet},
+    num::{NonZeroU8, NonZeroUsize},
+};
 
 use super::{PlayerOverlap, Region};
+use assembler::Assembler;
+use circuit::Circuit;
+use controller::Controller;
+use decal::Decal;
+use drone::ChargingPad;
+use electrolysis::Electrolyzer;
+use gas::{GasField, Vent};
+use history::EntityRow;
+use lod::UpdateLod;
+use node_settings::{NodeSettings, NodeTable, settings_in, transfer_item};
+use plugin::PluginMachine;
+use pressure::{ReliefValve, Vessel, VesselProperties};
+use separation::{Crystallizer, Distiller};
+use sim::SharedWorld;
+use station::Station;
+use tank::Tank;
+use thermal::{Thermal, ThermalProperties};
 
+pub mod assembler;
+pub mod blueprint;
+pub mod circuit;
+pub mod controller;
+pub mod decal;
+pub mod drone;
+pub mod electrolysis;
+pub mod gas;
 pub mod grid_vis;
+pub mod history;
+pub mod lod;
+pub mod machine_ui;
+pub mod node_settings;
+pub mod plugin;
+pub mod pressure;
+pub mod recipe;
+pub mod routing;
+pub mod script;
+pub mod separation;
+pub mod sim;
+pub mod spatial;
+pub mod station;
+pub mod tank;
+pub mod thermal;
 
 /// Get collision info between ray and box
 #[inline]
@@ -117,6 +179,11 @@ impl PipeNode {
     }
 }
 
+/// The center of the block at `position`, in factory coordinates
+pub fn block_center(position: FactoryVector3) -> Vector3 {
+    position.as_vec3() + Vector3::splat(0.5)
+}
+
 /// Each level doubles speed
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(u8)]
@@ -131,26 +198,152 @@ pub enum BeltLevel {
     Mk8 = 1 << 7,
 }
 
+impl BeltLevel {
+    /// Every level, slowest first
+    pub const ALL: [Self; 8] = [
+        Self::Mk1,
+        Self::Mk2,
+        Self::Mk3,
+        Self::Mk4,
+        Self::Mk5,
+        Self::Mk6,
+        Self::Mk7,
+        Self::Mk8,
+    ];
+
+    /// The number after "Mk"
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "a u8 has at most 8 trailing zeros"
+    )]
+    pub const fn number(self) -> u8 {
+        (self as u8).trailing_zeros() as u8 + 1
+    }
+
+    /// The level with `number` after "Mk", if there is one
+    #[must_use]
+    pub const fn from_number(number: u8) -> Option<Self> {
+        match number.checked_sub(1) {
+            Some(idx) if (idx as usize) < Self::ALL.len() => Some(Self::ALL[idx as usize]),
+            _ => None,
+        }
+    }
+}
+
 /// Belts are 1 meter wide, minimum 1 meter long, and have 1 meter vertical clearance.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Belt {
     /// Each level doubles speed
     pub level: BeltLevel,
     pub src: BeltOutputNode,
     pub dst: BeltInputNode,
+    /// The blocks the belt goes through from `src` to `dst`, see [`routing::find_route`]
+    pub path: Vec<FactoryVector3>,
 }
 
 impl Belt {
+    /// Items each meter of belt holds
+    const ITEMS_PER_METER: u32 = 4;
+
     /// Cubic meters per sec
     pub const fn speed(&self) -> usize {
         self.level as usize
     }
+
+    /// How many items fit on the belt at once
+    pub fn capacity(&self) -> u32 {
+        u32::try_from(self.path.len())
+            .unwrap_or(u32::MAX)
+            .saturating_mul(Self::ITEMS_PER_METER)
+    }
+
+    /// Where each of `count` items on the belt is drawn, in factory coordinates, when belts are
+    /// `progress` of the way to moving again (see [`Factory::tick`])
+    ///
+    /// Items are packed towards `dst`, first in first, and creep forward until the next move.
+    pub fn item_positions(&self, count: u32, progress: f32) -> Vec<Vector3> {
+        #[allow(clippy::cast_precision_loss, reason = "items per meter is small")]
+        const SPACING: f32 = 1.0 / Belt::ITEMS_PER_METER as f32;
+        let points = self
+            .path
+            .iter()
+            .copied()
+            .map(block_center)
+            .collect::<Vec<_>>();
+        (0..count.min(self.capacity()))
+            .map(|i| {
+                #[allow(
+                    clippy::cast_precision_loss,
+                    reason = "belts don't hold that many items"
+                )]
+                let behind = (i as f32 + 1.0 - progress.clamp(0.0, 1.0)) * SPACING;
+                along_from_end(&points, behind)
+            })
+            .collect()
+    }
 }
 
-#[derive(Debug)]
+/// The point `distance` back along the line strip through `points` from its end, stopping at
+/// its start
+fn along_from_end(points: &[Vector3], distance: f32) -> Vector3 {
+    let mut remaining = distance;
+    for pair in points.windows(2).rev() {
+        let (start, end) = (pair[0], pair[1]);
+        let length = start.distance(end);
+        if remaining <= length {
+            return end + (start - end).normalize_or_zero() * remaining;
+        }
+        remaining -= length;
+    }
+    points.first().copied().unwrap_or_default()
+}
+
+/// Draws the path items take, in factory coordinates
+impl DebugVis for Belt {
+    fn draw(&self, d: &mut draw3d::Renderer<'_>) -> draw::Result {
+        let path = self
+            .path
+            .iter()
+            .copied()
+            .map(block_center)
+            .collect::<Vec<_>>();
+        d.draw_line_strip(&path, None, Color::YELLOW)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Pipe {
     pub a: PipeNode,
     pub b: PipeNode,
+    /// The blocks the pipe goes through from `a` to `b`, see [`routing::find_route`]
+    pub path: Vec<FactoryVector3>,
+}
+
+impl Pipe {
+    /// Liters each meter of pipe holds
+    const LITERS_PER_METER: i32 = 100;
+    /// Liters per second that flow from `a` to `b`
+    const FLOW: f32 = 50.0;
+
+    /// How many liters fit in the pipe at once
+    pub fn capacity(&self) -> Q32_32 {
+        let meters = i32::try_from(self.path.len()).unwrap_or(i32::MAX);
+        Q32_32::from_i32(meters.saturating_mul(Self::LITERS_PER_METER))
+    }
+}
+
+/// Draws the path fluids take, in factory coordinates
+impl DebugVis for Pipe {
+    fn draw(&self, d: &mut draw3d::Renderer<'_>) -> draw::Result {
+        let path = self
+            .path
+            .iter()
+            .copied()
+            .map(block_center)
+            .collect::<Vec<_>>();
+        d.draw_line_strip(&path, None, Color::BLUE)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -161,10 +354,29 @@ pub struct MachineSize {
 }
 
 impl MachineSize {
+    /// A size of `width`, `height`, and `length` meters, or [`None`] if any of them is zero
+    #[inline]
+    #[must_use]
+    pub const fn new(width: u8, height: u8, length: u8) -> Option<Self> {
+        match (
+            NonZeroU8::new(width),
+            NonZeroU8::new(height),
+            NonZeroU8::new(length),
+        ) {
+            (Some(width), Some(height), Some(length)) => Some(Self {
+                width,
+                height,
+                length,
+            }),
+            _ => None,
+        }
+    }
+
     /// # Safety
     ///
     /// All parameters must be non-zero
     #[inline]
+    #[must_use]
     pub const unsafe fn new_unchecked(width: u8, height: u8, length: u8) -> Self {
         Self {
             // SAFETY: Caller must uphold safety contract
@@ -185,7 +397,130 @@ pub trait Clearance {
     fn clearance(&self) -> MachineSize;
 }
 
-pub trait Machine: Clearance + Bounds<FactoryVector3, BoundingBox = FactoryBounds> {
+/// What a machine is currently doing
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MachineStatus {
+    /// Nothing to process
+    #[default]
+    Idle,
+    /// Processing a recipe, `progress` is in `[0, 1]`
+    Working { progress: f32 },
+    /// Finished processing, but the output has nowhere to go
+    Blocked,
+}
+
+impl MachineStatus {
+    /// How busy the machine is, from 0 to 1, e.g. for how loud it is or how fast its moving parts
+    /// go
+    #[must_use]
+    pub const fn activity(self) -> f32 {
+        match self {
+            Self::Idle => 0.2,
+            Self::Working { .. } => 1.0,
+            Self::Blocked => 0.1,
+        }
+    }
+}
+
+impl std::fmt::Display for MachineStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Idle => f.write_str(&tr!("machine.idle")),
+            Self::Working { progress } => {
+                let percent = format!("{:.0}", progress * 100.0);
+                f.write_str(&tr!("machine.working", percent = percent))
+            }
+            Self::Blocked => f.write_str(&tr!("machine.blocked")),
+        }
+    }
+}
+
+pub trait Machine: Clearance + Bounds<FactoryVector3, BoundingBox = FactoryBounds> + Send {
+    /// The name shown to the player, in their language
+    #[must_use]
+    fn name(&self) -> String;
+
+    #[inline]
+    #[must_use]
+    fn status(&self) -> MachineStatus {
+        MachineStatus::Idle
+    }
+
+    /// Advance the machine by `dt` seconds
+    #[inline]
+    fn tick(&mut self, _dt: f32, _ctx: &mut TickContext<'_>) {}
+
+    /// Whether the machine needs [`TickContext::world`] when it's ticked
+    ///
+    /// Machines that don't are ticked at the same time as each other on several threads, and
+    /// the ones that do are ticked one at a time afterwards, see [`sim`].
+    #[inline]
+    #[must_use]
+    fn shares_world(&self) -> bool {
+        false
+    }
+
+    /// How the machine is drawn, if it has a model
+    ///
+    /// Machines without one are only drawn with [`DebugVis`].
+    #[inline]
+    #[must_use]
+    fn model(&self) -> Option<MachineModel> {
+        None
+    }
+
+    /// The sound the machine makes while it's running, if any
+    #[inline]
+    #[must_use]
+    fn sound(&self) -> Option<AmbientSound> {
+        None
+    }
+
+    /// How fast the machine works relative to normal when there's `daylight` outside, from 0 at
+    /// night to 1 in the day
+    ///
+    /// Most machines don't care, but solar ones slow down at night, see
+    /// [`solar_efficiency`](crate::time::solar_efficiency).
+    #[inline]
+    #[must_use]
+    fn efficiency(&self, _daylight: f32) -> f32 {
+        1.0
+    }
+
+    /// How the machine heats up, if it does
+    ///
+    /// Machines that do get a [`Thermal`] when they're built, and shut down while it's
+    /// overheated.
+    #[inline]
+    #[must_use]
+    fn thermal_properties(&self) -> Option<ThermalProperties> {
+        None
+    }
+
+    /// How the machine holds gas in, if it's sealed
+    ///
+    /// Machines that are get a [`Vessel`] when they're built, and stop for good once it's
+    /// ruptured.
+    #[inline]
+    #[must_use]
+    fn vessel_properties(&self) -> Option<VesselProperties> {
+        None
+    }
+
+    /// Joules the machine releases per second doing what it's doing, negative if it takes heat in
+    #[inline]
+    #[must_use]
+    fn heat(&self) -> Joule {
+        Joule::ZERO
+    }
+
+    /// Electricity the machine draws doing what it's doing
+    #[inline]
+    #[must_use]
+    fn power(&self) -> Watt {
+        Watt::ZERO
+    }
+
     #[inline]
     #[must_use]
     fn belt_inputs(&self) -> ArrayVec<BeltInputNode, 8> {
@@ -203,11 +538,167 @@ pub trait Machine: Clearance + Bounds<FactoryVector3, BoundingBox = FactoryBound
     fn pipe_nodes(&self) -> ArrayVec<PipeNode, 8> {
         ArrayVec::new()
     }
+
+    /// The tank pipes leaving `node` draw from, if it isn't [`MachineBuffers::fluid`]
+    ///
+    /// Machines that give off more than one fluid keep the rest in tanks of their own, each
+    /// piped out of a node of its own.
+    #[inline]
+    #[must_use]
+    fn outlet_mut(&mut self, _node: PipeNode) -> Option<&mut FluidTank> {
+        None
+    }
+
+    /// Which belt the next item moves through on `side`, where `open[i]` is whether the belt at
+    /// the `i`th node on that side can move one
+    ///
+    /// Items move one at a time, so this decides how they're shared between belts. By default
+    /// each belt is filled before the next.
+    #[inline]
+    #[must_use]
+    fn next_belt(&self, _side: BeltSide, open: &[bool]) -> Option<usize> {
+        open.iter().position(|&open| open)
+    }
+
+    /// An item moved through the belt at the `idx`th node on `side`, see [`Self::next_belt`]
+    #[inline]
+    fn moved_item(&mut self, _side: BeltSide, _idx: usize) {}
+
+    /// Empty buffers with room for what the machine works with
+    #[must_use]
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(4, 100),
+            output: ItemSlots::new(4, 100),
+            fluid: FluidTank::new(Q32_32::from_i32(1000)),
+        }
+    }
+}
+
+/// Which of a machine's belt nodes: the ones items come in through, or go out of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BeltSide {
+    Input,
+    Output,
+}
+
+/// A side of a machine, as seen from behind it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Front,
+    Back,
+    Left,
+    Right,
+}
+
+impl Side {
+    /// How far the side is turned from the front
+    pub const fn turn(self) -> Cardinal2D {
+        match self {
+            Self::Front => Cardinal2D::East,
+            Self::Left => Cardinal2D::North,
+            Self::Back => Cardinal2D::West,
+            Self::Right => Cardinal2D::South,
+        }
+    }
+
+    /// One block toward the side, when the machine faces east
+    pub const fn offset(self) -> FactoryVector3 {
+        self.turn().rotate_vector(FactoryVector3::new(0, 0, 1))
+    }
+}
+
+impl std::str::FromStr for Side {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "front" => Ok(Self::Front),
+            "back" => Ok(Self::Back),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What a machine can touch while it ticks, besides itself
+#[derive(Debug)]
+pub struct TickContext<'a> {
+    /// The machine's own buffers
+    pub buffers: &'a mut MachineBuffers,
+    /// How hot the machine is, which is [`thermal::AMBIENT`] for ones that don't heat up
+    pub temperature: Kelvin,
+    /// Where the machine reports what it made and used up each cycle
+    pub stats: &'a mut Statistics,
+    /// What's outside the factory, only for machines that [share it](Machine::shares_world)
+    pub world: Option<SharedWorld<'a>>,
+}
+
+/// The model a machine is drawn with, and where
+///
+/// Machines with the same model and material are drawn with a single instanced draw call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MachineModel {
+    pub model: AssetId,
+    /// Must support instancing
+    pub material: AssetId,
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    /// [`Asset::Animations`](crate::resource::Asset::Animations) for the model's bones, if it
+    /// has moving parts
+    ///
+    /// Posed machines can't be instanced, so they're drawn one at a time with the model's own
+    /// material instead of [`Self::material`].
+    pub animations: Option<AssetId>,
+}
+
+/// What a machine is holding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MachineBuffers {
+    /// Items waiting to be used, brought by belts
+    pub input: ItemSlots,
+    /// Items waiting to be taken away by belts
+    pub output: ItemSlots,
+    /// Fluid going in and out through pipes
+    pub fluid: FluidTank,
+}
+
+/// Draws the bounds and connection points, in factory coordinates
+impl DebugVis for dyn Machine + '_ {
+    fn draw(&self, d: &mut draw3d::Renderer<'_>) -> draw::Result {
+        let bounds = self.bounds();
+        d.draw_bounding_box(
+            BoundingBox {
+                min: bounds.min.as_vec3(),
+                max: bounds.max.as_vec3(),
+            },
+            Color::MAGENTA,
+        )?;
+        let nodes = (self
+            .belt_inputs()
+            .into_iter()
+            .map(|node| (node.0.position, Color::ORANGE)))
+        .chain(
+            self.belt_outputs()
+                .into_iter()
+                .map(|node| (node.0.position, Color::GREEN)),
+        )
+        .chain(
+            self.pipe_nodes()
+                .into_iter()
+                .map(|node| (node.position, Color::BLUE)),
+        );
+        for (position, color) in nodes {
+            let center = block_center(position);
+            d.draw_line(center, center + Vector3::UP, None, color)?;
+        }
+        Ok(())
+    }
 }
 
 pub trait DrawMachine: Machine {
     /// Render the machine
-    // TODO: batch draws of same machine type
     fn draw(
         &self,
         d: &mut dyn DynRaylibDraw3D,
@@ -218,7 +709,7 @@ pub trait DrawMachine: Machine {
 }
 
 /// Reacts two solutions
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Reactor {
     pub position: FactoryVector3,
     pub rotation: Cardinal2D,
@@ -232,55 +723,117 @@ impl const Clearance for Reactor {
     }
 }
 
+/// The space a machine of `size` takes up when it faces `rotation`, relative to its position
+///
+/// The machine's position is the corner it's turned about, so it's one corner of the result, and
+/// facing north or south swaps its width and length. Every block the machine covers when facing
+/// east is covered by its [`Cardinal2D::rotate_block`] here.
+pub fn rotate_footprint(size: MachineSize, rotation: Cardinal2D) -> FactoryBounds {
+    let MachineSize {
+        width,
+        height,
+        length,
+    } = size;
+    let size = FactoryVector3::new(width.get().into(), height.get().into(), length.get().into());
+    let corner = rotation.rotate_vector(size);
+    FactoryBounds {
+        min: FactoryVector3::ZERO.min(corner),
+        max: FactoryVector3::ZERO.max(corner),
+    }
+}
+
+/// The size of a machine and which way it faces, which is all it takes to know what space it
+/// takes up wherever it's put
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OrientedFootprint {
+    pub size: MachineSize,
+    pub rotation: Cardinal2D,
+}
+
+impl OrientedFootprint {
+    #[inline]
+    pub const fn new(size: MachineSize, rotation: Cardinal2D) -> Self {
+        Self { size, rotation }
+    }
+
+    /// The space taken up with the machine's position at `position`, see [`rotate_footprint`]
+    pub fn bounds_at(self, position: FactoryVector3) -> FactoryBounds {
+        let FactoryBounds { min, max } = rotate_footprint(self.size, self.rotation);
+        FactoryBounds {
+            min: position + min,
+            max: position + max,
+        }
+    }
+}
+
 impl Bounds<FactoryVector3> for Reactor {
     type BoundingBox = FactoryBounds;
 
     fn bounds(&self) -> Self::BoundingBox {
-        let FactoryVector3 { x, y, z } = self.position;
-        let MachineSize {
-            width,
-            height,
-            length,
-        } = self.clearance();
-        let width: i16 = width.get().into();
-        let height: i16 = height.get().into();
-        let length: i16 = length.get().into();
-        let (cos, sin, _) = self.rotation.cos_sin_tan();
-        #[allow(
-            clippy::cast_possible_truncation,
-            reason = "cos and sin of Cardinal2D are guaranteed to be -1, 0, or 1"
-        )]
-        let (cos, sin) = (cos as i16, sin as i16);
-        let width = cos * width + sin * length;
-        let length = sin * width + cos * length;
-        let (mut xs, mut zs) = ([x, x + width], [z, z + length]);
-        for a in [&mut xs, &mut zs] {
-            if !a.is_sorted() {
-                a.reverse();
-            }
-        }
-        let ([xmin, xmax], [zmin, zmax]) = (xs, zs);
-        // height is never negative (at the time of writing)
-        FactoryBounds {
-            min: FactoryVector3 {
-                x: xmin,
-                y,
-                z: zmin,
-            },
-            max: FactoryVector3 {
-                x: xmax,
-                y: y + height,
-                z: zmax,
-            },
-        }
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl Reactor {
+    /// Steel shell of a few tonnes, with a cooling jacket that only helps once it's piped water
+    pub const THERMAL: ThermalProperties = ThermalProperties {
+        heat_capacity: 2.0e6,
+        dissipation: 500.0,
+        max_temperature: Kelvin::new(600.0).unwrap(),
+    };
+
+    /// Room for gas above the solutions, rated for about 15 atmospheres
+    pub const VESSEL: VesselProperties = VesselProperties {
+        headspace: Liter::new(4000.0).unwrap(),
+        rating: Pascal::new(1.5e6).unwrap(),
+    };
+
+    /// The block at `offset` from the reactor's position when it faces east, rotated with the
+    /// reactor
+    const fn block(&self, offset: FactoryVector3) -> FactoryVector3 {
+        self.position.plus(self.rotation.rotate_block(offset))
     }
 }
 
 impl Machine for Reactor {
+    fn name(&self) -> String {
+        MachineKind::Reactor.name()
+    }
+
+    fn model(&self) -> Option<MachineModel> {
+        Some(MachineModel {
+            model: AssetId::Reactor,
+            material: AssetId::ReactorInstanced,
+            position: self.position,
+            rotation: self.rotation,
+            animations: Some(AssetId::ReactorAnimations),
+        })
+    }
+
+    fn sound(&self) -> Option<AmbientSound> {
+        Some(AmbientSound::ReactorHum)
+    }
+
+    fn thermal_properties(&self) -> Option<ThermalProperties> {
+        Some(Self::THERMAL)
+    }
+
+    fn vessel_properties(&self) -> Option<VesselProperties> {
+        Some(Self::VESSEL)
+    }
+
+    /// Whatever the recipe it's running releases
+    fn heat(&self) -> Joule {
+        match (self.status(), self.recipe()) {
+            (MachineStatus::Working { .. }, Some(recipe)) => recipe.heat_rate(),
+            _ => Joule::ZERO,
+        }
+    }
+
     fn belt_inputs(&self) -> ArrayVec<BeltInputNode, 8> {
         let mut arr = ArrayVec::new();
         arr.push(BeltInputNode(BeltNode {
-            position: self.position + FactoryVector3 { x: 0, y: 0, z: 0 },
+            position: self.block(FactoryVector3 { x: 0, y: 0, z: 0 }),
             rotation: self.rotation.as_ordinal(),
         }));
         arr
@@ -290,12 +843,11 @@ impl Machine for Reactor {
         let mut arr = ArrayVec::new();
         let MachineSize { length, .. } = self.clearance();
         arr.push(BeltOutputNode(BeltNode {
-            position: self.position
-                + FactoryVector3 {
-                    x: 0,
-                    y: 0,
-                    z: length.get().into(),
-                },
+            position: self.block(FactoryVector3 {
+                x: 0,
+                y: 0,
+                z: length.get().into(),
+            }),
             rotation: self.rotation.as_ordinal(),
         }));
         arr
@@ -305,21 +857,19 @@ impl Machine for Reactor {
         let mut arr = ArrayVec::new();
         let MachineSize { width, length, .. } = self.clearance();
         arr.push(PipeNode {
-            position: self.position
-                + FactoryVector3 {
-                    x: width.get().into(),
-                    y: 0,
-                    z: 0,
-                },
+            position: self.block(FactoryVector3 {
+                x: width.get().into(),
+                y: 0,
+                z: 0,
+            }),
             rotation: self.rotation.as_ordinal().as_3d(),
         });
         arr.push(PipeNode {
-            position: self.position
-                + FactoryVector3 {
-                    x: width.get().into(),
-                    y: 0,
-                    z: length.get().into(),
-                },
+            position: self.block(FactoryVector3 {
+                x: width.get().into(),
+                y: 0,
+                z: length.get().into(),
+            }),
             rotation: self.rotation.as_ordinal().as_3d(),
         });
         arr
@@ -346,138 +896,2058 @@ impl DrawMachine for Reactor {
     }
 }
 
-pub const fn machine_matrix(
-    player_pos: &PlayerVector3,
-    position: FactoryVector3,
-    origin: &RailVector3,
-    rotation: Cardinal2D,
-) -> Matrix {
-    let Vector3 { x, y, z } = position.to_player_relative(player_pos, origin);
-    let (cos, sin, _) = rotation.cos_sin_tan();
-    #[rustfmt::skip]
-    Matrix {
-        m0:  cos, m4: 0.0, m8:  sin, m12:   x,
-        m1:  0.0, m5: 1.0, m9:  0.0, m13:   y,
-        m2: -sin, m6: 0.0, m10: cos, m14:   z,
-        m3:  0.0, m7: 0.0, m11: 0.0, m15: 1.0,
-    }
+/// Digs ore out of the [`Deposit`](crate::region::rail::deposit::Deposit) it's built over and
+/// puts it on a belt
+#[derive(Debug, Clone, PartialEq)]
+pub struct Miner {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    /// Where the deposit being mined is, see [`Deposits`]
+    pub deposit: RailVector3,
+    /// Toward the next unit of ore, in `[0, 1]`
+    progress: f32,
+    status: MachineStatus,
 }
 
-/// Note: vectors are in Factory coordinates
-pub struct FactoryCollision<'a> {
-    pub target: Option<&'a dyn Machine>,
-    pub distance: f32,
-    pub normal: Vector3,
-    pub point: Vector3,
+impl const Clearance for Miner {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        Self::SIZE
+    }
 }
 
-#[derive(Debug)]
-pub struct Factory {
-    pub origin: RailVector3,
-    pub bounds: FactoryBounds,
-    pub reactors: Vec<Reactor>,
-}
+impl Bounds<FactoryVector3> for Miner {
+    type BoundingBox = FactoryBounds;
 
-impl Factory {
-    /// Cast a ray and see what it hits
-    pub fn get_ray_collision(&self, ray: Ray) -> Option<FactoryCollision<'_>> {
-        std::iter::once_with(|| {
-            let RayCollision {
-                hit,
-                distance,
-                point,
-                normal,
-            } = get_ray_collision_plane(ray, Vector3::ZERO, Vector3::UP);
-
-            if hit {
-                Some(FactoryCollision {
-                    target: None,
-                    distance,
-                    normal,
-                    point,
-                })
-            } else {
-                None
-            }
-        })
-        .chain(self.reactors.iter().map(|reactor| {
-            let bbox = reactor.bounds();
-            let bbox = BoundingBox {
-                min: Vector3 {
-                    x: bbox.min.x.into(),
-                    y: bbox.min.y.into(),
-                    z: bbox.min.z.into(),
-                },
-                max: Vector3 {
-                    x: bbox.max.x.into(),
-                    y: bbox.max.y.into(),
-                    z: bbox.max.z.into(),
-                },
-            };
-            let RayCollision {
-                hit,
-                distance,
-                point,
-                normal,
-            } = get_ray_collision_box(ray, bbox);
-
-            hit.then_some(FactoryCollision {
-                target: Some(reactor),
-                distance,
-                normal,
-                point,
-            })
-        }))
-        .flatten()
-        .min_by_key(|collision| PlayerCoord::from_f32(collision.distance))
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
     }
+}
 
-    fn draw_machines(
-        &self,
-        d: &mut dyn DynRaylibDraw3D,
+impl Miner {
+    // SAFETY: 2 is not zero
+    pub const SIZE: MachineSize = unsafe { MachineSize::new_unchecked(2, 2, 2) };
+
+    /// Units of ore mined per second from a deposit of average richness
+    pub const RATE: f32 = 0.5;
+
+    pub const fn new(position: FactoryVector3, rotation: Cardinal2D, deposit: RailVector3) -> Self {
+        Self {
+            position,
+            rotation,
+            deposit,
+            progress: 0.0,
+            status: MachineStatus::Idle,
+        }
+    }
+
+    /// The space a miner at `position` facing `rotation` would take up, for finding the deposit
+    /// under it before it's built
+    pub fn footprint(position: FactoryVector3, rotation: Cardinal2D) -> FactoryBounds {
+        OrientedFootprint::new(Self::SIZE, rotation).bounds_at(position)
+    }
+
+    /// The block at `offset` from the miner's position when it faces east, rotated with the
+    /// miner
+    const fn block(&self, offset: FactoryVector3) -> FactoryVector3 {
+        self.position.plus(self.rotation.rotate_block(offset))
+    }
+}
+
+impl Machine for Miner {
+    fn name(&self) -> String {
+        MachineKind::Miner.name()
+    }
+
+    /// Digs from the deposits outside
+    fn shares_world(&self) -> bool {
+        true
+    }
+
+    fn status(&self) -> MachineStatus {
+        self.status
+    }
+
+    /// Mines faster from richer deposits, and stops when the deposit runs out or the output is
+    /// full
+    fn tick(&mut self, dt: f32, ctx: &mut TickContext<'_>) {
+        let deposits = ctx.world.as_mut().map(|world| &mut *world.deposits);
+        let Some(deposit) = (deposits.and_then(|deposits| deposits.get_mut(self.deposit)))
+            .filter(|d| d.remaining > 0)
+        else {
+            self.progress = 0.0;
+            self.status = MachineStatus::Idle;
+            return;
+        };
+        self.progress += dt * Self::RATE * deposit.richness;
+        while self.progress >= 1.0 {
+            let Some(ore) = deposit.extract(1) else {
+                self.progress = 0.0;
+                self.status = MachineStatus::Idle;
+                return;
+            };
+            let item = ore.item.clone();
+            if let Some(rest) = ctx.buffers.output.insert(ore) {
+                // Leave it in the ground until there's room
+                deposit.remaining += rest.count;
+                self.progress = 1.0;
+                self.status = MachineStatus::Blocked;
+                return;
+            }
+            ctx.stats.produced(&item, 1);
+            self.progress -= 1.0;
+        }
+        self.status = MachineStatus::Working {
+            progress: self.progress,
+        };
+    }
+
+    fn belt_outputs(&self) -> ArrayVec<BeltOutputNode, 8> {
+        let mut arr = ArrayVec::new();
+        let MachineSize { length, .. } = self.clearance();
+        arr.push(BeltOutputNode(BeltNode {
+            position: self.block(FactoryVector3 {
+                x: 0,
+                y: 0,
+                z: length.get().into(),
+            }),
+            rotation: self.rotation.as_ordinal(),
+        }));
+        arr
+    }
+
+    /// Nothing goes in, and ore comes out
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(0, 0),
+            output: ItemSlots::new(1, 100),
+            fluid: FluidTank::new(Q32_32::from_i32(0)),
+        }
+    }
+}
+
+/// How many items a [`Splitter`] or [`Merger`] can hold, as many as the fastest belt moves at
+/// once
+const PASS_THROUGH: u32 = BeltLevel::Mk8 as u32;
+
+/// The first of the `open` nodes, starting from `next` and wrapping around
+fn round_robin(next: usize, open: &[bool]) -> Option<usize> {
+    (0..open.len())
+        .map(|i| (next + i) % open.len())
+        .find(|&i| open[i])
+}
+
+/// How a [`Splitter`] shares items between its outputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SplitMode {
+    /// Each item goes out of the next output that can take it, in turn
+    #[default]
+    RoundRobin,
+    /// Items are shared in proportion to each output's weight, in the same order every time
+    ///
+    /// Outputs with no weight get nothing.
+    Ratio([u8; Splitter::OUTPUTS]),
+}
+
+/// Shares the items from one belt between up to three, one block across
+///
+/// Outputs are ahead, then to the left, then to the right.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Splitter {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    pub mode: SplitMode,
+    /// The output after the last one used, for [`SplitMode::RoundRobin`]
+    next: usize,
+    /// Items sent out of each output since they all last got their share, for
+    /// [`SplitMode::Ratio`]
+    sent: [u32; Splitter::OUTPUTS],
+}
+
+impl const Clearance for Splitter {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        // SAFETY: 1 is not zero
+        unsafe { MachineSize::new_unchecked(1, 1, 1) }
+    }
+}
+
+impl Bounds<FactoryVector3> for Splitter {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl Splitter {
+    pub const OUTPUTS: usize = 3;
+
+    pub const fn new(position: FactoryVector3, rotation: Cardinal2D, mode: SplitMode) -> Self {
+        Self {
+            position,
+            rotation,
+            mode,
+            next: 0,
+            sent: [0; Self::OUTPUTS],
+        }
+    }
+
+    /// The block at `offset` from the splitter's position when it faces east, rotated with the
+    /// splitter
+    const fn block(&self, offset: FactoryVector3) -> FactoryVector3 {
+        self.position.plus(self.rotation.rotate_block(offset))
+    }
+
+    /// Which of the `open` outputs gets the next item
+    fn next_output(&self, open: &[bool]) -> Option<usize> {
+        match self.mode {
+            SplitMode::RoundRobin => round_robin(self.next, open),
+            // The one furthest behind its share, or the first of those
+            SplitMode::Ratio(weights) => (0..Self::OUTPUTS)
+                .filter(|&i| weights[i] > 0 && open.get(i) == Some(&true))
+                .min_by(|&a, &b| {
+                    let behind =
+                        |i: usize, j: usize| u64::from(self.sent[i] + 1) * u64::from(weights[j]);
+                    behind(a, b).cmp(&behind(b, a))
+                }),
+        }
+    }
+}
+
+impl Machine for Splitter {
+    fn name(&self) -> String {
+        MachineKind::Splitter.name()
+    }
+
+    /// Passes everything that comes in straight through
+    fn tick(&mut self, _dt: f32, ctx: &mut TickContext<'_>) {
+        let MachineBuffers { input, output, .. } = ctx.buffers;
+        transfer(input, output, u32::MAX);
+    }
+
+    fn belt_inputs(&self) -> ArrayVec<BeltInputNode, 8> {
+        let mut arr = ArrayVec::new();
+        arr.push(BeltInputNode(BeltNode {
+            position: self.block(FactoryVector3 { x: 0, y: 0, z: 0 }),
+            rotation: self.rotation.as_ordinal(),
+        }));
+        arr
+    }
+
+    fn belt_outputs(&self) -> ArrayVec<BeltOutputNode, 8> {
+        [
+            (FactoryVector3 { x: 0, y: 0, z: 1 }, Cardinal2D::East),
+            (FactoryVector3 { x: 1, y: 0, z: 0 }, Cardinal2D::North),
+            (FactoryVector3 { x: -1, y: 0, z: 0 }, Cardinal2D::South),
+        ]
+        .into_iter()
+        .map(|(offset, turn)| {
+            BeltOutputNode(BeltNode {
+                position: self.block(offset),
+                rotation: self.rotation.plus(turn).as_ordinal(),
+            })
+        })
+        .collect()
+    }
+
+    fn next_belt(&self, side: BeltSide, open: &[bool]) -> Option<usize> {
+        match side {
+            BeltSide::Input => open.iter().position(|&open| open),
+            BeltSide::Output => self.next_output(open),
+        }
+    }
+
+    fn moved_item(&mut self, side: BeltSide, idx: usize) {
+        if side == BeltSide::Input {
+            return;
+        }
+        self.next = (idx + 1) % Self::OUTPUTS;
+        if let SplitMode::Ratio(weights) = self.mode {
+            self.sent[idx] += 1;
+            if (self.sent.iter().zip(weights)).all(|(&sent, weight)| sent >= u32::from(weight)) {
+                for (sent, weight) in self.sent.iter_mut().zip(weights) {
+                    *sent -= u32::from(weight);
+                }
+            }
+        }
+    }
+
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(1, PASS_THROUGH),
+            output: ItemSlots::new(1, PASS_THROUGH),
+            fluid: FluidTank::new(Q32_32::from_i32(0)),
+        }
+    }
+}
+
+/// Joins the items from up to three belts onto one, taking from each in turn, one block across
+///
+/// Inputs are from behind, then from the left, then from the right.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merger {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    /// The input after the last one used
+    next: usize,
+}
+
+impl const Clearance for Merger {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        // SAFETY: 1 is not zero
+        unsafe { MachineSize::new_unchecked(1, 1, 1) }
+    }
+}
+
+impl Bounds<FactoryVector3> for Merger {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl Merger {
+    pub const INPUTS: usize = 3;
+
+    pub const fn new(position: FactoryVector3, rotation: Cardinal2D) -> Self {
+        Self {
+            position,
+            rotation,
+            next: 0,
+        }
+    }
+
+    /// The block at `offset` from the merger's position when it faces east, rotated with the
+    /// merger
+    const fn block(&self, offset: FactoryVector3) -> FactoryVector3 {
+        self.position.plus(self.rotation.rotate_block(offset))
+    }
+}
+
+impl Machine for Merger {
+    fn name(&self) -> String {
+        MachineKind::Merger.name()
+    }
+
+    /// Passes everything that comes in straight through
+    fn tick(&mut self, _dt: f32, ctx: &mut TickContext<'_>) {
+        let MachineBuffers { input, output, .. } = ctx.buffers;
+        while transfer(input, output, u32::MAX) {}
+    }
+
+    fn belt_inputs(&self) -> ArrayVec<BeltInputNode, 8> {
+        [Cardinal2D::East, Cardinal2D::South, Cardinal2D::North]
+            .into_iter()
+            .map(|turn| {
+                BeltInputNode(BeltNode {
+                    position: self.block(FactoryVector3 { x: 0, y: 0, z: 0 }),
+                    rotation: self.rotation.plus(turn).as_ordinal(),
+                })
+            })
+            .collect()
+    }
+
+    fn belt_outputs(&self) -> ArrayVec<BeltOutputNode, 8> {
+        let mut arr = ArrayVec::new();
+        arr.push(BeltOutputNode(BeltNode {
+            position: self.block(FactoryVector3 { x: 0, y: 0, z: 1 }),
+            rotation: self.rotation.as_ordinal(),
+        }));
+        arr
+    }
+
+    fn next_belt(&self, side: BeltSide, open: &[bool]) -> Option<usize> {
+        match side {
+            BeltSide::Input => round_robin(self.next, open),
+            BeltSide::Output => open.iter().position(|&open| open),
+        }
+    }
+
+    fn moved_item(&mut self, side: BeltSide, idx: usize) {
+        if side == BeltSide::Input {
+            self.next = (idx + 1) % Self::INPUTS;
+        }
+    }
+
+    /// Room for a different item from each input
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(Self::INPUTS, PASS_THROUGH),
+            output: ItemSlots::new(Self::INPUTS, PASS_THROUGH),
+            fluid: FluidTank::new(Q32_32::from_i32(0)),
+        }
+    }
+}
+
+/// A column of space the player can climb, one meter across
+///
+/// Ladders don't block anything, so they aren't machines and aren't in the spatial index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ladder {
+    /// The bottom of the ladder
+    pub position: FactoryVector3,
+    /// Meters
+    pub height: NonZeroU8,
+    /// The side the ladder is climbed from
+    pub facing: Cardinal2D,
+}
+
+impl Bounds<FactoryVector3> for Ladder {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        FactoryBounds {
+            min: self.position,
+            max: self.position
+                + FactoryVector3 {
+                    x: 1,
+                    y: self.height.get().into(),
+                    z: 1,
+                },
+        }
+    }
+}
+
+impl Ladder {
+    /// Meters between rungs
+    const RUNG_SPACING: f32 = 0.3;
+
+    /// Whether `position` is inside the ladder's space, counting its bottom but not its top
+    pub fn contains(self, position: FactoryVector3) -> bool {
+        let FactoryBounds { min, max } = self.bounds();
+        (min.x..max.x).contains(&position.x)
+            && (min.y..max.y).contains(&position.y)
+            && (min.z..max.z).contains(&position.z)
+    }
+
+    pub fn draw(
+        self,
+        d: &mut dyn DynRaylibDraw3D,
+        _thread: &RaylibThread,
+        player_pos: &PlayerVector3,
+        factory_origin: &RailVector3,
+    ) {
+        let facing = self.facing.as_ordinal().direction3();
+        let side = facing.cross(Vector3::UP) * 0.35;
+        let height = f32::from(self.height.get());
+        // Against the back of the block, away from where it's climbed from
+        let back = self.position.to_player_relative(player_pos, factory_origin)
+            + Vector3::new(0.5, 0.0, 0.5)
+            - facing * 0.4;
+        for rail in [back - side, back + side] {
+            d.draw_line3D(rail, rail + Vector3::UP * height, Color::BROWN);
+        }
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "ladders are at most 255 meters tall"
+        )]
+        let rungs = (height / Self::RUNG_SPACING) as u32;
+        #[allow(clippy::cast_precision_loss, reason = "rung counts are small")]
+        for i in 1..=rungs {
+            let rung = back + Vector3::UP * (i as f32 * Self::RUNG_SPACING);
+            d.draw_line3D(rung - side, rung + side, Color::BROWN);
+        }
+    }
+}
+
+/// A level above the ground that can be stood and built on
+///
+/// Floors have no thickness. Like ladders, they aren't machines and aren't in the spatial index,
+/// and they don't block anything, so they can be climbed up through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Floor {
+    /// The area the floor covers, at the height of its top (`min.y == max.y`)
+    pub bounds: FactoryBounds,
+}
+
+impl Floor {
+    /// A floor at height `y` covering the same area as `bounds`
+    pub const fn new(y: i16, bounds: &FactoryBounds) -> Self {
+        Self {
+            bounds: FactoryBounds {
+                min: FactoryVector3 { y, ..bounds.min },
+                max: FactoryVector3 { y, ..bounds.max },
+            },
+        }
+    }
+
+    /// The height of the top of the floor
+    #[inline]
+    pub const fn level(self) -> i16 {
+        self.bounds.min.y
+    }
+
+    /// Whether the floor is directly above or below the block at `position`
+    pub fn covers(self, position: FactoryVector3) -> bool {
+        let FactoryBounds { min, max } = self.bounds;
+        (min.x..max.x).contains(&position.x) && (min.z..max.z).contains(&position.z)
+    }
+
+    pub fn draw(
+        self,
+        d: &mut dyn DynRaylibDraw3D,
+        player_pos: &PlayerVector3,
+        origin: &RailVector3,
+    ) {
+        let min = self.bounds.min.to_player_relative(player_pos, origin);
+        let max = self.bounds.max.to_player_relative(player_pos, origin);
+        let size = Vector3 {
+            y: Self::THICKNESS,
+            ..max - min
+        };
+        // Hang below the top, so machines on the floor aren't covered
+        let center = min.midpoint(max) - Vector3::UP * (Self::THICKNESS * 0.5);
+        d.draw_cube_v(center, size, Color::DARKGRAY.alpha(0.8));
+        d.draw_cube_wires_v(center, size, Color::GRAY);
+    }
+
+    /// How thick floors look, in meters
+    const THICKNESS: f32 = 0.1;
+}
+
+/// Why a factory couldn't be expanded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpandError {
+    /// Factories are built on the ground, and can't go under it
+    Underground,
+    /// The factory would go past the edge of [`FactoryVector3`]'s range
+    TooBig,
+    /// The factory would overlap another region
+    Overlaps,
+}
+
+impl std::fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Underground => "factories can't expand underground",
+            Self::TooBig => "factories can't get that big",
+            Self::Overlaps => "the factory would overlap something else",
+        })
+    }
+}
+
+impl std::error::Error for ExpandError {}
+
+/// Why a miner couldn't be built
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinerError {
+    /// Miners dig into the ground, so they have to be on the bottom floor
+    NotOnGround,
+    /// Something else is in the way
+    Occupied,
+    /// There's no deposit under the miner to dig
+    NoDeposit,
+}
+
+impl std::fmt::Display for MinerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::NotOnGround => "miners have to be on the ground floor",
+            Self::Occupied => "no room for a miner here",
+            Self::NoDeposit => "there's no ore deposit under here",
+        })
+    }
+}
+
+impl std::error::Error for MinerError {}
+
+/// Why a station couldn't be built
+///
+/// Ordered from least to most nearly buildable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StationError {
+    /// Stations have to face out of the factory from against a wall
+    NotAtWall,
+    /// Something else is in the way
+    Occupied,
+    /// There are no rails right outside the station
+    NoRails,
+}
+
+impl std::fmt::Display for StationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::NotAtWall => "stations have to be against a wall",
+            Self::Occupied => "no room for a station here",
+            Self::NoRails => "there are no rails outside",
+        })
+    }
+}
+
+impl std::error::Error for StationError {}
+
+/// Move items one at a time between `machine`'s `buffers` and the belt at each of its `nodes` on
+/// `side`, if there is one, up to the belt's speed
+///
+/// Each node is set up by the same index of `settings`. Only the nodes with the highest
+/// priority of those that can still move items are open, and items a node's filter doesn't
+/// allow stay where they are.
+fn move_belt_items(
+    machine: &mut dyn Machine,
+    side: BeltSide,
+    buffers: &mut MachineBuffers,
+    belt_items: &mut Components<ItemSlots>,
+    nodes: &[Option<(Entity, u32)>],
+    settings: &[&NodeSettings],
+) {
+    let mut budgets = (nodes.iter())
+        .map(|belt| belt.map_or(0, |(_, speed)| speed))
+        .collect::<ArrayVec<_, 8>>();
+    loop {
+        let top = (budgets.iter().zip(settings))
+            .filter(|&(&budget, _)| budget > 0)
+            .map(|(_, settings)| settings.priority)
+            .max();
+        let open = (budgets.iter().zip(settings))
+            .map(|(&budget, settings)| budget > 0 && Some(settings.priority) == top)
+            .collect::<ArrayVec<_, 8>>();
+        let Some(idx) = machine.next_belt(side, &open).filter(|&idx| open[idx]) else {
+            break;
+        };
+        let moved = nodes[idx]
+            .and_then(|(entity, _)| belt_items.get_mut(entity))
+            .is_some_and(|items| match side {
+                BeltSide::Input => transfer_item(items, &mut buffers.input, settings[idx]),
+                BeltSide::Output => transfer_item(&mut buffers.output, items, settings[idx]),
+            });
+        if moved {
+            budgets[idx] -= 1;
+            machine.moved_item(side, idx);
+        } else {
+            budgets[idx] = 0;
+        }
+    }
+}
+
+pub const fn machine_matrix(
+    player_pos: &PlayerVector3,
+    position: FactoryVector3,
+    origin: &RailVector3,
+    rotation: Cardinal2D,
+) -> Matrix {
+    let Vector3 { x, y, z } = position.to_player_relative(player_pos, origin);
+    let (cos, sin, _) = rotation.cos_sin_tan();
+    #[rustfmt::skip]
+    Matrix {
+        m0:  cos, m4: 0.0, m8:  sin, m12:   x,
+        m1:  0.0, m5: 1.0, m9:  0.0, m13:   y,
+        m2: -sin, m6: 0.0, m10: cos, m14:   z,
+        m3:  0.0, m7: 0.0, m11: 0.0, m15: 1.0,
+    }
+}
+
+/// Draw each model's first mesh with its material at every transform, in one draw call per
+/// model and material
+///
+/// Models that aren't loaded are skipped.
+fn draw_instanced(
+    d: &mut dyn DynRaylibDraw3D,
+    resources: &Resources,
+    instances: BTreeMap<(AssetId, AssetId), Vec<Matrix>>,
+) {
+    for ((model, material), mut transforms) in instances {
+        if let Some(model) = resources.model(model)
+            && let Some(material) = resources.material(material)
+        {
+            let model_transform = *model.transform();
+            for transform in &mut transforms {
+                *transform = *transform * model_transform;
+            }
+            d.draw_mesh_instanced(*model.meshes()[0], **material, &transforms);
+        }
+    }
+}
+
+/// Draw a machine's model posed by its animator, returning whether it could be
+///
+/// Machines whose model doesn't have the bones their animations move, or whose animations aren't
+/// loaded, are left to [`draw_instanced`].
+fn draw_posed(
+    d: &mut dyn DynRaylibDraw3D,
+    resources: &Resources,
+    model: &MachineModel,
+    animator: &Animator,
+    transform: Matrix,
+) -> bool {
+    let Some((idle, working)) = model.animations.and_then(|id| resources.animations(id)) else {
+        return false;
+    };
+    let Some(posed) = resources.model(model.model) else {
+        return false;
+    };
+    let pose = animator.pose(idle, working);
+    if pose.is_empty() || usize::try_from(posed.as_ref().boneCount).ok() != Some(pose.len()) {
+        return false;
+    }
+    animation::apply_pose(posed, &pose);
+    d.draw_mesh(
+        *posed.meshes()[0],
+        *posed.materials()[0],
+        transform * *posed.transform(),
+    );
+    true
+}
+
+/// Note: vectors are in Factory coordinates
+pub struct FactoryCollision<'a> {
+    pub target: Option<&'a dyn Machine>,
+    /// Identifies [`Self::target`] within the factory
+    pub id: Option<MachineId>,
+    pub distance: f32,
+    pub normal: Vector3,
+    pub point: Vector3,
+}
+
+/// Identifies a machine within a [`Factory`]
+///
+/// Unlike indices into [`Factory::reactors`], ids don't change when other machines are removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MachineId(Entity);
+
+/// A type of machine, for what can be built
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MachineKind {
+    Reactor,
+    Miner,
+    Splitter,
+    Merger,
+    Station,
+    Vent,
+    Controller,
+    Assembler,
+    Tank,
+    ChargingPad,
+    ReliefValve,
+    Distiller,
+    Crystallizer,
+    Electrolyzer,
+}
+
+impl MachineKind {
+    /// The [`tr!`] key of the machine's name
+    pub const fn name_key(self) -> &'static str {
+        match self {
+            Self::Reactor => "machine.reactor",
+            Self::Miner => "machine.miner",
+            Self::Splitter => "machine.splitter",
+            Self::Merger => "machine.merger",
+            Self::Station => "machine.station",
+            Self::Vent => "machine.vent",
+            Self::Controller => "machine.controller",
+            Self::Assembler => "machine.assembler",
+            Self::Tank => "machine.tank",
+            Self::ChargingPad => "machine.charging_pad",
+            Self::ReliefValve => "machine.relief_valve",
+            Self::Distiller => "machine.distiller",
+            Self::Crystallizer => "machine.crystallizer",
+            Self::Electrolyzer => "machine.electrolyzer",
+        }
+    }
+
+    /// The name shown to the player, the same as [`Machine::name`]
+    pub fn name(self) -> String {
+        tr!(self.name_key())
+    }
+}
+
+/// Machines, belts, and pipes are [entities](crate::ecs), made of components:
+///
+/// - Machines have what makes them that type of machine, like a [`Reactor`], and
+///   [`MachineBuffers`], a [`Thermal`] if they heat up, and a [`Vessel`] if they're sealed. Everything else about them goes through the [`Machine`] trait, so only
+///   [`Self::machines`] and [`Self::machines_mut`] need to know what types there are.
+/// - Belts have a [`Belt`], and the [`ItemSlots`] of items on it.
+/// - Pipes have a [`Pipe`], and the [`FluidTank`] of fluid in it.
+#[derive(Debug)]
+pub struct Factory {
+    pub origin: RailVector3,
+    pub bounds: FactoryBounds,
+    entities: Entities,
+    reactors: Components<Reactor>,
+    miners: Components<Miner>,
+    splitters: Components<Splitter>,
+    mergers: Components<Merger>,
+    stations: Components<Station>,
+    vents: Components<Vent>,
+    /// Machines of the types plugins add
+    plugin_machines: Components<PluginMachine>,
+    controllers: Components<Controller>,
+    assemblers: Components<Assembler>,
+    tanks: Components<Tank>,
+    /// Each with a drone of its own
+    charging_pads: Components<ChargingPad>,
+    relief_valves: Components<ReliefValve>,
+    distillers: Components<Distiller>,
+    crystallizers: Components<Crystallizer>,
+    electrolyzers: Components<Electrolyzer>,
+    ladders: Vec<Ladder>,
+    belts: Components<Belt>,
+    pipes: Components<Pipe>,
+    floors: Vec<Floor>,
+    /// Markings painted on the ground and floors, at most one per block
+    decals: Vec<Decal>,
+    /// Must be kept in sync with the machine components
+    index: SpatialIndex<MachineId>,
+    /// What each machine is holding
+    buffers: Components<MachineBuffers>,
+    /// How hot each machine that heats up is
+    thermals: Components<Thermal>,
+    /// The gas sealed in each machine that holds it in
+    vessels: Components<Vessel>,
+    /// How far through its animations each machine with a model is
+    animators: Components<Animator>,
+    /// Machines a controller or the circuit has turned off, which don't run until they're turned
+    /// back on
+    disabled: Components<()>,
+    /// Filters and priorities of machines' belt nodes, see [`Self::set_node_settings`]
+    node_settings: Components<NodeTable>,
+    /// Signals between machines, which can turn them on and off
+    circuit: Circuit,
+    /// What controllers printed and why any stopped, waiting to be shown
+    script_log: Vec<String>,
+    /// Where vessels have ruptured, waiting to hurt whoever's nearby
+    ruptures: Vec<FactoryVector3>,
+    /// What's on each belt
+    belt_items: Components<ItemSlots>,
+    /// What's in each pipe
+    pipe_fluids: Components<FluidTank>,
+    /// Gas in the air, from machines that leak
+    gas: GasField,
+    /// What machines have made and used up recently
+    stats: Statistics,
+    /// Seconds since belts last moved items
+    belt_timer: f32,
+    /// How many threads machines are ticked on at once, see [`sim`]
+    pub threads: NonZeroUsize,
+    /// Which machines are ticked every tick, see [`lod`]
+    pub lod: UpdateLod,
+    /// Shown while the player is in the factory
+    pub grid: Option<GridVisualizer>,
+}
+
+impl Factory {
+    /// Liters of gas per second that leak out of an overheated machine
+    const LEAK: f32 = 20.0;
+
+    pub fn new(origin: RailVector3, bounds: FactoryBounds) -> Self {
+        Self {
+            origin,
+            bounds,
+            entities: Entities::new(),
+            reactors: Components::new(),
+            miners: Components::new(),
+            splitters: Components::new(),
+            mergers: Components::new(),
+            stations: Components::new(),
+            vents: Components::new(),
+            plugin_machines: Components::new(),
+            controllers: Components::new(),
+            assemblers: Components::new(),
+            tanks: Components::new(),
+            charging_pads: Components::new(),
+            relief_valves: Components::new(),
+            distillers: Components::new(),
+            crystallizers: Components::new(),
+            electrolyzers: Components::new(),
+            ladders: Vec::new(),
+            belts: Components::new(),
+            pipes: Components::new(),
+            floors: Vec::new(),
+            decals: Vec::new(),
+            index: SpatialIndex::new(),
+            buffers: Components::new(),
+            thermals: Components::new(),
+            vessels: Components::new(),
+            animators: Components::new(),
+            disabled: Components::new(),
+            node_settings: Components::new(),
+            circuit: Circuit::new(),
+            script_log: Vec::new(),
+            ruptures: Vec::new(),
+            belt_items: Components::new(),
+            pipe_fluids: Components::new(),
+            gas: GasField::new(bounds),
+            stats: Statistics::new(),
+            belt_timer: 0.0,
+            threads: std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+            lod: UpdateLod::new(),
+            grid: None,
+        }
+    }
+
+    #[inline]
+    pub fn reactors(&self) -> &[Reactor] {
+        self.reactors.as_slice()
+    }
+
+    /// The id of the reactor at `idx` in [`Self::reactors`]
+    pub fn reactor_id(&self, idx: usize) -> Option<MachineId> {
+        self.reactors.entities().get(idx).copied().map(MachineId)
+    }
+
+    #[inline]
+    pub fn miners(&self) -> &[Miner] {
+        self.miners.as_slice()
+    }
+
+    #[inline]
+    pub fn splitters(&self) -> &[Splitter] {
+        self.splitters.as_slice()
+    }
+
+    #[inline]
+    pub fn mergers(&self) -> &[Merger] {
+        self.mergers.as_slice()
+    }
+
+    #[inline]
+    pub fn stations(&self) -> &[Station] {
+        self.stations.as_slice()
+    }
+
+    #[inline]
+    pub fn vents(&self) -> &[Vent] {
+        self.vents.as_slice()
+    }
+
+    #[inline]
+    pub fn plugin_machines(&self) -> &[PluginMachine] {
+        self.plugin_machines.as_slice()
+    }
+
+    #[inline]
+    pub fn controllers(&self) -> &[Controller] {
+        self.controllers.as_slice()
+    }
+
+    #[inline]
+    pub fn assemblers(&self) -> &[Assembler] {
+        self.assemblers.as_slice()
+    }
+
+    #[inline]
+    pub fn tanks(&self) -> &[Tank] {
+        self.tanks.as_slice()
+    }
+
+    #[inline]
+    pub fn charging_pads(&self) -> &[ChargingPad] {
+        self.charging_pads.as_slice()
+    }
+
+    #[inline]
+    pub fn relief_valves(&self) -> &[ReliefValve] {
+        self.relief_valves.as_slice()
+    }
+
+    #[inline]
+    pub fn distillers(&self) -> &[Distiller] {
+        self.distillers.as_slice()
+    }
+
+    #[inline]
+    pub fn crystallizers(&self) -> &[Crystallizer] {
+        self.crystallizers.as_slice()
+    }
+
+    #[inline]
+    pub fn electrolyzers(&self) -> &[Electrolyzer] {
+        self.electrolyzers.as_slice()
+    }
+
+    /// How many machines of `kind` there are
+    pub const fn machine_count(&self, kind: MachineKind) -> usize {
+        match kind {
+            MachineKind::Reactor => self.reactors.len(),
+            MachineKind::Miner => self.miners.len(),
+            MachineKind::Splitter => self.splitters.len(),
+            MachineKind::Merger => self.mergers.len(),
+            MachineKind::Station => self.stations.len(),
+            MachineKind::Vent => self.vents.len(),
+            MachineKind::Controller => self.controllers.len(),
+            MachineKind::Assembler => self.assemblers.len(),
+            MachineKind::Tank => self.tanks.len(),
+            MachineKind::ChargingPad => self.charging_pads.len(),
+            MachineKind::ReliefValve => self.relief_valves.len(),
+            MachineKind::Distiller => self.distillers.len(),
+            MachineKind::Crystallizer => self.crystallizers.len(),
+            MachineKind::Electrolyzer => self.electrolyzers.len(),
+        }
+    }
+
+    #[inline]
+    pub fn ladders(&self) -> &[Ladder] {
+        &self.ladders
+    }
+
+    #[inline]
+    pub fn belts(&self) -> &[Belt] {
+        self.belts.as_slice()
+    }
+
+    #[inline]
+    pub fn pipes(&self) -> &[Pipe] {
+        self.pipes.as_slice()
+    }
+
+    /// Every belt, with how many items are on it
+    pub fn belt_loads(&self) -> impl Iterator<Item = (&Belt, u32)> {
+        (self.belts.iter()).map(|(entity, belt)| {
+            let items = self.belt_items.get(entity).map_or(0, ItemSlots::stored);
+            (belt, items)
+        })
+    }
+
+    /// Every pipe, with the fluid in it if it isn't empty
+    pub fn pipe_contents(&self) -> impl Iterator<Item = (&Pipe, Option<&FluidVolume>)> {
+        (self.pipes.iter()).map(|(entity, pipe)| {
+            let fluid = self.pipe_fluids.get(entity).and_then(FluidTank::contents);
+            (pipe, fluid)
+        })
+    }
+
+    /// Floors above the ground, in the order they were built
+    #[inline]
+    pub fn floors(&self) -> &[Floor] {
+        &self.floors
+    }
+
+    /// Build a floor in the factory
+    ///
+    /// Returns `false` without building it if it isn't inside the factory, is on the ground, or
+    /// overlaps another floor at the same level.
+    pub fn add_floor(&mut self, floor: Floor) -> bool {
+        let fits = floor.level() > 0
+            && self.bounds.union(&floor.bounds) == self.bounds
+            && !self.floors.iter().any(|other| {
+                other.level() == floor.level()
+                    && other
+                        .bounds
+                        .intersection(&floor.bounds)
+                        .is_some_and(|shared| shared.size().x > 0 && shared.size().z > 0)
+            });
+        if fits {
+            self.floors.push(floor);
+        }
+        fits
+    }
+
+    /// Remove a floor from the factory
+    ///
+    /// Note: the last floor takes the removed floor's index
+    pub fn remove_floor(&mut self, idx: usize) -> Option<Floor> {
+        let floor = (idx < self.floors.len()).then(|| self.floors.swap_remove(idx))?;
+        // Whatever was painted on it goes with it
+        self.decals
+            .retain(|decal| decal.position.y != floor.level() || !floor.covers(decal.position));
+        Some(floor)
+    }
+
+    /// Whether the block at `position` has the ground or a floor under it to paint on
+    pub fn is_floor(&self, position: FactoryVector3) -> bool {
+        if position.y == 0 {
+            Floor::new(0, &self.bounds).covers(position)
+        } else {
+            (self.floors.iter()).any(|floor| floor.level() == position.y && floor.covers(position))
+        }
+    }
+
+    /// Markings painted on the ground and floors, see [`decal`]
+    #[inline]
+    pub fn decals(&self) -> &[Decal] {
+        &self.decals
+    }
+
+    /// Paint `decal` over whatever's already painted on its block
+    ///
+    /// Returns `false` without painting it if there's no floor under it, see
+    /// [`Self::is_floor`].
+    pub fn paint_decal(&mut self, decal: Decal) -> bool {
+        if !self.is_floor(decal.position) {
+            return false;
+        }
+        _ = self.erase_decal(decal.position);
+        self.decals.push(decal);
+        true
+    }
+
+    /// Clear the decal painted on the block at `position`, if there is one
+    pub fn erase_decal(&mut self, position: FactoryVector3) -> Option<Decal> {
+        let idx = (self.decals.iter()).position(|decal| decal.position == position)?;
+        Some(self.decals.swap_remove(idx))
+    }
+
+    /// The height of the highest floor at or below `position`, which is 0 for the ground
+    ///
+    /// This is the level things are built on when the player is at `position`.
+    pub fn level_at(&self, position: FactoryVector3) -> i16 {
+        (self.floors.iter())
+            .filter(|floor| floor.level() <= position.y && floor.covers(position))
+            .map(|floor| floor.level())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The factory's bounds after growing `blocks` meters out of its `side`
+    pub fn expanded_bounds(
+        &self,
+        side: Cardinal3D,
+        blocks: u16,
+    ) -> Result<FactoryBounds, ExpandError> {
+        let blocks = i16::try_from(blocks).map_err(|_| ExpandError::TooBig)?;
+        let FactoryBounds { mut min, mut max } = self.bounds;
+        let edge = match side {
+            Cardinal3D::Down => return Err(ExpandError::Underground),
+            Cardinal3D::East => max.x.checked_add(blocks).map(|x| max.x = x),
+            Cardinal3D::North => max.z.checked_add(blocks).map(|z| max.z = z),
+            Cardinal3D::Up => max.y.checked_add(blocks).map(|y| max.y = y),
+            Cardinal3D::West => min.x.checked_sub(blocks).map(|x| min.x = x),
+            Cardinal3D::South => min.z.checked_sub(blocks).map(|z| min.z = z),
+        };
+        // The size has to fit too
+        let extent =
+            [(min.x, max.x), (min.y, max.y), (min.z, max.z)].map(|(min, max)| max.checked_sub(min));
+        match (edge, extent) {
+            (Some(()), [Some(_), Some(_), Some(_)]) => Ok(FactoryBounds { min, max }),
+            _ => Err(ExpandError::TooBig),
+        }
+    }
+
+    /// Grow the factory `blocks` meters out of its `side`
+    ///
+    /// Returns how many square meters of floor space were added, which is what the expansion
+    /// costs. Doesn't check for other regions in the way; see [`GameState::expand_factory`].
+    ///
+    /// [`GameState::expand_factory`]: crate::game::GameState::expand_factory
+    pub fn expand(&mut self, side: Cardinal3D, blocks: u16) -> Result<u32, ExpandError> {
+        let bounds = self.expanded_bounds(side, blocks)?;
+        let floor_area = |bounds: &FactoryBounds| {
+            let extent = bounds.size();
+            u32::from(extent.x.unsigned_abs()) * u32::from(extent.z.unsigned_abs())
+        };
+        // Upper floors only grow when they're built out, not when the factory does
+        let added = floor_area(&bounds) - floor_area(&self.bounds);
+        self.bounds = bounds;
+        self.gas.fit(bounds);
+        Ok(added)
+    }
+
+    /// Place a ladder in the factory
+    pub fn add_ladder(&mut self, ladder: Ladder) {
+        self.ladders.push(ladder);
+    }
+
+    /// Place a belt in the factory, returning the entity it is
+    pub fn add_belt(&mut self, belt: Belt) -> Entity {
+        let entity = self.entities.spawn();
+        self.belt_items
+            .insert(entity, ItemSlots::new(1, belt.capacity()));
+        self.belts.insert(entity, belt);
+        entity
+    }
+
+    /// Place a pipe in the factory, returning the entity it is
+    pub fn add_pipe(&mut self, pipe: Pipe) -> Entity {
+        let entity = self.entities.spawn();
+        self.pipe_fluids
+            .insert(entity, FluidTank::new(pipe.capacity()));
+        self.pipes.insert(entity, pipe);
+        entity
+    }
+
+    /// Remove a belt from the factory
+    ///
+    /// Note: the last belt takes the removed belt's index
+    pub fn remove_belt(&mut self, idx: usize) -> Option<Belt> {
+        let entity = *self.belts.entities().get(idx)?;
+        let belt = self.belts.remove(entity);
+        self.despawn(entity);
+        belt
+    }
+
+    /// Remove `entity` and every component it has
+    fn despawn(&mut self, entity: Entity) {
+        _ = self.take(entity);
+    }
+
+    /// Every block a belt or pipe goes through
+    pub fn conveyor_cells(&self) -> impl Iterator<Item = FactoryVector3> + '_ {
+        (self.belts().iter().map(|belt| &belt.path))
+            .chain(self.pipes().iter().map(|pipe| &pipe.path))
+            .flat_map(|path| routing::route_cells(path))
+    }
+
+    /// The spatial index of every machine in the factory
+    #[inline]
+    pub const fn spatial_index(&self) -> &SpatialIndex<MachineId> {
+        &self.index
+    }
+
+    /// Every machine in the factory, whatever its type
+    ///
+    /// This and [`Self::machines_mut`] are the only places that need to know every type of
+    /// machine; everything else goes through the [`Machine`] trait.
+    pub fn machines(&self) -> impl Iterator<Item = (MachineId, &dyn MachineUi)> {
+        let reactors = (self.reactors.iter()).map(|(entity, reactor)| (entity, reactor as _));
+        let miners = (self.miners.iter()).map(|(entity, miner)| (entity, miner as _));
+        let splitters = (self.splitters.iter()).map(|(entity, splitter)| (entity, splitter as _));
+        let mergers = (self.mergers.iter()).map(|(entity, merger)| (entity, merger as _));
+        let stations = (self.stations.iter()).map(|(entity, station)| (entity, station as _));
+        let vents = (self.vents.iter()).map(|(entity, vent)| (entity, vent as _));
+        let plugin_machines =
+            (self.plugin_machines.iter()).map(|(entity, machine)| (entity, machine as _));
+        let controllers =
+            (self.controllers.iter()).map(|(entity, controller)| (entity, controller as _));
+        let assemblers =
+            (self.assemblers.iter()).map(|(entity, assembler)| (entity, assembler as _));
+        let tanks = (self.tanks.iter()).map(|(entity, tank)| (entity, tank as _));
+        let charging_pads = (self.charging_pads.iter()).map(|(entity, pad)| (entity, pad as _));
+        let relief_valves = (self.relief_valves.iter()).map(|(entity, valve)| (entity, valve as _));
+        let distillers =
+            (self.distillers.iter()).map(|(entity, distiller)| (entity, distiller as _));
+        let crystallizers =
+            (self.crystallizers.iter()).map(|(entity, crystallizer)| (entity, crystallizer as _));
+        let electrolyzers =
+            (self.electrolyzers.iter()).map(|(entity, electrolyzer)| (entity, electrolyzer as _));
+        (reactors
+            .chain(miners)
+            .chain(splitters)
+            .chain(mergers)
+            .chain(stations)
+            .chain(vents)
+            .chain(plugin_machines)
+            .chain(controllers)
+            .chain(assemblers)
+            .chain(tanks)
+            .chain(charging_pads)
+            .chain(relief_valves)
+            .chain(distillers)
+            .chain(crystallizers)
+            .chain(electrolyzers))
+        .map(|(entity, machine)| (MachineId(entity), machine))
+    }
+
+    /// Every machine in the factory, whatever its type
+    fn machines_mut(&mut self) -> impl Iterator<Item = (MachineId, &mut dyn Machine)> {
+        let reactors = (self.reactors.iter_mut()).map(|(entity, reactor)| (entity, reactor as _));
+        let miners = (self.miners.iter_mut()).map(|(entity, miner)| (entity, miner as _));
+        let splitters =
+            (self.splitters.iter_mut()).map(|(entity, splitter)| (entity, splitter as _));
+        let mergers = (self.mergers.iter_mut()).map(|(entity, merger)| (entity, merger as _));
+        let stations = (self.stations.iter_mut()).map(|(entity, station)| (entity, station as _));
+        let vents = (self.vents.iter_mut()).map(|(entity, vent)| (entity, vent as _));
+        let plugin_machines =
+            (self.plugin_machines.iter_mut()).map(|(entity, machine)| (entity, machine as _));
+        let controllers =
+            (self.controllers.iter_mut()).map(|(entity, controller)| (entity, controller as _));
+        let assemblers =
+            (self.assemblers.iter_mut()).map(|(entity, assembler)| (entity, assembler as _));
+        let tanks = (self.tanks.iter_mut()).map(|(entity, tank)| (entity, tank as _));
+        let charging_pads = (self.charging_pads.iter_mut()).map(|(entity, pad)| (entity, pad as _));
+        let relief_valves =
+            (self.relief_valves.iter_mut()).map(|(entity, valve)| (entity, valve as _));
+        let distillers =
+            (self.distillers.iter_mut()).map(|(entity, distiller)| (entity, distiller as _));
+        let crystallizers = (self.crystallizers.iter_mut())
+            .map(|(entity, crystallizer)| (entity, crystallizer as _));
+        let electrolyzers = (self.electrolyzers.iter_mut())
+            .map(|(entity, electrolyzer)| (entity, electrolyzer as _));
+        (reactors
+            .chain(miners)
+            .chain(splitters)
+            .chain(mergers)
+            .chain(stations)
+            .chain(vents)
+            .chain(plugin_machines)
+            .chain(controllers)
+            .chain(assemblers)
+            .chain(tanks)
+            .chain(charging_pads)
+            .chain(relief_valves)
+            .chain(distillers)
+            .chain(crystallizers)
+            .chain(electrolyzers))
+        .map(|(entity, machine)| (MachineId(entity), machine))
+    }
+
+    /// Get the machine identified by `id`
+    pub fn machine(&self, id: MachineId) -> Option<&dyn Machine> {
+        self.machine_ui(id).map(|machine| machine as _)
+    }
+
+    /// Get the machine identified by `id`, with what its panel shows
+    pub fn machine_ui(&self, id: MachineId) -> Option<&dyn MachineUi> {
+        (self.machines())
+            .find(|&(other, _)| other == id)
+            .map(|(_, machine)| machine)
+    }
+
+    /// Get the machine identified by `id`
+    fn machine_mut(&mut self, id: MachineId) -> Option<&mut dyn Machine> {
+        (self.machines_mut())
+            .find(|&(other, _)| other == id)
+            .map(|(_, machine)| machine)
+    }
+
+    /// The first machine with a node `is_connected` to, if any
+    fn connected_machine(&self, is_connected: impl Fn(&dyn Machine) -> bool) -> Option<MachineId> {
+        (self.machines())
+            .find(|&(_, machine)| is_connected(machine))
+            .map(|(id, _)| id)
+    }
+
+    /// What the machine identified by `id` is holding
+    pub fn buffers(&self, id: MachineId) -> Option<&MachineBuffers> {
+        self.buffers.get(id.0)
+    }
+
+    /// What the machine identified by `id` is holding
+    pub fn buffers_mut(&mut self, id: MachineId) -> Option<&mut MachineBuffers> {
+        self.buffers.get_mut(id.0)
+    }
+
+    /// Gas in the factory's air
+    #[inline]
+    pub const fn gas(&self) -> &GasField {
+        &self.gas
+    }
+
+    /// Electricity the factory's machines are drawing, leaving out ones that are turned off
+    pub fn power_draw(&self) -> Watt {
+        (self.machines())
+            .filter(|(id, _)| !self.disabled.contains(id.0))
+            .fold(Watt::ZERO, |total, (_, machine)| total + machine.power())
+    }
+
+    /// What the factory's machines have made and used up recently
+    #[inline]
+    pub const fn statistics(&self) -> &Statistics {
+        &self.stats
+    }
+
+    /// Gas in the factory's air
+    #[inline]
+    pub const fn gas_mut(&mut self) -> &mut GasField {
+        &mut self.gas
+    }
+
+    /// How hot the machine identified by `id` is, if it's a type that heats up
+    pub fn thermal(&self, id: MachineId) -> Option<&Thermal> {
+        self.thermals.get(id.0)
+    }
+
+    /// The gas sealed in the machine identified by `id`, if it's a type that holds it in
+    pub fn vessel(&self, id: MachineId) -> Option<&Vessel> {
+        self.vessels.get(id.0)
+    }
+
+    /// Health lost per second by a player taking up `area` for touching machines hot enough to
+    /// burn, see [`Thermal::burn_damage`]
+    pub fn burn_damage(&self, area: &PlayerBounds) -> f32 {
+        // Close enough to touch, including standing on top
+        let reach = PlayerVector3::from_f32(0.1, 0.1, 0.1);
+        let touching = PlayerBounds {
+            min: area.min.minus(reach),
+            max: area.max.plus(reach),
+        };
+        (self.machines())
+            .filter(|(_, machine)| {
+                let FactoryBounds { min, max } = machine.bounds();
+                touching.overlaps(&PlayerBounds {
+                    min: min.to_player(&self.origin),
+                    max: max.to_player(&self.origin),
+                })
+            })
+            .filter_map(|(id, _)| self.thermal(id))
+            .map(Thermal::burn_damage)
+            .fold(0.0, f32::max)
+    }
+
+    /// Whether a machine taking up `bounds` would fit in the factory without overlapping another
+    /// machine, belt, or pipe
+    ///
+    /// Machines may touch, since [`FactoryBounds`] include their maximum corner.
+    pub fn is_vacant(&self, bounds: &FactoryBounds) -> bool {
+        self.bounds.union(bounds) == self.bounds
+            && self.is_vacant_of_machines(bounds)
+            && !self.conveyor_cells().any(|cell| {
+                bounds
+                    .intersection(&FactoryBounds {
+                        min: cell,
+                        max: cell + FactoryVector3::ONE,
+                    })
+                    .is_some_and(|shared| shared.volume() > 0)
+            })
+    }
+
+    /// Whether a belt or pipe along `path` would fit in the factory without crossing a machine or
+    /// another belt or pipe
+    ///
+    /// Its ends may be inside machines, since that's where it connects to them.
+    pub fn is_route_vacant(&self, path: &[FactoryVector3]) -> bool {
+        let taken = self.conveyor_cells().collect::<HashSet<_>>();
+        let ends = [path.first(), path.last()];
+        !path.is_empty()
+            // Checked first, since walking between points far outside could overflow
+            && path.iter().all(|point| self.bounds.contains(point))
+            && routing::route_cells(path).all(|cell| {
+                let block = FactoryBounds {
+                    min: cell,
+                    max: cell + FactoryVector3::ONE,
+                };
+                self.bounds.union(&block) == self.bounds
+                    && (ends.contains(&Some(&cell))
+                        || (!taken.contains(&cell) && self.is_vacant_of_machines(&block)))
+            })
+    }
+
+    /// Whether `bounds` doesn't overlap any machine, ignoring belts, pipes, and the walls
+    fn is_vacant_of_machines(&self, bounds: &FactoryBounds) -> bool {
+        self.index.query_aabb(bounds).into_iter().all(|id| {
+            self.index
+                .get(&id)
+                .and_then(|other| other.intersection(bounds))
+                .is_none_or(|shared| shared.volume() == 0)
+        })
+    }
+
+    /// Make a machine entity with the parts every machine has, returning its id
+    fn spawn_machine(&mut self, machine: &dyn Machine) -> MachineId {
+        let id = MachineId(self.entities.spawn());
+        self.index.insert(id, machine.bounds());
+        self.buffers.insert(id.0, machine.empty_buffers());
+        if let Some(properties) = machine.thermal_properties() {
+            self.thermals.insert(id.0, Thermal::new(properties));
+        }
+        if let Some(properties) = machine.vessel_properties() {
+            self.vessels.insert(id.0, Vessel::new(properties));
+        }
+        if machine.model().is_some() {
+            self.animators.insert(id.0, Animator::new());
+        }
+        id
+    }
+
+    /// Place a reactor in the factory
+    pub fn add_reactor(&mut self, reactor: Reactor) -> MachineId {
+        let id = self.spawn_machine(&reactor);
+        self.reactors.insert(id.0, reactor);
+        id
+    }
+
+    /// Remove a reactor from the factory
+    ///
+    /// Note: the last reactor takes the removed reactor's index, but keeps its [`MachineId`]
+    pub fn remove_reactor(&mut self, idx: usize) -> Option<Reactor> {
+        let entity = *self.reactors.entities().get(idx)?;
+        let reactor = self.reactors.remove(entity);
+        self.despawn(entity);
+        reactor
+    }
+
+    /// Place a miner in the factory
+    ///
+    /// Doesn't check that it's over a deposit, see
+    /// [`GameState::add_miner`](crate::game::GameState::add_miner).
+    pub fn add_miner(&mut self, miner: Miner) -> MachineId {
+        let id = self.spawn_machine(&miner);
+        self.miners.insert(id.0, miner);
+        id
+    }
+
+    /// Place a splitter in the factory
+    pub fn add_splitter(&mut self, splitter: Splitter) -> MachineId {
+        let id = self.spawn_machine(&splitter);
+        self.splitters.insert(id.0, splitter);
+        id
+    }
+
+    /// Place a merger in the factory
+    pub fn add_merger(&mut self, merger: Merger) -> MachineId {
+        let id = self.spawn_machine(&merger);
+        self.mergers.insert(id.0, merger);
+        id
+    }
+
+    /// Place a station in the factory
+    ///
+    /// Doesn't check that it's against a wall by the rails, see
+    /// [`GameState::add_station`](crate::game::GameState::add_station).
+    pub fn add_station(&mut self, station: Station) -> MachineId {
+        let id = self.spawn_machine(&station);
+        self.stations.insert(id.0, station);
+        id
+    }
+
+    /// Place a vent in the factory
+    pub fn add_vent(&mut self, vent: Vent) -> MachineId {
+        let id = self.spawn_machine(&vent);
+        self.vents.insert(id.0, vent);
+        id
+    }
+
+    /// Place a machine of a type a plugin added in the factory
+    pub fn add_plugin_machine(&mut self, machine: PluginMachine) -> MachineId {
+        let id = self.spawn_machine(&machine);
+        self.plugin_machines.insert(id.0, machine);
+        id
+    }
+
+    /// Place a controller in the factory
+    pub fn add_controller(&mut self, controller: Controller) -> MachineId {
+        let id = self.spawn_machine(&controller);
+        self.controllers.insert(id.0, controller);
+        id
+    }
+
+    /// Place an assembler in the factory
+    pub fn add_assembler(&mut self, assembler: Assembler) -> MachineId {
+        let id = self.spawn_machine(&assembler);
+        self.assemblers.insert(id.0, assembler);
+        id
+    }
+
+    /// Place a tank in the factory
+    pub fn add_tank(&mut self, tank: Tank) -> MachineId {
+        let id = self.spawn_machine(&tank);
+        self.tanks.insert(id.0, tank);
+        id
+    }
+
+    /// Place a relief valve in the factory
+    pub fn add_relief_valve(&mut self, valve: ReliefValve) -> MachineId {
+        let id = self.spawn_machine(&valve);
+        self.relief_valves.insert(id.0, valve);
+        id
+    }
+
+    /// Place a distiller in the factory
+    pub fn add_distiller(&mut self, distiller: Distiller) -> MachineId {
+        let id = self.spawn_machine(&distiller);
+        self.distillers.insert(id.0, distiller);
+        id
+    }
+
+    /// Place a crystallizer in the factory
+    pub fn add_crystallizer(&mut self, crystallizer: Crystallizer) -> MachineId {
+        let id = self.spawn_machine(&crystallizer);
+        self.crystallizers.insert(id.0, crystallizer);
+        id
+    }
+
+    /// Place an electrolyzer in the factory
+    pub fn add_electrolyzer(&mut self, electrolyzer: Electrolyzer) -> MachineId {
+        let id = self.spawn_machine(&electrolyzer);
+        self.electrolyzers.insert(id.0, electrolyzer);
+        id
+    }
+
+    /// Place a charging pad in the factory, with its drone
+    pub fn add_charging_pad(&mut self, pad: ChargingPad) -> MachineId {
+        let id = self.spawn_machine(&pad);
+        self.charging_pads.insert(id.0, pad);
+        id
+    }
+
+    /// The assembler identified by `id`, to set what it makes
+    pub fn assembler_mut(&mut self, id: MachineId) -> Option<&mut Assembler> {
+        self.assemblers.get_mut(id.0)
+    }
+
+    /// The distiller identified by `id`, to set what it heats to
+    pub fn distiller_mut(&mut self, id: MachineId) -> Option<&mut Distiller> {
+        self.distillers.get_mut(id.0)
+    }
+
+    /// The crystallizer identified by `id`, to set what it cools to
+    pub fn crystallizer_mut(&mut self, id: MachineId) -> Option<&mut Crystallizer> {
+        self.crystallizers.get_mut(id.0)
+    }
+
+    /// The electrolyzer identified by `id`, to set what it splits
+    pub fn electrolyzer_mut(&mut self, id: MachineId) -> Option<&mut Electrolyzer> {
+        self.electrolyzers.get_mut(id.0)
+    }
+
+    /// The controller taking up the block at `position`, if any
+    pub fn controller_at_mut(&mut self, position: FactoryVector3) -> Option<&mut Controller> {
+        (self.controllers.iter_mut())
+            .map(|(_, controller)| controller)
+            .find(|controller| controller.position == position)
+    }
+
+    #[inline]
+    pub const fn circuit(&self) -> &Circuit {
+        &self.circuit
+    }
+
+    #[inline]
+    pub const fn circuit_mut(&mut self) -> &mut Circuit {
+        &mut self.circuit
+    }
+
+    /// Whether the machine identified by `id` hasn't been turned off by a controller or the
+    /// circuit
+    pub fn is_enabled(&self, id: MachineId) -> bool {
+        !self.disabled.contains(id.0)
+    }
+
+    /// Turn the machine identified by `id` on or off
+    pub fn set_enabled(&mut self, id: MachineId, enable: bool) {
+        if enable {
+            self.disabled.remove(id.0);
+        } else if self.machine(id).is_some() {
+            self.disabled.insert(id.0, ());
+        }
+    }
+
+    /// What controllers have printed and why any stopped since this was last called
+    pub fn take_script_log(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.script_log)
+    }
+
+    /// Where vessels have ruptured since this was last called, oldest first
+    pub fn take_ruptures(&mut self) -> Vec<FactoryVector3> {
+        std::mem::take(&mut self.ruptures)
+    }
+
+    /// The belts and pipes connected to the machine identified by `id`
+    fn attached(&self, id: MachineId) -> Vec<Entity> {
+        let Some(machine) = self.machine(id) else {
+            return Vec::new();
+        };
+        let (inputs, outputs, pipe_nodes) = (
+            machine.belt_inputs(),
+            machine.belt_outputs(),
+            machine.pipe_nodes(),
+        );
+        let belts = (self.belts.iter())
+            .filter(|(_, belt)| inputs.contains(&belt.dst) || outputs.contains(&belt.src))
+            .map(|(entity, _)| entity);
+        let pipes = (self.pipes.iter())
+            .filter(|(_, pipe)| pipe_nodes.contains(&pipe.a) || pipe_nodes.contains(&pipe.b))
+            .map(|(entity, _)| entity);
+        belts.chain(pipes).collect()
+    }
+
+    /// The path of each belt and pipe connected to the machine identified by `id`, which
+    /// [`Self::demolish`] would remove with it
+    pub fn attached_paths(&self, id: MachineId) -> impl Iterator<Item = &[FactoryVector3]> {
+        self.attached(id).into_iter().filter_map(|entity| {
+            (self.belts.get(entity).map(|belt| belt.path.as_slice()))
+                .or_else(|| self.pipes.get(entity).map(|pipe| pipe.path.as_slice()))
+        })
+    }
+
+    /// Remove the machine identified by `id`, along with every belt and pipe connected to it
+    ///
+    /// Returns the items the machine and those belts were holding, or [`None`] if there's no
+    /// such machine. Fluids in the machine and pipes are lost.
+    pub fn demolish(&mut self, id: MachineId) -> Option<Vec<ItemStack>> {
+        let rows = self.take_machine(id)?;
+        Some(rows.iter().flat_map(EntityRow::items).cloned().collect())
+    }
+
+    /// Advance every machine in the factory by `dt` seconds with `daylight` outside, mining
+    /// from `deposits` and loading trains on `rail`, and move things between them
+    ///
+    /// Overheated machines stop and leak gas, which spreads through the factory.
+    pub fn tick(
+        &mut self,
+        dt: f32,
+        daylight: f32,
+        deposits: &mut Deposits,
+        rail: &mut RailNetwork,
+    ) {
+        // Signals are from the end of the last tick, so machines see them all at once
+        self.run_circuit();
+        self.tick_machines(dt, daylight, deposits, rail);
+        self.gas.diffuse(dt);
+        self.stats.tick(dt);
+        self.run_controllers();
+        // Items are whole, so belts move them in batches once a second
+        self.belt_timer += dt;
+        while self.belt_timer >= 1.0 {
+            self.belt_timer -= 1.0;
+            self.move_items();
+        }
+        self.move_drones(dt);
+        self.move_fluids(dt);
+    }
+
+    /// Move each belt's worth of items off of it and onto it
+    ///
+    /// Items move one at a time, with each machine choosing which of its belts is next, see
+    /// [`Machine::next_belt`], out of the ones with the highest [priority](NodeSettings::priority)
+    /// that can still take one.
+    fn move_items(&mut self) {
+        let belts = (self.belts.iter())
+            .map(|(entity, belt)| {
+                let speed = u32::try_from(belt.speed()).unwrap_or(u32::MAX);
+                (entity, belt.src, belt.dst, speed)
+            })
+            .collect::<Vec<_>>();
+        // Taken out so machines can be borrowed alongside them
+        let mut buffers = std::mem::take(&mut self.buffers);
+        let mut belt_items = std::mem::take(&mut self.belt_items);
+        let node_settings = std::mem::take(&mut self.node_settings);
+        // Unload first, to make room for what's loaded
+        for side in [BeltSide::Input, BeltSide::Output] {
+            for (id, machine) in self.machines_mut() {
+                let Some(buffers) = buffers.get_mut(id.0) else {
+                    continue;
+                };
+                // The belt at each node, if any, and how fast it is
+                let nodes = match side {
+                    BeltSide::Input => (machine.belt_inputs().into_iter())
+                        .map(|node| belts.iter().find(|&&(_, _, dst, _)| dst == node))
+                        .collect::<ArrayVec<_, 8>>(),
+                    BeltSide::Output => (machine.belt_outputs().into_iter())
+                        .map(|node| belts.iter().find(|&&(_, src, _, _)| src == node))
+                        .collect(),
+                }
+                .into_iter()
+                .map(|belt| belt.map(|&(entity, _, _, speed)| (entity, speed)))
+                .collect::<ArrayVec<_, 8>>();
+                let settings = (0..nodes.len())
+                    .map(|idx| settings_in(node_settings.get(id.0), side, idx))
+                    .collect::<ArrayVec<_, 8>>();
+                move_belt_items(machine, side, buffers, &mut belt_items, &nodes, &settings);
+            }
+        }
+        self.buffers = buffers;
+        self.belt_items = belt_items;
+        self.node_settings = node_settings;
+    }
+
+    /// Move `dt` seconds' worth of fluid through each pipe
+    ///
+    /// Fluid flows from `a` to `b`, except between two [`Tank`]s, where it flows from the fuller
+    /// one into the emptier one until they're about level. Pipes and machines only take fluid
+    /// that mixes with what they already hold, and once one is full, the pipes into it back up.
+    /// Pipes out of a machine's [outlets](Machine::outlet_mut) draw from those instead of its
+    /// buffers.
+    fn move_fluids(&mut self, dt: f32) {
+        let amount = Q32_32::from_f32(Pipe::FLOW * dt);
+        let level = |id: MachineId| {
+            (self.buffers.get(id.0)).map_or(Q32_32::ZERO, |buffers| buffers.fluid.stored())
+        };
+        let routes = (self.pipes.iter())
+            .map(|(entity, pipe)| {
+                let b = self.connected_machine(|machine| machine.pipe_nodes().contains(&pipe.b));
+                let a = self.connected_machine(|machine| machine.pipe_nodes().contains(&pipe.a));
+                // The machine it's drawn from, with the node it leaves through
+                let (from, to) = match (a, b) {
+                    (Some(a), Some(b)) if self.tanks.contains(a.0) && self.tanks.contains(b.0) => {
+                        if level(a) > level(b) + amount {
+                            (Some((a, pipe.a)), Some(b))
+                        } else if level(b) > level(a) + amount {
+                            (Some((b, pipe.b)), Some(a))
+                        } else {
+                            (None, None)
+                        }
+                    }
+                    (a, b) => (a.map(|a| (a, pipe.a)), b),
+                };
+                (entity, from, to)
+            })
+            .collect::<Vec<_>>();
+        for (entity, from, to) in routes {
+            let Some(fluid) = self.pipe_fluids.get_mut(entity) else {
+                continue;
+            };
+            if let Some(to) = to.and_then(|id| self.buffers.get_mut(id.0)) {
+                transfer(fluid, &mut to.fluid, amount);
+            }
+            let Some((from, node)) = from else {
+                continue;
+            };
+            // Taken out so the machine can be borrowed alongside it
+            let mut fluid = std::mem::replace(fluid, FluidTank::new(Q32_32::ZERO));
+            if let Some(outlet) =
+                (self.machine_mut(from)).and_then(|machine| machine.outlet_mut(node))
+            {
+                transfer(outlet, &mut fluid, amount);
+            } else if let Some(from) = self.buffers.get_mut(from.0) {
+                transfer(&mut from.fluid, &mut fluid, amount);
+            }
+            if let Some(pipe_fluid) = self.pipe_fluids.get_mut(entity) {
+                *pipe_fluid = fluid;
+            }
+        }
+    }
+
+    /// The sounds machines in the factory are making that can be heard from `listener`
+    pub fn sound_emitters(&self, listener: PlayerVector3) -> impl Iterator<Item = Emitter> + '_ {
+        self.machines().filter_map(move |(_, machine)| {
+            let FactoryBounds { min, max } = machine.bounds();
+            let position = min
+                .to_player(&self.origin)
+                .plus(max.to_player(&self.origin))
+                .scale(PlayerCoord::from_f32(0.5));
+            if position.minus(listener).to_vec3().length() >= audio::MAX_DISTANCE {
+                return None;
+            }
+            Some(Emitter {
+                sound: machine.sound()?,
+                position,
+                volume: machine.status().activity(),
+            })
+        })
+    }
+
+    /// A lamp over every machine, as bright as the machine is busy
+    pub fn light_sources(&self) -> impl Iterator<Item = LightSource> + '_ {
+        self.machines().map(|(_, machine)| {
+            let FactoryBounds { min, max } = machine.bounds();
+            let top = min
+                .to_player(&self.origin)
+                .plus(max.to_player(&self.origin))
+                .scale(PlayerCoord::from_f32(0.5));
+            LightSource {
+                kind: LightKind::Point {
+                    position: PlayerVector3 {
+                        y: max.to_player(&self.origin).y + PlayerCoord::from_f32(0.5),
+                        ..top
+                    },
+                },
+                color: Color::ORANGE,
+                intensity: machine.status().activity(),
+            }
+        })
+    }
+
+    /// The player's line of sight in Factory coordinates
+    pub fn vision_ray(&self, player: &Player) -> Ray {
+        Ray {
+            position: player.eye_pos().minus(self.origin.to_player()).to_vec3(),
+            ..player.vision_ray()
+        }
+    }
+
+    /// Cast a ray and see what it hits
+    ///
+    /// Note: `ray` must be in Factory coordinates
+    pub fn get_ray_collision(&self, ray: Ray) -> Option<FactoryCollision<'_>> {
+        let RayCollision {
+            hit,
+            distance,
+            point,
+            normal,
+        } = get_ray_collision_plane(ray, Vector3::ZERO, Vector3::UP);
+
+        let ground = hit.then_some(FactoryCollision {
+            target: None,
+            id: None,
+            distance,
+            normal,
+            point,
+        });
+        let floor = (self.floors.iter())
+            .filter_map(|floor| {
+                let level = Vector3::UP * f32::from(floor.level());
+                // Floors can be seen from below too
+                let normal = Vector3::UP * -ray.direction.y.signum();
+                let collision = get_ray_collision_plane(ray, level, normal);
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    reason = "collisions are inside the factory, which is indexed by i16"
+                )]
+                let block = FactoryVector3::new(
+                    collision.point.x.floor() as i16,
+                    floor.level(),
+                    collision.point.z.floor() as i16,
+                );
+                (collision.hit && floor.covers(block)).then_some(FactoryCollision {
+                    target: None,
+                    id: None,
+                    distance: collision.distance,
+                    normal,
+                    point: collision.point,
+                })
+            })
+            .chain(ground)
+            .min_by(|a, b| a.distance.total_cmp(&b.distance));
+
+        let max_distance = floor.as_ref().map_or(f32::INFINITY, |floor| floor.distance);
+        self.index
+            .query_ray(ray, max_distance)
+            .and_then(|(id, collision)| {
+                Some(FactoryCollision {
+                    target: Some(self.machine(id)?),
+                    id: Some(id),
+                    distance: collision.distance,
+                    normal: collision.normal,
+                    point: collision.point,
+                })
+            })
+            .or(floor)
+    }
+
+    /// Draw every machine that might be visible through `frustum`
+    ///
+    /// Machines of the same type are drawn with a single instanced draw call.
+    fn draw_machines(
+        &self,
+        d: &mut dyn DynRaylibDraw3D,
         thread: &RaylibThread,
         resources: &Resources,
         player_pos: &PlayerVector3,
         origin: &RailVector3,
+        frustum: &Frustum,
     ) {
-        let reactor_model_transform = *resources.reactor.transform();
-        for reactor in &self.reactors {
-            let matrix = machine_matrix(player_pos, reactor.position, origin, reactor.rotation)
-                * reactor_model_transform;
-            d.draw_mesh(
-                *resources.reactor.meshes()[0],
-                *resources.reactor.materials()[0],
-                matrix,
+        let to_player_relative = |bounds: FactoryBounds| BoundingBox {
+            min: bounds.min.to_player_relative(player_pos, origin),
+            max: bounds.max.to_player_relative(player_pos, origin),
+        };
+        let is_visible = |position: FactoryVector3| {
+            let center = position.to_player_relative(player_pos, origin) + Vector3::splat(0.5);
+            frustum.intersects_sphere(center, 1.0)
+        };
+
+        let visible_machines = self
+            .machines()
+            .map(|(id, machine)| (id, machine as &dyn Machine))
+            .filter(|(_, machine)| frustum.intersects_box(&to_player_relative(machine.bounds())))
+            .collect::<Vec<_>>();
+
+        let mut instances = BTreeMap::<(AssetId, AssetId), Vec<Matrix>>::new();
+        for (id, model) in visible_machines
+            .iter()
+            .filter_map(|(id, machine)| Some((id, machine.model()?)))
+        {
+            let transform = machine_matrix(player_pos, model.position, origin, model.rotation);
+            if let Some(animator) = self.animators.get(id.0)
+                && draw_posed(d, resources, &model, animator, transform)
+            {
+                continue;
+            }
+            instances
+                .entry((model.model, model.material))
+                .or_default()
+                .push(transform);
+        }
+        draw_instanced(d, resources, instances);
+        self.draw_heat_glow(d, player_pos, origin, frustum);
+        self.draw_tank_fluids(d, player_pos, origin, frustum);
+        self.draw_drones(d, player_pos, origin, frustum);
+        {
+            let mut buf = DynRender(d);
+            let mut debug = draw3d::Renderer::new(
+                &mut buf,
+                *draw3d::RenderingOptions::new()
+                    .offset(FactoryVector3::ZERO.to_player_relative(player_pos, origin)),
             );
-            let bounds = reactor.bounds();
-            let bbox = BoundingBox {
-                min: bounds.min.to_player_relative(player_pos, origin),
-                max: bounds.max.to_player_relative(player_pos, origin),
-            };
-            d.draw_bounding_box(bbox, Color::MAGENTA);
+            // Can't fail when drawing directly to the screen
+            for belt in self.belts() {
+                _ = belt.draw(&mut debug);
+            }
+            for pipe in self.pipes() {
+                _ = pipe.draw(&mut debug);
+            }
+            _ = self.gas.draw(&mut debug);
         }
 
-        // todo: other machines
-
-        for belt_input in self.reactors.iter().flat_map(Machine::belt_inputs)
-        // todo: chain other machines
+        for belt_input in visible_machines
+            .iter()
+            .flat_map(|(_, machine)| machine.belt_inputs())
+            .filter(|node| is_visible(node.0.position))
         {
             belt_input.draw(d, thread, player_pos, origin);
         }
 
-        for belt_output in self.reactors.iter().flat_map(Machine::belt_outputs)
-        // todo: chain other machines
+        for belt_output in visible_machines
+            .iter()
+            .flat_map(|(_, machine)| machine.belt_outputs())
+            .filter(|node| is_visible(node.0.position))
         {
             belt_output.draw(d, thread, player_pos, origin);
         }
 
-        for pipe_node in self.reactors.iter().flat_map(Machine::pipe_nodes)
-        // todo: chain other machines
+        for pipe_node in visible_machines
+            .iter()
+            .flat_map(|(_, machine)| machine.pipe_nodes())
+            .filter(|node| is_visible(node.position))
         {
             pipe_node.draw(d, thread, player_pos, origin);
         }
     }
 
+    /// Draw a glow around every visible machine hot enough to have one, see [`Thermal::glow`]
+    fn draw_heat_glow(
+        &self,
+        d: &mut dyn DynRaylibDraw3D,
+        player_pos: &PlayerVector3,
+        origin: &RailVector3,
+        frustum: &Frustum,
+    ) {
+        /// How far the glow reaches past the machine
+        const SPREAD: f32 = 0.05;
+        for (id, machine) in self.machines() {
+            let Some(glow) = self.thermal(id).and_then(Thermal::glow) else {
+                continue;
+            };
+            let FactoryBounds { min, max } = machine.bounds();
+            let (min, max) = (
+                min.to_player_relative(player_pos, origin),
+                max.to_player_relative(player_pos, origin),
+            );
+            if frustum.intersects_box(&BoundingBox { min, max }) {
+                let size = max - min + Vector3::splat(2.0 * SPREAD);
+                d.draw_cube((min + max) * 0.5, size.x, size.y, size.z, glow);
+            }
+        }
+    }
+
+    /// Draw the items on every belt, see [`Belt::item_positions`]
+    fn draw_belt_items(
+        &self,
+        d: &mut dyn DynRaylibDraw3D,
+        resources: &Resources,
+        player: &Player,
+        frustum: &Frustum,
+    ) {
+        let offset = FactoryVector3::ZERO.to_player_relative(&player.position, &self.origin);
+        let mut instances = BTreeMap::<(AssetId, AssetId), Vec<Matrix>>::new();
+        for (entity, belt) in self.belts.iter() {
+            let Some(items) = self.belt_items.get(entity) else {
+                continue;
+            };
+            let visuals = (items.stacks().iter()).flat_map(|stack| {
+                let visual = resources.item_visuals.get(&stack.item);
+                std::iter::repeat_n(visual, stack.count as usize)
+            });
+            let positions = belt.item_positions(items.stored(), self.belt_timer);
+            for (visual, position) in visuals.zip(positions) {
+                let position = position + offset;
+                if !frustum.intersects_sphere(position, ItemVisual::SIZE) {
+                    continue;
+                }
+                match visual {
+                    ItemVisual::Mesh { model, material } => instances
+                        .entry((model, material))
+                        .or_default()
+                        .push(Matrix::translate(position.x, position.y, position.z)),
+                    ItemVisual::Billboard { texture, tint } => {
+                        if let Some(texture) = resources.texture(texture) {
+                            d.draw_billboard(
+                                player.camera,
+                                texture,
+                                position,
+                                ItemVisual::SIZE,
+                                tint,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        draw_instanced(d, resources, instances);
+    }
+
     fn draw_highlight(
         d: &mut dyn DynRaylibDraw3D,
         _thread: &RaylibThread,
@@ -495,7 +2965,7 @@ impl Factory {
             };
             bbox.min -= EXPAND;
             bbox.max += EXPAND;
-            d.draw_bounding_box(bbox, Color::YELLOW);
+            d.draw_bounding_box(bbox, palette().highlight);
         } else {
             #[allow(clippy::cast_possible_truncation, reason = "this is intentional")]
             let position_in_factory = FactoryVector3 {
@@ -528,21 +2998,72 @@ impl PlayerOverlap for Factory {
     fn local_floor(&self, player: &Player) -> Option<PlayerCoord> {
         let position_in_factory = player.position.to_factory(&self.origin).unwrap();
 
-        self.reactors
-            .iter()
-            .filter_map(|reactor| {
-                let bounds = reactor.bounds();
-                (bounds.contains(&position_in_factory)
-                            // Don't teleport up more than a meter
-                            && position_in_factory.y.abs_diff(bounds.max.y) <= 1)
-                    .then_some(bounds.max.y)
+        self.index
+            .query_aabb(&FactoryBounds {
+                min: position_in_factory,
+                max: position_in_factory,
             })
+            .into_iter()
+            .filter_map(|id| Some(self.index.get(&id)?.max.y))
+            .chain(
+                (self.floors.iter())
+                    .filter(|floor| floor.covers(position_in_factory))
+                    .map(|floor| floor.level()),
+            )
+            // Don't teleport up more than a meter
+            .filter(|y| position_in_factory.y.abs_diff(*y) <= 1)
             .max()
             .map(|y| FactoryVector3::new(0, y, 0).to_player(&self.origin).y)
     }
+
+    fn can_climb(&self, player: &Player) -> bool {
+        player
+            .position
+            .to_factory(&self.origin)
+            .is_ok_and(|pos| self.ladders.iter().any(|ladder| ladder.contains(pos)))
+    }
+
+    /// Machines in `area`, except ones low enough to step up onto (see [`Self::local_floor`])
+    fn obstacles(&self, area: &PlayerBounds) -> Vec<PlayerBounds> {
+        let (Ok(min), Ok(max)) = (
+            area.min.to_factory(&self.origin),
+            area.max.to_factory(&self.origin),
+        ) else {
+            return Vec::new();
+        };
+        // Blocks partly inside `area` count too
+        let area_in_factory = FactoryBounds {
+            min,
+            max: max + FactoryVector3::ONE,
+        };
+        let step_height = area.min.y.plus(PlayerCoord::ONE);
+        self.index
+            .query_aabb(&area_in_factory)
+            .into_iter()
+            .filter_map(|id| self.index.get(&id))
+            .map(|bounds| PlayerBounds {
+                min: bounds.min.to_player(&self.origin),
+                max: bounds.max.to_player(&self.origin),
+            })
+            .filter(|bounds| bounds.max.y > step_height)
+            .collect()
+    }
 }
 
 impl Region for Factory {
+    fn assets(&self) -> &'static [AssetId] {
+        &[
+            AssetId::Reactor,
+            AssetId::ReactorInstanced,
+            AssetId::ItemCrate,
+            AssetId::ItemCrateInstanced,
+            AssetId::ItemIcon,
+            AssetId::Ghost,
+            AssetId::ReactorAnimations,
+            AssetId::Decals,
+        ]
+    }
+
     fn draw(
         &self,
         d: &mut dyn DynRaylibDraw3D,
@@ -552,16 +3073,349 @@ impl Region for Factory {
     ) {
         let origin = &self.origin;
         let player_pos = &player.position;
-        let player_vision_ray = player.vision_ray();
-        let player_lookat = self.get_ray_collision(player_vision_ray);
+        let player_lookat = self.get_ray_collision(self.vision_ray(player));
 
-        GridVisualizer {
-            start_time: player.region_last_changed,
+        if let Some(grid) = &self.grid {
+            grid.draw(d, thread, resources, player_pos, self);
         }
-        .draw(d, thread, resources, player_pos, self);
         if let Some(player_lookat) = &player_lookat {
             Self::draw_highlight(d, thread, resources, player_pos, origin, player_lookat);
         }
-        self.draw_machines(d, thread, resources, player_pos, origin);
+        let frustum = player.view_frustum();
+        for floor in &self.floors {
+            floor.draw(d, player_pos, origin);
+        }
+        decal::draw_decals(
+            d,
+            resources,
+            &self.decals,
+            FactoryVector3::ZERO.to_player_relative(player_pos, origin),
+        );
+        self.draw_machines(d, thread, resources, player_pos, origin, &frustum);
+        self.draw_belt_items(d, resources, player, &frustum);
+        for ladder in &self.ladders {
+            let bounds = ladder.bounds();
+            let bbox = BoundingBox {
+                min: bounds.min.to_player_relative(player_pos, origin),
+                max: bounds.max.to_player_relative(player_pos, origin),
+            };
+            if frustum.intersects_box(&bbox) {
+                ladder.draw(d, thread, player_pos, origin);
+            }
+        }
+    }
+
+    fn raycast_from_eye(
+        &self,
+        player: &Player,
+        direction: Vector3,
+        max_distance: f32,
+    ) -> Option<f32> {
+        let ray = Ray {
+            direction,
+            ..self.vision_ray(player)
+        };
+        self.get_ray_collision(ray)
+            .map(|collision| collision.distance)
+            .filter(|&distance| distance <= max_distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chem::element::Element, region::rail::terrain::DepositSite};
+
+    #[test]
+    fn test_rotate_footprint() {
+        let size = MachineSize::new(2, 1, 3).unwrap();
+        let bounds = |min: (i16, i16), max: (i16, i16)| FactoryBounds {
+            min: FactoryVector3::new(min.0, 0, min.1),
+            max: FactoryVector3::new(max.0, 1, max.1),
+        };
+        for (rotation, expect) in [
+            (Cardinal2D::East, bounds((0, 0), (2, 3))),
+            (Cardinal2D::North, bounds((0, -2), (3, 0))),
+            (Cardinal2D::West, bounds((-2, -3), (0, 0))),
+            (Cardinal2D::South, bounds((-3, 0), (0, 2))),
+        ] {
+            assert_eq!(rotate_footprint(size, rotation), expect, "{rotation:?}");
+        }
+        let footprint = OrientedFootprint::new(size, Cardinal2D::North);
+        assert_eq!(
+            footprint.bounds_at(FactoryVector3::new(5, 2, 5)),
+            FactoryBounds {
+                min: FactoryVector3::new(5, 2, 3),
+                max: FactoryVector3::new(8, 3, 5),
+            }
+        );
+    }
+
+    #[test]
+    fn test_expand_and_floors() {
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(0, 0, 0),
+                max: FactoryVector3::new(10, 10, 10),
+            },
+        );
+        assert_eq!(factory.expand(Cardinal3D::East, 5), Ok(50));
+        assert_eq!(factory.expand(Cardinal3D::Up, 5), Ok(0));
+        assert_eq!(
+            factory.bounds.max,
+            FactoryVector3::new(15, 15, 10),
+            "only the expanded sides should move"
+        );
+        assert_eq!(
+            factory.expand(Cardinal3D::Down, 1),
+            Err(ExpandError::Underground)
+        );
+        assert_eq!(
+            factory.expand(Cardinal3D::West, u16::MAX),
+            Err(ExpandError::TooBig)
+        );
+
+        let upstairs = Floor::new(4, &factory.bounds);
+        assert!(factory.add_floor(upstairs));
+        assert!(!factory.add_floor(upstairs), "floors can't overlap");
+        assert!(!factory.add_floor(Floor::new(0, &factory.bounds)));
+        assert!(!factory.add_floor(Floor::new(20, &factory.bounds)));
+        assert_eq!(factory.level_at(FactoryVector3::new(3, 2, 3)), 0);
+        assert_eq!(factory.level_at(FactoryVector3::new(3, 6, 3)), 4);
+        assert_eq!(factory.level_at(FactoryVector3::new(30, 6, 3)), 0);
+    }
+
+    #[test]
+    fn test_belts_move_items() {
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-2, 0, -2),
+                max: FactoryVector3::new(10, 8, 10),
+            },
+        );
+        for x in [0, 4] {
+            factory.add_reactor(Reactor {
+                position: FactoryVector3::new(x, 0, 0),
+                rotation: Cardinal2D::East,
+            });
+        }
+        let src = factory.reactors()[0].belt_outputs()[0];
+        let dst = factory.reactors()[1].belt_inputs()[0];
+        let path = routing::find_route(
+            &factory,
+            src.0.position,
+            dst.0.position,
+            routing::RouteRules::BELT,
+        )
+        .unwrap();
+        factory.add_belt(Belt {
+            level: BeltLevel::Mk2,
+            src,
+            dst,
+            path,
+        });
+
+        let [src, dst] = [0, 1].map(|idx| factory.reactor_id(idx).unwrap());
+        let water = ItemStack::new("H2O".parse().unwrap(), 3);
+        assert_eq!(factory.buffers_mut(src).unwrap().output.insert(water), None);
+        factory.tick(
+            0.5,
+            1.0,
+            &mut Deposits::default(),
+            &mut RailNetwork::default(),
+        );
+        assert_eq!(
+            factory.belt_items.as_slice()[0].stored(),
+            0,
+            "belts move once a second"
+        );
+        factory.tick(
+            0.5,
+            1.0,
+            &mut Deposits::default(),
+            &mut RailNetwork::default(),
+        );
+        assert_eq!(factory.belt_items.as_slice()[0].stored(), 2);
+        factory.tick(
+            1.0,
+            1.0,
+            &mut Deposits::default(),
+            &mut RailNetwork::default(),
+        );
+        assert_eq!(factory.buffers(dst).unwrap().input.stored(), 2);
+        assert_eq!(factory.belt_items.as_slice()[0].stored(), 1);
+        let belt = &factory.belts()[0];
+        let positions = belt.item_positions(3, 0.0);
+        assert_eq!(positions.len(), 3);
+        let end = block_center(*belt.path.last().unwrap());
+        assert!(
+            (positions[0].distance(end) - 0.25).abs() < 1e-6,
+            "the first item in should be a quarter meter from the end"
+        );
+        assert!(positions[1].distance(end) > positions[0].distance(end));
+
+        let refund = factory.demolish(dst).unwrap();
+        let total = refund.iter().map(|stack| stack.count).sum::<u32>();
+        assert_eq!(total, 3, "what it and its belt held should come back");
+        assert!(factory.belts().is_empty(), "its belt should go with it");
+        assert_eq!(factory.machine(dst).map(|_| ()), None);
+        assert!(factory.machine(src).is_some());
+        assert_eq!(factory.demolish(dst), None, "already demolished");
+    }
+
+    #[test]
+    fn test_miner() {
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-2, 0, -2),
+                max: FactoryVector3::new(10, 8, 10),
+            },
+        );
+        let mut deposits = Deposits::default();
+        let deposit = deposits.find(DepositSite {
+            position: RailVector3::new(1, 0, 1),
+            element: Element::Fe,
+            richness: 1.0,
+        });
+        deposit.remaining = 101;
+        let miner = factory.add_miner(Miner::new(
+            FactoryVector3::new(0, 0, 0),
+            Cardinal2D::East,
+            RailVector3::new(1, 0, 1),
+        ));
+        assert_eq!(factory.machine(miner).unwrap().name(), "Miner");
+
+        factory.tick(1.0, 1.0, &mut deposits, &mut RailNetwork::default());
+        assert_eq!(
+            factory.machine(miner).unwrap().status(),
+            MachineStatus::Working { progress: 0.5 }
+        );
+        factory.tick(1.0, 1.0, &mut deposits, &mut RailNetwork::default());
+        let output = &factory.buffers(miner).unwrap().output;
+        assert_eq!(
+            output.stacks(),
+            [ItemStack::new("Fe2O3".parse().unwrap(), 1)]
+        );
+
+        factory.tick(400.0, 1.0, &mut deposits, &mut RailNetwork::default());
+        assert_eq!(factory.buffers(miner).unwrap().output.stored(), 100);
+        assert_eq!(
+            factory.machine(miner).unwrap().status(),
+            MachineStatus::Blocked
+        );
+        assert_eq!(
+            deposits.get(RailVector3::new(1, 0, 1)).unwrap().remaining,
+            1,
+            "ore with nowhere to go stays in the ground"
+        );
+
+        _ = factory.buffers_mut(miner).unwrap().output.extract(100);
+        factory.tick(10.0, 1.0, &mut deposits, &mut RailNetwork::default());
+        assert_eq!(factory.buffers(miner).unwrap().output.stored(), 1);
+        assert_eq!(
+            factory.machine(miner).unwrap().status(),
+            MachineStatus::Idle
+        );
+    }
+
+    #[test]
+    fn test_splitter_order() {
+        let order = |mode, open: [bool; 3], count| {
+            let mut splitter = Splitter::new(FactoryVector3::default(), Cardinal2D::East, mode);
+            (0..count)
+                .map(|_| {
+                    let idx = splitter.next_belt(BeltSide::Output, &open).unwrap();
+                    splitter.moved_item(BeltSide::Output, idx);
+                    idx
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            order(SplitMode::RoundRobin, [true; 3], 6),
+            [0, 1, 2, 0, 1, 2]
+        );
+        assert_eq!(
+            order(SplitMode::RoundRobin, [true, false, true], 4),
+            [0, 2, 0, 2]
+        );
+        assert_eq!(
+            order(SplitMode::Ratio([2, 1, 0]), [true; 3], 6),
+            [0, 0, 1, 0, 0, 1]
+        );
+        assert_eq!(
+            order(SplitMode::Ratio([1, 3, 1]), [true; 3], 10),
+            [1, 1, 0, 1, 2, 1, 1, 0, 1, 2]
+        );
+        assert_eq!(
+            order(SplitMode::Ratio([2, 1, 0]), [false, true, true], 2),
+            [1, 1],
+            "closed outputs are skipped"
+        );
+    }
+
+    #[test]
+    fn test_splitter_and_merger() {
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-10, 0, -10),
+                max: FactoryVector3::new(10, 8, 10),
+            },
+        );
+        let splitter = factory.add_splitter(Splitter::new(
+            FactoryVector3::new(0, 0, 0),
+            Cardinal2D::East,
+            SplitMode::RoundRobin,
+        ));
+        let merger =
+            factory.add_merger(Merger::new(FactoryVector3::new(0, 0, 6), Cardinal2D::East));
+        let outputs = factory.splitters()[0].belt_outputs();
+        let inputs = factory.mergers()[0].belt_inputs();
+        for (src, dst) in [(outputs[0], inputs[0]), (outputs[1], inputs[2])] {
+            let path = routing::find_route(
+                &factory,
+                src.0.position,
+                dst.0.position,
+                routing::RouteRules::BELT,
+            )
+            .unwrap();
+            factory.add_belt(Belt {
+                level: BeltLevel::Mk4,
+                src,
+                dst,
+                path,
+            });
+        }
+
+        let water = ItemStack::new("H2O".parse().unwrap(), 6);
+        _ = factory.buffers_mut(splitter).unwrap().input.insert(water);
+        factory.tick(
+            1.0,
+            1.0,
+            &mut Deposits::default(),
+            &mut RailNetwork::default(),
+        );
+        let on_belts = factory.belt_items.as_slice().iter().map(ItemSlots::stored);
+        assert_eq!(
+            on_belts.collect::<Vec<_>>(),
+            [3, 3],
+            "round robin shares evenly, though the first belt could take all of them"
+        );
+        factory.tick(
+            1.0,
+            1.0,
+            &mut Deposits::default(),
+            &mut RailNetwork::default(),
+        );
+        factory.tick(
+            1.0,
+            1.0,
+            &mut Deposits::default(),
+            &mut RailNetwork::default(),
+        );
+        assert_eq!(factory.buffers(merger).unwrap().output.stored(), 6);
     }
 }
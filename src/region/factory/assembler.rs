@@ -0,0 +1,226 @@
+//! Machines that put parts together into bigger ones
+//!
+//! An assembler runs whichever [`Recipe`] it's set to, built in or from a plugin, the same way
+//! plugin machines run theirs. Its input and output can each be on any side, in the middle of it.
+
+use super::{
+    BeltInputNode, BeltNode, BeltOutputNode, Clearance, Machine, MachineBuffers, MachineKind,
+    MachineSize, MachineStatus, OrientedFootprint, Side, TickContext, recipe::Recipe,
+};
+use crate::{
+    chem::units::Joule,
+    container::{FluidTank, ItemSlots},
+    math::{
+        bounds::{Bounds, FactoryBounds},
+        coords::FactoryVector3,
+    },
+    ordinals::Cardinal2D,
+};
+use arrayvec::ArrayVec;
+use fixed_point::Q32_32;
+
+/// Runs a recipe with any number of ingredients, taking them in on one side and putting the
+/// products out of another
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assembler {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    recipe: Option<Recipe>,
+    /// Where items come in
+    input: Side,
+    /// Where products go out, never the same as [`Self::input`]
+    output: Side,
+    /// Toward the end of the current run of the recipe, in `[0, 1]`
+    progress: f32,
+    status: MachineStatus,
+}
+
+impl const Clearance for Assembler {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        Self::SIZE
+    }
+}
+
+impl Bounds<FactoryVector3> for Assembler {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl Assembler {
+    // SAFETY: 3 and 2 are not zero
+    pub const SIZE: MachineSize = unsafe { MachineSize::new_unchecked(3, 2, 3) };
+
+    /// The middle block of the bottom, when the assembler faces east
+    const MIDDLE: FactoryVector3 = FactoryVector3::new(1, 0, 1);
+
+    /// An assembler with no recipe, taking items in at the back and putting them out the front
+    pub const fn new(position: FactoryVector3, rotation: Cardinal2D) -> Self {
+        Self {
+            position,
+            rotation,
+            recipe: None,
+            input: Side::Back,
+            output: Side::Front,
+            progress: 0.0,
+            status: MachineStatus::Idle,
+        }
+    }
+
+    #[inline]
+    pub const fn recipe(&self) -> Option<&Recipe> {
+        self.recipe.as_ref()
+    }
+
+    /// Run `recipe` from now on, giving up on the current run
+    ///
+    /// Whatever was taken out of the input for the current run is lost.
+    pub fn set_recipe(&mut self, recipe: Option<Recipe>) {
+        self.recipe = recipe;
+        self.progress = 0.0;
+        self.status = MachineStatus::Idle;
+    }
+
+    /// Which sides items come in and go out
+    #[inline]
+    pub const fn sides(&self) -> (Side, Side) {
+        (self.input, self.output)
+    }
+
+    /// Take items in on the `input` side and put them out of the `output` side
+    ///
+    /// Returns false, changing nothing, if they're the same side.
+    pub fn set_sides(&mut self, input: Side, output: Side) -> bool {
+        if input == output {
+            return false;
+        }
+        self.input = input;
+        self.output = output;
+        true
+    }
+
+    /// The block at `offset` from the assembler's position when it faces east, rotated with the
+    /// assembler
+    const fn block(&self, offset: FactoryVector3) -> FactoryVector3 {
+        self.position.plus(self.rotation.rotate_block(offset))
+    }
+}
+
+impl Machine for Assembler {
+    fn name(&self) -> String {
+        MachineKind::Assembler.name()
+    }
+
+    fn status(&self) -> MachineStatus {
+        self.status
+    }
+
+    fn tick(&mut self, dt: f32, ctx: &mut TickContext<'_>) {
+        if let Some(recipe) = &self.recipe {
+            recipe.run(dt, ctx, &mut self.progress, &mut self.status);
+        }
+    }
+
+    /// Whatever its recipe releases
+    fn heat(&self) -> Joule {
+        match (self.status, &self.recipe) {
+            (MachineStatus::Working { .. }, Some(recipe)) => recipe.heat_rate(),
+            _ => Joule::ZERO,
+        }
+    }
+
+    /// In the middle of the input side, heading in
+    fn belt_inputs(&self) -> ArrayVec<BeltInputNode, 8> {
+        let mut arr = ArrayVec::new();
+        arr.push(BeltInputNode(BeltNode {
+            position: self.block(Self::MIDDLE.plus(self.input.offset())),
+            rotation: (self.rotation.plus(self.input.turn()))
+                .plus(Cardinal2D::West)
+                .as_ordinal(),
+        }));
+        arr
+    }
+
+    /// Just past the middle of the output side, heading out
+    fn belt_outputs(&self) -> ArrayVec<BeltOutputNode, 8> {
+        let out = self.output.offset();
+        let mut arr = ArrayVec::new();
+        arr.push(BeltOutputNode(BeltNode {
+            position: self.block(Self::MIDDLE.plus(out).plus(out)),
+            rotation: self.rotation.plus(self.output.turn()).as_ordinal(),
+        }));
+        arr
+    }
+
+    /// Room for a handful of different parts, and no fluid
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(6, 100),
+            output: ItemSlots::new(2, 100),
+            fluid: FluidTank::new(Q32_32::from_i32(0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        container::{Container, ItemStack},
+        math::coords::RailVector3,
+        region::{
+            factory::{Factory, recipe},
+            rail::{deposit::Deposits, network::RailNetwork},
+        },
+    };
+
+    #[test]
+    fn test_assembler() {
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-8, 0, 0),
+                max: FactoryVector3::new(8, 8, 10),
+            },
+        );
+        let mut assembler = Assembler::new(FactoryVector3::new(0, 0, 4), Cardinal2D::East);
+        assembler.set_recipe(recipe::find("Reactor kit").cloned());
+        assert!(!assembler.set_sides(Side::Left, Side::Left));
+        assert!(assembler.set_sides(Side::Left, Side::Right));
+        let input = assembler.belt_inputs()[0].0.position;
+        let output = assembler.belt_outputs()[0].0.position;
+        assert_eq!(input, FactoryVector3::new(2, 0, 5), "inside, on the left");
+        assert_eq!(output, FactoryVector3::new(-1, 0, 5), "just past the right");
+
+        let id = factory.add_assembler(assembler);
+        let buffer = &mut factory.buffers_mut(id).unwrap().input;
+        buffer.insert(ItemStack::new("Fe".parse().unwrap(), 8));
+        buffer.insert(ItemStack::new("Cu2Si".parse().unwrap(), 1));
+        let tick = |factory: &mut Factory| {
+            factory.tick(
+                10.0,
+                1.0,
+                &mut Deposits::default(),
+                &mut RailNetwork::default(),
+            );
+        };
+        tick(&mut factory);
+        assert_eq!(
+            factory.machine(id).unwrap().status(),
+            MachineStatus::Idle,
+            "one board short"
+        );
+        let buffer = &mut factory.buffers_mut(id).unwrap().input;
+        buffer.insert(ItemStack::new("Cu2Si".parse().unwrap(), 1));
+        tick(&mut factory);
+        let buffers = factory.buffers(id).unwrap();
+        assert_eq!(buffers.input.stored(), 0);
+        assert_eq!(
+            buffers.output.stacks(),
+            [ItemStack::new("Fe8Cu4Si2".parse().unwrap(), 1)]
+        );
+    }
+}
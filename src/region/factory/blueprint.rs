@@ -0,0 +1,836 @@
+//! Copying part of a factory to build again somewhere else
+//!
+//! Blueprints are shared as text, one item per line. Positions are written `x,y,z` relative to
+//! the minimum corner of the copied area, directions as their index in [`Ordinal2D::ALL`] or
+//! [`Ordinal3D::ALL`], and belt levels as their number.
+//!
+//! ```text
+//! blueprint 4,2,4
+//! reactor 0,0,0 0
+//! ladder 3,0,0 4 2
+//! belt 1 0,0,3 0 0,0,0 0 0,0,3 1,0,3 ...
+//! pipe 2,0,0 5 2,0,3 5 2,0,0 2,0,1 ...
+//! ```
+
+use super::{
+    Belt, BeltInputNode, BeltLevel, BeltNode, BeltOutputNode, Factory, Ladder, Pipe, PipeNode,
+    Reactor, history::Change,
+};
+use crate::{
+    math::{
+        bounds::{Bounds, FactoryBounds, SpacialBounds},
+        coords::{FactoryVector3, VectorConstants},
+    },
+    ordinals::{Cardinal2D, Ordinal2D, Ordinal3D},
+    research::Unlock,
+};
+use std::{num::NonZeroU8, str::FromStr};
+
+/// The sizes a blueprint can be read with, leaving room for the tallest ladder on top without
+/// overflowing
+const SIZES: FactoryBounds = FactoryBounds {
+    min: FactoryVector3::ZERO,
+    max: FactoryVector3::new(i16::MAX - 256, i16::MAX - 256, i16::MAX - 256),
+};
+
+/// Machines, belts, and pipes copied out of a factory, relative to the corner of the area they
+/// were copied from
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Blueprint {
+    /// The size of the copied area
+    pub size: FactoryVector3,
+    pub reactors: Vec<Reactor>,
+    pub ladders: Vec<Ladder>,
+    pub belts: Vec<Belt>,
+    pub pipes: Vec<Pipe>,
+}
+
+/// Why a [`Blueprint`] couldn't be built
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StampError {
+    /// Part of the blueprint would be outside of the factory
+    OutOfBounds,
+    /// The space each part of the blueprint would take up that something is already in
+    Collisions(Vec<FactoryBounds>),
+    /// Part of the blueprint hasn't been researched yet
+    Locked(Unlock),
+    /// The blueprint has reactors and is mirrored
+    ///
+    /// A mirrored reactor would need its inputs and outputs on the other side, and no turn
+    /// does that.
+    Unmirrorable,
+}
+
+impl std::fmt::Display for StampError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfBounds => f.write_str("blueprint doesn't fit in the factory"),
+            Self::Collisions(collisions) => {
+                write!(
+                    f,
+                    "{} parts of the blueprint are in the way",
+                    collisions.len()
+                )
+            }
+            Self::Locked(unlock) => write!(f, "not researched yet: {unlock}"),
+            Self::Unmirrorable => f.write_str("reactors can't be mirrored"),
+        }
+    }
+}
+
+impl std::error::Error for StampError {}
+
+/// Which positions a mirrored [`Blueprint`] swaps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorAxis {
+    /// East and west
+    X,
+    /// North and south
+    Z,
+}
+
+/// How a [`Blueprint`] is flipped and turned when it's built
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Orientation {
+    /// Applied before turning
+    pub mirror: Option<MirrorAxis>,
+    pub rotation: Cardinal2D,
+}
+
+impl From<Cardinal2D> for Orientation {
+    #[inline]
+    fn from(rotation: Cardinal2D) -> Self {
+        Self {
+            mirror: None,
+            rotation,
+        }
+    }
+}
+
+/// Moves copied positions to where they're being built, see [`Blueprint::placed`]
+#[derive(Debug, Clone, Copy)]
+struct Placement {
+    /// Flips positions within the blueprint's area before they're turned
+    mirror: Option<MirrorAxis>,
+    /// The size of the blueprint's area before it's turned
+    size: FactoryVector3,
+    rotation: Cardinal2D,
+    /// Where the rotated corner of the blueprint's area ends up
+    offset: FactoryVector3,
+}
+
+impl Placement {
+    /// Where the corner at `point` is after mirroring, before turning
+    const fn flip_point(self, point: FactoryVector3) -> FactoryVector3 {
+        let FactoryVector3 { x, y, z } = point;
+        match self.mirror {
+            None => point,
+            Some(MirrorAxis::X) => FactoryVector3::new(self.size.x - x, y, z),
+            Some(MirrorAxis::Z) => FactoryVector3::new(x, y, self.size.z - z),
+        }
+    }
+
+    /// Where the corner at `point` goes
+    const fn point(self, point: FactoryVector3) -> FactoryVector3 {
+        self.offset
+            .plus(self.rotation.rotate_vector(self.flip_point(point)))
+    }
+
+    /// Where the block at `position` goes
+    const fn block(self, position: FactoryVector3) -> FactoryVector3 {
+        let FactoryVector3 { x, y, z } = position;
+        // The far side of a flipped block is its new minimum corner
+        let flipped = match self.mirror {
+            None => position,
+            Some(MirrorAxis::X) => FactoryVector3::new(self.size.x - 1 - x, y, z),
+            Some(MirrorAxis::Z) => FactoryVector3::new(x, y, self.size.z - 1 - z),
+        };
+        self.offset.plus(self.rotation.rotate_block(flipped))
+    }
+
+    const fn cardinal(self, rotation: Cardinal2D) -> Cardinal2D {
+        let flipped = match self.mirror {
+            None => rotation,
+            Some(MirrorAxis::X) => rotation.flip_x(),
+            Some(MirrorAxis::Z) => rotation.flip_z(),
+        };
+        flipped.compose(self.rotation)
+    }
+
+    const fn belt_node(self, node: BeltNode) -> BeltNode {
+        let rotation = match self.mirror {
+            None => node.rotation,
+            Some(MirrorAxis::X) => node.rotation.flip_x(),
+            Some(MirrorAxis::Z) => node.rotation.flip_z(),
+        };
+        BeltNode {
+            position: self.block(node.position),
+            rotation: rotation.compose(self.rotation.as_ordinal()),
+        }
+    }
+
+    const fn pipe_node(self, node: PipeNode) -> PipeNode {
+        let rotation = match self.mirror {
+            None => node.rotation,
+            Some(MirrorAxis::X) => node.rotation.flip_x(),
+            Some(MirrorAxis::Z) => node.rotation.flip_z(),
+        };
+        PipeNode {
+            position: self.block(node.position),
+            rotation: rotation.rotate_y(self.rotation),
+        }
+    }
+
+    /// Only the space a reactor takes up is mirrored, since it can't be built mirrored (see
+    /// [`StampError::Unmirrorable`]) but should still be shown where it would go
+    fn reactor(self, reactor: &Reactor) -> Reactor {
+        let bounds = reactor.bounds();
+        let (a, b) = (self.flip_point(bounds.min), self.flip_point(bounds.max));
+        let position = reactor.position + (a.min(b) - bounds.min);
+        Reactor {
+            position: self.offset + self.rotation.rotate_vector(position),
+            rotation: reactor.rotation.compose(self.rotation),
+        }
+    }
+
+    fn path(self, path: &[FactoryVector3]) -> Vec<FactoryVector3> {
+        path.iter().map(|&position| self.block(position)).collect()
+    }
+}
+
+impl Blueprint {
+    /// Copy everything entirely inside `area` of `factory`
+    ///
+    /// Like machines, `area` counts its minimum corner but not its maximum.
+    #[must_use]
+    pub fn capture(factory: &Factory, area: &FactoryBounds) -> Self {
+        let fits = |bounds: FactoryBounds| area.union(&bounds) == *area;
+        let fits_path = |path: &[FactoryVector3]| {
+            super::routing::route_cells(path).all(|cell| {
+                fits(FactoryBounds {
+                    min: cell,
+                    max: cell + FactoryVector3::ONE,
+                })
+            })
+        };
+        let corner = area.min;
+        let relative = |position: FactoryVector3| position - corner;
+        let path = |path: &[FactoryVector3]| path.iter().copied().map(relative).collect();
+        Self {
+            size: area.size(),
+            reactors: (factory.reactors().iter())
+                .filter(|reactor| fits(reactor.bounds()))
+                .map(|reactor| Reactor {
+                    position: relative(reactor.position),
+                    ..reactor.clone()
+                })
+                .collect(),
+            ladders: (factory.ladders.iter())
+                .filter(|ladder| fits(ladder.bounds()))
+                .map(|ladder| Ladder {
+                    position: relative(ladder.position),
+                    ..*ladder
+                })
+                .collect(),
+            belts: (factory.belts().iter())
+                .filter(|belt| fits_path(&belt.path))
+                .map(|belt| {
+                    let mut belt = belt.clone();
+                    belt.src.0.position = relative(belt.src.0.position);
+                    belt.dst.0.position = relative(belt.dst.0.position);
+                    belt.path = path(&belt.path);
+                    belt
+                })
+                .collect(),
+            pipes: (factory.pipes().iter())
+                .filter(|pipe| fits_path(&pipe.path))
+                .map(|pipe| Pipe {
+                    a: PipeNode {
+                        position: relative(pipe.a.position),
+                        ..pipe.a
+                    },
+                    b: PipeNode {
+                        position: relative(pipe.b.position),
+                        ..pipe.b
+                    },
+                    path: path(&pipe.path),
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether there's nothing in the blueprint
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.reactors.is_empty()
+            && self.ladders.is_empty()
+            && self.belts.is_empty()
+            && self.pipes.is_empty()
+    }
+
+    /// The area the blueprint covers when built with its minimum corner at `at` in `orientation`
+    #[must_use]
+    pub const fn area(&self, at: FactoryVector3, orientation: Orientation) -> FactoryBounds {
+        // Mirroring keeps everything inside the same area
+        let rotated = orientation.rotation.rotate_vector(self.size);
+        let size = rotated.max(FactoryVector3::ZERO.minus(rotated));
+        FactoryBounds {
+            min: at,
+            max: at.plus(size),
+        }
+    }
+
+    /// The blueprint in factory coordinates, flipped and turned by `orientation` with the
+    /// minimum corner of its area at `at`
+    #[must_use]
+    pub fn placed(&self, at: FactoryVector3, orientation: Orientation) -> Self {
+        let Orientation { mirror, rotation } = orientation;
+        let rotated = rotation.rotate_vector(self.size);
+        let place = Placement {
+            mirror,
+            size: self.size,
+            rotation,
+            // Rotating can move the corner to negative coordinates, so move it back to `at`
+            offset: at - rotated.min(FactoryVector3::ZERO),
+        };
+        Self {
+            size: self.area(at, orientation).size(),
+            reactors: (self.reactors.iter())
+                .map(|reactor| place.reactor(reactor))
+                .collect(),
+            ladders: (self.ladders.iter())
+                .map(|ladder| Ladder {
+                    position: place.block(ladder.position),
+                    facing: place.cardinal(ladder.facing),
+                    ..*ladder
+                })
+                .collect(),
+            belts: (self.belts.iter())
+                .map(|belt| Belt {
+                    level: belt.level,
+                    src: BeltOutputNode(place.belt_node(belt.src.0)),
+                    dst: BeltInputNode(place.belt_node(belt.dst.0)),
+                    path: place.path(&belt.path),
+                })
+                .collect(),
+            pipes: (self.pipes.iter())
+                .map(|pipe| Pipe {
+                    a: place.pipe_node(pipe.a),
+                    b: place.pipe_node(pipe.b),
+                    path: place.path(&pipe.path),
+                })
+                .collect(),
+        }
+    }
+
+    /// Everything in the way of building the blueprint in `orientation` at `at`
+    ///
+    /// Returns the space each part of the blueprint would take up that something is already in.
+    #[must_use]
+    pub fn collisions(
+        &self,
+        factory: &Factory,
+        at: FactoryVector3,
+        orientation: Orientation,
+    ) -> Vec<FactoryBounds> {
+        let placed = self.placed(at, orientation);
+        let machines = (placed.reactors.iter().map(Bounds::bounds))
+            .chain(placed.ladders.iter().map(Bounds::bounds));
+        let conveyors = (placed.belts.iter().map(|belt| &belt.path))
+            .chain(placed.pipes.iter().map(|pipe| &pipe.path))
+            .flat_map(|path| super::routing::route_cells(path))
+            .map(|cell| FactoryBounds {
+                min: cell,
+                max: cell + FactoryVector3::ONE,
+            });
+        machines
+            .chain(conveyors)
+            .filter(|bounds| !factory.is_vacant(bounds))
+            .collect()
+    }
+
+    /// Whether the blueprint in `orientation` can be built at `at`
+    pub fn check(
+        &self,
+        factory: &Factory,
+        at: FactoryVector3,
+        orientation: Orientation,
+    ) -> Result<(), StampError> {
+        if orientation.mirror.is_some() && !self.reactors.is_empty() {
+            return Err(StampError::Unmirrorable);
+        }
+        let area = self.area(at, orientation);
+        if factory.bounds.union(&area) != factory.bounds {
+            return Err(StampError::OutOfBounds);
+        }
+        let collisions = self.collisions(factory, at, orientation);
+        if !collisions.is_empty() {
+            return Err(StampError::Collisions(collisions));
+        }
+        Ok(())
+    }
+
+    /// Build the blueprint in `orientation` at `at`, if nothing is in the way, returning what
+    /// was built
+    pub fn stamp(
+        &self,
+        factory: &mut Factory,
+        at: FactoryVector3,
+        orientation: Orientation,
+    ) -> Result<Change, StampError> {
+        self.check(factory, at, orientation)?;
+        let placed = self.placed(at, orientation);
+        let mut entities = Vec::new();
+        for reactor in placed.reactors {
+            entities.push(factory.add_reactor(reactor).0);
+        }
+        for &ladder in &placed.ladders {
+            factory.add_ladder(ladder);
+        }
+        for belt in placed.belts {
+            entities.push(factory.add_belt(belt));
+        }
+        for pipe in placed.pipes {
+            entities.push(factory.add_pipe(pipe));
+        }
+        Ok(Change::Built {
+            entities,
+            ladders: placed.ladders,
+        })
+    }
+}
+
+/// Writes a position as `x,y,z`
+pub(super) struct DisplayVector(pub(super) FactoryVector3);
+
+impl std::fmt::Display for DisplayVector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let FactoryVector3 { x, y, z } = self.0;
+        write!(f, "{x},{y},{z}")
+    }
+}
+
+impl std::fmt::Display for Blueprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ordinal = |rotation: Ordinal2D| rotation as u8;
+        let ordinal_3d = |rotation: Ordinal3D| rotation as u8;
+        let write_path = |f: &mut std::fmt::Formatter<'_>, path: &[FactoryVector3]| {
+            path.iter()
+                .try_for_each(|&position| write!(f, " {}", DisplayVector(position)))
+        };
+
+        writeln!(f, "blueprint {}", DisplayVector(self.size))?;
+        for reactor in &self.reactors {
+            writeln!(
+                f,
+                "reactor {} {}",
+                DisplayVector(reactor.position),
+                ordinal(reactor.rotation.as_ordinal()),
+            )?;
+        }
+        for ladder in &self.ladders {
+            writeln!(
+                f,
+                "ladder {} {} {}",
+                DisplayVector(ladder.position),
+                ladder.height,
+                ordinal(ladder.facing.as_ordinal()),
+            )?;
+        }
+        for belt in &self.belts {
+            write!(
+                f,
+                "belt {} {} {} {} {}",
+                belt.level.number(),
+                DisplayVector(belt.src.0.position),
+                ordinal(belt.src.0.rotation),
+                DisplayVector(belt.dst.0.position),
+                ordinal(belt.dst.0.rotation),
+            )?;
+            write_path(f, &belt.path)?;
+            writeln!(f)?;
+        }
+        for pipe in &self.pipes {
+            write!(
+                f,
+                "pipe {} {} {} {}",
+                DisplayVector(pipe.a.position),
+                ordinal_3d(pipe.a.rotation),
+                DisplayVector(pipe.b.position),
+                ordinal_3d(pipe.b.rotation),
+            )?;
+            write_path(f, &pipe.path)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why text couldn't be read as a [`Blueprint`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseBlueprintError {
+    /// The text didn't start with a `blueprint` line with a usable size
+    MissingHeader,
+    /// A line starting with something other than a kind of item
+    UnknownItem { line: usize },
+    /// An item with missing, extra, or invalid values, or that's outside the blueprint's size
+    InvalidItem { line: usize },
+}
+
+impl std::fmt::Display for ParseBlueprintError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader => f.write_str("not a blueprint"),
+            Self::UnknownItem { line } => write!(f, "unknown item on line {line}"),
+            Self::InvalidItem { line } => write!(f, "invalid item on line {line}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseBlueprintError {}
+
+/// The values on one line of a blueprint, after the kind of item
+pub(super) struct Values<'a>(pub(super) std::str::SplitWhitespace<'a>);
+
+impl Values<'_> {
+    pub(super) fn next<T: FromStr>(&mut self) -> Option<T> {
+        self.0.next()?.parse().ok()
+    }
+
+    pub(super) fn vector(&mut self) -> Option<FactoryVector3> {
+        let mut parts = self.0.next()?.split(',').map(str::parse);
+        let vector = FactoryVector3::new(
+            parts.next()?.ok()?,
+            parts.next()?.ok()?,
+            parts.next()?.ok()?,
+        );
+        parts.next().is_none().then_some(vector)
+    }
+
+    pub(super) fn ordinal(&mut self) -> Option<Ordinal2D> {
+        Ordinal2D::ALL.get(self.next::<usize>()?).copied()
+    }
+
+    pub(super) fn cardinal(&mut self) -> Option<Cardinal2D> {
+        self.ordinal()?.try_as_cardinal()
+    }
+
+    pub(super) fn ordinal_3d(&mut self) -> Option<Ordinal3D> {
+        Ordinal3D::ALL.get(self.next::<usize>()?).copied()
+    }
+
+    /// Every position left on the line
+    pub(super) fn path(&mut self) -> Option<Vec<FactoryVector3>> {
+        let mut path = Vec::new();
+        while self.0.clone().next().is_some() {
+            path.push(self.vector()?);
+        }
+        Some(path)
+    }
+
+    /// Only if there's nothing left on the line
+    pub(super) fn end<T>(mut self, value: T) -> Option<T> {
+        self.0.next().is_none().then_some(value)
+    }
+}
+
+impl FromStr for Blueprint {
+    type Err = ParseBlueprintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = (1..)
+            .zip(s.lines())
+            .filter(|(_, line)| !line.trim().is_empty());
+        let (_, header) = lines.next().ok_or(ParseBlueprintError::MissingHeader)?;
+        let mut header = header.split_whitespace();
+        if header.next() != Some("blueprint") {
+            return Err(ParseBlueprintError::MissingHeader);
+        }
+        let size = Values(header)
+            .vector()
+            .filter(|size| SIZES.contains(size))
+            .ok_or(ParseBlueprintError::MissingHeader)?;
+        let area = FactoryBounds {
+            min: FactoryVector3::ZERO,
+            max: size,
+        };
+        // Like `capture`, but positions are checked first, since the space taken up around one
+        // far outside could overflow
+        let fits = |bounds: FactoryBounds| area.union(&bounds) == area;
+        let fits_path = |path: &[FactoryVector3]| {
+            path.iter().all(|point| area.contains(point))
+                && super::routing::route_cells(path).all(|cell| {
+                    fits(FactoryBounds {
+                        min: cell,
+                        max: cell + FactoryVector3::ONE,
+                    })
+                })
+        };
+        let mut blueprint = Self {
+            size,
+            ..Self::default()
+        };
+
+        for (line, text) in lines {
+            let mut words = text.split_whitespace();
+            let kind = words.next().unwrap_or_default();
+            let mut values = Values(words);
+            let is_valid = match kind {
+                "reactor" => (|| {
+                    let reactor = Reactor {
+                        position: values.vector()?,
+                        rotation: values.cardinal()?,
+                    };
+                    (area.contains(&reactor.position) && fits(reactor.bounds())).then_some(())?;
+                    blueprint.reactors.push(values.end(reactor)?);
+                    Some(())
+                })(),
+                "ladder" => (|| {
+                    let ladder = Ladder {
+                        position: values.vector()?,
+                        height: values.next::<NonZeroU8>()?,
+                        facing: values.cardinal()?,
+                    };
+                    (area.contains(&ladder.position) && fits(ladder.bounds())).then_some(())?;
+                    blueprint.ladders.push(values.end(ladder)?);
+                    Some(())
+                })(),
+                "belt" => (|| {
+                    let level = BeltLevel::from_number(values.next()?)?;
+                    let src = BeltNode {
+                        position: values.vector()?,
+                        rotation: values.ordinal()?,
+                    };
+                    let dst = BeltNode {
+                        position: values.vector()?,
+                        rotation: values.ordinal()?,
+                    };
+                    let path = values.path()?;
+                    fits_path(&path).then_some(())?;
+                    blueprint.belts.push(Belt {
+                        level,
+                        src: BeltOutputNode(src),
+                        dst: BeltInputNode(dst),
+                        path,
+                    });
+                    Some(())
+                })(),
+                "pipe" => (|| {
+                    let a = PipeNode {
+                        position: values.vector()?,
+                        rotation: values.ordinal_3d()?,
+                    };
+                    let b = PipeNode {
+                        position: values.vector()?,
+                        rotation: values.ordinal_3d()?,
+                    };
+                    let path = values.path()?;
+                    fits_path(&path).then_some(())?;
+                    blueprint.pipes.push(Pipe { a, b, path });
+                    Some(())
+                })(),
+                _ => return Err(ParseBlueprintError::UnknownItem { line }),
+            };
+            if is_valid.is_none() {
+                return Err(ParseBlueprintError::InvalidItem { line });
+            }
+        }
+        Ok(blueprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::coords::RailVector3,
+        region::factory::{
+            Machine,
+            routing::{RouteRules, find_route},
+        },
+    };
+
+    #[test]
+    fn test_blueprint() {
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-20, 0, -20),
+                max: FactoryVector3::new(20, 10, 20),
+            },
+        );
+        let reactor = Reactor {
+            position: FactoryVector3::new(2, 0, 2),
+            rotation: Cardinal2D::East,
+        };
+        let src = reactor.belt_outputs()[0];
+        let dst = reactor.belt_inputs()[0];
+        factory.add_reactor(reactor.clone());
+        let path = find_route(&factory, src.0.position, dst.0.position, RouteRules::BELT).unwrap();
+        factory.add_belt(Belt {
+            level: BeltLevel::Mk2,
+            src,
+            dst,
+            path,
+        });
+
+        let area = FactoryBounds {
+            min: FactoryVector3::new(-5, 0, -5),
+            max: FactoryVector3::new(10, 5, 10),
+        };
+        let blueprint = Blueprint::capture(&factory, &area);
+        assert_eq!((blueprint.reactors.len(), blueprint.belts.len()), (1, 1));
+        assert_eq!(blueprint.reactors[0].position, FactoryVector3::new(7, 0, 7));
+        assert_eq!(blueprint.to_string().parse(), Ok(blueprint.clone()));
+        assert_eq!(
+            "blueprint 1,1,1\nreactor 0,0,0 1".parse::<Blueprint>(),
+            Err(ParseBlueprintError::InvalidItem { line: 2 })
+        );
+        assert_eq!(
+            "reactor 0,0,0 0".parse::<Blueprint>(),
+            Err(ParseBlueprintError::MissingHeader)
+        );
+
+        // Right on top of the original
+        assert!(matches!(
+            blueprint.stamp(&mut factory, area.min, Cardinal2D::East.into()),
+            Err(StampError::Collisions(collisions)) if !collisions.is_empty()
+        ));
+        let ladder = Blueprint {
+            size: FactoryVector3::new(1, 2, 1),
+            ladders: vec![Ladder {
+                position: FactoryVector3::ZERO,
+                height: NonZeroU8::new(2).unwrap(),
+                facing: Cardinal2D::East,
+            }],
+            ..Blueprint::default()
+        };
+        assert_eq!(
+            ladder
+                .collisions(&factory, reactor.position, Orientation::default())
+                .len(),
+            1,
+            "ladder inside the reactor"
+        );
+        assert_eq!(
+            blueprint.stamp(
+                &mut factory,
+                FactoryVector3::new(15, 0, 0),
+                Cardinal2D::East.into()
+            ),
+            Err(StampError::OutOfBounds)
+        );
+
+        let at = FactoryVector3::new(-20, 0, -20);
+        let north = Orientation::from(Cardinal2D::North);
+        let rotated = blueprint.placed(at, north);
+        assert_eq!(
+            blueprint.area(at, north),
+            FactoryBounds {
+                min: at,
+                max: at + FactoryVector3::new(15, 5, 15),
+            }
+        );
+        assert!(matches!(
+            blueprint.stamp(&mut factory, at, north),
+            Ok(Change::Built { entities, .. }) if entities.len() == 2
+        ));
+        assert_eq!(factory.reactors().len(), 2);
+        assert_eq!(factory.reactors()[1], rotated.reactors[0]);
+        // The belt still connects the rotated reactor to itself
+        let belt = &factory.belts()[1];
+        assert_eq!(belt.src, factory.reactors()[1].belt_outputs()[0]);
+        assert_eq!(belt.dst, factory.reactors()[1].belt_inputs()[0]);
+        assert_eq!(belt.path.first(), Some(&belt.src.0.position));
+    }
+
+    #[test]
+    fn test_parse_out_of_bounds() {
+        assert_eq!(
+            "blueprint 1,1,1\nladder 500,0,0 2 0".parse::<Blueprint>(),
+            Err(ParseBlueprintError::InvalidItem { line: 2 })
+        );
+        assert_eq!(
+            "blueprint 4,2,4\nladder 3,1,0 2 0".parse::<Blueprint>(),
+            Err(ParseBlueprintError::InvalidItem { line: 2 }),
+            "too tall"
+        );
+        assert_eq!(
+            "blueprint 4,2,4\nbelt 1 0,0,0 0 0,0,9 0 0,0,0 0,0,9".parse::<Blueprint>(),
+            Err(ParseBlueprintError::InvalidItem { line: 2 })
+        );
+        assert_eq!(
+            "blueprint 4,2,4\nreactor 32767,0,0 0".parse::<Blueprint>(),
+            Err(ParseBlueprintError::InvalidItem { line: 2 })
+        );
+        assert_eq!(
+            "blueprint 32767,1,1".parse::<Blueprint>(),
+            Err(ParseBlueprintError::MissingHeader)
+        );
+    }
+
+    #[test]
+    fn test_mirrored_blueprint() {
+        let factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-20, 0, -20),
+                max: FactoryVector3::new(20, 10, 20),
+            },
+        );
+        let mirrored = Orientation {
+            mirror: Some(MirrorAxis::X),
+            rotation: Cardinal2D::East,
+        };
+        let reactors = Blueprint {
+            size: FactoryVector3::new(2, 2, 3),
+            reactors: vec![Reactor {
+                position: FactoryVector3::ZERO,
+                rotation: Cardinal2D::East,
+            }],
+            ..Blueprint::default()
+        };
+        assert_eq!(
+            reactors.check(&factory, FactoryVector3::ZERO, mirrored),
+            Err(StampError::Unmirrorable)
+        );
+        assert_eq!(
+            reactors.placed(FactoryVector3::ZERO, mirrored).reactors[0].bounds(),
+            reactors.reactors[0].bounds(),
+            "mirrored in place, since it fills the whole area"
+        );
+
+        let ladders = Blueprint {
+            size: FactoryVector3::new(3, 2, 2),
+            ladders: vec![Ladder {
+                position: FactoryVector3::new(0, 0, 1),
+                height: NonZeroU8::new(2).unwrap(),
+                facing: Cardinal2D::East,
+            }],
+            ..Blueprint::default()
+        };
+        let at = FactoryVector3::new(0, 5, 0);
+        assert_eq!(
+            ladders.area(at, mirrored),
+            ladders.area(at, Orientation::default())
+        );
+        let placed = ladders.placed(at, mirrored);
+        assert_eq!(
+            placed.ladders[0].position,
+            at + FactoryVector3::new(2, 0, 1)
+        );
+        assert_eq!(placed.ladders[0].facing, Cardinal2D::West);
+        let placed = ladders.placed(
+            at,
+            Orientation {
+                mirror: Some(MirrorAxis::Z),
+                ..mirrored
+            },
+        );
+        assert_eq!(
+            placed.ladders[0].position,
+            at + FactoryVector3::new(0, 0, 0)
+        );
+        assert_eq!(placed.ladders[0].facing, Cardinal2D::East);
+    }
+}
@@ -0,0 +1,434 @@
+//! Signals carried by wires between machines and combinators
+//!
+//! [Sensors](Node::Sensor) turn something about a machine into a signal, combinators work out
+//! signals from the ones wired into them, and [`Control`]s drive machines with them. Every tick,
+//! each node works out its signal from what was on its wires at the end of the last one, so the
+//! order nodes were added in doesn't matter, and loops (like a latch holding itself on) settle the
+//! same way every time. A signal takes a tick to get through each combinator.
+
+use super::{BeltSide, Factory, MachineId, MachineStatus, script::Host};
+use crate::container::Item;
+
+/// What's on a wire
+pub type Signal = i64;
+
+/// Identifies a node in a [`Circuit`]
+///
+/// Shown to players as the order it was added in, starting from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+impl std::fmt::Display for NodeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::str::FromStr for NodeId {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+/// Something about a machine that a [sensor](Node::Sensor) reads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Port {
+    /// How many of the item, or of everything if [`None`], are in the buffer on that side
+    Count(BeltSide, Option<Item>),
+    /// Whole liters of fluid in its tank
+    Fluid,
+    /// Whole kelvin, for machines that heat up
+    Temperature,
+    /// 1 while it's working, otherwise 0
+    Working,
+}
+
+impl Port {
+    /// What the machine identified by `id` reports on this port, or 0 if it's gone
+    fn read(&self, host: &dyn Host, id: MachineId) -> Signal {
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "saturates, and machines don't hold anywhere near that much"
+        )]
+        let whole = |x: f64| x.round() as Signal;
+        match self {
+            Self::Count(side, item) => host.count(id, *side, item.as_ref()).map(Signal::from),
+            Self::Fluid => host.fluid(id).map(whole),
+            Self::Temperature => host.temperature(id).map(whole),
+            Self::Working => (host.status(id))
+                .map(|status| Signal::from(matches!(status, MachineStatus::Working { .. }))),
+        }
+        .unwrap_or(0)
+    }
+}
+
+/// How an [arithmetic combinator](Node::Arithmetic) combines its inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Arithmetic {
+    Add,
+    Sub,
+    Mul,
+    /// Rounds toward 0
+    Div,
+    Rem,
+}
+
+impl Arithmetic {
+    /// `a` and `b` combined, wrapping on overflow, and 0 when dividing by 0
+    pub const fn apply(self, a: Signal, b: Signal) -> Signal {
+        match self {
+            Self::Add => a.wrapping_add(b),
+            Self::Sub => a.wrapping_sub(b),
+            Self::Mul => a.wrapping_mul(b),
+            Self::Div | Self::Rem if b == 0 => 0,
+            Self::Div => a.wrapping_div(b),
+            Self::Rem => a.wrapping_rem(b),
+        }
+    }
+}
+
+/// How a [comparing combinator](Node::Compare) compares its inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compare {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Compare {
+    pub const fn apply(self, a: Signal, b: Signal) -> bool {
+        match self {
+            Self::Eq => a == b,
+            Self::Ne => a != b,
+            Self::Lt => a < b,
+            Self::Le => a <= b,
+            Self::Gt => a > b,
+            Self::Ge => a >= b,
+        }
+    }
+}
+
+/// Which of a combinator's inputs a wire goes into
+///
+/// Everything wired into the same input is added together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pin {
+    A,
+    B,
+}
+
+/// Something in a circuit that puts out a signal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    /// Reads a machine's port, or 0 once the machine's gone
+    Sensor { machine: MachineId, port: Port },
+    /// Always the same
+    Constant(Signal),
+    /// A and B combined
+    Arithmetic(Arithmetic),
+    /// 1 if A compares to B that way, otherwise 0
+    Compare(Compare),
+    /// 1 from when A isn't 0 until B isn't 0, and 0 after that until A isn't 0 again
+    ///
+    /// If both aren't 0 at once, B wins.
+    Latch,
+}
+
+impl Node {
+    /// Whether anything can be wired into it
+    pub const fn has_inputs(&self) -> bool {
+        matches!(self, Self::Arithmetic(_) | Self::Compare(_) | Self::Latch)
+    }
+}
+
+/// Carries one node's signal into another's input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Wire {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub pin: Pin,
+}
+
+/// What a [`Control`] does with its signal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Behavior {
+    /// Run the machine only while the signal isn't 0
+    Enable,
+    /// Set the station's cargo rule at `rule` to move only the item the signal counts to in
+    /// `items`, starting from 1, or anything while it's 0 or past the end
+    Filter { rule: usize, items: Vec<Item> },
+}
+
+/// Drives a machine with a node's signal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Control {
+    pub signal: NodeId,
+    pub machine: MachineId,
+    pub behavior: Behavior,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitError {
+    /// There's no such node in the circuit
+    UnknownNode(NodeId),
+    /// Sensors and constants can't be wired into
+    NoInputs(NodeId),
+}
+
+impl std::fmt::Display for CircuitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownNode(id) => write!(f, "there's no node {id}"),
+            Self::NoInputs(id) => write!(f, "nothing can be wired into node {id}"),
+        }
+    }
+}
+
+impl std::error::Error for CircuitError {}
+
+/// A factory's nodes, the wires between them, and what they control
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Circuit {
+    nodes: Vec<Node>,
+    wires: Vec<Wire>,
+    controls: Vec<Control>,
+    /// What each node put out at the end of the last tick
+    signals: Vec<Signal>,
+}
+
+impl Circuit {
+    pub const fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            wires: Vec::new(),
+            controls: Vec::new(),
+            signals: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    #[inline]
+    pub fn wires(&self) -> &[Wire] {
+        &self.wires
+    }
+
+    #[inline]
+    pub fn controls(&self) -> &[Control] {
+        &self.controls
+    }
+
+    /// What the node identified by `id` put out at the end of the last tick
+    pub fn signal(&self, id: NodeId) -> Option<Signal> {
+        self.signals.get(id.0).copied()
+    }
+
+    /// Add `node`, putting out 0 until the next tick
+    pub fn add(&mut self, node: Node) -> NodeId {
+        self.nodes.push(node);
+        self.signals.push(0);
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// Wire the output of `from` into the `pin` input of `to`
+    pub fn connect(&mut self, from: NodeId, to: NodeId, pin: Pin) -> Result<(), CircuitError> {
+        if from.0 >= self.nodes.len() {
+            return Err(CircuitError::UnknownNode(from));
+        }
+        let node = self.nodes.get(to.0).ok_or(CircuitError::UnknownNode(to))?;
+        if !node.has_inputs() {
+            return Err(CircuitError::NoInputs(to));
+        }
+        self.wires.push(Wire { from, to, pin });
+        Ok(())
+    }
+
+    /// Drive a machine with a node's signal, see [`Control`]
+    pub fn add_control(&mut self, control: Control) -> Result<(), CircuitError> {
+        if control.signal.0 >= self.nodes.len() {
+            return Err(CircuitError::UnknownNode(control.signal));
+        }
+        self.controls.push(control);
+        Ok(())
+    }
+
+    /// Point every sensor and control on the machine identified by `old` at `new` instead, once
+    /// it's been built again
+    pub fn replace_machine(&mut self, old: MachineId, new: MachineId) {
+        for node in &mut self.nodes {
+            if let Node::Sensor { machine, .. } = node
+                && *machine == old
+            {
+                *machine = new;
+            }
+        }
+        for control in &mut self.controls {
+            if control.machine == old {
+                control.machine = new;
+            }
+        }
+    }
+
+    /// Take out every node, wire, and control
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Everything wired into the `pin` input of `to`, as of the end of the last tick
+    fn input(&self, to: NodeId, pin: Pin) -> Signal {
+        (self.wires.iter())
+            .filter(|wire| wire.to == to && wire.pin == pin)
+            .filter_map(|wire| self.signal(wire.from))
+            .fold(0, Signal::wrapping_add)
+    }
+
+    /// Work out every node's signal from the ones at the end of the last tick, reading sensors
+    /// from `host`
+    pub fn tick(&mut self, host: &dyn Host) {
+        let signals = (self.nodes.iter().enumerate())
+            .map(|(idx, node)| {
+                let id = NodeId(idx);
+                let (a, b) = (self.input(id, Pin::A), self.input(id, Pin::B));
+                match node {
+                    Node::Sensor { machine, port } => port.read(host, *machine),
+                    &Node::Constant(n) => n,
+                    Node::Arithmetic(op) => op.apply(a, b),
+                    Node::Compare(op) => Signal::from(op.apply(a, b)),
+                    Node::Latch if b != 0 => 0,
+                    Node::Latch if a != 0 => 1,
+                    Node::Latch => self.signals[idx],
+                }
+            })
+            .collect();
+        self.signals = signals;
+    }
+}
+
+impl Factory {
+    /// Tick the circuit, then drive each machine it controls
+    ///
+    /// Controls of machines that are gone, or that aren't what they expect, do nothing.
+    pub(super) fn run_circuit(&mut self) {
+        // Taken out so sensors can look at the rest of the factory
+        let mut circuit = std::mem::take(&mut self.circuit);
+        circuit.tick(self);
+        for control in circuit.controls() {
+            let signal = circuit.signal(control.signal).unwrap_or(0);
+            match &control.behavior {
+                Behavior::Enable => self.set_enabled(control.machine, signal != 0),
+                Behavior::Filter { rule, items } => {
+                    if let Some(rule) = (self.stations.get_mut(control.machine.0))
+                        .and_then(|station| station.rules.get_mut(*rule))
+                    {
+                        rule.filter = (usize::try_from(signal).ok())
+                            .and_then(|n| n.checked_sub(1))
+                            .and_then(|idx| items.get(idx))
+                            .cloned();
+                    }
+                }
+            }
+        }
+        self.circuit = circuit;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        container::{Container, ItemStack},
+        math::{
+            bounds::FactoryBounds,
+            coords::{FactoryVector3, RailVector3},
+        },
+        ordinals::Cardinal2D,
+        region::factory::Reactor,
+    };
+
+    #[test]
+    fn test_circuit() {
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-8, 0, 0),
+                max: FactoryVector3::new(8, 8, 10),
+            },
+        );
+        let reactor = factory.add_reactor(Reactor {
+            position: FactoryVector3::new(0, 0, 4),
+            rotation: Cardinal2D::East,
+        });
+        // Stop the reactor once it has 5 or more of anything, until it's down to 0
+        let circuit = factory.circuit_mut();
+        let count = circuit.add(Node::Sensor {
+            machine: reactor,
+            port: Port::Count(BeltSide::Input, None),
+        });
+        let five = circuit.add(Node::Constant(5));
+        let zero = circuit.add(Node::Constant(0));
+        let full = circuit.add(Node::Compare(Compare::Ge));
+        let empty = circuit.add(Node::Compare(Compare::Le));
+        let stopped = circuit.add(Node::Latch);
+        let running = circuit.add(Node::Compare(Compare::Eq));
+        for (from, to, pin) in [
+            (count, full, Pin::A),
+            (five, full, Pin::B),
+            (count, empty, Pin::A),
+            (zero, empty, Pin::B),
+            (full, stopped, Pin::A),
+            (empty, stopped, Pin::B),
+            (stopped, running, Pin::A),
+            (zero, running, Pin::B),
+        ] {
+            circuit.connect(from, to, pin).unwrap();
+        }
+        assert_eq!(
+            circuit.connect(full, five, Pin::A),
+            Err(CircuitError::NoInputs(five))
+        );
+        (circuit.add_control(Control {
+            signal: running,
+            machine: reactor,
+            behavior: Behavior::Enable,
+        }))
+        .unwrap();
+
+        let item = ItemStack::new("H2O".parse().unwrap(), 5);
+        factory.buffers_mut(reactor).unwrap().input.insert(item);
+        // Sensor, compare, latch, compare
+        for _ in 0..3 {
+            factory.run_circuit();
+            assert!(factory.is_enabled(reactor));
+        }
+        factory.run_circuit();
+        assert!(!factory.is_enabled(reactor));
+
+        factory.buffers_mut(reactor).unwrap().input.take(0, 3);
+        for _ in 0..4 {
+            factory.run_circuit();
+        }
+        assert!(!factory.is_enabled(reactor), "latched until it's empty");
+        factory.buffers_mut(reactor).unwrap().input.take(0, 2);
+        for _ in 0..4 {
+            factory.run_circuit();
+        }
+        assert!(factory.is_enabled(reactor));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(Arithmetic::Div.apply(7, -2), -3);
+        assert_eq!(Arithmetic::Rem.apply(7, 0), 0);
+        assert_eq!(Arithmetic::Rem.apply(Signal::MIN, -1), 0);
+        assert_eq!(Arithmetic::Div.apply(Signal::MIN, -1), Signal::MIN);
+        assert_eq!(Arithmetic::Add.apply(Signal::MAX, 1), Signal::MIN);
+    }
+}
@@ -0,0 +1,234 @@
+//! Machines that run a player's [`Script`] every tick
+//!
+//! Controllers don't touch items. They look at the machines around them and turn them on and
+//! off or change how splitters share items, see [`Factory::run_controllers`]. A script that
+//! fails stops its controller until it's given a new one, and the error is shown in the console.
+
+use super::{
+    BeltSide, Clearance, Factory, Machine, MachineBuffers, MachineId, MachineKind, MachineSize,
+    MachineStatus, OrientedFootprint, SplitMode,
+    script::{Effect, Host, Script, ScriptError, Value},
+};
+use crate::{
+    chem::formula::Formula,
+    container::{Container, FluidTank, ItemSlots},
+    math::{
+        bounds::{Bounds, FactoryBounds, SpacialBounds},
+        coords::{FactoryVector3, VectorConstants},
+    },
+    ordinals::Cardinal2D,
+};
+use fixed_point::Q32_32;
+use std::collections::BTreeMap;
+
+/// Runs a [`Script`] every tick
+#[derive(Debug, Clone, PartialEq)]
+pub struct Controller {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    script: Script,
+    /// What the script remembers between ticks
+    variables: BTreeMap<String, Value>,
+    /// Why the script stopped, if it has
+    error: Option<ScriptError>,
+}
+
+impl const Clearance for Controller {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        Self::SIZE
+    }
+}
+
+impl Bounds<FactoryVector3> for Controller {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl Controller {
+    // SAFETY: 1 is not zero
+    pub const SIZE: MachineSize = unsafe { MachineSize::new_unchecked(1, 1, 1) };
+
+    pub const fn new(position: FactoryVector3, rotation: Cardinal2D, script: Script) -> Self {
+        Self {
+            position,
+            rotation,
+            script,
+            variables: BTreeMap::new(),
+            error: None,
+        }
+    }
+
+    #[inline]
+    pub const fn script(&self) -> &Script {
+        &self.script
+    }
+
+    /// Run `script` from now on, starting with nothing remembered
+    pub fn set_script(&mut self, script: Script) {
+        self.script = script;
+        self.variables.clear();
+        self.error = None;
+    }
+
+    /// Why the script stopped, if it has
+    #[inline]
+    pub const fn error(&self) -> Option<&ScriptError> {
+        self.error.as_ref()
+    }
+
+    /// Run the script once against `factory`, unless it's already stopped
+    ///
+    /// Returns the error if this is the run it stopped on.
+    pub(super) fn run(&mut self, factory: &Factory) -> Result<Vec<Effect>, ScriptError> {
+        if self.error.is_some() {
+            return Ok(Vec::new());
+        }
+        (self.script.run(factory, &mut self.variables))
+            .inspect_err(|e| self.error = Some(e.clone()))
+    }
+}
+
+impl Machine for Controller {
+    fn name(&self) -> String {
+        MachineKind::Controller.name()
+    }
+
+    /// Working while its script runs, and blocked once it's stopped
+    fn status(&self) -> MachineStatus {
+        if self.error.is_some() {
+            MachineStatus::Blocked
+        } else {
+            MachineStatus::Working { progress: 1.0 }
+        }
+    }
+
+    /// Nothing goes in or comes out
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(0, 0),
+            output: ItemSlots::new(0, 0),
+            fluid: FluidTank::new(Q32_32::from_i32(0)),
+        }
+    }
+}
+
+impl Host for Factory {
+    fn machine_at(&self, position: FactoryVector3) -> Option<MachineId> {
+        // Every machine is inside, and the block past the far edge of `i16` wouldn't fit
+        if !self.bounds.contains(&position) {
+            return None;
+        }
+        let block = FactoryBounds {
+            min: position,
+            max: position + FactoryVector3::ONE,
+        };
+        (self.index.query_aabb(&block).into_iter()).find(|id| {
+            (self.index.get(id))
+                .and_then(|bounds| bounds.intersection(&block))
+                .is_some_and(|shared| shared.volume() > 0)
+        })
+    }
+
+    fn count(&self, id: MachineId, side: BeltSide, item: Option<&Formula>) -> Option<u32> {
+        let buffers = self.buffers.get(id.0)?;
+        let slots = match side {
+            BeltSide::Input => &buffers.input,
+            BeltSide::Output => &buffers.output,
+        };
+        Some(match item {
+            None => slots.stored(),
+            Some(item) => (slots.stacks().iter())
+                .filter(|stack| stack.item == *item)
+                .fold(0, |total, stack| total.saturating_add(stack.count)),
+        })
+    }
+
+    fn fluid(&self, id: MachineId) -> Option<f64> {
+        let buffers = self.buffers.get(id.0)?;
+        Some(f64::from(buffers.fluid.stored().to_f32()))
+    }
+
+    fn temperature(&self, id: MachineId) -> Option<f64> {
+        self.thermals
+            .get(id.0)
+            .map(|thermal| thermal.temperature().get())
+    }
+
+    fn status(&self, id: MachineId) -> Option<MachineStatus> {
+        self.machine(id).map(Machine::status)
+    }
+
+    fn is_enabled(&self, id: MachineId) -> bool {
+        Factory::is_enabled(self, id)
+    }
+}
+
+impl Factory {
+    /// Run every controller's script once, and make the changes they ask for
+    ///
+    /// What scripts print, and why they stop, goes to the script log, see
+    /// [`Self::take_script_log`].
+    pub(super) fn run_controllers(&mut self) {
+        // Taken out so scripts can look at the rest of the factory
+        let mut controllers = std::mem::take(&mut self.controllers);
+        for (_, controller) in controllers.iter_mut() {
+            let position = controller.position;
+            match controller.run(self) {
+                Ok(effects) => {
+                    for effect in effects {
+                        self.apply_effect(effect, position);
+                    }
+                }
+                Err(e) => self.script_log.push(format!(
+                    "controller at ({}, {}, {}) stopped on {e}",
+                    position.x, position.y, position.z
+                )),
+            }
+        }
+        self.controllers = controllers;
+    }
+
+    /// Make the change a controller at `position` asked for
+    fn apply_effect(&mut self, effect: Effect, position: FactoryVector3) {
+        match effect {
+            Effect::Enable(id, enable) => self.set_enabled(id, enable),
+            Effect::Ratio(id, weights) => match self.splitters.get_mut(id.0) {
+                Some(splitter) => splitter.mode = SplitMode::Ratio(weights),
+                None => self.script_log.push(format!(
+                    "controller at ({}, {}, {}) set the ratio of something that isn't a splitter",
+                    position.x, position.y, position.z
+                )),
+            },
+            Effect::Print(text) => self.script_log.push(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::coords::RailVector3;
+
+    #[test]
+    fn test_machine_at_edge() {
+        let factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-8, 0, -8),
+                max: FactoryVector3::new(8, 8, 8),
+            },
+        );
+        let script = "print machine(32767, 0, 0)".parse::<Script>().unwrap();
+        assert_eq!(
+            script
+                .run(&factory, &mut BTreeMap::new())
+                .unwrap_err()
+                .reason,
+            "there's no machine at 32767, 0, 0"
+        );
+    }
+}
@@ -0,0 +1,507 @@
+//! Markings painted on factory floors
+//!
+//! Decals are quads laid flat on the floor, one block across, textured with part of the
+//! [`AssetId::Decals`] atlas. Nothing collides with them; they're only there for the player to
+//! plan with, like arrows showing which way a line runs or stripes around somewhere to keep
+//! clear.
+//!
+//! They're written as text the same way as [blueprints](super::blueprint), one per line, with
+//! the kind, the block's position, and the direction as its index in [`Ordinal2D::ALL`]. One
+//! painted off the grid ends with how far its middle is from the block's along x and z.
+//!
+//! ```text
+//! arrow 3,0,-2 2
+//! hazard 4,0,-2 0 0.25,-0.5
+//! zone:B 0,0,0 0
+//! ```
+//!
+//! [`Ordinal2D::ALL`]: crate::ordinals::Ordinal2D::ALL
+
+use super::blueprint::{DisplayVector, Values};
+use crate::{
+    locale::tr,
+    math::coords::{FactoryVector3, PlayerCoord},
+    ordinals::Cardinal2D,
+    resource::{AssetId, Resources},
+    rl_helpers::DynRaylibDraw3D,
+};
+use raylib::prelude::*;
+use std::str::FromStr;
+
+/// What's painted on a decal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DecalKind {
+    /// Points east until it's turned
+    #[default]
+    Arrow,
+    /// Yellow and black stripes, for places to keep clear of
+    Hazard,
+    /// An outlined square labelled with a letter, numbered from 0 for `A` below
+    /// [`Self::ZONES`]
+    Zone(u8),
+}
+
+impl DecalKind {
+    /// How many letters zones can be labelled with
+    pub const ZONES: u8 = 26;
+    /// How many kinds there are, counting each zone label
+    pub const COUNT: u8 = 2 + Self::ZONES;
+
+    /// Where the kind is in the atlas, and in the order [`Self::next`] goes through them
+    pub const fn index(self) -> u8 {
+        match self {
+            Self::Arrow => 0,
+            Self::Hazard => 1,
+            Self::Zone(label) => 2 + label,
+        }
+    }
+
+    /// The kind at `index`, see [`Self::index`]
+    pub const fn from_index(index: u8) -> Option<Self> {
+        match index {
+            0 => Some(Self::Arrow),
+            1 => Some(Self::Hazard),
+            _ if index < Self::COUNT => Some(Self::Zone(index - 2)),
+            _ => None,
+        }
+    }
+
+    /// The kind after this one, going back to the first after the last
+    pub const fn next(self) -> Self {
+        match Self::from_index((self.index() + 1) % Self::COUNT) {
+            Some(kind) => kind,
+            None => unreachable!(),
+        }
+    }
+
+    /// The kind before this one, going around to the last before the first
+    pub const fn prev(self) -> Self {
+        match Self::from_index((self.index() + Self::COUNT - 1) % Self::COUNT) {
+            Some(kind) => kind,
+            None => unreachable!(),
+        }
+    }
+
+    /// The letter a zone is labelled with
+    pub fn label(self) -> Option<char> {
+        match self {
+            Self::Zone(label) if label < Self::ZONES => Some(char::from(b'A' + label)),
+            Self::Arrow | Self::Hazard | Self::Zone(_) => None,
+        }
+    }
+
+    /// What the kind is called in the player's language
+    pub fn name(self) -> String {
+        match self {
+            Self::Arrow => tr!("decal.arrow"),
+            Self::Hazard => tr!("decal.hazard"),
+            Self::Zone(_) => tr!("decal.zone", label = self.label().unwrap_or('?')),
+        }
+    }
+
+    /// The part of the atlas the kind is drawn with, in texture coordinates
+    pub fn atlas_rect(self) -> Rectangle {
+        let index = self.index();
+        let (columns, rows) = (f32::from(ATLAS_COLUMNS), f32::from(ATLAS_ROWS));
+        Rectangle {
+            x: f32::from(index % ATLAS_COLUMNS) / columns,
+            y: f32::from(index / ATLAS_COLUMNS) / rows,
+            width: columns.recip(),
+            height: rows.recip(),
+        }
+    }
+
+    /// The color at `u` and `v` across the kind's part of the generated atlas, from 0 to 1
+    ///
+    /// Zone labels aren't included, since they're drawn with a font.
+    fn pixel(self, u: f32, v: f32) -> Color {
+        const PAINT: Color = Color::new(250, 200, 0, 230);
+        const STRIPE: Color = Color::new(20, 20, 20, 230);
+        const OUTLINE: Color = Color::new(255, 255, 255, 230);
+        const FILL: Color = Color::new(255, 255, 255, 40);
+        let across = (v - 0.5).abs();
+        match self {
+            Self::Arrow => {
+                let shaft = (0.15..0.55).contains(&u) && across < 0.1;
+                let head = (0.5..0.88).contains(&u) && across < 0.88 - u;
+                if shaft || head { PAINT } else { Color::BLANK }
+            }
+            Self::Hazard => {
+                if ((u + v) * 4.0).rem_euclid(2.0) < 1.0 {
+                    PAINT
+                } else {
+                    STRIPE
+                }
+            }
+            Self::Zone(_) => {
+                if u.min(v).min(1.0 - u).min(1.0 - v) < 0.08 {
+                    OUTLINE
+                } else {
+                    FILL
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for DecalKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self, self.label()) {
+            (Self::Arrow, _) => f.write_str("arrow"),
+            (Self::Hazard, _) => f.write_str("hazard"),
+            (Self::Zone(_), Some(label)) => write!(f, "zone:{label}"),
+            (Self::Zone(_), None) => f.write_str("zone:?"),
+        }
+    }
+}
+
+impl FromStr for DecalKind {
+    type Err = ParseDecalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "arrow" => Ok(Self::Arrow),
+            "hazard" => Ok(Self::Hazard),
+            _ => {
+                let label = s
+                    .strip_prefix("zone:")
+                    .ok_or(ParseDecalError::UnknownKind)?;
+                match label.as_bytes() {
+                    &[letter @ b'A'..=b'Z'] => Ok(Self::Zone(letter - b'A')),
+                    _ => Err(ParseDecalError::InvalidValues),
+                }
+            }
+        }
+    }
+}
+
+/// Kinds in each row of the atlas
+const ATLAS_COLUMNS: u8 = 8;
+/// Rows of kinds in the atlas, enough for [`DecalKind::COUNT`]
+const ATLAS_ROWS: u8 = DecalKind::COUNT.div_ceil(ATLAS_COLUMNS);
+/// Pixels across each kind in the generated atlas
+const ATLAS_CELL: u8 = 64;
+/// Font size of zone labels in the generated atlas
+const LABEL_SIZE: i32 = 40;
+
+/// The atlas every decal is cut out of, for when there's no `decals.png` to load
+pub fn atlas_image() -> Image {
+    let cell = i32::from(ATLAS_CELL);
+    let mut image = Image::gen_image_color(
+        i32::from(ATLAS_COLUMNS) * cell,
+        i32::from(ATLAS_ROWS) * cell,
+        Color::BLANK,
+    );
+    for kind in (0..DecalKind::COUNT).filter_map(DecalKind::from_index) {
+        let index = kind.index();
+        let left = i32::from(index % ATLAS_COLUMNS) * cell;
+        let top = i32::from(index / ATLAS_COLUMNS) * cell;
+        let size = f32::from(ATLAS_CELL);
+        for y in 0..ATLAS_CELL {
+            for x in 0..ATLAS_CELL {
+                let color = kind.pixel((f32::from(x) + 0.5) / size, (f32::from(y) + 0.5) / size);
+                if color.a > 0 {
+                    image.draw_pixel(left + i32::from(x), top + i32::from(y), color);
+                }
+            }
+        }
+        if let Some(label) = kind.label() {
+            let text = label.to_string();
+            let width = measure_text(&text, LABEL_SIZE);
+            image.draw_text(
+                &text,
+                left + (cell - width) / 2,
+                top + (cell - LABEL_SIZE) / 2,
+                LABEL_SIZE,
+                Color::WHITE,
+            );
+        }
+    }
+    image
+}
+
+/// A marking painted on the floor of one block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Decal {
+    /// The block it's painted on the floor of
+    pub position: FactoryVector3,
+    /// Which way the right side of its part of the atlas faces, like where an arrow points
+    pub rotation: Cardinal2D,
+    pub kind: DecalKind,
+    /// How far its middle is from the middle of [`Self::position`] along x and z, each at least
+    /// -0.5 and under 0.5 meters, from [placement](crate::placement) off the grid
+    pub offset: [PlayerCoord; 2],
+}
+
+impl Decal {
+    /// How far above the floor decals are drawn, so they don't flicker with it
+    const LIFT: f32 = 0.01;
+
+    pub const fn new(position: FactoryVector3, rotation: Cardinal2D, kind: DecalKind) -> Self {
+        Self {
+            position,
+            rotation,
+            kind,
+            offset: [PlayerCoord::ZERO; 2],
+        }
+    }
+
+    /// The corners of its quad in factory coordinates, wound to face up, with where each one is
+    /// in the atlas
+    pub fn corners(self) -> [(Vector3, Vector2); 4] {
+        let rect = self.kind.atlas_rect();
+        let [x, z] = self.offset.map(PlayerCoord::to_f32);
+        let center = self.position.as_vec3() + Vector3::new(0.5 + x, Self::LIFT, 0.5 + z);
+        [(-1, -1), (-1, 1), (1, 1), (1, -1)].map(|(u, v)| {
+            let offset = self.rotation.rotate_vector(FactoryVector3::new(u, 0, v));
+            let uv = Vector2::new(
+                rect.x + rect.width * f32::from(u + 1) * 0.5,
+                rect.y + rect.height * f32::from(v + 1) * 0.5,
+            );
+            (center + offset.as_vec3() * 0.5, uv)
+        })
+    }
+}
+
+impl std::fmt::Display for Decal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.kind,
+            DisplayVector(self.position),
+            self.rotation.as_ordinal() as u8,
+        )?;
+        let [x, z] = self.offset;
+        if x.is_zero() && z.is_zero() {
+            Ok(())
+        } else {
+            write!(f, " {x},{z}")
+        }
+    }
+}
+
+/// Why text couldn't be read as a [`Decal`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseDecalError {
+    /// The line started with something other than a kind of decal
+    UnknownKind,
+    /// The decal had missing, extra, or invalid values
+    InvalidValues,
+}
+
+impl std::fmt::Display for ParseDecalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::UnknownKind => "unknown kind of decal",
+            Self::InvalidValues => "invalid decal",
+        })
+    }
+}
+
+impl std::error::Error for ParseDecalError {}
+
+impl FromStr for Decal {
+    type Err = ParseDecalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let kind = words.next().unwrap_or_default().parse()?;
+        let mut values = Values(words);
+        (|| {
+            let decal = Self::new(values.vector()?, values.cardinal()?, kind);
+            let offset = match values.0.next() {
+                Some(word) => parse_offset(word)?,
+                None => decal.offset,
+            };
+            values.end(Self { offset, ..decal })
+        })()
+        .ok_or(ParseDecalError::InvalidValues)
+    }
+}
+
+/// Read `x,z` as a [`Decal::offset`]
+///
+/// Offsets are written with every digit, and any binary fraction within the block is exact as
+/// an `f64`, so this gets back exactly what was written.
+fn parse_offset(word: &str) -> Option<[PlayerCoord; 2]> {
+    /// How much one bit of a [`PlayerCoord`] is worth
+    const BITS_PER_METER: f64 = 4_294_967_296.0;
+    let (x, z) = word.split_once(',')?;
+    let offset = [x, z].map(|part| {
+        let meters = part.parse::<f64>().ok()?;
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "out of range is caught by the check after"
+        )]
+        let offset = PlayerCoord::from_bits((meters * BITS_PER_METER) as i64);
+        (-PlayerCoord::HALF <= offset && offset < PlayerCoord::HALF).then_some(offset)
+    });
+    let [Some(x), Some(z)] = offset else {
+        return None;
+    };
+    Some([x, z])
+}
+
+/// Draw `decals` textured from the atlas, offset from factory coordinates by `offset`
+pub fn draw_decals(
+    _d: &mut dyn DynRaylibDraw3D,
+    resources: &Resources,
+    decals: &[Decal],
+    offset: Vector3,
+) {
+    #[allow(
+        clippy::cast_possible_wrap,
+        reason = "RL_QUADS is an i32 in Raylib, but bindgen made it a u32"
+    )]
+    const RL_QUADS: i32 = ffi::RL_QUADS as i32;
+
+    if decals.is_empty() {
+        return;
+    }
+    let Some(atlas) = resources.texture(AssetId::Decals) else {
+        return;
+    };
+
+    #[allow(
+        clippy::multiple_unsafe_ops_per_block,
+        reason = "safety comment is complicated and shared by all operations in this block"
+    )]
+    // SAFETY: DynRaylibDraw3D is exclusively borrowed, guaranteeing the window has been
+    // initialized, 3D drawing processes are loaded, and rlgl statics are syncronous for this
+    // function, the same as when drawing the skybox.
+    unsafe {
+        ffi::rlSetTexture(atlas.id);
+        ffi::rlBegin(RL_QUADS);
+        ffi::rlColor4ub(255, 255, 255, 255);
+        for decal in decals {
+            for (position, uv) in decal.corners() {
+                let position = position + offset;
+                ffi::rlTexCoord2f(uv.x, uv.y);
+                ffi::rlVertex3f(position.x, position.y, position.z);
+            }
+        }
+        ffi::rlEnd();
+        ffi::rlSetTexture(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{bounds::FactoryBounds, coords::RailVector3},
+        region::factory::{Factory, Floor},
+    };
+
+    #[test]
+    fn test_decal_text() {
+        let decals = [
+            Decal::new(
+                FactoryVector3::new(3, 0, -2),
+                Cardinal2D::North,
+                DecalKind::Arrow,
+            ),
+            Decal::new(
+                FactoryVector3::new(4, 0, -2),
+                Cardinal2D::East,
+                DecalKind::Hazard,
+            ),
+            Decal::new(
+                FactoryVector3::new(0, 4, 0),
+                Cardinal2D::South,
+                DecalKind::Zone(25),
+            ),
+            Decal {
+                offset: [PlayerCoord::from_f32(0.375), -PlayerCoord::HALF],
+                ..Decal::new(
+                    FactoryVector3::new(1, 0, 1),
+                    Cardinal2D::East,
+                    DecalKind::Arrow,
+                )
+            },
+        ];
+        let text = decals.map(|decal| decal.to_string());
+        assert_eq!(
+            text,
+            [
+                "arrow 3,0,-2 2",
+                "hazard 4,0,-2 0",
+                "zone:Z 0,4,0 6",
+                "arrow 1,0,1 0 0.375,-0.5"
+            ]
+        );
+        for (decal, text) in decals.iter().zip(&text) {
+            assert_eq!(text.parse::<Decal>().as_ref(), Ok(decal));
+        }
+        let free = Decal {
+            offset: [PlayerCoord::from_bits(-1), PlayerCoord::from_f32(0.3)],
+            ..decals[0]
+        };
+        assert_eq!(
+            free.to_string().parse(),
+            Ok(free),
+            "offsets are read back exactly"
+        );
+        assert_eq!(
+            "stripes 0,0,0 0".parse::<Decal>(),
+            Err(ParseDecalError::UnknownKind)
+        );
+        assert_eq!(
+            "zone:a 0,0,0 0".parse::<Decal>(),
+            Err(ParseDecalError::InvalidValues)
+        );
+        assert_eq!(
+            "arrow 0,0,0 1".parse::<Decal>(),
+            Err(ParseDecalError::InvalidValues),
+            "decals only face cardinal directions"
+        );
+        assert_eq!(
+            "arrow 0,0,0 0 0.5,0".parse::<Decal>(),
+            Err(ParseDecalError::InvalidValues),
+            "offsets stay within the block"
+        );
+        assert_eq!(DecalKind::Arrow.prev(), DecalKind::Zone(25));
+        assert_eq!(DecalKind::Zone(25).next(), DecalKind::Arrow);
+    }
+
+    #[test]
+    fn test_paint_and_erase() {
+        let bounds = FactoryBounds {
+            min: FactoryVector3::new(0, 0, 0),
+            max: FactoryVector3::new(8, 8, 8),
+        };
+        let mut factory = Factory::new(RailVector3::default(), bounds);
+        let position = FactoryVector3::new(2, 0, 3);
+        let arrow = Decal::new(position, Cardinal2D::East, DecalKind::Arrow);
+        assert!(factory.paint_decal(arrow));
+        let hazard = Decal::new(position, Cardinal2D::East, DecalKind::Hazard);
+        assert!(factory.paint_decal(hazard));
+        assert_eq!(factory.decals(), [hazard], "painting covers what was there");
+        assert!(
+            !factory.paint_decal(Decal::new(
+                FactoryVector3::new(2, 3, 3),
+                Cardinal2D::East,
+                DecalKind::Arrow
+            )),
+            "decals are painted on floors"
+        );
+        assert!(!factory.paint_decal(Decal::new(
+            FactoryVector3::new(8, 0, 3),
+            Cardinal2D::East,
+            DecalKind::Arrow
+        )));
+
+        let upstairs = FactoryVector3::new(2, 3, 3);
+        let floor = Floor::new(3, &bounds);
+        assert!(factory.add_floor(floor));
+        assert!(factory.paint_decal(Decal::new(upstairs, Cardinal2D::East, DecalKind::Arrow)));
+        assert_eq!(factory.erase_decal(position), Some(hazard));
+        assert_eq!(factory.erase_decal(position), None);
+        assert!(factory.remove_floor(0).is_some());
+        assert!(
+            factory.decals().is_empty(),
+            "decals go with the floor they're on"
+        );
+    }
+}
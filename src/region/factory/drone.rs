@@ -0,0 +1,420 @@
+//! Drones that fly items from one machine to another, for where there's no belt between them
+//!
+//! Each [`ChargingPad`] has a drone of its own, which picks up from the front of one machine's
+//! output and drops off into another's input, landing on top of each. Drones fly around
+//! machines, belts, and pipes along the shortest route they can find, see
+//! [`RouteRules::DRONE`], and go back to their pad to charge before their battery runs out.
+
+use super::{
+    Clearance, Factory, Machine, MachineBuffers, MachineId, MachineKind, MachineSize,
+    MachineStatus, OrientedFootprint, block_center,
+    routing::{RouteRules, find_route},
+};
+use crate::{
+    container::{Container, FluidTank, ItemSlots, ItemStack},
+    math::{
+        bounds::{Bounds, FactoryBounds},
+        coords::{FactoryVector3, PlayerVector3, RailVector3, VectorConstants},
+        frustum::Frustum,
+    },
+    ordinals::Cardinal2D,
+    rl_helpers::DynRaylibDraw3D,
+};
+use fixed_point::Q32_32;
+use raylib::prelude::*;
+
+/// Which stop a drone is headed for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Stop {
+    Pad,
+    Source,
+    Destination,
+}
+
+/// Flies items between the machines its [`ChargingPad`] is set to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drone {
+    /// The block it's in, or last flew through
+    position: FactoryVector3,
+    /// The blocks it's flying through, ending at the stop it's headed for, or empty while it's
+    /// landed
+    path: Vec<FactoryVector3>,
+    /// The index in `path` of the block it's flying toward
+    next: usize,
+    /// How far it is from `position` to the next block, in `[0, 1)`
+    progress: f32,
+    cargo: Option<ItemStack>,
+    /// Seconds it can fly before it has to charge
+    battery: f32,
+    /// Seconds until it tries to find a route again, after there wasn't one
+    wait: f32,
+    /// Seconds since it was made, for bobbing up and down
+    age: f32,
+}
+
+impl Drone {
+    /// Items it can carry at once
+    pub const CAPACITY: u32 = 20;
+
+    /// Blocks per second it flies
+    pub const SPEED: f32 = 4.0;
+
+    /// Seconds it can fly on a full battery
+    pub const BATTERY: f32 = 60.0;
+
+    /// Seconds of battery it gains every second on its pad
+    const CHARGE_RATE: f32 = 10.0;
+
+    /// Seconds of battery left when it heads home to charge
+    const LOW_BATTERY: f32 = 0.25 * Self::BATTERY;
+
+    /// How much slower it flies once its battery is flat
+    const FLAT_SLOWDOWN: f32 = 0.25;
+
+    /// Seconds between trying to find a route when there isn't one
+    const RETRY: f32 = 1.0;
+
+    /// Seconds to bob up and down once
+    const BOB_PERIOD: f32 = 1.5;
+
+    /// How far it bobs up and down, in meters
+    const BOB_HEIGHT: f32 = 0.1;
+
+    /// Size of its body, in meters
+    const SIZE: f32 = 0.4;
+
+    /// A drone with a full battery, landed at `position`
+    pub const fn new(position: FactoryVector3) -> Self {
+        Self {
+            position,
+            path: Vec::new(),
+            next: 0,
+            progress: 0.0,
+            cargo: None,
+            battery: Self::BATTERY,
+            wait: 0.0,
+            age: 0.0,
+        }
+    }
+
+    #[inline]
+    pub const fn cargo(&self) -> Option<&ItemStack> {
+        self.cargo.as_ref()
+    }
+
+    #[inline]
+    pub const fn battery(&self) -> f32 {
+        self.battery
+    }
+
+    /// Whether it's on the way somewhere
+    #[inline]
+    pub const fn is_flying(&self) -> bool {
+        self.next < self.path.len()
+    }
+
+    /// Where it's drawn, in factory coordinates
+    pub fn draw_position(&self) -> Vector3 {
+        let here = block_center(self.position);
+        let there = self
+            .path
+            .get(self.next)
+            .map_or(here, |&next| block_center(next));
+        let bob = (self.age / Self::BOB_PERIOD * std::f32::consts::TAU).sin() * Self::BOB_HEIGHT;
+        here.lerp(there, self.progress) + Vector3::new(0.0, bob, 0.0)
+    }
+
+    /// Fly `dt` seconds further along its path, returning whether it's still on the way
+    fn fly(&mut self, dt: f32) -> bool {
+        if !self.is_flying() {
+            return false;
+        }
+        let speed = if self.battery > 0.0 {
+            Self::SPEED
+        } else {
+            Self::SPEED * Self::FLAT_SLOWDOWN
+        };
+        self.battery = (self.battery - dt).max(0.0);
+        self.progress += speed * dt;
+        while self.progress >= 1.0
+            && let Some(&next) = self.path.get(self.next)
+        {
+            self.progress -= 1.0;
+            self.position = next;
+            self.next += 1;
+        }
+        if self.is_flying() {
+            return true;
+        }
+        self.path.clear();
+        self.next = 0;
+        self.progress = 0.0;
+        false
+    }
+
+    /// Where it goes next: home if it's low on battery, otherwise to drop off what it's
+    /// carrying, or to pick more up
+    const fn next_stop(&self) -> Stop {
+        if self.battery < Self::LOW_BATTERY {
+            Stop::Pad
+        } else if self.cargo.is_some() {
+            Stop::Destination
+        } else {
+            Stop::Source
+        }
+    }
+}
+
+/// Where a drone lives and charges, and which machines it carries items between
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChargingPad {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    /// The machine whose output the drone picks up from
+    pub source: Option<MachineId>,
+    /// The machine whose input the drone drops off into
+    pub destination: Option<MachineId>,
+    drone: Drone,
+}
+
+impl const Clearance for ChargingPad {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        Self::SIZE
+    }
+}
+
+impl Bounds<FactoryVector3> for ChargingPad {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl ChargingPad {
+    // SAFETY: 1 is not zero
+    pub const SIZE: MachineSize = unsafe { MachineSize::new_unchecked(1, 1, 1) };
+
+    /// A pad with its drone landed on it, not carrying anything anywhere yet
+    pub const fn new(position: FactoryVector3, rotation: Cardinal2D) -> Self {
+        Self {
+            position,
+            rotation,
+            source: None,
+            destination: None,
+            drone: Drone::new(position.plus(FactoryVector3::Y)),
+        }
+    }
+
+    #[inline]
+    pub const fn drone(&self) -> &Drone {
+        &self.drone
+    }
+}
+
+impl Machine for ChargingPad {
+    fn name(&self) -> String {
+        MachineKind::ChargingPad.name()
+    }
+
+    /// Working while its drone is out, as far through as its battery is spent
+    fn status(&self) -> MachineStatus {
+        if self.drone.is_flying() {
+            MachineStatus::Working {
+                progress: 1.0 - self.drone.battery / Drone::BATTERY,
+            }
+        } else {
+            MachineStatus::Idle
+        }
+    }
+
+    /// Nothing goes in or comes out, the drone carries it all
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(0, 0),
+            output: ItemSlots::new(0, 0),
+            fluid: FluidTank::new(Q32_32::from_i32(0)),
+        }
+    }
+}
+
+impl Factory {
+    /// Where drones land on the machine identified by `id`: on top of it, in the middle
+    pub fn drone_stop(&self, id: MachineId) -> Option<FactoryVector3> {
+        let FactoryBounds { min, max } = *self.index.get(&id)?;
+        Some(FactoryVector3::new(
+            (min.x + max.x).div_euclid(2),
+            max.y,
+            (min.z + max.z).div_euclid(2),
+        ))
+    }
+
+    /// The charging pad closest to `position`, if there are any
+    pub fn nearest_charging_pad(&self, position: FactoryVector3) -> Option<MachineId> {
+        (self.charging_pads.iter())
+            .min_by_key(|(_, pad)| {
+                let delta = pad.position - position;
+                [delta.x, delta.y, delta.z]
+                    .map(|n| i32::from(n).pow(2))
+                    .iter()
+                    .sum::<i32>()
+            })
+            .map(|(entity, _)| MachineId(entity))
+    }
+
+    /// The charging pad identified by `id`, to set where its drone goes
+    pub fn charging_pad_mut(&mut self, id: MachineId) -> Option<&mut ChargingPad> {
+        self.charging_pads.get_mut(id.0)
+    }
+
+    /// Fly every drone `dt` seconds further, and load, unload, charge, and send off the ones
+    /// that have landed
+    ///
+    /// Drones on pads that have been turned off stay where they are.
+    pub(super) fn move_drones(&mut self, dt: f32) {
+        // Taken out so drones can find routes through the rest of the factory
+        let mut pads = std::mem::take(&mut self.charging_pads);
+        for (entity, pad) in pads.iter_mut() {
+            let id = MachineId(entity);
+            let drone = &mut pad.drone;
+            drone.age = (drone.age + dt) % Drone::BOB_PERIOD;
+            if !self.is_enabled(id) || drone.fly(dt) {
+                continue;
+            }
+            let (home, source, destination) = (
+                self.drone_stop(id),
+                (pad.source).and_then(|id| Some((id, self.drone_stop(id)?))),
+                (pad.destination).and_then(|id| Some((id, self.drone_stop(id)?))),
+            );
+            let stop = |stop| match stop {
+                Stop::Pad => home,
+                Stop::Source => source.map(|(_, stop)| stop),
+                Stop::Destination => destination.map(|(_, stop)| stop),
+            };
+            if Some(drone.position) == stop(Stop::Pad) && drone.battery < Drone::BATTERY {
+                drone.battery = (drone.battery + Drone::CHARGE_RATE * dt).min(Drone::BATTERY);
+                continue;
+            }
+            self.load_drone(drone, source, destination);
+            drone.wait -= dt;
+            if drone.wait > 0.0 {
+                continue;
+            }
+            // Home if there's nowhere to go
+            let goal = stop(drone.next_stop()).or(home);
+            let Some(goal) = goal.filter(|&goal| goal != drone.position) else {
+                continue;
+            };
+            match find_route(self, drone.position, goal, RouteRules::DRONE) {
+                Some(path) => {
+                    drone.path = path;
+                    drone.next = 1;
+                }
+                None => drone.wait = Drone::RETRY,
+            }
+        }
+        self.charging_pads = pads;
+    }
+
+    /// Drop off what `drone` is carrying if it's landed on its `destination`, or pick up from
+    /// the front of its `source`'s output if it's landed there empty
+    ///
+    /// Each is a machine and where drones land on it.
+    fn load_drone(
+        &mut self,
+        drone: &mut Drone,
+        source: Option<(MachineId, FactoryVector3)>,
+        destination: Option<(MachineId, FactoryVector3)>,
+    ) {
+        let landed_on =
+            |(id, stop): (MachineId, FactoryVector3)| (stop == drone.position).then_some(id);
+        if let Some(cargo) = drone.cargo.take() {
+            let buffers = destination
+                .and_then(landed_on)
+                .and_then(|id| self.buffers.get_mut(id.0));
+            drone.cargo = match buffers {
+                Some(buffers) => buffers.input.insert(cargo),
+                None => Some(cargo),
+            };
+        } else if let Some(buffers) = source
+            .and_then(landed_on)
+            .and_then(|id| self.buffers.get_mut(id.0))
+        {
+            drone.cargo = buffers.output.take(0, Drone::CAPACITY);
+        }
+    }
+
+    /// Draw every drone that might be visible through `frustum`, and what it's carrying
+    pub(super) fn draw_drones(
+        &self,
+        d: &mut dyn DynRaylibDraw3D,
+        player_pos: &PlayerVector3,
+        origin: &RailVector3,
+        frustum: &Frustum,
+    ) {
+        let offset = FactoryVector3::ZERO.to_player_relative(player_pos, origin);
+        for (_, pad) in self.charging_pads.iter() {
+            let drone = &pad.drone;
+            let position = drone.draw_position() + offset;
+            if !frustum.intersects_sphere(position, Drone::SIZE) {
+                continue;
+            }
+            let size = Drone::SIZE;
+            d.draw_cube(position, size, 0.5 * size, size, Color::SKYBLUE);
+            d.draw_cube_wires(position, size, 0.5 * size, size, Color::DARKBLUE);
+            if drone.cargo.is_some() {
+                let below = position - Vector3::new(0.0, 0.5 * size, 0.0);
+                d.draw_cube(below, 0.5 * size, 0.5 * size, 0.5 * size, Color::BROWN);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::factory::Reactor;
+
+    #[test]
+    fn test_drone() {
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-10, 0, -10),
+                max: FactoryVector3::new(10, 10, 10),
+            },
+        );
+        let source = factory.add_reactor(Reactor {
+            position: FactoryVector3::new(-6, 0, 0),
+            rotation: Cardinal2D::East,
+        });
+        let destination = factory.add_reactor(Reactor {
+            position: FactoryVector3::new(4, 0, 0),
+            rotation: Cardinal2D::East,
+        });
+        let pad = factory.add_charging_pad(ChargingPad::new(
+            FactoryVector3::new(0, 0, -6),
+            Cardinal2D::East,
+        ));
+        let water = || "H2O".parse().unwrap();
+        let output = &mut factory.buffers_mut(source).unwrap().output;
+        assert_eq!(output.insert(ItemStack::new(water(), 30)), None);
+
+        let charging_pad = factory.charging_pad_mut(pad).unwrap();
+        charging_pad.source = Some(source);
+        charging_pad.destination = Some(destination);
+        for _ in 0..200 {
+            factory.move_drones(0.1);
+        }
+        let input = &factory.buffers(destination).unwrap().input;
+        assert_eq!(
+            input.stored(),
+            30,
+            "should have made two trips, one of them not full"
+        );
+        let drone = factory.charging_pads.as_slice()[0].drone();
+        assert!(drone.cargo().is_none());
+        assert!(drone.battery() < Drone::BATTERY, "it's been flying");
+    }
+}
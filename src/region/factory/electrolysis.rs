@@ -0,0 +1,529 @@
+//! Machines that split compounds apart with electricity
+//!
+//! An [`Electrolyzer`] runs an [`Electrolysis`]: a reaction that takes energy in, made sure of
+//! by [balancing](Reaction::balance) it, with one liquid piped in and each gas it gives off piped
+//! out of an outlet of its own. Whether things are liquid or gas goes by [`chem::phase`] at
+//! [`thermal::AMBIENT`], and anything else comes in and goes out as items.
+//!
+//! Electrolyzers draw enough [power](Machine::power) to supply the reaction's enthalpy over a
+//! run, and more for what's lost along the way. Nothing supplies it yet, so it's only metered,
+//! see [`Factory::power_draw`](super::Factory::power_draw).
+//!
+//! [`chem::phase`]: crate::chem::phase
+
+use super::{
+    BeltInputNode, BeltOutputNode, Clearance, Machine, MachineBuffers, MachineKind, MachineSize,
+    MachineStatus, OrientedFootprint, PipeNode, Side, TickContext, recipe,
+    separation::{self, Batch},
+    thermal,
+};
+use crate::{
+    chem::{
+        formula::Formula,
+        molecule::Compound,
+        phase::Phase,
+        reaction::{BalanceError, BalancedReaction, Reaction},
+        units::{Joule, Mole, Pascal, Watt, ideal_gas},
+    },
+    container::{Container, FluidTank, FluidVolume, ItemSlots},
+    math::{
+        bounds::{Bounds, FactoryBounds},
+        coords::FactoryVector3,
+    },
+    ordinals::Cardinal2D,
+};
+use arrayvec::ArrayVec;
+use fixed_point::Q32_32;
+use std::{num::NonZeroU32, sync::LazyLock};
+
+/// Electrolyses that come with the game, see [`find`]
+static BUILT_IN: LazyLock<Vec<(&'static str, Electrolysis)>> = LazyLock::new(|| {
+    [
+        // Hydrogen and oxygen from water
+        ("water", "H2O -> H2 + O2"),
+        // Chlorine and lye from salt water, with the salt fed in as items
+        ("brine", "NaCl + H2O -> NaOH + H2 + Cl2"),
+    ]
+    .into_iter()
+    .map(|(name, reaction)| {
+        let reaction = reaction.parse().unwrap();
+        (name, Electrolysis::new(&reaction).unwrap())
+    })
+    .collect()
+});
+
+/// Electrolyses that come with the game, and their names
+pub fn built_in() -> &'static [(&'static str, Electrolysis)] {
+    &BUILT_IN
+}
+
+/// The electrolysis that comes with the game named `name`
+pub fn find(name: &str) -> Option<&'static Electrolysis> {
+    (BUILT_IN.iter())
+        .find(|(built_in, _)| *built_in == name)
+        .map(|(_, electrolysis)| electrolysis)
+}
+
+/// Why a reaction can't be run by an [`Electrolyzer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElectrolysisError {
+    /// The reaction can't be balanced
+    Unbalanced(BalanceError),
+    /// How much energy the reaction takes in isn't known
+    UnknownEnthalpy,
+    /// The reaction gives off energy instead, so it doesn't need electricity
+    Spontaneous,
+    /// None of the reactants is a liquid to pipe in
+    NoLiquid,
+    /// More than one of the reactants is a liquid, and there's only one inlet
+    TooManyLiquids,
+    /// None of the products is a gas
+    NoGas,
+    /// More of the products are gases than there are outlets
+    TooManyGases,
+}
+
+impl std::fmt::Display for ElectrolysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unbalanced(e) => e.fmt(f),
+            Self::UnknownEnthalpy => f.write_str("how much energy the reaction takes isn't known"),
+            Self::Spontaneous => f.write_str("reaction gives off energy instead of taking it in"),
+            Self::NoLiquid => f.write_str("reaction has no liquid to pipe in"),
+            Self::TooManyLiquids => f.write_str("reaction has more than one liquid to pipe in"),
+            Self::NoGas => f.write_str("reaction gives off no gas"),
+            Self::TooManyGases => write!(
+                f,
+                "reaction gives off more than {} gases",
+                Electrolyzer::OUTLETS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ElectrolysisError {}
+
+impl From<BalanceError> for ElectrolysisError {
+    fn from(value: BalanceError) -> Self {
+        Self::Unbalanced(value)
+    }
+}
+
+/// Liters of `count` moles of a liquid, taken as a kilogram per liter like distillate is
+#[allow(
+    clippy::cast_possible_truncation,
+    reason = "reactions are far smaller than f32 loses precision at"
+)]
+fn liquid_liters(liquid: &Compound, count: NonZeroU32) -> Q32_32 {
+    Q32_32::from_f32((liquid.molar_mass() * f64::from(count.get()) / 1000.0) as f32)
+}
+
+/// Liters of `count` moles of gas at [`thermal::AMBIENT`] and one atmosphere
+#[allow(
+    clippy::cast_possible_truncation,
+    reason = "reactions are far smaller than f32 loses precision at"
+)]
+fn gas_liters(count: NonZeroU32) -> Q32_32 {
+    let gas = ideal_gas(
+        Pascal::from_atm(1.0),
+        None,
+        Some(Mole::from_count(count.get())),
+        Some(thermal::AMBIENT),
+    );
+    Q32_32::from_f32(gas.map_or(0.0, |gas| gas.volume.get()) as f32)
+}
+
+/// A reaction an [`Electrolyzer`] can run, and where each species in it comes from or goes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Electrolysis {
+    reaction: BalancedReaction,
+    /// Taken in each run
+    enthalpy: Joule,
+    /// The reactant that's piped in, and liters of it each run
+    liquid: (Compound, Q32_32),
+    /// The reactants that come in as items
+    solids: Vec<(NonZeroU32, Formula)>,
+    /// The products that are piped out, in the order of the outlets, and liters of each run
+    gases: Vec<(Compound, Q32_32)>,
+    /// The products that go out as items
+    residue: Vec<(NonZeroU32, Formula)>,
+}
+
+impl Electrolysis {
+    /// Balance `reaction` and check that an electrolyzer can run it
+    pub fn new(reaction: &Reaction) -> Result<Self, ElectrolysisError> {
+        let reaction = reaction.balance()?;
+        let enthalpy = reaction
+            .enthalpy()
+            .ok_or(ElectrolysisError::UnknownEnthalpy)?;
+        if enthalpy <= Joule::ZERO {
+            return Err(ElectrolysisError::Spontaneous);
+        }
+        let phase = |formula: &Formula| formula.compound().phase(thermal::AMBIENT);
+
+        let (liquids, solids) = (reaction.reactants().iter())
+            .cloned()
+            .partition::<Vec<_>, _>(|(_, formula)| phase(formula) == Some(Phase::Liquid));
+        let liquid = match &liquids[..] {
+            [] => return Err(ElectrolysisError::NoLiquid),
+            [(count, formula)] => {
+                let liquid = formula.compound();
+                let liters = liquid_liters(&liquid, *count);
+                (liquid, liters)
+            }
+            _ => return Err(ElectrolysisError::TooManyLiquids),
+        };
+
+        let (gases, residue) = (reaction.products().iter())
+            .cloned()
+            .partition::<Vec<_>, _>(|(_, formula)| phase(formula) == Some(Phase::Gas));
+        if gases.is_empty() {
+            return Err(ElectrolysisError::NoGas);
+        }
+        if gases.len() > Electrolyzer::OUTLETS {
+            return Err(ElectrolysisError::TooManyGases);
+        }
+        let gases = (gases.into_iter())
+            .map(|(count, formula)| (formula.compound(), gas_liters(count)))
+            .collect();
+
+        Ok(Self {
+            reaction,
+            enthalpy,
+            liquid,
+            solids,
+            gases,
+            residue,
+        })
+    }
+
+    #[inline]
+    pub const fn reaction(&self) -> &BalancedReaction {
+        &self.reaction
+    }
+
+    /// Energy taken in by each run
+    #[inline]
+    pub const fn enthalpy(&self) -> Joule {
+        self.enthalpy
+    }
+
+    /// The reactant that's piped in, and liters of it each run
+    #[inline]
+    pub const fn liquid(&self) -> (&Compound, Q32_32) {
+        (&self.liquid.0, self.liquid.1)
+    }
+
+    /// The products that are piped out, in the order of the outlets, and liters of each run
+    #[inline]
+    pub fn gases(&self) -> &[(Compound, Q32_32)] {
+        &self.gases
+    }
+
+    /// Electricity drawn while it's running, with the energy that's lost along the way
+    pub fn power(&self) -> Watt {
+        (self.enthalpy / Electrolyzer::EFFICIENCY)
+            .over(f64::from(Electrolyzer::SECONDS))
+            .unwrap_or(Watt::ZERO)
+    }
+}
+
+/// Splits a liquid piped in on the left with electricity, piping the gases it gives off out of
+/// the right
+///
+/// Anything else the reaction takes comes in at the back as items, and anything else it makes
+/// goes out the front.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Electrolyzer {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    process: Option<Electrolysis>,
+    /// Where each gas goes until it's piped out, in the order of [`Electrolysis::gases`]
+    outlets: [FluidTank; Self::OUTLETS],
+    /// Toward the end of the current run, in `[0, 1]`
+    progress: f32,
+    status: MachineStatus,
+}
+
+impl const Clearance for Electrolyzer {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        Self::SIZE
+    }
+}
+
+impl Bounds<FactoryVector3> for Electrolyzer {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl Electrolyzer {
+    // SAFETY: 3 and 2 are not zero
+    pub const SIZE: MachineSize = unsafe { MachineSize::new_unchecked(3, 2, 3) };
+
+    /// How many gases it can pipe out
+    pub const OUTLETS: usize = 2;
+
+    /// Liters each outlet holds
+    const OUTLET_CAPACITY: i32 = 200;
+
+    /// How much of the electricity it draws goes into the reaction
+    const EFFICIENCY: f64 = 0.7;
+
+    /// Splitting water, taking items in at the back and putting them out the front, with the
+    /// liquid piped in on the left and the gases out of the right
+    pub fn new(position: FactoryVector3, rotation: Cardinal2D) -> Self {
+        Self {
+            position,
+            rotation,
+            process: find("water").cloned(),
+            outlets: std::array::from_fn(|_| {
+                FluidTank::new(Q32_32::from_i32(Self::OUTLET_CAPACITY))
+            }),
+            progress: 0.0,
+            status: MachineStatus::Idle,
+        }
+    }
+
+    #[inline]
+    pub const fn process(&self) -> Option<&Electrolysis> {
+        self.process.as_ref()
+    }
+
+    /// Run `process` from now on, giving up on the current run
+    ///
+    /// Whatever was taken in for the current run is lost, and so are gases left in outlets that
+    /// the new process pipes something else out of.
+    pub fn set_process(&mut self, process: Option<Electrolysis>) {
+        let gases = process.as_ref().map_or(&[][..], Electrolysis::gases);
+        for (idx, outlet) in self.outlets.iter_mut().enumerate() {
+            let kept = gases.get(idx).map(|(gas, _)| gas);
+            if outlet.contents().map(|fluid| &fluid.molecule) != kept {
+                *outlet = FluidTank::new(outlet.capacity());
+            }
+        }
+        self.process = process;
+        self.progress = 0.0;
+        self.status = MachineStatus::Idle;
+    }
+
+    /// Where each gas goes until it's piped out
+    #[inline]
+    pub const fn outlets(&self) -> &[FluidTank] {
+        &self.outlets
+    }
+
+    /// Just past the right, at the back and the front of a machine 3 blocks across
+    fn outlet_nodes(&self) -> [PipeNode; Self::OUTLETS] {
+        let side = Side::Right.offset();
+        [0, 2].map(|x| PipeNode {
+            position: (self.position).plus(
+                self.rotation
+                    .rotate_block(FactoryVector3::new(x, 0, 1).plus(side).plus(side)),
+            ),
+            rotation: self.rotation.plus(Side::Right.turn()).as_ordinal().as_3d(),
+        })
+    }
+}
+
+impl Batch for Electrolyzer {
+    /// Seconds each run of the reaction takes
+    const SECONDS: f32 = 4.0;
+
+    fn run_state(&mut self) -> (&mut f32, &mut MachineStatus) {
+        (&mut self.progress, &mut self.status)
+    }
+
+    /// Take in the liquid and the items for a run, if they're all there
+    fn start(&mut self, ctx: &mut TickContext<'_>) -> bool {
+        let Some(process) = &self.process else {
+            return false;
+        };
+        let (liquid, liters) = process.liquid();
+        let has_liquid = (ctx.buffers.fluid.contents())
+            .is_some_and(|fluid| fluid.molecule == *liquid && fluid.liters >= liters);
+        if !has_liquid
+            || !recipe::take_reactants(&mut ctx.buffers.input, &process.solids, ctx.stats)
+        {
+            return false;
+        }
+        _ = ctx.buffers.fluid.extract(liters);
+        true
+    }
+
+    /// Put each gas in its outlet and the rest in the output, if they all fit
+    fn finish(&mut self, ctx: &mut TickContext<'_>) -> bool {
+        let Some(process) = &self.process else {
+            return true;
+        };
+        let mut outlets = self.outlets.clone();
+        for ((gas, liters), outlet) in process.gases.iter().zip(&mut outlets) {
+            if outlet
+                .insert(FluidVolume::new(gas.clone(), *liters))
+                .is_some()
+            {
+                return false;
+            }
+        }
+        if !recipe::put_products(&mut ctx.buffers.output, &process.residue, ctx.stats) {
+            return false;
+        }
+        self.outlets = outlets;
+        true
+    }
+}
+
+impl Machine for Electrolyzer {
+    fn name(&self) -> String {
+        MachineKind::Electrolyzer.name()
+    }
+
+    fn status(&self) -> MachineStatus {
+        self.status
+    }
+
+    fn tick(&mut self, dt: f32, ctx: &mut TickContext<'_>) {
+        self.run(dt, ctx);
+    }
+
+    /// Whatever its process draws, while it's running
+    fn power(&self) -> Watt {
+        match (self.status, &self.process) {
+            (MachineStatus::Working { .. }, Some(process)) => process.power(),
+            _ => Watt::ZERO,
+        }
+    }
+
+    /// In the middle of the back, heading in
+    fn belt_inputs(&self) -> ArrayVec<BeltInputNode, 8> {
+        [separation::belt_input(self.position, self.rotation)]
+            .into_iter()
+            .collect()
+    }
+
+    /// Just past the middle of the front, heading out
+    fn belt_outputs(&self) -> ArrayVec<BeltOutputNode, 8> {
+        [separation::belt_output(self.position, self.rotation)]
+            .into_iter()
+            .collect()
+    }
+
+    /// The inlet just past the middle of the left, then the outlets just past the right
+    fn pipe_nodes(&self) -> ArrayVec<PipeNode, 8> {
+        [separation::pipe_node(self.position, self.rotation)]
+            .into_iter()
+            .chain(self.outlet_nodes())
+            .collect()
+    }
+
+    fn outlet_mut(&mut self, node: PipeNode) -> Option<&mut FluidTank> {
+        let idx = self
+            .outlet_nodes()
+            .iter()
+            .position(|&outlet| outlet == node)?;
+        self.outlets.get_mut(idx)
+    }
+
+    /// Room for what's fed in and made alongside the gases, and the liquid piped in
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(2, 100),
+            output: ItemSlots::new(2, 100),
+            fluid: FluidTank::new(Q32_32::from_i32(100)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        container::ItemStack,
+        math::coords::RailVector3,
+        region::{
+            factory::{Factory, Pipe, tank::Tank},
+            rail::{deposit::Deposits, network::RailNetwork},
+        },
+    };
+
+    #[test]
+    fn test_electrolysis() {
+        let electrolysis = |reaction: &str| Electrolysis::new(&reaction.parse().unwrap());
+        assert_eq!(
+            electrolysis("H2O -> H2").unwrap_err(),
+            ElectrolysisError::Unbalanced(BalanceError::Impossible)
+        );
+        assert_eq!(
+            electrolysis("H2 + O2 -> H2O").unwrap_err(),
+            ElectrolysisError::Spontaneous
+        );
+        assert_eq!(
+            electrolysis("CaCO3 -> CaO + CO2").unwrap_err(),
+            ElectrolysisError::NoLiquid,
+            "limestone is baked, not electrolyzed"
+        );
+        let brine = find("brine").unwrap();
+        assert_eq!(
+            brine.solids,
+            [(NonZeroU32::new(2).unwrap(), "NaCl".parse().unwrap())]
+        );
+        assert_eq!(brine.gases().len(), 2, "hydrogen and chlorine");
+
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-8, 0, -8),
+                max: FactoryVector3::new(12, 8, 10),
+            },
+        );
+        let electrolyzer = Electrolyzer::new(FactoryVector3::new(0, 0, 4), Cardinal2D::East);
+        let hydrogen = electrolyzer.pipe_nodes()[1];
+        let tank = Tank::new(FactoryVector3::new(0, 0, -4), Cardinal2D::East);
+        let inlet = tank.pipe_nodes()[0];
+        let electrolyzer = factory.add_electrolyzer(electrolyzer);
+        let tank = factory.add_tank(tank);
+        factory.add_pipe(Pipe {
+            a: hydrogen,
+            b: inlet,
+            path: vec![hydrogen.position, inlet.position],
+        });
+        let water = FluidVolume::new("H2O".parse().unwrap(), Q32_32::from_i32(10));
+        factory
+            .buffers_mut(electrolyzer)
+            .unwrap()
+            .fluid
+            .insert(water);
+        // Salt isn't part of splitting water, so it's left alone
+        let salt = ItemStack::new("NaCl".parse().unwrap(), 3);
+        factory
+            .buffers_mut(electrolyzer)
+            .unwrap()
+            .input
+            .insert(salt.clone());
+        factory.tick(
+            10.0,
+            1.0,
+            &mut Deposits::default(),
+            &mut RailNetwork::default(),
+        );
+        factory.move_fluids(1.0);
+
+        // Two whole runs of 2 H2O -> 2 H2 + O2 and half of a third
+        let buffers = factory.buffers(electrolyzer).unwrap();
+        let water_left = buffers.fluid.stored().to_f32();
+        assert!((water_left - (10.0 - 3.0 * 0.036)).abs() < 0.001);
+        assert_eq!(buffers.input.stacks(), [salt]);
+        let oxygen = factory.electrolyzers()[0].outlets()[1].contents().unwrap();
+        assert_eq!(oxygen.molecule, "O2".parse().unwrap());
+        assert!((oxygen.liters.to_f32() - 2.0 * 24.06).abs() < 0.1);
+        let hydrogen = factory.buffers(tank).unwrap().fluid.contents().unwrap();
+        assert_eq!(hydrogen.molecule, "H2".parse().unwrap());
+        assert!(hydrogen.liters > Q32_32::ZERO, "hydrogen is piped out");
+
+        let draw = factory.power_draw().get();
+        assert!((draw - 2.0 * 285.8e3 / 0.7 / 4.0).abs() < 1.0, "{draw} W");
+    }
+}
@@ -0,0 +1,359 @@
+//! Toxic gas in the air of a factory, and vents that clear it
+//!
+//! The air is split into a coarse [`GasField`] of cells, each [`GasField::CELL`] meters across.
+//! Gas leaks into the cell it comes out in and spreads to its neighbors over time, but the walls
+//! keep it in, so it builds up until a [`Vent`] takes it out.
+
+use super::{
+    Clearance, Machine, MachineBuffers, MachineKind, MachineSize, MachineStatus, OrientedFootprint,
+    TickContext,
+};
+use crate::{
+    container::{FluidTank, ItemSlots},
+    math::{
+        bounds::{Bounds, FactoryBounds, SpacialBounds},
+        coords::FactoryVector3,
+    },
+    ordinals::Cardinal2D,
+};
+use engine::{
+    draw,
+    draw3d::{self, DebugVis},
+};
+use fixed_point::Q32_32;
+use raylib::prelude::*;
+
+/// How much gas is in each part of a factory
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GasField {
+    /// The factory's bounds, which the cells start at the minimum corner of
+    bounds: FactoryBounds,
+    /// Cells along x, y, and z
+    size: [usize; 3],
+    /// Liters of gas per cubic meter of air in each cell, x first, then z, then y
+    cells: Vec<f32>,
+}
+
+impl GasField {
+    /// Meters across each cell
+    pub const CELL: i16 = 4;
+    /// Cubic meters of air in each cell
+    #[allow(clippy::cast_precision_loss, reason = "cells are a few meters across")]
+    const CELL_VOLUME: f32 = (Self::CELL as i32).pow(3) as f32;
+    /// Square meters per second gas spreads at
+    const DIFFUSION: f32 = 2.0;
+    /// Concentrations below this are harmless and not drawn
+    pub const HARMLESS: f32 = 0.01;
+    /// Concentration drawn at full strength
+    const DEADLY: f32 = 1.0;
+    /// Health lost per second for each liter per cubic meter breathed over [`Self::HARMLESS`]
+    const DAMAGE: f32 = 20.0;
+
+    /// Clean air filling `bounds`
+    pub fn new(bounds: FactoryBounds) -> Self {
+        let extent = bounds.size();
+        let cells = |extent: i16| {
+            usize::from(extent.unsigned_abs().div_ceil(Self::CELL.cast_unsigned())).max(1)
+        };
+        let size = [cells(extent.x), cells(extent.y), cells(extent.z)];
+        Self {
+            bounds,
+            size,
+            cells: vec![0.0; size.iter().product()],
+        }
+    }
+
+    /// Resize to fill `bounds`, keeping the gas where the old and new cells overlap
+    pub fn fit(&mut self, bounds: FactoryBounds) {
+        let mut fitted = Self::new(bounds);
+        for idx in 0..fitted.cells.len() {
+            let center = fitted.center(idx);
+            fitted.cells[idx] = self.concentration(center);
+        }
+        *self = fitted;
+    }
+
+    /// The index of the cell `position` is in, if it's inside the field
+    fn cell_of(&self, position: FactoryVector3) -> Option<usize> {
+        let offset = position - self.bounds.min;
+        let axis = |offset: i16, size: usize| {
+            let cell = usize::try_from(offset.div_euclid(Self::CELL)).ok()?;
+            (offset >= 0 && cell < size).then_some(cell)
+        };
+        let [x, y, z] = [
+            axis(offset.x, self.size[0])?,
+            axis(offset.y, self.size[1])?,
+            axis(offset.z, self.size[2])?,
+        ];
+        Some(x + self.size[0] * (z + self.size[2] * y))
+    }
+
+    /// The cell at `idx`, as x, y, and z
+    const fn coords(&self, idx: usize) -> [usize; 3] {
+        let [width, _, length] = self.size;
+        [idx % width, idx / (width * length), idx / width % length]
+    }
+
+    /// The block at the middle of the cell at `idx`
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap,
+        reason = "there are fewer cells than blocks, which are indexed by i16"
+    )]
+    fn center(&self, idx: usize) -> FactoryVector3 {
+        let [x, y, z] = self
+            .coords(idx)
+            .map(|cell| cell as i16 * Self::CELL + Self::CELL / 2);
+        self.bounds.min + FactoryVector3::new(x, y, z)
+    }
+
+    /// Liters of gas per cubic meter at `position`, which is none outside the factory
+    pub fn concentration(&self, position: FactoryVector3) -> f32 {
+        self.cell_of(position).map_or(0.0, |idx| self.cells[idx])
+    }
+
+    /// Liters of gas in the whole factory
+    pub fn total(&self) -> f32 {
+        self.cells.iter().sum::<f32>() * Self::CELL_VOLUME
+    }
+
+    /// Let `liters` of gas out at `position`
+    pub fn release(&mut self, position: FactoryVector3, liters: f32) {
+        if let Some(idx) = self.cell_of(position) {
+            self.cells[idx] += liters / Self::CELL_VOLUME;
+        }
+    }
+
+    /// Take `fraction` of the gas out of the cell `position` is in, returning how many liters
+    /// were taken
+    pub fn clear(&mut self, position: FactoryVector3, fraction: f32) -> f32 {
+        let Some(idx) = self.cell_of(position) else {
+            return 0.0;
+        };
+        let taken = self.cells[idx] * fraction.clamp(0.0, 1.0);
+        self.cells[idx] -= taken;
+        taken * Self::CELL_VOLUME
+    }
+
+    /// Let gas spread between neighboring cells for `dt` seconds
+    ///
+    /// Gas moves from cells with more to cells with less, and never through the walls.
+    pub fn diffuse(&mut self, dt: f32) {
+        let rate = Self::DIFFUSION * dt / f32::from(Self::CELL).powi(2);
+        // Split up so no cell gives away more than it has to its six neighbors
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "ticks are short"
+        )]
+        let steps = (rate * 6.0).ceil().max(1.0) as u16;
+        let rate = rate / f32::from(steps);
+        let [width, height, length] = self.size;
+        let neighbors = [1, width * length, width];
+        for _ in 0..steps {
+            let before = self.cells.clone();
+            for idx in 0..before.len() {
+                let coords = self.coords(idx);
+                for (axis, stride) in neighbors.into_iter().enumerate() {
+                    if coords[axis] + 1 >= [width, height, length][axis] {
+                        continue;
+                    }
+                    let flow = (before[idx] - before[idx + stride]) * rate;
+                    self.cells[idx] -= flow;
+                    self.cells[idx + stride] += flow;
+                }
+            }
+        }
+    }
+
+    /// Health lost per second breathing the air at `position`
+    pub fn damage(&self, position: FactoryVector3) -> f32 {
+        (self.concentration(position) - Self::HARMLESS).max(0.0) * Self::DAMAGE
+    }
+}
+
+/// Draws every cell with enough gas to be harmful, stronger the more there is, in factory
+/// coordinates
+impl DebugVis for GasField {
+    fn draw(&self, d: &mut draw3d::Renderer<'_>) -> draw::Result {
+        let half = Vector3::splat(f32::from(Self::CELL) * 0.5);
+        for (idx, &concentration) in self.cells.iter().enumerate() {
+            if concentration < Self::HARMLESS {
+                continue;
+            }
+            #[allow(
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss,
+                reason = "clamped to a u8"
+            )]
+            let alpha = (255.0 * (concentration / Self::DEADLY).clamp(0.2, 1.0)) as u8;
+            let center = self.center(idx).as_vec3();
+            d.draw_bounding_box(
+                BoundingBox {
+                    min: center - half,
+                    max: center + half,
+                },
+                Color::new(160, 255, 0, alpha),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Draws gas out of the factory
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vent {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    status: MachineStatus,
+}
+
+impl const Clearance for Vent {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        Self::SIZE
+    }
+}
+
+impl Bounds<FactoryVector3> for Vent {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl Vent {
+    // SAFETY: 1 and 2 are not zero
+    pub const SIZE: MachineSize = unsafe { MachineSize::new_unchecked(2, 1, 2) };
+
+    /// Fraction of the gas around it taken out per second
+    pub const RATE: f32 = 0.5;
+
+    pub const fn new(position: FactoryVector3, rotation: Cardinal2D) -> Self {
+        Self {
+            position,
+            rotation,
+            status: MachineStatus::Idle,
+        }
+    }
+}
+
+impl Machine for Vent {
+    fn name(&self) -> String {
+        MachineKind::Vent.name()
+    }
+
+    /// Working as hard as the air around it is bad
+    fn status(&self) -> MachineStatus {
+        self.status
+    }
+
+    /// Clears the air the other machines leak gas into
+    fn shares_world(&self) -> bool {
+        true
+    }
+
+    fn tick(&mut self, dt: f32, ctx: &mut TickContext<'_>) {
+        let Some(world) = &mut ctx.world else {
+            return;
+        };
+        // Drawn in through the middle
+        let intake = self.bounds().mid();
+        let concentration = world.gas.concentration(intake);
+        world.gas.clear(intake, 1.0 - (-Self::RATE * dt).exp());
+        self.status = if concentration < GasField::HARMLESS {
+            MachineStatus::Idle
+        } else {
+            MachineStatus::Working {
+                progress: (concentration / GasField::DEADLY).min(1.0),
+            }
+        };
+    }
+
+    /// Nothing goes in or comes out
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(0, 0),
+            output: ItemSlots::new(0, 0),
+            fluid: FluidTank::new(Q32_32::from_i32(0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::coords::RailVector3;
+    use crate::region::{
+        factory::Factory,
+        rail::{deposit::Deposits, network::RailNetwork},
+    };
+
+    fn bounds() -> FactoryBounds {
+        FactoryBounds {
+            min: FactoryVector3::new(-8, 0, 0),
+            max: FactoryVector3::new(8, 8, 10),
+        }
+    }
+
+    #[test]
+    fn test_gas_spreads() {
+        let mut gas = GasField::new(bounds());
+        assert_eq!(gas.size, [4, 2, 3]);
+        let leak = FactoryVector3::new(-8, 0, 0);
+        let far = FactoryVector3::new(7, 7, 9);
+        gas.release(leak, 640.0);
+        assert!((gas.concentration(leak) - 10.0).abs() < 1e-6);
+        assert!(gas.concentration(FactoryVector3::new(-9, 0, 0)).abs() < f32::EPSILON);
+
+        for _ in 0..10_000 {
+            gas.diffuse(0.1);
+        }
+        assert!((gas.total() - 640.0).abs() < 1e-2, "walls keep gas in");
+        assert!(
+            (gas.concentration(leak) - gas.concentration(far)).abs() < 1e-3,
+            "gas spreads evenly"
+        );
+
+        gas.fit(FactoryBounds {
+            max: FactoryVector3::new(8, 8, 20),
+            ..bounds()
+        });
+        assert!(
+            (gas.concentration(far) - 640.0 / (24.0 * 64.0)).abs() < 1e-3,
+            "gas stays where it was"
+        );
+        assert!(gas.concentration(FactoryVector3::new(7, 7, 19)).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_vent() {
+        let mut factory = Factory::new(RailVector3::default(), bounds());
+        let position = FactoryVector3::new(0, 0, 4);
+        let vent = factory.add_vent(Vent::new(position, Cardinal2D::East));
+        factory.gas_mut().release(position, 100.0);
+        let before = factory.gas().total();
+        factory.tick(
+            1.0,
+            1.0,
+            &mut Deposits::default(),
+            &mut RailNetwork::default(),
+        );
+        assert!(factory.gas().total() < before);
+        assert!(matches!(
+            factory.machine(vent).unwrap().status(),
+            MachineStatus::Working { .. }
+        ));
+        for _ in 0..600 {
+            factory.tick(
+                1.0,
+                1.0,
+                &mut Deposits::default(),
+                &mut RailNetwork::default(),
+            );
+        }
+        assert!(factory.gas().total() < 1.0, "vents clear the whole factory");
+        assert_eq!(factory.machine(vent).unwrap().status(), MachineStatus::Idle);
+    }
+}
@@ -1,19 +1,141 @@
 use crate::{
-    math::coords::{FactoryVector3, PlayerVector3},
-    region::factory::Factory,
+    math::{
+        bounds::FactoryBounds,
+        coords::{FactoryVector3, PlayerVector3, VectorConstants},
+    },
+    region::factory::{Factory, FactoryCollision},
     resource::Resources,
     rl_helpers::DynRaylibDraw3D,
 };
 use raylib::prelude::*;
-use std::time::{Duration, Instant};
+use std::{
+    num::NonZeroU8,
+    ops::RangeInclusive,
+    time::{Duration, Instant},
+};
+
+/// An axis-aligned plane in factory coordinates, on whole meters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GridPlane {
+    /// Horizontal, at height `y`
+    Xz { y: i16 },
+    /// Vertical, facing east and west, at `x`
+    Yz { x: i16 },
+    /// Vertical, facing north and south, at `z`
+    Xy { z: i16 },
+}
+
+impl Default for GridPlane {
+    #[inline]
+    fn default() -> Self {
+        Self::FLOOR
+    }
+}
+
+impl GridPlane {
+    /// The factory floor
+    pub const FLOOR: Self = Self::Xz { y: 0 };
+
+    /// The plane of the face `collision` hit
+    ///
+    /// Machines are boxes on whole meters, so their faces always lie on a grid plane.
+    pub fn from_collision(collision: &FactoryCollision<'_>) -> Self {
+        let Vector3 { x, y, z } = collision.point.round();
+        let normal = collision.normal.abs();
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "collisions are inside the factory, which is indexed by i16"
+        )]
+        if normal.x > normal.y && normal.x > normal.z {
+            Self::Yz { x: x as i16 }
+        } else if normal.z > normal.y {
+            Self::Xy { z: z as i16 }
+        } else {
+            Self::Xz { y: y as i16 }
+        }
+    }
+
+    /// The point on the plane at `(u, v)` in the plane's own axes
+    fn point(self, u: f32, v: f32) -> Vector3 {
+        match self {
+            Self::Xz { y } => Vector3::new(u, y.into(), v),
+            Self::Yz { x } => Vector3::new(x.into(), u, v),
+            Self::Xy { z } => Vector3::new(u, v, z.into()),
+        }
+    }
+
+    /// `point` projected onto the plane's own axes
+    const fn project(self, point: Vector3) -> Vector2 {
+        match self {
+            Self::Xz { .. } => Vector2::new(point.x, point.z),
+            Self::Yz { .. } => Vector2::new(point.y, point.z),
+            Self::Xy { .. } => Vector2::new(point.x, point.y),
+        }
+    }
+
+    /// The colors of lines across the plane's first and second axes, matching the world axes
+    const fn colors(self) -> [Color; 2] {
+        match self {
+            Self::Xz { .. } => [Color::RED, Color::BLUE],
+            Self::Yz { .. } => [Color::GREEN, Color::BLUE],
+            Self::Xy { .. } => [Color::RED, Color::GREEN],
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct GridVisualizer {
     pub start_time: Instant,
+    /// Lines per meter, e.g. 2 for a line every half meter
+    pub subdivisions: NonZeroU8,
+    pub plane: GridPlane,
+    /// Distance from the player at which lines have faded out completely, in meters
+    pub fade_distance: f32,
+    /// Space the machine being placed would take up, if placing one
+    pub footprint: Option<FactoryBounds>,
 }
 
 impl GridVisualizer {
     const ANIMATION_TIME: Duration = Duration::from_millis(500);
+    /// How far the grid extends from the player once fully grown, in meters
+    const GRID_SIZE_MAX: f32 = 20.0;
+    /// Lines are drawn in pieces of this length so each piece can fade on its own, in meters
+    const SEGMENT_LENGTH: f32 = 1.0;
+
+    /// A 1m grid on the floor that fades out at its edges
+    pub const fn new(start_time: Instant) -> Self {
+        Self {
+            start_time,
+            subdivisions: NonZeroU8::MIN,
+            plane: GridPlane::FLOOR,
+            fade_distance: Self::GRID_SIZE_MAX,
+            footprint: None,
+        }
+    }
+
+    /// Meters between lines
+    fn step(&self) -> f32 {
+        1.0 / f32::from(self.subdivisions.get())
+    }
+
+    /// Whether line number `line` is on a whole meter, and so drawn brighter
+    fn is_major(&self, line: i32) -> bool {
+        line.rem_euclid(self.subdivisions.get().into()) == 0
+    }
+
+    /// The lines along one of the plane's axes within `reach` of `center` and from `min` to
+    /// `max`, numbered by how many steps of `step` meters they are from the factory origin
+    ///
+    /// Empty if none are.
+    fn lines(center: f32, reach: f32, (min, max): (f32, f32), step: f32) -> RangeInclusive<i32> {
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "lines are inside the factory, which is indexed by i16"
+        )]
+        let range = ((center - reach).max(min) / step).ceil() as i32
+            ..=((center + reach).min(max) / step).floor() as i32;
+        range
+    }
 
     pub fn draw(
         &self,
@@ -23,45 +145,117 @@ impl GridVisualizer {
         player_pos: &PlayerVector3,
         factory: &Factory,
     ) {
-        const GRID_SIZE_MAX: i16 = 20;
-
         let t = self.start_time.elapsed().as_secs_f32();
         let grid_size = if t >= 1.0 {
-            GRID_SIZE_MAX
+            Self::GRID_SIZE_MAX
         } else {
-            #[allow(clippy::cast_possible_truncation, reason = "this is intentional")]
-            {
-                ease::circ_in_out(
-                    self.start_time.elapsed().as_secs_f32(),
-                    0.0,
-                    GRID_SIZE_MAX.into(),
-                    1.0,
-                ) as i16
-            }
+            ease::circ_in_out(t, 0.0, Self::GRID_SIZE_MAX, 1.0)
         };
 
         let origin = &factory.origin;
-        let position_in_factory = player_pos.to_factory(origin).unwrap();
-
-        let x_min = (position_in_factory.x - grid_size).max(factory.bounds.min.x);
-        let x_max = (position_in_factory.x + grid_size).min(factory.bounds.max.x);
-        let z_min = (position_in_factory.z - grid_size).max(factory.bounds.min.z);
-        let z_max = (position_in_factory.z + grid_size).min(factory.bounds.max.z);
-
-        for x in x_min..=x_max {
-            d.draw_line3D(
-                FactoryVector3 { x, y: 0, z: z_min }.to_player_relative(player_pos, origin),
-                FactoryVector3 { x, y: 0, z: z_max }.to_player_relative(player_pos, origin),
-                Color::RED,
+        // Everything is drawn in factory coordinates and moved by this to be relative to the player
+        let offset = FactoryVector3::ZERO.to_player_relative(player_pos, origin);
+        let player = -offset;
+
+        let plane = self.plane;
+        let bounds_min = plane.project(factory.bounds.min.as_vec3());
+        let bounds_max = plane.project(factory.bounds.max.as_vec3());
+        let center = plane.project(player);
+        let step = self.step();
+        let us = Self::lines(center.x, grid_size, (bounds_min.x, bounds_max.x), step);
+        let vs = Self::lines(center.y, grid_size, (bounds_min.y, bounds_max.y), step);
+        #[allow(clippy::cast_precision_loss, reason = "line numbers are small")]
+        let (min, max) = (
+            Vector2::new(*us.start() as f32, *vs.start() as f32) * step,
+            Vector2::new(*us.end() as f32, *vs.end() as f32) * step,
+        );
+
+        let fade = |point: Vector3| (1.0 - point.distance(player) / self.fade_distance).max(0.0);
+        let [u_color, v_color] = plane.colors();
+        let mut draw_line = |start: Vector3, end: Vector3, color: Color, is_major: bool| {
+            let length = start.distance(end);
+            #[allow(
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss,
+                reason = "lines are at most twice the grid size long"
+            )]
+            let segments = (length / Self::SEGMENT_LENGTH).ceil().max(1.0) as u32;
+            #[allow(clippy::cast_precision_loss, reason = "segment counts are small")]
+            for i in 0..segments {
+                let a = start.lerp(end, i as f32 / segments as f32);
+                let b = start.lerp(end, (i + 1) as f32 / segments as f32);
+                let alpha = fade(a.midpoint(b)) * if is_major { 1.0 } else { 0.4 };
+                if alpha > 0.0 {
+                    d.draw_line3D(a + offset, b + offset, color.alpha(alpha));
+                }
+            }
+        };
+
+        #[allow(clippy::cast_precision_loss, reason = "line numbers are small")]
+        for i in us {
+            let u = i as f32 * step;
+            draw_line(
+                plane.point(u, min.y),
+                plane.point(u, max.y),
+                u_color,
+                self.is_major(i),
+            );
+        }
+        #[allow(clippy::cast_precision_loss, reason = "line numbers are small")]
+        for i in vs {
+            let v = i as f32 * step;
+            draw_line(
+                plane.point(min.x, v),
+                plane.point(max.x, v),
+                v_color,
+                self.is_major(i),
             );
         }
 
-        for z in z_min..=z_max {
-            d.draw_line3D(
-                FactoryVector3 { x: x_min, y: 0, z }.to_player_relative(player_pos, origin),
-                FactoryVector3 { x: x_max, y: 0, z }.to_player_relative(player_pos, origin),
-                Color::BLUE,
+        if let Some(footprint) = self.footprint {
+            let min = footprint.min.as_vec3();
+            let max = footprint.max.as_vec3();
+            let size = max - min;
+            // Just above the floor so it isn't hidden by it
+            let floor = Vector3::new(0.5 * (min.x + max.x), min.y + 0.01, 0.5 * (min.z + max.z));
+            d.draw_plane(
+                floor + offset,
+                Vector2::new(size.x, size.z),
+                Color::YELLOW.alpha(0.25),
+            );
+            d.draw_bounding_box(
+                BoundingBox {
+                    min: min + offset,
+                    max: max + offset,
+                },
+                Color::YELLOW,
             );
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let mut grid = GridVisualizer::new(Instant::now());
+        assert!((grid.step() - 1.0).abs() < f32::EPSILON);
+        assert!(grid.is_major(-3));
+        grid.subdivisions = NonZeroU8::new(2).unwrap();
+        assert!((grid.step() - 0.5).abs() < f32::EPSILON);
+        assert!(grid.is_major(-2) && grid.is_major(4));
+        assert!(!grid.is_major(-3) && !grid.is_major(1));
+
+        let bounds = (-10.0, 10.0);
+        assert_eq!(GridVisualizer::lines(0.3, 2.0, bounds, 1.0), -1..=2);
+        assert_eq!(GridVisualizer::lines(0.3, 2.0, bounds, 0.5), -3..=4);
+        assert_eq!(
+            GridVisualizer::lines(9.0, 4.0, bounds, 1.0),
+            5..=10,
+            "lines stop at the edge of the factory"
+        );
+        assert!(GridVisualizer::lines(30.0, 4.0, bounds, 1.0).is_empty());
+    }
+}
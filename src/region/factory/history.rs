@@ -0,0 +1,542 @@
+//! Taking back building and demolishing in factories
+//!
+//! Each edit to a factory is recorded as a [`Change`], which [`History`] keeps so it can be
+//! undone and redone. Undoing building something demolishes it, and undoing demolishing
+//! something builds it again as it was, so each change turns into the other when it's undone.
+//!
+//! Whatever was in something demolished goes to the player, who has to give it back to build it
+//! again. Anything that didn't fit in the inventory and was lost comes back with it.
+//!
+//! Things built again are new entities with new ids, so the ids in the rest of the history and
+//! in the factory's [circuit](super::circuit) are changed to match.
+
+use super::{
+    Assembler, Belt, ChargingPad, Controller, Crystallizer, Distiller, Electrolyzer, Factory,
+    Ladder, MachineBuffers, MachineId, Merger, Miner, NodeTable, Pipe, PluginMachine, Reactor,
+    ReliefValve, Splitter, Station, Tank, Thermal, Vent, Vessel,
+};
+use crate::{
+    container::{Container, FluidTank, ItemSlots, ItemStack},
+    ecs::{Components, Entity},
+    math::bounds::FactoryBounds,
+};
+use engine::animation::Animator;
+use std::collections::VecDeque;
+
+/// Everything an entity had when it was taken out of a factory, to put it back with
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityRow {
+    /// The id it had
+    pub entity: Entity,
+    /// Where it was in the spatial index, if it's a machine
+    pub bounds: Option<FactoryBounds>,
+    reactor: Option<Reactor>,
+    miner: Option<Miner>,
+    splitter: Option<Splitter>,
+    merger: Option<Merger>,
+    station: Option<Station>,
+    vent: Option<Vent>,
+    plugin_machine: Option<PluginMachine>,
+    controller: Option<Controller>,
+    assembler: Option<Assembler>,
+    tank: Option<Tank>,
+    charging_pad: Option<ChargingPad>,
+    relief_valve: Option<ReliefValve>,
+    distiller: Option<Distiller>,
+    crystallizer: Option<Crystallizer>,
+    electrolyzer: Option<Electrolyzer>,
+    belt: Option<Belt>,
+    belt_items: Option<ItemSlots>,
+    pipe: Option<Pipe>,
+    pipe_fluid: Option<FluidTank>,
+    buffers: Option<MachineBuffers>,
+    thermal: Option<Thermal>,
+    vessel: Option<Vessel>,
+    node_settings: Option<NodeTable>,
+    disabled: bool,
+}
+
+impl EntityRow {
+    /// The items on the belt or in the machine
+    pub fn items(&self) -> impl Iterator<Item = &ItemStack> {
+        (self.belt_items.iter().flat_map(ItemSlots::stacks)).chain(
+            (self.buffers.iter())
+                .flat_map(|buffers| buffers.input.stacks().iter().chain(buffers.output.stacks())),
+        )
+    }
+}
+
+/// Whether each of `rows` could be put back in `factory` without overlapping anything
+///
+/// Belts and pipes may end inside machines, like when they were routed.
+fn fits(factory: &Factory, rows: &[EntityRow]) -> bool {
+    rows.iter().all(|row| {
+        (row.bounds.as_ref()).is_none_or(|bounds| factory.is_vacant(bounds))
+            && (row.belt.iter().map(|belt| belt.path.as_slice()))
+                .chain(row.pipe.iter().map(|pipe| pipe.path.as_slice()))
+                .all(|path| factory.is_route_vacant(path))
+    })
+}
+
+/// Give everything in `rows` to `inventory`
+///
+/// Returns what fit, and how many items didn't and were lost.
+pub fn refund(rows: &[EntityRow], inventory: &mut ItemSlots) -> (Vec<ItemStack>, u32) {
+    let mut refunded = Vec::new();
+    let mut lost = 0u32;
+    for stack in rows.iter().flat_map(EntityRow::items) {
+        let rest = inventory.insert(stack.clone()).map_or(0, |rest| rest.count);
+        lost = lost.saturating_add(rest);
+        if rest < stack.count {
+            refunded.push(ItemStack::new(stack.item.clone(), stack.count - rest));
+        }
+    }
+    (refunded, lost)
+}
+
+impl Factory {
+    /// Remove `entity` and every component it has, returning them, or [`None`] if it's already
+    /// gone
+    pub(super) fn take(&mut self, entity: Entity) -> Option<EntityRow> {
+        if !self.entities.is_alive(entity) {
+            return None;
+        }
+        let row = EntityRow {
+            entity,
+            bounds: self.index.remove(&MachineId(entity)),
+            reactor: self.reactors.remove(entity),
+            miner: self.miners.remove(entity),
+            splitter: self.splitters.remove(entity),
+            merger: self.mergers.remove(entity),
+            station: self.stations.remove(entity),
+            vent: self.vents.remove(entity),
+            plugin_machine: self.plugin_machines.remove(entity),
+            controller: self.controllers.remove(entity),
+            assembler: self.assemblers.remove(entity),
+            tank: self.tanks.remove(entity),
+            charging_pad: self.charging_pads.remove(entity),
+            relief_valve: self.relief_valves.remove(entity),
+            distiller: self.distillers.remove(entity),
+            crystallizer: self.crystallizers.remove(entity),
+            electrolyzer: self.electrolyzers.remove(entity),
+            belt: self.belts.remove(entity),
+            belt_items: self.belt_items.remove(entity),
+            pipe: self.pipes.remove(entity),
+            pipe_fluid: self.pipe_fluids.remove(entity),
+            buffers: self.buffers.remove(entity),
+            thermal: self.thermals.remove(entity),
+            vessel: self.vessels.remove(entity),
+            node_settings: self.node_settings.remove(entity),
+            disabled: self.disabled.remove(entity).is_some(),
+        };
+        self.animators.remove(entity);
+        self.lod.forget(entity);
+        self.entities.despawn(entity);
+        Some(row)
+    }
+
+    /// Put back what [`Self::take`] took as a new entity, returning its id
+    ///
+    /// Doesn't check that there's room for it.
+    fn restore(&mut self, row: EntityRow) -> Entity {
+        fn insert<T>(store: &mut Components<T>, entity: Entity, component: Option<T>) {
+            if let Some(component) = component {
+                store.insert(entity, component);
+            }
+        }
+
+        let entity = self.entities.spawn();
+        insert(&mut self.reactors, entity, row.reactor);
+        insert(&mut self.miners, entity, row.miner);
+        insert(&mut self.splitters, entity, row.splitter);
+        insert(&mut self.mergers, entity, row.merger);
+        insert(&mut self.stations, entity, row.station);
+        insert(&mut self.vents, entity, row.vent);
+        insert(&mut self.plugin_machines, entity, row.plugin_machine);
+        insert(&mut self.controllers, entity, row.controller);
+        insert(&mut self.assemblers, entity, row.assembler);
+        insert(&mut self.tanks, entity, row.tank);
+        insert(&mut self.charging_pads, entity, row.charging_pad);
+        insert(&mut self.relief_valves, entity, row.relief_valve);
+        insert(&mut self.distillers, entity, row.distiller);
+        insert(&mut self.crystallizers, entity, row.crystallizer);
+        insert(&mut self.electrolyzers, entity, row.electrolyzer);
+        insert(&mut self.belts, entity, row.belt);
+        insert(&mut self.belt_items, entity, row.belt_items);
+        insert(&mut self.pipes, entity, row.pipe);
+        insert(&mut self.pipe_fluids, entity, row.pipe_fluid);
+        insert(&mut self.buffers, entity, row.buffers);
+        insert(&mut self.thermals, entity, row.thermal);
+        insert(&mut self.vessels, entity, row.vessel);
+        insert(&mut self.node_settings, entity, row.node_settings);
+        insert(&mut self.disabled, entity, row.disabled.then_some(()));
+        if let Some(bounds) = row.bounds {
+            self.index.insert(MachineId(entity), bounds);
+        }
+        if self
+            .machine(MachineId(entity))
+            .is_some_and(|machine| machine.model().is_some())
+        {
+            self.animators.insert(entity, Animator::new());
+        }
+        entity
+    }
+
+    /// Remove the machine identified by `id` and every belt and pipe connected to it, returning
+    /// everything they had, or [`None`] if there's no such machine
+    pub fn take_machine(&mut self, id: MachineId) -> Option<Vec<EntityRow>> {
+        self.machine(id)?;
+        Some(
+            (self.attached(id).into_iter().chain([id.0]))
+                .filter_map(|entity| self.take(entity))
+                .collect(),
+        )
+    }
+
+    /// Remove a ladder exactly like `ladder`, returning `false` if there isn't one
+    pub fn remove_ladder(&mut self, ladder: Ladder) -> bool {
+        let Some(idx) = self.ladders.iter().position(|&other| other == ladder) else {
+            return false;
+        };
+        self.ladders.remove(idx);
+        true
+    }
+}
+
+/// Why a [`Change`] couldn't be undone or redone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoError {
+    /// Something has been built where it would go
+    Blocked,
+    /// The player doesn't have everything that was in it to give back
+    MissingItems,
+}
+
+impl std::fmt::Display for UndoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Blocked => f.write_str("something is in the way"),
+            Self::MissingItems => f.write_str("the items it held aren't in the inventory"),
+        }
+    }
+}
+
+impl std::error::Error for UndoError {}
+
+/// Something built or demolished in a factory
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Built {
+        entities: Vec<Entity>,
+        ladders: Vec<Ladder>,
+    },
+    Demolished {
+        rows: Vec<EntityRow>,
+        ladders: Vec<Ladder>,
+        /// What the player was given of what they held
+        refunded: Vec<ItemStack>,
+    },
+}
+
+impl Change {
+    /// Building the machine identified by `id`
+    pub fn machine(id: MachineId) -> Self {
+        Self::Built {
+            entities: vec![id.0],
+            ladders: Vec::new(),
+        }
+    }
+
+    /// Whether nothing was built or demolished
+    pub const fn is_empty(&self) -> bool {
+        match self {
+            Self::Built { entities, ladders } => entities.is_empty() && ladders.is_empty(),
+            Self::Demolished { rows, ladders, .. } => rows.is_empty() && ladders.is_empty(),
+        }
+    }
+
+    /// Whether the change could be taken back now
+    fn check(&self, factory: &Factory, inventory: &ItemSlots) -> Result<(), UndoError> {
+        let Self::Demolished { rows, refunded, .. } = self else {
+            return Ok(());
+        };
+        if !fits(factory, rows) {
+            return Err(UndoError::Blocked);
+        }
+        if !inventory.clone().take_all(refunded) {
+            return Err(UndoError::MissingItems);
+        }
+        Ok(())
+    }
+
+    /// Take the change back, returning the change that would take that back, and the old and
+    /// new id of everything built again
+    fn revert(
+        self,
+        factory: &mut Factory,
+        inventory: &mut ItemSlots,
+    ) -> (Self, Vec<(Entity, Entity)>) {
+        match self {
+            Self::Built { entities, ladders } => {
+                let rows: Vec<_> = (entities.into_iter())
+                    .filter_map(|entity| factory.take(entity))
+                    .collect();
+                let ladders = (ladders.into_iter())
+                    .filter(|&ladder| factory.remove_ladder(ladder))
+                    .collect();
+                let (refunded, _) = refund(&rows, inventory);
+                let demolished = Self::Demolished {
+                    rows,
+                    ladders,
+                    refunded,
+                };
+                (demolished, Vec::new())
+            }
+            Self::Demolished {
+                rows,
+                ladders,
+                refunded,
+            } => {
+                // Already checked that it's all there
+                inventory.take_all(&refunded);
+                let ids: Vec<_> = (rows.into_iter())
+                    .map(|row| (row.entity, factory.restore(row)))
+                    .collect();
+                for &ladder in &ladders {
+                    factory.add_ladder(ladder);
+                }
+                for &(old, new) in &ids {
+                    factory
+                        .circuit
+                        .replace_machine(MachineId(old), MachineId(new));
+                }
+                let built = Self::Built {
+                    entities: ids.iter().map(|&(_, new)| new).collect(),
+                    ladders,
+                };
+                (built, ids)
+            }
+        }
+    }
+
+    /// Refer to whatever had the old id of each pair by the new one
+    fn replace_ids(&mut self, ids: &[(Entity, Entity)]) {
+        let replace = |entity: &mut Entity| {
+            if let Some(&(_, new)) = ids.iter().find(|&&(old, _)| old == *entity) {
+                *entity = new;
+            }
+        };
+        match self {
+            Self::Built { entities, .. } => {
+                for entity in entities {
+                    replace(entity);
+                }
+            }
+            Self::Demolished { rows, .. } => {
+                for row in rows {
+                    replace(&mut row.entity);
+                }
+            }
+        }
+    }
+}
+
+/// A [`Change`] to the factory at some index
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub factory: usize,
+    pub change: Change,
+}
+
+/// Every edit made to factories, so they can be undone and redone
+///
+/// The oldest edits are forgotten once there are more than [`Self::LIMIT`].
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl History {
+    /// How many edits can be undone
+    pub const LIMIT: usize = 100;
+
+    pub const fn new() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Remember `change` to the factory at `factory`, which has already been made
+    ///
+    /// Anything that was undone can no longer be redone.
+    pub fn record(&mut self, factory: usize, change: Change) {
+        if change.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push_back(Edit { factory, change });
+        if self.undo.len() > Self::LIMIT {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Take back the most recent edit, returning `false` if there was nothing to undo
+    ///
+    /// Items taken out of the factory go to `inventory`, and items put back come out of it.
+    pub fn undo(
+        &mut self,
+        factories: &mut [Factory],
+        inventory: &mut ItemSlots,
+    ) -> Result<bool, UndoError> {
+        let Some(edit) = self.undo.back() else {
+            return Ok(false);
+        };
+        edit.change.check(&factories[edit.factory], inventory)?;
+        let edit = self.undo.pop_back().expect("just checked");
+        let redo = self.revert(edit, factories, inventory);
+        self.redo.push(redo);
+        Ok(true)
+    }
+
+    /// Make the most recently undone edit again, returning `false` if there was nothing to redo
+    ///
+    /// Items taken out of the factory go to `inventory`, and items put back come out of it.
+    pub fn redo(
+        &mut self,
+        factories: &mut [Factory],
+        inventory: &mut ItemSlots,
+    ) -> Result<bool, UndoError> {
+        let Some(edit) = self.redo.last() else {
+            return Ok(false);
+        };
+        edit.change.check(&factories[edit.factory], inventory)?;
+        let edit = self.redo.pop().expect("just checked");
+        let undo = self.revert(edit, factories, inventory);
+        self.undo.push_back(undo);
+        Ok(true)
+    }
+
+    /// Take back `edit`, returning the edit that takes that back
+    fn revert(&mut self, edit: Edit, factories: &mut [Factory], inventory: &mut ItemSlots) -> Edit {
+        let (change, ids) = edit.change.revert(&mut factories[edit.factory], inventory);
+        if !ids.is_empty() {
+            for other in (self.undo.iter_mut().chain(&mut self.redo))
+                .filter(|other| other.factory == edit.factory)
+            {
+                other.change.replace_ids(&ids);
+            }
+        }
+        Edit {
+            factory: edit.factory,
+            change,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::coords::{FactoryVector3, RailVector3},
+        ordinals::Cardinal2D,
+        region::factory::{
+            BeltLevel, Machine,
+            routing::{RouteRules, find_route},
+        },
+    };
+
+    #[test]
+    fn test_undo_redo() {
+        let mut factories = vec![Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-10, 0, -10),
+                max: FactoryVector3::new(10, 8, 10),
+            },
+        )];
+        let mut inventory = ItemSlots::new(4, 100);
+        let mut history = History::new();
+        let factory = &mut factories[0];
+        let reactor = Reactor {
+            position: FactoryVector3::new(0, 0, 0),
+            rotation: Cardinal2D::East,
+        };
+        let (src, dst) = (reactor.belt_outputs()[0], reactor.belt_inputs()[0]);
+        let id = factory.add_reactor(reactor.clone());
+        history.record(0, Change::machine(id));
+        let path = find_route(factory, src.0.position, dst.0.position, RouteRules::BELT).unwrap();
+        let belt = factory.add_belt(Belt {
+            level: BeltLevel::Mk1,
+            src,
+            dst,
+            path,
+        });
+        history.record(
+            0,
+            Change::Built {
+                entities: vec![belt],
+                ladders: Vec::new(),
+            },
+        );
+
+        let water = ItemStack::new("H2O".parse().unwrap(), 5);
+        _ = factory
+            .buffers
+            .get_mut(id.0)
+            .unwrap()
+            .input
+            .insert(water.clone());
+        let rows = factory.take_machine(id).unwrap();
+        assert_eq!(rows.len(), 2, "the belt goes with it");
+        let (refunded, lost) = refund(&rows, &mut inventory);
+        assert_eq!(
+            (refunded.as_slice(), lost),
+            (std::slice::from_ref(&water), 0)
+        );
+        history.record(
+            0,
+            Change::Demolished {
+                rows,
+                ladders: Vec::new(),
+                refunded,
+            },
+        );
+
+        let mut empty = ItemSlots::new(4, 100);
+        assert_eq!(
+            history.undo(&mut factories, &mut empty),
+            Err(UndoError::MissingItems)
+        );
+        let blocker = factories[0].add_reactor(reactor);
+        assert_eq!(
+            history.undo(&mut factories, &mut inventory),
+            Err(UndoError::Blocked)
+        );
+        factories[0].take(blocker.0);
+
+        assert_eq!(history.undo(&mut factories, &mut inventory), Ok(true));
+        assert_eq!(inventory.stacks(), []);
+        let id = MachineId(factories[0].reactors.entities()[0]);
+        assert_eq!(
+            factories[0].buffers.get(id.0).unwrap().input.stacks(),
+            [water]
+        );
+        assert_eq!(factories[0].belts().len(), 1);
+
+        // Built again with a new id, which undoing building it has to find
+        assert_eq!(history.undo(&mut factories, &mut inventory), Ok(true));
+        assert_eq!(factories[0].belts().len(), 0);
+        assert_eq!(history.undo(&mut factories, &mut inventory), Ok(true));
+        assert_eq!(factories[0].reactors().len(), 0);
+        assert_eq!(history.undo(&mut factories, &mut inventory), Ok(false));
+
+        assert_eq!(history.redo(&mut factories, &mut inventory), Ok(true));
+        assert_eq!(history.redo(&mut factories, &mut inventory), Ok(true));
+        assert_eq!(factories[0].belts().len(), 1);
+        assert_eq!(history.redo(&mut factories, &mut inventory), Ok(true));
+        assert_eq!(factories[0].reactors().len(), 0);
+        assert_eq!(history.redo(&mut factories, &mut inventory), Ok(false));
+    }
+}
@@ -0,0 +1,187 @@
+//! Ticking machines far from the player less often
+//!
+//! Machines within [`UpdateLod::NEAR`] meters of the player are ticked every tick. Farther ones
+//! are ticked once every [`UpdateLod::COARSE_TICKS`] ticks with all the time since, staggered so
+//! about as many come due each tick, and their animations are left where they are since nobody's
+//! there to see them.
+//!
+//! Nothing they make is lost to this: recipes, mining, and heat carry time over from one step to
+//! the next, so one long step comes out the same as many short ones, and a machine the player
+//! comes near is caught up with everything it's behind on right away.
+
+use crate::{
+    ecs::{Components, Entity},
+    math::{
+        bounds::FactoryBounds,
+        coords::{PlayerCoord, PlayerVector3, RailVector3},
+    },
+};
+
+/// How often each of a factory's machines is ticked, depending on how close the player is
+#[derive(Debug, Clone, Default)]
+pub struct UpdateLod {
+    /// Where the player is, or `None` to tick every machine every tick
+    pub viewer: Option<PlayerVector3>,
+    /// Seconds each machine that's waiting for its next coarse tick hasn't been ticked for
+    behind: Components<f32>,
+    /// Ticks so far, for staggering coarse ticks
+    ticks: usize,
+}
+
+impl UpdateLod {
+    /// Meters from the player within which machines are ticked every tick, a little past where
+    /// they can still be heard
+    pub const NEAR: f32 = 48.0;
+    /// How many ticks apart machines farther than [`Self::NEAR`] are ticked
+    pub const COARSE_TICKS: usize = 8;
+
+    pub const fn new() -> Self {
+        Self {
+            viewer: None,
+            behind: Components::new(),
+            ticks: 0,
+        }
+    }
+
+    /// Whether a machine taking up `bounds` in a factory at `origin` is close enough to the
+    /// player to be ticked every tick
+    pub fn is_near(&self, bounds: FactoryBounds, origin: &RailVector3) -> bool {
+        let Some(viewer) = self.viewer else {
+            return true;
+        };
+        let center = (bounds.min.to_player(origin))
+            .plus(bounds.max.to_player(origin))
+            .scale(PlayerCoord::from_f32(0.5));
+        center.minus(viewer).to_vec3().length() <= Self::NEAR
+    }
+
+    /// How many seconds to tick `entity`, the `idx`th machine, for a tick of `dt`, or `None` if
+    /// it waits for a later one
+    pub fn step(&mut self, entity: Entity, idx: usize, near: bool, dt: f32) -> Option<f32> {
+        let due = (self.ticks.wrapping_add(idx)).is_multiple_of(Self::COARSE_TICKS);
+        if near || due {
+            Some(self.behind.remove(entity).unwrap_or(0.0) + dt)
+        } else {
+            if let Some(behind) = self.behind.get_mut(entity) {
+                *behind += dt;
+            } else {
+                self.behind.insert(entity, dt);
+            }
+            None
+        }
+    }
+
+    /// Seconds `entity` hasn't been ticked for
+    pub fn behind(&self, entity: Entity) -> f32 {
+        self.behind.get(entity).copied().unwrap_or(0.0)
+    }
+
+    /// Move on to the next tick, once every machine has been stepped
+    pub const fn finish_tick(&mut self) {
+        self.ticks = self.ticks.wrapping_add(1);
+    }
+
+    /// Stop keeping track of `entity`, once it's been removed
+    pub fn forget(&mut self, entity: Entity) {
+        self.behind.remove(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chem::reaction::Reaction,
+        container::{Container, ItemStack},
+        math::coords::{FactoryVector3, VectorConstants},
+        ordinals::Cardinal2D,
+        plugin::MachineType,
+        region::{
+            factory::{Factory, MachineId, MachineSize, plugin::PluginMachine, recipe::Recipe},
+            rail::{deposit::Deposits, network::RailNetwork},
+        },
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn test_far_machines_catch_up() {
+        let reaction = "CaCO3 -> CaO + CO2".parse::<Reaction>().unwrap();
+        let kiln = &*Box::leak(Box::new(MachineType {
+            name: "kiln",
+            label: "Kiln",
+            size: MachineSize::new(2, 2, 2).unwrap(),
+            recipe: Some(Recipe::new(
+                "Calcination",
+                reaction.balance().unwrap(),
+                Duration::from_secs(2),
+            )),
+            thermal: None,
+            vessel: None,
+            model: None,
+        }));
+        let build = |viewer: Option<PlayerVector3>| {
+            let mut factory = Factory::new(
+                RailVector3::default(),
+                FactoryBounds {
+                    min: FactoryVector3::new(0, 0, 0),
+                    max: FactoryVector3::new(32, 4, 4),
+                },
+            );
+            factory.lod.viewer = viewer;
+            let ids = (0..10)
+                .map(|i| {
+                    let position = FactoryVector3::new(i * 3, 0, 0);
+                    let id = factory.add_plugin_machine(PluginMachine::new(
+                        kiln,
+                        position,
+                        Cardinal2D::East,
+                    ));
+                    let limestone = ItemStack::new("CaCO3".parse().unwrap(), 5);
+                    _ = factory.buffers_mut(id).unwrap().input.insert(limestone);
+                    id
+                })
+                .collect::<Vec<_>>();
+            (factory, ids)
+        };
+        let tick = |factory: &mut Factory, ticks: usize| {
+            for _ in 0..ticks {
+                factory.tick(
+                    0.25,
+                    1.0,
+                    &mut Deposits::default(),
+                    &mut RailNetwork::default(),
+                );
+            }
+        };
+        let made = |factory: &Factory, ids: &[MachineId]| {
+            (ids.iter())
+                .map(|&id| factory.buffers(id).unwrap().output.stored())
+                .collect::<Vec<_>>()
+        };
+        let far = PlayerVector3::new(
+            PlayerCoord::from_i32(10_000),
+            PlayerCoord::ZERO,
+            PlayerCoord::ZERO,
+        );
+
+        let (mut full, ids) = build(None);
+        let (mut coarse, _) = build(Some(far));
+        tick(&mut full, 19);
+        tick(&mut coarse, 19);
+        assert!(
+            ids.iter().any(|&id| coarse.lod.behind(id.0) > 0.0),
+            "some should be waiting for their next tick"
+        );
+        assert_ne!(made(&full, &ids), made(&coarse, &ids));
+
+        coarse.lod.viewer = Some(PlayerVector3::ZERO);
+        tick(&mut full, 1);
+        tick(&mut coarse, 1);
+        assert!(ids.iter().all(|&id| coarse.lod.behind(id.0) == 0.0));
+        assert_eq!(
+            made(&full, &ids),
+            made(&coarse, &ids),
+            "caught up once the player came near"
+        );
+    }
+}
@@ -0,0 +1,621 @@
+//! The panel opened by interacting with a machine
+
+use super::{
+    BeltSide, Machine, MachineId, MachineStatus, Merger, Miner, Reactor, Splitter,
+    assembler::Assembler,
+    controller::Controller,
+    drone::ChargingPad,
+    electrolysis::Electrolyzer,
+    gas::Vent,
+    node_settings::NodeSettings,
+    plugin::PluginMachine,
+    pressure::ReliefValve,
+    recipe::Recipe,
+    separation::{Crystallizer, Distiller},
+    station::Station,
+    tank::Tank,
+    thermal::{self, Thermal},
+};
+use crate::{
+    chem::units::{Joule, Kelvin, Watt},
+    container::{Container, ItemSlots, ItemStack},
+    game::GameState,
+    input::{EventInput, Inputs},
+    locale::tr,
+    player::Player,
+    region::RegionId,
+    ui::Panel,
+};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer},
+};
+use raylib::prelude::*;
+
+const MARGIN: f32 = 16.0;
+const FONT_SIZE: f32 = 20.0;
+const LINE_HEIGHT: f32 = FONT_SIZE + 8.0;
+const BAR_HEIGHT: f32 = 16.0;
+const SLOT_SIZE: f32 = 56.0;
+const SLOT_GAP: f32 = 4.0;
+/// Title, recipe, fluid, and progress bar
+const HEADER_HEIGHT: f32 = MARGIN + 3.0 * LINE_HEIGHT + BAR_HEIGHT + MARGIN;
+/// Belt node buttons, each as wide as three slots
+const NODE_WIDTH: f32 = 3.0 * (SLOT_SIZE + SLOT_GAP) - SLOT_GAP;
+const NODE_HEIGHT: f32 = LINE_HEIGHT;
+const NODES_PER_ROW: usize = 3;
+/// A label and a row of slots
+const ROW_HEIGHT: f32 = LINE_HEIGHT + SLOT_SIZE + MARGIN;
+
+const BACKGROUND: Color = Color::new(16, 16, 24, 230);
+const SLOT: Color = Color::new(0, 0, 0, 160);
+const PROGRESS: Color = Color::new(96, 192, 96, 255);
+
+/// What a machine's panel shows, beyond what every [`Machine`] has
+pub trait MachineUi: Machine {
+    /// The recipe the machine is set to run, if any
+    #[inline]
+    #[must_use]
+    fn recipe(&self) -> Option<&Recipe> {
+        None
+    }
+
+    /// The temperature the machine holds what it's working on at, if it can be set
+    #[inline]
+    #[must_use]
+    fn setpoint(&self) -> Option<Kelvin> {
+        None
+    }
+
+    /// What the machine is set to do, for machines that don't run [recipes](Self::recipe)
+    #[inline]
+    #[must_use]
+    fn process(&self) -> Option<String> {
+        None
+    }
+}
+
+impl MachineUi for Reactor {}
+
+impl MachineUi for Miner {}
+
+impl MachineUi for Splitter {}
+
+impl MachineUi for Merger {}
+
+impl MachineUi for Station {}
+
+impl MachineUi for Vent {}
+
+impl MachineUi for Controller {}
+
+impl MachineUi for Tank {}
+
+impl MachineUi for ChargingPad {}
+
+impl MachineUi for ReliefValve {}
+
+impl MachineUi for Distiller {
+    fn setpoint(&self) -> Option<Kelvin> {
+        Some(Distiller::setpoint(self))
+    }
+}
+
+impl MachineUi for Crystallizer {
+    fn setpoint(&self) -> Option<Kelvin> {
+        Some(Crystallizer::setpoint(self))
+    }
+}
+
+impl MachineUi for Electrolyzer {
+    fn process(&self) -> Option<String> {
+        Electrolyzer::process(self).map(|process| {
+            let power = process.power();
+            format!(
+                "{} (ΔH {:.0}, {power:.1})",
+                process.reaction(),
+                process.enthalpy()
+            )
+        })
+    }
+}
+
+impl MachineUi for Assembler {
+    fn recipe(&self) -> Option<&Recipe> {
+        Assembler::recipe(self)
+    }
+}
+
+impl MachineUi for PluginMachine {
+    fn recipe(&self) -> Option<&Recipe> {
+        self.kind.recipe.as_ref()
+    }
+}
+
+/// A row of slots in a [`MachinePanel`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    /// The machine's [input](super::MachineBuffers::input)
+    Input,
+    /// The machine's [output](super::MachineBuffers::output)
+    Output,
+    /// The player's inventory
+    Inventory,
+}
+
+impl Section {
+    pub const ALL: [Self; 3] = [Self::Input, Self::Output, Self::Inventory];
+
+    pub fn label(self) -> String {
+        tr!(match self {
+            Self::Input => "machine_panel.input",
+            Self::Output => "machine_panel.output",
+            Self::Inventory => "machine_panel.inventory",
+        })
+    }
+
+    /// How far down the panel the row is
+    const fn row(self) -> u8 {
+        match self {
+            Self::Input => 0,
+            Self::Output => 1,
+            Self::Inventory => 2,
+        }
+    }
+}
+
+/// Move up to `max` items out of `slot` in `section`, into the machine's input if it's from
+/// the inventory, or into the inventory if it's from the machine
+///
+/// Whatever doesn't fit stays where it was. Returns `true` if anything moved.
+pub fn move_stack(
+    state: &mut GameState,
+    factory: usize,
+    machine: MachineId,
+    (section, slot): (Section, usize),
+    max: u32,
+) -> bool {
+    let player = &mut state.player;
+    let Some(buffers) = (state.factories.get_mut(factory)).and_then(|f| f.buffers_mut(machine))
+    else {
+        return false;
+    };
+    let (from, to): (&mut ItemSlots, &mut ItemSlots) = match section {
+        Section::Input => (&mut buffers.input, &mut player.inventory),
+        Section::Output => (&mut buffers.output, &mut player.inventory),
+        Section::Inventory => (&mut player.inventory, &mut buffers.input),
+    };
+    let Some(taken) = from.take(slot, max) else {
+        return false;
+    };
+    let count = taken.count;
+    let rest = to.insert(taken);
+    let left = rest.as_ref().map_or(0, |rest| rest.count);
+    if let Some(rest) = rest {
+        // It just came out of `from`, so there's room for it
+        _ = from.insert(rest);
+    }
+    left < count
+}
+
+/// A machine's recipe, progress, belt nodes, and buffers, with the player's inventory to move
+/// items between
+///
+/// Left clicking a slot moves the whole stack and right clicking moves one item. Stacks in the
+/// inventory go into the machine's input, and stacks in the machine go into the inventory.
+///
+/// Left clicking a belt node filters it to the held item, or lets anything through if nothing's
+/// held, and right clicking changes its priority.
+#[derive(Debug, Clone)]
+pub struct MachinePanel {
+    /// The index of the factory the machine is in
+    factory: usize,
+    machine: MachineId,
+    /// The machine's name and status, and how hot it is if it heats up
+    title: String,
+    recipe: Option<String>,
+    /// Liters of fluid over the tank's capacity, if it holds any
+    fluid: Option<String>,
+    /// How far through its recipe the machine is, in `[0, 1]`
+    progress: f32,
+    /// The contents of each section, and how many slots it has
+    sections: [(Vec<ItemStack>, usize); 3],
+    /// How each of the machine's belt nodes is set up, inputs then outputs
+    nodes: Vec<(BeltSide, usize, NodeSettings)>,
+    /// The slot under the mouse cursor
+    hovered: Option<(Section, usize)>,
+    /// The index in [`Self::nodes`] of the node under the mouse cursor
+    hovered_node: Option<usize>,
+    /// Screen-space area of the whole panel
+    rect: Rectangle,
+}
+
+impl MachinePanel {
+    /// A panel for `machine` in the factory at `factory`, if there's a machine there
+    pub fn new(
+        rl: &RaylibHandle,
+        state: &GameState,
+        factory: usize,
+        machine: MachineId,
+    ) -> Option<Self> {
+        let mut panel = Self {
+            factory,
+            machine,
+            title: String::new(),
+            recipe: None,
+            fluid: None,
+            progress: 0.0,
+            sections: Default::default(),
+            nodes: Vec::new(),
+            hovered: None,
+            hovered_node: None,
+            rect: Rectangle::default(),
+        };
+        panel.refresh(state).then(|| {
+            panel.layout(rl);
+            panel
+        })
+    }
+
+    /// Copy what's shown out of `state`, returning `false` if the machine is gone
+    fn refresh(&mut self, state: &GameState) -> bool {
+        let Some(factory) = state.factories.get(self.factory) else {
+            return false;
+        };
+        let (Some(machine), Some(buffers)) = (
+            factory.machine_ui(self.machine),
+            factory.buffers(self.machine),
+        ) else {
+            return false;
+        };
+        let status = machine.status();
+        let name = machine.name();
+        self.title = match factory.thermal(self.machine) {
+            Some(thermal) => {
+                let kelvin = format!("{:.0}", thermal.temperature().get());
+                let key = if thermal.is_overheated() {
+                    "machine_panel.overheated"
+                } else {
+                    "machine_panel.temperature"
+                };
+                format!("{name} - {status} - {}", tr!(key, kelvin = kelvin))
+            }
+            None => format!("{name} - {status}"),
+        };
+        if let Some(vessel) = factory.vessel(self.machine) {
+            let pressure = if vessel.is_ruptured() {
+                tr!("machine_panel.ruptured")
+            } else {
+                let temperature =
+                    (factory.thermal(self.machine)).map_or(thermal::AMBIENT, Thermal::temperature);
+                tr!(
+                    "machine_panel.pressure",
+                    pressure = format!("{:.0}", vessel.pressure(temperature)),
+                    rating = format!("{:.0}", vessel.properties.rating),
+                )
+            };
+            self.title = format!("{} - {pressure}", self.title);
+        }
+        if let Some(setpoint) = machine.setpoint() {
+            let celsius = format!("{:.0}", setpoint.to_celsius());
+            let setpoint = tr!("machine_panel.setpoint", celsius = celsius);
+            self.title = format!("{} - {setpoint}", self.title);
+        }
+        let power = machine.power();
+        if power > Watt::ZERO {
+            let power = tr!("machine_panel.power", power = format!("{power:.1}"));
+            self.title = format!("{} - {power}", self.title);
+        }
+        self.recipe = machine.recipe().map(|recipe| {
+            let seconds = recipe.duration.as_secs_f32();
+            if recipe.heat == Joule::ZERO {
+                format!("{} ({seconds:.1}s)", recipe.reaction())
+            } else {
+                // Enthalpy is the heat taken in, the opposite of what's released
+                let enthalpy = Joule::ZERO - recipe.heat;
+                format!("{} ({seconds:.1}s, ΔH {enthalpy:.0})", recipe.reaction())
+            }
+        });
+        if self.recipe.is_none() {
+            self.recipe = machine.process();
+        }
+        self.fluid = buffers.fluid.contents().map(|fluid| {
+            format!(
+                "{}: {:.0}/{:.0} L",
+                fluid.molecule,
+                fluid.liters.to_f32(),
+                buffers.fluid.capacity().to_f32()
+            )
+        });
+        self.progress = match status {
+            MachineStatus::Working { progress } => progress,
+            MachineStatus::Blocked => 1.0,
+            MachineStatus::Idle => 0.0,
+        };
+        let section = |slots: &ItemSlots| (slots.stacks().to_vec(), slots.slot_count());
+        self.sections = [
+            section(&buffers.input),
+            section(&buffers.output),
+            section(&state.player.inventory),
+        ];
+        let inputs = (0..machine.belt_inputs().len()).map(|idx| (BeltSide::Input, idx));
+        let outputs = (0..machine.belt_outputs().len()).map(|idx| (BeltSide::Output, idx));
+        self.nodes = (inputs.chain(outputs))
+            .map(|(side, idx)| {
+                let settings = factory.node_settings(self.machine, side, idx).clone();
+                (side, idx, settings)
+            })
+            .collect();
+        true
+    }
+
+    /// How far down the panel the first row of slots is, past the header and belt nodes
+    #[allow(clippy::cast_precision_loss, reason = "machines have few belt nodes")]
+    const fn header_height(&self) -> f32 {
+        let rows = self.nodes.len().div_ceil(NODES_PER_ROW);
+        HEADER_HEIGHT + rows as f32 * (NODE_HEIGHT + SLOT_GAP)
+    }
+
+    /// Screen-space area of the button for the `idx`th of [`Self::nodes`]
+    #[allow(clippy::cast_precision_loss, reason = "machines have few belt nodes")]
+    const fn node_rect(&self, idx: usize) -> Rectangle {
+        let (row, column) = (idx / NODES_PER_ROW, idx % NODES_PER_ROW);
+        Rectangle::new(
+            self.rect.x + MARGIN + column as f32 * (NODE_WIDTH + SLOT_GAP),
+            self.rect.y + HEADER_HEIGHT + row as f32 * (NODE_HEIGHT + SLOT_GAP),
+            NODE_WIDTH,
+            NODE_HEIGHT,
+        )
+    }
+
+    /// Filter the `idx`th of [`Self::nodes`] to the item the player is holding, or to anything
+    /// if they aren't, or if `cycle` is set, change its priority instead
+    fn configure_node(&self, state: &mut GameState, idx: usize, cycle: bool) {
+        let Some((side, node, settings)) = self.nodes.get(idx) else {
+            return;
+        };
+        let settings = if cycle {
+            NodeSettings {
+                priority: settings.priority.cycle(),
+                ..settings.clone()
+            }
+        } else {
+            NodeSettings {
+                filter: state.player.held_item().map(|stack| stack.item.clone()),
+                ..settings.clone()
+            }
+        };
+        if let Some(factory) = state.factories.get_mut(self.factory) {
+            factory.set_node_settings(self.machine, *side, *node, settings);
+        }
+    }
+
+    /// Center the panel on the screen
+    #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+    fn layout(&mut self, rl: &RaylibHandle) {
+        let width = Player::HOTBAR_SLOTS as f32 * (SLOT_SIZE + SLOT_GAP) - SLOT_GAP + 2.0 * MARGIN;
+        let height = self.header_height() + f32::from(Section::Inventory.row() + 1) * ROW_HEIGHT;
+        self.rect = Rectangle::new(
+            0.5 * (rl.get_screen_width() as f32 - width),
+            0.5 * (rl.get_screen_height() as f32 - height),
+            width,
+            height,
+        );
+    }
+
+    /// Screen-space position of the label over `section`
+    fn label_position(&self, section: Section) -> Vector2 {
+        Vector2::new(
+            self.rect.x + MARGIN,
+            self.rect.y + self.header_height() + f32::from(section.row()) * ROW_HEIGHT,
+        )
+    }
+
+    /// Screen-space area of `slot` in `section`
+    #[allow(clippy::cast_precision_loss, reason = "there are only a few slots")]
+    fn slot_rect(&self, section: Section, slot: usize) -> Rectangle {
+        let label = self.label_position(section);
+        Rectangle::new(
+            label.x + slot as f32 * (SLOT_SIZE + SLOT_GAP),
+            label.y + LINE_HEIGHT,
+            SLOT_SIZE,
+            SLOT_SIZE,
+        )
+    }
+
+    /// Every slot in every section
+    fn slots(&self) -> impl Iterator<Item = (Section, usize)> + '_ {
+        (Section::ALL.into_iter().zip(&self.sections))
+            .flat_map(|(section, (_, slots))| (0..*slots).map(move |slot| (section, slot)))
+    }
+}
+
+impl Panel for MachinePanel {
+    /// Closes on escape or interacting again, or once the player leaves the factory or the
+    /// machine is removed
+    fn update(&mut self, rl: &mut RaylibHandle, inputs: &Inputs, state: &mut GameState) -> bool {
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE)
+            || inputs[EventInput::Interact]
+            || state.current_region != RegionId::Factory(self.factory)
+        {
+            return false;
+        }
+        self.layout(rl);
+
+        let mouse = rl.get_mouse_position();
+        let hovered = self.slots().find(|&(section, slot)| {
+            self.slot_rect(section, slot)
+                .check_collision_point_rec(mouse)
+        });
+        self.hovered = hovered;
+        self.hovered_node =
+            (0..self.nodes.len()).find(|&idx| self.node_rect(idx).check_collision_point_rec(mouse));
+        if let Some(idx) = self.hovered_node {
+            if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                self.configure_node(state, idx, false);
+            } else if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+                self.configure_node(state, idx, true);
+            }
+        }
+        if let Some(hovered) = hovered {
+            let max = if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                Some(u32::MAX)
+            } else if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+                Some(1)
+            } else {
+                None
+            };
+            if let Some(max) = max {
+                move_stack(state, self.factory, self.machine, hovered, max);
+            }
+        }
+        self.refresh(state)
+    }
+}
+
+impl Draw for MachinePanel {
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        d.draw_rectangle(self.rect, BACKGROUND)?;
+
+        let left = self.rect.x + MARGIN;
+        let mut y = self.rect.y + MARGIN;
+        let recipe = (self.recipe.clone()).unwrap_or_else(|| tr!("machine_panel.no_recipe"));
+        let fluid = (self.fluid.clone()).unwrap_or_else(|| tr!("machine_panel.no_fluid"));
+        for (text, color) in [
+            (self.title.as_str(), Color::WHITE),
+            (recipe.as_str(), Color::LIGHTGRAY),
+            (fluid.as_str(), Color::LIGHTGRAY),
+        ] {
+            d.draw_text(text, Vector2::new(left, y), FONT_SIZE, 0.0, color)?;
+            y += LINE_HEIGHT;
+        }
+
+        let bar = Rectangle::new(left, y, self.rect.width - 2.0 * MARGIN, BAR_HEIGHT);
+        d.draw_rectangle(
+            Rectangle {
+                width: bar.width * self.progress.clamp(0.0, 1.0),
+                ..bar
+            },
+            PROGRESS,
+        )?;
+        d.draw_rectangle_lines(bar, Some(1.0), Color::GRAY)?;
+
+        for (idx, (side, node, settings)) in self.nodes.iter().enumerate() {
+            let rect = self.node_rect(idx);
+            d.draw_rectangle(rect, SLOT)?;
+            let (thick, color) = if self.hovered_node == Some(idx) {
+                (3.0, Color::WHITE)
+            } else {
+                (1.0, Color::GRAY)
+            };
+            d.draw_rectangle_lines(rect, Some(thick), color)?;
+            let key = match side {
+                BeltSide::Input => "machine_panel.belt_input",
+                BeltSide::Output => "machine_panel.belt_output",
+            };
+            let filter = (settings.filter.as_ref())
+                .map_or_else(|| tr!("machine_panel.any_item"), ToString::to_string);
+            let text = tr!(
+                "machine_panel.node",
+                node = tr!(key, number = node + 1),
+                filter = filter,
+                priority = settings.priority.label(),
+            );
+            d.draw_text(
+                &text,
+                Vector2::new(rect.x + 4.0, rect.y + 4.0),
+                0.75 * FONT_SIZE,
+                0.0,
+                Color::WHITE,
+            )?;
+        }
+
+        for (section, slot) in self.slots() {
+            let rect = self.slot_rect(section, slot);
+            d.draw_rectangle(rect, SLOT)?;
+            let (thick, color) = if self.hovered == Some((section, slot)) {
+                (3.0, Color::WHITE)
+            } else {
+                (1.0, Color::GRAY)
+            };
+            d.draw_rectangle_lines(rect, Some(thick), color)?;
+        }
+
+        let font = d.font().ok_or(draw::Error)?;
+        for (section, (stacks, _)) in Section::ALL.into_iter().zip(&self.sections) {
+            d.draw_text(
+                &section.label(),
+                self.label_position(section),
+                FONT_SIZE,
+                0.0,
+                Color::WHITE,
+            )?;
+            for (slot, stack) in stacks.iter().enumerate() {
+                let rect = self.slot_rect(section, slot);
+                d.draw_text(
+                    &stack.item.to_string(),
+                    Vector2::new(rect.x + 4.0, rect.y + 4.0),
+                    0.75 * FONT_SIZE,
+                    0.0,
+                    Color::WHITE,
+                )?;
+                let count = stack.count.to_string();
+                let count_size = font.measure_text(&count, 0.75 * FONT_SIZE, 0.0);
+                d.draw_text(
+                    &count,
+                    Vector2::new(rect.x + rect.width, rect.y + rect.height) - count_size - 4.0,
+                    0.75 * FONT_SIZE,
+                    0.0,
+                    Color::WHITE,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        container::Item,
+        math::coords::{PlayerVector3, VectorConstants},
+    };
+
+    #[test]
+    fn test_move_stack() {
+        let water = || "H2O".parse::<Item>().unwrap();
+        let mut state = GameState::new(Player::new(PlayerVector3::ZERO, 0.0, 0.0, 45.0, 1.0));
+        let factory = 0;
+        let machine = state.factories[factory].reactor_id(0).unwrap();
+        assert!(state.factories[factory].buffers(machine).is_some());
+        assert_eq!(state.player.give(ItemStack::new(water(), 150)), None);
+
+        assert!(move_stack(
+            &mut state,
+            factory,
+            machine,
+            (Section::Inventory, 0),
+            u32::MAX
+        ));
+        let buffers = state.factories[factory].buffers(machine).unwrap();
+        assert_eq!(buffers.input.stored(), 150, "should fill two input slots");
+        assert!(state.player.inventory.stacks().is_empty());
+
+        assert!(move_stack(
+            &mut state,
+            factory,
+            machine,
+            (Section::Input, 1),
+            1
+        ));
+        assert_eq!(state.player.inventory.stored(), 1);
+        assert!(!move_stack(
+            &mut state,
+            factory,
+            machine,
+            (Section::Output, 0),
+            1
+        ));
+    }
+}
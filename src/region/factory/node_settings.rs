@@ -0,0 +1,237 @@
+//! Which items a machine's belt nodes let through, and which nodes items go through first
+//!
+//! Belt nodes are worked out from where their machine is each time they're needed, so how they're
+//! set up is kept with the machine instead, by which side and index each node is. See
+//! [`Factory::move_items`](super::Factory) for how it's used.
+
+use super::{BeltSide, Factory, MachineId};
+use crate::{
+    container::{Container, Item, ItemSlots},
+    locale::tr,
+};
+use std::collections::BTreeMap;
+
+/// Which of a machine's belt nodes items go through first
+///
+/// Items only go through a node while every node on the same side with a higher priority is
+/// full, has no belt, or won't take them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    /// The next priority up, going back around to the lowest after the highest
+    #[must_use]
+    pub const fn cycle(self) -> Self {
+        match self {
+            Self::Low => Self::Normal,
+            Self::Normal => Self::High,
+            Self::High => Self::Low,
+        }
+    }
+
+    pub fn label(self) -> String {
+        tr!(match self {
+            Self::Low => "priority.low",
+            Self::Normal => "priority.normal",
+            Self::High => "priority.high",
+        })
+    }
+}
+
+/// How a belt node is set up
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NodeSettings {
+    /// The only item let through, or `None` for any
+    pub filter: Option<Item>,
+    pub priority: Priority,
+}
+
+impl NodeSettings {
+    /// Whether `item` can go through the node
+    pub fn allows(&self, item: &Item) -> bool {
+        self.filter.as_ref().is_none_or(|filter| filter == item)
+    }
+}
+
+/// How a node is set up when it's never been changed
+static DEFAULT: NodeSettings = NodeSettings {
+    filter: None,
+    priority: Priority::Normal,
+};
+
+/// How each of a machine's belt nodes is set up, where it isn't the default
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NodeTable {
+    nodes: BTreeMap<(BeltSide, usize), NodeSettings>,
+}
+
+impl NodeTable {
+    /// How the `idx`th node on `side` is set up
+    pub fn get(&self, side: BeltSide, idx: usize) -> &NodeSettings {
+        self.nodes.get(&(side, idx)).unwrap_or(&DEFAULT)
+    }
+
+    fn set(&mut self, side: BeltSide, idx: usize, settings: NodeSettings) {
+        if settings == DEFAULT {
+            self.nodes.remove(&(side, idx));
+        } else {
+            self.nodes.insert((side, idx), settings);
+        }
+    }
+}
+
+/// How the `idx`th node on `side` is set up, by the `table` of its machine if it has one
+pub(super) fn settings_in(table: Option<&NodeTable>, side: BeltSide, idx: usize) -> &NodeSettings {
+    table.map_or(&DEFAULT, |table| table.get(side, idx))
+}
+
+/// Move one item that `settings` allows from `from` to `to`, from the first stack of one
+///
+/// Items it doesn't allow, and the item if `to` has no room for it, stay where they are. Returns
+/// `true` if an item moved.
+pub(super) fn transfer_item(
+    from: &mut ItemSlots,
+    to: &mut ItemSlots,
+    settings: &NodeSettings,
+) -> bool {
+    let Some(slot) = (from.stacks().iter()).position(|stack| settings.allows(&stack.item)) else {
+        return false;
+    };
+    let Some(taken) = from.take(slot, 1) else {
+        return false;
+    };
+    let Some(rest) = to.insert(taken) else {
+        return true;
+    };
+    // It just came out of `from`, so there's room for it
+    _ = from.insert(rest);
+    false
+}
+
+impl Factory {
+    /// How the `idx`th belt node on `side` of the machine identified by `id` is set up
+    pub fn node_settings(&self, id: MachineId, side: BeltSide, idx: usize) -> &NodeSettings {
+        settings_in(self.node_settings.get(id.0), side, idx)
+    }
+
+    /// Set up the `idx`th belt node on `side` of the machine identified by `id`
+    ///
+    /// Returns `false`, changing nothing, if there's no such machine or it doesn't have that
+    /// node.
+    pub fn set_node_settings(
+        &mut self,
+        id: MachineId,
+        side: BeltSide,
+        idx: usize,
+        settings: NodeSettings,
+    ) -> bool {
+        let Some(machine) = self.machine(id) else {
+            return false;
+        };
+        let nodes = match side {
+            BeltSide::Input => machine.belt_inputs().len(),
+            BeltSide::Output => machine.belt_outputs().len(),
+        };
+        if idx >= nodes {
+            return false;
+        }
+        if let Some(table) = self.node_settings.get_mut(id.0) {
+            table.set(side, idx, settings);
+        } else {
+            let mut table = NodeTable::default();
+            table.set(side, idx, settings);
+            self.node_settings.insert(id.0, table);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        container::ItemStack,
+        math::{
+            bounds::FactoryBounds,
+            coords::{FactoryVector3, RailVector3},
+        },
+        ordinals::Cardinal2D,
+        region::factory::{Belt, BeltLevel, Machine, SplitMode, Splitter},
+    };
+
+    #[test]
+    fn test_node_settings() {
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-8, 0, -8),
+                max: FactoryVector3::new(8, 8, 8),
+            },
+        );
+        let splitter = Splitter::new(
+            FactoryVector3::new(0, 0, 0),
+            Cardinal2D::East,
+            SplitMode::RoundRobin,
+        );
+        let (input, outputs) = (splitter.belt_inputs()[0], splitter.belt_outputs());
+        let id = factory.add_splitter(splitter);
+        for &src in &outputs {
+            factory.add_belt(Belt {
+                level: BeltLevel::Mk4,
+                src,
+                dst: input,
+                path: vec![src.0.position; 4],
+            });
+        }
+        let water = || "H2O".parse::<Item>().unwrap();
+        let on_belts = |factory: &Factory| {
+            (factory.belt_items.iter())
+                .map(|(_, items)| items.stored())
+                .collect::<Vec<_>>()
+        };
+
+        let high = NodeSettings {
+            filter: None,
+            priority: Priority::High,
+        };
+        assert!(!factory.set_node_settings(id, BeltSide::Output, outputs.len(), high.clone()));
+        assert!(factory.set_node_settings(id, BeltSide::Output, 1, high));
+        let output = &mut factory.buffers_mut(id).unwrap().output;
+        assert_eq!(output.insert(ItemStack::new(water(), 6)), None);
+        factory.move_items();
+        assert_eq!(
+            on_belts(&factory),
+            [0, 6, 0],
+            "all to the higher priority belt"
+        );
+
+        let salt = NodeSettings {
+            filter: Some("NaCl".parse().unwrap()),
+            priority: Priority::High,
+        };
+        assert!(factory.set_node_settings(id, BeltSide::Output, 1, salt.clone()));
+        let output = &mut factory.buffers_mut(id).unwrap().output;
+        assert_eq!(output.insert(ItemStack::new(water(), 6)), None);
+        factory.move_items();
+        assert_eq!(
+            on_belts(&factory),
+            [3, 6, 3],
+            "the filtered belt shouldn't take any"
+        );
+
+        // The first belt loops back into the splitter, but only salt is let in
+        assert!(factory.set_node_settings(id, BeltSide::Input, 0, salt));
+        factory.move_items();
+        assert_eq!(
+            on_belts(&factory),
+            [3, 6, 3],
+            "the water should stay on the belt"
+        );
+        assert_eq!(factory.buffers(id).unwrap().input.stored(), 0);
+    }
+}
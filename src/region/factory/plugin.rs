@@ -0,0 +1,207 @@
+//! Machines of the types [plugins](crate::plugin) add
+//!
+//! Every plugin machine works the same way: it takes what its recipe needs out of its input
+//! buffer, works for the recipe's duration, and puts the products in its output buffer, waiting
+//! there until they all fit.
+
+use super::{
+    BeltInputNode, BeltNode, BeltOutputNode, Clearance, Machine, MachineModel, MachineSize,
+    MachineStatus, OrientedFootprint, TickContext, pressure::VesselProperties,
+    thermal::ThermalProperties,
+};
+use crate::{
+    chem::units::Joule,
+    locale::tr_or,
+    math::{
+        bounds::{Bounds, FactoryBounds},
+        coords::FactoryVector3,
+    },
+    ordinals::Cardinal2D,
+    plugin::MachineType,
+    resource::AssetId,
+};
+use arrayvec::ArrayVec;
+
+/// A machine of a type added by a plugin
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginMachine {
+    pub kind: &'static MachineType,
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    /// Toward the end of the current run of the recipe, in `[0, 1]`
+    progress: f32,
+    status: MachineStatus,
+}
+
+impl const Clearance for PluginMachine {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        self.kind.size
+    }
+}
+
+impl Bounds<FactoryVector3> for PluginMachine {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl PluginMachine {
+    pub const fn new(
+        kind: &'static MachineType,
+        position: FactoryVector3,
+        rotation: Cardinal2D,
+    ) -> Self {
+        Self {
+            kind,
+            position,
+            rotation,
+            progress: 0.0,
+            status: MachineStatus::Idle,
+        }
+    }
+
+    /// The block at `offset` from the machine's position when it faces east, rotated with the
+    /// machine
+    const fn block(&self, offset: FactoryVector3) -> FactoryVector3 {
+        self.position.plus(self.rotation.rotate_block(offset))
+    }
+}
+
+impl Machine for PluginMachine {
+    fn name(&self) -> String {
+        tr_or(&format!("machine.{}", self.kind.name), self.kind.label)
+    }
+
+    fn status(&self) -> MachineStatus {
+        self.status
+    }
+
+    fn tick(&mut self, dt: f32, ctx: &mut TickContext<'_>) {
+        if let Some(recipe) = &self.kind.recipe {
+            recipe.run(dt, ctx, &mut self.progress, &mut self.status);
+        }
+    }
+
+    fn model(&self) -> Option<MachineModel> {
+        self.kind.model.map(|id| MachineModel {
+            model: AssetId::PluginModel(id),
+            material: AssetId::PluginMaterial(id),
+            position: self.position,
+            rotation: self.rotation,
+            animations: None,
+        })
+    }
+
+    fn thermal_properties(&self) -> Option<ThermalProperties> {
+        self.kind.thermal
+    }
+
+    fn vessel_properties(&self) -> Option<VesselProperties> {
+        self.kind.vessel
+    }
+
+    /// Whatever its recipe releases
+    fn heat(&self) -> Joule {
+        match (self.status, &self.kind.recipe) {
+            (MachineStatus::Working { .. }, Some(recipe)) => recipe.heat_rate(),
+            _ => Joule::ZERO,
+        }
+    }
+
+    /// At the back, like a reactor's
+    fn belt_inputs(&self) -> ArrayVec<BeltInputNode, 8> {
+        let mut arr = ArrayVec::new();
+        arr.push(BeltInputNode(BeltNode {
+            position: self.block(FactoryVector3 { x: 0, y: 0, z: 0 }),
+            rotation: self.rotation.as_ordinal(),
+        }));
+        arr
+    }
+
+    /// At the front, like a reactor's
+    fn belt_outputs(&self) -> ArrayVec<BeltOutputNode, 8> {
+        let mut arr = ArrayVec::new();
+        let MachineSize { length, .. } = self.clearance();
+        arr.push(BeltOutputNode(BeltNode {
+            position: self.block(FactoryVector3 {
+                x: 0,
+                y: 0,
+                z: length.get().into(),
+            }),
+            rotation: self.rotation.as_ordinal(),
+        }));
+        arr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chem::reaction::Reaction,
+        container::{Container, ItemStack},
+        math::coords::RailVector3,
+        region::{
+            factory::{Factory, recipe::Recipe},
+            rail::{deposit::Deposits, network::RailNetwork},
+        },
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn test_plugin_machine() {
+        let reaction = "CaCO3 -> CaO + CO2".parse::<Reaction>().unwrap();
+        let kiln = Box::leak(Box::new(MachineType {
+            name: "kiln",
+            label: "Kiln",
+            size: MachineSize::new(2, 2, 2).unwrap(),
+            recipe: Some(Recipe::new(
+                "Calcination",
+                reaction.balance().unwrap(),
+                Duration::from_secs(2),
+            )),
+            thermal: None,
+            vessel: None,
+            model: None,
+        }));
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-8, 0, 0),
+                max: FactoryVector3::new(8, 8, 10),
+            },
+        );
+        let id = factory.add_plugin_machine(PluginMachine::new(
+            kiln,
+            FactoryVector3::new(0, 0, 4),
+            Cardinal2D::East,
+        ));
+        let limestone = ItemStack::new("CaCO3".parse().unwrap(), 1);
+        factory.buffers_mut(id).unwrap().input.insert(limestone);
+        let tick = |factory: &mut Factory| {
+            factory.tick(
+                1.0,
+                1.0,
+                &mut Deposits::default(),
+                &mut RailNetwork::default(),
+            );
+        };
+        tick(&mut factory);
+        assert_eq!(
+            factory.machine(id).unwrap().status(),
+            MachineStatus::Working { progress: 0.5 }
+        );
+        tick(&mut factory);
+        let buffers = factory.buffers(id).unwrap();
+        assert_eq!(buffers.input.stored(), 0);
+        assert_eq!(
+            buffers.output.stored(),
+            2,
+            "one quicklime and one carbon dioxide"
+        );
+        assert_eq!(factory.machine(id).unwrap().name(), "Kiln");
+    }
+}
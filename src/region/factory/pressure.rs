@@ -0,0 +1,202 @@
+//! Gas sealed in machines, which presses harder on their walls the hotter it gets
+//!
+//! Machines with [`VesselProperties`] get a [`Vessel`] when they're built: a headspace of air,
+//! sealed in at [`AMBIENT`] and atmospheric pressure. Coolant that [`Thermal`](super::Thermal)
+//! boils off turns to steam in it instead of escaping, and the gas's pressure follows the
+//! [ideal gas law](ideal_gas) as the machine heats up and cools down.
+//!
+//! A [`ReliefValve`] touching a vessel lets gas out to keep it under [`ReliefValve::OPENS_AT`]
+//! of its rating. A vessel pushed past its rating without one ruptures, spilling the fluid in it
+//! and hurting anyone nearby, and doesn't run again until it's rebuilt.
+
+use super::{
+    Clearance, Factory, Machine, MachineBuffers, MachineKind, MachineSize, OrientedFootprint,
+    thermal::AMBIENT,
+};
+use crate::{
+    chem::units::{Kelvin, Liter, Mole, Pascal, ideal_gas},
+    container::{FluidTank, ItemSlots},
+    ecs::Entity,
+    math::{
+        bounds::{Bounds, FactoryBounds},
+        coords::FactoryVector3,
+    },
+    ordinals::Cardinal2D,
+};
+use fixed_point::Q32_32;
+use std::collections::HashSet;
+
+/// Moles of steam from each liter of coolant boiled off, the same as for water
+pub const STEAM_PER_LITER: f64 = 1000.0 / 18.015;
+
+/// How a type of machine holds gas in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VesselProperties {
+    /// Space above the fluid, which gas fills
+    pub headspace: Liter,
+    /// The most pressure it holds before rupturing
+    pub rating: Pascal,
+}
+
+/// The gas sealed in a machine
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vessel {
+    pub properties: VesselProperties,
+    gas: Mole,
+    /// Whether it's burst, which leaves it open to the air
+    ruptured: bool,
+}
+
+impl Vessel {
+    /// Sealed with the headspace full of air
+    pub fn new(properties: VesselProperties) -> Self {
+        Self {
+            properties,
+            gas: Self::air(properties.headspace),
+            ruptured: false,
+        }
+    }
+
+    /// Moles of air filling `volume` at [`AMBIENT`] and atmospheric pressure
+    fn air(volume: Liter) -> Mole {
+        ideal_gas(Pascal::from_atm(1.0), Some(volume), None, Some(AMBIENT))
+            .map_or(Mole::ZERO, |gas| gas.moles)
+    }
+
+    /// Moles of gas in the headspace
+    #[inline]
+    pub const fn gas(&self) -> Mole {
+        self.gas
+    }
+
+    /// Whether it's burst, so the machine won't run
+    #[inline]
+    pub const fn is_ruptured(&self) -> bool {
+        self.ruptured
+    }
+
+    /// How hard the gas presses on the walls at `temperature`
+    pub fn pressure(&self, temperature: Kelvin) -> Pascal {
+        let headspace = self.properties.headspace;
+        ideal_gas(None, Some(headspace), Some(self.gas), Some(temperature))
+            .map_or(Pascal::ZERO, |gas| gas.pressure)
+    }
+
+    /// Take in `steam`, let out anything over the relief pressure if it's `relieved`, and burst
+    /// if that leaves it over its rating at `temperature`
+    ///
+    /// Returns `true` if it ruptured just now.
+    pub fn tick(&mut self, temperature: Kelvin, steam: Mole, relieved: bool) -> bool {
+        if self.ruptured {
+            return false;
+        }
+        let VesselProperties { headspace, rating } = self.properties;
+        self.gas += steam;
+        if relieved {
+            let relief = Some(rating * ReliefValve::OPENS_AT);
+            if let Some(most) = ideal_gas(relief, Some(headspace), None, Some(temperature))
+                && self.gas > most.moles
+            {
+                self.gas = most.moles;
+            }
+        }
+        if self.pressure(temperature) > rating {
+            self.ruptured = true;
+            self.gas = Self::air(headspace);
+            return true;
+        }
+        false
+    }
+}
+
+/// Lets gas out of every vessel it touches before it gets close to rupturing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReliefValve {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+}
+
+impl const Clearance for ReliefValve {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        Self::SIZE
+    }
+}
+
+impl Bounds<FactoryVector3> for ReliefValve {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl ReliefValve {
+    // SAFETY: 1 is not zero
+    pub const SIZE: MachineSize = unsafe { MachineSize::new_unchecked(1, 1, 1) };
+
+    /// Fraction of a vessel's rating it lets gas out over
+    pub const OPENS_AT: f64 = 0.8;
+
+    pub const fn new(position: FactoryVector3, rotation: Cardinal2D) -> Self {
+        Self { position, rotation }
+    }
+}
+
+impl Machine for ReliefValve {
+    fn name(&self) -> String {
+        MachineKind::ReliefValve.name()
+    }
+
+    /// Nothing goes in or comes out but gas
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(0, 0),
+            output: ItemSlots::new(0, 0),
+            fluid: FluidTank::new(Q32_32::from_i32(0)),
+        }
+    }
+}
+
+impl Factory {
+    /// Vessels touching a relief valve
+    pub(super) fn relieved(&self) -> HashSet<Entity> {
+        (self.relief_valves.iter())
+            .flat_map(|(_, valve)| self.index.query_aabb(&valve.bounds()))
+            .map(|id| id.0)
+            .filter(|&entity| self.vessels.contains(entity))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vessel() {
+        let properties = VesselProperties {
+            headspace: Liter::new(1000.0).unwrap(),
+            rating: Pascal::from_atm(5.0).unwrap(),
+        };
+        let mut vessel = Vessel::new(properties);
+        let atm = Pascal::from_atm(1.0).unwrap();
+        assert!((vessel.pressure(AMBIENT) - atm).get().abs() < 1e-6);
+        let hot = AMBIENT * 2.0;
+        assert!(
+            (vessel.pressure(hot) / atm - 2.0).abs() < 1e-9,
+            "heating it twice as hot should double the pressure"
+        );
+
+        // About 5 L of water boiled off, which is far too much steam for 1000 L
+        let steam = Mole::new(5.0 * STEAM_PER_LITER).unwrap();
+        let mut relieved = vessel.clone();
+        assert!(!relieved.tick(hot, steam, true));
+        let most = properties.rating * ReliefValve::OPENS_AT;
+        assert!((relieved.pressure(hot) - most).get().abs() < 1e-6);
+
+        assert!(vessel.tick(hot, steam, false));
+        assert!(vessel.is_ruptured());
+        assert!(!vessel.tick(hot, steam, false), "it only bursts once");
+    }
+}
@@ -0,0 +1,258 @@
+use super::{MachineStatus, TickContext};
+use crate::{
+    chem::{
+        formula::Formula,
+        reaction::BalancedReaction,
+        solution::Solution,
+        units::{Joule, Kelvin, Liter, Mole},
+    },
+    container::{Container, FluidTank, ItemSlots, ItemStack},
+    plugin,
+    stats::Statistics,
+};
+use std::{num::NonZeroU32, sync::LazyLock, time::Duration};
+
+/// Recipes that come with the game, see [`built_in`]
+static BUILT_IN: LazyLock<Vec<Recipe>> = LazyLock::new(|| {
+    let recipe = |name, reactants: &[(u32, &str)], products: &[(u32, &str)], seconds| {
+        let side = |side: &[(u32, &str)]| {
+            (side.iter())
+                .map(|&(n, item)| (NonZeroU32::new(n).unwrap(), item.parse().unwrap()))
+                .collect()
+        };
+        let reaction = BalancedReaction::try_new(side(reactants), side(products)).unwrap();
+        Recipe::new(name, reaction, Duration::from_secs(seconds))
+    };
+    vec![
+        // Copper traces on a silicon wafer
+        recipe("Circuit board", &[(2, "Cu"), (1, "Si")], &[(1, "Cu2Si")], 4),
+        // Iron plates and the boards to control them
+        recipe(
+            "Reactor kit",
+            &[(8, "Fe"), (2, "Cu2Si")],
+            &[(1, "Fe8Cu4Si2")],
+            10,
+        ),
+        // A reactor kit's frame with more boards to steer it, worn as a jetpack
+        recipe(
+            "Jetpack",
+            &[(1, "Fe8Cu4Si2"), (2, "Cu2Si")],
+            &[(1, "Fe8Cu8Si4")],
+            20,
+        ),
+        // Chalk settling out of two salts dissolved in water
+        recipe(
+            "Precipitated chalk",
+            &[(1, "CaCl2"), (1, "Na2CO3")],
+            &[(1, "CaCO3"), (2, "NaCl")],
+            6,
+        )
+        .in_water(Liter::new(1.0).unwrap()),
+    ]
+});
+
+/// Recipes that come with the game, which research unlocks
+pub fn built_in() -> &'static [Recipe] {
+    &BUILT_IN
+}
+
+/// The recipe named `name`, whether it comes with the game or was added by a
+/// [plugin](crate::plugin)
+pub fn find(name: &str) -> Option<&'static Recipe> {
+    (built_in().iter())
+        .find(|recipe| recipe.name() == name)
+        .or_else(|| plugin::registry().recipe(name))
+}
+
+/// How many of `item` are in `slots`
+fn count(slots: &ItemSlots, item: &Formula) -> u32 {
+    (slots.stacks().iter())
+        .filter(|stack| stack.item == *item)
+        .fold(0, |total, stack| total.saturating_add(stack.count))
+}
+
+/// Take `reactants` out of `input` if they're all there, returning whether they were
+pub(super) fn take_reactants(
+    input: &mut ItemSlots,
+    reactants: &[(NonZeroU32, Formula)],
+    stats: &mut Statistics,
+) -> bool {
+    if !(reactants.iter()).all(|(n, item)| count(input, item) >= n.get()) {
+        return false;
+    }
+    for (n, item) in reactants {
+        let mut left = n.get();
+        while left > 0
+            && let Some(slot) = input.stacks().iter().position(|stack| stack.item == *item)
+            && let Some(taken) = input.take(slot, left)
+        {
+            left -= taken.count;
+        }
+        stats.consumed(item, n.get());
+    }
+    true
+}
+
+/// Whether `tank` holds `volume` of water that `reactants` all stay dissolved in at
+/// `temperature`, taking each item as a mole
+fn dissolves(
+    tank: &FluidTank,
+    volume: Liter,
+    reactants: &[(NonZeroU32, Formula)],
+    temperature: Kelvin,
+) -> bool {
+    let mut solution = Solution::water(volume);
+    let has_water = (tank.contents()).is_some_and(|fluid| {
+        fluid.molecule == solution.solvent && f64::from(fluid.liters.to_f32()) >= volume.get()
+    });
+    for (n, item) in reactants {
+        solution.dissolve(item.compound(), Mole::from_count(n.get()));
+    }
+    has_water && !solution.is_supersaturated(temperature)
+}
+
+/// Put `products` in `output` if they all fit, returning whether they did
+pub(super) fn put_products(
+    output: &mut ItemSlots,
+    products: &[(NonZeroU32, Formula)],
+    stats: &mut Statistics,
+) -> bool {
+    let mut filled = output.clone();
+    let fits = (products.iter()).all(|(n, item)| {
+        filled
+            .insert(ItemStack::new(item.clone(), n.get()))
+            .is_none()
+    });
+    if fits {
+        *output = filled;
+        for (n, item) in products {
+            stats.produced(item, n.get());
+        }
+    }
+    fits
+}
+
+/// A process a machine can run
+///
+/// Recipes can only be made from balanced reactions, so machines never create or destroy matter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipe {
+    /// The name shown to the player, which research unlocks it by
+    name: &'static str,
+    reaction: BalancedReaction,
+    /// How long one run of the reaction takes
+    pub duration: Duration,
+    /// Released by one run of the reaction, negative if it takes heat in
+    pub heat: Joule,
+    /// Water the reactants are dissolved in for each run, if they're run in solution
+    ///
+    /// The machine needs this much water in its tank, and can't start while it's too cold for
+    /// the reactants to all dissolve in it. The water isn't used up.
+    pub solvent: Option<Liter>,
+}
+
+impl Recipe {
+    /// Releasing the heat its reaction's [enthalpy](BalancedReaction::enthalpy) says it does,
+    /// or none if that isn't known
+    #[must_use]
+    pub fn new(name: &'static str, reaction: BalancedReaction, duration: Duration) -> Self {
+        let heat = reaction
+            .enthalpy()
+            .map_or(Joule::ZERO, |enthalpy| Joule::ZERO - enthalpy);
+        Self {
+            name,
+            reaction,
+            duration,
+            heat,
+            solvent: None,
+        }
+    }
+
+    /// The same recipe, with its reactants dissolved in `volume` of water each run
+    #[must_use]
+    pub const fn in_water(mut self, volume: Liter) -> Self {
+        self.solvent = Some(volume);
+        self
+    }
+
+    /// The same recipe, releasing `heat` each run instead of what its reaction's enthalpy says
+    #[must_use]
+    pub const fn with_heat(mut self, heat: Joule) -> Self {
+        self.heat = heat;
+        self
+    }
+
+    /// Heat released per second while the recipe is running
+    #[must_use]
+    pub fn heat_rate(&self) -> Joule {
+        let seconds = self.duration.as_secs_f64();
+        if seconds > 0.0 {
+            self.heat / seconds
+        } else {
+            Joule::ZERO
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn reaction(&self) -> &BalancedReaction {
+        &self.reaction
+    }
+
+    /// Run the recipe for `dt` seconds in a machine that's `progress` through it
+    ///
+    /// Idle machines take what the recipe needs out of their input buffer, work for its duration,
+    /// and then put the products in their output buffer, staying blocked until they all fit.
+    /// Time left over once it's done goes towards the next run, so one long tick makes as much as
+    /// many short ones.
+    pub(super) fn run(
+        &self,
+        dt: f32,
+        ctx: &mut TickContext<'_>,
+        progress: &mut f32,
+        status: &mut MachineStatus,
+    ) {
+        let reaction = self.reaction();
+        let seconds = self.duration.as_secs_f32();
+        let mut dt = dt;
+        loop {
+            if *status == MachineStatus::Idle
+                && (self.solvent).is_none_or(|volume| {
+                    let tank = &ctx.buffers.fluid;
+                    dissolves(tank, volume, reaction.reactants(), ctx.temperature)
+                })
+                && take_reactants(&mut ctx.buffers.input, reaction.reactants(), ctx.stats)
+            {
+                *progress = 0.0;
+                *status = MachineStatus::Working { progress: 0.0 };
+            }
+            if let MachineStatus::Working { .. } = status {
+                let left = (1.0 - *progress) * seconds;
+                if dt < left {
+                    *progress += dt / seconds;
+                    *status = MachineStatus::Working {
+                        progress: *progress,
+                    };
+                    return;
+                }
+                dt -= left;
+                *progress = 1.0;
+            }
+            if *status == MachineStatus::Idle {
+                return;
+            }
+            if !put_products(&mut ctx.buffers.output, reaction.products(), ctx.stats) {
+                *status = MachineStatus::Blocked;
+                return;
+            }
+            *progress = 0.0;
+            *status = MachineStatus::Idle;
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use super::Factory;
+use crate::math::{
+    bounds::{FactoryBounds, SpacialBounds},
+    coords::{FactoryVector3, VectorConstants},
+};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    num::NonZeroU8,
+};
+
+/// Limits on where a belt or pipe can go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RouteRules {
+    /// Empty blocks needed above every block of the route
+    pub clearance: u8,
+    /// Blocks along for every block up or down, or [`None`] if the route can go straight up
+    pub ramp_length: Option<NonZeroU8>,
+}
+
+impl RouteRules {
+    /// Items ride on top of belts, which can't be steeper than one block up every two along
+    pub const BELT: Self = Self {
+        clearance: 1,
+        ramp_length: NonZeroU8::new(2),
+    };
+
+    /// Pipes can go anywhere there's room for them
+    pub const PIPE: Self = Self {
+        clearance: 0,
+        ramp_length: None,
+    };
+
+    /// Drones fly, so they go anywhere pipes can, but around belts and pipes instead of through
+    /// them
+    pub const DRONE: Self = Self::PIPE;
+}
+
+/// Each block along a route costs this much, so turns can cost less than a block
+const STEP_COST: u32 = 4;
+/// Makes the route prefer going straight over zigzagging
+const TURN_COST: u32 = 1;
+/// How many blocks to try before giving up on finding a route
+const MAX_SEARCH: usize = 1 << 16;
+
+/// The horizontal directions a route can go, which are also the directions a route can be
+/// facing after each step
+const DIRECTIONS: [FactoryVector3; 4] = [
+    FactoryVector3::X,
+    FactoryVector3::Z,
+    FactoryVector3::NEG_X,
+    FactoryVector3::NEG_Z,
+];
+/// The direction a route is facing after going straight up or down, or before it starts
+const NO_DIRECTION: u8 = 4;
+
+/// A block the search got to and which way it was going, so turns can cost extra
+type Step = (FactoryVector3, u8);
+
+/// Every block a route passes through going straight from `from` to `to`, not counting `from`
+///
+/// Ramps take up the blocks both above and below them.
+fn swept(from: FactoryVector3, to: FactoryVector3) -> impl Iterator<Item = FactoryVector3> {
+    let delta = to - from;
+    let steps = delta.x.abs().max(delta.z.abs());
+    let along = FactoryVector3::new(delta.x.signum(), 0, delta.z.signum());
+    let is_vertical = steps == 0;
+    (1..=steps.max(1)).flat_map(move |i| {
+        let start_level = if is_vertical { to } else { from + along * i };
+        let end_level = FactoryVector3 {
+            y: to.y,
+            ..start_level
+        };
+        [start_level]
+            .into_iter()
+            .chain((start_level != end_level).then_some(end_level))
+    })
+}
+
+/// Every block taken up by a route with `waypoints`, see [`find_route`]
+pub fn route_cells(waypoints: &[FactoryVector3]) -> impl Iterator<Item = FactoryVector3> + '_ {
+    (waypoints.first().copied().into_iter()).chain(
+        waypoints
+            .windows(2)
+            .flat_map(|pair| swept(pair[0], pair[1])),
+    )
+}
+
+/// The cheapest possible cost from `from` to `to`, for A*
+fn estimate(from: FactoryVector3, to: FactoryVector3, rules: RouteRules) -> u32 {
+    let delta = to - from;
+    let along = u32::from(delta.x.unsigned_abs()) + u32::from(delta.z.unsigned_abs());
+    let rise = u32::from(delta.y.unsigned_abs());
+    STEP_COST
+        * match rules.ramp_length {
+            // Every ramp goes along as well as up
+            Some(ramp_length) => along.max(rise * u32::from(ramp_length.get())) + rise,
+            None => along + rise,
+        }
+}
+
+/// The shortest path for a belt or pipe from `start` to `goal` in `factory` following `rules`,
+/// as the block at the end of each step, starting with `start`
+///
+/// Routes go around machines and other routes. `start` and `goal` are allowed to be inside
+/// machines since that's where some machines connect. Returns [`None`] if there is no route
+/// or it's too far to find.
+pub fn find_route(
+    factory: &Factory,
+    start: FactoryVector3,
+    goal: FactoryVector3,
+    rules: RouteRules,
+) -> Option<Vec<FactoryVector3>> {
+    let taken = factory.conveyor_cells().collect::<HashSet<_>>();
+    let clearance = FactoryVector3::new(1, 1 + i16::from(rules.clearance), 1);
+    let is_clear = |cell: FactoryVector3| {
+        let space = FactoryBounds {
+            min: cell,
+            max: cell + clearance,
+        };
+        cell == start
+            || cell == goal
+            || (!taken.contains(&cell)
+                && factory.bounds.union(&space) == factory.bounds
+                && factory.is_vacant_of_machines(&space))
+    };
+
+    let mut came_from = HashMap::<Step, Step>::new();
+    let mut costs = HashMap::<Step, u32>::from([((start, NO_DIRECTION), 0)]);
+    // Cheapest estimate first, then the furthest along
+    let mut open = BinaryHeap::from([(
+        Reverse(estimate(start, goal, rules)),
+        0,
+        [start.x, start.y, start.z],
+        NO_DIRECTION,
+    )]);
+    let mut searched = 0;
+
+    while let Some((_, cost, [x, y, z], facing)) = open.pop() {
+        let current = FactoryVector3::new(x, y, z);
+        if current == goal {
+            let mut path = vec![goal];
+            let mut step = (goal, facing);
+            while let Some(&previous) = came_from.get(&step) {
+                path.push(previous.0);
+                step = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if costs
+            .get(&(current, facing))
+            .is_some_and(|&known| known < cost)
+        {
+            // Already found a cheaper way here
+            continue;
+        }
+        searched += 1;
+        if searched > MAX_SEARCH {
+            return None;
+        }
+
+        let mut moves = Vec::with_capacity(14);
+        for (direction, along) in (0..).zip(DIRECTIONS) {
+            moves.push((current + along, direction));
+            if let Some(ramp_length) = rules.ramp_length {
+                let ramp = current + along * i16::from(ramp_length.get());
+                moves.push((ramp + FactoryVector3::Y, direction));
+                moves.push((ramp + FactoryVector3::NEG_Y, direction));
+            }
+        }
+        if rules.ramp_length.is_none() {
+            moves.push((current + FactoryVector3::Y, NO_DIRECTION));
+            moves.push((current + FactoryVector3::NEG_Y, NO_DIRECTION));
+        }
+
+        for (next, direction) in moves {
+            if !swept(current, next).all(is_clear) {
+                continue;
+            }
+            let delta = next - current;
+            let length = delta.x.unsigned_abs() + delta.z.unsigned_abs() + delta.y.unsigned_abs();
+            let mut next_cost = cost + STEP_COST * u32::from(length);
+            if facing != NO_DIRECTION && direction != facing {
+                next_cost += TURN_COST;
+            }
+            if costs
+                .get(&(next, direction))
+                .is_some_and(|&known| known <= next_cost)
+            {
+                continue;
+            }
+            costs.insert((next, direction), next_cost);
+            came_from.insert((next, direction), (current, facing));
+            open.push((
+                Reverse(next_cost + estimate(next, goal, rules)),
+                next_cost,
+                [next.x, next.y, next.z],
+                direction,
+            ));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{math::coords::RailVector3, ordinals::Cardinal2D, region::factory::Reactor};
+
+    #[test]
+    fn test_find_route() {
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-10, 0, -10),
+                max: FactoryVector3::new(10, 10, 10),
+            },
+        );
+        let start = FactoryVector3::new(0, 0, 0);
+        let goal = FactoryVector3::new(6, 0, 0);
+
+        let straight = find_route(&factory, start, goal, RouteRules::BELT).unwrap();
+        assert_eq!(straight.len(), 7);
+        assert_eq!(route_cells(&straight).count(), 7);
+
+        // A reactor in the way from x 2 to 4, z 0 to 3
+        factory.add_reactor(Reactor {
+            position: FactoryVector3::new(2, 0, 0),
+            rotation: Cardinal2D::default(),
+        });
+        let around = find_route(&factory, start, goal, RouteRules::BELT).unwrap();
+        assert_eq!((around.first(), around.last()), (Some(&start), Some(&goal)));
+        assert!(around.len() > straight.len());
+        for cell in route_cells(&around).filter(|&cell| cell != start && cell != goal) {
+            assert!(factory.is_vacant(&FactoryBounds {
+                min: cell,
+                max: cell + FactoryVector3::ONE,
+            }));
+        }
+
+        // Belts can only ramp, pipes can go straight up
+        let up = FactoryVector3::new(0, 3, 0);
+        let ramped = find_route(&factory, start, up, RouteRules::BELT).unwrap();
+        let piped = find_route(&factory, start, up, RouteRules::PIPE).unwrap();
+        assert_eq!(piped.len(), 4);
+        assert_eq!(ramped.last(), Some(&up));
+        assert!(ramped.windows(2).all(|pair| {
+            let delta = pair[1] - pair[0];
+            delta.y == 0 || delta.x.abs() + delta.z.abs() == 2
+        }));
+
+        // Outside of the factory
+        assert_eq!(
+            find_route(
+                &factory,
+                start,
+                FactoryVector3::new(0, 0, 20),
+                RouteRules::PIPE
+            ),
+            None
+        );
+    }
+}
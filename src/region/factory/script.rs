@@ -0,0 +1,912 @@
+//! The little language [`Controller`](super::controller::Controller)s are programmed in
+//!
+//! A script is a list of statements, one per line, run top to bottom every tick. It can only
+//! look at the factory through a [`Host`], and can only change it by returning [`Effect`]s, so
+//! it can't reach anything it isn't given. Variables are kept between ticks, and read as 0 before
+//! they're first set, so scripts can count and remember.
+//!
+//! ```text
+//! # Keep the kiln below 550 K, and count how often it had to stop
+//! let kiln = machine(4, 0, 2)
+//! if temperature(kiln) > 550 and enabled(kiln) then let stops = stops + 1
+//! if temperature(kiln) > 550 then disable kiln
+//! if temperature(kiln) < 450 then enable kiln
+//! if input(kiln, "CaCO3") < 10 then ratio machine(2, 0, 2), 1, 0, 0
+//! ```
+//!
+//! Statements are `let <name> = <expr>`, `if <expr> then <statement>`, `enable <machine>`,
+//! `disable <machine>`, `ratio <splitter>, <a>, <b>, <c>`, and `print <expr>`. Expressions have
+//! numbers, `"text"`, `true` and `false`, arithmetic, comparisons, `and`, `or`, `not`, and
+//! [`Function`] calls.
+
+use super::{BeltSide, MachineId, MachineStatus};
+use crate::{chem::formula::Formula, math::coords::FactoryVector3};
+use std::{collections::BTreeMap, str::FromStr};
+
+/// What a script, or a circuit's sensors, can see of the factory it's in
+pub trait Host {
+    /// The machine taking up the block at `position`
+    fn machine_at(&self, position: FactoryVector3) -> Option<MachineId>;
+
+    /// How many of `item` the machine has on `side`, or of everything if [`None`]
+    fn count(&self, id: MachineId, side: BeltSide, item: Option<&Formula>) -> Option<u32>;
+
+    /// Liters of fluid in the machine
+    fn fluid(&self, id: MachineId) -> Option<f64>;
+
+    /// Kelvin, for machines that heat up
+    fn temperature(&self, id: MachineId) -> Option<f64>;
+
+    fn status(&self, id: MachineId) -> Option<MachineStatus>;
+
+    /// Whether the machine hasn't been disabled
+    fn is_enabled(&self, id: MachineId) -> bool;
+}
+
+/// A change a script wants made to the factory
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    /// Turn the machine on or off
+    Enable(MachineId, bool),
+    /// Share a splitter's items between its outputs by these weights
+    Ratio(MachineId, [u8; 3]),
+    /// Show a line in the console
+    Print(String),
+}
+
+/// Something a script works with
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Bool(bool),
+    Text(String),
+    Machine(MachineId),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Text(text) => f.write_str(text),
+            Self::Machine(_) => f.write_str("machine"),
+        }
+    }
+}
+
+/// What a script can ask the [`Host`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Function {
+    /// `machine(x, y, z)`, the machine at a block
+    Machine,
+    /// `input(m)` or `input(m, "item")`, items waiting to be used
+    Input,
+    /// `output(m)` or `output(m, "item")`, items waiting to be taken away
+    Output,
+    /// `fluid(m)`, liters
+    Fluid,
+    /// `temperature(m)`, kelvin
+    Temperature,
+    /// `working(m)`
+    Working,
+    /// `enabled(m)`
+    Enabled,
+}
+
+impl Function {
+    const ALL: [Self; 7] = [
+        Self::Machine,
+        Self::Input,
+        Self::Output,
+        Self::Fluid,
+        Self::Temperature,
+        Self::Working,
+        Self::Enabled,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Machine => "machine",
+            Self::Input => "input",
+            Self::Output => "output",
+            Self::Fluid => "fluid",
+            Self::Temperature => "temperature",
+            Self::Working => "working",
+            Self::Enabled => "enabled",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Bool(bool),
+    Text(String),
+    Var(String),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(Box<Expr>, Op, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Statement {
+    Let(String, Expr),
+    If(Expr, Box<Statement>),
+    Enable(Expr, bool),
+    Ratio(Expr, [Expr; 3]),
+    Print(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Text(String),
+    Symbol(&'static str),
+}
+
+/// Symbols, longest first so `<=` isn't read as `<`
+const SYMBOLS: [&str; 14] = [
+    "<=", ">=", "==", "!=", "<", ">", "=", "+", "-", "*", "/", "(", ")", ",",
+];
+
+/// Words that can't be variable names
+const KEYWORDS: [&str; 12] = [
+    "let", "if", "then", "and", "or", "not", "true", "false", "enable", "disable", "ratio", "print",
+];
+
+/// Most tokens a line can have
+///
+/// Every operator nests an expression one deeper, so this also keeps working one out, or
+/// dropping it, from running out of stack.
+const MAX_TOKENS: usize = 256;
+
+/// Most brackets, signs, calls, and `if`s a part of a line can be inside
+const MAX_DEPTH: usize = 32;
+
+/// Longest text a script can make, in bytes
+///
+/// Steps are cheap, but doubling text takes one, so without this a few lines could fill memory.
+const MAX_TEXT: usize = 4096;
+
+/// Split a line into tokens, stopping at a `#` comment
+fn tokenize(line: &str) -> Result<Vec<Token>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut rest = line.trim_start();
+    while let Some(ch) = rest.chars().next() {
+        if ch == '#' {
+            break;
+        }
+        let len = if ch == '"' {
+            let end = rest[1..]
+                .find('"')
+                .ok_or("text is missing its closing quote")?;
+            tokens.push(Token::Text(rest[1..=end].to_string()));
+            end + 2
+        } else if ch.is_ascii_digit() || ch == '.' {
+            let len = (rest.find(|c: char| !c.is_ascii_digit() && c != '.')).unwrap_or(rest.len());
+            tokens.push(Token::Number(
+                rest[..len].parse().map_err(|_| "invalid number")?,
+            ));
+            len
+        } else if ch.is_alphabetic() || ch == '_' {
+            let len = (rest.find(|c: char| !c.is_alphanumeric() && c != '_')).unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..len].to_string()));
+            len
+        } else {
+            let symbol = (SYMBOLS.into_iter())
+                .find(|symbol| rest.starts_with(symbol))
+                .ok_or("unexpected character")?;
+            tokens.push(Token::Symbol(symbol));
+            symbol.len()
+        };
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Reads the tokens of one line
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// How many brackets, signs, calls, and `if`s the parser is inside
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Move past the symbol or keyword `word` if it's next, returning whether it was
+    fn eat(&mut self, word: &str) -> bool {
+        let found = match self.peek() {
+            Some(Token::Symbol(symbol)) => *symbol == word,
+            Some(Token::Name(name)) => name == word,
+            _ => false,
+        };
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, word: &str, reason: &'static str) -> Result<(), &'static str> {
+        if self.eat(word) { Ok(()) } else { Err(reason) }
+    }
+
+    /// Parse with `parse` one level deeper, failing if that's deeper than [`MAX_DEPTH`]
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, &'static str>,
+    ) -> Result<T, &'static str> {
+        if self.depth >= MAX_DEPTH {
+            return Err("nested too deeply");
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn statement(&mut self) -> Result<Statement, &'static str> {
+        if self.eat("let") {
+            let Some(Token::Name(name)) = self.next() else {
+                return Err("expected a name after let");
+            };
+            if KEYWORDS.contains(&name.as_str()) {
+                return Err("can't use a keyword as a name");
+            }
+            self.expect("=", "expected = after the name")?;
+            Ok(Statement::Let(name, self.expr()?))
+        } else if self.eat("if") {
+            let condition = self.expr()?;
+            self.expect("then", "expected then after the condition")?;
+            Ok(Statement::If(
+                condition,
+                Box::new(self.nested(Self::statement)?),
+            ))
+        } else if self.eat("enable") {
+            Ok(Statement::Enable(self.expr()?, true))
+        } else if self.eat("disable") {
+            Ok(Statement::Enable(self.expr()?, false))
+        } else if self.eat("ratio") {
+            let splitter = self.expr()?;
+            let mut weights = Vec::with_capacity(3);
+            for _ in 0..3 {
+                self.expect(",", "ratio takes a splitter and three weights")?;
+                weights.push(self.expr()?);
+            }
+            let weights = weights
+                .try_into()
+                .map_err(|_| "ratio takes three weights")?;
+            Ok(Statement::Ratio(splitter, weights))
+        } else if self.eat("print") {
+            Ok(Statement::Print(self.expr()?))
+        } else {
+            Err("expected let, if, enable, disable, ratio, or print")
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, &'static str> {
+        let mut lhs = self.and()?;
+        while self.eat("or") {
+            lhs = Expr::Binary(Box::new(lhs), Op::Or, Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, &'static str> {
+        let mut lhs = self.comparison()?;
+        while self.eat("and") {
+            lhs = Expr::Binary(Box::new(lhs), Op::And, Box::new(self.comparison()?));
+        }
+        Ok(lhs)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, &'static str> {
+        let lhs = self.sum()?;
+        let ops = [
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ];
+        match ops.into_iter().find(|(symbol, _)| self.eat(symbol)) {
+            Some((_, op)) => Ok(Expr::Binary(Box::new(lhs), op, Box::new(self.sum()?))),
+            None => Ok(lhs),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, &'static str> {
+        let mut lhs = self.product()?;
+        loop {
+            let op = if self.eat("+") {
+                Op::Add
+            } else if self.eat("-") {
+                Op::Sub
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, &'static str> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = if self.eat("*") {
+                Op::Mul
+            } else if self.eat("/") {
+                Op::Div
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, &'static str> {
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.nested(Self::unary)?)));
+        }
+        if self.eat("not") {
+            return Ok(Expr::Not(Box::new(self.nested(Self::unary)?)));
+        }
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Text(text)) => Ok(Expr::Text(text)),
+            Some(Token::Symbol("(")) => {
+                let inner = self.nested(Self::expr)?;
+                self.expect(")", "expected )")?;
+                Ok(inner)
+            }
+            Some(Token::Name(name)) => match name.as_str() {
+                "true" => Ok(Expr::Bool(true)),
+                "false" => Ok(Expr::Bool(false)),
+                _ if KEYWORDS.contains(&name.as_str()) => Err("expected a value"),
+                _ if self.eat("(") => {
+                    let function = (Function::ALL.into_iter())
+                        .find(|function| function.name() == name)
+                        .ok_or("no function has that name")?;
+                    let mut args = Vec::new();
+                    if !self.eat(")") {
+                        args.push(self.nested(Self::expr)?);
+                        while self.eat(",") {
+                            args.push(self.nested(Self::expr)?);
+                        }
+                        self.expect(")", "expected ) after the arguments")?;
+                    }
+                    Ok(Expr::Call(function, args))
+                }
+                _ => Ok(Expr::Var(name)),
+            },
+            _ => Err("expected a value"),
+        }
+    }
+}
+
+/// Why a script couldn't be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseScriptError {
+    /// Counting from 1
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl std::fmt::Display for ParseScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ParseScriptError {}
+
+/// Why a script stopped partway through a tick
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    /// Counting from 1
+    pub line: usize,
+    pub reason: String,
+}
+
+impl std::fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// A program for a controller
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    /// Each statement, with the line it's on
+    statements: Vec<(usize, Statement)>,
+}
+
+impl FromStr for Script {
+    type Err = ParseScriptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut statements = Vec::new();
+        for (idx, line) in s.lines().enumerate() {
+            let error = |reason| ParseScriptError {
+                line: idx + 1,
+                reason,
+            };
+            let tokens = tokenize(line).map_err(error)?;
+            if tokens.is_empty() {
+                continue;
+            }
+            if tokens.len() > MAX_TOKENS {
+                return Err(error("the line is too long"));
+            }
+            let mut parser = Parser {
+                tokens,
+                pos: 0,
+                depth: 0,
+            };
+            let statement = parser.statement().map_err(error)?;
+            if parser.peek().is_some() {
+                return Err(error("unexpected words at the end of the line"));
+            }
+            statements.push((idx + 1, statement));
+        }
+        Ok(Self { statements })
+    }
+}
+
+impl Script {
+    /// Most steps a script can take in one tick, so a long script can't hold up the game
+    ///
+    /// Each value worked out and each statement run is one step.
+    pub const BUDGET: u32 = 10_000;
+
+    /// Run the script once, with `variables` from the last run, returning what it wants changed
+    ///
+    /// Nothing changes if it stops with an error, but variables set before the error keep their
+    /// new values.
+    pub fn run(
+        &self,
+        host: &dyn Host,
+        variables: &mut BTreeMap<String, Value>,
+    ) -> Result<Vec<Effect>, ScriptError> {
+        let mut run = Run {
+            host,
+            variables,
+            steps: 0,
+            effects: Vec::new(),
+        };
+        for (line, statement) in &self.statements {
+            (run.statement(statement)).map_err(|reason| ScriptError {
+                line: *line,
+                reason,
+            })?;
+        }
+        Ok(run.effects)
+    }
+}
+
+/// One run of a script
+struct Run<'a> {
+    host: &'a dyn Host,
+    variables: &'a mut BTreeMap<String, Value>,
+    steps: u32,
+    effects: Vec<Effect>,
+}
+
+impl Run<'_> {
+    fn step(&mut self) -> Result<(), String> {
+        self.steps += 1;
+        if self.steps > Script::BUDGET {
+            return Err(format!("took more than {} steps", Script::BUDGET));
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), String> {
+        self.step()?;
+        match statement {
+            Statement::Let(name, expr) => {
+                let value = self.eval(expr)?;
+                self.variables.insert(name.clone(), value);
+            }
+            Statement::If(condition, then) => {
+                if self.boolean(condition)? {
+                    self.statement(then)?;
+                }
+            }
+            &Statement::Enable(ref machine, enable) => {
+                let id = self.machine(machine)?;
+                self.effects.push(Effect::Enable(id, enable));
+            }
+            Statement::Ratio(splitter, weights) => {
+                let id = self.machine(splitter)?;
+                let mut ratio = [0; 3];
+                for (weight, expr) in ratio.iter_mut().zip(weights) {
+                    let n = self.number(expr)?;
+                    if !(0.0..=f64::from(u8::MAX)).contains(&n) || n.fract().abs() > 0.0 {
+                        return Err("weights are whole numbers from 0 to 255".to_string());
+                    }
+                    #[allow(
+                        clippy::cast_possible_truncation,
+                        clippy::cast_sign_loss,
+                        reason = "checked to be a whole number that fits"
+                    )]
+                    let n = n as u8;
+                    *weight = n;
+                }
+                self.effects.push(Effect::Ratio(id, ratio));
+            }
+            Statement::Print(expr) => {
+                let value = self.eval(expr)?;
+                self.effects.push(Effect::Print(value.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    fn number(&mut self, expr: &Expr) -> Result<f64, String> {
+        match self.eval(expr)? {
+            Value::Number(n) => Ok(n),
+            other => Err(format!("expected a number, got {other}")),
+        }
+    }
+
+    fn boolean(&mut self, expr: &Expr) -> Result<bool, String> {
+        match self.eval(expr)? {
+            Value::Bool(b) => Ok(b),
+            other => Err(format!("expected true or false, got {other}")),
+        }
+    }
+
+    fn machine(&mut self, expr: &Expr) -> Result<MachineId, String> {
+        match self.eval(expr)? {
+            Value::Machine(id) => Ok(id),
+            other => Err(format!("expected a machine, got {other}")),
+        }
+    }
+
+    fn eval(&mut self, expr: &Expr) -> Result<Value, String> {
+        self.step()?;
+        Ok(match expr {
+            &Expr::Number(n) => Value::Number(n),
+            &Expr::Bool(b) => Value::Bool(b),
+            Expr::Text(text) => Value::Text(text.clone()),
+            Expr::Var(name) => (self.variables.get(name).cloned()).unwrap_or(Value::Number(0.0)),
+            Expr::Neg(inner) => Value::Number(-self.number(inner)?),
+            Expr::Not(inner) => Value::Bool(!self.boolean(inner)?),
+            Expr::Binary(lhs, Op::And, rhs) => {
+                Value::Bool(self.boolean(lhs)? && self.boolean(rhs)?)
+            }
+            Expr::Binary(lhs, Op::Or, rhs) => Value::Bool(self.boolean(lhs)? || self.boolean(rhs)?),
+            &Expr::Binary(ref lhs, op, ref rhs) => {
+                let (lhs, rhs) = (self.eval(lhs)?, self.eval(rhs)?);
+                binary(lhs, op, rhs)?
+            }
+            &Expr::Call(function, ref args) => {
+                let args = (args.iter())
+                    .map(|arg| self.eval(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.call(function, &args)?
+            }
+        })
+    }
+
+    fn call(&self, function: Function, args: &[Value]) -> Result<Value, String> {
+        let host = self.host;
+        let missing = || format!("that machine is gone, in {}()", function.name());
+        let value = match (function, args) {
+            (Function::Machine, [Value::Number(x), Value::Number(y), Value::Number(z)]) => {
+                let [x, y, z] = [x, y, z].map(|&n| n.floor());
+                let range = f64::from(i16::MIN)..=f64::from(i16::MAX);
+                if ![x, y, z].iter().all(|n| range.contains(n)) {
+                    return Err("that block is nowhere near the factory".to_string());
+                }
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    reason = "checked to be in the range of i16"
+                )]
+                let position = FactoryVector3::new(x as i16, y as i16, z as i16);
+                let id = (host.machine_at(position))
+                    .ok_or_else(|| format!("there's no machine at {x}, {y}, {z}"))?;
+                Value::Machine(id)
+            }
+            (Function::Input | Function::Output, [Value::Machine(id), rest @ ..]) => {
+                let item = match rest {
+                    [] => None,
+                    [Value::Text(formula)] => Some(
+                        (formula.parse::<Formula>())
+                            .map_err(|e| format!("{formula} isn't an item: {e}"))?,
+                    ),
+                    _ => return Err(usage(function)),
+                };
+                let side = match function {
+                    Function::Input => BeltSide::Input,
+                    _ => BeltSide::Output,
+                };
+                let count = (host.count(*id, side, item.as_ref())).ok_or_else(missing)?;
+                Value::Number(count.into())
+            }
+            (Function::Fluid, [Value::Machine(id)]) => {
+                Value::Number(host.fluid(*id).ok_or_else(missing)?)
+            }
+            (Function::Temperature, [Value::Machine(id)]) => Value::Number(
+                host.temperature(*id)
+                    .ok_or("that machine doesn't heat up")?,
+            ),
+            (Function::Working, [Value::Machine(id)]) => Value::Bool(matches!(
+                host.status(*id).ok_or_else(missing)?,
+                MachineStatus::Working { .. }
+            )),
+            (Function::Enabled, [Value::Machine(id)]) => Value::Bool(host.is_enabled(*id)),
+            _ => return Err(usage(function)),
+        };
+        Ok(value)
+    }
+}
+
+/// How `function` is meant to be called
+fn usage(function: Function) -> String {
+    let args = match function {
+        Function::Machine => "x, y, z",
+        Function::Input | Function::Output => "machine or machine, \"item\"",
+        Function::Fluid | Function::Temperature | Function::Working | Function::Enabled => {
+            "machine"
+        }
+    };
+    format!("{}() takes {args}", function.name())
+}
+
+fn binary(lhs: Value, op: Op, rhs: Value) -> Result<Value, String> {
+    Ok(match (lhs, op, rhs) {
+        (Value::Number(a), _, Value::Number(b)) => match op {
+            Op::Add => Value::Number(a + b),
+            Op::Sub => Value::Number(a - b),
+            Op::Mul => Value::Number(a * b),
+            Op::Div if b.abs() < f64::MIN_POSITIVE => return Err("divided by zero".to_string()),
+            Op::Div => Value::Number(a / b),
+            Op::Lt => Value::Bool(a < b),
+            Op::Le => Value::Bool(a <= b),
+            Op::Gt => Value::Bool(a > b),
+            Op::Ge => Value::Bool(a >= b),
+            Op::Eq => Value::Bool((a - b).abs() < f64::EPSILON),
+            Op::Ne => Value::Bool((a - b).abs() >= f64::EPSILON),
+            Op::And | Op::Or => unreachable!("and and or are evaluated lazily"),
+        },
+        (a @ Value::Text(_), Op::Add, b) | (a, Op::Add, b @ Value::Text(_)) => {
+            let text = format!("{a}{b}");
+            if text.len() > MAX_TEXT {
+                return Err("text is too long".to_string());
+            }
+            Value::Text(text)
+        }
+        (a, Op::Eq, b) => Value::Bool(a == b),
+        (a, Op::Ne, b) => Value::Bool(a != b),
+        (a, _, b) => return Err(format!("can't do that with {a} and {b}")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::Entities;
+
+    /// One machine at the origin, as hot as it is full
+    struct TestHost {
+        id: MachineId,
+        input: u32,
+    }
+
+    impl Host for TestHost {
+        fn machine_at(&self, position: FactoryVector3) -> Option<MachineId> {
+            (position == FactoryVector3::new(0, 0, 0)).then_some(self.id)
+        }
+
+        fn count(&self, id: MachineId, side: BeltSide, _item: Option<&Formula>) -> Option<u32> {
+            (id == self.id).then_some(match side {
+                BeltSide::Input => self.input,
+                BeltSide::Output => 0,
+            })
+        }
+
+        fn fluid(&self, _id: MachineId) -> Option<f64> {
+            Some(0.0)
+        }
+
+        fn temperature(&self, _id: MachineId) -> Option<f64> {
+            Some(f64::from(self.input) * 10.0)
+        }
+
+        fn status(&self, _id: MachineId) -> Option<MachineStatus> {
+            Some(MachineStatus::Idle)
+        }
+
+        fn is_enabled(&self, _id: MachineId) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_script() {
+        let script = "\
+            # Comments and blank lines are skipped
+
+            let m = machine(0, 0, 0)
+            let ticks = ticks + 1
+            if input(m, \"Fe\") > 10 and temperature(m) >= 200 then disable m
+            if not enabled(m) or ticks == 2 then ratio m, 1, 2 * 2, 0
+            print \"tick \" + ticks
+        "
+        .parse::<Script>()
+        .unwrap();
+        let host = TestHost {
+            id: MachineId(Entities::new().spawn()),
+            input: 20,
+        };
+        let mut variables = BTreeMap::new();
+        assert_eq!(
+            script.run(&host, &mut variables),
+            Ok(vec![
+                Effect::Enable(host.id, false),
+                Effect::Print("tick 1".to_string())
+            ])
+        );
+        assert_eq!(
+            script.run(&host, &mut variables),
+            Ok(vec![
+                Effect::Enable(host.id, false),
+                Effect::Ratio(host.id, [1, 4, 0]),
+                Effect::Print("tick 2".to_string())
+            ]),
+            "variables are kept between runs"
+        );
+
+        let error = "print machine(1, 0, 0)".parse::<Script>().unwrap();
+        assert_eq!(
+            error.run(&host, &mut variables).unwrap_err().to_string(),
+            "line 1: there's no machine at 1, 0, 0"
+        );
+        assert_eq!(
+            "let if = 1".parse::<Script>(),
+            Err(ParseScriptError {
+                line: 1,
+                reason: "can't use a keyword as a name"
+            })
+        );
+        assert_eq!("print 1\nenable".parse::<Script>().unwrap_err().line, 2);
+    }
+
+    #[test]
+    fn test_budget() {
+        let long = "let x = x + 1\n".repeat(usize::try_from(Script::BUDGET).unwrap());
+        let script = long.parse::<Script>().unwrap();
+        let host = TestHost {
+            id: MachineId(Entities::new().spawn()),
+            input: 0,
+        };
+        let error = script.run(&host, &mut BTreeMap::new()).unwrap_err();
+        assert!(error.reason.starts_with("took more than"));
+
+        let doubling = "let s = s + s".parse::<Script>().unwrap();
+        let mut variables = BTreeMap::from([("s".to_string(), Value::Text("ab".to_string()))]);
+        let error = std::iter::repeat_with(|| doubling.run(&host, &mut variables))
+            .take(64)
+            .find_map(Result::err)
+            .expect("text can't grow forever across runs");
+        assert_eq!(error.reason, "text is too long");
+        assert!(matches!(&variables["s"], Value::Text(s) if s.len() <= MAX_TEXT));
+    }
+
+    /// What `print <expr>` prints
+    fn print(expr: &str) -> Result<String, String> {
+        let script = format!("print {expr}").parse::<Script>().unwrap();
+        let host = TestHost {
+            id: MachineId(Entities::new().spawn()),
+            input: 0,
+        };
+        match script.run(&host, &mut BTreeMap::new()) {
+            Ok(effects) => match effects.as_slice() {
+                [Effect::Print(text)] => Ok(text.clone()),
+                _ => panic!("printing should be the only effect"),
+            },
+            Err(error) => Err(error.reason),
+        }
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(print("1 + 2 * 3"), Ok("7".to_string()));
+        assert_eq!(print("(1 + 2) * 3"), Ok("9".to_string()));
+        assert_eq!(print("10 - 4 - 3"), Ok("3".to_string()), "left to right");
+        assert_eq!(print("-2 * -3"), Ok("6".to_string()));
+        assert_eq!(print("1 + 1 == 2 and 3 < 2"), Ok("false".to_string()));
+        assert_eq!(print("true or false and false"), Ok("true".to_string()));
+        assert_eq!(print("not false and false"), Ok("false".to_string()));
+        assert_eq!(print("1 / 0"), Err("divided by zero".to_string()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let reason = |source: &str| source.parse::<Script>().unwrap_err().reason;
+        assert_eq!(reason("print \"open"), "text is missing its closing quote");
+        assert_eq!(reason("print 1.2.3"), "invalid number");
+        assert_eq!(reason("print 1 ; 2"), "unexpected character");
+        assert_eq!(reason("let = 1"), "expected a name after let");
+        assert_eq!(reason("let x 1"), "expected = after the name");
+        assert_eq!(
+            reason("if true enable x"),
+            "expected then after the condition"
+        );
+        assert_eq!(reason("print (1 + 2"), "expected )");
+        assert_eq!(reason("print nothing(1)"), "no function has that name");
+        assert_eq!(
+            reason("ratio x, 1, 2"),
+            "ratio takes a splitter and three weights"
+        );
+        assert_eq!(
+            reason("print 1 2"),
+            "unexpected words at the end of the line"
+        );
+        assert_eq!(
+            reason("jump"),
+            "expected let, if, enable, disable, ratio, or print"
+        );
+    }
+
+    #[test]
+    fn test_nesting() {
+        let nest = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(print(&nest(MAX_DEPTH - 1)), Ok("1".to_string()));
+        let reason = |source: &str| source.parse::<Script>().unwrap_err().reason;
+        assert_eq!(
+            reason(&format!("print {}", nest(MAX_DEPTH + 1))),
+            "nested too deeply"
+        );
+        assert_eq!(
+            reason(&format!("print {}1", "-".repeat(100_000))),
+            "the line is too long"
+        );
+        assert_eq!(
+            reason(&format!("print {}1", "not -".repeat(MAX_DEPTH))),
+            "nested too deeply"
+        );
+        assert_eq!(
+            reason(&format!("{}print 1", "if true then ".repeat(MAX_DEPTH + 1))),
+            "nested too deeply"
+        );
+        assert_eq!(
+            reason(&format!("print 1{}", " + 1".repeat(MAX_TOKENS))),
+            "the line is too long",
+            "long chains nest as deep as brackets do"
+        );
+        let longest = format!("print 0{}", " + 1".repeat((MAX_TOKENS - 2) / 2));
+        assert_eq!(print(&longest["print ".len()..]), Ok("127".to_string()));
+    }
+}
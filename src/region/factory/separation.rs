@@ -0,0 +1,566 @@
+//! Machines that pull mixtures apart without changing what's in them
+//!
+//! A [`Distiller`] heats what it's fed until everything that boils by its setpoint has, and
+//! condenses that into its tank, leaving the rest as items. A [`Crystallizer`] dissolves what
+//! it's fed in hot water and cools it to its setpoint, so whatever's less soluble cold comes
+//! back out as crystals. Both go by the melting and boiling points in [`chem::phase`] and the
+//! solubilities in [`chem::solution`], and nothing they do makes or uses up any matter.
+//!
+//! [`chem::phase`]: crate::chem::phase
+//! [`chem::solution`]: crate::chem::solution
+
+use super::{
+    BeltInputNode, BeltNode, BeltOutputNode, Clearance, Machine, MachineBuffers, MachineKind,
+    MachineSize, MachineStatus, OrientedFootprint, PipeNode, Side, TickContext,
+};
+use crate::{
+    chem::{
+        molecule::Compound,
+        solution::{Solution, solubility},
+        units::{Kelvin, Liter, Mole},
+    },
+    container::{Container, FluidTank, FluidVolume, ItemSlots, ItemStack},
+    math::{
+        bounds::{Bounds, FactoryBounds},
+        coords::FactoryVector3,
+    },
+    ordinals::Cardinal2D,
+};
+use arrayvec::ArrayVec;
+use fixed_point::Q32_32;
+
+/// Items come in at the back, in the middle of the bottom of a machine 3 blocks across
+pub(super) const fn belt_input(position: FactoryVector3, rotation: Cardinal2D) -> BeltInputNode {
+    let offset = FactoryVector3::new(1, 0, 1).plus(Side::Back.offset());
+    BeltInputNode(BeltNode {
+        position: position.plus(rotation.rotate_block(offset)),
+        rotation: (rotation.plus(Side::Back.turn()))
+            .plus(Cardinal2D::West)
+            .as_ordinal(),
+    })
+}
+
+/// Items go out just past the front, in the middle of the bottom of a machine 3 blocks across
+pub(super) const fn belt_output(position: FactoryVector3, rotation: Cardinal2D) -> BeltOutputNode {
+    let out = Side::Front.offset();
+    let offset = FactoryVector3::new(1, 0, 1).plus(out).plus(out);
+    BeltOutputNode(BeltNode {
+        position: position.plus(rotation.rotate_block(offset)),
+        rotation: rotation.plus(Side::Front.turn()).as_ordinal(),
+    })
+}
+
+/// Fluid goes in or out just past the left, in the middle of the bottom of a machine 3 blocks
+/// across
+pub(super) const fn pipe_node(position: FactoryVector3, rotation: Cardinal2D) -> PipeNode {
+    let side = Side::Left.offset();
+    let offset = FactoryVector3::new(1, 0, 1).plus(side).plus(side);
+    PipeNode {
+        position: position.plus(rotation.rotate_block(offset)),
+        rotation: rotation.plus(Side::Left.turn()).as_ordinal().as_3d(),
+    }
+}
+
+/// A machine that works through one batch at a time
+pub(super) trait Batch {
+    /// Seconds each batch takes
+    const SECONDS: f32;
+
+    /// How far through the current batch it is, in `[0, 1]`, and what it's doing
+    fn run_state(&mut self) -> (&mut f32, &mut MachineStatus);
+
+    /// Begin a batch from what's in [`TickContext::buffers`], returning whether there was
+    /// anything to do
+    fn start(&mut self, ctx: &mut TickContext<'_>) -> bool;
+
+    /// Finish the current batch into [`TickContext::buffers`], returning whether it all fit
+    fn finish(&mut self, ctx: &mut TickContext<'_>) -> bool;
+
+    /// Work for `dt` seconds, starting a batch if it's idle and staying blocked until the last
+    /// one fits
+    ///
+    /// Time left over once a batch is done goes towards the next, the same as for
+    /// [recipes](super::recipe::Recipe::run).
+    fn run(&mut self, mut dt: f32, ctx: &mut TickContext<'_>) {
+        loop {
+            if *self.run_state().1 == MachineStatus::Idle && self.start(ctx) {
+                let (progress, status) = self.run_state();
+                *progress = 0.0;
+                *status = MachineStatus::Working { progress: 0.0 };
+            }
+            let (progress, status) = self.run_state();
+            if let MachineStatus::Working { .. } = status {
+                let left = (1.0 - *progress) * Self::SECONDS;
+                if dt < left {
+                    *progress += dt / Self::SECONDS;
+                    *status = MachineStatus::Working {
+                        progress: *progress,
+                    };
+                    return;
+                }
+                dt -= left;
+                *progress = 1.0;
+            }
+            if *status == MachineStatus::Idle {
+                return;
+            }
+            let done = self.finish(ctx);
+            let (progress, status) = self.run_state();
+            if !done {
+                *status = MachineStatus::Blocked;
+                return;
+            }
+            *progress = 0.0;
+            *status = MachineStatus::Idle;
+        }
+    }
+}
+
+/// Boils off everything it's fed that boils by its setpoint, condensing it into its tank
+///
+/// What's left, including anything whose boiling point isn't known, comes out as items.
+/// Distilled items become a liter of fluid for each kilogram of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distiller {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    /// What it heats each batch to
+    setpoint: Kelvin,
+    /// What's being heated in the current run
+    batch: Vec<ItemStack>,
+    /// Toward the end of the current run, in `[0, 1]`
+    progress: f32,
+    status: MachineStatus,
+}
+
+impl const Clearance for Distiller {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        Self::SIZE
+    }
+}
+
+impl Bounds<FactoryVector3> for Distiller {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl Distiller {
+    // SAFETY: 3 and 5 are not zero
+    pub const SIZE: MachineSize = unsafe { MachineSize::new_unchecked(3, 5, 3) };
+
+    /// Between where alcohol and water boil, to separate them
+    pub const DEFAULT_SETPOINT: Kelvin = Kelvin::new(363.15).unwrap();
+
+    /// Most items heated at once
+    const BATCH: u32 = 10;
+
+    /// Heating to [`Self::DEFAULT_SETPOINT`], taking items in at the back and putting what's
+    /// left out the front, with the distillate piped out of the left
+    pub const fn new(position: FactoryVector3, rotation: Cardinal2D) -> Self {
+        Self {
+            position,
+            rotation,
+            setpoint: Self::DEFAULT_SETPOINT,
+            batch: Vec::new(),
+            progress: 0.0,
+            status: MachineStatus::Idle,
+        }
+    }
+
+    /// What it heats each batch to
+    #[inline]
+    pub const fn setpoint(&self) -> Kelvin {
+        self.setpoint
+    }
+
+    /// Heat batches to `setpoint` from the next one on
+    pub const fn set_setpoint(&mut self, setpoint: Kelvin) {
+        self.setpoint = setpoint;
+    }
+
+    /// Whether `item` boils by the setpoint
+    fn boils(&self, item: &ItemStack) -> bool {
+        (item.item.compound().boiling_point()).is_some_and(|boiling| boiling <= self.setpoint)
+    }
+}
+
+impl Batch for Distiller {
+    /// Seconds to heat a batch
+    const SECONDS: f32 = 5.0;
+
+    fn run_state(&mut self) -> (&mut f32, &mut MachineStatus) {
+        (&mut self.progress, &mut self.status)
+    }
+
+    /// Up to [`Self::BATCH`] items from the input
+    fn start(&mut self, ctx: &mut TickContext<'_>) -> bool {
+        let buffers = &mut *ctx.buffers;
+        let mut taken = 0;
+        while taken < Self::BATCH
+            && let Some(stack) = buffers.input.extract(Self::BATCH - taken)
+        {
+            taken += stack.count;
+            self.batch.push(stack);
+        }
+        !self.batch.is_empty()
+    }
+
+    /// Condense what boiled into the tank and put the rest in the output
+    fn finish(&mut self, ctx: &mut TickContext<'_>) -> bool {
+        let buffers = &mut *ctx.buffers;
+        let mut fluid = buffers.fluid.clone();
+        let mut output = buffers.output.clone();
+        for stack in &self.batch {
+            let fits = if self.boils(stack) {
+                let molecule = stack.item.compound();
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    reason = "batches are far smaller than f32 loses precision at"
+                )]
+                let liters = (molecule.molar_mass() * f64::from(stack.count) / 1000.0) as f32;
+                let distillate = FluidVolume::new(molecule, Q32_32::from_f32(liters));
+                fluid.insert(distillate).is_none()
+            } else {
+                output.insert(stack.clone()).is_none()
+            };
+            if !fits {
+                return false;
+            }
+        }
+        buffers.fluid = fluid;
+        buffers.output = output;
+        self.batch.clear();
+        true
+    }
+}
+
+impl Machine for Distiller {
+    fn name(&self) -> String {
+        MachineKind::Distiller.name()
+    }
+
+    fn status(&self) -> MachineStatus {
+        self.status
+    }
+
+    fn tick(&mut self, dt: f32, ctx: &mut TickContext<'_>) {
+        self.run(dt, ctx);
+    }
+
+    /// In the middle of the back, heading in
+    fn belt_inputs(&self) -> ArrayVec<BeltInputNode, 8> {
+        [belt_input(self.position, self.rotation)]
+            .into_iter()
+            .collect()
+    }
+
+    /// Just past the middle of the front, heading out
+    fn belt_outputs(&self) -> ArrayVec<BeltOutputNode, 8> {
+        [belt_output(self.position, self.rotation)]
+            .into_iter()
+            .collect()
+    }
+
+    /// Just past the middle of the left, heading out
+    fn pipe_nodes(&self) -> ArrayVec<PipeNode, 8> {
+        [pipe_node(self.position, self.rotation)]
+            .into_iter()
+            .collect()
+    }
+
+    /// Room for a mixture, what's left of it, and a tank for the distillate
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(4, 100),
+            output: ItemSlots::new(4, 100),
+            fluid: FluidTank::new(Q32_32::from_i32(1000)),
+        }
+    }
+}
+
+/// Dissolves what it's fed in hot water, and cools it to its setpoint to crystallize back out
+/// whatever won't stay dissolved
+///
+/// Items that don't dissolve any more in the hot water, or at all, are filtered straight out.
+/// The water and whatever stays dissolved in it cold are kept for the next batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crystallizer {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    /// What it cools each batch to
+    setpoint: Kelvin,
+    /// The water and everything dissolved in it
+    liquor: Solution,
+    /// Toward the end of the current run, in `[0, 1]`
+    progress: f32,
+    status: MachineStatus,
+}
+
+impl const Clearance for Crystallizer {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        Self::SIZE
+    }
+}
+
+impl Bounds<FactoryVector3> for Crystallizer {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl Crystallizer {
+    // SAFETY: 3 and 2 are not zero
+    pub const SIZE: MachineSize = unsafe { MachineSize::new_unchecked(3, 2, 3) };
+
+    /// Just above freezing
+    pub const DEFAULT_SETPOINT: Kelvin = Kelvin::new(278.15).unwrap();
+
+    /// What items are dissolved at
+    pub const HOT: Kelvin = Kelvin::new(353.15).unwrap();
+
+    /// Water it keeps everything dissolved in
+    pub const VOLUME: Liter = Liter::new(1.0).unwrap();
+
+    /// Cooling to [`Self::DEFAULT_SETPOINT`], taking items in at the back and putting crystals
+    /// out the front, with water piped in on the left
+    pub fn new(position: FactoryVector3, rotation: Cardinal2D) -> Self {
+        Self {
+            position,
+            rotation,
+            setpoint: Self::DEFAULT_SETPOINT,
+            liquor: Solution::water(Liter::ZERO),
+            progress: 0.0,
+            status: MachineStatus::Idle,
+        }
+    }
+
+    /// What it cools each batch to
+    #[inline]
+    pub const fn setpoint(&self) -> Kelvin {
+        self.setpoint
+    }
+
+    /// Cool batches to `setpoint` from the next one on
+    pub const fn set_setpoint(&mut self, setpoint: Kelvin) {
+        self.setpoint = setpoint;
+    }
+
+    /// The water and everything dissolved in it
+    #[inline]
+    pub const fn liquor(&self) -> &Solution {
+        &self.liquor
+    }
+
+    /// How many whole items of each solute won't stay dissolved at the setpoint
+    fn crystals(&self) -> Vec<(Compound, u32)> {
+        (self.liquor.excess(self.setpoint).into_iter())
+            .filter_map(|(solute, excess)| {
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    clippy::cast_sign_loss,
+                    reason = "the crystallizer only holds a liter"
+                )]
+                let count = excess.get().floor() as u32;
+                (count > 0).then_some((solute, count))
+            })
+            .collect()
+    }
+}
+
+impl Batch for Crystallizer {
+    /// Seconds to cool a batch
+    const SECONDS: f32 = 8.0;
+
+    fn run_state(&mut self) -> (&mut f32, &mut MachineStatus) {
+        (&mut self.progress, &mut self.status)
+    }
+
+    /// Top up the water from the tank, then dissolve what's in the input while it's hot,
+    /// filtering out what doesn't dissolve, and start if there's anything to crystallize
+    fn start(&mut self, ctx: &mut TickContext<'_>) -> bool {
+        let buffers = &mut *ctx.buffers;
+        if let Some(short) = Self::VOLUME.checked_sub(self.liquor.volume)
+            && short > Liter::ZERO
+            && (buffers.fluid.contents()).is_some_and(|fluid| fluid.molecule == self.liquor.solvent)
+        {
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "the crystallizer holds a liter"
+            )]
+            let short = Q32_32::from_f32(short.get() as f32);
+            if let Some(water) = buffers.fluid.extract(short) {
+                // Exactly full if it could be, so rounding doesn't leave it a drop short
+                self.liquor.volume = if water.liters == short {
+                    Self::VOLUME
+                } else {
+                    (self.liquor.volume)
+                        + Liter::new(water.liters.to_f32().into()).unwrap_or(Liter::ZERO)
+                };
+            }
+        }
+        if self.liquor.volume < Self::VOLUME {
+            return false;
+        }
+        for slot in (0..buffers.input.stacks().len()).rev() {
+            let stack = &buffers.input.stacks()[slot];
+            let solute = stack.item.compound();
+            let room = solubility(&solute, &self.liquor.solvent, Self::HOT).map_or(0.0, |limit| {
+                (limit.in_volume(self.liquor.volume) - self.liquor.moles(&solute)).get()
+            });
+            #[allow(
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss,
+                reason = "clamped to what's in the stack"
+            )]
+            let dissolved = room.floor().clamp(0.0, f64::from(stack.count)) as u32;
+            if let Some(taken) = buffers.input.take(slot, dissolved) {
+                self.liquor.dissolve(solute, Mole::from_count(taken.count));
+            }
+            // Whatever's left of the stack didn't dissolve
+            if let Some(rest) = buffers.input.take(slot, u32::MAX)
+                && let Some(rest) = buffers.output.insert(rest)
+            {
+                _ = buffers.input.insert(rest);
+            }
+        }
+        !self.crystals().is_empty()
+    }
+
+    /// Put what won't stay dissolved at the setpoint in the output as crystals
+    ///
+    /// Only whole items crystallize, and the rest stays dissolved.
+    fn finish(&mut self, ctx: &mut TickContext<'_>) -> bool {
+        let buffers = &mut *ctx.buffers;
+        let mut filled = buffers.output.clone();
+        let crystals = self.crystals();
+        for (solute, count) in &crystals {
+            if (filled.insert(ItemStack::new(solute.clone().into(), *count))).is_some() {
+                return false;
+            }
+        }
+        for (solute, count) in crystals {
+            self.liquor.remove(&solute, Mole::from_count(count));
+        }
+        buffers.output = filled;
+        true
+    }
+}
+
+impl Machine for Crystallizer {
+    fn name(&self) -> String {
+        MachineKind::Crystallizer.name()
+    }
+
+    fn status(&self) -> MachineStatus {
+        self.status
+    }
+
+    fn tick(&mut self, dt: f32, ctx: &mut TickContext<'_>) {
+        self.run(dt, ctx);
+    }
+
+    /// In the middle of the back, heading in
+    fn belt_inputs(&self) -> ArrayVec<BeltInputNode, 8> {
+        [belt_input(self.position, self.rotation)]
+            .into_iter()
+            .collect()
+    }
+
+    /// Just past the middle of the front, heading out
+    fn belt_outputs(&self) -> ArrayVec<BeltOutputNode, 8> {
+        [belt_output(self.position, self.rotation)]
+            .into_iter()
+            .collect()
+    }
+
+    /// Just past the middle of the left, where water's piped in
+    fn pipe_nodes(&self) -> ArrayVec<PipeNode, 8> {
+        [pipe_node(self.position, self.rotation)]
+            .into_iter()
+            .collect()
+    }
+
+    /// Room for what's fed in, the crystals and what's filtered out, and water
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(4, 100),
+            output: ItemSlots::new(4, 100),
+            fluid: FluidTank::new(Q32_32::from_i32(100)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::coords::RailVector3,
+        region::{
+            factory::Factory,
+            rail::{deposit::Deposits, network::RailNetwork},
+        },
+    };
+
+    #[test]
+    fn test_separation() {
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-8, 0, 0),
+                max: FactoryVector3::new(12, 8, 10),
+            },
+        );
+        let still = factory.add_distiller(Distiller::new(
+            FactoryVector3::new(0, 0, 4),
+            Cardinal2D::East,
+        ));
+        let chiller = factory.add_crystallizer(Crystallizer::new(
+            FactoryVector3::new(6, 0, 4),
+            Cardinal2D::East,
+        ));
+        let item = |formula: &str, count| ItemStack::new(formula.parse().unwrap(), count);
+        let input = &mut factory.buffers_mut(still).unwrap().input;
+        input.insert(item("C2H6O", 2));
+        input.insert(item("H2O", 3));
+        let buffers = factory.buffers_mut(chiller).unwrap();
+        let water = FluidVolume::new(Solution::water_molecule(), Q32_32::from_i32(10));
+        buffers.fluid.insert(water);
+        // Saltpeter with some salt mixed in, more than dissolves even hot
+        buffers.input.insert(item("KNO3", 20));
+        buffers.input.insert(item("NaCl", 5));
+        factory.tick(
+            10.0,
+            1.0,
+            &mut Deposits::default(),
+            &mut RailNetwork::default(),
+        );
+
+        let buffers = factory.buffers(still).unwrap();
+        let alcohol = buffers.fluid.contents().unwrap();
+        assert_eq!(alcohol.molecule, "C2H6O".parse().unwrap());
+        assert!((alcohol.liters.to_f32() - 0.092).abs() < 0.001);
+        assert_eq!(
+            buffers.output.stacks(),
+            [item("H2O", 3)],
+            "water boils hotter"
+        );
+
+        let buffers = factory.buffers(chiller).unwrap();
+        assert_eq!(
+            buffers.output.stacks(),
+            [item("KNO3", 18)],
+            "4 that didn't dissolve and 14 that crystallized"
+        );
+        assert_eq!(buffers.fluid.stored(), Q32_32::from_i32(9));
+        let salt = "NaCl".parse().unwrap();
+        let crystallizer = &factory.crystallizers()[0];
+        assert_eq!(crystallizer.liquor().moles(&salt), Mole::new(5.0).unwrap());
+    }
+}
@@ -0,0 +1,316 @@
+//! Ticking a factory's machines on several threads at once
+//!
+//! Each tick is done in two phases. First, every machine that only changes its own state, buffers,
+//! [`Thermal`], [`Vessel`], and [`Animator`] is ticked, spread across [`Factory::threads`] threads, with what
+//! it made and used up written to [`Statistics`] of its own. Then, going through the machines in
+//! the order they'd be ticked one at a time, those statistics are added to the factory's, gas
+//! leaks out of overheated machines, ruptures are noted down, and the machines that [share the
+//! world](super::Machine::shares_world) are ticked with it.
+//!
+//! Nothing a machine reads in the first phase is written by any other machine, so the factory
+//! comes out exactly the same whichever threads its machines were ticked on.
+//!
+//! Machines far from the player are only ticked every few ticks, see [`lod`](super::lod).
+
+use super::{Factory, Machine, MachineBuffers, MachineStatus, TickContext, gas::GasField};
+use crate::{
+    chem::units::{Joule, Mole},
+    container::FluidTank,
+    math::bounds::SpacialBounds,
+    region::{
+        factory::{
+            pressure::{self, Vessel},
+            thermal::{self, Thermal},
+        },
+        rail::{deposit::Deposits, network::RailNetwork},
+    },
+    stats::Statistics,
+};
+use engine::animation::{AnimationState, Animator};
+use std::collections::HashMap;
+
+/// What's outside a machine, which only machines that [share it](Machine::shares_world) get
+#[derive(Debug)]
+pub struct SharedWorld<'a> {
+    /// Ore in the ground, for machines that dig it up
+    pub deposits: &'a mut Deposits,
+    /// The rails outside, for machines that load trains
+    pub rail: &'a mut RailNetwork,
+    /// Gas in the factory's air, for machines that clear it
+    pub gas: &'a mut GasField,
+}
+
+/// One machine's part of a tick, with everything of its own that it changes
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "each is about something different, not states of one thing"
+)]
+struct Job<'a> {
+    machine: &'a mut dyn Machine,
+    /// Seconds since it was last ticked, more than one tick's if it's far from the player
+    elapsed: f32,
+    buffers: &'a mut MachineBuffers,
+    thermal: Option<&'a mut Thermal>,
+    vessel: Option<&'a mut Vessel>,
+    animator: Option<&'a mut Animator>,
+    disabled: bool,
+    /// Whether a relief valve is keeping its vessel's pressure down
+    relieved: bool,
+    /// What it made and used up, if it was ticked in the first phase
+    stats: Statistics,
+    /// Whether it's overheated, so gas is leaking out of it
+    leaking: bool,
+    /// Whether its vessel burst this tick
+    ruptured: bool,
+}
+
+impl Job<'_> {
+    /// Tick the machine for the time it's been waiting with `daylight` outside, and `world` if it
+    /// shares it, reporting what it made and used up to `stats`, and animate it `dt` seconds
+    fn run(
+        &mut self,
+        dt: f32,
+        daylight: f32,
+        world: Option<SharedWorld<'_>>,
+        stats: &mut Statistics,
+    ) {
+        // Overheated machines shut down until they've cooled off, and ruptured ones for good
+        let running = !self.disabled
+            && !(self.thermal.as_ref()).is_some_and(|thermal| thermal.is_overheated())
+            && !(self.vessel.as_ref()).is_some_and(|vessel| vessel.is_ruptured());
+        let temperature =
+            (self.thermal.as_ref()).map_or(thermal::AMBIENT, |thermal| thermal.temperature());
+        let work_dt = self.elapsed * self.machine.efficiency(daylight);
+        if running {
+            self.machine.tick(
+                work_dt,
+                &mut TickContext {
+                    buffers: self.buffers,
+                    temperature,
+                    stats,
+                    world,
+                },
+            );
+        }
+        let mut boiled_off = 0.0;
+        if let Some(thermal) = &mut self.thermal {
+            let heat = if running {
+                self.machine.heat() * f64::from(work_dt)
+            } else {
+                Joule::ZERO
+            };
+            boiled_off = thermal.tick(self.elapsed, heat, &mut self.buffers.fluid);
+            // Getting that hot breaks the seals
+            self.leaking = thermal.is_overheated();
+        }
+        if let Some(vessel) = &mut self.vessel {
+            let temperature =
+                (self.thermal.as_ref()).map_or(thermal::AMBIENT, |thermal| thermal.temperature());
+            let steam =
+                Mole::new(f64::from(boiled_off) * pressure::STEAM_PER_LITER).unwrap_or(Mole::ZERO);
+            if vessel.tick(temperature, steam, self.relieved) {
+                self.ruptured = true;
+                // Whatever was in it spills out
+                self.buffers.fluid = FluidTank::new(self.buffers.fluid.capacity());
+            }
+        }
+        if let Some(animator) = &mut self.animator {
+            let doing = self.machine.status();
+            let pose = match doing {
+                MachineStatus::Working { .. } => AnimationState::Working,
+                MachineStatus::Idle | MachineStatus::Blocked => AnimationState::Idle,
+            };
+            animator.tick(
+                dt,
+                pose,
+                doing.activity() * self.machine.efficiency(daylight),
+            );
+        }
+    }
+}
+
+impl Factory {
+    /// The fewest machines worth starting another thread for
+    const MACHINES_PER_THREAD: usize = 64;
+
+    /// Advance every machine by `dt` seconds with `daylight` outside, mining from `deposits` and
+    /// loading trains on `rail`
+    pub(super) fn tick_machines(
+        &mut self,
+        dt: f32,
+        daylight: f32,
+        deposits: &mut Deposits,
+        rail: &mut RailNetwork,
+    ) {
+        // Taken out so machines can be borrowed alongside their buffers
+        let mut buffers = std::mem::take(&mut self.buffers);
+        let mut thermals = std::mem::take(&mut self.thermals);
+        let mut vessels = std::mem::take(&mut self.vessels);
+        let mut animators = std::mem::take(&mut self.animators);
+        let mut gas = std::mem::take(&mut self.gas);
+        let mut statistics = std::mem::take(&mut self.stats);
+        let disabled = std::mem::take(&mut self.disabled);
+        let mut lod = std::mem::take(&mut self.lod);
+        let relieved = self.relieved();
+        let threads = self.threads.get();
+        let origin = self.origin;
+
+        let mut buffers_of = buffers.iter_mut().collect::<HashMap<_, _>>();
+        let mut thermal_of = thermals.iter_mut().collect::<HashMap<_, _>>();
+        let mut vessel_of = vessels.iter_mut().collect::<HashMap<_, _>>();
+        let mut animator_of = animators.iter_mut().collect::<HashMap<_, _>>();
+        let mut jobs = (self.machines_mut().enumerate())
+            .filter_map(|(idx, (id, machine))| {
+                let near = lod.is_near(machine.bounds(), &origin);
+                let elapsed = lod.step(id.0, idx, near, dt)?;
+                Some(Job {
+                    buffers: buffers_of.remove(&id.0)?,
+                    thermal: thermal_of.remove(&id.0),
+                    vessel: vessel_of.remove(&id.0),
+                    relieved: relieved.contains(&id.0),
+                    // Nobody's close enough to see it move
+                    animator: animator_of.remove(&id.0).filter(|_| near),
+                    disabled: disabled.contains(id.0),
+                    machine,
+                    elapsed,
+                    stats: Statistics::new(),
+                    leaking: false,
+                    ruptured: false,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut local = (jobs.iter_mut())
+            .filter(|job| !job.machine.shares_world())
+            .collect::<Vec<_>>();
+        let per_thread = (local.len().div_ceil(threads)).max(Self::MACHINES_PER_THREAD);
+        let run_all = |jobs: &mut [&mut Job<'_>]| {
+            for job in jobs {
+                let mut stats = Statistics::new();
+                job.run(dt, daylight, None, &mut stats);
+                job.stats = stats;
+            }
+        };
+        std::thread::scope(|scope| {
+            let mut chunks = local.chunks_mut(per_thread);
+            let here = chunks.next();
+            for chunk in chunks {
+                scope.spawn(move || run_all(chunk));
+            }
+            if let Some(chunk) = here {
+                run_all(chunk);
+            }
+        });
+
+        let mut ruptures = Vec::new();
+        for job in &mut jobs {
+            if job.machine.shares_world() {
+                let world = SharedWorld {
+                    deposits,
+                    rail,
+                    gas: &mut gas,
+                };
+                job.run(dt, daylight, Some(world), &mut statistics);
+            } else {
+                statistics.merge(&job.stats);
+            }
+            if job.leaking {
+                gas.release(job.machine.bounds().mid(), Self::LEAK * job.elapsed);
+            }
+            if job.ruptured {
+                ruptures.push(job.machine.bounds().mid());
+            }
+        }
+        drop(jobs);
+        lod.finish_tick();
+
+        self.buffers = buffers;
+        self.thermals = thermals;
+        self.vessels = vessels;
+        self.ruptures.extend(ruptures);
+        self.animators = animators;
+        self.gas = gas;
+        self.stats = statistics;
+        self.disabled = disabled;
+        self.lod = lod;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chem::reaction::Reaction,
+        container::{Container, ItemStack},
+        math::{
+            bounds::FactoryBounds,
+            coords::{FactoryVector3, RailVector3},
+        },
+        ordinals::Cardinal2D,
+        plugin::MachineType,
+        region::factory::{MachineSize, gas::Vent, plugin::PluginMachine, recipe::Recipe},
+    };
+    use std::{num::NonZeroUsize, time::Duration};
+
+    #[test]
+    fn test_threads_agree() {
+        let reaction = "CaCO3 -> CaO + CO2".parse::<Reaction>().unwrap();
+        let kiln = &*Box::leak(Box::new(MachineType {
+            name: "kiln",
+            label: "Kiln",
+            size: MachineSize::new(2, 2, 2).unwrap(),
+            recipe: Some(Recipe::new(
+                "Calcination",
+                reaction.balance().unwrap(),
+                Duration::from_secs(2),
+            )),
+            thermal: None,
+            vessel: None,
+            model: None,
+        }));
+        let run = |threads: usize| {
+            let mut factory = Factory::new(
+                RailVector3::default(),
+                FactoryBounds {
+                    min: FactoryVector3::new(0, 0, 0),
+                    max: FactoryVector3::new(64, 4, 64),
+                },
+            );
+            factory.threads = NonZeroUsize::new(threads).unwrap();
+            let ids = (0..300)
+                .map(|i| {
+                    let position = FactoryVector3::new(i % 20 * 3, 0, i / 20 * 3);
+                    let id = factory.add_plugin_machine(PluginMachine::new(
+                        kiln,
+                        position,
+                        Cardinal2D::East,
+                    ));
+                    let limestone =
+                        ItemStack::new("CaCO3".parse().unwrap(), 1 + u32::try_from(i % 5).unwrap());
+                    _ = factory.buffers_mut(id).unwrap().input.insert(limestone);
+                    id
+                })
+                .collect::<Vec<_>>();
+            // Shares the world, so it's ticked after the rest
+            factory.add_vent(Vent::new(FactoryVector3::new(0, 0, 60), Cardinal2D::East));
+            for _ in 0..10 {
+                factory.tick(
+                    0.5,
+                    1.0,
+                    &mut Deposits::default(),
+                    &mut RailNetwork::default(),
+                );
+            }
+            let buffers = (ids.iter())
+                .map(|&id| factory.buffers(id).unwrap().clone())
+                .collect::<Vec<_>>();
+            (buffers, factory.statistics().clone())
+        };
+        let one = run(1);
+        assert!(
+            one.1.total_produced(&"CaO".parse().unwrap()) > 0,
+            "the kilns should've run"
+        );
+        assert!(one == run(4), "more threads shouldn't change anything");
+    }
+}
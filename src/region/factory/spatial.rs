@@ -0,0 +1,350 @@
+use super::get_ray_collision_box;
+use crate::math::{
+    bounds::{FactoryBounds, SpacialBounds},
+    coords::FactoryVector3,
+};
+use raylib::prelude::*;
+use std::{collections::HashMap, hash::Hash};
+
+type Cell = [i16; 3];
+
+/// Uniform grid of buckets for accelerating spatial queries on machines
+///
+/// Note: all coordinates are in Factory coordinates
+#[derive(Debug, Clone)]
+pub struct SpatialIndex<K> {
+    cells: HashMap<Cell, Vec<K>>,
+    entries: HashMap<K, FactoryBounds>,
+    /// Every occupied cell is inside this range (but not every cell inside it is occupied)
+    extent: Option<(Cell, Cell)>,
+}
+
+impl<K> Default for SpatialIndex<K> {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+            extent: None,
+        }
+    }
+}
+
+impl<K: Copy + Ord + Hash> SpatialIndex<K> {
+    /// Side length of each grid cell, in meters
+    pub const CELL_SIZE: i16 = 8;
+
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    const fn cell_of(v: FactoryVector3) -> Cell {
+        [
+            v.x.div_euclid(Self::CELL_SIZE),
+            v.y.div_euclid(Self::CELL_SIZE),
+            v.z.div_euclid(Self::CELL_SIZE),
+        ]
+    }
+
+    /// Every cell touched by `bounds`
+    fn cells_in(bounds: &FactoryBounds) -> impl Iterator<Item = Cell> {
+        let [x0, y0, z0] = Self::cell_of(bounds.min);
+        let [x1, y1, z1] = Self::cell_of(bounds.max);
+        (x0..=x1).flat_map(move |x| (y0..=y1).flat_map(move |y| (z0..=z1).map(move |z| [x, y, z])))
+    }
+
+    /// The number of entries in the index
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The bounds `key` was inserted with
+    #[inline]
+    #[must_use]
+    pub fn get(&self, key: &K) -> Option<&FactoryBounds> {
+        self.entries.get(key)
+    }
+
+    /// Insert `key` occupying `bounds`, replacing its previous bounds if already present
+    pub fn insert(&mut self, key: K, bounds: FactoryBounds) {
+        self.remove(&key);
+        for cell in Self::cells_in(&bounds) {
+            self.cells.entry(cell).or_default().push(key);
+        }
+        let (lo, hi) = (Self::cell_of(bounds.min), Self::cell_of(bounds.max));
+        self.extent = Some(match self.extent {
+            Some((min, max)) => (
+                std::array::from_fn(|i| min[i].min(lo[i])),
+                std::array::from_fn(|i| max[i].max(hi[i])),
+            ),
+            None => (lo, hi),
+        });
+        self.entries.insert(key, bounds);
+    }
+
+    /// Remove `key` from the index, returning the bounds it occupied
+    pub fn remove(&mut self, key: &K) -> Option<FactoryBounds> {
+        let bounds = self.entries.remove(key)?;
+        for cell in Self::cells_in(&bounds) {
+            if let Some(bucket) = self.cells.get_mut(&cell) {
+                bucket.retain(|k| k != key);
+                if bucket.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+        if self.entries.is_empty() {
+            self.extent = None;
+        }
+        Some(bounds)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+        self.extent = None;
+    }
+
+    /// Every entry overlapping `area`, sorted by key
+    #[must_use]
+    pub fn query_aabb(&self, area: &FactoryBounds) -> Vec<K> {
+        let mut found = Self::cells_in(area)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|key| self.entries[key].overlaps(area))
+            .collect::<Vec<_>>();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// Every entry touching the sphere of `radius` around `center`, sorted by key
+    #[must_use]
+    pub fn query_sphere(&self, center: Vector3, radius: f32) -> Vec<K> {
+        #[allow(clippy::cast_possible_truncation, reason = "this is intentional")]
+        let to_factory = |v: Vector3| FactoryVector3 {
+            x: v.x.floor() as i16,
+            y: v.y.floor() as i16,
+            z: v.z.floor() as i16,
+        };
+        let area = FactoryBounds {
+            min: to_factory(center - radius),
+            max: to_factory(center + radius),
+        };
+        let mut found = Self::cells_in(&area)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|key| {
+                let bounds = &self.entries[key];
+                let closest = center.clamp(bounds.min.as_vec3(), bounds.max.as_vec3());
+                closest.distance_squared(center) <= radius * radius
+            })
+            .collect::<Vec<_>>();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// The nearest entry hit by `ray` within `max_distance`
+    ///
+    /// Walks the grid cells along the ray, so only entries near the ray are tested.
+    #[must_use]
+    pub fn query_ray(&self, ray: Ray, max_distance: f32) -> Option<(K, RayCollision)> {
+        let (min_cell, max_cell) = self.extent?;
+        let cell_size = f32::from(Self::CELL_SIZE);
+        let extent = BoundingBox {
+            min: Vector3::from_array(min_cell.map(f32::from)) * cell_size,
+            max: Vector3::from_array(max_cell.map(|c| f32::from(c) + 1.0)) * cell_size,
+        };
+
+        // Clip the ray to the occupied extent
+        let t_start = if extent.contains(&ray.position) {
+            0.0
+        } else {
+            let entry = get_ray_collision_box(ray, extent);
+            if !entry.hit {
+                return None;
+            }
+            entry.distance
+        };
+        if t_start > max_distance {
+            return None;
+        }
+
+        let start = ray.position + ray.direction * t_start;
+        #[allow(clippy::cast_possible_truncation, reason = "clamped to the extent")]
+        let mut cell: Cell = std::array::from_fn(|i| {
+            ((start[i] / cell_size).floor() as i16).clamp(min_cell[i], max_cell[i])
+        });
+        let step: [i16; 3] = std::array::from_fn(|i| {
+            if ray.direction[i] > 0.0 {
+                1
+            } else if ray.direction[i] < 0.0 {
+                -1
+            } else {
+                0
+            }
+        });
+        // Distance along the ray to the next cell boundary on each axis
+        let mut t_next: [f32; 3] = std::array::from_fn(|i| match step[i] {
+            0 => f32::INFINITY,
+            s => {
+                let boundary = f32::from(cell[i] + s.max(0)) * cell_size;
+                t_start + (boundary - start[i]) / ray.direction[i]
+            }
+        });
+        let t_delta: [f32; 3] = std::array::from_fn(|i| match step[i] {
+            0 => f32::INFINITY,
+            _ => cell_size / ray.direction[i].abs(),
+        });
+
+        let mut nearest: Option<(K, RayCollision)> = None;
+        loop {
+            for &key in self.cells.get(&cell).into_iter().flatten() {
+                let bounds = &self.entries[&key];
+                let collision = get_ray_collision_box(
+                    ray,
+                    BoundingBox {
+                        min: bounds.min.as_vec3(),
+                        max: bounds.max.as_vec3(),
+                    },
+                );
+                if collision.hit
+                    && collision.distance <= max_distance
+                    && nearest
+                        .as_ref()
+                        .is_none_or(|(_, best)| collision.distance < best.distance)
+                {
+                    nearest = Some((key, collision));
+                }
+            }
+
+            let axis = (0..3)
+                .min_by(|&a, &b| t_next[a].total_cmp(&t_next[b]))
+                .unwrap();
+            let t_exit = t_next[axis];
+
+            // Nothing in a later cell can be closer than a hit already found before this cell's exit
+            if nearest
+                .as_ref()
+                .is_some_and(|(_, best)| best.distance <= t_exit)
+                || t_exit > max_distance
+            {
+                break;
+            }
+
+            cell[axis] += step[axis];
+            if !(min_cell[axis]..=max_cell[axis]).contains(&cell[axis]) {
+                break;
+            }
+            t_next[axis] += t_delta[axis];
+        }
+        nearest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(min: (i16, i16, i16), max: (i16, i16, i16)) -> FactoryBounds {
+        FactoryBounds {
+            min: FactoryVector3::new(min.0, min.1, min.2),
+            max: FactoryVector3::new(max.0, max.1, max.2),
+        }
+    }
+
+    #[test]
+    fn test_query_aabb() {
+        let mut index = SpatialIndex::new();
+        index.insert(0, bounds((0, 0, 0), (2, 2, 3)));
+        index.insert(1, bounds((-20, 0, -20), (-18, 2, -17)));
+        index.insert(2, bounds((7, 0, 7), (9, 2, 10)));
+        assert_eq!(index.query_aabb(&bounds((1, 1, 1), (8, 1, 8))), vec![0, 2]);
+        assert_eq!(
+            index.query_aabb(&bounds((-19, 0, -19), (-19, 0, -19))),
+            vec![1]
+        );
+        assert!(
+            index
+                .query_aabb(&bounds((30, 0, 30), (40, 0, 40)))
+                .is_empty()
+        );
+
+        index.remove(&2);
+        assert_eq!(index.query_aabb(&bounds((1, 1, 1), (8, 1, 8))), vec![0]);
+        index.insert(0, bounds((-20, 0, 0), (-18, 2, 3)));
+        assert!(index.query_aabb(&bounds((1, 1, 1), (8, 1, 8))).is_empty());
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn test_query_sphere() {
+        let mut index = SpatialIndex::new();
+        index.insert(0, bounds((0, 0, 0), (2, 2, 3)));
+        index.insert(1, bounds((10, 0, 0), (12, 2, 3)));
+        assert_eq!(
+            index.query_sphere(Vector3::new(4.0, 1.0, 1.0), 2.5),
+            vec![0]
+        );
+        assert_eq!(
+            index.query_sphere(Vector3::new(6.0, 1.0, 1.0), 4.5),
+            vec![0, 1]
+        );
+        assert!(
+            index
+                .query_sphere(Vector3::new(6.0, 10.0, 1.0), 4.5)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_query_ray() {
+        let along_x = |x: f32, y: f32, dir: f32| Ray {
+            position: Vector3::new(x, y, 1.0),
+            direction: Vector3::new(dir, 0.0, 0.0),
+        };
+        let mut index = SpatialIndex::new();
+        assert!(index.query_ray(along_x(-5.0, 1.0, 1.0), 100.0).is_none());
+
+        index.insert(0, bounds((0, 0, 0), (2, 2, 3)));
+        index.insert(1, bounds((10, 0, 0), (12, 2, 3)));
+        index.insert(2, bounds((20, 0, 0), (22, 2, 3)));
+        let hit = |ray, max_distance| {
+            index
+                .query_ray(ray, max_distance)
+                .map(|(key, collision)| (key, collision.distance))
+        };
+        assert_eq!(hit(along_x(-5.0, 1.0, 1.0), 100.0), Some((0, 5.0)));
+        assert_eq!(
+            hit(along_x(30.0, 1.0, -1.0), 100.0),
+            Some((2, 8.0)),
+            "the nearest of everything along the ray"
+        );
+        assert_eq!(
+            hit(along_x(6.0, 1.0, 1.0), 100.0),
+            Some((1, 4.0)),
+            "starting between entries"
+        );
+        assert_eq!(hit(along_x(-5.0, 10.0, 1.0), 100.0), None, "passes over");
+        assert_eq!(hit(along_x(-5.0, 1.0, -1.0), 100.0), None, "faces away");
+        assert_eq!(hit(along_x(-5.0, 1.0, 1.0), 4.0), None, "out of reach");
+        assert_eq!(hit(along_x(-5.0, 1.0, 1.0), 5.0), Some((0, 5.0)));
+        assert_eq!(
+            hit(along_x(8.0, 1.0, -1.0), 100.0),
+            Some((0, 6.0)),
+            "ignores what's behind it"
+        );
+    }
+}
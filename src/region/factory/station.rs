@@ -0,0 +1,332 @@
+//! Stations, where trains are loaded and unloaded
+//!
+//! A station is built against a wall of the factory, facing out onto the rails. Whenever a train
+//! is waiting at its [`Stop`](crate::region::rail::network::Stop), it moves cargo between the
+//! train and its own buffers as its [`CargoRule`]s say, and belts take it from there.
+
+use super::{
+    BeltInputNode, BeltNode, BeltOutputNode, Clearance, Machine, MachineBuffers, MachineKind,
+    MachineSize, MachineStatus, OrientedFootprint, TickContext,
+};
+use crate::{
+    container::{Container, Item, ItemSlots},
+    math::{
+        bounds::{Bounds, FactoryBounds},
+        coords::FactoryVector3,
+    },
+    ordinals::Cardinal2D,
+    region::rail::network::StopId,
+};
+use arrayvec::ArrayVec;
+
+/// Which way a [`CargoRule`] moves cargo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CargoDirection {
+    /// From the station onto the train
+    Load,
+    /// From the train into the station
+    Unload,
+}
+
+/// Something a station moves between itself and trains
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CargoRule {
+    pub direction: CargoDirection,
+    /// Only this item, or anything if [`None`]
+    pub filter: Option<Item>,
+    /// Loading stops once the train has this many of the item, and unloading once it's down to
+    /// this many
+    pub threshold: u32,
+}
+
+/// How many of `item` are in `slots`
+fn count(slots: &ItemSlots, item: &Item) -> u32 {
+    (slots.stacks().iter())
+        .filter(|stack| &stack.item == item)
+        .fold(0, |total, stack| total.saturating_add(stack.count))
+}
+
+impl CargoRule {
+    /// Fill trains with up to `threshold` of `filter`, or of everything
+    pub const fn load(filter: Option<Item>, threshold: u32) -> Self {
+        Self {
+            direction: CargoDirection::Load,
+            filter,
+            threshold,
+        }
+    }
+
+    /// Empty trains down to `threshold` of `filter`, or of everything
+    pub const fn unload(filter: Option<Item>, threshold: u32) -> Self {
+        Self {
+            direction: CargoDirection::Unload,
+            filter,
+            threshold,
+        }
+    }
+
+    /// Whether the rule moves `item` when the train has `in_train` of it
+    fn wants(&self, item: &Item, in_train: u32) -> bool {
+        self.filter.as_ref().is_none_or(|filter| filter == item)
+            && match self.direction {
+                CargoDirection::Load => in_train < self.threshold,
+                CargoDirection::Unload => in_train > self.threshold,
+            }
+    }
+
+    /// Move one item between the station's `buffers` and a train's `cargo`, if the rule wants
+    /// any of them moved and there's room, returning the item it moved
+    fn apply(&self, buffers: &mut MachineBuffers, cargo: &mut ItemSlots) -> Option<Item> {
+        let (from, to) = match self.direction {
+            CargoDirection::Load => (&mut buffers.input, cargo),
+            CargoDirection::Unload => (cargo, &mut buffers.output),
+        };
+        let candidates = (from.stacks().iter())
+            .map(|stack| stack.item.clone())
+            .collect::<Vec<_>>();
+        for item in candidates {
+            let in_train = match self.direction {
+                CargoDirection::Load => count(to, &item),
+                CargoDirection::Unload => count(from, &item),
+            };
+            if !self.wants(&item, in_train) {
+                continue;
+            }
+            let Some(slot) = (from.stacks().iter()).position(|stack| stack.item == item) else {
+                continue;
+            };
+            let Some(one) = from.take(slot, 1) else {
+                continue;
+            };
+            match to.insert(one) {
+                None => return Some(item),
+                // It just came out, so there's room for it
+                Some(rest) => _ = from.insert(rest),
+            }
+        }
+        None
+    }
+}
+
+/// Loads and unloads trains waiting on the rails outside, to and from belts
+///
+/// Cargo to load comes in on a belt from behind, and unloaded cargo leaves on a belt beside it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Station {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    /// Where trains stop for it
+    pub stop: StopId,
+    /// Tried in order for each item moved
+    pub rules: Vec<CargoRule>,
+    /// Toward moving the next item, in `[0, 1]`
+    progress: f32,
+    status: MachineStatus,
+}
+
+impl const Clearance for Station {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        Self::SIZE
+    }
+}
+
+impl Bounds<FactoryVector3> for Station {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl Station {
+    // SAFETY: 2 is not zero
+    pub const SIZE: MachineSize = unsafe { MachineSize::new_unchecked(2, 2, 2) };
+
+    /// Items moved per second
+    pub const RATE: f32 = 10.0;
+
+    pub const fn new(
+        position: FactoryVector3,
+        rotation: Cardinal2D,
+        stop: StopId,
+        rules: Vec<CargoRule>,
+    ) -> Self {
+        Self {
+            position,
+            rotation,
+            stop,
+            rules,
+            progress: 0.0,
+            status: MachineStatus::Idle,
+        }
+    }
+
+    /// The space a station at `position` facing `rotation` would take up
+    pub fn footprint(position: FactoryVector3, rotation: Cardinal2D) -> FactoryBounds {
+        OrientedFootprint::new(Self::SIZE, rotation).bounds_at(position)
+    }
+
+    /// The blocks just past the front of a station at `position` facing `rotation`, one of which
+    /// the rails have to run through
+    pub fn front(position: FactoryVector3, rotation: Cardinal2D) -> [FactoryVector3; 2] {
+        let z = Self::SIZE.length.get().into();
+        [0, 1].map(|x| position.plus(rotation.rotate_block(FactoryVector3 { x, y: 0, z })))
+    }
+
+    /// The block at `offset` from the station's position when it faces east, rotated with the
+    /// station
+    const fn block(&self, offset: FactoryVector3) -> FactoryVector3 {
+        self.position.plus(self.rotation.rotate_block(offset))
+    }
+}
+
+impl Machine for Station {
+    fn name(&self) -> String {
+        MachineKind::Station.name()
+    }
+
+    fn status(&self) -> MachineStatus {
+        self.status
+    }
+
+    /// Loads and unloads trains on the rails outside
+    fn shares_world(&self) -> bool {
+        true
+    }
+
+    /// Works through the rules while a train is waiting, showing how long until it leaves
+    ///
+    /// Cargo loaded onto trains counts as used up, and cargo unloaded as made, since it leaves or
+    /// enters the factory. Unloaded cargo is also counted as delivered to the station's stop.
+    fn tick(&mut self, dt: f32, ctx: &mut TickContext<'_>) {
+        let rail = ctx.world.as_mut().map(|world| &mut *world.rail);
+        let Some(train) = rail.and_then(|rail| rail.train_at_mut(self.stop)) else {
+            self.progress = 0.0;
+            self.status = MachineStatus::Idle;
+            return;
+        };
+        self.progress += dt * Self::RATE;
+        while self.progress >= 1.0 {
+            let moved = (self.rules.iter()).find_map(|rule| {
+                let item = rule.apply(ctx.buffers, &mut train.cargo)?;
+                Some((rule.direction, item))
+            });
+            let Some((direction, item)) = moved else {
+                self.progress = 0.0;
+                self.status = MachineStatus::Idle;
+                return;
+            };
+            match direction {
+                CargoDirection::Load => ctx.stats.consumed(&item, 1),
+                CargoDirection::Unload => {
+                    ctx.stats.produced(&item, 1);
+                    ctx.stats.delivered(self.stop, &item, 1);
+                }
+            }
+            self.progress -= 1.0;
+            self.status = MachineStatus::Working {
+                progress: train.departure_progress().unwrap_or(0.0),
+            };
+        }
+    }
+
+    fn belt_inputs(&self) -> ArrayVec<BeltInputNode, 8> {
+        let mut arr = ArrayVec::new();
+        arr.push(BeltInputNode(BeltNode {
+            position: self.block(FactoryVector3 { x: 0, y: 0, z: 0 }),
+            rotation: self.rotation.as_ordinal(),
+        }));
+        arr
+    }
+
+    fn belt_outputs(&self) -> ArrayVec<BeltOutputNode, 8> {
+        let mut arr = ArrayVec::new();
+        let MachineSize { width, .. } = self.clearance();
+        arr.push(BeltOutputNode(BeltNode {
+            position: self.block(FactoryVector3 {
+                x: i16::from(width.get()) - 1,
+                y: 0,
+                z: -1,
+            }),
+            rotation: self.rotation.plus(Cardinal2D::West).as_ordinal(),
+        }));
+        arr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        container::ItemStack,
+        math::coords::RailVector3,
+        region::{
+            factory::Factory,
+            rail::{
+                deposit::Deposits,
+                network::{RailNetwork, Track, Train},
+            },
+        },
+    };
+
+    #[test]
+    fn test_station() {
+        let mut rail = RailNetwork::new(vec![Track {
+            points: vec![RailVector3::new(0, 0, 2), RailVector3::new(10, 0, 2)],
+        }]);
+        let stop = rail.stop_at(RailVector3::new(4, 0, 2)).unwrap();
+        let water = "H2O".parse::<Item>().unwrap();
+        let iron = "Fe".parse::<Item>().unwrap();
+        let mut train = Train::new(0, 4.0);
+        assert_eq!(train.cargo.insert(ItemStack::new(water.clone(), 5)), None);
+        rail.add_train(train);
+        rail.tick(1.0);
+
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(0, 0, -10),
+                max: FactoryVector3::new(10, 8, 2),
+            },
+        );
+        let station = factory.add_station(Station::new(
+            FactoryVector3::new(4, 0, 0),
+            Cardinal2D::East,
+            stop,
+            vec![
+                CargoRule::unload(Some(water.clone()), 2),
+                CargoRule::load(None, 3),
+            ],
+        ));
+        let buffers = factory.buffers_mut(station).unwrap();
+        assert_eq!(buffers.input.insert(ItemStack::new(iron.clone(), 10)), None);
+
+        factory.tick(0.2, 1.0, &mut Deposits::default(), &mut rail);
+        assert!(matches!(
+            factory.machine(station).unwrap().status(),
+            MachineStatus::Working { .. }
+        ));
+        factory.tick(1.0, 1.0, &mut Deposits::default(), &mut rail);
+        assert_eq!(
+            factory.machine(station).unwrap().status(),
+            MachineStatus::Idle,
+            "nothing's left to move"
+        );
+        let buffers = factory.buffers(station).unwrap();
+        assert_eq!(buffers.output.stacks(), [ItemStack::new(water.clone(), 3)]);
+        assert_eq!(buffers.input.stacks(), [ItemStack::new(iron.clone(), 7)]);
+        let cargo = &rail.train_at_mut(stop).unwrap().cargo;
+        assert_eq!(count(cargo, &water), 2);
+        assert_eq!(count(cargo, &iron), 3);
+
+        let stats = factory.statistics();
+        assert_eq!(stats.get(&water).unwrap().produced.minutes_ago(0), 3);
+        assert_eq!(stats.total_delivered(stop, &water), 3);
+        assert_eq!(
+            stats.get(&iron).unwrap().consumed.minutes_ago(0),
+            3,
+            "loading trains sends cargo away"
+        );
+    }
+}
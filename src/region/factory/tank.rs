@@ -0,0 +1,225 @@
+//! Machines that hold a lot of one fluid, to even out what flows through pipes
+//!
+//! A tank only holds fluid; how it gets in and out is up to
+//! [`Factory::move_fluids`](super::Factory), which also levels out tanks piped to each other.
+
+use super::{
+    Clearance, Factory, Machine, MachineBuffers, MachineKind, MachineSize, OrientedFootprint,
+    PipeNode, Side, TickContext, VesselProperties,
+};
+use crate::{
+    chem::units::{Liter, Pascal},
+    container::{FluidTank, ItemSlots, Molecule},
+    math::{
+        bounds::{Bounds, FactoryBounds},
+        coords::{FactoryVector3, PlayerVector3, RailVector3},
+        frustum::Frustum,
+    },
+    ordinals::Cardinal2D,
+    rl_helpers::DynRaylibDraw3D,
+};
+use arrayvec::ArrayVec;
+use fixed_point::Q32_32;
+use raylib::prelude::*;
+
+/// Holds up to [`Tank::CAPACITY`] liters of a single fluid, piped in and out of any side
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tank {
+    pub position: FactoryVector3,
+    pub rotation: Cardinal2D,
+    /// Seconds into the ripple of the fluid's surface, for drawing it
+    ripple: f32,
+}
+
+impl const Clearance for Tank {
+    #[inline]
+    fn clearance(&self) -> MachineSize {
+        Self::SIZE
+    }
+}
+
+impl Bounds<FactoryVector3> for Tank {
+    type BoundingBox = FactoryBounds;
+
+    fn bounds(&self) -> Self::BoundingBox {
+        OrientedFootprint::new(self.clearance(), self.rotation).bounds_at(self.position)
+    }
+}
+
+impl Tank {
+    // SAFETY: 3 is not zero
+    pub const SIZE: MachineSize = unsafe { MachineSize::new_unchecked(3, 3, 3) };
+
+    /// Liters it holds when full
+    pub const CAPACITY: i32 = 20_000;
+
+    /// Sealed with a little room above the fluid, but not built to hold much pressure
+    pub const VESSEL: VesselProperties = VesselProperties {
+        headspace: Liter::new(2000.0).unwrap(),
+        rating: Pascal::new(3.0e5).unwrap(),
+    };
+
+    /// Seconds for the surface to rise and fall once
+    const RIPPLE_PERIOD: f32 = 3.0;
+
+    /// How far the surface rises and falls, as a fraction of the tank's height
+    const RIPPLE_HEIGHT: f32 = 0.01;
+
+    /// The middle block of the bottom, when the tank faces east
+    const MIDDLE: FactoryVector3 = FactoryVector3::new(1, 0, 1);
+
+    pub const fn new(position: FactoryVector3, rotation: Cardinal2D) -> Self {
+        Self {
+            position,
+            rotation,
+            ripple: 0.0,
+        }
+    }
+
+    /// How high the surface of `liters` of fluid is right now, as a fraction of the tank's
+    /// height
+    ///
+    /// The surface ripples less the closer it is to the top or bottom, so it never goes past
+    /// them.
+    pub fn surface(&self, liters: Q32_32) -> f32 {
+        #[allow(
+            clippy::cast_precision_loss,
+            reason = "the capacity is well within what f32 holds exactly"
+        )]
+        let fill = (liters.to_f32() / Self::CAPACITY as f32).clamp(0.0, 1.0);
+        let wave = (self.ripple / Self::RIPPLE_PERIOD * std::f32::consts::TAU).sin();
+        let room = fill.min(1.0 - fill);
+        fill + wave * Self::RIPPLE_HEIGHT.min(room)
+    }
+
+    /// The block at `offset` from the tank's position when it faces east, rotated with the tank
+    const fn block(&self, offset: FactoryVector3) -> FactoryVector3 {
+        self.position.plus(self.rotation.rotate_block(offset))
+    }
+}
+
+/// What color `molecule` is drawn as in a tank
+///
+/// Each fluid gets a color of its own, stepping around the color wheel by the golden angle so
+/// that similar ones don't look alike.
+fn fluid_color(molecule: &Molecule) -> Color {
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "only the hue matters, and molar masses are small"
+    )]
+    let hue = (molecule.molar_mass() as f32 * 137.508) % 360.0;
+    Color {
+        a: 180,
+        ..Color::color_from_hsv(hue, 0.6, 0.8)
+    }
+}
+
+impl Machine for Tank {
+    fn name(&self) -> String {
+        MachineKind::Tank.name()
+    }
+
+    fn tick(&mut self, dt: f32, _ctx: &mut TickContext<'_>) {
+        self.ripple = (self.ripple + dt) % Self::RIPPLE_PERIOD;
+    }
+
+    /// Just past the middle of each side, heading out
+    fn pipe_nodes(&self) -> ArrayVec<PipeNode, 8> {
+        [Side::Front, Side::Left, Side::Back, Side::Right]
+            .into_iter()
+            .map(|side| PipeNode {
+                position: self.block(Self::MIDDLE.plus(side.offset()).plus(side.offset())),
+                rotation: self.rotation.plus(side.turn()).as_ordinal().as_3d(),
+            })
+            .collect()
+    }
+
+    fn vessel_properties(&self) -> Option<VesselProperties> {
+        Some(Self::VESSEL)
+    }
+
+    /// Only fluid
+    fn empty_buffers(&self) -> MachineBuffers {
+        MachineBuffers {
+            input: ItemSlots::new(0, 0),
+            output: ItemSlots::new(0, 0),
+            fluid: FluidTank::new(Q32_32::from_i32(Self::CAPACITY)),
+        }
+    }
+}
+
+impl Factory {
+    /// Draw the fluid in every tank that might be visible through `frustum`, up to its surface
+    pub(super) fn draw_tank_fluids(
+        &self,
+        d: &mut dyn DynRaylibDraw3D,
+        player_pos: &PlayerVector3,
+        origin: &RailVector3,
+        frustum: &Frustum,
+    ) {
+        /// How far inside the tank's walls the fluid is drawn, so they don't hide it
+        const INSET: f32 = 0.05;
+        for (entity, tank) in self.tanks.iter() {
+            let Some(fluid) =
+                (self.buffers.get(entity)).and_then(|buffers| buffers.fluid.contents())
+            else {
+                continue;
+            };
+            let FactoryBounds { min, max } = tank.bounds();
+            let (min, max) = (
+                min.to_player_relative(player_pos, origin) + Vector3::splat(INSET),
+                max.to_player_relative(player_pos, origin) - Vector3::splat(INSET),
+            );
+            let top = min.y + (max.y - min.y) * tank.surface(fluid.liters);
+            let max = Vector3::new(max.x, top, max.z);
+            if frustum.intersects_box(&BoundingBox { min, max }) {
+                let size = max - min;
+                let color = fluid_color(&fluid.molecule);
+                d.draw_cube((min + max) * 0.5, size.x, size.y, size.z, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        container::{Container, FluidVolume},
+        region::factory::Pipe,
+    };
+
+    #[test]
+    fn test_tank() {
+        let mut factory = Factory::new(
+            RailVector3::default(),
+            FactoryBounds {
+                min: FactoryVector3::new(-8, 0, 0),
+                max: FactoryVector3::new(12, 8, 10),
+            },
+        );
+        let left = Tank::new(FactoryVector3::new(0, 0, 4), Cardinal2D::East);
+        let right = Tank::new(FactoryVector3::new(6, 0, 4), Cardinal2D::East);
+        let [left_nodes, right_nodes] = [&left, &right].map(Tank::pipe_nodes);
+        assert_eq!(left_nodes.len(), 4, "one on each side");
+        // Piped between the sides facing each other, from the second tank to the first, which is
+        // against the way fluid would flow if they weren't both tanks
+        let (a, b) = (right_nodes[3], left_nodes[1]);
+        assert_eq!(a.position, FactoryVector3::new(5, 0, 5));
+        assert_eq!(b.position, FactoryVector3::new(3, 0, 5));
+        let (left, right) = (factory.add_tank(left), factory.add_tank(right));
+        factory.add_pipe(Pipe {
+            a,
+            b,
+            path: vec![a.position, FactoryVector3::new(4, 0, 5), b.position],
+        });
+        let water = FluidVolume::new("H2O".parse().unwrap(), Q32_32::from_i32(1000));
+        factory.buffers_mut(left).unwrap().fluid.insert(water);
+        for _ in 0..100 {
+            factory.move_fluids(1.0);
+        }
+        let [left, right] = [left, right].map(|id| factory.buffers(id).unwrap().fluid.stored());
+        assert!(right > Q32_32::from_i32(400), "the tanks should level out");
+        assert!(left > Q32_32::from_i32(400), "the tanks should level out");
+    }
+}
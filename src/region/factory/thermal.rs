@@ -0,0 +1,262 @@
+//! Heat, which machines build up as they work and have to get rid of
+//!
+//! Machines with [`ThermalProperties`] get a [`Thermal`] when they're built. The heat they
+//! release warms them up, some of it leaks away into the air, and water piped in boils off to
+//! carry away more once they run hot. A machine that gets too hot shuts down until it's cooled
+//! off.
+
+use crate::{
+    chem::units::{Joule, Kelvin},
+    container::{Container, FluidTank, Molecule},
+};
+use fixed_point::Q32_32;
+use raylib::prelude::*;
+
+/// The temperature of the air in factories
+pub const AMBIENT: Kelvin = Kelvin::new(293.15).unwrap();
+
+/// How a type of machine heats up and cools down
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThermalProperties {
+    /// Joules to warm the machine by one kelvin
+    pub heat_capacity: f64,
+    /// Watts lost to the air for each kelvin the machine is over [`AMBIENT`]
+    pub dissipation: f64,
+    /// The machine shuts down once it's this hot
+    pub max_temperature: Kelvin,
+}
+
+impl ThermalProperties {
+    /// Coolant keeps the machine below this, and it starts again once it's cooled down to it
+    #[must_use]
+    pub fn safe_temperature(&self) -> Kelvin {
+        AMBIENT + (self.max_temperature - AMBIENT) * Thermal::SAFE_FRACTION
+    }
+}
+
+/// How hot a machine is
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thermal {
+    pub properties: ThermalProperties,
+    temperature: Kelvin,
+    /// Whether it got too hot, and hasn't cooled down to the safe temperature since
+    overheated: bool,
+}
+
+/// Interpolate from `a` at 0 to `b` at 1
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "channels stay between the two colors'"
+)]
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let channel = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * t).round() as u8;
+    Color::new(
+        channel(a.r, b.r),
+        channel(a.g, b.g),
+        channel(a.b, b.b),
+        channel(a.a, b.a),
+    )
+}
+
+impl Thermal {
+    /// Joules carried away by each liter of coolant that boils off, about what water takes
+    pub const COOLANT_HEAT: f64 = 2.26e6;
+    /// How far from [`AMBIENT`] to the maximum temperature is safe
+    const SAFE_FRACTION: f64 = 0.75;
+    /// How hot a machine has to be to glow, see [`Self::heat_level`]
+    const GLOW_START: f32 = 0.5;
+    /// Health lost per second touching a machine that's about to overheat
+    const BURN: f32 = 30.0;
+    /// What a machine glows as it heats up from [`Self::GLOW_START`] to overheating
+    const GLOW_RAMP: [Color; 3] = [
+        Color::new(139, 0, 0, 0),
+        Color::new(255, 64, 0, 96),
+        Color::new(255, 220, 160, 160),
+    ];
+
+    /// A machine that's as warm as the air
+    pub const fn new(properties: ThermalProperties) -> Self {
+        Self {
+            properties,
+            temperature: AMBIENT,
+            overheated: false,
+        }
+    }
+
+    #[inline]
+    pub const fn temperature(&self) -> Kelvin {
+        self.temperature
+    }
+
+    /// Whether the machine is shut down to cool off
+    #[inline]
+    pub const fn is_overheated(&self) -> bool {
+        self.overheated
+    }
+
+    /// The fluid that's boiled off to cool machines
+    pub fn coolant() -> Molecule {
+        "H2O".parse().expect("water should be a valid molecule")
+    }
+
+    /// How hot the machine is, from 0 when it's as warm as the air to 1 when it overheats
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "only used for how the machine looks"
+    )]
+    pub fn heat_level(&self) -> f32 {
+        let max = self.properties.max_temperature;
+        ((self.temperature - AMBIENT) / (max - AMBIENT)).clamp(0.0, 1.0) as f32
+    }
+
+    /// How far from starting to glow to overheating the machine is, or less than 0 if it isn't
+    /// glowing
+    fn glow_level(&self) -> f32 {
+        (self.heat_level() - Self::GLOW_START) / (1.0 - Self::GLOW_START)
+    }
+
+    /// The color the machine glows, if it's hot enough to
+    pub fn glow(&self) -> Option<Color> {
+        let t = self.glow_level();
+        if t <= 0.0 {
+            return None;
+        }
+        let [cold, warm, hot] = Self::GLOW_RAMP;
+        Some(if t < 0.5 {
+            lerp_color(cold, warm, 2.0 * t)
+        } else {
+            lerp_color(warm, hot, 2.0 * t - 1.0)
+        })
+    }
+
+    /// Health lost per second touching the machine, which only burns once it's hot enough to
+    /// glow
+    pub fn burn_damage(&self) -> f32 {
+        self.glow_level().max(0.0) * Self::BURN
+    }
+
+    /// Take in `heat` released over the last `dt` seconds, lose some to the air, and boil off
+    /// coolant from `tank` to keep under the safe temperature
+    ///
+    /// Returns the liters of coolant boiled off.
+    pub fn tick(&mut self, dt: f32, heat: Joule, tank: &mut FluidTank) -> f32 {
+        let ThermalProperties {
+            heat_capacity,
+            dissipation,
+            max_temperature,
+        } = self.properties;
+        let ambient = AMBIENT.get();
+        let mut temperature = self.temperature.get() + heat.get() / heat_capacity;
+        // Solved exactly over the step, so long ticks can't overshoot the air temperature
+        let kept = (-dissipation * f64::from(dt) / heat_capacity).exp();
+        temperature = ambient + (temperature - ambient) * kept;
+
+        let safe = self.properties.safe_temperature().get();
+        let coolant = Self::coolant();
+        let mut boiled_off = 0.0;
+        if temperature > safe
+            && tank
+                .contents()
+                .is_some_and(|fluid| fluid.molecule == coolant)
+        {
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "machines don't boil off billions of liters at once"
+            )]
+            let needed = ((temperature - safe) * heat_capacity / Self::COOLANT_HEAT) as f32;
+            if let Some(boiled) = tank.extract(Q32_32::from_f32(needed)) {
+                boiled_off = boiled.liters.to_f32();
+                temperature -= f64::from(boiled_off) * Self::COOLANT_HEAT / heat_capacity;
+            }
+        }
+
+        // Absorbing heat can't take it below absolute zero
+        self.temperature = Kelvin::new(temperature.max(0.0)).unwrap_or(max_temperature);
+        if self.temperature >= max_temperature {
+            self.overheated = true;
+        } else if self.temperature.get() <= safe {
+            self.overheated = false;
+        }
+        boiled_off
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::FluidVolume;
+
+    const PROPERTIES: ThermalProperties = ThermalProperties {
+        heat_capacity: 1000.0,
+        dissipation: 0.0,
+        max_temperature: Kelvin::new(693.15).unwrap(),
+    };
+
+    #[test]
+    fn test_overheating() {
+        let mut tank = FluidTank::new(Q32_32::from_i32(100));
+        let mut thermal = Thermal::new(PROPERTIES);
+        assert_eq!(thermal.glow(), None);
+        assert!(thermal.burn_damage().abs() < f32::EPSILON);
+
+        thermal.tick(1.0, Joule::new(100_000.0).unwrap(), &mut tank);
+        assert!((thermal.temperature().get() - 393.15).abs() < 1e-6);
+        assert!(!thermal.is_overheated());
+        thermal.tick(1.0, Joule::new(400_000.0).unwrap(), &mut tank);
+        assert!(thermal.is_overheated());
+        assert!(thermal.glow().is_some(), "overheated machines glow");
+        assert!((thermal.burn_damage() - Thermal::BURN).abs() < 1e-4);
+
+        thermal.tick(1.0, Joule::new(-100_000.0).unwrap(), &mut tank);
+        assert!(
+            thermal.is_overheated(),
+            "machines stay shut down until they're safe"
+        );
+        thermal.tick(1.0, Joule::new(-200_000.0).unwrap(), &mut tank);
+        assert!(!thermal.is_overheated());
+
+        let mut cooling = Thermal::new(ThermalProperties {
+            dissipation: 10.0,
+            ..PROPERTIES
+        });
+        cooling.tick(1.0, Joule::new(100_000.0).unwrap(), &mut tank);
+        cooling.tick(10_000.0, Joule::ZERO, &mut tank);
+        assert!(
+            (cooling.temperature().get() - AMBIENT.get()).abs() < 1e-3,
+            "machines cool to the air temperature"
+        );
+    }
+
+    #[test]
+    fn test_coolant() {
+        let safe = PROPERTIES.safe_temperature().get();
+        let mut tank = FluidTank::new(Q32_32::from_i32(100));
+        let salt = "NaCl".parse::<Molecule>().unwrap();
+        assert_eq!(
+            tank.insert(FluidVolume::new(salt, Q32_32::from_i32(10))),
+            None
+        );
+        let mut thermal = Thermal::new(PROPERTIES);
+        thermal.tick(
+            1.0,
+            Joule::new((safe + 50.0 - AMBIENT.get()) * 1000.0).unwrap(),
+            &mut tank,
+        );
+        assert!(
+            (thermal.temperature().get() - safe - 50.0).abs() < 1e-6,
+            "only water is used as coolant"
+        );
+
+        let mut tank = FluidTank::new(Q32_32::from_i32(100));
+        let water = FluidVolume::new(Thermal::coolant(), Q32_32::from_i32(10));
+        assert_eq!(tank.insert(water), None);
+        thermal.tick(1.0, Joule::ZERO, &mut tank);
+        assert!(
+            (thermal.temperature().get() - safe).abs() < 1e-2,
+            "coolant keeps machines at the safe temperature"
+        );
+        let boiled = 10.0 - tank.stored().to_f32();
+        assert!((f64::from(boiled) - 50_000.0 / Thermal::COOLANT_HEAT).abs() < 1e-5);
+    }
+}
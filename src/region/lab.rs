@@ -2,16 +2,24 @@ use raylib::prelude::*;
 
 use crate::{
     chem::element::Element,
+    container::{Container, Item, ItemSlots},
     math::{
         bounds::{Bounds, LabBounds, SpacialBounds},
         coords::{LabVector3, PlayerCoord, PlayerVector3},
     },
     player::Player,
-    resource::Resources,
+    research::sample_points,
+    resource::{AssetId, PERIODIC_OFFSETS, Resources},
     rl_helpers::DynRaylibDraw3D,
+    rng::Rng,
 };
 
-use super::{PlayerOverlap, Region};
+use super::{PlayerOverlap, Region, factory::get_ray_collision_box};
+use instrument::Instrument;
+
+pub mod instrument;
+pub mod instrument_panel;
+pub mod table_panel;
 
 // Lab is not grid aligned, and small enough that I don't care about floating point error
 
@@ -32,6 +40,39 @@ pub struct PeriodicTable {
 }
 
 impl PeriodicTable {
+    /// Side length of each element's block, in meters
+    pub const CELL_SIZE: f32 = 0.25;
+
+    /// Number of columns and rows in [`PERIODIC_OFFSETS`]
+    pub fn grid_size() -> (u8, u8) {
+        PERIODIC_OFFSETS
+            .iter()
+            .fold((0, 0), |(cols, rows), &(col, row)| {
+                (cols.max(col + 1), rows.max(row + 1))
+            })
+    }
+
+    /// Height of `element`'s block relative to [`Self::CELL_SIZE`]
+    fn y_scale(&self, element: Element) -> f32 {
+        match self.variable {
+            PeriodTableVariable::NoVariable => 1.0,
+            PeriodTableVariable::Protons => f32::from(element.protons().get()) / 50.0,
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "heights don't need f64 precision"
+            )]
+            PeriodTableVariable::Mass => (element.standard_atomic_weight() / 125.0) as f32,
+            // Flat for elements without one, so they stand out
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "heights don't need f64 precision"
+            )]
+            PeriodTableVariable::ElectroNegativity => element
+                .electronegativity()
+                .map_or(0.05, |value| (value / 1.7) as f32),
+        }
+    }
+
     pub fn draw(
         &self,
         d: &mut dyn DynRaylibDraw3D,
@@ -40,20 +81,13 @@ impl PeriodicTable {
         player: &Player,
         origin: &PlayerVector3,
     ) {
-        let mesh = &resources.periodic_table_mesh;
+        let Some((mesh, cells)) = resources.periodic_table() else {
+            return;
+        };
         let Vector3 { x, y, z } = self.position.to_player_relative(&player.position, origin);
         let translation = Matrix::translate(x, y, z);
-        for (element, (matrix, material)) in Element::list()
-            .iter()
-            .zip(resources.periodic_table_mats.iter())
-        {
-            let protons = element.protons().get();
-            let y_scale = match self.variable {
-                PeriodTableVariable::NoVariable => 1.0,
-                PeriodTableVariable::Protons => f32::from(protons) / 50.0,
-                PeriodTableVariable::Mass => todo!(),
-                PeriodTableVariable::ElectroNegativity => todo!(),
-            };
+        for (element, (matrix, material)) in Element::list().iter().zip(cells.iter()) {
+            let y_scale = self.y_scale(*element);
             // SAFETY: TBD
             let material = unsafe { WeakMaterial::from_raw(**material) };
             d.draw_mesh(
@@ -71,18 +105,339 @@ impl PeriodicTable {
 impl Bounds<Vector3> for PeriodicTable {
     type BoundingBox = BoundingBox;
 
+    /// In lab coordinates
     fn bounds(&self) -> Self::BoundingBox {
-        todo!()
+        let half_cell = 0.5 * Self::CELL_SIZE;
+        let (cols, rows) = Self::grid_size();
+        let height = Element::list()
+            .iter()
+            .map(|&element| self.y_scale(element))
+            .fold(0.0, f32::max)
+            * Self::CELL_SIZE;
+        let position = self.position.as_vec3();
+        BoundingBox {
+            min: position + Vector3::new(-half_cell, 0.0, -half_cell),
+            max: position
+                + Vector3::new(
+                    f32::from(cols) * Self::CELL_SIZE - half_cell,
+                    height,
+                    f32::from(rows) * Self::CELL_SIZE - half_cell,
+                ),
+        }
     }
 }
 
 impl LabEquipment for PeriodicTable {}
 
+/// Where the player wakes up after dying
+#[derive(Debug)]
+pub struct Bed {
+    /// The corner at the foot of the bed, on the floor
+    pub position: LabVector3,
+}
+
+impl Bed {
+    /// Width, height, and length in meters
+    const SIZE: Vector3 = Vector3::new(1.0, 0.5, 2.0);
+
+    /// Where the player respawns, standing beside the bed
+    pub fn spawn_point(&self, origin: &PlayerVector3) -> PlayerVector3 {
+        let beside = LabVector3::from_f32(Self::SIZE.x + 0.5, 0.0, 0.5 * Self::SIZE.z);
+        self.position.plus(beside).to_player(origin)
+    }
+
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D, player: &Player, origin: &PlayerVector3) {
+        let center = self.position.to_player_relative(&player.position, origin) + Self::SIZE * 0.5;
+        d.draw_cube_v(center, Self::SIZE, Color::LIGHTGRAY);
+        d.draw_cube_wires_v(center, Self::SIZE, Color::DARKGRAY);
+    }
+}
+
+impl Bounds<Vector3> for Bed {
+    type BoundingBox = BoundingBox;
+
+    /// In lab coordinates
+    fn bounds(&self) -> Self::BoundingBox {
+        let position = self.position.as_vec3();
+        BoundingBox {
+            min: position,
+            max: position + Self::SIZE,
+        }
+    }
+}
+
+impl LabEquipment for Bed {}
+
+/// Where [contracts](crate::contract) are offered and taken
+#[derive(Debug)]
+pub struct ContractBoard {
+    /// The bottom left corner of its legs, on the floor
+    pub position: LabVector3,
+}
+
+impl ContractBoard {
+    /// Width, height, and thickness of the board, in meters
+    const SIZE: Vector3 = Vector3::new(2.0, 1.2, 0.1);
+    /// How high the bottom of the board is off the floor
+    const LIFT: f32 = 0.9;
+
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D, player: &Player, origin: &PlayerVector3) {
+        let corner = self.position.to_player_relative(&player.position, origin);
+        let center = corner + Vector3::new(0.0, Self::LIFT, 0.0) + Self::SIZE * 0.5;
+        d.draw_cube_v(center, Self::SIZE, Color::DARKGREEN);
+        d.draw_cube_wires_v(center, Self::SIZE, Color::BROWN);
+        for x in [0.1, Self::SIZE.x - 0.1] {
+            let foot = corner + Vector3::new(x, 0.0, 0.5 * Self::SIZE.z);
+            d.draw_line3D(
+                foot,
+                foot + Vector3::new(0.0, Self::LIFT, 0.0),
+                Color::BROWN,
+            );
+        }
+    }
+}
+
+impl Bounds<Vector3> for ContractBoard {
+    type BoundingBox = BoundingBox;
+
+    /// In lab coordinates, just the board and not its legs
+    fn bounds(&self) -> Self::BoundingBox {
+        let min = self.position.as_vec3() + Vector3::new(0.0, Self::LIFT, 0.0);
+        BoundingBox {
+            min,
+            max: min + Self::SIZE,
+        }
+    }
+}
+
+impl LabEquipment for ContractBoard {}
+
+/// A bench with an [`Instrument`] on it, for practicing measurements for research points
+#[derive(Debug)]
+pub struct MeasuringBench {
+    /// The corner of the bench, on the floor
+    pub position: LabVector3,
+    pub instrument: Instrument,
+}
+
+impl MeasuringBench {
+    /// Width, height, and depth of the bench, in meters
+    const SIZE: Vector3 = Vector3::new(1.2, 0.9, 0.6);
+
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D, player: &Player, origin: &PlayerVector3) {
+        let corner = self.position.to_player_relative(&player.position, origin);
+        let center = corner + Self::SIZE * 0.5;
+        d.draw_cube_v(center, Self::SIZE, Color::BEIGE);
+        d.draw_cube_wires_v(center, Self::SIZE, Color::BROWN);
+        let top = corner + Vector3::new(0.5 * Self::SIZE.x, Self::SIZE.y, 0.5 * Self::SIZE.z);
+        match self.instrument {
+            Instrument::AnalyticalBalance => {
+                let size = Vector3::new(0.3, 0.35, 0.35);
+                d.draw_cube_v(
+                    top + Vector3::new(0.0, 0.5 * size.y, 0.0),
+                    size,
+                    Color::WHITE,
+                );
+                d.draw_cube_wires_v(
+                    top + Vector3::new(0.0, 0.5 * size.y, 0.0),
+                    size,
+                    Color::GRAY,
+                );
+            }
+            Instrument::Ruler => {
+                let size = Vector3::new(0.3, 0.005, 0.04);
+                d.draw_cube_v(
+                    top + Vector3::new(0.0, 0.5 * size.y, 0.0),
+                    size,
+                    Color::YELLOW,
+                );
+            }
+            Instrument::GraduatedCylinder => {
+                d.draw_cylinder(top, 0.03, 0.03, 0.25, 12, Color::SKYBLUE.fade(0.6));
+                d.draw_cylinder(top, 0.028, 0.028, 0.15, 12, Color::BLUE.fade(0.6));
+            }
+        }
+    }
+}
+
+impl Bounds<Vector3> for MeasuringBench {
+    type BoundingBox = BoundingBox;
+
+    /// In lab coordinates, up to the top of whatever's on it
+    fn bounds(&self) -> Self::BoundingBox {
+        let position = self.position.as_vec3();
+        BoundingBox {
+            min: position,
+            max: position + Self::SIZE + Vector3::new(0.0, 0.35, 0.0),
+        }
+    }
+}
+
+impl LabEquipment for MeasuringBench {}
+
+/// Scans samples to find out what they are, for the [compendium](crate::compendium)
+#[derive(Debug)]
+pub struct Spectrometer {
+    /// The corner of its case, on the floor
+    pub position: LabVector3,
+    /// Waiting to be scanned
+    pub samples: ItemSlots,
+    /// Seconds since the last sample was scanned
+    pub scan_timer: f32,
+}
+
+impl Spectrometer {
+    /// Width, height, and depth of the case, in meters
+    const SIZE: Vector3 = Vector3::new(1.0, 1.4, 0.8);
+
+    /// Slots for samples waiting to be scanned
+    pub const SLOTS: usize = 4;
+
+    /// Seconds it takes to scan one sample
+    pub const SCAN_TIME: f32 = 5.0;
+
+    /// An empty spectrometer at `position`
+    pub const fn new(position: LabVector3) -> Self {
+        Self {
+            position,
+            samples: ItemSlots::new(Self::SLOTS, u32::MAX),
+            scan_timer: 0.0,
+        }
+    }
+
+    /// Scan `dt` seconds' worth of samples, using up one of each and returning what they were
+    pub fn scan(&mut self, dt: f32) -> Vec<Item> {
+        if self.samples.stacks().is_empty() {
+            self.scan_timer = 0.0;
+            return Vec::new();
+        }
+        self.scan_timer += dt;
+        let mut scanned = Vec::new();
+        while self.scan_timer >= Self::SCAN_TIME {
+            self.scan_timer -= Self::SCAN_TIME;
+            let Some(sample) = self.samples.extract(1) else {
+                break;
+            };
+            scanned.push(sample.item);
+        }
+        scanned
+    }
+
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D, player: &Player, origin: &PlayerVector3) {
+        let corner = self.position.to_player_relative(&player.position, origin);
+        let center = corner + Self::SIZE * 0.5;
+        d.draw_cube_v(center, Self::SIZE, Color::LIGHTGRAY);
+        d.draw_cube_wires_v(center, Self::SIZE, Color::DARKGRAY);
+        // The window the sample goes in, lit while it's scanning
+        let window = Vector3::new(0.4, 0.3, 0.02);
+        let color = if self.samples.stacks().is_empty() {
+            Color::DARKBLUE
+        } else {
+            Color::VIOLET
+        };
+        d.draw_cube_v(
+            corner + Vector3::new(0.5 * Self::SIZE.x, 0.7 * Self::SIZE.y, Self::SIZE.z),
+            window,
+            color,
+        );
+    }
+}
+
+impl Bounds<Vector3> for Spectrometer {
+    type BoundingBox = BoundingBox;
+
+    /// In lab coordinates
+    fn bounds(&self) -> Self::BoundingBox {
+        let position = self.position.as_vec3();
+        BoundingBox {
+            min: position,
+            max: position + Self::SIZE,
+        }
+    }
+}
+
+impl LabEquipment for Spectrometer {}
+
 #[derive(Debug)]
 pub struct Laboratory {
     pub origin: PlayerVector3,
     pub bounds: LabBounds,
     pub periodic_tables: Vec<PeriodicTable>,
+    /// Where the player respawns
+    pub bed: Bed,
+    pub contract_board: ContractBoard,
+    pub benches: Vec<MeasuringBench>,
+    pub spectrometer: Spectrometer,
+    /// Where what's measured at the benches comes from
+    pub challenges: Rng,
+    /// Waiting to be analyzed for research points
+    pub samples: ItemSlots,
+    /// Seconds since the last sample was analyzed
+    pub analysis_timer: f32,
+}
+
+impl Laboratory {
+    /// Slots for samples waiting to be analyzed
+    pub const SAMPLE_SLOTS: usize = 8;
+
+    /// Seconds it takes to analyze one sample
+    pub const ANALYSIS_TIME: f32 = 1.0;
+
+    /// Analyze `dt` seconds' worth of samples, using them up and returning the research points
+    /// they were worth
+    pub fn analyze(&mut self, dt: f32) -> u32 {
+        if self.samples.stacks().is_empty() {
+            self.analysis_timer = 0.0;
+            return 0;
+        }
+        self.analysis_timer += dt;
+        let mut points = 0u32;
+        while self.analysis_timer >= Self::ANALYSIS_TIME {
+            self.analysis_timer -= Self::ANALYSIS_TIME;
+            let Some(sample) = self.samples.extract(1) else {
+                break;
+            };
+            points = points.saturating_add(sample_points(&sample));
+        }
+        points
+    }
+
+    /// The nearest of `equipment` the player is looking at, if any are within [`Player::REACH`]
+    fn looked_at<'a, T: LabEquipment>(
+        &self,
+        player: &Player,
+        equipment: impl IntoIterator<Item = &'a T>,
+    ) -> Option<&'a T> {
+        let ray = Ray {
+            position: player.eye_pos().to_lab(&self.origin).as_vec3(),
+            direction: player.vision_ray().direction,
+        };
+        (equipment.into_iter())
+            .map(|equipment| (equipment, get_ray_collision_box(ray, equipment.bounds())))
+            .filter(|(_, collision)| collision.hit && collision.distance <= Player::REACH)
+            .min_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance))
+            .map(|(equipment, _)| equipment)
+    }
+
+    /// The nearest periodic table the player is looking at, if one is within [`Player::REACH`]
+    pub fn looked_at_periodic_table(&self, player: &Player) -> Option<&PeriodicTable> {
+        self.looked_at(player, &self.periodic_tables)
+    }
+
+    /// The contract board, if the player is looking at it from within [`Player::REACH`]
+    pub fn looked_at_contract_board(&self, player: &Player) -> Option<&ContractBoard> {
+        self.looked_at(player, [&self.contract_board])
+    }
+
+    /// The nearest measuring bench the player is looking at, if one is within [`Player::REACH`]
+    pub fn looked_at_bench(&self, player: &Player) -> Option<&MeasuringBench> {
+        self.looked_at(player, &self.benches)
+    }
+
+    /// The spectrometer, if the player is looking at it from within [`Player::REACH`]
+    pub fn looked_at_spectrometer(&self, player: &Player) -> Option<&Spectrometer> {
+        self.looked_at(player, [&self.spectrometer])
+    }
 }
 
 impl PlayerOverlap for Laboratory {
@@ -96,6 +451,10 @@ impl PlayerOverlap for Laboratory {
 }
 
 impl Region for Laboratory {
+    fn assets(&self) -> &'static [AssetId] {
+        &[AssetId::PeriodicTable]
+    }
+
     fn draw(
         &self,
         d: &mut dyn DynRaylibDraw3D,
@@ -106,6 +465,12 @@ impl Region for Laboratory {
         for periodic_table in &self.periodic_tables {
             periodic_table.draw(d, thread, resources, player, &self.origin);
         }
+        self.bed.draw(d, player, &self.origin);
+        self.contract_board.draw(d, player, &self.origin);
+        for bench in &self.benches {
+            bench.draw(d, player, &self.origin);
+        }
+        self.spectrometer.draw(d, player, &self.origin);
 
         let bbox = self.bounds;
         let bbox = BoundingBox {
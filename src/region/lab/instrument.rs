@@ -0,0 +1,220 @@
+//! Measuring instruments in the lab, and grading the readings the player takes with them
+//!
+//! Each [`Instrument`] shows something to measure, and the player writes down what they read. A
+//! reading is only right if it's as precise as the instrument allows and no more: every digit on
+//! a digital readout, or every digit the scale marks plus one estimated between the finest marks.
+//! Right readings are worth research points.
+
+use crate::{
+    chem::measurement::{Measurement, Unit},
+    locale::tr,
+    rng::Rng,
+};
+
+/// Something in the lab that measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Instrument {
+    /// Weighs samples to a tenth of a milligram on a digital readout
+    AnalyticalBalance,
+    /// Marked every millimeter
+    Ruler,
+    /// Marked every milliliter, read from the bottom of the meniscus
+    GraduatedCylinder,
+}
+
+impl Instrument {
+    pub const ALL: [Self; 3] = [
+        Self::AnalyticalBalance,
+        Self::Ruler,
+        Self::GraduatedCylinder,
+    ];
+
+    /// The name shown to the player, in their language
+    pub fn name(self) -> String {
+        tr!(match self {
+            Self::AnalyticalBalance => "lab.analytical_balance",
+            Self::Ruler => "lab.ruler",
+            Self::GraduatedCylinder => "lab.graduated_cylinder",
+        })
+    }
+
+    /// What it measures, in the player's language
+    pub fn quantity(self) -> String {
+        tr!(match self {
+            Self::AnalyticalBalance => "measure.mass",
+            Self::Ruler => "measure.length",
+            Self::GraduatedCylinder => "measure.volume",
+        })
+    }
+
+    /// The symbol of the unit readings are written in
+    pub const fn unit(self) -> &'static str {
+        match self {
+            Self::AnalyticalBalance => "g",
+            Self::Ruler => "cm",
+            Self::GraduatedCylinder => "mL",
+        }
+    }
+
+    /// Whether it shows a number instead of a scale to read
+    pub const fn is_digital(self) -> bool {
+        matches!(self, Self::AnalyticalBalance)
+    }
+
+    /// Decimal places a reading should have
+    ///
+    /// Scales are read one place past their finest marks, by estimating between them.
+    pub const fn decimals(self) -> usize {
+        match self {
+            Self::AnalyticalBalance => 4,
+            Self::Ruler => 2,
+            Self::GraduatedCylinder => 1,
+        }
+    }
+
+    /// How far off a reading can be and still be right
+    ///
+    /// Digital readouts are copied exactly, and estimates can be off by a couple in the last
+    /// place.
+    pub fn tolerance(self) -> f64 {
+        let last_place = 10f64.powi(-i32::try_from(self.decimals()).unwrap_or(i32::MAX));
+        let off_by = if self.is_digital() { 0.5 } else { 2.0 };
+        // Plus a little for what f64 can't represent exactly
+        last_place * off_by + 1e-9
+    }
+
+    /// The least and most that's measured, in steps of the last place
+    const fn steps(self) -> (u32, u32) {
+        match self {
+            // 0.5 g to 50 g
+            Self::AnalyticalBalance => (5_000, 500_000),
+            // 1 cm to 15 cm
+            Self::Ruler => (100, 1_500),
+            // 10 mL to 95 mL
+            Self::GraduatedCylinder => (100, 950),
+        }
+    }
+
+    /// Research points for a right reading
+    pub const fn points(self) -> u32 {
+        match self {
+            Self::AnalyticalBalance => 2,
+            Self::Ruler | Self::GraduatedCylinder => 5,
+        }
+    }
+}
+
+/// How a reading was marked
+#[derive(Debug, Clone, PartialEq)]
+pub enum Grade {
+    /// Right, to as many significant figures as it was written with
+    Correct { figures: u32 },
+    /// Written to `recorded` decimal places instead of `expected`
+    WrongPrecision { recorded: usize, expected: usize },
+    /// Precise enough, but what was really there was `actual`
+    WrongValue { actual: Measurement },
+    /// Not a number, or in the wrong unit
+    Unreadable,
+}
+
+impl Grade {
+    /// Research points for a reading with `instrument` marked this way
+    pub const fn points(&self, instrument: Instrument) -> u32 {
+        match self {
+            Self::Correct { .. } => instrument.points(),
+            _ => 0,
+        }
+    }
+}
+
+/// Something to measure with an [`Instrument`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Challenge {
+    pub instrument: Instrument,
+    /// What's really there
+    pub actual: f64,
+}
+
+impl Challenge {
+    /// Something random to measure with `instrument`
+    pub fn new(instrument: Instrument, rng: &mut Rng) -> Self {
+        let (min, max) = instrument.steps();
+        let steps = min + rng.below(max - min);
+        let places = i32::try_from(instrument.decimals()).unwrap_or(i32::MAX);
+        Self {
+            instrument,
+            actual: f64::from(steps) * 10f64.powi(-places),
+        }
+    }
+
+    /// What's really there, as precisely as it should be read
+    pub fn answer(&self) -> Measurement {
+        let places = i32::try_from(self.instrument.decimals()).unwrap_or(i32::MAX);
+        Measurement::new(self.actual, -places, Unit::base(self.instrument.unit()))
+    }
+
+    /// What a digital instrument shows
+    pub fn readout(&self) -> Option<String> {
+        (self.instrument.is_digital()).then(|| self.answer().to_string())
+    }
+
+    /// Mark `recorded`, a number optionally followed by the instrument's unit
+    pub fn grade(&self, recorded: &str) -> Grade {
+        let Ok(reading) = recorded.parse::<Measurement>() else {
+            return Grade::Unreadable;
+        };
+        let answer = self.answer();
+        if !reading.unit.is_dimensionless() && reading.unit != answer.unit {
+            return Grade::Unreadable;
+        }
+        if reading.last_place != answer.last_place {
+            return Grade::WrongPrecision {
+                recorded: reading.decimals().unwrap_or(0) as usize,
+                expected: self.instrument.decimals(),
+            };
+        }
+        if (reading.value - self.actual).abs() > self.instrument.tolerance() {
+            return Grade::WrongValue { actual: answer };
+        }
+        Grade::Correct {
+            figures: reading.figures().unwrap_or(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grade() {
+        let ruler = Challenge {
+            instrument: Instrument::Ruler,
+            actual: 7.43,
+        };
+        assert_eq!(ruler.grade("7.44"), Grade::Correct { figures: 3 });
+        assert_eq!(ruler.grade("7.42 cm"), Grade::Correct { figures: 3 });
+        assert_eq!(
+            ruler.grade("7.4"),
+            Grade::WrongPrecision {
+                recorded: 1,
+                expected: 2
+            },
+            "the estimated digit is missing"
+        );
+        assert_eq!(
+            ruler.grade("7.50"),
+            Grade::WrongValue {
+                actual: "7.43 cm".parse().unwrap()
+            }
+        );
+        assert_eq!(ruler.grade("7.43 mL"), Grade::Unreadable);
+        assert_eq!(ruler.grade("seven"), Grade::Unreadable);
+
+        let balance = Challenge::new(Instrument::AnalyticalBalance, &mut Rng::new(0));
+        let readout = balance.readout().unwrap();
+        assert!(matches!(balance.grade(&readout), Grade::Correct { .. }));
+        let (min, max) = Instrument::AnalyticalBalance.steps();
+        assert!((f64::from(min) * 1e-4..f64::from(max) * 1e-4).contains(&balance.actual));
+    }
+}
@@ -0,0 +1,375 @@
+use super::instrument::{Challenge, Grade, Instrument};
+use crate::{game::GameState, input::Inputs, locale::tr, ui::Panel};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer},
+};
+use raylib::prelude::*;
+
+const MARGIN: f32 = 16.0;
+const FONT_SIZE: f32 = 20.0;
+const ROW_HEIGHT: f32 = FONT_SIZE + 8.0;
+const WIDTH: f32 = 720.0;
+const SCENE_HEIGHT: f32 = 240.0;
+/// Rows above the scene, for the title and the hint
+const ROWS_ABOVE: usize = 2;
+/// Rows below the scene, for what's typed, the grade, and what to do next
+const ROWS_BELOW: usize = 3;
+
+const BACKGROUND: Color = Color::new(16, 16, 24, 230);
+const SCENE: Color = Color::new(40, 40, 52, 255);
+const DISPLAY: Color = Color::new(120, 240, 140, 255);
+const RULER: Color = Color::new(232, 200, 96, 255);
+const LIQUID: Color = Color::new(64, 128, 224, 160);
+const RIGHT: Color = Color::new(96, 192, 96, 255);
+const WRONG: Color = Color::new(224, 96, 64, 255);
+
+/// Centimeters along the ruler
+const RULER_LENGTH: u32 = 16;
+/// Milliliters of the cylinder shown at once, around the meniscus
+const CYLINDER_WINDOW: f32 = 12.0;
+/// Line segments the meniscus is drawn with
+const SEGMENTS: u8 = 16;
+
+/// Measuring something with an [`Instrument`] at a bench in the lab, opened by interacting with
+/// the bench
+///
+/// The reading is typed and entered to be graded, and entered again for another.
+#[derive(Debug, Clone)]
+pub struct MeasuringPanel {
+    challenge: Challenge,
+    /// What's been typed
+    entry: String,
+    /// How the last entry was graded, until the next challenge
+    grade: Option<Grade>,
+    /// Screen-space area of the whole panel
+    rect: Rectangle,
+}
+
+impl MeasuringPanel {
+    /// Something new to measure with `instrument`, fit to the screen
+    pub fn new(rl: &RaylibHandle, state: &mut GameState, instrument: Instrument) -> Self {
+        let mut panel = Self {
+            challenge: Challenge::new(instrument, &mut state.lab.challenges),
+            entry: String::new(),
+            grade: None,
+            rect: Rectangle::default(),
+        };
+        panel.layout(rl);
+        panel
+    }
+
+    /// Center the panel on the screen
+    #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+    fn layout(&mut self, rl: &RaylibHandle) {
+        let rows = (ROWS_ABOVE + ROWS_BELOW) as f32;
+        let height = rows * ROW_HEIGHT + SCENE_HEIGHT + 4.0 * MARGIN;
+        self.rect = Rectangle::new(
+            0.5 * (rl.get_screen_width() as f32 - WIDTH),
+            0.5 * (rl.get_screen_height() as f32 - height),
+            WIDTH,
+            height,
+        );
+    }
+
+    /// Screen-space area of the `row`th row, counting from the title and skipping the scene
+    #[allow(clippy::cast_precision_loss, reason = "there are only a few rows")]
+    fn row_rect(&self, row: usize) -> Rectangle {
+        let below = if row < ROWS_ABOVE {
+            MARGIN
+        } else {
+            SCENE_HEIGHT + 3.0 * MARGIN
+        };
+        Rectangle::new(
+            self.rect.x + MARGIN,
+            self.rect.y + below + row as f32 * ROW_HEIGHT,
+            WIDTH - 2.0 * MARGIN,
+            ROW_HEIGHT,
+        )
+    }
+
+    /// Screen-space area the instrument is drawn in
+    #[allow(clippy::cast_precision_loss, reason = "there are only a few rows")]
+    fn scene_rect(&self) -> Rectangle {
+        Rectangle::new(
+            self.rect.x + MARGIN,
+            self.rect.y + 2.0 * MARGIN + ROWS_ABOVE as f32 * ROW_HEIGHT,
+            WIDTH - 2.0 * MARGIN,
+            SCENE_HEIGHT,
+        )
+    }
+
+    fn draw_text(
+        &self,
+        d: &mut Renderer<'_>,
+        row: usize,
+        text: &str,
+        color: Color,
+    ) -> draw::Result {
+        let rect = self.row_rect(row);
+        d.draw_text(
+            text,
+            Vector2::new(rect.x + 8.0, rect.y + 0.5 * (rect.height - FONT_SIZE)),
+            FONT_SIZE,
+            0.0,
+            color,
+        )
+    }
+
+    /// What to say about the last entry's grade
+    fn feedback(&self) -> Option<(String, Color)> {
+        let instrument = self.challenge.instrument;
+        let unit = instrument.unit();
+        Some(match self.grade.as_ref()? {
+            Grade::Correct { figures } => (
+                tr!(
+                    "measure.correct",
+                    figures = figures,
+                    points = instrument.points()
+                ),
+                RIGHT,
+            ),
+            &Grade::WrongPrecision { recorded, expected } => {
+                let key = if recorded < expected {
+                    "measure.not_precise"
+                } else {
+                    "measure.too_precise"
+                };
+                let text = tr!(
+                    key,
+                    instrument = instrument.name(),
+                    recorded = recorded,
+                    expected = expected,
+                );
+                (text, WRONG)
+            }
+            Grade::WrongValue { actual } => (tr!("measure.wrong_value", actual = actual), WRONG),
+            Grade::Unreadable => (tr!("measure.unreadable", unit = unit), WRONG),
+        })
+    }
+
+    /// A digital readout, as the balance shows it
+    fn draw_balance(&self, d: &mut Renderer<'_>, scene: Rectangle) -> draw::Result {
+        let readout = self.challenge.readout().unwrap_or_default();
+        let size = 3.0 * FONT_SIZE;
+        let display = Rectangle::new(
+            scene.x + 0.5 * scene.width - 200.0,
+            scene.y + 0.5 * scene.height - size,
+            400.0,
+            2.0 * size,
+        );
+        d.draw_rectangle(display, Color::BLACK)?;
+        d.draw_rectangle_lines(display, Some(2.0), Color::GRAY)?;
+        d.draw_text(
+            &readout,
+            Vector2::new(display.x + MARGIN, display.y + 0.5 * size),
+            size,
+            0.0,
+            DISPLAY,
+        )
+    }
+
+    /// A ruler with millimeter marks, under a bar as long as what's measured
+    #[allow(clippy::cast_possible_truncation, reason = "only for drawing")]
+    fn draw_ruler(&self, d: &mut Renderer<'_>, scene: Rectangle) -> draw::Result {
+        #[allow(clippy::cast_precision_loss, reason = "the ruler is short")]
+        let per_cm = (scene.width - 2.0 * MARGIN) / RULER_LENGTH as f32;
+        let left = scene.x + MARGIN;
+        let top = scene.y + 0.5 * scene.height;
+        d.draw_rectangle(
+            Rectangle::new(
+                left,
+                top - 40.0,
+                self.challenge.actual as f32 * per_cm,
+                32.0,
+            ),
+            Color::LIGHTGRAY,
+        )?;
+        d.draw_rectangle(
+            Rectangle::new(left - 8.0, top, scene.width - 2.0 * MARGIN + 16.0, 64.0),
+            RULER,
+        )?;
+        for mm in 0..=RULER_LENGTH * 10 {
+            #[allow(clippy::cast_precision_loss, reason = "there are only a few marks")]
+            let x = left + mm as f32 * 0.1 * per_cm;
+            let length = match (mm % 10, mm % 5) {
+                (0, _) => 28.0,
+                (_, 0) => 20.0,
+                _ => 12.0,
+            };
+            d.draw_line(
+                Vector2::new(x, top),
+                Vector2::new(x, top + length),
+                Some(1.0),
+                Color::BLACK,
+            )?;
+            if mm % 10 == 0 {
+                d.draw_text(
+                    &(mm / 10).to_string(),
+                    Vector2::new(x - 4.0, top + 34.0),
+                    FONT_SIZE * 0.8,
+                    0.0,
+                    Color::BLACK,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A close up of the cylinder around the meniscus, with milliliter marks
+    #[allow(clippy::cast_possible_truncation, reason = "only for drawing")]
+    fn draw_cylinder(&self, d: &mut Renderer<'_>, scene: Rectangle) -> draw::Result {
+        let actual = self.challenge.actual as f32;
+        let low = actual - 0.5 * CYLINDER_WINDOW;
+        let per_ml = (scene.height - 2.0 * MARGIN) / CYLINDER_WINDOW;
+        let bottom = scene.y + scene.height - MARGIN;
+        let y = |ml: f32| bottom - (ml - low) * per_ml;
+        let tube = Rectangle::new(
+            scene.x + 0.5 * scene.width - 80.0,
+            scene.y + MARGIN,
+            160.0,
+            scene.height - 2.0 * MARGIN,
+        );
+
+        // The liquid, which curves up where it meets the glass
+        let level = y(actual);
+        d.draw_rectangle(
+            Rectangle::new(tube.x, level, tube.width, bottom - level),
+            LIQUID,
+        )?;
+        let rise = 0.4 * per_ml;
+        let surface = |t: f32| level - rise * (2.0 * t - 1.0).powi(4);
+        for i in 0..SEGMENTS {
+            let [t0, t1] = [i, i + 1].map(|i| f32::from(i) / f32::from(SEGMENTS));
+            let [x0, x1] = [t0, t1].map(|t| tube.x + t * tube.width);
+            let [top0, top1] = [surface(t0), surface(t1)];
+            d.draw_triangle(
+                &[
+                    Vector2::new(x0, top0),
+                    Vector2::new(x0, level),
+                    Vector2::new(x1, level),
+                ],
+                LIQUID,
+            )?;
+            d.draw_triangle(
+                &[
+                    Vector2::new(x0, top0),
+                    Vector2::new(x1, level),
+                    Vector2::new(x1, top1),
+                ],
+                LIQUID,
+            )?;
+            d.draw_line(
+                Vector2::new(x0, top0),
+                Vector2::new(x1, top1),
+                Some(2.0),
+                Color::SKYBLUE,
+            )?;
+        }
+
+        let first = low.ceil() as i32;
+        let last = (low + CYLINDER_WINDOW).floor() as i32;
+        for ml in first..=last {
+            #[allow(clippy::cast_precision_loss, reason = "cylinders hold little")]
+            let mark = y(ml as f32);
+            let length = if ml % 5 == 0 { 0.5 } else { 0.25 } * tube.width;
+            d.draw_line(
+                Vector2::new(tube.x, mark),
+                Vector2::new(tube.x + length, mark),
+                Some(1.0),
+                Color::WHITE,
+            )?;
+            if ml % 5 == 0 {
+                d.draw_text(
+                    &ml.to_string(),
+                    Vector2::new(tube.x + tube.width + 8.0, mark - 0.5 * FONT_SIZE),
+                    FONT_SIZE,
+                    0.0,
+                    Color::WHITE,
+                )?;
+            }
+        }
+        d.draw_rectangle_lines(tube, Some(2.0), Color::LIGHTGRAY)
+    }
+}
+
+impl Panel for MeasuringPanel {
+    /// Handle typing the reading, and entering it or moving on to the next
+    ///
+    /// Closes on escape.
+    fn update(&mut self, rl: &mut RaylibHandle, _inputs: &Inputs, state: &mut GameState) -> bool {
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            return false;
+        }
+        self.layout(rl);
+        let entered = rl.is_key_pressed(KeyboardKey::KEY_ENTER);
+        if self.grade.is_some() {
+            if entered {
+                let instrument = self.challenge.instrument;
+                self.challenge = Challenge::new(instrument, &mut state.lab.challenges);
+                self.entry.clear();
+                self.grade = None;
+            }
+            return true;
+        }
+
+        while let Some(ch) = rl.get_char_pressed() {
+            if !ch.is_control() {
+                self.entry.push(ch);
+            }
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE)
+            || rl.is_key_pressed_repeat(KeyboardKey::KEY_BACKSPACE)
+        {
+            self.entry.pop();
+        }
+        if entered && !self.entry.trim().is_empty() {
+            let grade = self.challenge.grade(&self.entry);
+            let points = grade.points(self.challenge.instrument);
+            state.research.points = state.research.points.saturating_add(points);
+            self.grade = Some(grade);
+        }
+        true
+    }
+}
+
+impl Draw for MeasuringPanel {
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        let instrument = self.challenge.instrument;
+        d.draw_rectangle(self.rect, BACKGROUND)?;
+        let title = tr!(
+            "measure.title",
+            instrument = instrument.name(),
+            quantity = instrument.quantity(),
+            unit = instrument.unit(),
+        );
+        self.draw_text(d, 0, &title, Color::WHITE)?;
+        let hint = tr!(match instrument {
+            Instrument::AnalyticalBalance => "measure.balance_hint",
+            Instrument::Ruler => "measure.ruler_hint",
+            Instrument::GraduatedCylinder => "measure.cylinder_hint",
+        });
+        self.draw_text(d, 1, &hint, Color::LIGHTGRAY)?;
+
+        let scene = self.scene_rect();
+        d.draw_rectangle(scene, SCENE)?;
+        match instrument {
+            Instrument::AnalyticalBalance => self.draw_balance(d, scene)?,
+            Instrument::Ruler => self.draw_ruler(d, scene)?,
+            Instrument::GraduatedCylinder => self.draw_cylinder(d, scene)?,
+        }
+
+        let entry_row = ROWS_ABOVE;
+        d.draw_rectangle_lines(self.row_rect(entry_row), Some(2.0), Color::LIGHTGRAY)?;
+        if self.entry.is_empty() {
+            self.draw_text(d, entry_row, &tr!("measure.entry"), Color::GRAY)?;
+        } else {
+            self.draw_text(d, entry_row, &self.entry, Color::WHITE)?;
+        }
+        if let Some((text, color)) = self.feedback() {
+            self.draw_text(d, entry_row + 1, &text, color)?;
+            self.draw_text(d, entry_row + 2, &tr!("measure.next"), Color::LIGHTGRAY)?;
+        }
+        Ok(())
+    }
+}
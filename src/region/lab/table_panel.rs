@@ -0,0 +1,396 @@
+use super::PeriodicTable;
+use crate::{
+    chem::{
+        element::Element,
+        orbital::ElectronConfig,
+        reaction::{BalancedReaction, ParseReactionError, Reaction},
+    },
+    game::GameState,
+    input::Inputs,
+    locale::tr,
+    resource::PERIODIC_OFFSETS,
+    ui::Panel,
+};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer, RenderingOptions},
+};
+use raylib::prelude::*;
+use std::collections::BTreeSet;
+
+const MARGIN: f32 = 16.0;
+const MAX_CELL_SIZE: f32 = 48.0;
+const SEARCH_HEIGHT: f32 = 32.0;
+const DETAILS_HEIGHT: f32 = 132.0;
+const FONT_SIZE: f32 = 20.0;
+
+const BACKGROUND: Color = Color::new(16, 16, 24, 230);
+const METAL: Color = Color::new(96, 160, 224, 255);
+const NONMETAL: Color = Color::new(128, 208, 112, 255);
+const NOBLE_GAS: Color = Color::new(192, 128, 224, 255);
+const UNDISCOVERED: Color = Color::new(64, 64, 72, 255);
+
+/// Interactive 2D view of the periodic table, opened by interacting with a [`PeriodicTable`]
+///
+/// Elements can be hovered for details, and typing filters the table by name, symbol, or atomic
+/// number. Elements that haven't been [discovered](crate::compendium) are blank.
+///
+/// Typing a reaction with an arrow, like `H2 + O2 -> H2O`, balances it instead, for the player to
+/// experiment with: the table highlights the elements in it, and the details show the balanced
+/// reaction or why it can't be balanced.
+#[derive(Debug, Clone, Default)]
+pub struct PeriodicTablePanel {
+    /// Filters which elements are highlighted
+    pub search: String,
+    /// The element under the mouse cursor
+    pub hovered: Option<Element>,
+    /// Elements in something the player has discovered
+    discovered: BTreeSet<Element>,
+    /// Screen-space area of the whole panel
+    rect: Rectangle,
+    /// Side length of each element's cell in pixels
+    cell_size: f32,
+}
+
+impl PeriodicTablePanel {
+    /// An empty search, fit to the screen
+    pub fn new(rl: &RaylibHandle, state: &GameState) -> Self {
+        let mut panel = Self {
+            discovered: state.compendium.elements(),
+            ..Self::default()
+        };
+        panel.layout(rl);
+        panel
+    }
+
+    /// Fit the panel to the screen
+    #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+    fn layout(&mut self, rl: &RaylibHandle) {
+        let (cols, rows) = PeriodicTable::grid_size();
+        let screen_width = rl.get_screen_width() as f32;
+        let screen_height = rl.get_screen_height() as f32;
+        self.cell_size = ((screen_width - 4.0 * MARGIN) / f32::from(cols))
+            .min((screen_height - SEARCH_HEIGHT - DETAILS_HEIGHT - 6.0 * MARGIN) / f32::from(rows))
+            .clamp(1.0, MAX_CELL_SIZE);
+        let width = f32::from(cols) * self.cell_size + 2.0 * MARGIN;
+        let height =
+            f32::from(rows) * self.cell_size + SEARCH_HEIGHT + DETAILS_HEIGHT + 4.0 * MARGIN;
+        self.rect = Rectangle::new(
+            0.5 * (screen_width - width),
+            0.5 * (screen_height - height),
+            width,
+            height,
+        );
+    }
+
+    const fn search_rect(&self) -> Rectangle {
+        Rectangle::new(
+            self.rect.x + MARGIN,
+            self.rect.y + MARGIN,
+            self.rect.width - 2.0 * MARGIN,
+            SEARCH_HEIGHT,
+        )
+    }
+
+    const fn details_rect(&self) -> Rectangle {
+        Rectangle::new(
+            self.rect.x + MARGIN,
+            self.rect.y + self.rect.height - MARGIN - DETAILS_HEIGHT,
+            self.rect.width - 2.0 * MARGIN,
+            DETAILS_HEIGHT,
+        )
+    }
+
+    /// Screen-space area of `element`'s cell
+    fn cell_rect(&self, element: Element) -> Rectangle {
+        let (col, row) = PERIODIC_OFFSETS[usize::from(element.protons().get() - 1)];
+        Rectangle::new(
+            self.rect.x + MARGIN + f32::from(col) * self.cell_size,
+            self.rect.y + 2.0 * MARGIN + SEARCH_HEIGHT + f32::from(row) * self.cell_size,
+            self.cell_size,
+            self.cell_size,
+        )
+    }
+
+    /// [`Self::search`] read as a reaction, or [`None`] if it has no arrow
+    fn reaction(&self) -> Option<Result<Reaction, ParseReactionError>> {
+        match self.search.parse::<Reaction>() {
+            Err(ParseReactionError::MissingArrow) => None,
+            reaction => Some(reaction),
+        }
+    }
+
+    /// The reaction typed into [`Self::search`] balanced, or why it can't be, or [`None`] if
+    /// what's typed isn't a reaction
+    pub fn balanced(&self) -> Option<Result<BalancedReaction, String>> {
+        self.reaction().map(|reaction| {
+            reaction
+                .map_err(|e| e.to_string())?
+                .balance()
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    /// Whether `element` matches [`Self::search`]
+    ///
+    /// An empty search matches everything. A reaction matches the elements in it, a number
+    /// matches the atomic number exactly, and anything else matches the start of the symbol or
+    /// name, ignoring case.
+    pub fn matches(&self, element: Element) -> bool {
+        let search = self.search.trim();
+        if search.is_empty() {
+            return true;
+        }
+        if let Some(reaction) = self.reaction() {
+            return reaction.is_ok_and(|reaction| reaction.elements().contains(&element));
+        }
+        if let Ok(protons) = search.parse::<u8>() {
+            return element.protons().get() == protons;
+        }
+        let starts_with = |s: &str| {
+            s.get(..search.len())
+                .is_some_and(|s| s.eq_ignore_ascii_case(search))
+        };
+        starts_with(element.symbol())
+            || starts_with(element.name())
+            || starts_with(&element.local_name())
+    }
+
+    const fn category_color(element: Element) -> Color {
+        if element.is_noble_gas() {
+            NOBLE_GAS
+        } else if element.is_metal() {
+            METAL
+        } else {
+            NONMETAL
+        }
+    }
+
+    /// Show the typed reaction balanced below the table, or why it can't be
+    fn draw_balanced(&self, d: &mut Renderer<'_>) -> draw::Result {
+        let (text, color) = match self.balanced() {
+            None => return Ok(()),
+            Some(Ok(reaction)) => (
+                tr!("table_panel.balanced", reaction = reaction),
+                Color::WHITE,
+            ),
+            Some(Err(reason)) => (
+                tr!("table_panel.unbalanced", reason = reason),
+                Color::ORANGE,
+            ),
+        };
+        let details = self.details_rect();
+        d.push_layer(RenderingOptions::new().clip(details));
+        d.draw_text(
+            &text,
+            Vector2::new(details.x + 8.0, details.y + 8.0),
+            FONT_SIZE,
+            0.0,
+            color,
+        )?;
+        d.pop_layer()?;
+        Ok(())
+    }
+
+    /// Describe `element` below the table
+    fn draw_details(&self, d: &mut Renderer<'_>, element: Element) -> draw::Result {
+        let details = self.details_rect();
+        if !self.discovered.contains(&element) {
+            d.draw_text(
+                &tr!("table_panel.undiscovered"),
+                Vector2::new(details.x + 8.0, details.y + 8.0),
+                FONT_SIZE,
+                0.0,
+                Color::GRAY,
+            )?;
+            return Ok(());
+        }
+        let category = tr!(if element.is_noble_gas() {
+            "table_panel.noble_gas"
+        } else if element.is_metal() {
+            "table_panel.metal"
+        } else {
+            "table_panel.nonmetal"
+        });
+        // Long electron configurations would otherwise spill out of the panel
+        d.push_layer(RenderingOptions::new().clip(details));
+        d.draw_text(
+            &tr!(
+                "table_panel.details",
+                name = element.local_name(),
+                symbol = element.symbol(),
+                number = element.protons(),
+                weight = format!("{:.3}", element.standard_atomic_weight()),
+                category = category,
+                config = ElectronConfig::new(element.protons().get()),
+            ),
+            Vector2::new(details.x + 8.0, details.y + 8.0),
+            FONT_SIZE,
+            0.0,
+            Color::WHITE,
+        )?;
+        d.pop_layer()?;
+        Ok(())
+    }
+}
+
+impl Panel for PeriodicTablePanel {
+    /// Handle typing and hovering
+    ///
+    /// Interacting doesn't close the panel, since it's also typed into the search.
+    fn update(&mut self, rl: &mut RaylibHandle, _inputs: &Inputs, state: &mut GameState) -> bool {
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            return false;
+        }
+        self.layout(rl);
+        // The spectrometer keeps scanning while the panel's open
+        self.discovered = state.compendium.elements();
+
+        while let Some(ch) = rl.get_char_pressed() {
+            if !ch.is_control() {
+                self.search.push(ch);
+            }
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE)
+            || rl.is_key_pressed_repeat(KeyboardKey::KEY_BACKSPACE)
+        {
+            self.search.pop();
+        }
+
+        let mouse = rl.get_mouse_position();
+        self.hovered = Element::list()
+            .iter()
+            .copied()
+            .find(|&element| self.cell_rect(element).check_collision_point_rec(mouse));
+        true
+    }
+}
+
+impl Draw for PeriodicTablePanel {
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        d.draw_rectangle(self.rect, BACKGROUND)?;
+        d.draw_rectangle_lines(self.search_rect(), Some(2.0), Color::LIGHTGRAY)?;
+        d.draw_rectangle_lines(self.details_rect(), Some(2.0), Color::DARKGRAY)?;
+
+        for &element in Element::list() {
+            let cell = self.cell_rect(element);
+            let inner = Rectangle::new(
+                cell.x + 1.0,
+                cell.y + 1.0,
+                cell.width - 2.0,
+                cell.height - 2.0,
+            );
+            let color = if self.discovered.contains(&element) {
+                Self::category_color(element)
+            } else {
+                UNDISCOVERED
+            };
+            let color = if self.matches(element) {
+                color
+            } else {
+                color.fade(0.2)
+            };
+            d.draw_rectangle(inner, color)?;
+            if self.hovered == Some(element) {
+                d.draw_rectangle_lines(cell, Some(2.0), Color::WHITE)?;
+            }
+        }
+
+        let search = self.search_rect();
+        let (search_text, search_color) = if self.search.is_empty() {
+            (tr!("table_panel.search"), Color::GRAY)
+        } else {
+            (self.search.clone(), Color::WHITE)
+        };
+        d.draw_text(
+            &search_text,
+            Vector2::new(search.x + 8.0, search.y + 0.5 * (search.height - FONT_SIZE)),
+            FONT_SIZE,
+            0.0,
+            search_color,
+        )?;
+
+        let symbol_size = (0.4 * self.cell_size).min(FONT_SIZE);
+        let number_size = 0.5 * symbol_size;
+        for &element in Element::list() {
+            let cell = self.cell_rect(element);
+            let color = if self.matches(element) {
+                Color::BLACK
+            } else {
+                Color::BLACK.fade(0.3)
+            };
+            d.draw_text(
+                &element.protons().to_string(),
+                Vector2::new(cell.x + 3.0, cell.y + 2.0),
+                number_size,
+                0.0,
+                color,
+            )?;
+            let symbol = if self.discovered.contains(&element) {
+                element.symbol()
+            } else {
+                "?"
+            };
+            d.draw_text(
+                symbol,
+                Vector2::new(cell.x + 3.0, cell.y + cell.height - symbol_size - 2.0),
+                symbol_size,
+                0.0,
+                color,
+            )?;
+        }
+
+        if let Some(element) = self.hovered {
+            self.draw_details(d, element)?;
+        } else {
+            self.draw_balanced(d)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let mut panel = PeriodicTablePanel::default();
+        assert!(panel.matches(Element::Og));
+        panel.search = "fe".to_string();
+        assert!(panel.matches(Element::Fe));
+        assert!(!panel.matches(Element::F));
+        panel.search = "Oxy".to_string();
+        assert!(panel.matches(Element::O));
+        panel.search = "26".to_string();
+        assert!(panel.matches(Element::Fe));
+        assert!(!panel.matches(Element::Mn));
+        assert!(panel.balanced().is_none());
+    }
+
+    #[test]
+    fn test_balancing() {
+        let mut panel = PeriodicTablePanel {
+            search: "H2 + O2 -> H2O".to_string(),
+            ..PeriodicTablePanel::default()
+        };
+        assert_eq!(
+            panel.balanced().unwrap().unwrap().to_string(),
+            "2H₂ + O₂ → 2H₂O"
+        );
+        assert!(panel.matches(Element::H) && panel.matches(Element::O));
+        assert!(!panel.matches(Element::Fe));
+
+        panel.search = "H2 -> O2".to_string();
+        assert_eq!(
+            panel.balanced(),
+            Some(Err("reaction cannot be balanced".to_string()))
+        );
+        panel.search = "H2 -> Xx".to_string();
+        assert!(panel.balanced().unwrap().is_err());
+        assert!(
+            !panel.matches(Element::H),
+            "nothing matches a reaction that can't be read"
+        );
+    }
+}
@@ -1,17 +1,19 @@
 use crate::{
     math::{
-        bounds::SpacialBounds,
+        bounds::{PlayerBounds, SpacialBounds},
         coords::{PlayerCoord, PlayerVector3},
     },
     player::Player,
     region::rail::World,
-    resource::Resources,
+    resource::{AssetId, Resources},
     rl_helpers::DynRaylibDraw3D,
 };
 use factory::Factory;
 use lab::Laboratory;
 use raylib::prelude::*;
 
+pub mod events;
+pub mod exterior;
 pub mod factory;
 pub mod lab;
 pub mod rail;
@@ -22,9 +24,29 @@ pub trait PlayerOverlap {
 
     #[must_use]
     fn local_floor(&self, player: &Player) -> Option<PlayerCoord>;
+
+    /// Whether the player is on something they can climb, like a ladder
+    #[inline]
+    #[must_use]
+    fn can_climb(&self, _player: &Player) -> bool {
+        false
+    }
+
+    /// Solid things in `area` the player can't move through
+    #[inline]
+    #[must_use]
+    fn obstacles(&self, _area: &PlayerBounds) -> Vec<PlayerBounds> {
+        Vec::new()
+    }
 }
 
 pub trait Region: PlayerOverlap {
+    /// Assets the region needs to draw, which are loaded while the player is in it
+    #[must_use]
+    fn assets(&self) -> &'static [AssetId] {
+        &[]
+    }
+
     fn draw(
         &self,
         d: &mut dyn DynRaylibDraw3D,
@@ -32,6 +54,23 @@ pub trait Region: PlayerOverlap {
         resources: &Resources,
         player: &Player,
     );
+
+    /// How far a ray from the player's eye in `direction` goes before hitting something, if it
+    /// hits anything within `max_distance`
+    ///
+    /// Only checks the world floor by default.
+    #[must_use]
+    fn raycast_from_eye(
+        &self,
+        player: &Player,
+        direction: Vector3,
+        max_distance: f32,
+    ) -> Option<f32> {
+        let height = player.eye_pos().to_vec3().y;
+        (direction.y < 0.0)
+            .then(|| height / -direction.y)
+            .filter(|&distance| distance <= max_distance)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -1,17 +1,38 @@
 use crate::{
-    math::coords::PlayerCoord, player::Player, resource::Resources, rl_helpers::DynRaylibDraw3D,
+    math::coords::PlayerCoord,
+    player::Player,
+    resource::{AssetId, Resources},
+    rl_helpers::DynRaylibDraw3D,
 };
+use deposit::Deposits;
+use network::RailNetwork;
 use raylib::prelude::*;
+use terrain::Terrain;
 
 use super::{PlayerOverlap, Region};
 
-fn draw_skybox(_d: &mut dyn DynRaylibDraw3D, _thread: &RaylibThread, resources: &Resources) {
+pub mod deposit;
+pub mod handcar;
+pub mod network;
+pub mod signal;
+pub mod terrain;
+
+fn draw_skybox(
+    _d: &mut dyn DynRaylibDraw3D,
+    _thread: &RaylibThread,
+    resources: &Resources,
+    tint: Color,
+) {
     #[allow(
         clippy::cast_possible_wrap,
         reason = "RL_QUADS is an i32 in Raylib, but bindgen made it a u32"
     )]
     const RL_QUADS: i32 = ffi::RL_QUADS as i32;
 
+    let Some(skybox) = resources.texture(AssetId::Skybox) else {
+        return;
+    };
+
     #[allow(
         clippy::multiple_unsafe_ops_per_block,
         reason = "safety comment is complicated and shared by all operations in this block"
@@ -22,10 +43,10 @@ fn draw_skybox(_d: &mut dyn DynRaylibDraw3D, _thread: &RaylibThread, resources:
     // RaylibThread (which does not implement Send/Sync) is borrowed, guaranteeing
     // this is the thread that initialized the window and graphics.
     unsafe {
-        ffi::rlSetTexture(resources.skybox.id);
+        ffi::rlSetTexture(skybox.id);
         ffi::rlBegin(RL_QUADS);
         {
-            ffi::rlColor4ub(255, 255, 255, 255);
+            ffi::rlColor4ub(tint.r, tint.g, tint.b, tint.a);
 
             ffi::rlTexCoord2f(0.0, 1.0);
             ffi::rlVertex3f(-1000.0, 50.0, -1000.0);
@@ -45,19 +66,31 @@ fn draw_skybox(_d: &mut dyn DynRaylibDraw3D, _thread: &RaylibThread, resources:
 }
 
 #[derive(Debug)]
-pub struct World {}
+pub struct World {
+    /// Multiplies the skybox, for the time of day
+    pub sky_tint: Color,
+    pub terrain: Terrain,
+    /// Ore deposits that have been found, see [`Terrain::deposit_site`]
+    pub deposits: Deposits,
+    pub rail: RailNetwork,
+}
 
 impl PlayerOverlap for World {
     fn is_overlapping(&self, _player: &Player) -> bool {
         true
     }
 
-    fn local_floor(&self, _player: &Player) -> Option<PlayerCoord> {
-        None // TODO
+    fn local_floor(&self, player: &Player) -> Option<PlayerCoord> {
+        let Vector3 { x, z, .. } = player.position.to_vec3();
+        Some(PlayerCoord::from_f32(self.terrain.height_at(x, z)))
     }
 }
 
 impl Region for World {
+    fn assets(&self) -> &'static [AssetId] {
+        &[AssetId::Skybox, AssetId::Terrain]
+    }
+
     fn draw(
         &self,
         d: &mut dyn DynRaylibDraw3D,
@@ -65,11 +98,27 @@ impl Region for World {
         resources: &Resources,
         player: &Player,
     ) {
-        d.draw_plane(
-            (-player.position).to_vec3(),
-            Vector2::new(1000.0, 1000.0),
-            Color::DARKGREEN,
-        );
-        draw_skybox(d, thread, resources);
+        if let Some(material) = resources.material(AssetId::Terrain) {
+            resources.terrain.draw(d, material, player.position);
+        }
+        self.rail.draw(d, player.position);
+        draw_skybox(d, thread, resources, self.sky_tint);
+    }
+
+    /// Steps along the ray until it's under the ground
+    fn raycast_from_eye(
+        &self,
+        player: &Player,
+        direction: Vector3,
+        max_distance: f32,
+    ) -> Option<f32> {
+        const STEP: f32 = 0.25;
+        let eye = player.eye_pos().to_vec3();
+        std::iter::successors(Some(0.0), |distance| Some(distance + STEP))
+            .take_while(|&distance| distance <= max_distance)
+            .find(|&distance| {
+                let point = eye + direction * distance;
+                point.y <= self.terrain.height_at(point.x, point.z)
+            })
     }
 }
@@ -0,0 +1,134 @@
+//! Ore in the ground, which miners dig up
+//!
+//! Where deposits are is decided by the [`Terrain`](super::terrain::Terrain), as a
+//! [`DepositSite`] in some chunks. A site only becomes a [`Deposit`] with ore being taken out of
+//! it once something finds it, so the rest of the world doesn't have to be kept track of.
+
+use super::terrain::DepositSite;
+use crate::{
+    chem::element::Element,
+    container::{Item, ItemStack},
+    math::coords::RailVector3,
+};
+use std::collections::HashMap;
+
+/// The mineral an element is mined as, which has to be crushed and refined to get the element
+pub const fn ore_formula(element: Element) -> &'static str {
+    match element {
+        Element::Fe => "Fe2O3",
+        Element::Cu => "Cu2S",
+        Element::Al => "Al2O3",
+        Element::Si => "SiO2",
+        Element::Zn => "ZnS",
+        Element::Sn => "SnO2",
+        Element::Ni => "NiS",
+        Element::Pb => "PbS",
+        // Found on their own, like coal and sulfur
+        _ => element.symbol(),
+    }
+}
+
+/// Ore of one element in the ground
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deposit {
+    /// The cell on the surface over the deposit
+    pub position: RailVector3,
+    pub element: Element,
+    /// How much ore there is compared to an average deposit, which also makes mining it faster
+    pub richness: f32,
+    /// Units of ore left to mine
+    pub remaining: u32,
+}
+
+impl Deposit {
+    /// Units of ore in an average deposit
+    pub const AMOUNT: u32 = 10_000;
+
+    /// The deposit at `site`, with none of it mined yet
+    pub fn new(site: DepositSite) -> Self {
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss,
+            reason = "richness is a small positive factor"
+        )]
+        let remaining = (Self::AMOUNT as f32 * site.richness) as u32;
+        Self {
+            position: site.position,
+            element: site.element,
+            richness: site.richness,
+            remaining,
+        }
+    }
+
+    /// What's mined from the deposit
+    pub fn ore(&self) -> Item {
+        ore_formula(self.element)
+            .parse()
+            .expect("ore formulas should be valid")
+    }
+
+    /// Take up to `count` units of ore out of the deposit, if there's any left
+    pub fn extract(&mut self, count: u32) -> Option<ItemStack> {
+        let count = count.min(self.remaining);
+        self.remaining -= count;
+        (count > 0).then(|| ItemStack::new(self.ore(), count))
+    }
+}
+
+/// Every deposit that's been found, by position
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Deposits {
+    found: HashMap<RailVector3, Deposit>,
+}
+
+impl Deposits {
+    pub fn get(&self, position: RailVector3) -> Option<&Deposit> {
+        self.found.get(&position)
+    }
+
+    pub fn get_mut(&mut self, position: RailVector3) -> Option<&mut Deposit> {
+        self.found.get_mut(&position)
+    }
+
+    /// The deposit at `site`, keeping track of it from now on if it hasn't been found before
+    pub fn find(&mut self, site: DepositSite) -> &mut Deposit {
+        (self.found)
+            .entry(site.position)
+            .or_insert_with(|| Deposit::new(site))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deposit() {
+        let site = DepositSite {
+            position: RailVector3::new(3, 1, 4),
+            element: Element::Fe,
+            richness: 0.5,
+        };
+        let mut deposits = Deposits::default();
+        assert_eq!(deposits.get(site.position), None);
+        let deposit = deposits.find(site);
+        assert_eq!(deposit.remaining, Deposit::AMOUNT / 2);
+        assert_eq!(
+            deposit.extract(3),
+            Some(ItemStack::new("Fe2O3".parse().unwrap(), 3))
+        );
+        assert_eq!(
+            deposits.find(site).remaining,
+            Deposit::AMOUNT / 2 - 3,
+            "found deposits are remembered"
+        );
+
+        let deposit = deposits.get_mut(site.position).unwrap();
+        assert_eq!(
+            deposit.extract(u32::MAX).unwrap().count,
+            Deposit::AMOUNT / 2 - 3
+        );
+        assert_eq!(deposit.extract(1), None);
+    }
+}
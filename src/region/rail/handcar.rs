@@ -0,0 +1,139 @@
+//! Handcars the player can ride along the rails
+//!
+//! Unlike [trains](super::network::Train), a handcar only moves when someone drives it. Its
+//! place on the track is kept in fixed point, the same as the player's position, so it doesn't
+//! drift as it's moved a little each tick.
+
+use super::network::Track;
+use crate::math::coords::{PlayerCoord, PlayerVector3};
+
+/// A small car pushed along a track by whoever is riding it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Handcar {
+    /// The index of the track it's on in [`RailNetwork::tracks`](super::network::RailNetwork::tracks)
+    pub track: usize,
+    /// Meters along the track
+    pub distance: PlayerCoord,
+    /// Meters per second, positive towards the end of the track
+    pub speed: f32,
+    /// How hard it's being driven, from -1 for full reverse to 1 for full ahead
+    throttle: f32,
+    is_braking: bool,
+}
+
+impl Handcar {
+    /// Meters per second, either way
+    pub const MAX_SPEED: f32 = 12.0;
+    /// Meters per second per second at full throttle
+    const ACCELERATION: f32 = 3.0;
+    /// Meters per second per second lost while braking
+    const BRAKING: f32 = 8.0;
+    /// Meters per second per second lost to the rails while coasting
+    const ROLLING_RESISTANCE: f32 = 0.4;
+    /// Where the rider stands, above the middle of the rails
+    pub const DECK_HEIGHT: PlayerCoord = PlayerCoord::from_f32(0.5);
+
+    /// A handcar standing still `distance` meters along `track`
+    pub const fn new(track: usize, distance: PlayerCoord) -> Self {
+        Self {
+            track,
+            distance,
+            speed: 0.0,
+            throttle: 0.0,
+            is_braking: false,
+        }
+    }
+
+    /// Drive with `throttle` from -1 to 1, and brake if `is_braking`, until told otherwise
+    pub const fn drive(&mut self, throttle: f32, is_braking: bool) {
+        self.throttle = throttle.clamp(-1.0, 1.0);
+        self.is_braking = is_braking;
+    }
+
+    /// Let go of the controls, so it coasts to a stop
+    pub const fn release(&mut self) {
+        self.drive(0.0, false);
+    }
+
+    /// Where the deck is on `track`
+    pub fn position(&self, track: &Track) -> PlayerVector3 {
+        let mut position = track.point_at(self.distance);
+        position.y = position.y.plus(Self::DECK_HEIGHT);
+        position
+    }
+
+    /// Accelerate and move `dt` seconds along `track`, stopping at its ends
+    pub(super) fn tick(&mut self, dt: f32, track: &Track) {
+        let slowing = if self.is_braking {
+            Self::BRAKING
+        } else if self.throttle.abs() < f32::EPSILON {
+            Self::ROLLING_RESISTANCE
+        } else {
+            0.0
+        };
+        let speed = self.speed + self.throttle * Self::ACCELERATION * dt;
+        // Slow down towards standing still, without going past it
+        let speed = speed.signum() * (speed.abs() - slowing * dt).max(0.0);
+        self.speed = speed.clamp(-Self::MAX_SPEED, Self::MAX_SPEED);
+
+        let length = track.length_fp();
+        let to = (self.distance.plus(PlayerCoord::from_f32(self.speed * dt)))
+            .clamp(PlayerCoord::ZERO, length);
+        if to == PlayerCoord::ZERO || to == length {
+            self.speed = 0.0;
+        }
+        self.distance = to;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::coords::RailVector3;
+
+    #[test]
+    fn test_handcar() {
+        let track = Track {
+            points: vec![RailVector3::new(0, 0, 0), RailVector3::new(200, 0, 0)],
+        };
+        let mut handcar = Handcar::new(0, PlayerCoord::from_i32(10));
+        handcar.tick(1.0, &track);
+        assert_eq!(
+            handcar.distance,
+            PlayerCoord::from_i32(10),
+            "nobody's driving"
+        );
+
+        handcar.drive(1.0, false);
+        for _ in 0..50 {
+            handcar.tick(0.1, &track);
+        }
+        assert!((handcar.speed - Handcar::MAX_SPEED).abs() < 1e-6);
+        handcar.drive(1.0, true);
+        handcar.tick(1.0, &track);
+        assert!(
+            handcar.speed < Handcar::MAX_SPEED,
+            "braking beats the throttle"
+        );
+        handcar.release();
+        for _ in 0..300 {
+            handcar.tick(0.1, &track);
+        }
+        assert!(handcar.speed.abs() < f32::EPSILON, "coasts to a stop");
+
+        handcar.drive(1.0, false);
+        for _ in 0..300 {
+            handcar.tick(0.1, &track);
+        }
+        assert_eq!(
+            handcar.distance,
+            PlayerCoord::from_i32(200),
+            "stops at the end"
+        );
+        assert!(handcar.speed.abs() < f32::EPSILON);
+        handcar.drive(-1.0, false);
+        handcar.tick(1.0, &track);
+        assert_eq!(handcar.distance, PlayerCoord::from_i32(197));
+        assert_eq!(handcar.position(&track).y, Handcar::DECK_HEIGHT);
+    }
+}
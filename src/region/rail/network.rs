@@ -0,0 +1,722 @@
+//! Rails, and the trains that run on them
+//!
+//! Rails are laid as [`Track`]s, each a line of straight runs. A train runs back and forth along
+//! its track, waiting at each [`Stop`] it comes to so that stations there can load and unload it.
+//! [Signals](super::signal) split the tracks into blocks, so trains sharing a track wait for each
+//! other instead of running into each other.
+//! [`Handcar`]s share the tracks, but only move when the player drives them.
+
+use super::{
+    handcar::Handcar,
+    signal::{Blocks, Signal, SignalId},
+};
+use crate::{
+    container::ItemSlots,
+    math::coords::{PlayerCoord, PlayerVector3, RailVector3, VectorConstants},
+    rl_helpers::DynRaylibDraw3D,
+};
+use raylib::prelude::*;
+
+/// Meters between `a` and `b` on the same run
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "tracks are much shorter than 2^24 meters"
+)]
+const fn run_length(a: RailVector3, b: RailVector3) -> f32 {
+    (a.x.abs_diff(b.x) + a.y.abs_diff(b.y) + a.z.abs_diff(b.z)) as f32
+}
+
+/// Meters between `a` and `b` on the same run, in fixed point
+#[allow(
+    clippy::cast_possible_wrap,
+    reason = "tracks are much shorter than 2^31 meters"
+)]
+const fn run_length_fp(a: RailVector3, b: RailVector3) -> PlayerCoord {
+    PlayerCoord::from_i32((a.x.abs_diff(b.x) + a.y.abs_diff(b.y) + a.z.abs_diff(b.z)) as i32)
+}
+
+/// The middle of the rails through `cell`
+const fn center(cell: RailVector3) -> PlayerVector3 {
+    cell.to_player()
+        .plus(PlayerVector3::from_f32(0.5, 0.0, 0.5))
+}
+
+/// A line of rails through the middle of each cell it runs along
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Track {
+    /// Where the rails start, turn, and end, with each run between them along x or z
+    pub points: Vec<RailVector3>,
+}
+
+impl Track {
+    /// The straight runs between [`Self::points`], from the start
+    fn runs(&self) -> impl Iterator<Item = (RailVector3, RailVector3)> + '_ {
+        self.points.windows(2).map(|run| (run[0], run[1]))
+    }
+
+    /// Meters from one end to the other
+    pub fn length(&self) -> f32 {
+        self.runs().map(|(a, b)| run_length(a, b)).sum()
+    }
+
+    /// Meters from one end to the other, in fixed point
+    pub fn length_fp(&self) -> PlayerCoord {
+        (self.runs()).fold(PlayerCoord::ZERO, |length, (a, b)| {
+            length.plus(run_length_fp(a, b))
+        })
+    }
+
+    /// How far along the track `cell` is, if the rails run through it
+    pub fn distance_to(&self, cell: RailVector3) -> Option<f32> {
+        let mut start = 0.0;
+        for (a, b) in self.runs() {
+            let between = |a: i32, b: i32, c: i32| (a.min(b)..=a.max(b)).contains(&c);
+            if between(a.x, b.x, cell.x) && between(a.y, b.y, cell.y) && between(a.z, b.z, cell.z) {
+                return Some(start + run_length(a, cell));
+            }
+            start += run_length(a, b);
+        }
+        None
+    }
+
+    /// The middle of the rails `distance` meters along the track, stopping at the ends
+    pub fn position_at(&self, distance: f32) -> PlayerVector3 {
+        self.point_at(PlayerCoord::from_f32(distance))
+    }
+
+    /// The middle of the rails `distance` meters along the track, stopping at the ends, without
+    /// leaving fixed point
+    pub fn point_at(&self, distance: PlayerCoord) -> PlayerVector3 {
+        let mut remaining = distance.max(PlayerCoord::ZERO);
+        for (a, b) in self.runs() {
+            let length = run_length_fp(a, b);
+            if remaining <= length && length.is_positive() {
+                return center(a).lerp(center(b), remaining.divide(length));
+            }
+            remaining = remaining.minus(length);
+        }
+        center(self.points.last().copied().unwrap_or_default())
+    }
+
+    /// The [yaw](crate::player::Player::yaw) of facing along the track towards its end,
+    /// `distance` meters along it
+    ///
+    /// Right where the track turns, this is the way the next run goes.
+    pub fn heading_at(&self, distance: PlayerCoord) -> f32 {
+        let mut remaining = distance.max(PlayerCoord::ZERO);
+        let mut heading = 0.0;
+        for (a, b) in self.runs() {
+            #[allow(
+                clippy::cast_precision_loss,
+                reason = "only the direction matters, not the exact length"
+            )]
+            let (x, z) = ((b.x - a.x) as f32, (b.z - a.z) as f32);
+            heading = (-x).atan2(-z);
+            let length = run_length_fp(a, b);
+            if remaining < length {
+                break;
+            }
+            remaining = remaining.minus(length);
+        }
+        heading
+    }
+
+    /// Points along the middle of the rails from `from` meters along the track to `to`, with one
+    /// wherever the track turns in between
+    pub fn path_between(&self, from: f32, to: f32) -> Vec<PlayerVector3> {
+        let (low, high) = (from.min(to), from.max(to));
+        let mut turns = (self.runs())
+            .scan(0.0, |start, (a, b)| {
+                *start += run_length(a, b);
+                Some(*start)
+            })
+            .filter(|&distance| low < distance && distance < high)
+            .collect::<Vec<_>>();
+        if from > to {
+            turns.reverse();
+        }
+        (std::iter::once(from)
+            .chain(turns)
+            .chain(std::iter::once(to)))
+        .map(|distance| self.position_at(distance))
+        .collect()
+    }
+
+    /// Draw the rails, relative to `player_pos`
+    fn draw(&self, d: &mut dyn DynRaylibDraw3D, player_pos: PlayerVector3) {
+        const RAIL_HEIGHT: f32 = 0.1;
+        for (a, b) in self.runs() {
+            let min = RailVector3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+            #[allow(
+                clippy::cast_precision_loss,
+                reason = "tracks are much shorter than 2^24 meters"
+            )]
+            let size = Vector3::new(
+                a.x.abs_diff(b.x) as f32 + 1.0,
+                RAIL_HEIGHT,
+                a.z.abs_diff(b.z) as f32 + 1.0,
+            );
+            let center = min.to_player_relative(player_pos) + size * 0.5;
+            d.draw_cube(center, size.x, size.y, size.z, Color::DARKBROWN);
+        }
+    }
+}
+
+/// Identifies a [`Stop`] on a [`RailNetwork`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StopId(usize);
+
+/// A place on a track where trains wait
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stop {
+    /// The cell the rails run through there
+    pub cell: RailVector3,
+    /// The index of the track in [`RailNetwork::tracks`]
+    pub track: usize,
+    /// Meters along the track
+    pub distance: f32,
+}
+
+/// What a [`Train`] is doing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrainState {
+    Running,
+    /// Waiting at `stop` to be loaded and unloaded
+    Stopped {
+        stop: StopId,
+        departs_in: f32,
+    },
+    /// Waiting in front of `signal` for the block past it to clear, for `waited` seconds so far
+    Held {
+        signal: SignalId,
+        waited: f32,
+    },
+}
+
+/// Cars that carry cargo along a track
+#[derive(Debug, Clone, PartialEq)]
+pub struct Train {
+    /// The index of the track it runs on in [`RailNetwork::tracks`]
+    pub track: usize,
+    /// Meters along the track
+    pub distance: f32,
+    /// Meters per second while running
+    pub speed: f32,
+    /// Whether it's heading for the end of the track, rather than the start
+    pub forward: bool,
+    pub cargo: ItemSlots,
+    pub state: TrainState,
+}
+
+impl Train {
+    /// Seconds a train waits at each stop
+    pub const DWELL: f32 = 20.0;
+    /// How many different items a train can carry
+    pub const CARGO_SLOTS: usize = 8;
+    /// How many of each item fit in a slot
+    pub const STACK_LIMIT: u32 = 500;
+    /// Meters per second per second trains slow down by when braking for a red signal
+    pub const BRAKING: f32 = 2.0;
+    /// Seconds a train waits at a red signal before turning back, in case the train holding the
+    /// block past it is waiting on this one
+    pub const PATIENCE: f32 = 15.0;
+
+    /// An empty train at the start of `track`
+    pub const fn new(track: usize, speed: f32) -> Self {
+        Self {
+            track,
+            distance: 0.0,
+            speed,
+            forward: true,
+            cargo: ItemSlots::new(Self::CARGO_SLOTS, Self::STACK_LIMIT),
+            state: TrainState::Running,
+        }
+    }
+
+    /// How far through waiting at a stop the train is, from 0 to 1, or [`None`] if it's running
+    pub const fn departure_progress(&self) -> Option<f32> {
+        match self.state {
+            TrainState::Running | TrainState::Held { .. } => None,
+            TrainState::Stopped { departs_in, .. } => Some(1.0 - departs_in / Self::DWELL),
+        }
+    }
+
+    /// How fast to go for the next `dt` seconds, reserving the block past the next signal once
+    /// it's in sight
+    ///
+    /// Runs `slip` slower than [`Train::speed`], from 0 to 1. If the signal is red, the train brakes so that it stops [`Blocks::GAP`] short of it, and
+    /// this also returns the signal and where it stops if it gets there within `dt`.
+    fn approach(
+        &self,
+        idx: usize,
+        dt: f32,
+        slip: f32,
+        blocks: &mut Blocks,
+    ) -> (f32, Option<(SignalId, f32)>) {
+        let top_speed = self.speed * (1.0 - slip);
+        let Some(id) = blocks.next_signal(self.track, self.distance, self.forward) else {
+            return (top_speed, None);
+        };
+        let Some(&signal) = blocks.signal(id) else {
+            return (top_speed, None);
+        };
+        let (stopping_point, remaining) = if self.forward {
+            let at = signal.distance - Blocks::GAP;
+            (at.max(self.distance), at - self.distance)
+        } else {
+            let at = signal.distance + Blocks::GAP;
+            (at.min(self.distance), self.distance - at)
+        };
+        let remaining = remaining.max(0.0);
+        let sighting = top_speed * top_speed / (2.0 * Self::BRAKING) + top_speed * dt;
+        if remaining > sighting || blocks.reserve(blocks.past(&signal, self.forward), idx) {
+            return (top_speed, None);
+        }
+        // As fast as it can still stop from in time
+        let speed = top_speed.min((2.0 * Self::BRAKING * remaining).sqrt());
+        let held = (speed * dt >= remaining).then_some((id, stopping_point));
+        (speed, held)
+    }
+
+    /// Move `dt` seconds along `track`, `slip` slower than usual, stopping at the first of
+    /// `stops` it comes to and in front of red signals
+    ///
+    /// `idx` is the train's index in [`RailNetwork::trains`], which it reserves blocks under.
+    /// Trains turn around at the ends of the track, and after waiting too long at a signal.
+    fn tick(
+        &mut self,
+        idx: usize,
+        dt: f32,
+        slip: f32,
+        track: &Track,
+        stops: &[Stop],
+        blocks: &mut Blocks,
+    ) {
+        match &mut self.state {
+            TrainState::Running => {}
+            TrainState::Stopped { departs_in, .. } => {
+                *departs_in -= dt;
+                if *departs_in <= 0.0 {
+                    self.state = TrainState::Running;
+                }
+                return;
+            }
+            TrainState::Held { signal, waited } => {
+                let past = (blocks.signal(*signal)).map(|signal| blocks.past(signal, self.forward));
+                if past.is_none_or(|block| blocks.reserve(block, idx)) {
+                    self.state = TrainState::Running;
+                } else {
+                    *waited += dt;
+                    if *waited >= Self::PATIENCE {
+                        self.forward = !self.forward;
+                        self.state = TrainState::Running;
+                    }
+                }
+                return;
+            }
+        }
+        let length = track.length();
+        let from = self.distance;
+        let (speed, held) = self.approach(idx, dt, slip, blocks);
+        let to = if let Some((_, at)) = held {
+            at
+        } else if self.forward {
+            (from + speed * dt).min(length)
+        } else {
+            (from - speed * dt).max(0.0)
+        };
+        // Not the one it's leaving
+        let passed = |stop: &Stop| {
+            if self.forward {
+                from < stop.distance && stop.distance <= to
+            } else {
+                to <= stop.distance && stop.distance < from
+            }
+        };
+        let next = (stops.iter().enumerate())
+            .filter(|(_, stop)| stop.track == self.track && passed(stop))
+            .min_by(|(_, a), (_, b)| {
+                (a.distance - from)
+                    .abs()
+                    .total_cmp(&(b.distance - from).abs())
+            });
+        if let Some((stop_idx, stop)) = next {
+            self.distance = stop.distance;
+            self.state = TrainState::Stopped {
+                stop: StopId(stop_idx),
+                departs_in: Self::DWELL,
+            };
+        } else if let Some((signal, _)) = held {
+            self.distance = to;
+            self.state = TrainState::Held {
+                signal,
+                waited: 0.0,
+            };
+        } else {
+            self.distance = to;
+            if (self.forward && to >= length) || (!self.forward && to <= 0.0) {
+                self.forward = !self.forward;
+            }
+        }
+        let current = blocks.block_at(self.track, self.distance);
+        blocks.release_behind(idx, current, self.forward);
+    }
+}
+
+/// Every track, and the stops, signals, trains, and handcars on them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RailNetwork {
+    tracks: Vec<Track>,
+    stops: Vec<Stop>,
+    blocks: Blocks,
+    trains: Vec<Train>,
+    handcars: Vec<Handcar>,
+    /// Meters every train has run, added up
+    traveled: f64,
+    /// How much slower than their speed trains run, from 0 to 1, such as on wet rails
+    slip: f32,
+}
+
+impl RailNetwork {
+    pub const fn new(tracks: Vec<Track>) -> Self {
+        Self {
+            tracks,
+            stops: Vec::new(),
+            blocks: Blocks::new(),
+            trains: Vec::new(),
+            handcars: Vec::new(),
+            traveled: 0.0,
+            slip: 0.0,
+        }
+    }
+
+    #[inline]
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    #[inline]
+    pub fn trains(&self) -> &[Train] {
+        &self.trains
+    }
+
+    /// Meters every train has run, added up
+    #[inline]
+    pub const fn traveled(&self) -> f64 {
+        self.traveled
+    }
+
+    #[inline]
+    pub fn stops(&self) -> &[Stop] {
+        &self.stops
+    }
+
+    /// The id of every stop, in the same order as [`Self::stops`]
+    pub fn stop_ids(&self) -> impl Iterator<Item = StopId> + use<> {
+        (0..self.stops.len()).map(StopId)
+    }
+
+    /// The signals, and which trains hold which blocks between them
+    #[inline]
+    pub const fn blocks(&self) -> &Blocks {
+        &self.blocks
+    }
+
+    #[inline]
+    pub fn handcars(&self) -> &[Handcar] {
+        &self.handcars
+    }
+
+    #[inline]
+    pub fn handcar_mut(&mut self, idx: usize) -> Option<&mut Handcar> {
+        self.handcars.get_mut(idx)
+    }
+
+    /// Where the deck of the handcar at `idx` is, and which way along the track it faces
+    pub fn handcar_position(&self, idx: usize) -> Option<(PlayerVector3, f32)> {
+        let handcar = self.handcars.get(idx)?;
+        let track = self.tracks.get(handcar.track)?;
+        Some((handcar.position(track), track.heading_at(handcar.distance)))
+    }
+
+    /// Put `train` on its track, holding the block it's in unless another train already does
+    pub fn add_train(&mut self, train: Train) {
+        let block = self.blocks.block_at(train.track, train.distance);
+        _ = self.blocks.reserve(block, self.trains.len());
+        self.trains.push(train);
+    }
+
+    /// Put `handcar` on its track
+    pub fn add_handcar(&mut self, handcar: Handcar) {
+        self.handcars.push(handcar);
+    }
+
+    pub fn stop(&self, id: StopId) -> Option<&Stop> {
+        self.stops.get(id.0)
+    }
+
+    /// The stop where the rails run through `cell`, adding one if there isn't one there yet
+    ///
+    /// Returns [`None`] if there are no rails through `cell`.
+    pub fn stop_at(&mut self, cell: RailVector3) -> Option<StopId> {
+        if let Some(idx) = self.stops.iter().position(|stop| stop.cell == cell) {
+            return Some(StopId(idx));
+        }
+        let (track, distance) = (self.tracks.iter().enumerate())
+            .find_map(|(idx, track)| Some((idx, track.distance_to(cell)?)))?;
+        self.stops.push(Stop {
+            cell,
+            track,
+            distance,
+        });
+        Some(StopId(self.stops.len() - 1))
+    }
+
+    /// The signal where the rails run through `cell`, adding one if there isn't one there yet
+    ///
+    /// Returns [`None`] if there are no rails through `cell`.
+    pub fn signal_at(&mut self, cell: RailVector3) -> Option<SignalId> {
+        if let Some(id) = self.blocks.find(cell) {
+            return Some(id);
+        }
+        let (track, distance) = (self.tracks.iter().enumerate())
+            .find_map(|(idx, track)| Some((idx, track.distance_to(cell)?)))?;
+        let trains = (self.trains.iter()).map(|train| (train.track, train.distance));
+        Some(self.blocks.add(
+            Signal {
+                cell,
+                track,
+                distance,
+            },
+            trains,
+        ))
+    }
+
+    /// The train waiting at `stop`, if there is one
+    pub fn train_at_mut(&mut self, stop: StopId) -> Option<&mut Train> {
+        (self.trains.iter_mut())
+            .find(|train| matches!(train.state, TrainState::Stopped { stop: at, .. } if at == stop))
+    }
+
+    /// Make trains run `slip` slower than their speed from now on, from 0 to 1
+    pub const fn set_slip(&mut self, slip: f32) {
+        self.slip = slip.clamp(0.0, 1.0);
+    }
+
+    /// Move every train and handcar `dt` seconds along its track
+    pub fn tick(&mut self, dt: f32) {
+        for (idx, train) in self.trains.iter_mut().enumerate() {
+            if let Some(track) = self.tracks.get(train.track) {
+                let from = train.distance;
+                train.tick(idx, dt, self.slip, track, &self.stops, &mut self.blocks);
+                self.traveled += f64::from((train.distance - from).abs());
+            }
+        }
+        for handcar in &mut self.handcars {
+            if let Some(track) = self.tracks.get(handcar.track) {
+                handcar.tick(dt, track);
+            }
+        }
+    }
+
+    /// Whether a train is waiting in front of `signal`
+    pub fn is_holding(&self, signal: SignalId) -> bool {
+        (self.trains.iter())
+            .any(|train| matches!(train.state, TrainState::Held { signal: at, .. } if at == signal))
+    }
+
+    /// Draw the rails, signals, trains, and handcars, relative to `player_pos`
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D, player_pos: PlayerVector3) {
+        const TRAIN_SIZE: f32 = 2.0;
+        const HANDCAR_SIZE: Vector3 = Vector3::new(1.5, 0.4, 1.5);
+        const SIGNAL_HEIGHT: f32 = 3.0;
+        for track in &self.tracks {
+            track.draw(d, player_pos);
+        }
+        for (idx, signal) in self.blocks.signals().iter().enumerate() {
+            let Some(track) = self.tracks.get(signal.track) else {
+                continue;
+            };
+            let base = track
+                .position_at(signal.distance)
+                .minus(player_pos)
+                .to_vec3();
+            let lamp = if self.is_holding(SignalId(idx)) {
+                Color::RED
+            } else {
+                Color::LIME
+            };
+            d.draw_line3D(base, base + Vector3::UP * SIGNAL_HEIGHT, Color::DARKGRAY);
+            d.draw_sphere(base + Vector3::UP * SIGNAL_HEIGHT, 0.25, lamp);
+        }
+        for train in &self.trains {
+            let Some(track) = self.tracks.get(train.track) else {
+                continue;
+            };
+            let position = track
+                .position_at(train.distance)
+                .minus(player_pos)
+                .to_vec3();
+            d.draw_cube(
+                position + Vector3::UP * (0.5 * TRAIN_SIZE),
+                TRAIN_SIZE,
+                TRAIN_SIZE,
+                TRAIN_SIZE,
+                Color::MAROON,
+            );
+        }
+        for idx in 0..self.handcars.len() {
+            let Some((deck, _)) = self.handcar_position(idx) else {
+                continue;
+            };
+            let position = deck.minus(player_pos).to_vec3();
+            d.draw_cube(
+                position - Vector3::UP * (0.5 * HANDCAR_SIZE.y),
+                HANDCAR_SIZE.x,
+                HANDCAR_SIZE.y,
+                HANDCAR_SIZE.z,
+                Color::BROWN,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trains_stop() {
+        let track = Track {
+            points: vec![
+                RailVector3::new(0, 0, 0),
+                RailVector3::new(10, 0, 0),
+                RailVector3::new(10, 0, 5),
+            ],
+        };
+        assert!((track.length() - 15.0).abs() < 1e-6);
+        assert_eq!(track.distance_to(RailVector3::new(10, 0, 3)), Some(13.0));
+        assert_eq!(track.distance_to(RailVector3::new(3, 0, 3)), None);
+
+        let mut network = RailNetwork::new(vec![track]);
+        assert_eq!(network.stop_at(RailVector3::new(3, 0, 3)), None);
+        let stop = network.stop_at(RailVector3::new(4, 0, 0)).unwrap();
+        assert_eq!(network.stop_at(RailVector3::new(4, 0, 0)), Some(stop));
+        network.add_train(Train::new(0, 2.0));
+
+        network.tick(1.0);
+        assert!(network.train_at_mut(stop).is_none());
+        assert!((network.traveled() - 2.0).abs() < 1e-6);
+        network.tick(1.5);
+        let train = network
+            .train_at_mut(stop)
+            .expect("the train passed the stop");
+        assert!(
+            (train.distance - 4.0).abs() < 1e-6,
+            "trains stop right at stops"
+        );
+
+        network.tick(Train::DWELL);
+        network.tick(1.0);
+        assert!(
+            network.train_at_mut(stop).is_none(),
+            "trains leave after a while"
+        );
+        network.tick(10.0);
+        assert!(
+            !network.trains()[0].forward,
+            "trains turn around at the end"
+        );
+    }
+
+    #[test]
+    fn test_signals() {
+        let mut network = RailNetwork::new(vec![Track {
+            points: vec![RailVector3::new(0, 0, 0), RailVector3::new(100, 0, 0)],
+        }]);
+        let mut ahead = Train::new(0, 2.0);
+        ahead.distance = 55.0;
+        network.add_train(ahead);
+        network.add_train(Train::new(0, 10.0));
+        let first = network.signal_at(RailVector3::new(50, 0, 0)).unwrap();
+        let second = network.signal_at(RailVector3::new(80, 0, 0)).unwrap();
+        assert_eq!(network.signal_at(RailVector3::new(50, 0, 0)), Some(first));
+        assert_eq!(network.signal_at(RailVector3::new(0, 0, 5)), None);
+        let blocks = network.blocks();
+        assert_eq!(
+            blocks.block_at(0, 50.0).index,
+            0,
+            "signals end the block before them"
+        );
+        assert_eq!(blocks.holder(blocks.block_at(0, 55.0)), Some(0));
+        assert_eq!(blocks.holder(blocks.block_at(0, 0.0)), Some(1));
+
+        for _ in 0..100 {
+            network.tick(0.1);
+        }
+        let behind = &network.trains()[1];
+        assert!(
+            matches!(behind.state, TrainState::Held { signal, .. } if signal == first),
+            "{behind:?}"
+        );
+        assert!(
+            (behind.distance - (50.0 - Blocks::GAP)).abs() < 1e-6,
+            "trains stop short of red signals"
+        );
+        assert!(network.is_holding(first));
+        assert!(!network.is_holding(second));
+
+        // The train ahead passes the second signal after 12.5 seconds
+        for _ in 0..50 {
+            network.tick(0.1);
+        }
+        let blocks = network.blocks();
+        assert_eq!(network.trains()[1].state, TrainState::Running);
+        assert!(network.trains()[1].distance > 50.0);
+        assert_eq!(blocks.holder(blocks.block_at(0, 60.0)), Some(1));
+        assert_eq!(
+            blocks.holder(blocks.block_at(0, 10.0)),
+            None,
+            "blocks are let go once trains leave them"
+        );
+    }
+
+    #[test]
+    fn test_track_path() {
+        let track = Track {
+            points: vec![
+                RailVector3::new(0, 0, 0),
+                RailVector3::new(10, 0, 0),
+                RailVector3::new(10, 0, 5),
+            ],
+        };
+        let at = |distances: &[f32]| {
+            (distances.iter())
+                .map(|&distance| track.position_at(distance))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(track.path_between(2.0, 13.0), at(&[2.0, 10.0, 13.0]));
+        assert_eq!(
+            track.path_between(13.0, 2.0),
+            at(&[13.0, 10.0, 2.0]),
+            "paths go the way they're asked for"
+        );
+        assert_eq!(track.path_between(11.0, 14.0), at(&[11.0, 14.0]));
+
+        assert_eq!(track.length_fp(), PlayerCoord::from_i32(15));
+        assert_eq!(
+            track.point_at(PlayerCoord::from_i32(5)),
+            PlayerVector3::from_f32(5.5, 0.0, 0.5)
+        );
+        let east = track.heading_at(PlayerCoord::from_i32(5));
+        let south = track.heading_at(PlayerCoord::from_i32(12));
+        assert!(
+            (east + std::f32::consts::FRAC_PI_2).abs() < 1e-6,
+            "facing +x"
+        );
+        assert!(
+            (south.abs() - std::f32::consts::PI).abs() < 1e-6,
+            "facing +z"
+        );
+    }
+}
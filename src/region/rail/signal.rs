@@ -0,0 +1,170 @@
+//! Signals that keep trains from running into each other
+//!
+//! Signals split each [`Track`](super::network::Track) into blocks, and only one train is let
+//! into a block at a time. A train holds the block it's in, and reserves the block past the next
+//! signal once it's close enough to see it. If another train already holds that block, the
+//! signal is red, and the train brakes to wait in front of it until the block clears.
+
+use crate::math::coords::RailVector3;
+use std::collections::BTreeMap;
+
+/// Identifies a [`Signal`] on a [`RailNetwork`](super::network::RailNetwork)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SignalId(pub(super) usize);
+
+/// Where one block ends and the next begins
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Signal {
+    /// The cell the rails run through there
+    pub cell: RailVector3,
+    /// The index of the track in [`RailNetwork::tracks`](super::network::RailNetwork::tracks)
+    pub track: usize,
+    /// Meters along the track
+    pub distance: f32,
+}
+
+/// A stretch of track between two signals, or between a signal and an end of the track
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlockId {
+    pub track: usize,
+    /// How many signals come before it, from the start of the track
+    pub index: usize,
+}
+
+/// Every signal, and which train holds each block
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Blocks {
+    signals: Vec<Signal>,
+    /// The index of the train holding each block that's held, in
+    /// [`RailNetwork::trains`](super::network::RailNetwork::trains)
+    holders: BTreeMap<BlockId, usize>,
+}
+
+impl Blocks {
+    /// Meters short of a red signal that trains stop, so they stay clear of the block past it
+    pub const GAP: f32 = 3.0;
+
+    /// No signals, so each track is one block
+    pub const fn new() -> Self {
+        Self {
+            signals: Vec::new(),
+            holders: BTreeMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn signals(&self) -> &[Signal] {
+        &self.signals
+    }
+
+    pub fn signal(&self, id: SignalId) -> Option<&Signal> {
+        self.signals.get(id.0)
+    }
+
+    /// The signal at `cell`, if there is one
+    pub fn find(&self, cell: RailVector3) -> Option<SignalId> {
+        (self.signals.iter())
+            .position(|signal| signal.cell == cell)
+            .map(SignalId)
+    }
+
+    /// Add `signal`, splitting the block it's in
+    ///
+    /// Blocks past it on the same track are counted from one further along, so every block is
+    /// given back to whichever train is in it, by `trains`' `(track, distance)`. Reservations
+    /// ahead of trains are dropped, and made again once they come up to the signal.
+    pub(super) fn add(
+        &mut self,
+        signal: Signal,
+        trains: impl IntoIterator<Item = (usize, f32)>,
+    ) -> SignalId {
+        self.signals.push(signal);
+        self.holders.clear();
+        for (idx, (track, distance)) in trains.into_iter().enumerate() {
+            let block = self.block_at(track, distance);
+            self.holders.entry(block).or_insert(idx);
+        }
+        SignalId(self.signals.len() - 1)
+    }
+
+    /// The block `distance` meters along `track`
+    ///
+    /// Right at a signal is counted as the block before it.
+    pub fn block_at(&self, track: usize, distance: f32) -> BlockId {
+        let index = (self.signals.iter())
+            .filter(|signal| signal.track == track && signal.distance < distance)
+            .count();
+        BlockId { track, index }
+    }
+
+    /// The block a train passing `signal` goes into, heading for the end of the track if
+    /// `forward` or the start if not
+    pub fn past(&self, signal: &Signal, forward: bool) -> BlockId {
+        let before = self.block_at(signal.track, signal.distance);
+        if forward {
+            BlockId {
+                index: before.index + 1,
+                ..before
+            }
+        } else {
+            before
+        }
+    }
+
+    /// The index of the train holding `block`, if any
+    pub fn holder(&self, block: BlockId) -> Option<usize> {
+        self.holders.get(&block).copied()
+    }
+
+    /// Reserve `block` for `train`, unless another train already holds it
+    ///
+    /// Returns whether `train` holds it now.
+    pub(super) fn reserve(&mut self, block: BlockId, train: usize) -> bool {
+        *self.holders.entry(block).or_insert(train) == train
+    }
+
+    /// Let go of the blocks `train` holds behind `current`, once it's left them
+    pub(super) fn release_behind(&mut self, train: usize, current: BlockId, forward: bool) {
+        self.holders.retain(|block, &mut holder| {
+            let is_behind = if forward {
+                block.index < current.index
+            } else {
+                block.index > current.index
+            };
+            holder != train || block.track != current.track || !is_behind
+        });
+    }
+
+    /// The first signal on `track` strictly ahead of `distance`, heading for the end of the
+    /// track if `forward` or the start if not
+    pub fn next_signal(&self, track: usize, distance: f32, forward: bool) -> Option<SignalId> {
+        let ahead = (self.signals.iter().enumerate()).filter(|(_, signal)| {
+            signal.track == track
+                && if forward {
+                    signal.distance > distance
+                } else {
+                    signal.distance < distance
+                }
+        });
+        let nearest = ahead.min_by(|(_, a), (_, b)| {
+            (a.distance - distance)
+                .abs()
+                .total_cmp(&(b.distance - distance).abs())
+        });
+        nearest.map(|(idx, _)| SignalId(idx))
+    }
+
+    /// Each block on `track`, with where it starts and ends along a track `length` meters long
+    pub fn spans(&self, track: usize, length: f32) -> Vec<(BlockId, f32, f32)> {
+        let mut bounds = (self.signals.iter())
+            .filter(|signal| signal.track == track)
+            .map(|signal| signal.distance)
+            .collect::<Vec<_>>();
+        bounds.sort_by(f32::total_cmp);
+        (std::iter::once(0.0).chain(bounds.iter().copied()))
+            .zip(bounds.iter().copied().chain(std::iter::once(length)))
+            .enumerate()
+            .map(|(index, (start, end))| (BlockId { track, index }, start, end))
+            .collect()
+    }
+}
@@ -0,0 +1,599 @@
+//! The ground of the rail region
+//!
+//! Terrain is generated from a seed with value noise, so the same seed always makes the same
+//! ground and none of it needs to be stored. Heights and [`Biome`]s are sampled at the corners of
+//! [`RailVector3`] cells, and the ground is split into [`Chunk`]s that are meshed and drawn around
+//! the player by [`TerrainMeshes`]. Each chunk may also have a [`DepositSite`] of ore.
+
+use crate::{
+    chem::element::Element,
+    math::coords::{PlayerVector3, RailVector3},
+    rl_helpers::DynRaylibDraw3D,
+};
+use raylib::prelude::*;
+use std::collections::BTreeMap;
+
+/// A number that looks random but is always the same for the same inputs
+const fn hash(seed: u32, x: i32, z: i32) -> u32 {
+    let mut h = seed
+        ^ x.cast_unsigned().wrapping_mul(0x27d4_eb2d)
+        ^ z.cast_unsigned().wrapping_mul(0x1656_67b1);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+/// `h` as a fraction in `[0, 1)`
+#[allow(clippy::cast_precision_loss, reason = "only the top 24 bits are kept")]
+const fn unit(h: u32) -> f32 {
+    (h >> 8) as f32 / (1 << 24) as f32
+}
+
+/// Eases `t` in `[0, 1]` so noise has no creases at lattice points
+const fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Smooth random values over the plane, the same for the same seed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Noise {
+    pub seed: u32,
+}
+
+impl Noise {
+    pub const fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+
+    /// Noise that doesn't line up with this one
+    #[must_use]
+    pub const fn offset(self, by: u32) -> Self {
+        Self::new(self.seed.wrapping_add(by.wrapping_mul(0x9e37_79b9)))
+    }
+
+    /// The noise at integer coordinates, in `[0, 1)`
+    #[inline]
+    pub const fn lattice(self, x: i32, z: i32) -> f32 {
+        unit(hash(self.seed, x, z))
+    }
+
+    /// The noise between integer coordinates, interpolated smoothly, in `[0, 1)`
+    pub fn value(self, x: f32, z: f32) -> f32 {
+        let (x0, z0) = (x.floor(), z.floor());
+        let (tx, tz) = (smoothstep(x - x0), smoothstep(z - z0));
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "terrain isn't sampled anywhere near i32::MAX"
+        )]
+        let (ix, iz) = (x0 as i32, z0 as i32);
+        let top = self.lattice(ix, iz) + (self.lattice(ix + 1, iz) - self.lattice(ix, iz)) * tx;
+        let bottom = self.lattice(ix, iz + 1)
+            + (self.lattice(ix + 1, iz + 1) - self.lattice(ix, iz + 1)) * tx;
+        top + (bottom - top) * tz
+    }
+
+    /// `octaves` layers of noise, each twice as detailed and half as strong as the last, in
+    /// `[0, 1)`
+    pub fn fractal(self, x: f32, z: f32, octaves: u32) -> f32 {
+        let (mut sum, mut total, mut amplitude, mut frequency) = (0.0, 0.0, 1.0, 1.0);
+        for octave in 0..octaves {
+            sum += amplitude * self.offset(octave).value(x * frequency, z * frequency);
+            total += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        if total > 0.0 { sum / total } else { 0.0 }
+    }
+}
+
+/// The kind of land, which decides what it looks like and what ore is found in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Biome {
+    Plains,
+    Forest,
+    Desert,
+    Hills,
+    Mountains,
+}
+
+impl Biome {
+    pub const ALL: [Self; 5] = [
+        Self::Plains,
+        Self::Forest,
+        Self::Desert,
+        Self::Hills,
+        Self::Mountains,
+    ];
+
+    /// The color of the ground
+    pub const fn color(self) -> Color {
+        match self {
+            Self::Plains => Color::new(104, 160, 72, 255),
+            Self::Forest => Color::new(48, 112, 56, 255),
+            Self::Desert => Color::new(208, 184, 120, 255),
+            Self::Hills => Color::new(128, 144, 88, 255),
+            Self::Mountains => Color::new(128, 124, 120, 255),
+        }
+    }
+
+    /// Elements whose ore deposits are found here
+    pub const fn ores(self) -> &'static [Element] {
+        match self {
+            Self::Plains => &[Element::Fe, Element::Cu, Element::C],
+            Self::Forest => &[Element::C, Element::Fe],
+            Self::Desert => &[Element::Si, Element::Al, Element::S],
+            Self::Hills => &[Element::Cu, Element::Zn, Element::Sn],
+            Self::Mountains => &[Element::Fe, Element::Ni, Element::Pb],
+        }
+    }
+}
+
+/// The terrain at a corner of a cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerrainSample {
+    /// Height of the ground in meters
+    pub height: f32,
+    pub biome: Biome,
+}
+
+/// An area kept flat, so buildings aren't buried or left floating
+///
+/// The ground eases back to its natural height over [`Terrain::PAD_BLEND`] meters around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pad {
+    /// Lowest corner, whose height the ground is flattened to
+    pub min: RailVector3,
+    pub max: RailVector3,
+}
+
+impl Pad {
+    /// How far `(x, z)` is outside the pad, ignoring height
+    fn distance(&self, x: f32, z: f32) -> f32 {
+        #[allow(clippy::cast_precision_loss, reason = "pads are near the origin")]
+        let [min_x, min_z, max_x, max_z] =
+            [self.min.x, self.min.z, self.max.x, self.max.z].map(|v| v as f32);
+        let dx = (min_x - x).max(x - max_x).max(0.0);
+        let dz = (min_z - z).max(z - max_z).max(0.0);
+        dx.hypot(dz)
+    }
+}
+
+/// Where on the ground a chunk's ore deposit is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepositSite {
+    /// The cell on the surface over the deposit
+    pub position: RailVector3,
+    pub element: Element,
+    /// How much ore there is compared to an average deposit, in `[0.5, 1.5)`
+    pub richness: f32,
+}
+
+/// Identifies a [`Chunk`] by its position in chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ChunkPos {
+    pub const fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    /// The chunk the cell at `position` is in
+    pub const fn containing(position: RailVector3) -> Self {
+        Self::new(
+            position.x.div_euclid(Chunk::SIZE),
+            position.z.div_euclid(Chunk::SIZE),
+        )
+    }
+
+    /// The corner of the chunk with the lowest coordinates, at height 0
+    pub const fn origin(self) -> RailVector3 {
+        RailVector3::new(self.x * Chunk::SIZE, 0, self.z * Chunk::SIZE)
+    }
+
+    /// Every chunk at most `radius` chunks away on each axis
+    pub fn around(self, radius: i32) -> impl Iterator<Item = Self> {
+        (-radius..=radius).flat_map(move |dz| {
+            (-radius..=radius).map(move |dx| Self::new(self.x + dx, self.z + dz))
+        })
+    }
+}
+
+/// A square of the ground, sampled at the corner of each cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub pos: ChunkPos,
+    /// [`Self::SAMPLES`] squared, row by row, with an extra ring around the edge so normals
+    /// match the neighboring chunks
+    samples: Vec<TerrainSample>,
+}
+
+impl Chunk {
+    /// Cells along each side
+    pub const SIZE: i32 = 32;
+
+    /// Samples along each side, including the ring around the edge
+    const SAMPLES: usize = Self::SIZE as usize + 3;
+
+    /// The sample at the corner `(x, z)` cells from [`ChunkPos::origin`], which can be one
+    /// past either edge
+    pub fn sample(&self, x: i32, z: i32) -> TerrainSample {
+        let [x, z] = [x, z].map(|v| usize::try_from(v + 1).expect("sample should be in the chunk"));
+        self.samples[z * Self::SAMPLES + x]
+    }
+
+    /// A mesh of the ground, relative to [`ChunkPos::origin`]
+    pub fn mesh(&self) -> TerrainMesh {
+        let corners = Self::SIZE + 1;
+        let mut mesh = TerrainMesh::default();
+        for z in 0..corners {
+            for x in 0..corners {
+                let sample = self.sample(x, z);
+                let height = |x, z| self.sample(x, z).height;
+                #[allow(clippy::cast_precision_loss, reason = "chunks are small")]
+                mesh.vertices
+                    .push(Vector3::new(x as f32, sample.height, z as f32));
+                // From the slope across the neighboring samples
+                mesh.normals.push(
+                    Vector3::new(
+                        height(x - 1, z) - height(x + 1, z),
+                        2.0,
+                        height(x, z - 1) - height(x, z + 1),
+                    )
+                    .normalize(),
+                );
+                mesh.colors.push(sample.biome.color());
+            }
+        }
+        for z in 0..Self::SIZE {
+            for x in 0..Self::SIZE {
+                let corner =
+                    |dx, dz| u16::try_from((z + dz) * corners + x + dx).expect("chunks are small");
+                // Counterclockwise from above, so the top is the front face
+                mesh.indices.extend([
+                    corner(0, 0),
+                    corner(0, 1),
+                    corner(1, 1),
+                    corner(0, 0),
+                    corner(1, 1),
+                    corner(1, 0),
+                ]);
+            }
+        }
+        mesh
+    }
+}
+
+/// The triangles of a [`Chunk`], ready to upload
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TerrainMesh {
+    pub vertices: Vec<Vector3>,
+    pub normals: Vec<Vector3>,
+    pub colors: Vec<Color>,
+    /// Three for each triangle
+    pub indices: Vec<u16>,
+}
+
+impl TerrainMesh {
+    /// Copy into a mesh on the GPU
+    pub fn upload(&self, _thread: &RaylibThread) -> Mesh {
+        /// Copy `data` into memory allocated by raylib, which it frees when the mesh is unloaded
+        ///
+        /// # Safety
+        ///
+        /// `T` must be laid out as a run of `U`s, like [`Vector3`] is three `f32`s.
+        unsafe fn alloc<T: Copy, U>(data: &[T]) -> *mut U {
+            let size = u32::try_from(size_of_val(data)).expect("chunk meshes are small");
+            // SAFETY: MemAlloc only needs raylib's allocator, which is always there
+            let ptr = unsafe { ffi::MemAlloc(size) }.cast::<T>();
+            assert!(!ptr.is_null(), "out of memory");
+            // SAFETY: MemAlloc returned at least `size` bytes, which is all of `data`
+            unsafe { ptr.copy_from_nonoverlapping(data.as_ptr(), data.len()) };
+            ptr.cast()
+        }
+
+        #[allow(
+            clippy::multiple_unsafe_ops_per_block,
+            reason = "safety comment is shared by all operations in this block"
+        )]
+        // SAFETY: Every other field of a zeroed mesh is null or 0, which raylib treats as
+        // missing. The arrays are allocated by raylib, are as long as the counts say, and are
+        // owned by the mesh from here on. RaylibThread is borrowed, so this is the thread that
+        // initialized the window and graphics.
+        unsafe {
+            let mut mesh: ffi::Mesh = std::mem::zeroed();
+            mesh.vertexCount = i32::try_from(self.vertices.len()).expect("chunk meshes are small");
+            mesh.triangleCount =
+                i32::try_from(self.indices.len() / 3).expect("chunk meshes are small");
+            mesh.vertices = alloc(&self.vertices);
+            mesh.normals = alloc(&self.normals);
+            mesh.colors = alloc(&self.colors);
+            mesh.indices = alloc(&self.indices);
+            ffi::UploadMesh(&raw mut mesh, false);
+            Mesh::from_raw(mesh)
+        }
+    }
+}
+
+/// The generator of the ground
+#[derive(Debug, Clone, PartialEq)]
+pub struct Terrain {
+    noise: Noise,
+    /// Areas kept flat for buildings
+    pub pads: Vec<Pad>,
+}
+
+impl Terrain {
+    /// Meters across the largest features
+    const SCALE: f32 = 160.0;
+    /// Meters from the lowest possible ground to the highest
+    const RELIEF: f32 = 48.0;
+    /// Meters across areas of similar [`Biome`]
+    const CLIMATE_SCALE: f32 = 400.0;
+    const HILLS_HEIGHT: f32 = 8.0;
+    const MOUNTAINS_HEIGHT: f32 = 16.0;
+    /// Meters over which the ground eases from a [`Pad`] to its natural height
+    pub const PAD_BLEND: f32 = 32.0;
+    /// Chance of a chunk having a [`DepositSite`]
+    const DEPOSIT_CHANCE: f32 = 0.3;
+
+    pub const fn new(seed: u32, pads: Vec<Pad>) -> Self {
+        Self {
+            noise: Noise::new(seed),
+            pads,
+        }
+    }
+
+    pub const fn seed(&self) -> u32 {
+        self.noise.seed
+    }
+
+    /// Height of the ground at a cell corner, before it's flattened for pads
+    fn natural_height(&self, x: f32, z: f32) -> f32 {
+        // Squared so there's more flat land than mountains
+        let n = self.noise.fractal(x / Self::SCALE, z / Self::SCALE, 5);
+        (n * n).mul_add(Self::RELIEF, -0.25 * Self::RELIEF)
+    }
+
+    /// Height of the ground in meters at the corner `(x, z)` of a cell
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "terrain is near enough the origin"
+    )]
+    pub fn height(&self, x: i32, z: i32) -> f32 {
+        let (x, z) = (x as f32, z as f32);
+        let natural = self.natural_height(x, z);
+        let pad = (self.pads.iter())
+            .map(|pad| (pad, 1.0 - pad.distance(x, z) / Self::PAD_BLEND))
+            .filter(|&(_, weight)| weight > 0.0)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        match pad {
+            Some((pad, weight)) => {
+                natural + (pad.min.y as f32 - natural) * smoothstep(weight.min(1.0))
+            }
+            None => natural,
+        }
+    }
+
+    /// Height of the ground anywhere, between the heights at the corners around it
+    pub fn height_at(&self, x: f32, z: f32) -> f32 {
+        let (x0, z0) = (x.floor(), z.floor());
+        let (tx, tz) = (x - x0, z - z0);
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "terrain isn't sampled anywhere near i32::MAX"
+        )]
+        let (ix, iz) = (x0 as i32, z0 as i32);
+        let top = self.height(ix, iz) + (self.height(ix + 1, iz) - self.height(ix, iz)) * tx;
+        let bottom =
+            self.height(ix, iz + 1) + (self.height(ix + 1, iz + 1) - self.height(ix, iz + 1)) * tx;
+        top + (bottom - top) * tz
+    }
+
+    /// The kind of land at the corner `(x, z)` of a cell, which is `height` meters high
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "terrain is near enough the origin"
+    )]
+    fn biome(&self, x: i32, z: i32, height: f32) -> Biome {
+        let moisture = (self.noise.offset(1000)).fractal(
+            x as f32 / Self::CLIMATE_SCALE,
+            z as f32 / Self::CLIMATE_SCALE,
+            3,
+        );
+        if height >= Self::MOUNTAINS_HEIGHT {
+            Biome::Mountains
+        } else if height >= Self::HILLS_HEIGHT {
+            Biome::Hills
+        } else if moisture < 0.4 {
+            Biome::Desert
+        } else if moisture > 0.6 {
+            Biome::Forest
+        } else {
+            Biome::Plains
+        }
+    }
+
+    /// The terrain at the corner `(x, z)` of a cell
+    pub fn sample(&self, x: i32, z: i32) -> TerrainSample {
+        let height = self.height(x, z);
+        TerrainSample {
+            height,
+            biome: self.biome(x, z, height),
+        }
+    }
+
+    /// The cell the ground is in at `(x, z)`
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "terrain is only tens of meters high"
+    )]
+    pub fn surface(&self, x: i32, z: i32) -> RailVector3 {
+        RailVector3::new(x, self.height(x, z).floor() as i32, z)
+    }
+
+    pub fn chunk(&self, pos: ChunkPos) -> Chunk {
+        let origin = pos.origin();
+        let range = -1..=Chunk::SIZE + 1;
+        let samples = (range.clone())
+            .flat_map(|z| range.clone().map(move |x| (x, z)))
+            .map(|(x, z)| self.sample(origin.x + x, origin.z + z))
+            .collect();
+        Chunk { pos, samples }
+    }
+
+    /// Where the ore deposit in the chunk at `pos` is, if it has one
+    ///
+    /// Deposits can be under [`Pad`]s too, to be mined from the factory on top.
+    pub fn deposit_site(&self, pos: ChunkPos) -> Option<DepositSite> {
+        let noise = self.noise.offset(2000);
+        if noise.lattice(pos.x, pos.z) >= Self::DEPOSIT_CHANCE {
+            return None;
+        }
+        let roll = hash(noise.offset(1).seed, pos.x, pos.z);
+        let origin = pos.origin();
+        let size = Chunk::SIZE.cast_unsigned();
+        let [x, z] = [roll % size, (roll / size) % size]
+            .map(|v| i32::try_from(v).expect("less than the chunk size"));
+        let position = self.surface(origin.x + x, origin.z + z);
+        let ores = self.sample(position.x, position.z).biome.ores();
+        let pick = (roll / (size * size)) as usize % ores.len();
+        Some(DepositSite {
+            position,
+            element: ores[pick],
+            richness: 0.5 + noise.offset(2).lattice(pos.x, pos.z),
+        })
+    }
+
+    /// Every [`DepositSite`] in chunks at most `radius` chunks from the one `center` is in
+    pub fn deposit_sites_near(&self, center: RailVector3, radius: i32) -> Vec<DepositSite> {
+        (ChunkPos::containing(center).around(radius))
+            .filter_map(|pos| self.deposit_site(pos))
+            .collect()
+    }
+}
+
+/// Meshes of the chunks around the player, made as they come into view
+#[derive(Debug, Default)]
+pub struct TerrainMeshes {
+    chunks: BTreeMap<ChunkPos, Mesh>,
+}
+
+impl TerrainMeshes {
+    pub const fn new() -> Self {
+        Self {
+            chunks: BTreeMap::new(),
+        }
+    }
+
+    /// How many chunks out from the player's are drawn
+    pub const VIEW_RADIUS: i32 = 4;
+
+    /// How many chunks are meshed each frame at most, so walking doesn't stutter
+    const MESHED_PER_FRAME: usize = 2;
+
+    /// Mesh the nearest missing chunks around `center`, and drop the ones out of view
+    pub fn update(&mut self, thread: &RaylibThread, terrain: &Terrain, center: ChunkPos) {
+        let in_view = |pos: ChunkPos| {
+            (pos.x - center.x).abs() <= Self::VIEW_RADIUS
+                && (pos.z - center.z).abs() <= Self::VIEW_RADIUS
+        };
+        self.chunks.retain(|&pos, _| in_view(pos));
+        let mut missing = (center.around(Self::VIEW_RADIUS))
+            .filter(|pos| !self.chunks.contains_key(pos))
+            .collect::<Vec<_>>();
+        missing.sort_by_key(|pos| (pos.x - center.x).pow(2) + (pos.z - center.z).pow(2));
+        for pos in missing.into_iter().take(Self::MESHED_PER_FRAME) {
+            let mesh = terrain.chunk(pos).mesh().upload(thread);
+            self.chunks.insert(pos, mesh);
+        }
+    }
+
+    /// Unload every chunk
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Draw every meshed chunk with `material`, relative to the player at `player_pos`
+    pub fn draw(
+        &self,
+        d: &mut dyn DynRaylibDraw3D,
+        material: &Material,
+        player_pos: PlayerVector3,
+    ) {
+        for (pos, mesh) in &self.chunks {
+            let Vector3 { x, y, z } = pos.origin().to_player_relative(player_pos);
+            // SAFETY: TBD
+            let material = unsafe { WeakMaterial::from_raw(**material) };
+            d.draw_mesh(**mesh, *material, Matrix::translate(x, y, z));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terrain() {
+        let pad = Pad {
+            min: RailVector3::new(-30, 0, -30),
+            max: RailVector3::new(30, 30, 30),
+        };
+        let terrain = Terrain::new(7, vec![pad]);
+        assert_eq!(
+            terrain.chunk(ChunkPos::new(3, -2)),
+            Terrain::new(7, vec![pad]).chunk(ChunkPos::new(3, -2)),
+            "the same seed makes the same ground"
+        );
+        assert!(terrain.height(0, 0).abs() < 1e-6, "pads are flat");
+        assert!(terrain.height(-30, 30).abs() < 1e-6);
+        assert!((terrain.height(500, 500) - terrain.natural_height(500.0, 500.0)).abs() < 1e-6);
+        assert!((terrain.height_at(12.5, -40.25)).is_finite());
+
+        let chunk = terrain.chunk(ChunkPos::new(-1, 0));
+        assert_eq!(
+            chunk.sample(Chunk::SIZE, 5),
+            terrain.chunk(ChunkPos::new(0, 0)).sample(0, 5),
+            "neighboring chunks share their edge"
+        );
+        assert_eq!(
+            ChunkPos::containing(RailVector3::new(-1, 9, 32)),
+            ChunkPos::new(-1, 1)
+        );
+
+        let sites = terrain.deposit_sites_near(RailVector3::ZERO, 8);
+        assert!(!sites.is_empty());
+        for site in sites {
+            assert_eq!(
+                site.position,
+                terrain.surface(site.position.x, site.position.z)
+            );
+            assert!((0.5..1.5).contains(&site.richness));
+        }
+    }
+
+    #[test]
+    fn test_mesh() {
+        let chunk = Terrain::new(0, Vec::new()).chunk(ChunkPos::new(0, 0));
+        let mesh = chunk.mesh();
+        let corners = usize::try_from(Chunk::SIZE + 1).unwrap();
+        assert_eq!(mesh.vertices.len(), corners * corners);
+        assert_eq!(mesh.normals.len(), mesh.vertices.len());
+        assert_eq!(mesh.colors.len(), mesh.vertices.len());
+        assert_eq!(mesh.indices.len(), 6 * (corners - 1) * (corners - 1));
+        for triangle in mesh.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[usize::from(triangle[i])]);
+            assert!((b - a).cross(c - a).y > 0.0, "triangles should face up");
+        }
+        assert!(
+            mesh.normals
+                .iter()
+                .all(|n| n.y > 0.0 && (n.length() - 1.0).abs() < 1e-4)
+        );
+    }
+}
@@ -0,0 +1,442 @@
+//! What the player has learned how to build
+//!
+//! The [lab](crate::region::lab::Laboratory) turns samples into research points, which are spent
+//! on [`Tech`]s. Each tech needs the ones before it, and unlocks machines, belt levels, or
+//! recipes. Placing things checks [`Research`] first, so locked content can't be built.
+//!
+//! Research is saved as text, with the points left and each unlocked tech by name:
+//!
+//! ```text
+//! research 120
+//! unlocked basics belt-mk2
+//! ```
+
+use crate::{
+    container::ItemStack,
+    plugin,
+    region::factory::{BeltLevel, MachineKind, blueprint::Blueprint, recipe::Recipe},
+};
+use std::{collections::BTreeSet, str::FromStr};
+
+/// Something a [`Tech`] lets the player build or use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unlock {
+    Machine(MachineKind),
+    Belt(BeltLevel),
+    /// The [`Recipe`] with this [name](Recipe::name)
+    Recipe(&'static str),
+    /// Sensors, combinators, and the wires between them
+    Circuits,
+}
+
+impl std::fmt::Display for Unlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Machine(kind) => f.write_str(&kind.name()),
+            Self::Belt(level) => write!(f, "Mk{} belts", level.number()),
+            Self::Recipe(name) => write!(f, "the {name} recipe"),
+            Self::Circuits => f.write_str("circuits"),
+        }
+    }
+}
+
+/// Identifies a [`Tech`] in [`Tech::ALL`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TechId {
+    Basics,
+    BeltMk2,
+    BeltMk3,
+    BeltMk4,
+    BeltMk5,
+    BeltMk6,
+    BeltMk7,
+    BeltMk8,
+    Combustion,
+    Automation,
+    Assembly,
+    FluidStorage,
+    Separation,
+    Electrolysis,
+    Flight,
+}
+
+impl TechId {
+    pub const fn tech(self) -> &'static Tech {
+        &Tech::ALL[self as usize]
+    }
+}
+
+/// One node of the tech tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tech {
+    pub id: TechId,
+    /// Used to save research and to refer to the tech in the console
+    pub name: &'static str,
+    /// Research points spent to unlock it
+    pub cost: u32,
+    /// Techs that have to be unlocked first
+    pub prerequisites: &'static [TechId],
+    pub unlocks: &'static [Unlock],
+}
+
+impl Tech {
+    /// The whole tech tree, in the same order as [`TechId`]
+    ///
+    /// Techs that cost nothing and need nothing are unlocked from the start.
+    pub const ALL: [Self; 15] = [
+        Self::new(
+            TechId::Basics,
+            "basics",
+            0,
+            &[],
+            &[
+                Unlock::Machine(MachineKind::Reactor),
+                Unlock::Machine(MachineKind::Miner),
+                Unlock::Machine(MachineKind::Splitter),
+                Unlock::Machine(MachineKind::Merger),
+                Unlock::Machine(MachineKind::Station),
+                Unlock::Machine(MachineKind::Vent),
+                Unlock::Machine(MachineKind::ChargingPad),
+                Unlock::Machine(MachineKind::ReliefValve),
+                Unlock::Belt(BeltLevel::Mk1),
+            ],
+        ),
+        Self::new(
+            TechId::BeltMk2,
+            "belt-mk2",
+            50,
+            &[TechId::Basics],
+            &[Unlock::Belt(BeltLevel::Mk2)],
+        ),
+        Self::new(
+            TechId::BeltMk3,
+            "belt-mk3",
+            100,
+            &[TechId::BeltMk2],
+            &[Unlock::Belt(BeltLevel::Mk3)],
+        ),
+        Self::new(
+            TechId::BeltMk4,
+            "belt-mk4",
+            200,
+            &[TechId::BeltMk3],
+            &[Unlock::Belt(BeltLevel::Mk4)],
+        ),
+        Self::new(
+            TechId::BeltMk5,
+            "belt-mk5",
+            400,
+            &[TechId::BeltMk4],
+            &[Unlock::Belt(BeltLevel::Mk5)],
+        ),
+        Self::new(
+            TechId::BeltMk6,
+            "belt-mk6",
+            800,
+            &[TechId::BeltMk5],
+            &[Unlock::Belt(BeltLevel::Mk6)],
+        ),
+        Self::new(
+            TechId::BeltMk7,
+            "belt-mk7",
+            1600,
+            &[TechId::BeltMk6],
+            &[Unlock::Belt(BeltLevel::Mk7)],
+        ),
+        Self::new(
+            TechId::BeltMk8,
+            "belt-mk8",
+            3200,
+            &[TechId::BeltMk7],
+            &[Unlock::Belt(BeltLevel::Mk8)],
+        ),
+        Self::new(
+            TechId::Combustion,
+            "combustion",
+            100,
+            &[TechId::Basics],
+            &[Unlock::Recipe("Combustion")],
+        ),
+        Self::new(
+            TechId::Automation,
+            "automation",
+            150,
+            &[TechId::Basics],
+            &[Unlock::Machine(MachineKind::Controller), Unlock::Circuits],
+        ),
+        Self::new(
+            TechId::Assembly,
+            "assembly",
+            200,
+            &[TechId::Basics],
+            &[
+                Unlock::Machine(MachineKind::Assembler),
+                Unlock::Recipe("Circuit board"),
+                Unlock::Recipe("Reactor kit"),
+            ],
+        ),
+        Self::new(
+            TechId::FluidStorage,
+            "fluid-storage",
+            75,
+            &[TechId::Basics],
+            &[
+                Unlock::Machine(MachineKind::Tank),
+                Unlock::Recipe("Precipitated chalk"),
+            ],
+        ),
+        Self::new(
+            TechId::Separation,
+            "separation",
+            150,
+            &[TechId::FluidStorage],
+            &[
+                Unlock::Machine(MachineKind::Distiller),
+                Unlock::Machine(MachineKind::Crystallizer),
+            ],
+        ),
+        Self::new(
+            TechId::Electrolysis,
+            "electrolysis",
+            200,
+            &[TechId::Separation],
+            &[Unlock::Machine(MachineKind::Electrolyzer)],
+        ),
+        Self::new(
+            TechId::Flight,
+            "flight",
+            1000,
+            &[TechId::Assembly, TechId::Electrolysis],
+            &[Unlock::Recipe("Jetpack")],
+        ),
+    ];
+
+    const fn new(
+        id: TechId,
+        name: &'static str,
+        cost: u32,
+        prerequisites: &'static [TechId],
+        unlocks: &'static [Unlock],
+    ) -> Self {
+        Self {
+            id,
+            name,
+            cost,
+            prerequisites,
+            unlocks,
+        }
+    }
+
+    /// The tech called `name`, if there is one
+    pub fn find(name: &str) -> Option<&'static Self> {
+        Self::ALL.iter().find(|tech| tech.name == name)
+    }
+
+    /// Unlocked from the start
+    pub const fn is_free(&self) -> bool {
+        self.cost == 0 && self.prerequisites.is_empty()
+    }
+}
+
+/// Research points a sample is worth, one for each atom in it
+pub fn sample_points(sample: &ItemStack) -> u32 {
+    let atoms = (sample.item.element_counts().values()).fold(0u32, |sum, &n| sum.saturating_add(n));
+    atoms.saturating_mul(sample.count)
+}
+
+/// Why a [`Tech`] couldn't be researched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResearchError {
+    AlreadyUnlocked,
+    /// Contains the first prerequisite that isn't unlocked yet
+    MissingPrerequisite(TechId),
+    /// Contains how many more points are needed
+    NotEnoughPoints(u32),
+}
+
+impl std::fmt::Display for ResearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AlreadyUnlocked => f.write_str("already researched"),
+            Self::MissingPrerequisite(id) => write!(f, "needs {} first", id.tech().name),
+            Self::NotEnoughPoints(short) => write!(f, "needs {short} more research points"),
+        }
+    }
+}
+
+impl std::error::Error for ResearchError {}
+
+/// Research points and the techs they've been spent on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Research {
+    /// Points not spent yet
+    pub points: u32,
+    unlocked: BTreeSet<TechId>,
+}
+
+impl Default for Research {
+    /// Only the free techs
+    fn default() -> Self {
+        Self {
+            points: 0,
+            unlocked: (Tech::ALL.iter())
+                .filter(|tech| tech.is_free())
+                .map(|tech| tech.id)
+                .collect(),
+        }
+    }
+}
+
+impl Research {
+    #[inline]
+    pub fn is_unlocked(&self, id: TechId) -> bool {
+        self.unlocked.contains(&id)
+    }
+
+    /// Whether `id` could be researched now, given enough points
+    pub fn is_available(&self, id: TechId) -> bool {
+        !self.is_unlocked(id) && (id.tech().prerequisites.iter()).all(|&id| self.is_unlocked(id))
+    }
+
+    /// Techs that could be researched now, given enough points
+    pub fn available(&self) -> impl Iterator<Item = &'static Tech> + '_ {
+        (Tech::ALL.iter()).filter(|tech| self.is_available(tech.id))
+    }
+
+    /// Spend points on `id`
+    pub fn research(&mut self, id: TechId) -> Result<(), ResearchError> {
+        let tech = id.tech();
+        if self.is_unlocked(id) {
+            return Err(ResearchError::AlreadyUnlocked);
+        }
+        if let Some(&missing) = (tech.prerequisites.iter()).find(|&&id| !self.is_unlocked(id)) {
+            return Err(ResearchError::MissingPrerequisite(missing));
+        }
+        self.points = (self.points.checked_sub(tech.cost))
+            .ok_or_else(|| ResearchError::NotEnoughPoints(tech.cost - self.points))?;
+        self.unlocked.insert(id);
+        Ok(())
+    }
+
+    /// Whether an unlocked tech unlocks `unlock`
+    pub fn allows(&self, unlock: Unlock) -> bool {
+        (self.unlocked.iter()).any(|id| id.tech().unlocks.contains(&unlock))
+    }
+
+    /// Whether `recipe` has been unlocked
+    ///
+    /// Recipes added by [plugins](crate::plugin) aren't in the tech tree, so they always are.
+    pub fn allows_recipe(&self, recipe: &Recipe) -> bool {
+        self.allows(Unlock::Recipe(recipe.name()))
+            || plugin::registry().recipe(recipe.name()).is_some()
+    }
+
+    /// The fastest belt level that's been unlocked, which new belts are built at
+    ///
+    /// Mk1 even if it hasn't been unlocked, so belts can always be built.
+    pub fn best_belt(&self) -> BeltLevel {
+        (BeltLevel::ALL.into_iter())
+            .rev()
+            .find(|&level| self.allows(Unlock::Belt(level)))
+            .unwrap_or(BeltLevel::Mk1)
+    }
+
+    /// The first thing in `blueprint` that hasn't been unlocked, if any
+    pub fn locked_in(&self, blueprint: &Blueprint) -> Option<Unlock> {
+        let machines = (!blueprint.reactors.is_empty()).then_some(MachineKind::Reactor);
+        (machines.map(Unlock::Machine).into_iter())
+            .chain(blueprint.belts.iter().map(|belt| Unlock::Belt(belt.level)))
+            .find(|&unlock| !self.allows(unlock))
+    }
+}
+
+impl std::fmt::Display for Research {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "research {}", self.points)?;
+        f.write_str("unlocked")?;
+        for id in &self.unlocked {
+            write!(f, " {}", id.tech().name)?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Why text couldn't be read as [`Research`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseResearchError {
+    /// Doesn't start with `research` and the number of points
+    MissingHeader,
+    /// Contains the name that isn't a tech
+    UnknownTech(String),
+}
+
+impl std::fmt::Display for ParseResearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader => f.write_str("not research"),
+            Self::UnknownTech(name) => write!(f, "unknown tech `{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for ParseResearchError {}
+
+impl FromStr for Research {
+    type Err = ParseResearchError;
+
+    /// Free techs are unlocked even if they aren't listed, in case they were added since
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let (Some("research"), Some(Ok(points))) = (words.next(), words.next().map(str::parse))
+        else {
+            return Err(ParseResearchError::MissingHeader);
+        };
+        let mut research = Self {
+            points,
+            ..Self::default()
+        };
+        for name in words.skip_while(|&word| word == "unlocked") {
+            let tech = Tech::find(name)
+                .ok_or_else(|| ParseResearchError::UnknownTech(name.to_string()))?;
+            research.unlocked.insert(tech.id);
+        }
+        Ok(research)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tech_tree() {
+        for (idx, tech) in Tech::ALL.iter().enumerate() {
+            assert_eq!(tech.id as usize, idx, "{} is out of order", tech.name);
+        }
+
+        let mut research = Research::default();
+        assert!(research.allows(Unlock::Machine(MachineKind::Reactor)));
+        assert_eq!(research.best_belt(), BeltLevel::Mk1);
+        assert_eq!(
+            research.research(TechId::BeltMk3),
+            Err(ResearchError::MissingPrerequisite(TechId::BeltMk2))
+        );
+        assert_eq!(
+            research.research(TechId::BeltMk2),
+            Err(ResearchError::NotEnoughPoints(50))
+        );
+
+        research.points = sample_points(&ItemStack::new("H2O".parse().unwrap(), 20));
+        assert_eq!(research.points, 60);
+        assert_eq!(research.research(TechId::BeltMk2), Ok(()));
+        assert_eq!(research.points, 10);
+        assert_eq!(research.best_belt(), BeltLevel::Mk2);
+        assert!(research.is_available(TechId::BeltMk3));
+
+        assert_eq!(research.to_string().parse(), Ok(research));
+        assert_eq!(
+            "research 0\nunlocked warp-drive".parse::<Research>(),
+            Err(ParseResearchError::UnknownTech("warp-drive".to_string()))
+        );
+    }
+}
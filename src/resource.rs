@@ -1,5 +1,18 @@
-use crate::rlights::{Light, LightType};
+use crate::{
+    item_visual::{ItemVisual, ItemVisuals},
+    plugin::{self, ModelId, PluginModel},
+    region::{factory::decal, rail::terrain::TerrainMeshes},
+};
+use engine::animation::Clip;
 use raylib::prelude::*;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, btree_map},
+    ffi::CString,
+    io,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
 
 // if you have a better idea, go ahead
 #[rustfmt::skip]
@@ -13,57 +26,652 @@ pub static PERIODIC_OFFSETS: [(u8, u8); 118] = [
     (0,6),(1,6),(2,6),(3,6),(4,6),(5,6),(6,6),(7,6),(8,6),(9,6),(10,6),(11,6),(12,6),(13,6),(14,6),(15,6),(16,6),(17,6),(18,6),(19,6),(20,6),(21,6),(22,6),(23,6),(24,6),(25,6),(26,6),(27,6),(28,6),(29,6),(30,6),(31,6),
 ];
 
+/// Keyframes per second in animations raylib loads, which it samples every 17 milliseconds
+const ANIMATION_FPS: f32 = 1000.0 / 17.0;
+
+/// Shaders built into the executable, by file name in the asset directory
+const EMBEDDED_SHADERS: [(&str, &str); 3] = [
+    ("lighting.fs", include_str!("../assets/lighting.fs")),
+    ("lighting.vs", include_str!("../assets/lighting.vs")),
+    (
+        "lighting_instancing.vs",
+        include_str!("../assets/lighting_instancing.vs"),
+    ),
+];
+
+/// Identifies an asset in [`Resources`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AssetId {
+    /// [`Asset::Texture`]
+    Skybox,
+    /// [`Asset::Model`]
+    Reactor,
+    /// [`Asset::Material`], the reactor's material but for use with
+    /// [`RaylibDraw3D::draw_mesh_instanced`]
+    ReactorInstanced,
+    /// [`Asset::Model`], only used for its mesh, see [`ItemVisual::CRATE`]
+    ItemCrate,
+    /// [`Asset::Material`] for drawing [`Self::ItemCrate`] with
+    /// [`RaylibDraw3D::draw_mesh_instanced`]
+    ItemCrateInstanced,
+    /// [`Asset::Texture`], a white icon to be tinted, see [`ItemVisual::element`]
+    ItemIcon,
+    /// [`Asset::Material`], lit and colored by each vertex, for the chunks in
+    /// [`Resources::terrain`]
+    Terrain,
+    /// [`Asset::Model`]
+    OrbitalS,
+    /// [`Asset::Model`]
+    OrbitalP,
+    /// [`Asset::Model`]
+    OrbitalD,
+    /// [`Asset::Model`]
+    OrbitalF,
+    /// [`Asset::PeriodicTable`]
+    PeriodicTable,
+    /// [`Asset::Mesh`], a unit cube stretched over whatever is being previewed with
+    /// [`draw_ghost`](engine::draw3d::Renderer::draw_ghost)
+    Ghost,
+    /// [`Asset::Animations`] for the bones of [`Self::Reactor`]
+    ReactorAnimations,
+    /// [`Asset::Texture`], an atlas of everything that can be painted on factory floors, see
+    /// [`DecalKind::atlas_rect`](crate::region::factory::decal::DecalKind::atlas_rect)
+    Decals,
+    /// [`Asset::Model`] of a [`PluginModel`]
+    PluginModel(ModelId),
+    /// [`Asset::Material`] in the color of a [`PluginModel`], for
+    /// [`RaylibDraw3D::draw_mesh_instanced`]
+    PluginMaterial(ModelId),
+}
+
+impl AssetId {
+    /// Whether the asset uses the lighting shader
+    pub const fn is_lit(self) -> bool {
+        matches!(
+            self,
+            Self::Reactor
+                | Self::ReactorInstanced
+                | Self::ItemCrateInstanced
+                | Self::Terrain
+                | Self::PluginMaterial(_)
+        )
+    }
+
+    /// Files in the asset directory the asset is built from
+    ///
+    /// Shaders are built into the game, and textures are generated when their file isn't there,
+    /// so these only need to exist to override the defaults while hot reloading. Plugin models
+    /// are read from wherever their plugin says, so they aren't watched.
+    pub const fn sources(self) -> &'static [&'static str] {
+        match self {
+            Self::Skybox => &["skybox.png"],
+            Self::Reactor => &["lighting.vs", "lighting.fs", "reactor.png"],
+            Self::ReactorInstanced => &["lighting_instancing.vs", "lighting.fs", "reactor.png"],
+            Self::ItemCrateInstanced => &["lighting_instancing.vs", "lighting.fs"],
+            Self::ItemIcon => &["item_icon.png"],
+            Self::ReactorAnimations => &["reactor.glb"],
+            Self::Decals => &["decals.png"],
+            Self::Terrain => &["lighting.vs", "lighting.fs"],
+            Self::ItemCrate
+            | Self::OrbitalS
+            | Self::OrbitalP
+            | Self::OrbitalD
+            | Self::OrbitalF
+            | Self::PeriodicTable
+            | Self::Ghost
+            | Self::PluginModel(_)
+            | Self::PluginMaterial(_) => &[],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Asset {
+    Texture(Texture2D),
+    Model(Model),
+    Material(Material),
+    Mesh(Mesh),
+    /// What a machine's moving parts do while it's idle and while it's working, see
+    /// [`Animator`](engine::animation::Animator)
+    Animations {
+        idle: Clip,
+        working: Clip,
+    },
+    /// The mesh of one cell, and the transform and material of each element's cell
+    PeriodicTable {
+        mesh: Mesh,
+        cells: Box<[(Matrix, Material); 118]>,
+    },
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    /// A source file couldn't be read
+    Io { path: PathBuf, error: io::Error },
+    /// raylib couldn't load part of an asset
+    Load { id: AssetId, error: Error },
+    /// Part of an asset loaded but isn't usable, like a shader that didn't compile
+    Invalid { id: AssetId, reason: &'static str },
+}
+
+impl std::fmt::Display for AssetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "couldn't read {}: {error}", path.display()),
+            Self::Load { id, error } => write!(f, "couldn't load {id:?}: {error}"),
+            Self::Invalid { id, reason } => write!(f, "{id:?} is invalid: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { error, .. } => Some(error),
+            Self::Load { error, .. } => Some(error),
+            Self::Invalid { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    asset: Asset,
+    /// [`Resources::acquire`] calls not yet matched by [`Resources::release`]
+    refs: usize,
+    /// When each of the asset's sources was last modified as of loading it, if it exists
+    versions: Vec<Option<SystemTime>>,
+}
+
 #[derive(Debug)]
+struct HotReload {
+    /// Where source files are read from
+    dir: PathBuf,
+    last_poll: Instant,
+}
+
+/// Every loaded asset
+///
+/// Assets are loaded the first time they're acquired and unloaded once everything that acquired
+/// them has released them.
+#[derive(Debug, Default)]
 pub struct Resources {
-    pub skybox: Texture2D,
-    pub reactor: Model,
-    pub orbital_s: Model,
-    pub orbital_p: Model,
-    pub orbital_d: Model,
-    pub orbital_f: Model,
-    pub periodic_table_mesh: Mesh,
-    pub periodic_table_mats: [(Matrix, Material); 118],
+    entries: BTreeMap<AssetId, Entry>,
+    hot_reload: Option<HotReload>,
+    /// How items are drawn
+    pub item_visuals: ItemVisuals,
+    /// The ground around the player, see [`RegionSystems`](crate::region::events::RegionSystems)
+    pub terrain: TerrainMeshes,
 }
 
 impl Resources {
-    #[allow(clippy::too_many_lines, reason = "shut the fuck up")]
-    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
+    /// How often to check source files for changes while hot reloading
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// A registry with nothing loaded yet
+    pub const fn new() -> Self {
         Self {
-            skybox: {
-                let image = Image::gen_image_gradient_radial(
-                    256,
-                    256,
-                    0.1,
-                    Color::DODGERBLUE,
-                    Color::CORAL,
-                );
-                rl.load_texture_from_image(thread, &image).unwrap()
+            entries: BTreeMap::new(),
+            hot_reload: None,
+            item_visuals: ItemVisuals::new(),
+            terrain: TerrainMeshes::new(),
+        }
+    }
+
+    /// Read sources from `dir` instead of using the built in ones, and reload assets when their
+    /// sources change
+    #[must_use]
+    pub fn with_hot_reload(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.hot_reload = Some(HotReload {
+            dir: dir.into(),
+            last_poll: Instant::now(),
+        });
+        self
+    }
+
+    /// Load `id` if it isn't loaded yet, and keep it loaded until it's released
+    pub fn acquire(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        id: AssetId,
+    ) -> Result<(), AssetError> {
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.refs += 1;
+            return Ok(());
+        }
+        let versions = self.versions(id);
+        let asset = self.load(rl, thread, id)?;
+        self.entries.insert(
+            id,
+            Entry {
+                asset,
+                refs: 1,
+                versions,
             },
-            reactor: {
+        );
+        Ok(())
+    }
+
+    /// [`Self::acquire`] every asset in `ids`, or none of them if any fail to load
+    pub fn acquire_all(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        ids: &[AssetId],
+    ) -> Result<(), AssetError> {
+        for (i, &id) in ids.iter().enumerate() {
+            if let Err(e) = self.acquire(rl, thread, id) {
+                self.release_all(&ids[..i]);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Undo one [`Self::acquire`] of `id`, unloading it if nothing else has it acquired
+    pub fn release(&mut self, id: AssetId) {
+        if let btree_map::Entry::Occupied(mut entry) = self.entries.entry(id) {
+            entry.get_mut().refs -= 1;
+            if entry.get().refs == 0 {
+                entry.remove();
+            }
+        }
+    }
+
+    pub fn release_all(&mut self, ids: &[AssetId]) {
+        for &id in ids {
+            self.release(id);
+        }
+    }
+
+    #[inline]
+    pub fn is_loaded(&self, id: AssetId) -> bool {
+        self.entries.contains_key(&id)
+    }
+
+    /// The asset identified by `id`, if it's loaded
+    #[inline]
+    pub fn get(&self, id: AssetId) -> Option<&Asset> {
+        self.entries.get(&id).map(|entry| &entry.asset)
+    }
+
+    pub fn texture(&self, id: AssetId) -> Option<&Texture2D> {
+        match self.get(id)? {
+            Asset::Texture(texture) => Some(texture),
+            _ => None,
+        }
+    }
+
+    pub fn model(&self, id: AssetId) -> Option<&Model> {
+        match self.get(id)? {
+            Asset::Model(model) => Some(model),
+            _ => None,
+        }
+    }
+
+    pub fn material(&self, id: AssetId) -> Option<&Material> {
+        match self.get(id)? {
+            Asset::Material(material) => Some(material),
+            _ => None,
+        }
+    }
+
+    pub fn mesh(&self, id: AssetId) -> Option<&Mesh> {
+        match self.get(id)? {
+            Asset::Mesh(mesh) => Some(mesh),
+            _ => None,
+        }
+    }
+
+    /// The idle and working clips of `id`, if it's loaded
+    pub fn animations(&self, id: AssetId) -> Option<(&Clip, &Clip)> {
+        match self.get(id)? {
+            Asset::Animations { idle, working } => Some((idle, working)),
+            _ => None,
+        }
+    }
+
+    /// The shaders of every loaded asset that uses the lighting shader
+    pub fn lit_shaders_mut(&mut self) -> impl Iterator<Item = &mut WeakShader> {
+        self.entries
+            .iter_mut()
+            .filter(|(id, _)| id.is_lit())
+            .flat_map(|(_, entry)| match &mut entry.asset {
+                Asset::Model(model) => model
+                    .materials_mut()
+                    .iter_mut()
+                    .map(RaylibMaterial::shader_mut)
+                    .collect::<Vec<_>>(),
+                Asset::Material(material) => vec![material.shader_mut()],
+                Asset::Texture(_)
+                | Asset::Mesh(_)
+                | Asset::Animations { .. }
+                | Asset::PeriodicTable { .. } => Vec::new(),
+            })
+    }
+
+    /// The mesh and cells of [`AssetId::PeriodicTable`], if it's loaded
+    pub fn periodic_table(&self) -> Option<(&Mesh, &[(Matrix, Material); 118])> {
+        match self.get(AssetId::PeriodicTable)? {
+            Asset::PeriodicTable { mesh, cells } => Some((mesh, cells)),
+            _ => None,
+        }
+    }
+
+    /// Reload every loaded asset whose sources have changed since it was loaded
+    ///
+    /// Does nothing unless hot reloading is enabled, and checks at most every
+    /// [`Self::POLL_INTERVAL`]. Assets that fail to reload keep their old version, and the
+    /// errors are returned.
+    pub fn poll_hot_reload(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+    ) -> Vec<AssetError> {
+        let Some(hot_reload) = &mut self.hot_reload else {
+            return Vec::new();
+        };
+        if hot_reload.last_poll.elapsed() < Self::POLL_INTERVAL {
+            return Vec::new();
+        }
+        hot_reload.last_poll = Instant::now();
+
+        let changed: Vec<(AssetId, Vec<Option<SystemTime>>)> = self
+            .entries
+            .iter()
+            .map(|(&id, entry)| (id, entry, self.versions(id)))
+            .filter(|(_, entry, versions)| &entry.versions != versions)
+            .map(|(id, _, versions)| (id, versions))
+            .collect();
+        let mut errors = Vec::new();
+        for (id, versions) in changed {
+            let reloaded = self.load(rl, thread, id);
+            // Don't try again until the sources change again
+            let entry = self
+                .entries
+                .get_mut(&id)
+                .expect("changed assets are loaded");
+            entry.versions = versions;
+            match reloaded {
+                Ok(asset) => entry.asset = asset,
+                Err(e) => errors.push(e),
+            }
+        }
+        errors
+    }
+
+    /// When each of `id`'s sources was last modified, or [`None`] for ones that don't exist
+    fn versions(&self, id: AssetId) -> Vec<Option<SystemTime>> {
+        let Some(hot_reload) = &self.hot_reload else {
+            return Vec::new();
+        };
+        id.sources()
+            .iter()
+            .map(|name| {
+                std::fs::metadata(hot_reload.dir.join(name))
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+            })
+            .collect()
+    }
+
+    /// The source of the built in shader `name`, or the one in the asset directory if hot
+    /// reloading
+    fn shader_source(&self, name: &str) -> Result<Cow<'static, str>, AssetError> {
+        if let Some(hot_reload) = &self.hot_reload {
+            let path = hot_reload.dir.join(name);
+            return match std::fs::read_to_string(&path) {
+                Ok(source) => Ok(Cow::Owned(source)),
+                Err(error) => Err(AssetError::Io { path, error }),
+            };
+        }
+        let (_, source) = EMBEDDED_SHADERS
+            .iter()
+            .find(|(file, _)| *file == name)
+            .expect("shader should be built in");
+        Ok(Cow::Borrowed(source))
+    }
+
+    /// The image `name` in the asset directory if hot reloading and it exists, otherwise the
+    /// result of `generate`
+    fn image(
+        &self,
+        id: AssetId,
+        name: &str,
+        generate: impl FnOnce() -> Image,
+    ) -> Result<Image, AssetError> {
+        match &self.hot_reload {
+            Some(hot_reload) if hot_reload.dir.join(name).is_file() => {
+                let path = hot_reload.dir.join(name);
+                Image::load_image(&path.to_string_lossy())
+                    .map_err(|error| AssetError::Load { id, error })
+            }
+            _ => Ok(generate()),
+        }
+    }
+
+    /// The idle and working clips in the model file `name` in the asset directory if hot
+    /// reloading and it exists, otherwise empty clips
+    ///
+    /// The clips named "idle" and "working" are used, or the first two if they aren't named.
+    fn load_animations(&self, id: AssetId, name: &str) -> Result<Asset, AssetError> {
+        let path = match &self.hot_reload {
+            Some(hot_reload) if hot_reload.dir.join(name).is_file() => hot_reload.dir.join(name),
+            _ => {
+                return Ok(Asset::Animations {
+                    idle: Clip::default(),
+                    working: Clip::default(),
+                });
+            }
+        };
+        let path =
+            CString::new(path.to_string_lossy().into_owned()).map_err(|_| AssetError::Invalid {
+                id,
+                reason: "path contains a nul byte",
+            })?;
+        let mut count = 0;
+        // SAFETY: `path` is nul terminated and lives through the call
+        let raw = unsafe { ffi::LoadModelAnimations(path.as_ptr(), &raw mut count) };
+        if raw.is_null() {
+            return Err(AssetError::Invalid {
+                id,
+                reason: "no animations",
+            });
+        }
+        // SAFETY: raylib allocates `count` animations, which are unloaded below
+        let loaded =
+            unsafe { std::slice::from_raw_parts(raw, usize::try_from(count).unwrap_or(0)) };
+        let named = |clip: &str| {
+            loaded.iter().position(|animation| {
+                let name = animation.name.map(|c| c.to_ne_bytes()[0]);
+                name.split(|&b| b == 0).next() == Some(clip.as_bytes())
+            })
+        };
+        let clip = |idx: Option<usize>| {
+            idx.and_then(|idx| loaded.get(idx))
+                .map_or_else(Clip::default, |animation| {
+                    // SAFETY: `animation` was just loaded by raylib
+                    unsafe { Clip::from_raw(animation, ANIMATION_FPS) }
+                })
+        };
+        let idle = clip(named("idle").or(Some(0)));
+        let working = clip(named("working").or(Some(1)));
+        // SAFETY: loaded by `LoadModelAnimations` with `count` animations, and the clips are
+        // copies
+        unsafe { ffi::UnloadModelAnimations(raw, count) };
+        Ok(Asset::Animations { idle, working })
+    }
+
+    /// A texture of the image `name` in the asset directory if hot reloading and it exists,
+    /// otherwise of the result of `generate`
+    fn texture_asset(
+        &self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        id: AssetId,
+        name: &str,
+        generate: impl FnOnce() -> Image,
+    ) -> Result<Asset, AssetError> {
+        let image = self.image(id, name, generate)?;
+        rl.load_texture_from_image(thread, &image)
+            .map(Asset::Texture)
+            .map_err(|error| AssetError::Load { id, error })
+    }
+
+    /// Load the lighting fragment shader with the vertex shader `vs`
+    ///
+    /// The lights are set each frame by [`LightingManager`](crate::lighting::LightingManager).
+    fn lighting_shader(
+        &self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        id: AssetId,
+        vs: &str,
+    ) -> Result<Shader, AssetError> {
+        let shader = rl.load_shader_from_memory(
+            thread,
+            Some(&self.shader_source(vs)?),
+            Some(&self.shader_source("lighting.fs")?),
+        );
+        if !shader.is_shader_valid() {
+            return Err(AssetError::Invalid {
+                id,
+                reason: "shader failed to compile",
+            });
+        }
+        Ok(shader)
+    }
+
+    /// A lit material of one `color` for [`RaylibDraw3D::draw_mesh_instanced`]
+    fn instanced_material(
+        &self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        id: AssetId,
+        color: Color,
+    ) -> Result<Material, AssetError> {
+        // SAFETY: TBD
+        let mut mat = unsafe { Material::from_raw(*rl.load_material_default(thread)) };
+
+        let mut shader = self.lighting_shader(rl, thread, id, "lighting_instancing.vs")?;
+        shader.locs_mut()[ShaderLocationIndex::SHADER_LOC_MATRIX_MODEL as usize] =
+            shader.get_shader_location_attribute("instanceTransform");
+        // SAFETY: Material unloads non-default shader on its own
+        *mat.shader_mut() = unsafe { shader.make_weak() };
+
+        *mat.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize].color_mut() = color;
+        if !mat.is_material_valid() {
+            return Err(AssetError::Invalid {
+                id,
+                reason: "invalid material",
+            });
+        }
+        Ok(mat)
+    }
+
+    /// The reactor's albedo texture
+    fn reactor_texture(
+        &self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        id: AssetId,
+    ) -> Result<Texture2D, AssetError> {
+        let image = self.image(id, "reactor.png", || {
+            Image::gen_image_gradient_linear(64, 64, 0, Color::GRAY, Color::LIGHTGRAY)
+        })?;
+        rl.load_texture_from_image(thread, &image)
+            .map_err(|error| AssetError::Load { id, error })
+    }
+
+    /// A lit material colored only by each vertex, for terrain
+    fn terrain_material(
+        &self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        id: AssetId,
+    ) -> Result<Material, AssetError> {
+        // SAFETY: TBD
+        let mut mat = unsafe { Material::from_raw(*rl.load_material_default(thread)) };
+        let shader = self.lighting_shader(rl, thread, id, "lighting.vs")?;
+        // SAFETY: Material unloads non-default shader on its own
+        *mat.shader_mut() = unsafe { shader.make_weak() };
+        *mat.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize].color_mut() = Color::WHITE;
+        if !mat.is_material_valid() {
+            return Err(AssetError::Invalid {
+                id,
+                reason: "invalid material",
+            });
+        }
+        Ok(mat)
+    }
+
+    /// The model a plugin added as `model`
+    fn plugin_model(
+        &self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        id: AssetId,
+        model: ModelId,
+    ) -> Result<Asset, AssetError> {
+        let load_error = |error| AssetError::Load { id, error };
+        let source = *plugin::registry().model(model).ok_or(AssetError::Invalid {
+            id,
+            reason: "no plugin added it",
+        })?;
+        let mut model = match source {
+            PluginModel::File { path, .. } => {
+                let path = match &self.hot_reload {
+                    Some(hot_reload) if hot_reload.dir.join(path).is_file() => {
+                        hot_reload.dir.join(path)
+                    }
+                    _ => PathBuf::from(path),
+                };
+                rl.load_model(thread, &path.to_string_lossy())
+                    .map_err(load_error)?
+            }
+            PluginModel::Box { size, center, .. } => {
+                let mesh = Mesh::gen_mesh_cube(thread, size.x, size.y, size.z);
+                // SAFETY: Model unloads meshes on its own
+                let mut model = rl
+                    .load_model_from_mesh(thread, unsafe { mesh.make_weak() })
+                    .map_err(load_error)?;
+                model.transform = Matrix::translate(center.x, center.y, center.z).into();
+                model
+            }
+        };
+        for material in model.materials_mut() {
+            *material.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize].color_mut() =
+                source.color();
+        }
+        if !model.is_model_valid() {
+            return Err(AssetError::Invalid {
+                id,
+                reason: "invalid model",
+            });
+        }
+        Ok(Asset::Model(model))
+    }
+
+    fn load(
+        &self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        id: AssetId,
+    ) -> Result<Asset, AssetError> {
+        let load_error = |error| AssetError::Load { id, error };
+        match id {
+            AssetId::Skybox => self.texture_asset(rl, thread, id, "skybox.png", || {
+                Image::gen_image_gradient_radial(256, 256, 0.1, Color::DODGERBLUE, Color::CORAL)
+            }),
+            AssetId::Reactor => {
                 // Mesh
                 let mesh = Mesh::gen_mesh_cube(thread, 2.0, 2.0, 3.0);
 
                 let mut mat = rl.load_material_default(thread);
 
                 // Shader
-                let mut shader = rl.load_shader_from_memory(
-                    thread,
-                    Some(include_str!("../assets/lighting.vs")),
-                    Some(include_str!("../assets/lighting.fs")),
-                );
-                assert!(shader.is_shader_valid());
-                shader.set_shader_value(
-                    shader.get_shader_location("ambient"),
-                    Vector4::new(0.2, 0.2, 0.2, 1.0),
-                );
-                Light::new(
-                    LightType::Directional,
-                    Vector3::new(0.0, 50.0, 0.0),
-                    Vector3::ZERO,
-                    Color::WHITE,
-                    &mut shader,
-                )
-                .unwrap();
+                let shader = self.lighting_shader(rl, thread, id, "lighting.vs")?;
                 // SAFETY: Material unloads non-default shader on its own
                 *mat.shader_mut() = unsafe { shader.make_weak() };
 
@@ -72,109 +680,233 @@ impl Resources {
                     Color::GRAY;
 
                 // Texture
-                let image =
-                    Image::gen_image_gradient_linear(64, 64, 0, Color::GRAY, Color::LIGHTGRAY);
-                let texture = rl.load_texture_from_image(thread, &image).unwrap();
+                let texture = self.reactor_texture(rl, thread, id)?;
                 // SAFETY: Material unloads non-default textures on its own
                 mat.set_material_texture(MaterialMapIndex::MATERIAL_MAP_ALBEDO, unsafe {
                     texture.make_weak()
                 });
-                assert!(mat.is_material_valid());
+                if !mat.is_material_valid() {
+                    return Err(AssetError::Invalid {
+                        id,
+                        reason: "invalid material",
+                    });
+                }
 
                 // SAFETY: Model unloads meshes on its own
                 let mut model = rl
                     .load_model_from_mesh(thread, unsafe { mesh.make_weak() })
-                    .unwrap();
+                    .map_err(load_error)?;
                 model.materials_mut()[0] = mat;
                 model.transform = Matrix::translate(1.0, 1.0, 1.5).into();
 
-                assert!(model.is_model_valid());
-                model
-            },
-            orbital_s: {
-                let mesh = Mesh::gen_mesh_sphere(thread, 1.0, 10, 10);
-                let mut material = rl.load_material_default(thread);
-                *material.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize].color_mut() =
-                    Color::BLUE;
-                // SAFETY: Model unloads meshes on its own
-                let mut model = rl
-                    .load_model_from_mesh(thread, unsafe { mesh.make_weak() })
-                    .unwrap();
-                model.materials_mut()[0] = material;
-                model.transform = Matrix::identity().into();
-                model
-            },
-            orbital_p: {
-                let mesh = Mesh::gen_mesh_cube(thread, 1.0, 1.0, 1.0); // TODO
-                let mut material = rl.load_material_default(thread);
-                *material.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize].color_mut() =
-                    Color::MAGENTA;
-                // SAFETY: Model unloads meshes on its own
-                let mut model = rl
-                    .load_model_from_mesh(thread, unsafe { mesh.make_weak() })
-                    .unwrap();
-                model.materials_mut()[0] = material;
-                model.transform = Matrix::identity().into();
-                model
-            },
-            orbital_d: {
-                let mesh = Mesh::gen_mesh_cube(thread, 1.0, 1.0, 1.0); // TODO
-                let mut material = rl.load_material_default(thread);
-                *material.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize].color_mut() =
-                    Color::MAGENTA;
+                if !model.is_model_valid() {
+                    return Err(AssetError::Invalid {
+                        id,
+                        reason: "invalid model",
+                    });
+                }
+                Ok(Asset::Model(model))
+            }
+            AssetId::ReactorInstanced => {
+                let mut mat = self.instanced_material(rl, thread, id, Color::GRAY)?;
+                let texture = self.reactor_texture(rl, thread, id)?;
+                // SAFETY: Material unloads non-default textures on its own
+                mat.set_material_texture(MaterialMapIndex::MATERIAL_MAP_ALBEDO, unsafe {
+                    texture.make_weak()
+                });
+                if !mat.is_material_valid() {
+                    return Err(AssetError::Invalid {
+                        id,
+                        reason: "invalid material",
+                    });
+                }
+                Ok(Asset::Material(mat))
+            }
+            AssetId::ItemCrate => {
+                let mesh = Mesh::gen_mesh_cube(
+                    thread,
+                    ItemVisual::SIZE,
+                    ItemVisual::SIZE,
+                    ItemVisual::SIZE,
+                );
                 // SAFETY: Model unloads meshes on its own
                 let mut model = rl
                     .load_model_from_mesh(thread, unsafe { mesh.make_weak() })
-                    .unwrap();
-                model.materials_mut()[0] = material;
+                    .map_err(load_error)?;
                 model.transform = Matrix::identity().into();
-                model
-            },
-            orbital_f: {
-                let mesh = Mesh::gen_mesh_cube(thread, 1.0, 1.0, 1.0); // TODO
+                Ok(Asset::Model(model))
+            }
+            AssetId::ItemCrateInstanced => self
+                .instanced_material(rl, thread, id, Color::BROWN)
+                .map(Asset::Material),
+            AssetId::ItemIcon => self.texture_asset(rl, thread, id, "item_icon.png", || {
+                Image::gen_image_gradient_radial(32, 32, 0.6, Color::WHITE, Color::BLANK)
+            }),
+            AssetId::Terrain => self.terrain_material(rl, thread, id).map(Asset::Material),
+            AssetId::OrbitalS | AssetId::OrbitalP | AssetId::OrbitalD | AssetId::OrbitalF => {
+                let (mesh, color) = match id {
+                    AssetId::OrbitalS => (Mesh::gen_mesh_sphere(thread, 1.0, 10, 10), Color::BLUE),
+                    // TODO
+                    _ => (Mesh::gen_mesh_cube(thread, 1.0, 1.0, 1.0), Color::MAGENTA),
+                };
                 let mut material = rl.load_material_default(thread);
                 *material.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize].color_mut() =
-                    Color::MAGENTA;
+                    color;
                 // SAFETY: Model unloads meshes on its own
                 let mut model = rl
                     .load_model_from_mesh(thread, unsafe { mesh.make_weak() })
-                    .unwrap();
+                    .map_err(load_error)?;
                 model.materials_mut()[0] = material;
                 model.transform = Matrix::identity().into();
-                model
-            },
-            periodic_table_mesh: Mesh::gen_mesh_cube(thread, 0.25, 0.25, 0.25),
-            periodic_table_mats: {
-                PERIODIC_OFFSETS.map(|(col, row)| {
-                    let [x, z] = [col, row].map(|x| f32::from(x) * 0.25);
-                    #[rustfmt::skip]
-                    let matrix = Matrix {
-                        m0: 1.0, m4: 0.0, m8:  0.0, m12:   x,
-                        m1: 0.0, m5: 1.0, m9:  0.0, m13: 0.0,
-                        m2: 0.0, m6: 0.0, m10: 1.0, m14:   z,
-                        m3: 0.0, m7: 0.0, m11: 0.0, m15: 1.0,
-                    };
-
-                    let image = Image::gen_image_white_noise(128, 128, 0.5);
-                    let texture = rl.load_texture_from_image(thread, &image).unwrap();
-
-                    // TODO: lights don't seem to work well if multiple shaders are being loaded.
-                    // Need to find a way of reusing the lighting shader...
-
-                    // SAFETY: TBD
-                    let mut material =
-                        unsafe { Material::from_raw(*rl.load_material_default(thread)) };
-
-                    *material.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize]
-                        // SAFETY: Material unloads non-default textures
-                        .texture_mut() = unsafe { texture.make_weak() };
-
-                    *material.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize]
-                        .color_mut() = Color::LIGHTGRAY;
-
-                    (matrix, material)
-                })
-            },
+                Ok(Asset::Model(model))
+            }
+            AssetId::PeriodicTable => load_periodic_table(rl, thread, id),
+            AssetId::Ghost => Ok(Asset::Mesh(Mesh::gen_mesh_cube(thread, 1.0, 1.0, 1.0))),
+            AssetId::ReactorAnimations => self.load_animations(id, "reactor.glb"),
+            AssetId::Decals => self.texture_asset(rl, thread, id, "decals.png", decal::atlas_image),
+            AssetId::PluginModel(model) => self.plugin_model(rl, thread, id, model),
+            AssetId::PluginMaterial(model) => {
+                let color = (plugin::registry().model(model))
+                    .map(PluginModel::color)
+                    .ok_or(AssetError::Invalid {
+                        id,
+                        reason: "no plugin added it",
+                    })?;
+                self.instanced_material(rl, thread, id, color)
+                    .map(Asset::Material)
+            }
         }
     }
 }
+
+/// The cells of the periodic table, see [`Asset::PeriodicTable`]
+fn load_periodic_table(
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+    id: AssetId,
+) -> Result<Asset, AssetError> {
+    let load_error = |error| AssetError::Load { id, error };
+    let mut cells = Vec::with_capacity(PERIODIC_OFFSETS.len());
+    for (col, row) in PERIODIC_OFFSETS {
+        let [x, z] = [col, row].map(|x| f32::from(x) * 0.25);
+        #[rustfmt::skip]
+        let matrix = Matrix {
+            m0: 1.0, m4: 0.0, m8:  0.0, m12:   x,
+            m1: 0.0, m5: 1.0, m9:  0.0, m13: 0.0,
+            m2: 0.0, m6: 0.0, m10: 1.0, m14:   z,
+            m3: 0.0, m7: 0.0, m11: 0.0, m15: 1.0,
+        };
+
+        let image = Image::gen_image_white_noise(128, 128, 0.5);
+        let texture = rl
+            .load_texture_from_image(thread, &image)
+            .map_err(load_error)?;
+
+        // TODO: lights don't seem to work well if multiple shaders are being loaded.
+        // Need to find a way of reusing the lighting shader...
+
+        // SAFETY: TBD
+        let mut material = unsafe { Material::from_raw(*rl.load_material_default(thread)) };
+
+        *material.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize]
+            // SAFETY: Material unloads non-default textures
+            .texture_mut() = unsafe { texture.make_weak() };
+
+        *material.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize].color_mut() =
+            Color::LIGHTGRAY;
+
+        cells.push((matrix, material));
+    }
+    Ok(Asset::PeriodicTable {
+        mesh: Mesh::gen_mesh_cube(thread, 0.25, 0.25, 0.25),
+        cells: cells
+            .into_boxed_slice()
+            .try_into()
+            .expect("there is a cell for every element"),
+    })
+}
+
+/// Samples per second of generated sounds
+const SAMPLE_RATE: u32 = 22050;
+
+/// `seconds` of the waveform `f`, which takes the time in seconds and returns a value in `[-1, 1]`
+fn synthesize(seconds: f32, f: impl Fn(f32) -> f32) -> Vec<i16> {
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss,
+        reason = "generated sounds are a few seconds long at most"
+    )]
+    let len = (seconds * SAMPLE_RATE as f32) as u32;
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        reason = "samples are clamped to the range of i16"
+    )]
+    (0..len)
+        .map(|i| (f(i as f32 / SAMPLE_RATE as f32).clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16)
+        .collect()
+}
+
+/// Encode mono 16 bit samples as a WAV file
+fn encode_wav(samples: &[i16]) -> Vec<u8> {
+    const BYTES_PER_SAMPLE: u16 = 2;
+    let data_len = u32::try_from(samples.len() * usize::from(BYTES_PER_SAMPLE))
+        .expect("generated sounds should be small");
+    let mut wav = Vec::with_capacity(44 + samples.len() * 2);
+    wav.extend(b"RIFF");
+    wav.extend((36 + data_len).to_le_bytes());
+    wav.extend(b"WAVEfmt ");
+    wav.extend(16u32.to_le_bytes());
+    // PCM, 1 channel
+    wav.extend(1u16.to_le_bytes());
+    wav.extend(1u16.to_le_bytes());
+    wav.extend(SAMPLE_RATE.to_le_bytes());
+    wav.extend((SAMPLE_RATE * u32::from(BYTES_PER_SAMPLE)).to_le_bytes());
+    wav.extend(BYTES_PER_SAMPLE.to_le_bytes());
+    wav.extend((BYTES_PER_SAMPLE * 8).to_le_bytes());
+    wav.extend(b"data");
+    wav.extend(data_len.to_le_bytes());
+    wav.extend(samples.iter().flat_map(|sample| sample.to_le_bytes()));
+    wav
+}
+
+/// A sound of `seconds` of the waveform `f`, see [`synthesize`]
+fn load_generated_sound(
+    audio: &RaylibAudio,
+    seconds: f32,
+    f: impl Fn(f32) -> f32,
+) -> Result<Sound<'_>, Error> {
+    let wave = audio.new_wave_from_memory(".wav", &encode_wav(&synthesize(seconds, f)))?;
+    audio.new_sound_from_wave(&wave)
+}
+
+/// Sounds, which are kept apart from [`Resources`] because they borrow the audio device
+#[derive(Debug)]
+pub struct AudioResources<'aud> {
+    /// One second loop, played while reactors are nearby
+    pub reactor_hum: Sound<'aud>,
+    pub click: Sound<'aud>,
+    pub error: Sound<'aud>,
+}
+
+impl<'aud> AudioResources<'aud> {
+    pub fn new(audio: &'aud RaylibAudio) -> Result<Self, Error> {
+        use std::f32::consts::TAU;
+
+        Ok(Self {
+            // Whole numbers of cycles per second, so that it loops without a pop
+            reactor_hum: load_generated_sound(audio, 1.0, |t| {
+                0.5 * (TAU * 60.0 * t).sin()
+                    + 0.3 * (TAU * 120.0 * t).sin()
+                    + 0.1 * (TAU * 180.0 * t).sin()
+            })?,
+            click: load_generated_sound(audio, 0.03, |t| {
+                (TAU * 1500.0 * t).sin() * (-150.0 * t).exp()
+            })?,
+            error: load_generated_sound(audio, 0.2, |t| {
+                (TAU * 220.0 * t).sin().signum() * 0.4 * (-10.0 * t).exp()
+            })?,
+        })
+    }
+}
@@ -579,3 +579,42 @@ impl<D: RaylibDraw3D> DynRaylibDraw3D for D {
         self.draw_model_points_ex(model, position, rotation_axis, angle, scale, tint);
     }
 }
+
+/// Lets the engine's 3D [`Renderer`](engine::draw3d::Renderer) draw through a [`DynRaylibDraw3D`]
+pub struct DynRender<'a>(pub &'a mut dyn DynRaylibDraw3D);
+
+impl engine::draw3d::Render for DynRender<'_> {
+    fn draw_line(
+        &mut self,
+        start_pos: Vector3,
+        end_pos: Vector3,
+        thick: Option<f32>,
+        color: Color,
+    ) {
+        match thick {
+            Some(thick) => self
+                .0
+                .draw_capsule(start_pos, end_pos, thick * 0.5, 10, 5, color),
+            None => self.0.draw_line3D(start_pos, end_pos, color),
+        }
+    }
+
+    fn draw_triangle(&mut self, &[v1, v2, v3]: &[Vector3; 3], color: Color) {
+        self.0.draw_triangle3D(v1, v2, v3, color);
+    }
+
+    fn draw_mesh(&mut self, mesh: &Mesh, material: &Material, transform: &Matrix) {
+        self.0
+            .draw_mesh(*mesh.as_ref(), *material.as_ref(), *transform);
+    }
+
+    fn draw_ghost(&mut self, mesh: &Mesh, transform: &Matrix, color: Color) {
+        engine::draw3d::ghost_pass(transform, color, |material, transform| {
+            self.0.draw_mesh(*mesh.as_ref(), *material, transform);
+        });
+    }
+
+    fn draw(&mut self, args: engine::draw3d::Arguments<'_>) -> engine::draw::Result {
+        engine::draw3d::render(self, args)
+    }
+}
@@ -0,0 +1,164 @@
+//! Random numbers that come out the same on every machine
+//!
+//! Gameplay randomness is drawn from an [`Rng`] instead of floats, so the same world seed makes
+//! the same game in saves, replays, and every player's copy of a multiplayer session. Each system
+//! has its own [`Stream`] derived from the world seed, so drawing more numbers in one doesn't
+//! change what any other gets.
+
+use fixed_point::Q32_32;
+use std::ops::Range;
+
+/// Mix `x` into a number that looks random, with every bit depending on every other
+///
+/// This is the output step of `SplitMix64`.
+const fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// A system with randomness of its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stream {
+    Terrain,
+    Contracts,
+    /// What's measured at the lab's benches
+    Measurements,
+}
+
+/// A `xoshiro256**` generator
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    /// A generator that always makes the same numbers for the same `seed`
+    pub const fn new(seed: u64) -> Self {
+        // Spread the seed over the whole state with SplitMix64, which never makes it all zeroes
+        let mut state = [0; 4];
+        let mut x = seed;
+        let mut i = 0;
+        while i < state.len() {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            state[i] = mix(x);
+            i += 1;
+        }
+        Self { state }
+    }
+
+    /// The generator for `stream` in the world made from `seed`
+    pub const fn stream(seed: u64, stream: Stream) -> Self {
+        Self::new(seed ^ mix(stream as u64 + 1))
+    }
+
+    /// A generator for the `index`th of something, independent of this one and of every other
+    /// index, without drawing anything from this one
+    #[must_use]
+    pub const fn split(&self, index: u64) -> Self {
+        let [a, _, c, _] = self.state;
+        Self::new(mix(a ^ c.rotate_left(32)) ^ mix(index))
+    }
+
+    pub const fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = *s1 << 17;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+        result
+    }
+
+    pub const fn next_u32(&mut self) -> u32 {
+        // The top bits are the most random
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// A number in `[0, 1)`
+    pub const fn unit(&mut self) -> Q32_32 {
+        Q32_32::from_bits(self.next_u32() as i64)
+    }
+
+    /// A number in `range`, or its start if it's empty
+    pub const fn range(&mut self, range: Range<Q32_32>) -> Q32_32 {
+        if range.end.less_or_equal(range.start) {
+            return range.start;
+        }
+        range.start.lerp(range.end, self.unit())
+    }
+
+    /// A whole number in `[0, n)`, each as likely as the others
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero
+    pub const fn below(&mut self, n: u32) -> u32 {
+        assert!(n != 0, "there are no numbers below zero to pick from");
+        // Lemire's method, retrying the few numbers that would make some results more likely
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let m = self.next_u32() as u64 * n as u64;
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "only the low bits are wanted"
+            )]
+            if m as u32 >= threshold {
+                return (m >> 32) as u32;
+            }
+        }
+    }
+
+    /// One of `items`, each as likely as the others, or `None` if there aren't any
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        let len = u32::try_from(items.len()).unwrap_or(u32::MAX);
+        (len != 0).then(|| &items[self.below(len) as usize])
+    }
+
+    /// `true` with a probability of `p`
+    pub const fn chance(&mut self, p: Q32_32) -> bool {
+        self.unit().less_than(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        let mut rng = Rng::new(42);
+        assert_eq!(
+            [rng.next_u64(), rng.next_u64()],
+            [0x1578_0b2e_0c2e_c716, 0x6104_d986_6d11_3a7e],
+            "the same numbers on every platform"
+        );
+        let draw = |mut rng: Rng| std::array::from_fn::<_, 8, _>(|_| rng.next_u64());
+        assert_eq!(draw(Rng::new(7)), draw(Rng::new(7)));
+        assert_ne!(
+            draw(Rng::stream(7, Stream::Terrain)),
+            draw(Rng::stream(7, Stream::Contracts))
+        );
+        let base = Rng::new(7);
+        assert_ne!(draw(base.split(0)), draw(base.split(1)));
+        assert_eq!(base, Rng::new(7), "splitting doesn't draw anything");
+
+        let (min, max) = (Q32_32::from_i32(-3), Q32_32::from_i32(5));
+        let mut counts = [0; 6];
+        for _ in 0..6000 {
+            assert!((min..max).contains(&rng.range(min..max)));
+            counts[rng.below(6) as usize] += 1;
+        }
+        assert!(
+            counts.iter().all(|&count| (800..1200).contains(&count)),
+            "{counts:?} should be about even"
+        );
+        assert_eq!(rng.range(max..min), max, "empty");
+        assert_eq!(rng.pick::<u8>(&[]), None);
+        assert!(!rng.chance(Q32_32::ZERO));
+        assert!(rng.chance(Q32_32::ONE));
+    }
+}
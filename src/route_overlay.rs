@@ -0,0 +1,276 @@
+//! Where things are carried, drawn over the world to see why they aren't getting there
+//!
+//! Belts are drawn with arrows the way items flow on them, gray when empty and red when they're
+//! full and backed up. Pipes are drawn in the color of the fluid in them. Outside, each train's
+//! path to the end of the track it's heading for is drawn above the rails, with the stops along
+//! the way. Each block of track between signals is drawn just over the rails, red if a train is
+//! in it, orange if one has only reserved it, and green if it's free.
+//!
+//! Machines are outlined too, with a post on each of their connection points: orange for belt
+//! inputs, green for belt outputs, and blue for pipes.
+
+use crate::{
+    container::{FluidVolume, Molecule},
+    game::GameState,
+    math::coords::{FactoryVector3, PlayerVector3, VectorConstants},
+    palette::palette,
+    region::{
+        RegionId,
+        factory::{Belt, Factory, Machine, Pipe, block_center},
+        rail::network::{RailNetwork, Track, Train},
+    },
+    rl_helpers::{DynRaylibDraw3D, DynRender},
+};
+use engine::{
+    draw,
+    draw3d::{self, DebugVis},
+};
+use raylib::prelude::*;
+
+/// Length of each arrow, in meters
+const ARROW_SIZE: f32 = 0.3;
+/// How high above the rails train paths are drawn, so they clear the trains
+const TRAIN_PATH_HEIGHT: f32 = 3.0;
+/// Meters between arrows on train paths
+const TRAIN_ARROW_SPACING: f32 = 4.0;
+/// How high above the rails blocks are drawn
+const BLOCK_HEIGHT: f32 = 0.5;
+
+/// Draw a line through `points`, with arrows along it pointing toward the last one
+///
+/// Each segment gets at least one arrow, and one every `spacing` meters if it's longer.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss,
+    reason = "segments are much shorter than 2^24 meters"
+)]
+fn draw_flow(
+    d: &mut draw3d::Renderer<'_>,
+    points: &[Vector3],
+    spacing: f32,
+    color: Color,
+) -> draw::Result {
+    d.draw_line_strip(points, None, color)?;
+    for pair in points.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let Some(direction) = (end - start).try_normalize() else {
+            continue;
+        };
+        // Vertical segments have no horizontal side, so spread their arrows along x instead
+        let side = direction
+            .cross(Vector3::UP)
+            .try_normalize()
+            .unwrap_or(Vector3::X)
+            * (0.5 * ARROW_SIZE);
+        let count = (start.distance(end) / spacing).ceil().max(1.0) as usize;
+        for i in 0..count {
+            let t = (i as f32 + 0.5) / count as f32;
+            let tip = start.lerp(end, t) + direction * (0.5 * ARROW_SIZE);
+            let back = tip - direction * ARROW_SIZE;
+            d.draw_line(tip, back + side, None, color)?;
+            d.draw_line(tip, back - side, None, color)?;
+        }
+    }
+    Ok(())
+}
+
+/// The color a fluid is drawn in
+///
+/// Each element steps around the color wheel by the golden angle, like
+/// [`ItemVisual::element`](crate::item_visual::ItemVisual::element), so different fluids rarely
+/// end up looking alike.
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "only the hue matters, which wraps around anyway"
+)]
+fn fluid_color(molecule: &Molecule) -> Color {
+    let steps = (molecule.element_counts().into_iter())
+        .map(|(element, count)| u64::from(element.protons().get()) * u64::from(count))
+        .sum::<u64>();
+    let hue = (steps as f32 * 137.508) % 360.0;
+    Color::color_from_hsv(hue, 0.7, 0.95)
+}
+
+/// A belt, and how many items are on it
+struct BeltRoute<'a> {
+    belt: &'a Belt,
+    items: u32,
+}
+
+/// Draws the belt with arrows the way items flow, in factory coordinates
+impl DebugVis for BeltRoute<'_> {
+    fn draw(&self, d: &mut draw3d::Renderer<'_>) -> draw::Result {
+        let color = match self.items {
+            0 => Color::GRAY,
+            items if items >= self.belt.capacity() => palette().invalid,
+            _ => Color::YELLOW,
+        };
+        let points = (self.belt.path.iter().copied())
+            .map(block_center)
+            .collect::<Vec<_>>();
+        draw_flow(d, &points, 1.0, color)
+    }
+}
+
+/// A pipe, and the fluid in it
+struct PipeRoute<'a> {
+    pipe: &'a Pipe,
+    fluid: Option<&'a FluidVolume>,
+}
+
+/// Draws the pipe in the color of its fluid, with arrows the way it flows, in factory coordinates
+impl DebugVis for PipeRoute<'_> {
+    fn draw(&self, d: &mut draw3d::Renderer<'_>) -> draw::Result {
+        let color = self
+            .fluid
+            .map_or(Color::GRAY, |fluid| fluid_color(&fluid.molecule));
+        let points = (self.pipe.path.iter().copied())
+            .map(block_center)
+            .collect::<Vec<_>>();
+        draw_flow(d, &points, 1.0, color)
+    }
+}
+
+/// A train, and the track it runs on
+struct TrainRoute<'a> {
+    train: &'a Train,
+    track: &'a Track,
+    /// Where the path is drawn relative to
+    player_pos: PlayerVector3,
+}
+
+impl TrainRoute<'_> {
+    /// Where `position` is drawn, raised above the rails
+    fn lift(&self, position: PlayerVector3) -> Vector3 {
+        position.minus(self.player_pos).to_vec3() + Vector3::UP * TRAIN_PATH_HEIGHT
+    }
+}
+
+/// Draws the path to the end of the track the train is heading for, relative to the player
+impl DebugVis for TrainRoute<'_> {
+    fn draw(&self, d: &mut draw3d::Renderer<'_>) -> draw::Result {
+        let end = if self.train.forward {
+            self.track.length()
+        } else {
+            0.0
+        };
+        let path = self.track.path_between(self.train.distance, end);
+        let points = path.into_iter().map(|position| self.lift(position));
+        let points = points.collect::<Vec<_>>();
+        draw_flow(d, &points, TRAIN_ARROW_SPACING, Color::SKYBLUE)
+    }
+}
+
+/// Draws the routes belts, pipes, and trains carry things along, while it's visible
+#[derive(Debug, Default)]
+pub struct RouteOverlay {
+    pub is_visible: bool,
+}
+
+impl RouteOverlay {
+    /// Draw every machine, belt, and pipe in `factory`, in factory coordinates
+    fn draw_factory(d: &mut draw3d::Renderer<'_>, factory: &Factory) -> draw::Result {
+        for (_, machine) in factory.machines() {
+            DebugVis::draw(machine as &dyn Machine, d)?;
+        }
+        for (belt, items) in factory.belt_loads() {
+            BeltRoute { belt, items }.draw(d)?;
+        }
+        for (pipe, fluid) in factory.pipe_contents() {
+            PipeRoute { pipe, fluid }.draw(d)?;
+        }
+        Ok(())
+    }
+
+    /// Draw each block of track in the color of whether it's occupied, relative to `player_pos`
+    fn draw_blocks(
+        d: &mut draw3d::Renderer<'_>,
+        rail: &RailNetwork,
+        player_pos: PlayerVector3,
+    ) -> draw::Result {
+        let blocks = rail.blocks();
+        let occupied = (rail.trains().iter())
+            .map(|train| blocks.block_at(train.track, train.distance))
+            .collect::<Vec<_>>();
+        let palette = palette();
+        for (idx, track) in rail.tracks().iter().enumerate() {
+            for (block, start, end) in blocks.spans(idx, track.length()) {
+                let color = if occupied.contains(&block) {
+                    palette.invalid
+                } else if blocks.holder(block).is_some() {
+                    palette.warning
+                } else {
+                    palette.valid
+                };
+                let points = (track.path_between(start, end).into_iter())
+                    .map(|position| {
+                        position.minus(player_pos).to_vec3() + Vector3::UP * BLOCK_HEIGHT
+                    })
+                    .collect::<Vec<_>>();
+                d.draw_line_strip(&points, None, color)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw every train's path, a post at each stop, and the blocks between signals, relative to
+    /// `player_pos`
+    fn draw_rail(
+        d: &mut draw3d::Renderer<'_>,
+        rail: &RailNetwork,
+        player_pos: PlayerVector3,
+    ) -> draw::Result {
+        for train in rail.trains() {
+            let Some(track) = rail.tracks().get(train.track) else {
+                continue;
+            };
+            TrainRoute {
+                train,
+                track,
+                player_pos,
+            }
+            .draw(d)?;
+        }
+        for stop in rail.stops() {
+            let Some(track) = rail.tracks().get(stop.track) else {
+                continue;
+            };
+            let base = track.position_at(stop.distance).minus(player_pos).to_vec3();
+            d.draw_line(
+                base,
+                base + Vector3::UP * TRAIN_PATH_HEIGHT,
+                None,
+                Color::WHITE,
+            )?;
+        }
+        Self::draw_blocks(d, rail, player_pos)
+    }
+
+    /// Draw the routes in the region the player is in, in the 3D view
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D, state: &GameState) {
+        if !self.is_visible {
+            return;
+        }
+        let player_pos = state.player.position;
+        let mut buf = DynRender(d);
+        // Can't fail when drawing directly to the screen
+        match state.current_region {
+            RegionId::Factory(idx) => {
+                let factory = &state.factories[idx];
+                let mut d = draw3d::Renderer::new(
+                    &mut buf,
+                    *draw3d::RenderingOptions::new().offset(
+                        FactoryVector3::ZERO.to_player_relative(&player_pos, &factory.origin),
+                    ),
+                );
+                _ = Self::draw_factory(&mut d, factory);
+            }
+            RegionId::Rail => {
+                let mut d = draw3d::Renderer::new(&mut buf, draw3d::RenderingOptions::new());
+                _ = Self::draw_rail(&mut d, &state.world.rail, player_pos);
+            }
+            RegionId::Lab => {}
+        }
+    }
+}
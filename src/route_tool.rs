@@ -0,0 +1,259 @@
+use crate::{
+    game::GameState,
+    input::{EventInput, Inputs},
+    locale::tr,
+    math::{
+        bounds::PlayerBounds,
+        coords::{FactoryVector3, PlayerCoord, PlayerVector3, VectorConstants},
+    },
+    palette::palette,
+    player::Player,
+    region::{
+        RegionId,
+        factory::{
+            Belt, BeltInputNode, BeltOutputNode, Factory, Machine, Pipe, PipeNode, block_center,
+            history::Change,
+            routing::{self, RouteRules},
+        },
+    },
+    rl_helpers::{DynRaylibDraw3D, DynRender},
+};
+use engine::{draw, draw3d};
+use raylib::prelude::*;
+
+/// Somewhere on a machine a belt or pipe can connect to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Port {
+    BeltInput(BeltInputNode),
+    BeltOutput(BeltOutputNode),
+    Pipe(PipeNode),
+}
+
+impl Port {
+    /// The block the port is in
+    #[must_use]
+    pub const fn position(self) -> FactoryVector3 {
+        match self {
+            Self::BeltInput(BeltInputNode(node)) | Self::BeltOutput(BeltOutputNode(node)) => {
+                node.position
+            }
+            Self::Pipe(node) => node.position,
+        }
+    }
+
+    /// Whether a route can start at this port
+    #[must_use]
+    pub const fn is_source(self) -> bool {
+        matches!(self, Self::BeltOutput(_) | Self::Pipe(_))
+    }
+
+    /// Whether a route starting at `self` can end at `end`
+    ///
+    /// Belts go from outputs to inputs, and pipes go between any two pipe nodes.
+    #[must_use]
+    pub fn can_connect(self, end: Self) -> bool {
+        match (self, end) {
+            (Self::BeltOutput(_), Self::BeltInput(_)) => true,
+            (Self::Pipe(a), Self::Pipe(b)) => a != b,
+            _ => false,
+        }
+    }
+
+    /// Where a route starting at `self` is allowed to go
+    #[must_use]
+    pub const fn rules(self) -> RouteRules {
+        match self {
+            Self::BeltInput(_) | Self::BeltOutput(_) => RouteRules::BELT,
+            Self::Pipe(_) => RouteRules::PIPE,
+        }
+    }
+
+    /// Every port on every machine in `factory`
+    fn all(factory: &Factory) -> impl Iterator<Item = Self> + '_ {
+        // todo: other machines
+        factory.reactors().iter().flat_map(|reactor| {
+            (reactor.belt_inputs().into_iter().map(Self::BeltInput))
+                .chain(reactor.belt_outputs().into_iter().map(Self::BeltOutput))
+                .chain(reactor.pipe_nodes().into_iter().map(Self::Pipe))
+        })
+    }
+
+    /// The port in `factory` the player is looking at, if one is within [`Player::REACH`]
+    #[must_use]
+    pub fn looked_at(factory: &Factory, player: &Player) -> Option<Self> {
+        let eye = player.eye_pos();
+        let direction = PlayerVector3::from_vec3(player.vision_ray().direction);
+        let reach = PlayerCoord::from_f32(Player::REACH);
+        Self::all(factory)
+            .filter_map(|port| {
+                let position = port.position();
+                let block = PlayerBounds {
+                    min: position.to_player(&factory.origin),
+                    max: (position + FactoryVector3::ONE).to_player(&factory.origin),
+                };
+                eye.ray_intersect_aabb(direction, &block)
+                    .filter(|&distance| distance <= reach)
+                    .map(|distance| (distance, port))
+            })
+            .min_by_key(|&(distance, _)| distance)
+            .map(|(_, port)| port)
+    }
+}
+
+/// Connects machines with belts and pipes, finding the way between them automatically
+///
+/// Interacting with a belt output or pipe starts a route. Looking at where it should end shows
+/// where it would go, and interacting again builds it.
+#[derive(Debug, Default)]
+pub struct RouteTool {
+    pub is_active: bool,
+    /// The index of the factory the route is in, and where the route starts
+    start: Option<(usize, Port)>,
+    /// The port the player is looking at
+    target: Option<Port>,
+    /// Where the route would end and the way there, if there is one
+    ///
+    /// Only found again when the player looks at a different port.
+    preview: Option<(Port, Option<Vec<FactoryVector3>>)>,
+}
+
+impl RouteTool {
+    /// Forget the route being planned
+    pub fn cancel(&mut self) {
+        self.start = None;
+        self.target = None;
+        self.preview = None;
+    }
+
+    /// Find the previewed route again, since the factory has changed under it
+    pub fn recheck(&mut self) {
+        self.preview = None;
+    }
+
+    /// Toggle the tool, and start, preview, or build a route
+    pub fn update(&mut self, inputs: &Inputs, state: &mut GameState) {
+        if inputs[EventInput::ToggleRouteTool] {
+            self.is_active = !self.is_active;
+            self.cancel();
+        }
+        let RegionId::Factory(idx) = state.current_region else {
+            self.cancel();
+            return;
+        };
+        if !self.is_active {
+            return;
+        }
+        if self.start.is_some_and(|(factory, _)| factory != idx) {
+            self.cancel();
+        }
+        let factory = &state.factories[idx];
+        self.target = Port::looked_at(factory, &state.player);
+
+        let Some((_, start)) = self.start else {
+            if inputs[EventInput::Interact]
+                && let Some(target) = self.target.filter(|target| target.is_source())
+            {
+                self.start = Some((idx, target));
+            }
+            return;
+        };
+
+        match self.target.filter(|&target| start.can_connect(target)) {
+            Some(end) if self.preview.as_ref().is_none_or(|(port, _)| *port != end) => {
+                let path =
+                    routing::find_route(factory, start.position(), end.position(), start.rules());
+                self.preview = Some((end, path));
+            }
+            Some(_) => {}
+            None => self.preview = None,
+        }
+
+        if inputs[EventInput::Interact] {
+            // Interacting without a route to build cancels it
+            if let Some((end, Some(path))) = self.preview.take() {
+                let level = state.research.best_belt();
+                let factory = &mut state.factories[idx];
+                let entity = match (start, end) {
+                    (Port::BeltOutput(src), Port::BeltInput(dst)) => factory.add_belt(Belt {
+                        level,
+                        src,
+                        dst,
+                        path,
+                    }),
+                    (Port::Pipe(a), Port::Pipe(b)) => factory.add_pipe(Pipe { a, b, path }),
+                    _ => unreachable!("only ports that can connect are previewed"),
+                };
+                state.history.record(
+                    idx,
+                    Change::Built {
+                        entities: vec![entity],
+                        ladders: Vec::new(),
+                    },
+                );
+            }
+            self.cancel();
+        }
+    }
+
+    /// Tells the player what to do next, or [`None`] if the tool isn't in use
+    #[must_use]
+    pub fn prompt(&self) -> Option<String> {
+        if !self.is_active {
+            return None;
+        }
+        Some(tr!(match (self.start, &self.preview) {
+            (None, _) if self.target.is_some_and(Port::is_source) => "route_tool.start",
+            (None, _) => "route_tool.find_start",
+            (Some(_), Some((_, Some(_)))) => "route_tool.build",
+            (Some(_), Some((_, None))) => "route_tool.no_path",
+            (Some((_, Port::Pipe(_))), None) => "route_tool.find_pipe",
+            (Some(_), None) => "route_tool.find_belt",
+        }))
+    }
+
+    /// Draw the route being planned, in factory coordinates
+    fn draw_preview(&self, d: &mut draw3d::Renderer<'_>) -> draw::Result {
+        let block = |position: FactoryVector3| BoundingBox {
+            min: position.as_vec3(),
+            max: (position + FactoryVector3::ONE).as_vec3(),
+        };
+        if let Some((_, start)) = self.start {
+            d.draw_bounding_box(block(start.position()), palette().highlight)?;
+        }
+        match (self.start, &self.preview) {
+            (Some(_), Some((_, Some(path)))) => {
+                for cell in routing::route_cells(path) {
+                    d.draw_bounding_box(block(cell), palette().valid)?;
+                }
+                let path = path.iter().copied().map(block_center).collect::<Vec<_>>();
+                d.draw_line_strip(&path, None, Color::WHITE)
+            }
+            (Some((_, start)), Some((end, None))) => {
+                d.draw_bounding_box(block(end.position()), palette().invalid)?;
+                d.draw_line(
+                    block_center(start.position()),
+                    block_center(end.position()),
+                    None,
+                    palette().invalid,
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Draw the route being planned in the 3D view
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D, state: &GameState) {
+        let Some((idx, _)) = self.start else {
+            return;
+        };
+        let origin = &state.factories[idx].origin;
+        let mut buf = DynRender(d);
+        let mut d = draw3d::Renderer::new(
+            &mut buf,
+            *draw3d::RenderingOptions::new()
+                .offset(FactoryVector3::ZERO.to_player_relative(&state.player.position, origin)),
+        );
+        // Can't fail when drawing directly to the screen
+        _ = self.draw_preview(&mut d);
+    }
+}
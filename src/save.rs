@@ -0,0 +1,508 @@
+//! Saving the game to disk, so that a crash can't lose more than the last few minutes
+//!
+//! A [`SaveSlot`] is a folder with the save, a few older backups of it, and a thumbnail. Saves
+//! are written to a temporary file and renamed over the last one, so the save in the slot is
+//! always either the old one or the new one, and never half of each. The save ends with an
+//! `end` line, so one that was cut short is noticed rather than loaded as it is:
+//!
+//! ```text
+//! save 1
+//! playtime 5400
+//! clock 3 18.5 72
+//! weather rain clear 12 0.5
+//! research 120
+//! unlocked basics belt-mk2
+//! milestones first-ore
+//! discovered Fe₂O₃ H₂O
+//! credits 300
+//! end
+//! ```
+//!
+//! Only what can already be written as text is saved so far. Factories, trains, and the player
+//! start where a new game puts them.
+
+pub mod panel;
+
+use crate::{
+    compendium::Compendium, game::GameState, milestone::Milestones, research::Research, settings,
+    time::WorldClock, weather::Weather,
+};
+use raylib::prelude::*;
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::SystemTime,
+};
+
+/// Folder in [`settings::app_dir`] that slots are kept in
+const SAVES_DIR: &str = "saves";
+
+/// Name of the save in a slot
+const FILE_NAME: &str = "world.txt";
+
+/// Name of the slot used until there's a way to pick one
+const DEFAULT_SLOT: &str = "world";
+
+/// The version written at the top of saves, which is raised when older games can't read them
+const VERSION: u32 = 1;
+
+/// Everything saved about a game
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveData {
+    /// See [`GameState::playtime`]
+    pub playtime: f64,
+    pub clock: WorldClock,
+    pub weather: Weather,
+    pub research: Research,
+    pub milestones: Milestones,
+    pub compendium: Compendium,
+    pub credits: u64,
+}
+
+impl SaveData {
+    /// What would be saved of `state`
+    pub fn new(state: &GameState) -> Self {
+        Self {
+            playtime: state.playtime,
+            clock: state.clock,
+            weather: state.weather,
+            research: state.research.clone(),
+            milestones: state.milestones.clone(),
+            compendium: state.compendium.clone(),
+            credits: state.credits,
+        }
+    }
+
+    /// Put the saved game back into `state`
+    pub fn restore(self, state: &mut GameState) {
+        state.playtime = self.playtime;
+        state.clock = self.clock;
+        state.weather = self.weather;
+        state.research = self.research;
+        state.milestones = self.milestones;
+        state.compendium = self.compendium;
+        state.credits = self.credits;
+    }
+}
+
+impl std::fmt::Display for SaveData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "save {VERSION}")?;
+        writeln!(f, "playtime {}", self.playtime)?;
+        write!(
+            f,
+            "{}{}{}{}{}",
+            self.clock, self.weather, self.research, self.milestones, self.compendium
+        )?;
+        writeln!(f, "credits {}", self.credits)?;
+        writeln!(f, "end")
+    }
+}
+
+/// Why text couldn't be read as [`SaveData`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseSaveError {
+    /// Doesn't start with `save` and the version
+    MissingHeader,
+    /// Written by a newer version of the game
+    UnsupportedVersion(u32),
+    /// The line, counting from 1, isn't part of a save or couldn't be read
+    InvalidLine(usize),
+    /// Is missing something, or stops before the `end` line like when the game crashed while
+    /// writing it
+    Incomplete,
+}
+
+impl std::fmt::Display for ParseSaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader => f.write_str("not a save"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "saved by a newer version of the game ({version})")
+            }
+            Self::InvalidLine(line) => write!(f, "line {line} couldn't be read"),
+            Self::Incomplete => f.write_str("the save was cut short"),
+        }
+    }
+}
+
+impl std::error::Error for ParseSaveError {}
+
+impl FromStr for SaveData {
+    type Err = ParseSaveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate().peekable();
+        let version = (lines.next())
+            .and_then(|(_, line)| line.strip_prefix("save "))
+            .and_then(|version| version.trim().parse().ok())
+            .ok_or(ParseSaveError::MissingHeader)?;
+        if version > VERSION {
+            return Err(ParseSaveError::UnsupportedVersion(version));
+        }
+
+        let (mut playtime, mut clock, mut weather) = (None, None, None);
+        let (mut research, mut milestones, mut credits) = (None, None, None);
+        let mut compendium = None;
+        let mut ended = false;
+        while let Some((idx, line)) = lines.next() {
+            let invalid = || ParseSaveError::InvalidLine(idx + 1);
+            let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match word {
+                "playtime" => {
+                    let seconds = rest.parse::<f64>().ok().filter(|t| t.is_finite());
+                    playtime = Some(seconds.ok_or_else(invalid)?);
+                }
+                "clock" => clock = Some(line.parse().map_err(|_| invalid())?),
+                "weather" => weather = Some(line.parse().map_err(|_| invalid())?),
+                "research" => {
+                    // The unlocked techs are on the next line
+                    let mut text = line.to_string();
+                    if let Some((_, unlocked)) =
+                        lines.next_if(|(_, next)| next.starts_with("unlocked"))
+                    {
+                        _ = write!(text, "\n{unlocked}");
+                    }
+                    research = Some(text.parse().map_err(|_| invalid())?);
+                }
+                "milestones" => milestones = Some(line.parse().map_err(|_| invalid())?),
+                "discovered" => compendium = Some(line.parse().map_err(|_| invalid())?),
+                "credits" => credits = Some(rest.parse().map_err(|_| invalid())?),
+                "end" => {
+                    ended = true;
+                    break;
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        let incomplete = || ParseSaveError::Incomplete;
+        if !ended {
+            return Err(incomplete());
+        }
+        Ok(Self {
+            playtime: playtime.ok_or_else(incomplete)?,
+            clock: clock.ok_or_else(incomplete)?,
+            weather: weather.ok_or_else(incomplete)?,
+            research: research.ok_or_else(incomplete)?,
+            milestones: milestones.ok_or_else(incomplete)?,
+            // Saves from before the spectrometer haven't discovered anything
+            compendium: compendium.unwrap_or_default(),
+            credits: credits.ok_or_else(incomplete)?,
+        })
+    }
+}
+
+/// What's shown about a save before loading it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveInfo {
+    pub path: PathBuf,
+    /// See [`GameState::playtime`]
+    pub playtime: f64,
+    /// When the file was written, if the platform keeps track
+    pub saved: Option<SystemTime>,
+}
+
+/// Why a [`SaveSlot`] couldn't be loaded
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    /// The save can't be read, or is missing even though there are backups of it
+    Damaged,
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::Damaged => f.write_str("the save is damaged"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Damaged => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Write `contents` to `path` without ever leaving it half written
+///
+/// They're written to `temp` first, then `temp` is renamed over `path` once it's all on the
+/// disk. If `backups` are given, whatever was at `path` is moved to the first of them first,
+/// and each older backup to the next, with the last one dropped.
+fn write_atomic(path: &Path, temp: &Path, contents: &[u8], backups: &[PathBuf]) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    {
+        let mut file = fs::File::create(temp)?;
+        io::Write::write_all(&mut file, contents)?;
+        file.sync_all()?;
+    }
+    if path.exists() {
+        for pair in backups.windows(2).rev() {
+            if pair[0].exists() {
+                fs::rename(&pair[0], &pair[1])?;
+            }
+        }
+        if let Some(newest) = backups.first() {
+            fs::rename(path, newest)?;
+        }
+    }
+    fs::rename(temp, path)?;
+    sync_dir(dir);
+    Ok(())
+}
+
+/// Make sure renames in `dir` are on the disk, where the platform allows it
+fn sync_dir(dir: &Path) {
+    // Folders can't be opened as files on Windows, where renames are kept anyway
+    if cfg!(unix)
+        && let Ok(dir) = fs::File::open(dir)
+    {
+        _ = dir.sync_all();
+    }
+}
+
+/// A folder with a save in it, along with its backups and thumbnail
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveSlot {
+    dir: PathBuf,
+}
+
+impl SaveSlot {
+    /// How many older saves are kept besides the newest
+    pub const BACKUPS: usize = 3;
+
+    pub const fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// The slot in [`settings::app_dir`] the game saves to, if it could be found
+    pub fn default_slot() -> Option<Self> {
+        Some(Self::new(
+            settings::app_dir()?.join(SAVES_DIR).join(DEFAULT_SLOT),
+        ))
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(FILE_NAME)
+    }
+
+    /// Where a save is written before it replaces [`Self::path`]
+    fn temp_path(&self) -> PathBuf {
+        self.dir.join(format!("{FILE_NAME}.tmp"))
+    }
+
+    /// The older saves, newest first
+    fn backup_paths(&self) -> Vec<PathBuf> {
+        (1..=Self::BACKUPS)
+            .map(|n| self.dir.join(format!("world.{n}.txt")))
+            .collect()
+    }
+
+    /// A picture of the game when it was last saved
+    pub fn thumbnail_path(&self) -> PathBuf {
+        self.dir.join("thumbnail.png")
+    }
+
+    /// Save `data`, keeping the last save as a backup
+    ///
+    /// A save that can't be read isn't kept as a backup, so the backups stay worth going back
+    /// to.
+    pub fn save(&self, data: &SaveData) -> io::Result<()> {
+        let path = self.path();
+        if path.exists() && Self::read(&path).is_err() {
+            fs::remove_file(&path)?;
+        }
+        write_atomic(
+            &path,
+            &self.temp_path(),
+            data.to_string().as_bytes(),
+            &self.backup_paths(),
+        )
+    }
+
+    /// Save `image` as the slot's thumbnail, replacing the last one all at once
+    pub fn save_thumbnail(&self, image: &Image) -> io::Result<()> {
+        let path = self.thumbnail_path();
+        let temp = self.dir.join("thumbnail.tmp.png");
+        crate::capture::save(image, &temp)?;
+        fs::rename(temp, path)?;
+        sync_dir(&self.dir);
+        Ok(())
+    }
+
+    fn read(path: &Path) -> Result<SaveData, LoadError> {
+        let text = fs::read_to_string(path)?;
+        text.parse().map_err(|_| LoadError::Damaged)
+    }
+
+    /// The save in the slot, or `None` if nothing's been saved yet
+    ///
+    /// If the game stopped partway through saving, whichever save was whole is used and the
+    /// rest is cleaned up. If the save itself is damaged, the [backups](Self::backups) can be
+    /// gone back to instead.
+    pub fn load(&self) -> Result<Option<SaveData>, LoadError> {
+        let (path, temp) = (self.path(), self.temp_path());
+        if temp.exists() {
+            if !path.exists() && Self::read(&temp).is_ok() {
+                // Stopped between moving the last save to the backups and moving this one in
+                fs::rename(&temp, &path)?;
+            } else {
+                fs::remove_file(&temp)?;
+            }
+        }
+        match Self::read(&path) {
+            Ok(data) => Ok(Some(data)),
+            Err(LoadError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {
+                if self.backups().is_empty() {
+                    Ok(None)
+                } else {
+                    Err(LoadError::Damaged)
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Each backup that can be loaded, newest first
+    pub fn backups(&self) -> Vec<SaveInfo> {
+        (self.backup_paths().into_iter())
+            .filter_map(|path| {
+                let data = Self::read(&path).ok()?;
+                let saved = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+                Some(SaveInfo {
+                    path,
+                    playtime: data.playtime,
+                    saved,
+                })
+            })
+            .collect()
+    }
+
+    /// Go back to the backup at `path`, making it the save
+    pub fn restore(&self, path: &Path) -> Result<SaveData, LoadError> {
+        let data = Self::read(path)?;
+        write_atomic(
+            &self.path(),
+            &self.temp_path(),
+            data.to_string().as_bytes(),
+            &[],
+        )?;
+        Ok(data)
+    }
+}
+
+/// Saving every so often while the game runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Autosave {
+    /// Seconds between saves
+    pub interval: f32,
+    /// Seconds since the last save
+    elapsed: f32,
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self {
+            interval: 300.0,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl Autosave {
+    /// Count `dt` more seconds of playing, returning whether it's time to save
+    pub fn due(&mut self, dt: f32) -> bool {
+        self.elapsed += dt;
+        if self.elapsed < self.interval {
+            return false;
+        }
+        self.elapsed = 0.0;
+        true
+    }
+
+    /// Start counting again after saving some other way
+    pub const fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A slot in a new folder of its own
+    fn temp_slot(name: &str) -> SaveSlot {
+        let dir = std::env::temp_dir().join(format!("save-test-{name}-{}", std::process::id()));
+        _ = fs::remove_dir_all(&dir);
+        SaveSlot::new(dir)
+    }
+
+    #[test]
+    fn test_save_slot() {
+        let mut data = SaveData {
+            playtime: 90.5,
+            clock: WorldClock::default(),
+            weather: Weather::default(),
+            research: Research::default(),
+            milestones: Milestones::default(),
+            compendium: "discovered Fe2O3 H2O".parse().unwrap(),
+            credits: 300,
+        };
+        assert_eq!(data.to_string().parse(), Ok(data.clone()));
+        let text = data.to_string();
+        let cut = &text[..text.len() - "credits 300\nend\n".len()];
+        assert_eq!(cut.parse::<SaveData>(), Err(ParseSaveError::Incomplete));
+
+        let slot = temp_slot("rotate");
+        assert!(matches!(slot.load(), Ok(None)), "nothing saved yet");
+        for n in 0..5 {
+            data.credits = n;
+            slot.save(&data).unwrap();
+        }
+        assert_eq!(slot.load().unwrap().map(|data| data.credits), Some(4));
+        let backups = slot.backups();
+        assert_eq!(backups.len(), SaveSlot::BACKUPS, "the oldest are dropped");
+        let restored = slot.restore(&backups[0].path).unwrap();
+        assert_eq!(restored.credits, 3);
+
+        // As if the game stopped halfway through writing the save over the old one
+        fs::write(slot.path(), cut).unwrap();
+        assert!(matches!(slot.load(), Err(LoadError::Damaged)));
+        data.credits = 10;
+        slot.save(&data).unwrap();
+        assert_eq!(
+            SaveSlot::read(&slot.backup_paths()[0])
+                .map(|data| data.credits)
+                .ok(),
+            Some(3),
+            "the damaged save isn't kept as a backup"
+        );
+
+        // As if it stopped after moving the last save to the backups
+        fs::rename(slot.path(), slot.temp_path()).unwrap();
+        assert_eq!(slot.load().unwrap().map(|data| data.credits), Some(10));
+        assert!(!slot.temp_path().exists());
+        _ = fs::remove_dir_all(&slot.dir);
+    }
+
+    #[test]
+    fn test_autosave() {
+        let mut autosave = Autosave::default();
+        assert!(!autosave.due(200.0));
+        assert!(autosave.due(100.0));
+        assert!(!autosave.due(1.0));
+    }
+}
@@ -0,0 +1,192 @@
+//! The prompt shown at startup when the save couldn't be loaded
+
+use super::{SaveInfo, SaveSlot};
+use crate::{game::GameState, input::Inputs, locale::tr, ui::Panel};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer},
+};
+use raylib::prelude::*;
+use std::time::SystemTime;
+
+const MARGIN: f32 = 16.0;
+const FONT_SIZE: f32 = 20.0;
+const ROW_HEIGHT: f32 = FONT_SIZE + 8.0;
+const WIDTH: f32 = 640.0;
+/// Rows for the title and the explanation above the choices
+const HEADER_ROWS: usize = 2;
+
+const BACKGROUND: Color = Color::new(16, 16, 24, 230);
+const HOVERED: Color = Color::new(255, 255, 255, 32);
+const SELECTED: Color = Color::new(96, 160, 224, 255);
+const ERROR: Color = Color::new(224, 96, 64, 255);
+
+/// Whole minutes in `seconds`
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "games last much less than u64::MAX minutes"
+)]
+fn minutes(seconds: f64) -> u64 {
+    (seconds / 60.0).floor().max(0.0) as u64
+}
+
+/// How `backup` is described, with how long it had been played and how long ago it was saved
+fn describe(backup: &SaveInfo) -> String {
+    let played = minutes(backup.playtime);
+    let ago = (backup.saved).and_then(|saved| SystemTime::now().duration_since(saved).ok());
+    match ago {
+        Some(ago) => tr!("recovery.backup", played = played, ago = ago.as_secs() / 60),
+        None => tr!("recovery.backup_undated", played = played),
+    }
+}
+
+/// Going back to one of the backups, or starting over
+///
+/// Clicking a backup or pressing enter on it loads it, and escape or the last choice starts a new
+/// game, leaving the backups where they are.
+#[derive(Debug, Clone)]
+pub struct RecoveryPanel {
+    slot: SaveSlot,
+    backups: Vec<SaveInfo>,
+    /// Each backup, then starting over
+    choices: Vec<String>,
+    selected: usize,
+    /// The choice under the mouse cursor
+    hovered: Option<usize>,
+    /// Why the last backup couldn't be loaded
+    notice: Option<String>,
+    /// Screen-space area of the whole panel
+    rect: Rectangle,
+}
+
+impl RecoveryPanel {
+    pub fn new(rl: &RaylibHandle, slot: SaveSlot) -> Self {
+        let backups = slot.backups();
+        let choices = (backups.iter().map(describe))
+            .chain([tr!("recovery.new_game")])
+            .collect();
+        let mut panel = Self {
+            slot,
+            backups,
+            choices,
+            selected: 0,
+            hovered: None,
+            notice: None,
+            rect: Rectangle::default(),
+        };
+        panel.layout(rl);
+        panel
+    }
+
+    /// Center the panel on the screen, tall enough for every choice and the notice
+    #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+    fn layout(&mut self, rl: &RaylibHandle) {
+        let rows = HEADER_ROWS + self.choices.len() + 1;
+        let height = rows as f32 * ROW_HEIGHT + 2.0 * MARGIN;
+        self.rect = Rectangle::new(
+            0.5 * (rl.get_screen_width() as f32 - WIDTH),
+            0.5 * (rl.get_screen_height() as f32 - height),
+            WIDTH,
+            height,
+        );
+    }
+
+    /// Screen-space area of the `row`th row, counting from the title
+    #[allow(clippy::cast_precision_loss, reason = "there are only a few rows")]
+    fn row_rect(&self, row: usize) -> Rectangle {
+        Rectangle::new(
+            self.rect.x + MARGIN,
+            self.rect.y + MARGIN + row as f32 * ROW_HEIGHT,
+            WIDTH - 2.0 * MARGIN,
+            ROW_HEIGHT,
+        )
+    }
+
+    /// Load the backup at `idx`, or start over if it's past the backups, returning whether the
+    /// panel is done
+    fn choose(&mut self, idx: usize, state: &mut GameState) -> bool {
+        let Some(backup) = self.backups.get(idx) else {
+            return true;
+        };
+        match self.slot.restore(&backup.path) {
+            Ok(data) => {
+                data.restore(state);
+                true
+            }
+            Err(e) => {
+                self.notice = Some(tr!("recovery.failed", error = e));
+                false
+            }
+        }
+    }
+
+    fn draw_text(
+        &self,
+        d: &mut Renderer<'_>,
+        row: usize,
+        text: &str,
+        color: Color,
+    ) -> draw::Result {
+        let rect = self.row_rect(row);
+        d.draw_text(
+            text,
+            Vector2::new(rect.x + 8.0, rect.y + 0.5 * (rect.height - FONT_SIZE)),
+            FONT_SIZE,
+            0.0,
+            color,
+        )
+    }
+}
+
+impl Panel for RecoveryPanel {
+    /// Closes on escape, which starts a new game, or once a backup is loaded
+    fn update(&mut self, rl: &mut RaylibHandle, _inputs: &Inputs, state: &mut GameState) -> bool {
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            return false;
+        }
+        self.layout(rl);
+
+        if rl.is_key_pressed(KeyboardKey::KEY_DOWN) {
+            self.selected = (self.selected + 1).min(self.choices.len() - 1);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_UP) {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) && self.choose(self.selected, state) {
+            return false;
+        }
+        let mouse = rl.get_mouse_position();
+        self.hovered = (0..self.choices.len())
+            .find(|&idx| (self.row_rect(HEADER_ROWS + idx)).check_collision_point_rec(mouse));
+        if let Some(idx) = self.hovered
+            && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+        {
+            self.selected = idx;
+            return !self.choose(idx, state);
+        }
+        true
+    }
+}
+
+impl Draw for RecoveryPanel {
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        d.draw_rectangle(self.rect, BACKGROUND)?;
+        self.draw_text(d, 0, &tr!("recovery.title"), Color::WHITE)?;
+        self.draw_text(d, 1, &tr!("recovery.explanation"), Color::LIGHTGRAY)?;
+        for (idx, choice) in self.choices.iter().enumerate() {
+            let row = HEADER_ROWS + idx;
+            if idx == self.selected {
+                d.draw_rectangle(self.row_rect(row), SELECTED)?;
+            } else if self.hovered == Some(idx) {
+                d.draw_rectangle(self.row_rect(row), HOVERED)?;
+            }
+            self.draw_text(d, row, choice, Color::WHITE)?;
+        }
+        if let Some(notice) = &self.notice {
+            let row = HEADER_ROWS + self.choices.len();
+            self.draw_text(d, row, notice, ERROR)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,490 @@
+//! The player's preferences, kept between games
+//!
+//! [`Settings`] are loaded at startup from [`Settings::path`], edited in the
+//! [`SettingsPanel`](panel::SettingsPanel), and saved again when it closes. The file is a small
+//! subset of TOML: a `[section]` header for each page of the menu, then `key = value` lines.
+//! Per-input overrides go in their own sections after the rest, like `[controls.keys]`.
+
+pub mod panel;
+
+use crate::{
+    audio::Audio,
+    input::{AxisSource, Bindings, EventInput, InputMode, KeyStateExt, VectorInput, VectorSource},
+    locale::{self, tr},
+    palette::{self, ColorVision},
+    player::Player,
+};
+use raylib::prelude::{KeyboardKey::*, *};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Name of the folder in the platform's config folder that settings are saved in
+const APP_DIR: &str = "factory-train-game";
+
+/// Name of the settings file in [`APP_DIR`]
+const FILE_NAME: &str = "settings.toml";
+
+/// The game's folder in the platform's config folder, if it could be found
+///
+/// Settings and [saves](crate::save) are kept in it.
+pub fn app_dir() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).map(PathBuf::from);
+    let config = if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))
+    };
+    Some(config?.join(APP_DIR))
+}
+
+/// How far the camera turns for each pixel the mouse moves, at a sensitivity of 1
+const LOOK_PER_PIXEL: f32 = 0.001;
+
+/// Keys that inputs can be rebound to
+#[rustfmt::skip]
+pub const BINDABLE_KEYS: [KeyboardKey; 76] = [
+    KEY_A, KEY_B, KEY_C, KEY_D, KEY_E, KEY_F, KEY_G, KEY_H, KEY_I, KEY_J, KEY_K, KEY_L, KEY_M,
+    KEY_N, KEY_O, KEY_P, KEY_Q, KEY_R, KEY_S, KEY_T, KEY_U, KEY_V, KEY_W, KEY_X, KEY_Y, KEY_Z,
+    KEY_ZERO, KEY_ONE, KEY_TWO, KEY_THREE, KEY_FOUR, KEY_FIVE, KEY_SIX, KEY_SEVEN, KEY_EIGHT,
+    KEY_NINE, KEY_SPACE, KEY_ENTER, KEY_TAB, KEY_BACKSPACE, KEY_DELETE, KEY_INSERT, KEY_HOME,
+    KEY_END, KEY_PAGE_UP, KEY_PAGE_DOWN, KEY_F1, KEY_F2, KEY_F3, KEY_F4, KEY_F5, KEY_F6, KEY_F7,
+    KEY_F8, KEY_F9, KEY_F10, KEY_F11, KEY_LEFT_SHIFT, KEY_LEFT_CONTROL, KEY_LEFT_ALT,
+    KEY_RIGHT_SHIFT, KEY_RIGHT_CONTROL, KEY_RIGHT_ALT, KEY_GRAVE, KEY_MINUS, KEY_EQUAL,
+    KEY_LEFT_BRACKET, KEY_RIGHT_BRACKET, KEY_SEMICOLON, KEY_APOSTROPHE, KEY_BACKSLASH, KEY_COMMA,
+    KEY_PERIOD, KEY_SLASH, KEY_CAPS_LOCK, KEY_PAUSE,
+];
+
+/// What `key` is called in menus and the settings file, like `F2` for [`KEY_F2`]
+pub fn key_name(key: KeyboardKey) -> String {
+    let name = format!("{key:?}");
+    name.strip_prefix("KEY_").unwrap_or(&name).to_string()
+}
+
+/// The [bindable](BINDABLE_KEYS) key called `name`
+pub fn key_from_name(name: &str) -> Option<KeyboardKey> {
+    BINDABLE_KEYS
+        .into_iter()
+        .find(|&key| key_name(key).eq_ignore_ascii_case(name))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WindowMode {
+    Windowed,
+    Fullscreen,
+}
+
+impl WindowMode {
+    pub const ALL: [Self; 2] = [Self::Windowed, Self::Fullscreen];
+
+    /// What the mode is called in the settings file
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Windowed => "windowed",
+            Self::Fullscreen => "fullscreen",
+        }
+    }
+}
+
+/// A group of settings, shown as a page of the menu and a section of the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Page {
+    General,
+    Graphics,
+    Audio,
+    Controls,
+    Accessibility,
+}
+
+impl Page {
+    pub const ALL: [Self; 5] = [
+        Self::General,
+        Self::Graphics,
+        Self::Audio,
+        Self::Controls,
+        Self::Accessibility,
+    ];
+
+    /// What the page is called in the menu
+    pub fn label(self) -> String {
+        tr!(match self {
+            Self::General => "settings.general",
+            Self::Graphics => "settings.graphics",
+            Self::Audio => "settings.audio",
+            Self::Controls => "settings.controls",
+            Self::Accessibility => "settings.accessibility",
+        })
+    }
+
+    /// The section of the settings file the page is saved in
+    pub const fn section(self) -> &'static str {
+        match self {
+            Self::General => "general",
+            Self::Graphics => "graphics",
+            Self::Audio => "audio",
+            Self::Controls => "controls",
+            Self::Accessibility => "accessibility",
+        }
+    }
+}
+
+/// Section of the settings file with each [`Settings::keys`] override
+const KEYS_SECTION: &str = "controls.keys";
+
+/// Section of the settings file with each [`Settings::modes`] override
+const MODES_SECTION: &str = "accessibility.modes";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Code of the language to show text in, see [`locale`]
+    pub language: String,
+    pub window_mode: WindowMode,
+    /// Frames per second to stop at, or 0 for no limit
+    pub target_fps: u32,
+    /// Vertical field of view in degrees
+    pub fovy: f32,
+    /// How much everything is turned up or down, in `[0, 1]`
+    pub master_volume: f32,
+    /// In `[0, 1]`
+    pub music_volume: f32,
+    /// Volume of sounds in the world, in `[0, 1]`
+    pub effects_volume: f32,
+    /// In `[0, 1]`
+    pub ui_volume: f32,
+    /// How fast the mouse turns the camera, relative to the default
+    pub mouse_sensitivity: f32,
+    /// Keys bound to inputs in place of their default bindings
+    pub keys: BTreeMap<EventInput, KeyboardKey>,
+    /// Inputs set to be held or toggled in place of their [default](EventInput::default_mode)
+    pub modes: BTreeMap<EventInput, InputMode>,
+    /// Which [`Palette`](palette::Palette) highlights and the HUD are drawn with
+    pub color_vision: ColorVision,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: locale::ENGLISH.to_string(),
+            window_mode: WindowMode::Windowed,
+            target_fps: 60,
+            fovy: 45.0,
+            master_volume: 1.0,
+            music_volume: 0.5,
+            effects_volume: 1.0,
+            ui_volume: 0.8,
+            mouse_sensitivity: 1.0,
+            keys: BTreeMap::new(),
+            modes: BTreeMap::new(),
+            color_vision: ColorVision::Normal,
+        }
+    }
+}
+
+/// A line of the settings file that couldn't be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSettingsError {
+    /// Counting from 1
+    pub line: usize,
+    pub kind: ParseSettingsErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseSettingsErrorKind {
+    /// The line isn't a `[section]` or `key = value`
+    Syntax,
+    /// There's no setting by that name in the section
+    UnknownKey(String),
+    /// The value isn't one the setting can have
+    InvalidValue(String),
+}
+
+impl std::fmt::Display for ParseSettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ParseSettingsErrorKind::Syntax => f.write_str("expected `[section]` or `key = value`"),
+            ParseSettingsErrorKind::UnknownKey(key) => write!(f, "unknown setting `{key}`"),
+            ParseSettingsErrorKind::InvalidValue(key) => write!(f, "invalid value for `{key}`"),
+        }
+    }
+}
+
+impl std::error::Error for ParseSettingsError {}
+
+/// A number in `value`, if it's in `range`
+fn parse_in<T: FromStr + PartialOrd>(value: &str, range: std::ops::RangeInclusive<T>) -> Option<T> {
+    value.parse().ok().filter(|x| range.contains(x))
+}
+
+impl Settings {
+    /// The lowest and highest [`Self::fovy`]
+    pub const FOVY_RANGE: std::ops::RangeInclusive<f32> = 30.0..=110.0;
+    /// The lowest and highest [`Self::mouse_sensitivity`]
+    pub const SENSITIVITY_RANGE: std::ops::RangeInclusive<f32> = 0.1..=5.0;
+    /// Choices for [`Self::target_fps`]
+    pub const TARGET_FPS_CHOICES: [u32; 5] = [30, 60, 120, 144, 0];
+
+    /// Where settings are saved, in [`app_dir`], if it could be found
+    pub fn path() -> Option<PathBuf> {
+        Some(app_dir()?.join(FILE_NAME))
+    }
+
+    /// Read the settings at `path`, or the defaults if there's no file there yet
+    ///
+    /// Lines that can't be used are left at their defaults and returned.
+    pub fn load(path: &Path) -> io::Result<(Self, Vec<ParseSettingsError>)> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok((Self::default(), Vec::new())),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the settings to `path`, making its folder if it doesn't exist
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_string())
+    }
+
+    /// Read settings from the text of a settings file
+    ///
+    /// Unlike [`FromStr`], lines that can't be used don't stop the rest from being read. They're
+    /// left at their defaults and returned.
+    pub fn parse(text: &str) -> (Self, Vec<ParseSettingsError>) {
+        let mut settings = Self::default();
+        let mut errors = Vec::new();
+        let mut section = "";
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let result = if let Some(name) =
+                (line.strip_prefix('[')).and_then(|line| line.strip_suffix(']'))
+            {
+                section = name.trim();
+                Ok(())
+            } else if let Some((key, value)) = line.split_once('=') {
+                settings.set(section, key.trim(), value.trim().trim_matches('"'))
+            } else {
+                Err(ParseSettingsErrorKind::Syntax)
+            };
+            if let Err(kind) = result {
+                errors.push(ParseSettingsError {
+                    line: line_number,
+                    kind,
+                });
+            }
+        }
+        (settings, errors)
+    }
+
+    /// Set the setting called `key` in `section` of the file to `value`
+    fn set(&mut self, section: &str, key: &str, value: &str) -> Result<(), ParseSettingsErrorKind> {
+        let invalid = || ParseSettingsErrorKind::InvalidValue(key.to_string());
+        if section == KEYS_SECTION || section == MODES_SECTION {
+            let input = (EventInput::ALL.into_iter())
+                .find(|input| format!("{input:?}") == key)
+                .ok_or_else(|| ParseSettingsErrorKind::UnknownKey(key.to_string()))?;
+            if section == KEYS_SECTION {
+                self.keys
+                    .insert(input, key_from_name(value).ok_or_else(invalid)?);
+            } else {
+                let mode = (InputMode::ALL.into_iter())
+                    .find(|mode| mode.name() == value)
+                    .ok_or_else(invalid)?;
+                self.modes.insert(input, mode);
+            }
+            return Ok(());
+        }
+        let volume = |value: &str| parse_in(value, 0.0..=1.0).ok_or_else(invalid);
+        match (section, key) {
+            ("general", "language") => {
+                let is_code = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+                if value.is_empty() || !value.chars().all(is_code) {
+                    return Err(invalid());
+                }
+                self.language = value.to_string();
+            }
+            ("graphics", "window_mode") => {
+                self.window_mode = (WindowMode::ALL.into_iter())
+                    .find(|mode| mode.name() == value)
+                    .ok_or_else(invalid)?;
+            }
+            ("graphics", "target_fps") => self.target_fps = value.parse().map_err(|_| invalid())?,
+            ("graphics", "fov") => {
+                self.fovy = parse_in(value, Self::FOVY_RANGE).ok_or_else(invalid)?;
+            }
+            ("audio", "master_volume") => self.master_volume = volume(value)?,
+            ("audio", "music_volume") => self.music_volume = volume(value)?,
+            ("audio", "effects_volume") => self.effects_volume = volume(value)?,
+            ("audio", "ui_volume") => self.ui_volume = volume(value)?,
+            ("controls", "mouse_sensitivity") => {
+                self.mouse_sensitivity =
+                    parse_in(value, Self::SENSITIVITY_RANGE).ok_or_else(invalid)?;
+            }
+            ("accessibility", "color_vision") => {
+                self.color_vision = (ColorVision::ALL.into_iter())
+                    .find(|vision| vision.name() == value)
+                    .ok_or_else(invalid)?;
+            }
+            _ => return Err(ParseSettingsErrorKind::UnknownKey(key.to_string())),
+        }
+        Ok(())
+    }
+
+    /// How `input` follows its key, with [`Self::modes`] over its default
+    pub fn mode(&self, input: EventInput) -> InputMode {
+        (self.modes.get(&input).copied()).unwrap_or_else(|| input.default_mode())
+    }
+
+    /// Rebuild `bindings` from the defaults with these settings' sensitivity, keys, and modes
+    pub fn apply_bindings(&self, bindings: &mut Bindings) {
+        *bindings = Bindings::default_binds();
+        bindings[VectorInput::Look] = VectorSource::Mouse.scale(AxisSource::Constant(
+            LOOK_PER_PIXEL * self.mouse_sensitivity,
+        ));
+        for (&input, &key) in &self.keys {
+            bindings[input] = if input.is_held() {
+                key.down()
+            } else {
+                key.pressed()
+            };
+        }
+        for (&input, &mode) in &self.modes {
+            bindings.set_mode(input, mode);
+        }
+    }
+
+    /// Make the game match the settings, as far as it doesn't already
+    pub fn apply(
+        &self,
+        rl: &mut RaylibHandle,
+        bindings: &mut Bindings,
+        player: &mut Player,
+        audio: Option<&mut Audio<'_>>,
+    ) {
+        if locale::language() != self.language
+            && let Err(e) = locale::set_language(&self.language)
+        {
+            eprintln!("failed to change language: {e}");
+        }
+        rl.set_target_fps(self.target_fps);
+        if rl.is_window_fullscreen() != (self.window_mode == WindowMode::Fullscreen) {
+            rl.toggle_fullscreen();
+        }
+        player.camera_settings.fovy = self.fovy;
+        player.camera_settings.zoom_fovy = self.fovy / 3.0;
+        self.apply_bindings(bindings);
+        palette::set_color_vision(self.color_vision);
+        if let Some(audio) = audio {
+            audio.set_master_volume(self.master_volume);
+            audio.music_volume = self.music_volume;
+            audio.effects_volume = self.effects_volume;
+            audio.ui_volume = self.ui_volume;
+        }
+    }
+}
+
+impl std::fmt::Display for Settings {
+    /// The settings file
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut text = String::new();
+        for page in Page::ALL {
+            if page != Page::General {
+                text.push('\n');
+            }
+            _ = writeln!(text, "[{}]", page.section());
+            match page {
+                Page::General => _ = writeln!(text, "language = \"{}\"", self.language),
+                Page::Graphics => {
+                    _ = writeln!(text, "window_mode = \"{}\"", self.window_mode.name());
+                    _ = writeln!(text, "target_fps = {}", self.target_fps);
+                    _ = writeln!(text, "fov = {}", self.fovy);
+                }
+                Page::Audio => {
+                    _ = writeln!(text, "master_volume = {}", self.master_volume);
+                    _ = writeln!(text, "music_volume = {}", self.music_volume);
+                    _ = writeln!(text, "effects_volume = {}", self.effects_volume);
+                    _ = writeln!(text, "ui_volume = {}", self.ui_volume);
+                }
+                Page::Controls => {
+                    _ = writeln!(text, "mouse_sensitivity = {}", self.mouse_sensitivity);
+                }
+                Page::Accessibility => {
+                    _ = writeln!(text, "color_vision = \"{}\"", self.color_vision.name());
+                }
+            }
+        }
+        if !self.keys.is_empty() {
+            _ = writeln!(text, "\n[{KEYS_SECTION}]");
+            for (input, &key) in &self.keys {
+                _ = writeln!(text, "{input:?} = \"{}\"", key_name(key));
+            }
+        }
+        if !self.modes.is_empty() {
+            _ = writeln!(text, "\n[{MODES_SECTION}]");
+            for (input, mode) in &self.modes {
+                _ = writeln!(text, "{input:?} = \"{}\"", mode.name());
+            }
+        }
+        f.write_str(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_file() {
+        let mut settings = Settings {
+            language: "de".to_string(),
+            window_mode: WindowMode::Fullscreen,
+            fovy: 70.0,
+            music_volume: 0.25,
+            color_vision: ColorVision::Tritanopia,
+            ..Settings::default()
+        };
+        settings.keys.insert(EventInput::Interact, KEY_F);
+        settings.keys.insert(EventInput::Sprint, KEY_LEFT_CONTROL);
+        settings.modes.insert(EventInput::Zoom, InputMode::Toggle);
+        let (parsed, errors) = Settings::parse(&settings.to_string());
+        assert_eq!(errors, []);
+        assert_eq!(parsed, settings);
+
+        let (parsed, errors) = Settings::parse(
+            "# comment\n\
+            [audio]\n\
+            ui_volume = 0.5\n\
+            music_volume = 2\n\
+            [graphics]\n\
+            fov = 90 # degrees\n\
+            brightness = 1\n\
+            nonsense\n\
+            [controls.keys]\n\
+            Jump = \"j\"\n",
+        );
+        assert_eq!(
+            errors.iter().map(|e| e.line).collect::<Vec<_>>(),
+            [4, 7, 8],
+            "volumes can't be over 1, and there's no brightness setting"
+        );
+        assert!((parsed.ui_volume - 0.5).abs() < 1e-6);
+        assert!((parsed.fovy - 90.0).abs() < 1e-6);
+        assert!(
+            (parsed.music_volume - Settings::default().music_volume).abs() < 1e-6,
+            "invalid lines are left at their defaults"
+        );
+        assert_eq!(parsed.keys.get(&EventInput::Jump), Some(&KEY_J));
+    }
+}
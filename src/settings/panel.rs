@@ -0,0 +1,407 @@
+//! The settings menu
+
+use super::{BINDABLE_KEYS, Page, Settings, WindowMode, key_name};
+use crate::{
+    game::GameState,
+    input::{EventInput, InputMode, Inputs},
+    locale::{self, tr},
+    palette::ColorVision,
+    ui::Panel,
+};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer},
+};
+use raylib::prelude::*;
+use std::{cell::RefCell, rc::Rc};
+
+const MARGIN: f32 = 16.0;
+const FONT_SIZE: f32 = 20.0;
+const ROW_HEIGHT: f32 = FONT_SIZE + 8.0;
+const TAB_WIDTH: f32 = 132.0;
+const WIDTH: f32 = 5.0 * TAB_WIDTH + 2.0 * MARGIN;
+
+const BACKGROUND: Color = Color::new(16, 16, 24, 230);
+const HOVERED: Color = Color::new(255, 255, 255, 32);
+const SELECTED_TAB: Color = Color::new(96, 160, 224, 255);
+
+/// How much a click changes [`Settings::fovy`]
+const FOVY_STEP: f32 = 5.0;
+/// How much a click changes a volume
+const VOLUME_STEP: f32 = 0.1;
+/// How much a click changes [`Settings::mouse_sensitivity`]
+const SENSITIVITY_STEP: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Volume {
+    Master,
+    Music,
+    Effects,
+    Ui,
+}
+
+impl Volume {
+    const fn get(self, settings: &Settings) -> f32 {
+        match self {
+            Self::Master => settings.master_volume,
+            Self::Music => settings.music_volume,
+            Self::Effects => settings.effects_volume,
+            Self::Ui => settings.ui_volume,
+        }
+    }
+
+    const fn get_mut(self, settings: &mut Settings) -> &mut f32 {
+        match self {
+            Self::Master => &mut settings.master_volume,
+            Self::Music => &mut settings.music_volume,
+            Self::Effects => &mut settings.effects_volume,
+            Self::Ui => &mut settings.ui_volume,
+        }
+    }
+}
+
+/// A setting on a page of the [`SettingsPanel`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Row {
+    Language,
+    WindowMode,
+    TargetFps,
+    Fovy,
+    Volume(Volume),
+    MouseSensitivity,
+    /// The key bound to an input
+    Key(EventInput),
+    ColorVision,
+    /// Whether an input is pressed, held, or toggled
+    Mode(EventInput),
+}
+
+impl Row {
+    /// The settings on `page`, top to bottom
+    pub fn on_page(page: Page) -> Vec<Self> {
+        match page {
+            Page::General => vec![Self::Language],
+            Page::Graphics => vec![Self::WindowMode, Self::TargetFps, Self::Fovy],
+            Page::Audio => [Volume::Master, Volume::Music, Volume::Effects, Volume::Ui]
+                .map(Self::Volume)
+                .to_vec(),
+            Page::Controls => std::iter::once(Self::MouseSensitivity)
+                .chain(EventInput::ALL.map(Self::Key))
+                .collect(),
+            Page::Accessibility => std::iter::once(Self::ColorVision)
+                .chain(EventInput::ALL.map(Self::Mode))
+                .collect(),
+        }
+    }
+
+    pub fn label(self) -> String {
+        tr!(match self {
+            Self::Language => "settings.language",
+            Self::WindowMode => "settings.window",
+            Self::TargetFps => "settings.target_fps",
+            Self::Fovy => "settings.fovy",
+            Self::Volume(Volume::Master) => "settings.master_volume",
+            Self::Volume(Volume::Music) => "settings.music_volume",
+            Self::Volume(Volume::Effects) => "settings.effects_volume",
+            Self::Volume(Volume::Ui) => "settings.ui_volume",
+            Self::MouseSensitivity => "settings.mouse_sensitivity",
+            Self::ColorVision => "settings.color_vision",
+            Self::Key(input) | Self::Mode(input) => return input.label(),
+        })
+    }
+
+    /// The setting's value as shown in the menu
+    pub fn value(self, settings: &Settings) -> String {
+        match self {
+            Self::Language => settings.language.clone(),
+            Self::WindowMode => tr!(match settings.window_mode {
+                WindowMode::Windowed => "settings.windowed",
+                WindowMode::Fullscreen => "settings.fullscreen",
+            }),
+            Self::TargetFps if settings.target_fps == 0 => tr!("settings.unlimited"),
+            Self::TargetFps => format!("{} fps", settings.target_fps),
+            Self::Fovy => format!("{:.0}°", settings.fovy),
+            Self::Volume(volume) => format!("{:.0}%", volume.get(settings) * 100.0),
+            Self::MouseSensitivity => format!("{:.1}x", settings.mouse_sensitivity),
+            Self::Key(input) => settings
+                .keys
+                .get(&input)
+                .map_or_else(|| tr!("settings.default_key"), |&key| key_name(key)),
+            Self::ColorVision => tr!(match settings.color_vision {
+                ColorVision::Normal => "settings.normal_vision",
+                ColorVision::Deuteranopia => "settings.deuteranopia",
+                ColorVision::Protanopia => "settings.protanopia",
+                ColorVision::Tritanopia => "settings.tritanopia",
+            }),
+            Self::Mode(input) => tr!(match settings.mode(input) {
+                InputMode::Press => "settings.press",
+                InputMode::Hold => "settings.hold",
+                InputMode::Toggle => "settings.toggle",
+            }),
+        }
+    }
+
+    /// Change the setting to the next choice, or the previous one if `forward` is false
+    ///
+    /// Does nothing for [`Self::Key`], which is changed by pressing a key.
+    pub fn step(self, settings: &mut Settings, forward: bool) {
+        /// The choice after `current` in `choices`, or before it if not `forward`, wrapping
+        fn cycle<T: Clone + PartialEq>(choices: &[T], current: &T, forward: bool) -> T {
+            let idx = choices.iter().position(|x| x == current).unwrap_or(0);
+            let next = if forward {
+                idx + 1
+            } else {
+                idx + choices.len() - 1
+            };
+            choices[next % choices.len()].clone()
+        }
+        let sign = if forward { 1.0 } else { -1.0 };
+        match self {
+            Self::Language => {
+                settings.language = cycle(&locale::available(), &settings.language, forward);
+            }
+            Self::WindowMode => {
+                settings.window_mode = cycle(&WindowMode::ALL, &settings.window_mode, forward);
+            }
+            Self::TargetFps => {
+                settings.target_fps =
+                    cycle(&Settings::TARGET_FPS_CHOICES, &settings.target_fps, forward);
+            }
+            Self::Fovy => {
+                let (min, max) = Settings::FOVY_RANGE.into_inner();
+                settings.fovy = (settings.fovy + sign * FOVY_STEP).clamp(min, max);
+            }
+            Self::Volume(volume) => {
+                let volume = volume.get_mut(settings);
+                // Rounded so repeated steps land back on whole percents
+                *volume = ((*volume + sign * VOLUME_STEP).clamp(0.0, 1.0) * 100.0).round() / 100.0;
+            }
+            Self::MouseSensitivity => {
+                let (min, max) = Settings::SENSITIVITY_RANGE.into_inner();
+                settings.mouse_sensitivity =
+                    ((settings.mouse_sensitivity + sign * SENSITIVITY_STEP).clamp(min, max) * 10.0)
+                        .round()
+                        / 10.0;
+            }
+            Self::ColorVision => {
+                settings.color_vision = cycle(&ColorVision::ALL, &settings.color_vision, forward);
+            }
+            Self::Mode(input) => {
+                let mode = cycle(&InputMode::ALL, &settings.mode(input), forward);
+                // Only kept while it's different, so changing the default changes it too
+                if mode == input.default_mode() {
+                    settings.modes.remove(&input);
+                } else {
+                    settings.modes.insert(input, mode);
+                }
+            }
+            Self::Key(_) => {}
+        }
+    }
+}
+
+/// Pages of settings, changed by clicking them
+///
+/// Left clicking a setting changes it to the next choice and right clicking to the previous.
+/// Left clicking a key waits for a new key to be pressed for it, and right clicking puts it
+/// back to the default. Changes apply right away, and are saved when the menu closes.
+pub struct SettingsPanel {
+    /// Shared with the game, which applies any changes
+    settings: Rc<RefCell<Settings>>,
+    page: Page,
+    /// The tab or row under the mouse cursor
+    hovered: Option<Result<Page, Row>>,
+    /// The input waiting for a key to be pressed
+    listening: Option<EventInput>,
+    /// Screen-space area of the whole panel
+    rect: Rectangle,
+}
+
+impl SettingsPanel {
+    pub fn new(rl: &RaylibHandle, settings: Rc<RefCell<Settings>>) -> Self {
+        let mut panel = Self {
+            settings,
+            page: Page::Graphics,
+            hovered: None,
+            listening: None,
+            rect: Rectangle::default(),
+        };
+        panel.layout(rl);
+        panel
+    }
+
+    /// Center the panel on the screen, tall enough for the longest page
+    #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+    fn layout(&mut self, rl: &RaylibHandle) {
+        let rows = Page::ALL.map(|page| Row::on_page(page).len());
+        let rows = rows.into_iter().max().unwrap_or_default() as f32;
+        let height = 3.0 * MARGIN + (rows + 1.0) * ROW_HEIGHT;
+        self.rect = Rectangle::new(
+            0.5 * (rl.get_screen_width() as f32 - WIDTH),
+            0.5 * (rl.get_screen_height() as f32 - height),
+            WIDTH,
+            height,
+        );
+    }
+
+    fn save(&self) {
+        let Some(path) = Settings::path() else {
+            eprintln!("failed to save settings: couldn't find the config folder");
+            return;
+        };
+        if let Err(e) = self.settings.borrow().save(&path) {
+            eprintln!("failed to save settings to {}: {e}", path.display());
+        }
+    }
+
+    /// Screen-space area of the tab for `page`
+    #[allow(clippy::cast_precision_loss, reason = "there are only a few pages")]
+    fn tab_rect(&self, page: Page) -> Rectangle {
+        let idx = Page::ALL.iter().position(|&p| p == page).unwrap_or(0);
+        Rectangle::new(
+            self.rect.x + MARGIN + idx as f32 * TAB_WIDTH,
+            self.rect.y + MARGIN,
+            TAB_WIDTH,
+            ROW_HEIGHT,
+        )
+    }
+
+    /// Screen-space area of the `idx`th row of the page
+    #[allow(clippy::cast_precision_loss, reason = "there are only a few rows")]
+    fn row_rect(&self, idx: usize) -> Rectangle {
+        Rectangle::new(
+            self.rect.x + MARGIN,
+            self.rect.y + 2.0 * MARGIN + (idx + 1) as f32 * ROW_HEIGHT,
+            self.rect.width - 2.0 * MARGIN,
+            ROW_HEIGHT,
+        )
+    }
+}
+
+impl Panel for SettingsPanel {
+    /// Closes on escape or opening the settings again, unless waiting for a key
+    fn update(&mut self, rl: &mut RaylibHandle, inputs: &Inputs, _state: &mut GameState) -> bool {
+        if let Some(input) = self.listening {
+            if let Some(key) = rl.get_key_pressed() {
+                if BINDABLE_KEYS.contains(&key) {
+                    self.settings.borrow_mut().keys.insert(input, key);
+                }
+                // Any other key, like escape, cancels
+                self.listening = None;
+            }
+            return true;
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) || inputs[EventInput::OpenSettings] {
+            self.save();
+            return false;
+        }
+        self.layout(rl);
+
+        let mouse = rl.get_mouse_position();
+        let tab = (Page::ALL.into_iter())
+            .find(|&page| self.tab_rect(page).check_collision_point_rec(mouse));
+        let row = (Row::on_page(self.page).into_iter().enumerate())
+            .find(|&(idx, _)| self.row_rect(idx).check_collision_point_rec(mouse))
+            .map(|(_, row)| row);
+        self.hovered = tab.map(Ok).or(row.map(Err));
+
+        let left = rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+        let right = rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT);
+        match self.hovered {
+            Some(Ok(page)) if left => self.page = page,
+            Some(Err(Row::Key(input))) if left => self.listening = Some(input),
+            Some(Err(Row::Key(input))) if right => {
+                self.settings.borrow_mut().keys.remove(&input);
+            }
+            Some(Err(row)) if left || right => row.step(&mut self.settings.borrow_mut(), left),
+            _ => {}
+        }
+        true
+    }
+}
+
+impl Draw for SettingsPanel {
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        d.draw_rectangle(self.rect, BACKGROUND)?;
+        for page in Page::ALL {
+            let rect = self.tab_rect(page);
+            if page == self.page {
+                d.draw_rectangle(rect, SELECTED_TAB)?;
+            } else if self.hovered == Some(Ok(page)) {
+                d.draw_rectangle(rect, HOVERED)?;
+            }
+            d.draw_rectangle_lines(rect, Some(1.0), Color::GRAY)?;
+            d.draw_text(
+                &page.label(),
+                Vector2::new(rect.x + 8.0, rect.y + 4.0),
+                FONT_SIZE,
+                0.0,
+                Color::WHITE,
+            )?;
+        }
+
+        let font = d.font().ok_or(draw::Error)?;
+        let settings = self.settings.borrow();
+        for (idx, row) in Row::on_page(self.page).into_iter().enumerate() {
+            let rect = self.row_rect(idx);
+            if self.hovered == Some(Err(row)) {
+                d.draw_rectangle(rect, HOVERED)?;
+            }
+            d.draw_text(
+                &row.label(),
+                Vector2::new(rect.x + 4.0, rect.y + 4.0),
+                FONT_SIZE,
+                0.0,
+                Color::WHITE,
+            )?;
+            let (value, color) = match row {
+                Row::Key(input) if self.listening == Some(input) => {
+                    (tr!("settings.press_key"), Color::YELLOW)
+                }
+                _ => (row.value(&settings), Color::LIGHTGRAY),
+            };
+            let size = font.measure_text(&value, FONT_SIZE, 0.0);
+            d.draw_text(
+                &value,
+                Vector2::new(rect.x + rect.width - size.x - 4.0, rect.y + 4.0),
+                FONT_SIZE,
+                0.0,
+                color,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step() {
+        let mut settings = Settings::default();
+        Row::TargetFps.step(&mut settings, false);
+        assert_eq!(settings.target_fps, 30);
+        Row::TargetFps.step(&mut settings, false);
+        assert_eq!(settings.target_fps, 0, "choices wrap around");
+        assert_eq!(Row::TargetFps.value(&settings), "Unlimited");
+
+        for _ in 0..20 {
+            Row::Volume(Volume::Music).step(&mut settings, true);
+        }
+        assert!((settings.music_volume - 1.0).abs() < 1e-6);
+        Row::Volume(Volume::Music).step(&mut settings, false);
+        assert_eq!(Row::Volume(Volume::Music).value(&settings), "90%");
+
+        Row::Mode(EventInput::Sprint).step(&mut settings, true);
+        assert_eq!(
+            settings.modes.get(&EventInput::Sprint),
+            Some(&InputMode::Toggle)
+        );
+        Row::Mode(EventInput::Sprint).step(&mut settings, false);
+        assert!(
+            settings.modes.is_empty(),
+            "going back to the default mode isn't kept as a change"
+        );
+    }
+}
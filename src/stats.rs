@@ -0,0 +1,223 @@
+//! How fast each item is made and used up
+//!
+//! Machines report what they make and use up to their factory's [`Statistics`] through their
+//! [`TickContext`](crate::region::factory::TickContext) each time they finish a cycle. Counts are
+//! added up per minute in a ring of the last [`HISTORY`] minutes, and graphed in the
+//! [`StatsPanel`](panel::StatsPanel) to show what's holding production back.
+//!
+//! Stations also report what trains deliver to each stop, which is what
+//! [contracts](crate::contract) are measured against.
+
+use crate::{container::Item, region::rail::network::StopId};
+use std::collections::BTreeMap;
+
+pub mod panel;
+
+/// How many minutes are kept
+pub const HISTORY: usize = 60;
+
+/// Seconds in a minute
+const MINUTE: f32 = 60.0;
+
+/// How many of something there were in each of the last [`HISTORY`] minutes, including the one
+/// still going
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinuteCounts {
+    counts: [u32; HISTORY],
+    /// Where the current minute is in `counts`
+    current: usize,
+}
+
+impl Default for MinuteCounts {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MinuteCounts {
+    /// Nothing in any minute
+    pub const fn new() -> Self {
+        Self {
+            counts: [0; HISTORY],
+            current: 0,
+        }
+    }
+
+    /// Count `count` more in the current minute
+    const fn add(&mut self, count: u32) {
+        self.counts[self.current] = self.counts[self.current].saturating_add(count);
+    }
+
+    /// Start the next minute, forgetting the oldest
+    const fn advance(&mut self) {
+        self.current = (self.current + 1) % HISTORY;
+        self.counts[self.current] = 0;
+    }
+
+    /// The count `minutes` minutes ago, where 0 is the current minute
+    ///
+    /// Minutes longer ago than [`HISTORY`] have nothing in them.
+    pub const fn minutes_ago(&self, minutes: usize) -> u32 {
+        if minutes < HISTORY {
+            self.counts[(self.current + HISTORY - minutes) % HISTORY]
+        } else {
+            0
+        }
+    }
+
+    /// The count in each minute, oldest first and ending with the current one
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..HISTORY).rev().map(|minutes| self.minutes_ago(minutes))
+    }
+
+    /// The most in any one minute
+    pub fn peak(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Add `other`'s counts to these, lining up minutes by how long ago they were
+    fn merge(&mut self, other: &Self) {
+        for minutes in 0..HISTORY {
+            let idx = (self.current + HISTORY - minutes) % HISTORY;
+            self.counts[idx] = self.counts[idx].saturating_add(other.minutes_ago(minutes));
+        }
+    }
+}
+
+/// How many of an item were made and used up in each minute, and ever
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ItemRates {
+    pub produced: MinuteCounts,
+    pub consumed: MinuteCounts,
+    pub total_produced: u64,
+    pub total_consumed: u64,
+}
+
+/// How many of each item have been made and used up recently
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Statistics {
+    items: BTreeMap<Item, ItemRates>,
+    /// How many of each item trains have ever unloaded at each stop
+    deliveries: BTreeMap<(StopId, Item), u64>,
+    /// Seconds into the current minute
+    elapsed: f32,
+}
+
+impl Statistics {
+    pub const fn new() -> Self {
+        Self {
+            items: BTreeMap::new(),
+            deliveries: BTreeMap::new(),
+            elapsed: 0.0,
+        }
+    }
+
+    /// Report that `count` of `item` were made, or brought in
+    pub fn produced(&mut self, item: &Item, count: u32) {
+        let rates = self.rates_mut(item);
+        rates.produced.add(count);
+        rates.total_produced += u64::from(count);
+    }
+
+    /// Report that `count` of `item` were used up, or sent away
+    pub fn consumed(&mut self, item: &Item, count: u32) {
+        let rates = self.rates_mut(item);
+        rates.consumed.add(count);
+        rates.total_consumed += u64::from(count);
+    }
+
+    /// Report that `count` of `item` were unloaded from a train at `stop`
+    pub fn delivered(&mut self, stop: StopId, item: &Item, count: u32) {
+        *self.deliveries.entry((stop, item.clone())).or_default() += u64::from(count);
+    }
+
+    /// How many of `item` have ever been unloaded at `stop`
+    pub fn total_delivered(&self, stop: StopId, item: &Item) -> u64 {
+        (self.deliveries.get(&(stop, item.clone()))).map_or(0, |&count| count)
+    }
+
+    fn rates_mut(&mut self, item: &Item) -> &mut ItemRates {
+        self.items.entry(item.clone()).or_default()
+    }
+
+    /// Advance `dt` seconds, starting a new minute for every item each time one passes
+    pub fn tick(&mut self, dt: f32) {
+        self.elapsed += dt;
+        while self.elapsed >= MINUTE {
+            self.elapsed -= MINUTE;
+            for rates in self.items.values_mut() {
+                rates.produced.advance();
+                rates.consumed.advance();
+            }
+        }
+    }
+
+    /// Every item that's been made or used up, in order
+    pub fn items(&self) -> impl Iterator<Item = (&Item, &ItemRates)> {
+        self.items.iter()
+    }
+
+    pub fn get(&self, item: &Item) -> Option<&ItemRates> {
+        self.items.get(item)
+    }
+
+    /// How many of `item` have ever been made
+    pub fn total_produced(&self, item: &Item) -> u64 {
+        self.get(item).map_or(0, |rates| rates.total_produced)
+    }
+
+    /// Add `other`'s counts to these, like for the totals of several factories
+    pub fn merge(&mut self, other: &Self) {
+        for (item, rates) in other.items() {
+            let ours = self.rates_mut(item);
+            ours.produced.merge(&rates.produced);
+            ours.consumed.merge(&rates.consumed);
+            ours.total_produced += rates.total_produced;
+            ours.total_consumed += rates.total_consumed;
+        }
+        for (key, count) in &other.deliveries {
+            *self.deliveries.entry(key.clone()).or_default() += count;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statistics() {
+        let water = "H2O".parse::<Item>().unwrap();
+        let mut stats = Statistics::new();
+        stats.produced(&water, 3);
+        stats.tick(30.0);
+        stats.consumed(&water, 1);
+        stats.tick(40.0);
+        stats.produced(&water, 5);
+
+        let rates = stats.get(&water).unwrap();
+        assert_eq!(rates.produced.minutes_ago(0), 5);
+        assert_eq!(rates.produced.minutes_ago(1), 3);
+        assert_eq!(rates.consumed.minutes_ago(1), 1);
+        assert_eq!(rates.produced.iter().count(), HISTORY);
+        assert_eq!(rates.produced.iter().last(), Some(5), "oldest first");
+        assert_eq!(rates.produced.peak(), 5);
+
+        stats.tick(3600.0);
+        let rates = stats.get(&water).unwrap();
+        assert_eq!(rates.produced.peak(), 0, "old minutes are forgotten");
+        assert_eq!(stats.total_produced(&water), 8, "but totals aren't");
+
+        let mut total = Statistics::new();
+        total.tick(MINUTE);
+        let mut other = Statistics::new();
+        other.produced(&water, 2);
+        other.tick(MINUTE);
+        other.produced(&water, 4);
+        total.merge(&other);
+        let rates = total.get(&water).unwrap();
+        assert_eq!(rates.produced.minutes_ago(0), 4);
+        assert_eq!(rates.produced.minutes_ago(1), 2);
+    }
+}
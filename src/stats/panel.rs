@@ -0,0 +1,335 @@
+//! The statistics panel, graphing what's been made and used up over the last hour
+
+use super::{HISTORY, ItemRates, MinuteCounts, Statistics};
+use crate::{
+    container::Item,
+    game::GameState,
+    input::{EventInput, Inputs},
+    locale::tr,
+    region::RegionId,
+    ui::Panel,
+};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer},
+};
+use raylib::prelude::*;
+
+const MARGIN: f32 = 16.0;
+const FONT_SIZE: f32 = 20.0;
+const ROW_HEIGHT: f32 = FONT_SIZE + 8.0;
+const LIST_WIDTH: f32 = 280.0;
+const GRAPH_WIDTH: f32 = 480.0;
+const GRAPH_HEIGHT: f32 = 240.0;
+/// The graph, with a row of labels above and below it
+const BODY_HEIGHT: f32 = GRAPH_HEIGHT + 2.0 * ROW_HEIGHT;
+/// How many items are listed at once, scrolling to keep the selected one in view
+const VISIBLE_ROWS: usize = 10;
+
+const BACKGROUND: Color = Color::new(16, 16, 24, 230);
+const HOVERED: Color = Color::new(255, 255, 255, 32);
+const SELECTED: Color = Color::new(96, 160, 224, 255);
+const GRAPH: Color = Color::new(0, 0, 0, 160);
+const PRODUCED: Color = Color::new(96, 192, 96, 255);
+const CONSUMED: Color = Color::new(224, 96, 64, 255);
+
+/// The points of a line graph of `counts` across `rect`, oldest on the left, with `peak` at the
+/// top
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "counts per minute are much less than 2^24"
+)]
+fn graph_points(counts: &MinuteCounts, peak: u32, rect: Rectangle) -> Vec<Vector2> {
+    let step = rect.width / (HISTORY - 1) as f32;
+    let scale = rect.height / peak.max(1) as f32;
+    (counts.iter().enumerate())
+        .map(|(minute, count)| {
+            Vector2::new(
+                rect.x + minute as f32 * step,
+                rect.y + rect.height - count as f32 * scale,
+            )
+        })
+        .collect()
+}
+
+/// A list of every item made or used up, with a graph of how many of the selected one were
+/// each minute
+///
+/// Opened inside a factory it shows that factory, and anywhere else it shows every factory
+/// together. Clicking an item or pressing up and down selects it.
+#[derive(Debug, Clone)]
+pub struct StatsPanel {
+    /// Which factory is shown, or every factory together if [`None`]
+    factory: Option<usize>,
+    title: String,
+    rows: Vec<(Item, ItemRates)>,
+    /// The item graphed, if there are any
+    selected: Option<Item>,
+    /// The first row listed
+    scroll: usize,
+    /// The row under the mouse cursor
+    hovered: Option<usize>,
+    /// Screen-space area of the whole panel
+    rect: Rectangle,
+}
+
+impl StatsPanel {
+    /// A panel for the factory the player is in, or every factory if they aren't in one
+    pub fn new(rl: &RaylibHandle, state: &GameState) -> Self {
+        let factory = match state.current_region {
+            RegionId::Factory(idx) => Some(idx),
+            RegionId::Rail | RegionId::Lab => None,
+        };
+        let mut panel = Self {
+            factory,
+            title: String::new(),
+            rows: Vec::new(),
+            selected: None,
+            scroll: 0,
+            hovered: None,
+            rect: Rectangle::default(),
+        };
+        panel.refresh(state);
+        panel.layout(rl);
+        panel
+    }
+
+    /// Copy the statistics out of `state`
+    fn refresh(&mut self, state: &GameState) {
+        let mut statistics = Statistics::new();
+        if let Some(idx) = self.factory {
+            self.title = tr!("stats_panel.title_factory", number = idx + 1);
+            if let Some(factory) = state.factories.get(idx) {
+                statistics.merge(factory.statistics());
+            }
+        } else {
+            self.title = tr!("stats_panel.title_all");
+            for factory in &state.factories {
+                statistics.merge(factory.statistics());
+            }
+        }
+        self.rows = (statistics.items())
+            .map(|(item, rates)| (item.clone(), rates.clone()))
+            .collect();
+        if self.selected_row().is_none() {
+            self.selected = self.rows.first().map(|(item, _)| item.clone());
+        }
+    }
+
+    /// The index of the selected item in the list
+    fn selected_row(&self) -> Option<usize> {
+        let selected = self.selected.as_ref()?;
+        self.rows.iter().position(|(item, _)| item == selected)
+    }
+
+    /// Select the item in `row`, scrolling it into view
+    fn select(&mut self, row: usize) {
+        let Some((item, _)) = self.rows.get(row) else {
+            return;
+        };
+        self.selected = Some(item.clone());
+        self.scroll = self
+            .scroll
+            .clamp((row + 1).saturating_sub(VISIBLE_ROWS), row);
+    }
+
+    /// Center the panel on the screen
+    #[allow(clippy::cast_precision_loss, reason = "screen sizes are small")]
+    fn layout(&mut self, rl: &RaylibHandle) {
+        let width = LIST_WIDTH + GRAPH_WIDTH + 3.0 * MARGIN;
+        let height = ROW_HEIGHT + BODY_HEIGHT + 3.0 * MARGIN;
+        self.rect = Rectangle::new(
+            0.5 * (rl.get_screen_width() as f32 - width),
+            0.5 * (rl.get_screen_height() as f32 - height),
+            width,
+            height,
+        );
+    }
+
+    /// The top of the list and graph
+    const fn body_top(&self) -> f32 {
+        self.rect.y + 2.0 * MARGIN + ROW_HEIGHT
+    }
+
+    /// Screen-space area of `row` in the list, if it's scrolled into view
+    #[allow(clippy::cast_precision_loss, reason = "there are only a few rows")]
+    fn row_rect(&self, row: usize) -> Option<Rectangle> {
+        let shown = row.checked_sub(self.scroll).filter(|&i| i < VISIBLE_ROWS)?;
+        Some(Rectangle::new(
+            self.rect.x + MARGIN,
+            self.body_top() + shown as f32 * ROW_HEIGHT,
+            LIST_WIDTH,
+            ROW_HEIGHT,
+        ))
+    }
+
+    /// Screen-space area of the graph, without its labels
+    fn graph_rect(&self) -> Rectangle {
+        Rectangle::new(
+            self.rect.x + 2.0 * MARGIN + LIST_WIDTH,
+            self.body_top() + ROW_HEIGHT,
+            GRAPH_WIDTH,
+            GRAPH_HEIGHT,
+        )
+    }
+
+    /// Draw the list of items, with how many of each were made and used up last minute
+    fn draw_list(&self, d: &mut Renderer<'_>) -> draw::Result {
+        if self.rows.is_empty() {
+            let position = Vector2::new(self.rect.x + MARGIN, self.body_top());
+            return d.draw_text(
+                &tr!("stats_panel.empty"),
+                position,
+                FONT_SIZE,
+                0.0,
+                Color::GRAY,
+            );
+        }
+        let selected = self.selected_row();
+        for (row, (item, rates)) in self.rows.iter().enumerate() {
+            let Some(rect) = self.row_rect(row) else {
+                continue;
+            };
+            if selected == Some(row) {
+                d.draw_rectangle(rect, SELECTED)?;
+            } else if self.hovered == Some(row) {
+                d.draw_rectangle(rect, HOVERED)?;
+            }
+            let rate = tr!(
+                "stats_panel.rate",
+                produced = rates.produced.minutes_ago(1),
+                consumed = rates.consumed.minutes_ago(1)
+            );
+            d.draw_text(
+                &format!("{item}  {rate}"),
+                Vector2::new(rect.x + 8.0, rect.y + 0.5 * (rect.height - FONT_SIZE)),
+                FONT_SIZE,
+                0.0,
+                Color::WHITE,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draw the graph of the selected item, made and used up each minute
+    fn draw_graph(&self, d: &mut Renderer<'_>) -> draw::Result {
+        let graph = self.graph_rect();
+        d.draw_rectangle(graph, GRAPH)?;
+        d.draw_rectangle_lines(graph, Some(1.0), Color::GRAY)?;
+        let Some((_, rates)) = self.selected_row().map(|row| &self.rows[row]) else {
+            return Ok(());
+        };
+        let peak = rates.produced.peak().max(rates.consumed.peak()).max(1);
+        for (counts, color) in [(&rates.produced, PRODUCED), (&rates.consumed, CONSUMED)] {
+            let points = graph_points(counts, peak, graph);
+            for pair in points.windows(2) {
+                d.draw_line(pair[0], pair[1], Some(2.0), color)?;
+            }
+        }
+
+        let font = d.font().ok_or(draw::Error)?;
+        let above = graph.y - ROW_HEIGHT;
+        let below = graph.y + graph.height + 4.0;
+        let peak = tr!("stats_panel.per_minute", count = peak);
+        d.draw_text(
+            &peak,
+            Vector2::new(graph.x, above),
+            FONT_SIZE,
+            0.0,
+            Color::WHITE,
+        )?;
+        let mut x = graph.x + graph.width;
+        for (label, color) in [
+            (tr!("stats_panel.consumed"), CONSUMED),
+            (tr!("stats_panel.produced"), PRODUCED),
+        ] {
+            x -= font.measure_text(&label, FONT_SIZE, 0.0).x;
+            d.draw_text(&label, Vector2::new(x, above), FONT_SIZE, 0.0, color)?;
+            x -= MARGIN;
+        }
+        let oldest = tr!("stats_panel.minutes_ago", minutes = HISTORY);
+        d.draw_text(
+            &oldest,
+            Vector2::new(graph.x, below),
+            FONT_SIZE,
+            0.0,
+            Color::GRAY,
+        )?;
+        let now = tr!("stats_panel.now");
+        let now_x = graph.x + graph.width - font.measure_text(&now, FONT_SIZE, 0.0).x;
+        d.draw_text(
+            &now,
+            Vector2::new(now_x, below),
+            FONT_SIZE,
+            0.0,
+            Color::GRAY,
+        )
+    }
+}
+
+impl Panel for StatsPanel {
+    /// Closes on escape or opening the statistics again
+    fn update(&mut self, rl: &mut RaylibHandle, inputs: &Inputs, state: &mut GameState) -> bool {
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) || inputs[EventInput::OpenStatistics] {
+            return false;
+        }
+        self.layout(rl);
+        self.refresh(state);
+
+        let selected = self.selected_row().unwrap_or(0);
+        if rl.is_key_pressed(KeyboardKey::KEY_DOWN)
+            || rl.is_key_pressed_repeat(KeyboardKey::KEY_DOWN)
+        {
+            self.select(selected + 1);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_UP) || rl.is_key_pressed_repeat(KeyboardKey::KEY_UP) {
+            self.select(selected.saturating_sub(1));
+        }
+        let mouse = rl.get_mouse_position();
+        self.hovered = (0..self.rows.len()).find(|&row| {
+            self.row_rect(row)
+                .is_some_and(|rect| rect.check_collision_point_rec(mouse))
+        });
+        if let Some(row) = self.hovered
+            && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+        {
+            self.select(row);
+        }
+        true
+    }
+}
+
+impl Draw for StatsPanel {
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        d.draw_rectangle(self.rect, BACKGROUND)?;
+        d.draw_text(
+            &self.title,
+            Vector2::new(self.rect.x + MARGIN, self.rect.y + MARGIN),
+            FONT_SIZE,
+            0.0,
+            Color::WHITE,
+        )?;
+        self.draw_list(d)?;
+        self.draw_graph(d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph_points() {
+        let mut counts = MinuteCounts::new();
+        counts.add(4);
+        let rect = Rectangle::new(10.0, 20.0, 590.0, 100.0);
+        let points = graph_points(&counts, 8, rect);
+        assert_eq!(points.len(), HISTORY);
+        assert_eq!(points[0], Vector2::new(10.0, 120.0), "oldest on the left");
+        assert_eq!(
+            points[HISTORY - 1],
+            Vector2::new(600.0, 70.0),
+            "the current minute on the right, halfway to the peak"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! The world clock, and stepping the game at a fixed rate
+//!
+//! The clock runs faster than real time by [`WorldClock::scale`], and is saved as text with the
+//! day, the hour, and the scale:
+//!
+//! ```text
+//! clock 3 18.5 72
+//! ```
+
+use crate::lighting::Sky;
+use std::{f32::consts::TAU, str::FromStr};
+
+/// Ticks per second of game time, regardless of frame rate
+pub const TICK_RATE: u32 = 60;
+
+/// The most ticks run for one frame, so a slow frame doesn't make the next one slower still
+pub const MAX_TICKS_PER_FRAME: u32 = 10;
+
+/// How fast a solar machine works with no daylight at all, relative to noon
+const NIGHT_SOLAR_EFFICIENCY: f32 = 0.1;
+
+/// Turns frames of any length into a whole number of ticks of [`TICK_RATE`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FixedTimestep {
+    /// Seconds that haven't been ticked yet
+    accumulated: f32,
+}
+
+impl FixedTimestep {
+    /// Seconds per tick
+    #[allow(clippy::cast_precision_loss, reason = "the tick rate is small")]
+    pub const STEP: f32 = 1.0 / TICK_RATE as f32;
+
+    /// Add a frame that took `dt` seconds, returning how many ticks are due
+    ///
+    /// Time past [`MAX_TICKS_PER_FRAME`] is dropped rather than caught up on later.
+    pub fn advance(&mut self, dt: f32) -> u32 {
+        self.accumulated += dt;
+        let mut ticks = 0;
+        while self.accumulated >= Self::STEP {
+            self.accumulated -= Self::STEP;
+            ticks += 1;
+            if ticks == MAX_TICKS_PER_FRAME {
+                self.accumulated = 0.0;
+                break;
+            }
+        }
+        ticks
+    }
+}
+
+/// The time of day in the world, which the sky and solar machines follow
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldClock {
+    /// Days since the game started
+    day: u32,
+    /// Hours since midnight, in `[0, 24)`
+    hours: f32,
+    /// Seconds of game time per real second
+    pub scale: f32,
+}
+
+impl Default for WorldClock {
+    /// Noon on the first day, with a day lasting 20 minutes
+    fn default() -> Self {
+        Self {
+            day: 0,
+            hours: 12.0,
+            scale: 72.0,
+        }
+    }
+}
+
+impl WorldClock {
+    #[inline]
+    pub const fn day(&self) -> u32 {
+        self.day
+    }
+
+    /// Hours since midnight, in `[0, 24)`
+    #[inline]
+    pub const fn hours(&self) -> f32 {
+        self.hours
+    }
+
+    /// Minutes of game time since the game started
+    pub fn total_minutes(&self) -> f64 {
+        (f64::from(self.day) * 24.0 + f64::from(self.hours)) * 60.0
+    }
+
+    /// Jump to `hours` after midnight on the same day, wrapping into `[0, 24)`
+    pub fn set_hours(&mut self, hours: f32) {
+        self.hours = hours.rem_euclid(24.0);
+    }
+
+    /// Advance by `dt` real seconds
+    pub fn tick(&mut self, dt: f32) {
+        let hours = self.hours + dt * self.scale / 3600.0;
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "a tick is never days long"
+        )]
+        let days = (hours / 24.0).floor() as u32;
+        self.day = self.day.saturating_add(days);
+        self.hours = hours.rem_euclid(24.0);
+    }
+
+    /// How high the sun is, from `-1` at midnight to `1` at noon
+    pub fn sun_elevation(&self) -> f32 {
+        ((self.hours - 6.0) / 24.0 * TAU).sin()
+    }
+
+    /// How much sunlight there is, from 0 at night to 1 once the sun is up
+    ///
+    /// Fades in over dawn and out over dusk.
+    pub fn daylight(&self) -> f32 {
+        (self.sun_elevation() * 4.0 + 0.5).clamp(0.0, 1.0)
+    }
+
+    #[inline]
+    pub fn is_night(&self) -> bool {
+        self.daylight() <= 0.0
+    }
+
+    /// The sky outside right now
+    #[inline]
+    pub fn sky(&self) -> Sky {
+        Sky::at(self.hours)
+    }
+}
+
+/// How fast a machine powered by the sun works given some [`WorldClock::daylight`], relative to
+/// noon
+pub fn solar_efficiency(daylight: f32) -> f32 {
+    NIGHT_SOLAR_EFFICIENCY + (1.0 - NIGHT_SOLAR_EFFICIENCY) * daylight.clamp(0.0, 1.0)
+}
+
+impl std::fmt::Display for WorldClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "clock {} {} {}", self.day, self.hours, self.scale)
+    }
+}
+
+/// Why text couldn't be read as a [`WorldClock`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseClockError;
+
+impl std::fmt::Display for ParseClockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("not a clock")
+    }
+}
+
+impl std::error::Error for ParseClockError {}
+
+impl FromStr for WorldClock {
+    type Err = ParseClockError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        if words.next() != Some("clock") {
+            return Err(ParseClockError);
+        }
+        let mut next = || words.next().ok_or(ParseClockError);
+        let day = next()?.parse().map_err(|_| ParseClockError)?;
+        let hours = next()?.parse::<f32>().map_err(|_| ParseClockError)?;
+        let scale = next()?.parse::<f32>().map_err(|_| ParseClockError)?;
+        if !(hours.is_finite() && scale.is_finite()) {
+            return Err(ParseClockError);
+        }
+        let mut clock = Self { day, hours, scale };
+        clock.set_hours(hours);
+        Ok(clock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock() {
+        let mut clock = WorldClock {
+            scale: 3600.0,
+            ..WorldClock::default()
+        };
+        assert!(clock.daylight() >= 1.0, "should be full daylight at noon");
+        clock.tick(13.0);
+        assert_eq!((clock.day(), clock.hours()), (1, 1.0));
+        assert!(clock.is_night());
+        assert!(solar_efficiency(clock.daylight()) < solar_efficiency(1.0));
+
+        assert_eq!(clock.to_string().parse(), Ok(clock));
+        assert_eq!(
+            "clock 1 noon 72".parse::<WorldClock>(),
+            Err(ParseClockError)
+        );
+
+        let mut timestep = FixedTimestep::default();
+        assert_eq!(timestep.advance(2.5 * FixedTimestep::STEP), 2);
+        assert_eq!(timestep.advance(0.6 * FixedTimestep::STEP), 1);
+        assert_eq!(timestep.advance(60.0), MAX_TICKS_PER_FRAME);
+    }
+}
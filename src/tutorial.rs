@@ -0,0 +1,445 @@
+//! Objectives that walk the player through the game, one at a time
+//!
+//! A [`Tutorial`] is a list of [`Objective`]s, each done once its [`Trigger`] happens. The one
+//! being worked on is shown in the [HUD](crate::hud::Hud), with a marker pointing at its
+//! [`Waypoint`] if it has one.
+//!
+//! Tutorials are read from [`TUTORIAL_PATH`] when the game starts, so they can be changed without
+//! rebuilding the game. They're written in [RON](ron), like:
+//!
+//! ```ron
+//! Tutorial(
+//!     objectives: [
+//!         Objective(
+//!             text: "tutorial.enter_lab",
+//!             trigger: EnterRegion(Lab),
+//!             waypoint: Some(Lab(0.0, 1.0, 0.0)),
+//!         ),
+//!         Objective(
+//!             text: "Build a reactor",
+//!             trigger: BuildMachine(Reactor),
+//!         ),
+//!     ],
+//! )
+//! ```
+//!
+//! Text is looked up with [`tr!`], so it can be a key in the language files, or shown as written
+//! if it isn't one.
+
+use crate::{
+    game::GameState,
+    locale::tr,
+    math::coords::{FactoryVector3, LabVector3, PlayerVector3, VectorConstants},
+    milestone::{Milestone, MilestoneId},
+    region::{events::RegionKind, factory::MachineKind},
+    research::{Tech, TechId},
+};
+use ron::{ParseRonError, Value};
+use std::{io, path::Path, str::FromStr};
+
+pub mod ron;
+
+/// Where the tutorial is read from
+pub const TUTORIAL_PATH: &str = "assets/tutorial.ron";
+
+/// The tutorial the game comes with, used if [`TUTORIAL_PATH`] can't be read
+const BUILT_IN: &str = include_str!("../assets/tutorial.ron");
+
+/// What has to happen to finish an [`Objective`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// The player is in a region of this kind
+    EnterRegion(RegionKind),
+    /// Another machine of this kind is built, in any factory
+    BuildMachine(MachineKind),
+    /// Another belt is built, in any factory
+    BuildBelt,
+    Research(TechId),
+    Milestone(MilestoneId),
+}
+
+impl Trigger {
+    /// A count that goes up as the trigger gets closer to happening
+    fn measure(self, state: &GameState) -> usize {
+        match self {
+            Self::EnterRegion(kind) => usize::from(state.current_region.kind() == kind),
+            Self::BuildMachine(kind) => (state.factories.iter())
+                .map(|factory| factory.machine_count(kind))
+                .sum(),
+            Self::BuildBelt => (state.factories.iter())
+                .map(|factory| factory.belts().len())
+                .sum(),
+            Self::Research(id) => usize::from(state.research.is_unlocked(id)),
+            Self::Milestone(id) => usize::from(state.milestones.is_reached(id)),
+        }
+    }
+
+    /// Whether the trigger has happened, given what it [measured](Self::measure) now and when
+    /// the objective started
+    ///
+    /// Building counts from when the objective started, since factories may already have
+    /// machines in them. Everything else only has to be true now.
+    const fn is_met(self, measured: usize, started: usize) -> bool {
+        match self {
+            Self::BuildMachine(_) | Self::BuildBelt => measured > started,
+            Self::EnterRegion(_) | Self::Research(_) | Self::Milestone(_) => measured > 0,
+        }
+    }
+}
+
+/// A place to point the player to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Waypoint {
+    /// In the lab, relative to its origin
+    Lab(LabVector3),
+    /// In the factory at this index, relative to its origin
+    Factory(usize, PlayerVector3),
+    /// Anywhere, in player coordinates
+    World(PlayerVector3),
+}
+
+impl Waypoint {
+    /// Where the waypoint is, if it's somewhere that exists
+    pub fn position(&self, state: &GameState) -> Option<PlayerVector3> {
+        match *self {
+            Self::Lab(position) => Some(position.to_player(&state.lab.origin)),
+            Self::Factory(idx, offset) => {
+                let factory = state.factories.get(idx)?;
+                Some(FactoryVector3::ZERO.to_player(&factory.origin).plus(offset))
+            }
+            Self::World(position) => Some(position),
+        }
+    }
+}
+
+/// One step of a [`Tutorial`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Objective {
+    /// What to do, as a [`tr!`] key or as written
+    pub text: String,
+    pub trigger: Trigger,
+    pub waypoint: Option<Waypoint>,
+}
+
+impl Objective {
+    /// The text shown to the player, translated if it's a key
+    pub fn label(&self) -> String {
+        tr!(&self.text)
+    }
+}
+
+/// Objectives done in order, and how far along the player is
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Tutorial {
+    objectives: Vec<Objective>,
+    /// The objective being worked on, or the number of objectives once they're all done
+    current: usize,
+    /// What the current objective's trigger measured when it started, or [`None`] if it hasn't
+    /// been checked yet
+    started: Option<usize>,
+}
+
+/// Why a tutorial couldn't be loaded
+#[derive(Debug)]
+pub enum TutorialError {
+    /// The file couldn't be read
+    Io(io::Error),
+    /// The file isn't RON
+    Ron(ParseRonError),
+    /// The RON isn't a tutorial; contains what was expected instead
+    Invalid(&'static str),
+}
+
+impl std::fmt::Display for TutorialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::Ron(e) => e.fmt(f),
+            Self::Invalid(expected) => write!(f, "expected {expected}"),
+        }
+    }
+}
+
+impl std::error::Error for TutorialError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Ron(e) => Some(e),
+            Self::Invalid(_) => None,
+        }
+    }
+}
+
+impl Tutorial {
+    pub const fn new(objectives: Vec<Objective>) -> Self {
+        Self {
+            objectives,
+            current: 0,
+            started: None,
+        }
+    }
+
+    /// The tutorial the game comes with
+    pub fn built_in() -> Self {
+        BUILT_IN
+            .parse()
+            .expect("the built-in tutorial should be valid")
+    }
+
+    /// Read a tutorial from the file at `path`
+    pub fn load(path: &Path) -> Result<Self, TutorialError> {
+        std::fs::read_to_string(path)
+            .map_err(TutorialError::Io)?
+            .parse()
+    }
+
+    /// The objective being worked on, if there are any left
+    pub fn current(&self) -> Option<&Objective> {
+        self.objectives.get(self.current)
+    }
+
+    /// How many objectives are done, and how many there are
+    pub const fn progress(&self) -> (usize, usize) {
+        (self.current, self.objectives.len())
+    }
+
+    /// Move on to the next objective, whether or not this one is done
+    pub fn skip(&mut self) {
+        self.current = (self.current + 1).min(self.objectives.len());
+        self.started = None;
+    }
+
+    /// Start again from the first objective
+    pub const fn restart(&mut self) {
+        self.current = 0;
+        self.started = None;
+    }
+
+    /// Move past every objective whose trigger has happened in `state`
+    pub fn update(&mut self, state: &GameState) {
+        while let Some(objective) = self.current() {
+            let trigger = objective.trigger;
+            let measured = trigger.measure(state);
+            let started = *self.started.get_or_insert(measured);
+            if !trigger.is_met(measured, started) {
+                return;
+            }
+            self.skip();
+        }
+    }
+}
+
+const fn number(value: &Value) -> Result<f32, TutorialError> {
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "positions are much smaller than f32::MAX"
+    )]
+    match *value {
+        Value::Number(n) => Ok(n as f32),
+        _ => Err(TutorialError::Invalid("a number")),
+    }
+}
+
+/// Three numbers, starting at `values[0]`
+fn position(values: &[Value]) -> Result<[f32; 3], TutorialError> {
+    match values {
+        [x, y, z] => Ok([number(x)?, number(y)?, number(z)?]),
+        _ => Err(TutorialError::Invalid("x, y, and z")),
+    }
+}
+
+/// The name of the tuple with nothing in it, like `Lab`
+fn unit(value: &Value) -> Option<&str> {
+    match value {
+        Value::Tuple(name, items) if items.is_empty() => Some(name),
+        _ => None,
+    }
+}
+
+/// The name in quotes
+fn string(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(string) => Some(string),
+        _ => None,
+    }
+}
+
+impl TryFrom<&Value> for Trigger {
+    type Error = TutorialError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        const EXPECTED: &str =
+            "EnterRegion(..), BuildMachine(..), BuildBelt, Research(\"..\"), or Milestone(\"..\")";
+        let Value::Tuple(name, items) = value else {
+            return Err(TutorialError::Invalid(EXPECTED));
+        };
+        match (name.as_str(), items.as_slice()) {
+            ("EnterRegion", [kind]) => match unit(kind) {
+                Some("Rail") => Ok(Self::EnterRegion(RegionKind::Rail)),
+                Some("Factory") => Ok(Self::EnterRegion(RegionKind::Factory)),
+                Some("Lab") => Ok(Self::EnterRegion(RegionKind::Lab)),
+                _ => Err(TutorialError::Invalid("Rail, Factory, or Lab")),
+            },
+            ("BuildMachine", [kind]) => match unit(kind) {
+                Some("Reactor") => Ok(Self::BuildMachine(MachineKind::Reactor)),
+                Some("Miner") => Ok(Self::BuildMachine(MachineKind::Miner)),
+                Some("Splitter") => Ok(Self::BuildMachine(MachineKind::Splitter)),
+                Some("Merger") => Ok(Self::BuildMachine(MachineKind::Merger)),
+                Some("Station") => Ok(Self::BuildMachine(MachineKind::Station)),
+                Some("Vent") => Ok(Self::BuildMachine(MachineKind::Vent)),
+                Some("Controller") => Ok(Self::BuildMachine(MachineKind::Controller)),
+                Some("Assembler") => Ok(Self::BuildMachine(MachineKind::Assembler)),
+                Some("Tank") => Ok(Self::BuildMachine(MachineKind::Tank)),
+                Some("ChargingPad") => Ok(Self::BuildMachine(MachineKind::ChargingPad)),
+                Some("ReliefValve") => Ok(Self::BuildMachine(MachineKind::ReliefValve)),
+                Some("Distiller") => Ok(Self::BuildMachine(MachineKind::Distiller)),
+                Some("Crystallizer") => Ok(Self::BuildMachine(MachineKind::Crystallizer)),
+                Some("Electrolyzer") => Ok(Self::BuildMachine(MachineKind::Electrolyzer)),
+                _ => Err(TutorialError::Invalid("a kind of machine")),
+            },
+            ("BuildBelt", []) => Ok(Self::BuildBelt),
+            ("Research", [tech]) => (string(tech).and_then(Tech::find))
+                .map(|tech| Self::Research(tech.id))
+                .ok_or(TutorialError::Invalid("the name of a tech")),
+            ("Milestone", [milestone]) => (string(milestone).and_then(Milestone::find))
+                .map(|milestone| Self::Milestone(milestone.id))
+                .ok_or(TutorialError::Invalid("the name of a milestone")),
+            _ => Err(TutorialError::Invalid(EXPECTED)),
+        }
+    }
+}
+
+impl TryFrom<&Value> for Waypoint {
+    type Error = TutorialError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        const EXPECTED: &str = "Lab(x, y, z), Factory(index, x, y, z), or World(x, y, z)";
+        let Value::Tuple(name, items) = value else {
+            return Err(TutorialError::Invalid(EXPECTED));
+        };
+        match (name.as_str(), items.as_slice()) {
+            ("Lab", items) => {
+                let [x, y, z] = position(items)?;
+                Ok(Self::Lab(LabVector3::from_f32(x, y, z)))
+            }
+            ("Factory", [Value::Number(idx), items @ ..]) if idx.fract() == 0.0 && *idx >= 0.0 => {
+                let [x, y, z] = position(items)?;
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    clippy::cast_sign_loss,
+                    reason = "checked that it's a whole number that isn't negative"
+                )]
+                let idx = *idx as usize;
+                Ok(Self::Factory(idx, PlayerVector3::from_f32(x, y, z)))
+            }
+            ("World", items) => {
+                let [x, y, z] = position(items)?;
+                Ok(Self::World(PlayerVector3::from_f32(x, y, z)))
+            }
+            _ => Err(TutorialError::Invalid(EXPECTED)),
+        }
+    }
+}
+
+impl TryFrom<&Value> for Objective {
+    type Error = TutorialError;
+
+    /// The waypoint can be left out instead of being `None`
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        let text = (value.field("text").and_then(string))
+            .ok_or(TutorialError::Invalid("`text: \"..\"`"))?;
+        let trigger = (value.field("trigger"))
+            .ok_or(TutorialError::Invalid("`trigger: ..`"))?
+            .try_into()?;
+        let waypoint = match value.field("waypoint") {
+            None => None,
+            Some(Value::Tuple(name, items)) if name == "None" && items.is_empty() => None,
+            Some(Value::Tuple(name, items)) if name == "Some" && items.len() == 1 => {
+                Some(Waypoint::try_from(&items[0])?)
+            }
+            Some(_) => return Err(TutorialError::Invalid("`waypoint: Some(..)` or `None`")),
+        };
+        Ok(Self {
+            text: text.to_string(),
+            trigger,
+            waypoint,
+        })
+    }
+}
+
+impl FromStr for Tutorial {
+    type Err = TutorialError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.parse::<Value>().map_err(TutorialError::Ron)?;
+        let Some(Value::List(objectives)) = value.field("objectives") else {
+            return Err(TutorialError::Invalid("`Tutorial(objectives: [..])`"));
+        };
+        let objectives = (objectives.iter())
+            .map(Objective::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(objectives))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ordinals::Cardinal2D,
+        player::Player,
+        region::{RegionId, factory::Reactor},
+    };
+
+    #[test]
+    fn test_tutorial() {
+        let mut state = GameState::new(Player::new(PlayerVector3::ZERO, 0.0, 0.0, 45.0, 1.0));
+        assert!(Tutorial::built_in().progress().1 > 0);
+
+        let mut tutorial = r#"
+            Tutorial(objectives: [
+                Objective(
+                    text: "Go to the lab",
+                    trigger: EnterRegion(Lab),
+                    waypoint: Some(Lab(0, 1, 0)),
+                ),
+                Objective(text: "Build a reactor", trigger: BuildMachine(Reactor)),
+                Objective(text: "Never done", trigger: Milestone("rail-network")),
+            ])
+        "#
+        .parse::<Tutorial>()
+        .unwrap();
+        let waypoint = tutorial.current().unwrap().waypoint.unwrap();
+        assert!(waypoint.position(&state).is_some());
+        assert_eq!(
+            Waypoint::Factory(99, PlayerVector3::ZERO).position(&state),
+            None
+        );
+        tutorial.update(&state);
+        assert_eq!(tutorial.progress(), (0, 3));
+
+        state.current_region = RegionId::Lab;
+        tutorial.update(&state);
+        assert_eq!(
+            tutorial.progress(),
+            (1, 3),
+            "factories already have reactors, so it takes building another"
+        );
+        _ = state.factories[0].add_reactor(Reactor {
+            position: FactoryVector3::new(10, 0, 10),
+            rotation: Cardinal2D::default(),
+        });
+        tutorial.update(&state);
+        assert_eq!(tutorial.progress(), (2, 3));
+        assert_eq!(
+            tutorial.current().map(Objective::label),
+            Some("Never done".to_string())
+        );
+
+        assert!(matches!(
+            "Tutorial(objectives: [Objective(text: \"\", trigger: BuildMachine(Rocket))])"
+                .parse::<Tutorial>(),
+            Err(TutorialError::Invalid("a kind of machine"))
+        ));
+    }
+}
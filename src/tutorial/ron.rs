@@ -0,0 +1,276 @@
+//! Just enough of [RON](https://github.com/ron-rs/ron) to read [tutorials](super)
+//!
+//! Numbers, strings, lists, tuples, and structs are supported, with or without a name in front,
+//! along with `//` comments and trailing commas. Names on their own, like `None` or `Lab`, are
+//! read as tuples with nothing in them.
+
+use std::str::FromStr;
+
+/// A piece of RON
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    String(String),
+    /// `[a, b]`
+    List(Vec<Value>),
+    /// `Name(a, b)`, `(a, b)`, or just `Name`, with an empty name if it has none
+    Tuple(String, Vec<Value>),
+    /// `Name(field: a)` or `(field: a)`, with an empty name if it has none
+    Struct(String, Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The field called `name`, if this is a struct with one
+    pub fn field(&self, name: &str) -> Option<&Self> {
+        let Self::Struct(_, fields) = self else {
+            return None;
+        };
+        (fields.iter()).find_map(|(field, value)| (field == name).then_some(value))
+    }
+}
+
+/// Where RON couldn't be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseRonError {
+    /// Counting from 1
+    pub line: usize,
+    pub expected: &'static str,
+}
+
+impl std::fmt::Display for ParseRonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: expected {}", self.line, self.expected)
+    }
+}
+
+impl std::error::Error for ParseRonError {}
+
+/// Reads [`Value`]s off the front of the text
+struct Parser<'a> {
+    rest: &'a str,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    const fn error(&self, expected: &'static str) -> ParseRonError {
+        ParseRonError {
+            line: self.line,
+            expected,
+        }
+    }
+
+    /// Move past the first `len` bytes, counting lines
+    fn advance(&mut self, len: usize) {
+        let (skipped, rest) = self.rest.split_at(len);
+        self.line += skipped.matches('\n').count();
+        self.rest = rest;
+    }
+
+    /// Move past whitespace and comments
+    fn skip_space(&mut self) {
+        loop {
+            let trimmed = self.rest.trim_start();
+            self.advance(self.rest.len() - trimmed.len());
+            if !self.rest.starts_with("//") {
+                return;
+            }
+            self.advance(self.rest.find('\n').unwrap_or(self.rest.len()));
+        }
+    }
+
+    /// Move past `c` if it's next
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_space();
+        let found = self.rest.starts_with(c);
+        if found {
+            self.advance(c.len_utf8());
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char, expected: &'static str) -> Result<(), ParseRonError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    /// A name made of letters, digits, and underscores, if one is next
+    fn ident(&mut self) -> Option<&'a str> {
+        self.skip_space();
+        let len = (self.rest)
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest.len());
+        let ident = &self.rest[..len];
+        if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        self.advance(len);
+        Some(ident)
+    }
+
+    /// Items separated by commas up to `close`, each read by `item`
+    fn items<T>(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<T, ParseRonError>,
+    ) -> Result<Vec<T>, ParseRonError> {
+        let mut items = Vec::new();
+        while !self.eat(close) {
+            items.push(item(self)?);
+            if !self.eat(',') {
+                self.expect(close, "`,` or a closing bracket")?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    fn value(&mut self) -> Result<Value, ParseRonError> {
+        self.skip_space();
+        match self.rest.chars().next() {
+            Some('"') => self.string().map(Value::String),
+            Some('[') => {
+                self.advance(1);
+                self.items(']', Self::value).map(Value::List)
+            }
+            Some('(') => self.body(String::new()),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number().map(Value::Number),
+            _ => {
+                let name = self
+                    .ident()
+                    .ok_or_else(|| self.error("a value"))?
+                    .to_string();
+                self.skip_space();
+                if self.rest.starts_with('(') {
+                    self.body(name)
+                } else {
+                    Ok(Value::Tuple(name, Vec::new()))
+                }
+            }
+        }
+    }
+
+    /// The brackets after a tuple or struct's name
+    fn body(&mut self, name: String) -> Result<Value, ParseRonError> {
+        self.expect('(', "`(`")?;
+        let (rest, line) = (self.rest, self.line);
+        if self.ident().is_some() && self.eat(':') {
+            (self.rest, self.line) = (rest, line);
+            let fields = self.items(')', |parser| {
+                let field = parser.ident().ok_or_else(|| parser.error("a field name"))?;
+                parser.expect(':', "`:`")?;
+                Ok((field.to_string(), parser.value()?))
+            })?;
+            Ok(Value::Struct(name, fields))
+        } else {
+            (self.rest, self.line) = (rest, line);
+            self.items(')', Self::value)
+                .map(|items| Value::Tuple(name, items))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseRonError> {
+        self.expect('"', "`\"`")?;
+        let mut string = String::new();
+        let mut chars = self.rest.char_indices();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.advance(idx + 1);
+                    return Ok(string);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => string.push('\n'),
+                    Some((_, c @ ('"' | '\\'))) => string.push(c),
+                    _ => return Err(self.error("`\\n`, `\\\"`, or `\\\\`")),
+                },
+                c => string.push(c),
+            }
+        }
+        Err(self.error("the end of the string"))
+    }
+
+    fn number(&mut self) -> Result<f64, ParseRonError> {
+        let len = (self.rest)
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .unwrap_or(self.rest.len());
+        let number = self.rest[..len]
+            .parse()
+            .map_err(|_| self.error("a number"))?;
+        self.advance(len);
+        Ok(number)
+    }
+}
+
+impl FromStr for Value {
+    type Err = ParseRonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { rest: s, line: 1 };
+        let value = parser.value()?;
+        parser.skip_space();
+        if parser.rest.is_empty() {
+            Ok(value)
+        } else {
+            Err(parser.error("the end of the file"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ron() {
+        let value = r#"
+            // A comment
+            Tutorial(
+                objectives: [
+                    (text: "Say \"hi\"", at: Some(Lab(1, -2.5, 3e1))),
+                    (text: "", at: None,),
+                ],
+            )
+        "#
+        .parse::<Value>()
+        .unwrap();
+        let Some(Value::List(objectives)) = value.field("objectives") else {
+            panic!("expected a list of objectives, got {value:?}");
+        };
+        assert_eq!(
+            objectives[0].field("text"),
+            Some(&Value::String("Say \"hi\"".to_string()))
+        );
+        assert_eq!(
+            objectives[0].field("at"),
+            Some(&Value::Tuple(
+                "Some".to_string(),
+                vec![Value::Tuple(
+                    "Lab".to_string(),
+                    vec![Value::Number(1.0), Value::Number(-2.5), Value::Number(30.0)]
+                )]
+            ))
+        );
+        assert_eq!(
+            objectives[1].field("at"),
+            Some(&Value::Tuple("None".to_string(), Vec::new()))
+        );
+
+        assert_eq!(
+            "(a: 1,\n b 2)".parse::<Value>(),
+            Err(ParseRonError {
+                line: 2,
+                expected: "`:`"
+            })
+        );
+        assert_eq!(
+            "[1, 2".parse::<Value>(),
+            Err(ParseRonError {
+                line: 1,
+                expected: "`,` or a closing bracket"
+            })
+        );
+    }
+}
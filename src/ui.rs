@@ -0,0 +1,63 @@
+//! Windows drawn over the game, like machine panels
+//!
+//! Open [`Panel`]s are kept on a [`UiStack`]. Only the top one gets input, and while any are
+//! open the game is in [`AppState::MenuOpen`](crate::app_state::AppState::MenuOpen), so the
+//! cursor is released and the mouse doesn't turn the camera.
+
+use crate::{game::GameState, input::Inputs};
+use engine::{
+    draw,
+    draw2d::{Draw, Renderer},
+};
+use raylib::prelude::*;
+
+/// A window that takes over the mouse and keyboard while it's open
+pub trait Panel: Draw {
+    /// Handle input, returning `false` once the panel should close
+    ///
+    /// Panels close on escape by convention.
+    fn update(&mut self, rl: &mut RaylibHandle, inputs: &Inputs, state: &mut GameState) -> bool;
+}
+
+/// Every open [`Panel`], with the one on top getting input
+#[derive(Default)]
+pub struct UiStack {
+    panels: Vec<Box<dyn Panel>>,
+}
+
+impl UiStack {
+    /// Whether any panel is open, and so has focus
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        !self.panels.is_empty()
+    }
+
+    /// Open `panel` over any already open
+    pub fn push(&mut self, panel: impl Panel + 'static) {
+        self.panels.push(Box::new(panel));
+    }
+
+    /// Close the top panel
+    pub fn pop(&mut self) {
+        self.panels.pop();
+    }
+
+    /// Update the top panel, closing it if it's done
+    pub fn update(&mut self, rl: &mut RaylibHandle, inputs: &Inputs, state: &mut GameState) {
+        if let Some(panel) = self.panels.last_mut()
+            && !panel.update(rl, inputs, state)
+        {
+            self.pop();
+        }
+    }
+}
+
+impl Draw for UiStack {
+    /// Bottom to top, so the top panel is drawn over the rest
+    fn draw(&self, d: &mut Renderer<'_>) -> draw::Result {
+        for panel in &self.panels {
+            panel.draw(d)?;
+        }
+        Ok(())
+    }
+}
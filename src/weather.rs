@@ -0,0 +1,432 @@
+//! Rain, fog, and storms outside
+//!
+//! The weather changes every [`PERIOD_HOURS`] hours on the [world clock](crate::time::WorldClock),
+//! following a forecast that's the same in every game, and each change fades in over
+//! [`FADE_MINUTES`] minutes of game time. Clouds dim the [`Sky`] and slow solar machines down, wet
+//! ground tints the ambient light, and wet rails slow trains down a little. Rain and storms fall
+//! as streaks around the player, and fog and rain haze the view.
+//!
+//! The weather is saved as text with what it is, what it's fading from, which stretch of the
+//! forecast it's from, and how far it's faded in:
+//!
+//! ```text
+//! weather rain clear 9 0.5
+//! ```
+
+use crate::{
+    lighting::{Sky, lerp_color},
+    math::coords::PlayerVector3,
+    region::rail::terrain::Noise,
+    rl_helpers::DynRaylibDraw3D,
+    time::WorldClock,
+};
+use raylib::prelude::*;
+use std::str::FromStr;
+
+/// Hours of game time between changes in the weather
+const PERIOD_HOURS: f64 = 6.0;
+/// Minutes of game time each change takes to fade in
+const FADE_MINUTES: f32 = 30.0;
+/// Makes the same forecast in every game
+const SEED: u32 = 0x5eed_ca57;
+
+/// How much slower trains run on soaked rails
+const RAIL_SLIP: f32 = 0.15;
+
+/// Meters across the square around the player that rain falls in
+const RAIN_AREA: f32 = 40.0;
+/// How far above and below the player rain falls from and to
+const RAIN_HEIGHT: f32 = 12.0;
+/// Meters per second rain falls
+const RAIN_SPEED: f32 = 14.0;
+/// Length of each streak of rain
+const DROP_LENGTH: f32 = 0.6;
+const RAIN_COLOR: Color = Color::new(170, 180, 200, 160);
+/// Haze at full strength
+const HAZE_COLOR: Color = Color::new(150, 155, 165, 255);
+/// The sky during a lightning flash
+const FLASH_COLOR: Color = Color::new(220, 225, 255, 255);
+
+/// What the weather is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeatherKind {
+    #[default]
+    Clear,
+    Rain,
+    Fog,
+    /// Heavy rain blown sideways, with lightning
+    Storm,
+}
+
+impl WeatherKind {
+    pub const ALL: [Self; 4] = [Self::Clear, Self::Rain, Self::Fog, Self::Storm];
+
+    /// Used to save the weather and to set it in the console
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Clear => "clear",
+            Self::Rain => "rain",
+            Self::Fog => "fog",
+            Self::Storm => "storm",
+        }
+    }
+
+    /// How much of the sun gets through, from 0 to 1
+    const fn sunlight(self) -> f32 {
+        match self {
+            Self::Clear => 1.0,
+            Self::Rain => 0.6,
+            Self::Fog => 0.75,
+            Self::Storm => 0.35,
+        }
+    }
+
+    /// How wet the ground and rails get, from 0 to 1
+    const fn wetness(self) -> f32 {
+        match self {
+            Self::Clear => 0.0,
+            Self::Rain => 0.7,
+            Self::Fog => 0.2,
+            Self::Storm => 1.0,
+        }
+    }
+
+    /// How much the view is hazed over, from 0 to 1
+    const fn haze(self) -> f32 {
+        match self {
+            Self::Clear => 0.0,
+            Self::Rain => 0.15,
+            Self::Fog => 0.55,
+            Self::Storm => 0.3,
+        }
+    }
+
+    /// How many streaks of rain fall around the player
+    const fn drops(self) -> f32 {
+        match self {
+            Self::Clear | Self::Fog => 0.0,
+            Self::Rain => 400.0,
+            Self::Storm => 900.0,
+        }
+    }
+
+    /// The weather for the `period`th stretch of [`PERIOD_HOURS`] hours
+    ///
+    /// Clear half the time, then rain, fog, and storms from most to least often.
+    fn forecast(period: u64) -> Self {
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "the forecast repeats after 2^32 periods, which nobody will play for"
+        )]
+        let roll = Noise::new(SEED).lattice(period as i32, 0);
+        match roll {
+            r if r < 0.5 => Self::Clear,
+            r if r < 0.75 => Self::Rain,
+            r if r < 0.9 => Self::Fog,
+            _ => Self::Storm,
+        }
+    }
+}
+
+/// Why text couldn't be read as a [`WeatherKind`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWeatherError(String);
+
+impl std::fmt::Display for ParseWeatherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown weather `{}`", self.0)
+    }
+}
+
+impl std::error::Error for ParseWeatherError {}
+
+impl FromStr for WeatherKind {
+    type Err = ParseWeatherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        (Self::ALL.into_iter())
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| ParseWeatherError(s.to_string()))
+    }
+}
+
+/// The weather outside, fading from one kind to the next
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Weather {
+    kind: WeatherKind,
+    /// What it's fading from
+    previous: WeatherKind,
+    /// Which stretch of [`PERIOD_HOURS`] the weather was last forecast for, or [`None`] if it
+    /// hasn't been yet
+    period: Option<u64>,
+    /// How far `kind` has faded in over `previous`, from 0 to 1
+    blend: f32,
+}
+
+impl Weather {
+    #[inline]
+    pub const fn kind(&self) -> WeatherKind {
+        self.kind
+    }
+
+    /// Start fading into `kind`, which lasts until the forecast next changes
+    pub const fn set(&mut self, kind: WeatherKind) {
+        self.previous = self.kind;
+        self.kind = kind;
+        self.blend = 0.0;
+    }
+
+    /// Follow the forecast to `clock`'s time, fading in by `dt` more real seconds
+    ///
+    /// The first time, the weather starts out as forecast without fading in.
+    pub fn tick(&mut self, dt: f32, clock: &WorldClock) {
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "the clock never runs backwards, and won't reach 2^64 periods"
+        )]
+        let period = (clock.total_minutes() / (60.0 * PERIOD_HOURS)) as u64;
+        match self.period {
+            None => {
+                *self = Self {
+                    kind: WeatherKind::forecast(period),
+                    previous: WeatherKind::forecast(period),
+                    period: Some(period),
+                    blend: 1.0,
+                };
+            }
+            Some(last) if last != period => {
+                self.period = Some(period);
+                self.set(WeatherKind::forecast(period));
+            }
+            Some(_) => {}
+        }
+        self.blend = (self.blend + dt * clock.scale / 60.0 / FADE_MINUTES).min(1.0);
+    }
+
+    /// `f` of the weather, partway between what it's fading from and to
+    fn mix(&self, f: impl Fn(WeatherKind) -> f32) -> f32 {
+        let (from, to) = (f(self.previous), f(self.kind));
+        from + (to - from) * self.blend
+    }
+
+    /// How much of the sun gets through, from 0 to 1
+    ///
+    /// Solar machines see this much of the daylight.
+    pub fn sunlight(&self) -> f32 {
+        self.mix(WeatherKind::sunlight)
+    }
+
+    /// How wet everything outside is, from 0 to 1
+    pub fn wetness(&self) -> f32 {
+        self.mix(WeatherKind::wetness)
+    }
+
+    /// How much slower than their speed trains run on rails this wet, from 0 to 1
+    pub fn rail_slip(&self) -> f32 {
+        RAIL_SLIP * self.wetness()
+    }
+
+    /// What's drawn over the view outside, faint or clear when there's no haze
+    pub fn haze(&self) -> Color {
+        HAZE_COLOR.alpha(self.mix(WeatherKind::haze))
+    }
+
+    /// Whether lightning is lighting up the sky at `clock`'s time
+    ///
+    /// Each minute of game time in a storm has a small chance of a flash at its start.
+    fn is_flashing(&self, clock: &WorldClock) -> bool {
+        if self.kind != WeatherKind::Storm || self.blend < 1.0 {
+            return false;
+        }
+        let minutes = clock.total_minutes();
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "the flashes repeat after 2^32 minutes, which nobody will play for"
+        )]
+        let minute = minutes.floor() as i64 as i32;
+        minutes.fract() < 0.15 && Noise::new(SEED).offset(1).lattice(minute, 0) < 0.08
+    }
+
+    /// The sky outside at `clock`'s time, in this weather
+    pub fn sky(&self, clock: &WorldClock) -> Sky {
+        let sky = (clock.sky())
+            .overcast(1.0 - self.sunlight())
+            .wet(self.wetness());
+        if self.is_flashing(clock) {
+            Sky {
+                light: FLASH_COLOR,
+                ambient: lerp_color(sky.ambient, FLASH_COLOR, 0.5),
+                tint: FLASH_COLOR,
+                ..sky
+            }
+        } else {
+            sky
+        }
+    }
+
+    /// Draw streaks of rain falling around `player_pos`, relative to it
+    ///
+    /// Each drop stays put as the player moves, wrapping around to the other side of the area
+    /// once it's too far away. Storms blow the rain sideways.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        reason = "there are only a few hundred drops, and the time wraps around"
+    )]
+    pub fn draw(&self, d: &mut dyn DynRaylibDraw3D, player_pos: PlayerVector3, clock: &WorldClock) {
+        let drops = self.mix(WeatherKind::drops) as i32;
+        if drops == 0 {
+            return;
+        }
+        let wind = if self.kind == WeatherKind::Storm {
+            Vector3::new(0.5, 0.0, 0.2)
+        } else {
+            Vector3::ZERO
+        };
+        let fall = (Vector3::NEG_Y + wind).normalize() * DROP_LENGTH;
+        // Real seconds, wrapped so the precision doesn't run out over long games
+        let seconds = ((clock.total_minutes() * 60.0 / f64::from(clock.scale.max(1.0)))
+            % f64::from(2.0 * RAIN_HEIGHT / RAIN_SPEED * 1000.0)) as f32;
+        let player = player_pos.to_vec3();
+        let noise = Noise::new(SEED).offset(2);
+        let wrap = |offset: f32, at: f32| (offset - at).rem_euclid(RAIN_AREA) - 0.5 * RAIN_AREA;
+        for i in 0..drops {
+            let x = wrap(noise.lattice(i, 0) * RAIN_AREA, player.x);
+            let z = wrap(noise.lattice(i, 1) * RAIN_AREA, player.z);
+            let height = 2.0 * RAIN_HEIGHT;
+            let y = RAIN_HEIGHT - (noise.lattice(i, 2) * height + seconds * RAIN_SPEED) % height;
+            let top = Vector3::new(x, y, z) - wind * y;
+            d.draw_line3D(top, top + fall, RAIN_COLOR);
+        }
+    }
+}
+
+impl std::fmt::Display for Weather {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let period = self
+            .period
+            .map_or_else(|| "-".to_string(), |p| p.to_string());
+        writeln!(
+            f,
+            "weather {} {} {period} {}",
+            self.kind.name(),
+            self.previous.name(),
+            self.blend
+        )
+    }
+}
+
+/// Why text couldn't be read as [`Weather`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseSavedWeatherError {
+    /// Doesn't start with `weather`
+    MissingHeader,
+    /// Is missing something, or has a number that isn't one
+    Malformed,
+    Kind(ParseWeatherError),
+}
+
+impl std::fmt::Display for ParseSavedWeatherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader => f.write_str("not weather"),
+            Self::Malformed => f.write_str("expected `weather <kind> <kind> <period> <blend>`"),
+            Self::Kind(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ParseSavedWeatherError {}
+
+impl From<ParseWeatherError> for ParseSavedWeatherError {
+    fn from(e: ParseWeatherError) -> Self {
+        Self::Kind(e)
+    }
+}
+
+impl FromStr for Weather {
+    type Err = ParseSavedWeatherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        if words.next() != Some("weather") {
+            return Err(ParseSavedWeatherError::MissingHeader);
+        }
+        let mut next = || words.next().ok_or(ParseSavedWeatherError::Malformed);
+        let kind = next()?.parse()?;
+        let previous = next()?.parse()?;
+        let period = match next()? {
+            "-" => None,
+            period => Some(
+                period
+                    .parse()
+                    .map_err(|_| ParseSavedWeatherError::Malformed)?,
+            ),
+        };
+        let blend = (next()?.parse::<f32>())
+            .ok()
+            .filter(|blend| blend.is_finite())
+            .ok_or(ParseSavedWeatherError::Malformed)?;
+        Ok(Self {
+            kind,
+            previous,
+            period,
+            blend: blend.clamp(0.0, 1.0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weather() {
+        let mut clock = WorldClock::default();
+        // An hour a second
+        clock.scale = 3600.0;
+        let mut weather = Weather::default();
+        weather.tick(0.0, &clock);
+        assert_eq!(
+            weather.kind(),
+            WeatherKind::forecast(2),
+            "noon is the third period"
+        );
+        assert!(
+            (weather.blend - 1.0).abs() < f32::EPSILON,
+            "no fading at first"
+        );
+
+        weather.set(WeatherKind::Storm);
+        let before = weather;
+        weather.tick(FADE_MINUTES * 60.0 / clock.scale / 2.0, &clock);
+        assert!((weather.blend - 0.5).abs() < 1e-6);
+        assert!(weather.sunlight() < before.sunlight() || before.previous == WeatherKind::Storm);
+        assert!(weather.rail_slip() > 0.0);
+
+        // Forecasts are picked often enough to see each kind of weather within a few weeks
+        let seen = (0..120).map(WeatherKind::forecast).collect::<Vec<_>>();
+        for kind in WeatherKind::ALL {
+            assert!(seen.contains(&kind), "never {}", kind.name());
+        }
+
+        clock.tick(6.0);
+        weather.tick(0.0, &clock);
+        assert_eq!(weather.kind(), WeatherKind::forecast(3));
+        assert_eq!(weather.previous, WeatherKind::Storm, "fades from the storm");
+
+        let text = weather.to_string();
+        assert_eq!(text.parse(), Ok(weather));
+        assert_eq!(
+            "weather hail clear 3 0".parse::<Weather>(),
+            Err(ParseSavedWeatherError::Kind(ParseWeatherError(
+                "hail".to_string()
+            )))
+        );
+        assert_eq!(
+            "weather rain clear 3".parse::<Weather>(),
+            Err(ParseSavedWeatherError::Malformed)
+        );
+    }
+}